@@ -14,4 +14,5 @@ pub mod dfont;
 pub mod otf;
 pub mod ttc;
 pub mod woff;
+pub mod woff2;
 
@@ -12,59 +12,132 @@
 //!
 //! See Microsoft's spec: https://www.microsoft.com/typography/otspec/otff.htm
 
-use byteorder::{BigEndian, ReadBytesExt};
 use error::FontError;
-use font::{Font, FontTable};
+use font::{Font, FontTable, TableDirectory, TableRecord, TableRecords};
 use std::mem;
+use tables::cbdt::{self, CbdtTable};
+use tables::cblc::{self, CblcTable};
 use tables::cff::{self, CffTable};
 use tables::cvt;
 use tables::cmap::{self, CmapTable};
+use tables::colr::{self, ColrTable};
+use tables::cpal::{self, CpalTable};
+use tables::dsig::{self, DsigTable};
+use tables::ebdt::{self, EbdtTable};
+use tables::eblc;
 use tables::fpgm;
+use tables::fvar::{self, FvarTable};
+use tables::gasp::{self, GaspTable};
 use tables::glyf::{self, GlyfTable};
+use tables::gpos::{self, GposTable};
+use tables::gsub::{self, GsubTable};
+use tables::gvar::{self, GvarTable};
 use tables::head::{self, HeadTable};
 use tables::hhea::{self, HheaTable};
 use tables::hmtx::{self, HmtxTable};
 use tables::kern::{self, KernTable};
 use tables::loca::{self, LocaTable};
+use tables::math::{self, MathTable};
+use tables::maxp::{self, MaxpTable};
+use tables::meta::{self, MetaTable};
+use tables::morx::{self, MorxTable};
+use tables::name::{self, NameTable};
 use tables::os_2::{self, Os2Table};
+use tables::post::{self, PostTable};
 use tables::prep;
-use util::Jump;
+use tables::sbix::{self, SbixTable};
+use tables::stat::{self, StatTable};
+use tables::svg::{self, SvgTable};
+use tables::vhea::{self, VheaTable};
+use tables::vmtx::{self, VmtxTable};
+use tables::vorg::{self, VorgTable};
+use util::{self, Reader};
 
 const OTTO: u32 = ((b'O' as u32) << 24) |
                   ((b'T' as u32) << 16) |
                   ((b'T' as u32) << 8)  |
                    (b'O' as u32);
 
-pub const KNOWN_TABLE_COUNT: usize = 12;
+pub const KNOWN_TABLE_COUNT: usize = 37;
 
 pub static KNOWN_TABLES: [u32; KNOWN_TABLE_COUNT] = [
+    cbdt::TAG,
+    cblc::TAG,
     cff::TAG,
+    cff::CFF2_TAG,
+    colr::TAG,
+    cpal::TAG,
+    dsig::TAG,
+    ebdt::TAG,
+    eblc::TAG,
+    gpos::TAG,
+    gsub::TAG,
+    math::TAG,
     os_2::TAG,
+    stat::TAG,
+    svg::TAG,
+    vorg::TAG,
     cmap::TAG,
     cvt::TAG,
     fpgm::TAG,
+    fvar::TAG,
+    gasp::TAG,
     glyf::TAG,
+    gvar::TAG,
     head::TAG,
     hhea::TAG,
     hmtx::TAG,
     kern::TAG,
     loca::TAG,
+    maxp::TAG,
+    meta::TAG,
+    morx::TAG,
+    name::TAG,
+    post::TAG,
     prep::TAG,
+    sbix::TAG,
+    vhea::TAG,
+    vmtx::TAG,
 ];
 
 // This must agree with the above.
-const TABLE_INDEX_CFF:  usize = 0;
-const TABLE_INDEX_OS_2: usize = 1;
-const TABLE_INDEX_CMAP: usize = 2;
-const TABLE_INDEX_CVT:  usize = 3;
-const TABLE_INDEX_FPGM: usize = 4;
-const TABLE_INDEX_GLYF: usize = 5;
-const TABLE_INDEX_HEAD: usize = 6;
-const TABLE_INDEX_HHEA: usize = 7;
-const TABLE_INDEX_HMTX: usize = 8;
-const TABLE_INDEX_KERN: usize = 9;
-const TABLE_INDEX_LOCA: usize = 10;
-const TABLE_INDEX_PREP: usize = 11;
+const TABLE_INDEX_CBDT: usize = 0;
+const TABLE_INDEX_CBLC: usize = 1;
+const TABLE_INDEX_CFF:  usize = 2;
+const TABLE_INDEX_CFF2: usize = 3;
+const TABLE_INDEX_COLR: usize = 4;
+const TABLE_INDEX_CPAL: usize = 5;
+const TABLE_INDEX_DSIG: usize = 6;
+const TABLE_INDEX_EBDT: usize = 7;
+const TABLE_INDEX_EBLC: usize = 8;
+const TABLE_INDEX_GPOS: usize = 9;
+const TABLE_INDEX_GSUB: usize = 10;
+const TABLE_INDEX_MATH: usize = 11;
+const TABLE_INDEX_OS_2: usize = 12;
+const TABLE_INDEX_STAT: usize = 13;
+const TABLE_INDEX_SVG:  usize = 14;
+const TABLE_INDEX_VORG: usize = 15;
+const TABLE_INDEX_CMAP: usize = 16;
+const TABLE_INDEX_CVT:  usize = 17;
+const TABLE_INDEX_FPGM: usize = 18;
+const TABLE_INDEX_FVAR: usize = 19;
+const TABLE_INDEX_GASP: usize = 20;
+const TABLE_INDEX_GLYF: usize = 21;
+const TABLE_INDEX_GVAR: usize = 22;
+const TABLE_INDEX_HEAD: usize = 23;
+const TABLE_INDEX_HHEA: usize = 24;
+const TABLE_INDEX_HMTX: usize = 25;
+const TABLE_INDEX_KERN: usize = 26;
+const TABLE_INDEX_LOCA: usize = 27;
+const TABLE_INDEX_MAXP: usize = 28;
+const TABLE_INDEX_META: usize = 29;
+const TABLE_INDEX_MORX: usize = 30;
+const TABLE_INDEX_NAME: usize = 31;
+const TABLE_INDEX_POST: usize = 32;
+const TABLE_INDEX_PREP: usize = 33;
+const TABLE_INDEX_SBIX: usize = 34;
+const TABLE_INDEX_VHEA: usize = 35;
+const TABLE_INDEX_VMTX: usize = 36;
 
 pub static SFNT_VERSIONS: [u32; 3] = [
     0x10000,
@@ -79,43 +152,259 @@ pub struct FontTables<'a> {
     pub head: HeadTable,
     pub hhea: HheaTable,
     pub hmtx: HmtxTable<'a>,
+    pub maxp: MaxpTable,
     pub os_2: Os2Table,
 
     // Optional tables.
+    pub cbdt: Option<CbdtTable<'a>>,
+    pub cblc: Option<CblcTable<'a>>,
     pub cff: Option<CffTable<'a>>,
+    pub cff2: Option<CffTable<'a>>,
+    pub colr: Option<ColrTable<'a>>,
+    pub cpal: Option<CpalTable<'a>>,
+    pub dsig: Option<DsigTable>,
+    pub ebdt: Option<EbdtTable<'a>>,
+    pub eblc: Option<CblcTable<'a>>,
+    pub fvar: Option<FvarTable>,
+    pub gasp: Option<GaspTable<'a>>,
     pub glyf: Option<GlyfTable<'a>>,
+    pub gpos: Option<GposTable<'a>>,
+    pub gsub: Option<GsubTable<'a>>,
+    pub gvar: Option<GvarTable<'a>>,
     pub loca: Option<LocaTable<'a>>,
+    pub math: Option<MathTable<'a>>,
+    pub meta: Option<MetaTable<'a>>,
+    pub morx: Option<MorxTable<'a>>,
     pub kern: Option<KernTable<'a>>,
+    pub name: Option<NameTable<'a>>,
+    pub post: Option<PostTable<'a>>,
+    pub sbix: Option<SbixTable<'a>>,
+    pub stat: Option<StatTable>,
+    pub svg: Option<SvgTable<'a>>,
+    pub vhea: Option<VheaTable>,
+    pub vmtx: Option<VmtxTable<'a>>,
+    pub vorg: Option<VorgTable<'a>>,
 
     // Optional tables that need no parsing.
     pub cvt: Option<FontTable<'a>>,
     pub fpgm: Option<FontTable<'a>>,
     pub prep: Option<FontTable<'a>>,
+
+    // Every known table as it appears in the file, in the order of `KNOWN_TABLES`.
+    pub unparsed: [Option<FontTable<'a>>; KNOWN_TABLE_COUNT],
 }
 
-impl<'a> Font<'a> {
-    pub fn from_otf<'b>(bytes: &'b [u8], offset: u32) -> Result<Font<'b>, FontError> {
-        let mut reader = bytes;
-        try!(reader.jump(offset as usize).map_err(FontError::eof));
+impl<'a> FontTables<'a> {
+    /// Returns the data of the table with the given tag, if it's one that we know about and the
+    /// font has it.
+    pub fn unparsed_table(&self, tag: u32) -> Option<FontTable<'a>> {
+        KNOWN_TABLES.binary_search(&tag).ok().and_then(|table_index| self.unparsed[table_index])
+    }
+}
+
+impl<'a> TableDirectory<'a> {
+    /// Reads the table directory at the start of a font file.
+    #[inline]
+    pub fn new(bytes: &'a [u8]) -> Result<TableDirectory<'a>, FontError> {
+        TableDirectory::from_offset(bytes, 0)
+    }
+
+    /// Reads the table directory at the given offset into a font collection file.
+    ///
+    /// Table offsets are relative to the start of `bytes`, as they are in collections.
+    pub fn from_offset(bytes: &'a [u8], offset: u32) -> Result<TableDirectory<'a>, FontError> {
+        let mut reader = try!(Reader::at(bytes, offset as usize));
 
         // Check the magic number.
-        if !SFNT_VERSIONS.contains(&try!(reader.read_u32::<BigEndian>().map_err(FontError::eof))) {
+        let sfnt_version = try!(reader.read_u32_be());
+        if !SFNT_VERSIONS.contains(&sfnt_version) {
             return Err(FontError::UnknownFormat)
         }
 
-        let num_tables = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
-        try!(reader.jump(mem::size_of::<u16>() * 3).map_err(FontError::eof));
+        let num_tables = try!(reader.read_u16_be());
+        let search_range = try!(reader.read_u16_be());
+        let entry_selector = try!(reader.read_u16_be());
+        try!(reader.jump(mem::size_of::<u16>()));
 
-        let mut tables = [None; KNOWN_TABLE_COUNT];
-        for _ in 0..num_tables {
-            let table_id = try!(reader.read_u32::<BigEndian>().map_err(FontError::eof));
-            let _checksum = try!(reader.read_u32::<BigEndian>().map_err(FontError::eof));
-            let offset = try!(reader.read_u32::<BigEndian>().map_err(FontError::eof)) as usize;
-            let length = try!(reader.read_u32::<BigEndian>().map_err(FontError::eof)) as usize;
+        // We don't use the binary search parameters, and many fonts get them slightly wrong, so
+        // only reject values that couldn't describe a directory of this size.
+        if search_range as u32 > num_tables as u32 * 16 || entry_selector > 15 {
+            return Err(FontError::BadTableDirectory)
+        }
+
+        let records = try!(reader.subslice(num_tables as usize * 16));
+        Ok(TableDirectory {
+            bytes: bytes,
+            sfnt_version: sfnt_version,
+            header: &bytes[(offset as usize)..(offset as usize + 12)],
+            records: records,
+        })
+    }
+
+    /// Returns the version number of the sfnt, which is `0x00010000` or `true` for TrueType
+    /// outlines and `OTTO` for CFF outlines.
+    #[inline]
+    pub fn sfnt_version(&self) -> u32 {
+        self.sfnt_version
+    }
+
+    /// Returns the number of tables in the directory.
+    #[inline]
+    pub fn num_tables(&self) -> u16 {
+        (self.records.len() / 16) as u16
+    }
+
+    /// Returns an iterator over the records of the tables in the directory.
+    #[inline]
+    pub fn records(&self) -> TableRecords<'a> {
+        TableRecords {
+            reader: Reader::new(self.records),
+        }
+    }
+
+    /// Returns the table with the given tag, or `None` if the font has no such table or its
+    /// record points outside the file.
+    pub fn table(&self, tag: u32) -> Option<FontTable<'a>> {
+        self.records()
+            .find(|record| record.tag == tag)
+            .and_then(|record| self.table_for_record(&record).ok())
+    }
+
+    /// Returns the data of the table that the given record describes.
+    pub fn table_for_record(&self, record: &TableRecord) -> Result<FontTable<'a>, FontError> {
+        let mut reader = try!(Reader::at(self.bytes, record.offset as usize));
+        Ok(FontTable {
+            bytes: try!(reader.subslice(record.length as usize)),
+        })
+    }
+
+    /// Checks every table against its checksum in the directory, and the whole font against the
+    /// checksum adjustment in its `head` table.
+    ///
+    /// This catches corrupt and truncated files before parsing fails in more confusing ways.
+    /// Fonts without a `head` table only have their tables checked.
+    pub fn verify_checksums(&self) -> Result<(), FontError> {
+        let mut font_checksum = checksum(self.header).wrapping_add(checksum(self.records));
+        let mut checksum_adjustment = None;
+        for record in self.records() {
+            let table = try!(self.table_for_record(&record));
+
+            // The `head` table's checksum is computed with its checksum adjustment zeroed, since
+            // that's derived from the checksum of the whole font.
+            let table_checksum = if record.tag == head::TAG {
+                let mut reader = try!(Reader::at(table.bytes, 8));
+                let adjustment = try!(reader.read_u32_be());
+                checksum_adjustment = Some(adjustment);
+                checksum(table.bytes).wrapping_sub(adjustment)
+            } else {
+                checksum(table.bytes)
+            };
+            if table_checksum != record.checksum {
+                return Err(FontError::BadTableChecksum(record.tag))
+            }
+
+            // Each table adds its checksum to the font's, as if the tables followed the directory
+            // with only zero padding between them. This is also how the adjustment is computed for
+            // fonts within collections.
+            font_checksum = font_checksum.wrapping_add(table_checksum)
+        }
+
+        match checksum_adjustment {
+            Some(adjustment) if adjustment != FONT_CHECKSUM.wrapping_sub(font_checksum) => {
+                Err(FontError::BadFontChecksum)
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Writes an sfnt with the given version and tables, sorting the tables by tag and filling in the
+/// checksums.
+///
+/// The checksum adjustment in the `head` table, if there is one, must be zero; it's set to match
+/// the checksum of the whole font.
+pub fn write_sfnt(sfnt_version: u32, tables: &mut [(u32, Vec<u8>)]) -> Vec<u8> {
+    tables.sort_by(|&(a, _), &(b, _)| a.cmp(&b));
+
+    let num_tables = tables.len() as u16;
+    let (search_range, entry_selector, range_shift) =
+        util::binary_search_parameters(num_tables, 16);
+    let mut bytes = vec![];
+    util::push_u32(&mut bytes, sfnt_version);
+    for &value in &[num_tables, search_range, entry_selector, range_shift] {
+        util::push_u16(&mut bytes, value)
+    }
+
+    // Each table starts on a 4-byte boundary.
+    let mut offset = 12 + 16 * tables.len();
+    let mut head_offset = None;
+    for &(tag, ref table) in tables.iter() {
+        util::push_u32(&mut bytes, tag);
+        util::push_u32(&mut bytes, checksum(table));
+        util::push_u32(&mut bytes, offset as u32);
+        util::push_u32(&mut bytes, table.len() as u32);
+        if tag == head::TAG {
+            head_offset = Some(offset)
+        }
+        offset += (table.len() + 3) & !3
+    }
+    for &(_, ref table) in tables.iter() {
+        bytes.extend_from_slice(table);
+        while bytes.len() % 4 != 0 {
+            bytes.push(0)
+        }
+    }
+
+    if let Some(head_offset) = head_offset {
+        let adjustment = FONT_CHECKSUM.wrapping_sub(checksum(&bytes));
+        for (index, byte) in bytes[(head_offset + 8)..(head_offset + 12)].iter_mut().enumerate() {
+            *byte = (adjustment >> (24 - 8 * index)) as u8
+        }
+    }
+    bytes
+}
+
+// The value that the checksum of a whole font, including its `head` table's checksum adjustment,
+// must be.
+const FONT_CHECKSUM: u32 = 0xb1b0afba;
+
+// Computes the checksum of the given data: the sum of its big-endian u32 values, with the last one
+// padded with zeros.
+fn checksum(bytes: &[u8]) -> u32 {
+    bytes.chunks(4).fold(0, |sum: u32, chunk| {
+        let value = (0..4).fold(0, |value, index| {
+            (value << 8) | chunk.get(index).cloned().unwrap_or(0) as u32
+        });
+        sum.wrapping_add(value)
+    })
+}
+
+impl<'a> Iterator for TableRecords<'a> {
+    type Item = TableRecord;
 
+    fn next(&mut self) -> Option<TableRecord> {
+        // The directory was checked to contain whole records, so only the end can stop us.
+        if self.reader.is_empty() {
+            return None
+        }
+        Some(TableRecord {
+            tag: self.reader.read_u32_be().unwrap(),
+            checksum: self.reader.read_u32_be().unwrap(),
+            offset: self.reader.read_u32_be().unwrap(),
+            length: self.reader.read_u32_be().unwrap(),
+        })
+    }
+}
+
+impl<'a> Font<'a> {
+    pub fn from_otf<'b>(bytes: &'b [u8], offset: u32) -> Result<Font<'b>, FontError> {
+        let directory = try!(TableDirectory::from_offset(bytes, offset));
+
+        let mut tables = [None; KNOWN_TABLE_COUNT];
+        for record in directory.records() {
             // Find the table ID in our list of known IDs, which must be sorted.
             debug_assert!(KNOWN_TABLES.windows(2).all(|w| w[0] < w[1]));
-            let slot = match KNOWN_TABLES.binary_search(&table_id) {
+            let slot = match KNOWN_TABLES.binary_search(&record.tag) {
                 Err(_) => continue,
                 Ok(table_index) => &mut tables[table_index],
             };
@@ -125,9 +414,7 @@ impl<'a> Font<'a> {
                 return Err(FontError::Failed)
             }
 
-            *slot = Some(FontTable {
-                bytes: &bytes[offset..offset + length],
-            })
+            *slot = Some(try!(directory.table_for_record(&record)))
         }
 
         Font::from_table_list(bytes, &tables)
@@ -137,34 +424,73 @@ impl<'a> Font<'a> {
     pub fn from_table_list<'b>(bytes: &'b [u8],
                                tables: &[Option<FontTable<'b>>; KNOWN_TABLE_COUNT])
                                -> Result<Font<'b>, FontError> {
+        // Variable fonts have a `CFF2` table in place of `CFF `, though some ship both.
         let cff_table = match tables[TABLE_INDEX_CFF] {
             None => None,
             Some(cff_table) => Some(try!(CffTable::new(cff_table))),
         };
-
-        let loca_table = match tables[TABLE_INDEX_LOCA] {
+        let cff2_table = match tables[TABLE_INDEX_CFF2] {
             None => None,
-            Some(loca_table) => Some(try!(LocaTable::new(loca_table))),
+            Some(cff2_table) => Some(try!(CffTable::new(cff2_table))),
         };
 
         // For brevity below…
         let missing = FontError::RequiredTableMissing;
 
+        // The `loca` table's format is given by the `head` table.
+        let head_table = try!(HeadTable::new(try!(tables[TABLE_INDEX_HEAD].ok_or(missing))));
+        let loca_table = match tables[TABLE_INDEX_LOCA] {
+            None => None,
+            Some(loca_table) => Some(try!(LocaTable::new(loca_table, &head_table))),
+        };
+
+        let maxp_table = try!(MaxpTable::new(try!(tables[TABLE_INDEX_MAXP].ok_or(missing))));
+        let sbix_table = tables[TABLE_INDEX_SBIX].and_then(|table| {
+            SbixTable::new(table, maxp_table.num_glyphs()).ok()
+        });
+
         let tables = FontTables {
             cmap: CmapTable::new(try!(tables[TABLE_INDEX_CMAP].ok_or(missing))),
-            head: try!(HeadTable::new(try!(tables[TABLE_INDEX_HEAD].ok_or(missing)))),
+            head: head_table,
             hhea: try!(HheaTable::new(try!(tables[TABLE_INDEX_HHEA].ok_or(missing)))),
             hmtx: HmtxTable::new(try!(tables[TABLE_INDEX_HMTX].ok_or(missing))),
+            maxp: maxp_table,
             os_2: try!(Os2Table::new(try!(tables[TABLE_INDEX_OS_2].ok_or(missing)))),
 
+            cbdt: tables[TABLE_INDEX_CBDT].and_then(|table| CbdtTable::new(table).ok()),
+            cblc: tables[TABLE_INDEX_CBLC].and_then(|table| CblcTable::new(table).ok()),
             cff: cff_table,
+            cff2: cff2_table,
+            colr: tables[TABLE_INDEX_COLR].and_then(|table| ColrTable::new(table).ok()),
+            cpal: tables[TABLE_INDEX_CPAL].and_then(|table| CpalTable::new(table).ok()),
+            dsig: tables[TABLE_INDEX_DSIG].and_then(|table| DsigTable::new(table).ok()),
+            ebdt: tables[TABLE_INDEX_EBDT].and_then(|table| EbdtTable::new(table).ok()),
+            eblc: tables[TABLE_INDEX_EBLC].and_then(|table| CblcTable::new(table).ok()),
+            fvar: tables[TABLE_INDEX_FVAR].and_then(|table| FvarTable::new(table).ok()),
+            gasp: tables[TABLE_INDEX_GASP].and_then(|table| GaspTable::new(table).ok()),
             glyf: tables[TABLE_INDEX_GLYF].map(GlyfTable::new),
+            gpos: tables[TABLE_INDEX_GPOS].and_then(|table| GposTable::new(table).ok()),
+            gsub: tables[TABLE_INDEX_GSUB].and_then(|table| GsubTable::new(table).ok()),
+            gvar: tables[TABLE_INDEX_GVAR].and_then(|table| GvarTable::new(table).ok()),
             loca: loca_table,
+            math: tables[TABLE_INDEX_MATH].and_then(|table| MathTable::new(table).ok()),
+            meta: tables[TABLE_INDEX_META].and_then(|table| MetaTable::new(table).ok()),
+            morx: tables[TABLE_INDEX_MORX].and_then(|table| MorxTable::new(table).ok()),
             kern: tables[TABLE_INDEX_KERN].and_then(|table| KernTable::new(table).ok()),
+            name: tables[TABLE_INDEX_NAME].and_then(|table| NameTable::new(table).ok()),
+            post: tables[TABLE_INDEX_POST].and_then(|table| PostTable::new(table).ok()),
+            sbix: sbix_table,
+            stat: tables[TABLE_INDEX_STAT].and_then(|table| StatTable::new(table).ok()),
+            svg: tables[TABLE_INDEX_SVG].and_then(|table| SvgTable::new(table).ok()),
+            vhea: tables[TABLE_INDEX_VHEA].and_then(|table| VheaTable::new(table).ok()),
+            vmtx: tables[TABLE_INDEX_VMTX].map(VmtxTable::new),
+            vorg: tables[TABLE_INDEX_VORG].and_then(|table| VorgTable::new(table).ok()),
 
             cvt: tables[TABLE_INDEX_CVT],
             fpgm: tables[TABLE_INDEX_FPGM],
             prep: tables[TABLE_INDEX_PREP],
+
+            unparsed: *tables,
         };
 
         Ok(Font::from_tables(bytes, tables))
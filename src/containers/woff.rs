@@ -11,8 +11,6 @@
 //! Web Open Font Format 1.0 (`.woff`) files.
 //!
 //! See the specification: https://www.w3.org/TR/WOFF/
-//!
-//! TODO(pcwalton): WOFF 2.0.
 
 use byteorder::{BigEndian, ReadBytesExt};
 use containers::otf::{KNOWN_TABLES, KNOWN_TABLE_COUNT, SFNT_VERSIONS};
@@ -85,6 +83,9 @@ impl<'a> Font<'a> {
 
             // Allocate space in the buffer.
             let comp_end = offset as usize + comp_length as usize;
+            if orig_length as usize > buffer.len() {
+                return Err(FontError::Failed)
+            }
             let mut temp = buffer;  // borrow check black magic
             let (mut dest, mut rest) = temp.split_at_mut(orig_length as usize);
             buffer = rest;
@@ -0,0 +1,532 @@
+// Copyright 2017 The Servo Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Web Open Font Format 2.0 (`.woff2`) files.
+//!
+//! See the specification: https://www.w3.org/TR/WOFF2/
+
+use brotli_decompressor::Decompressor;
+use byteorder::{BigEndian, ReadBytesExt};
+use containers::otf::{KNOWN_TABLES, KNOWN_TABLE_COUNT, SFNT_VERSIONS};
+use error::FontError;
+use font::{Font, FontTable};
+use std::cmp;
+use std::i16;
+use std::io::Read;
+use std::mem;
+use tables::{glyf, loca};
+use util::{Jump, push_u16};
+
+pub const MAGIC_NUMBER: u32 = ((b'w' as u32) << 24) |
+                               ((b'O' as u32) << 16) |
+                               ((b'F' as u32) << 8) |
+                                (b'2' as u32);
+
+// The tags that table directory entries can refer to by index instead of spelling them out.
+static KNOWN_TAGS: [&'static [u8; 4]; 63] = [
+    b"cmap", b"head", b"hhea", b"hmtx", b"maxp", b"name", b"OS/2", b"post",
+    b"cvt ", b"fpgm", b"glyf", b"loca", b"prep", b"CFF ", b"VORG", b"EBDT",
+    b"EBLC", b"gasp", b"hdmx", b"kern", b"LTSH", b"PCLT", b"VDMX", b"vhea",
+    b"vmtx", b"BASE", b"GDEF", b"GPOS", b"GSUB", b"EBSC", b"JSTF", b"MATH",
+    b"CBDT", b"CBLC", b"COLR", b"CPAL", b"SVG ", b"sbix", b"acnt", b"avar",
+    b"bdat", b"bloc", b"bsln", b"cvar", b"fdsc", b"feat", b"fmtx", b"fvar",
+    b"gvar", b"hsty", b"just", b"lcar", b"mort", b"morx", b"opbd", b"prop",
+    b"trak", b"Zapf", b"Silf", b"Glat", b"Gloc", b"Feat", b"Sill",
+];
+
+// The tag index meaning that the tag follows the flags explicitly.
+const EXPLICIT_TAG_INDEX: u8 = 63;
+
+// The `glyf` transform's option flag indicating that an overlap bitmap follows the streams.
+const OPTION_OVERLAP_SIMPLE_BITMAP: u16 = 1 << 0;
+
+// Codes for the `255UInt16` variable-length integer encoding.
+const WORD_CODE: u8 = 253;
+const ONE_MORE_BYTE_CODE_2: u8 = 254;
+const ONE_MORE_BYTE_CODE_1: u8 = 255;
+const LOWEST_U_CODE: u16 = 253;
+
+bitflags! {
+    flags SimpleFlags: u8 {
+        const ON_CURVE = 1 << 0,
+        const X_SHORT_VECTOR = 1 << 1,
+        const Y_SHORT_VECTOR = 1 << 2,
+        const THIS_X_IS_SAME = 1 << 4,
+        const THIS_Y_IS_SAME = 1 << 5,
+        const OVERLAP_SIMPLE = 1 << 6,
+    }
+}
+
+bitflags! {
+    flags CompositeFlags: u16 {
+        const ARG_1_AND_2_ARE_WORDS = 1 << 0,
+        const WE_HAVE_A_SCALE = 1 << 3,
+        const MORE_COMPONENTS = 1 << 5,
+        const WE_HAVE_AN_X_AND_Y_SCALE = 1 << 6,
+        const WE_HAVE_A_TWO_BY_TWO = 1 << 7,
+        const WE_HAVE_INSTRUCTIONS = 1 << 8,
+    }
+}
+
+impl<'a> Font<'a> {
+    /// Creates a new font from a buffer containing data in the WOFF 2.0 format.
+    ///
+    /// The given buffer will be used to hold the decompressed tables.
+    ///
+    /// Decompresses eagerly.
+    pub fn from_woff2<'b>(bytes: &'b [u8], buffer: &'b mut Vec<u8>)
+                          -> Result<Font<'b>, FontError> {
+        let mut reader = bytes;
+
+        // Check magic number.
+        let magic_number = try!(reader.read_u32::<BigEndian>().map_err(FontError::eof));
+        if magic_number != MAGIC_NUMBER {
+            return Err(FontError::UnknownFormat)
+        }
+
+        // Check the flavor. Collections aren't supported.
+        let flavor = try!(reader.read_u32::<BigEndian>().map_err(FontError::eof));
+        if !SFNT_VERSIONS.contains(&flavor) {
+            return Err(FontError::UnknownFormat)
+        }
+
+        // Get the number of tables and the size of the compressed data.
+        let _length = try!(reader.read_u32::<BigEndian>().map_err(FontError::eof));
+        let num_tables = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+        let _reserved = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+        let _total_sfnt_size = try!(reader.read_u32::<BigEndian>().map_err(FontError::eof));
+        let total_compressed_size = try!(reader.read_u32::<BigEndian>().map_err(FontError::eof));
+        try!(reader.jump(mem::size_of::<u16>() * 2 + mem::size_of::<u32>() * 5)
+                   .map_err(FontError::eof));
+
+        let mut entries = Vec::with_capacity(num_tables as usize);
+        for _ in 0..num_tables {
+            entries.push(try!(TableEntry::new(&mut reader)))
+        }
+
+        // All tables are compressed together in a single Brotli stream.
+        if reader.len() < total_compressed_size as usize {
+            return Err(FontError::UnexpectedEof)
+        }
+        let mut data = vec![];
+        let mut decompressor = Decompressor::new(&reader[0..total_compressed_size as usize], 4096);
+        try!(decompressor.read_to_end(&mut data).map_err(|_| FontError::Woff2BadCompression));
+
+        // Reconstruct the tables, remembering where in the buffer each one went.
+        debug_assert!(KNOWN_TABLES.windows(2).all(|w| w[0] < w[1]));
+        let mut locations = [None; KNOWN_TABLE_COUNT];
+        let (mut reconstructed_loca, mut loca_transformed) = (None, false);
+        let mut data_reader = &data[..];
+        for entry in &entries {
+            let length = entry.transform_length.unwrap_or(entry.orig_length) as usize;
+            if data_reader.len() < length {
+                return Err(FontError::UnexpectedEof)
+            }
+            let (table, rest) = data_reader.split_at(length);
+            data_reader = rest;
+
+            let table_index = match KNOWN_TABLES.binary_search(&entry.tag) {
+                Err(_) => continue,
+                Ok(table_index) => table_index,
+            };
+
+            // Make sure there isn't more than one copy of the table.
+            if locations[table_index].is_some() {
+                return Err(FontError::Failed)
+            }
+
+            let start = buffer.len();
+            match (entry.tag, entry.transform_length) {
+                (glyf::TAG, Some(_)) => {
+                    let loca = try!(reconstruct_glyf(table, buffer));
+                    locations[table_index] = Some((start, buffer.len()));
+
+                    let loca_start = buffer.len();
+                    buffer.extend_from_slice(&loca);
+                    reconstructed_loca = Some((loca_start, buffer.len()))
+                }
+                (loca::TAG, Some(_)) => {
+                    // The `loca` table is rebuilt along with the `glyf` table.
+                    loca_transformed = true
+                }
+                (_, Some(_)) => return Err(FontError::Woff2UnsupportedTransform),
+                (_, None) => {
+                    buffer.extend_from_slice(table);
+                    locations[table_index] = Some((start, buffer.len()))
+                }
+            }
+        }
+
+        if loca_transformed {
+            let table_index = KNOWN_TABLES.binary_search(&loca::TAG).unwrap();
+            if locations[table_index].is_some() {
+                return Err(FontError::Failed)
+            }
+            locations[table_index] = Some(try!(reconstructed_loca.ok_or(FontError::Failed)))
+        }
+
+        let buffer: &'b Vec<u8> = buffer;
+        let mut tables = [None; KNOWN_TABLE_COUNT];
+        for (table, location) in tables.iter_mut().zip(locations.iter()) {
+            if let Some((start, end)) = *location {
+                *table = Some(FontTable {
+                    bytes: &buffer[start..end],
+                })
+            }
+        }
+
+        Font::from_table_list(bytes, &tables)
+    }
+}
+
+// An entry in the table directory.
+struct TableEntry {
+    tag: u32,
+    orig_length: u32,
+    // The length of the transformed table, if the table is transformed.
+    transform_length: Option<u32>,
+}
+
+impl TableEntry {
+    fn new(reader: &mut &[u8]) -> Result<TableEntry, FontError> {
+        let flags = try!(reader.read_u8().map_err(FontError::eof));
+        let tag = match flags & 0x3f {
+            EXPLICIT_TAG_INDEX => try!(reader.read_u32::<BigEndian>().map_err(FontError::eof)),
+            tag_index => {
+                let tag = KNOWN_TAGS[tag_index as usize];
+                ((tag[0] as u32) << 24) | ((tag[1] as u32) << 16) | ((tag[2] as u32) << 8) |
+                    (tag[3] as u32)
+            }
+        };
+
+        // For `glyf` and `loca`, transform version 0 is the glyph transform and version 3 is the
+        // null transform. Every other table is untransformed in version 0.
+        let transform_version = flags >> 6;
+        let transformed = match tag {
+            glyf::TAG | loca::TAG => transform_version == 0,
+            _ => transform_version != 0,
+        };
+
+        let orig_length = try!(read_uint_base_128(reader));
+        let transform_length = if transformed {
+            Some(try!(read_uint_base_128(reader)))
+        } else {
+            None
+        };
+
+        Ok(TableEntry {
+            tag: tag,
+            orig_length: orig_length,
+            transform_length: transform_length,
+        })
+    }
+}
+
+// Reverses the glyph transform, appending the `glyf` table to the given buffer and returning the
+// `loca` table that goes with it.
+//
+// The transformed table splits glyph data into separate streams of contour counts, point counts,
+// flags, coordinates, composite glyph data, bounding boxes, and instructions, which compress
+// better.
+fn reconstruct_glyf(mut reader: &[u8], glyf: &mut Vec<u8>) -> Result<Vec<u8>, FontError> {
+    let _reserved = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+    let option_flags = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+    let num_glyphs = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof)) as usize;
+    let index_format = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+
+    let mut stream_sizes = [0; 7];
+    for stream_size in &mut stream_sizes {
+        *stream_size = try!(reader.read_u32::<BigEndian>().map_err(FontError::eof)) as usize
+    }
+    let mut streams = [&[][..]; 7];
+    for (stream, &stream_size) in streams.iter_mut().zip(stream_sizes.iter()) {
+        *stream = try!(split_stream(&mut reader, stream_size))
+    }
+    let (mut n_contour_stream, mut n_points_stream, mut flag_stream, mut glyph_stream) =
+        (streams[0], streams[1], streams[2], streams[3]);
+    let (mut composite_stream, mut bbox_stream, mut instruction_stream) =
+        (streams[4], streams[5], streams[6]);
+
+    let bbox_bitmap = try!(split_stream(&mut bbox_stream, (num_glyphs + 31) / 32 * 4));
+    let overlap_bitmap = if option_flags & OPTION_OVERLAP_SIMPLE_BITMAP != 0 {
+        Some(try!(split_stream(&mut reader, (num_glyphs + 7) / 8)))
+    } else {
+        None
+    };
+
+    let glyf_start = glyf.len();
+    let mut offsets = Vec::with_capacity(num_glyphs + 1);
+    for glyph_id in 0..num_glyphs {
+        offsets.push(glyf.len() - glyf_start);
+
+        let has_bbox = bitmap_contains(bbox_bitmap, glyph_id);
+        let n_contours = try!(n_contour_stream.read_i16::<BigEndian>().map_err(FontError::eof));
+        match n_contours {
+            0 if has_bbox => return Err(FontError::Woff2MalformedGlyf),
+            0 => {}
+            -1 => {
+                // Composite glyphs always store their bounding box.
+                if !has_bbox {
+                    return Err(FontError::Woff2MalformedGlyf)
+                }
+                push_u16(glyf, n_contours as u16);
+                glyf.extend_from_slice(try!(split_stream(&mut bbox_stream, 8)));
+
+                let components = composite_stream;
+                let mut have_instructions = false;
+                loop {
+                    let flags = try!(composite_stream.read_u16::<BigEndian>()
+                                                     .map_err(FontError::eof));
+                    let flags = CompositeFlags::from_bits_truncate(flags);
+                    let mut length = if flags.contains(ARG_1_AND_2_ARE_WORDS) { 6 } else { 4 };
+                    if flags.contains(WE_HAVE_A_SCALE) {
+                        length += 2
+                    } else if flags.contains(WE_HAVE_AN_X_AND_Y_SCALE) {
+                        length += 4
+                    } else if flags.contains(WE_HAVE_A_TWO_BY_TWO) {
+                        length += 8
+                    }
+                    try!(composite_stream.jump(length).map_err(FontError::eof));
+
+                    have_instructions = have_instructions || flags.contains(WE_HAVE_INSTRUCTIONS);
+                    if !flags.contains(MORE_COMPONENTS) {
+                        break
+                    }
+                }
+                glyf.extend_from_slice(&components[0..(components.len() -
+                                                       composite_stream.len())]);
+
+                if have_instructions {
+                    let instruction_length = try!(read_255_u16(&mut glyph_stream));
+                    push_u16(glyf, instruction_length);
+                    glyf.extend_from_slice(try!(split_stream(&mut instruction_stream,
+                                                             instruction_length as usize)))
+                }
+            }
+            n_contours if n_contours > 0 => {
+                let mut end_points = Vec::with_capacity(n_contours as usize);
+                let mut point_count = 0;
+                for _ in 0..n_contours {
+                    point_count += try!(read_255_u16(&mut n_points_stream)) as u32;
+                    if point_count == 0 || point_count > 0x10000 {
+                        return Err(FontError::Woff2MalformedGlyf)
+                    }
+                    end_points.push((point_count - 1) as u16)
+                }
+
+                let mut points = Vec::with_capacity(point_count as usize);
+                let (mut x, mut y) = (0, 0);
+                for _ in 0..point_count {
+                    let flag = try!(flag_stream.read_u8().map_err(FontError::eof));
+                    let (dx, dy) = try!(read_triplet(flag & 0x7f, &mut glyph_stream));
+                    x += dx;
+                    y += dy;
+                    if x < i16::MIN as i32 || x > i16::MAX as i32 || y < i16::MIN as i32 ||
+                            y > i16::MAX as i32 {
+                        return Err(FontError::Woff2MalformedGlyf)
+                    }
+                    points.push((x, y, flag & 0x80 == 0))
+                }
+
+                let instruction_length = try!(read_255_u16(&mut glyph_stream));
+                let instructions = try!(split_stream(&mut instruction_stream,
+                                                     instruction_length as usize));
+
+                push_u16(glyf, n_contours as u16);
+                if has_bbox {
+                    glyf.extend_from_slice(try!(split_stream(&mut bbox_stream, 8)))
+                } else {
+                    let (mut x_min, mut y_min) = (i32::max_value(), i32::max_value());
+                    let (mut x_max, mut y_max) = (i32::min_value(), i32::min_value());
+                    for &(x, y, _) in &points {
+                        x_min = cmp::min(x_min, x);
+                        y_min = cmp::min(y_min, y);
+                        x_max = cmp::max(x_max, x);
+                        y_max = cmp::max(y_max, y);
+                    }
+                    for &value in &[x_min, y_min, x_max, y_max] {
+                        push_u16(glyf, value as u16)
+                    }
+                }
+                for &end_point in &end_points {
+                    push_u16(glyf, end_point)
+                }
+                push_u16(glyf, instruction_length);
+                glyf.extend_from_slice(instructions);
+
+                let overlap = overlap_bitmap.map_or(false, |bitmap| {
+                    bitmap_contains(bitmap, glyph_id)
+                });
+                push_simple_glyph_points(glyf, &points, overlap)
+            }
+            _ => return Err(FontError::Woff2MalformedGlyf),
+        }
+
+        // Pad glyphs to four bytes, which keeps offsets even as short `loca` tables require.
+        while (glyf.len() - glyf_start) % 4 != 0 {
+            glyf.push(0)
+        }
+    }
+    offsets.push(glyf.len() - glyf_start);
+
+    let mut loca = vec![];
+    for &offset in &offsets {
+        match index_format {
+            0 if offset / 2 <= 0xffff => push_u16(&mut loca, (offset / 2) as u16),
+            0 => return Err(FontError::Woff2MalformedGlyf),
+            _ => {
+                push_u16(&mut loca, (offset >> 16) as u16);
+                push_u16(&mut loca, offset as u16)
+            }
+        }
+    }
+    Ok(loca)
+}
+
+// Appends the flags and coordinates of a simple glyph, in the usual `glyf` encoding.
+fn push_simple_glyph_points(glyf: &mut Vec<u8>, points: &[(i32, i32, bool)], overlap: bool) {
+    let (mut xs, mut ys) = (vec![], vec![]);
+    let (mut last_x, mut last_y) = (0, 0);
+    for (index, &(x, y, on_curve)) in points.iter().enumerate() {
+        let mut flags = SimpleFlags::empty();
+        if on_curve {
+            flags.insert(ON_CURVE)
+        }
+        if overlap && index == 0 {
+            flags.insert(OVERLAP_SIMPLE)
+        }
+        flags.insert(push_coordinate(&mut xs, x - last_x, X_SHORT_VECTOR, THIS_X_IS_SAME));
+        flags.insert(push_coordinate(&mut ys, y - last_y, Y_SHORT_VECTOR, THIS_Y_IS_SAME));
+        glyf.push(flags.bits());
+        last_x = x;
+        last_y = y;
+    }
+    glyf.extend_from_slice(&xs);
+    glyf.extend_from_slice(&ys);
+}
+
+// Appends one coordinate delta in its shortest encoding and returns the flags that select it.
+fn push_coordinate(coordinates: &mut Vec<u8>, delta: i32, short: SimpleFlags, same: SimpleFlags)
+                   -> SimpleFlags {
+    if delta == 0 {
+        same
+    } else if delta > -256 && delta < 256 {
+        coordinates.push(delta.abs() as u8);
+        if delta > 0 {
+            short | same
+        } else {
+            short
+        }
+    } else {
+        push_u16(coordinates, delta as u16);
+        SimpleFlags::empty()
+    }
+}
+
+// Decodes the coordinate deltas of a point, given the low seven bits of its flag.
+//
+// The flag selects one of a number of encodings of varying length, so that small and axis-aligned
+// deltas take fewer bytes.
+fn read_triplet(flag: u8, reader: &mut &[u8]) -> Result<(i32, i32), FontError> {
+    let mut bytes = [0; 4];
+    let byte_count = match flag {
+        0...83 => 1,
+        84...119 => 2,
+        120...123 => 3,
+        _ => 4,
+    };
+    for byte in &mut bytes[0..byte_count] {
+        *byte = try!(reader.read_u8().map_err(FontError::eof))
+    }
+    let (b0, b1, b2, b3) = (bytes[0] as i32, bytes[1] as i32, bytes[2] as i32, bytes[3] as i32);
+
+    let flag_value = flag as i32;
+    Ok(match flag {
+        0...9 => (0, with_sign(flag, ((flag_value & 14) << 7) + b0)),
+        10...19 => (with_sign(flag, (((flag_value - 10) & 14) << 7) + b0), 0),
+        20...83 => {
+            let index = flag_value - 20;
+            (with_sign(flag, 1 + (index & 0x30) + (b0 >> 4)),
+             with_sign(flag >> 1, 1 + ((index & 0x0c) << 2) + (b0 & 0x0f)))
+        }
+        84...119 => {
+            let index = flag_value - 84;
+            (with_sign(flag, 1 + ((index / 12) << 8) + b0),
+             with_sign(flag >> 1, 1 + (((index % 12) >> 2) << 8) + b1))
+        }
+        120...123 => {
+            (with_sign(flag, (b0 << 4) + (b1 >> 4)), with_sign(flag >> 1, ((b1 & 0x0f) << 8) + b2))
+        }
+        _ => (with_sign(flag, (b0 << 8) + b1), with_sign(flag >> 1, (b2 << 8) + b3)),
+    })
+}
+
+// The low bit of a triplet flag is set if the value is positive.
+#[inline]
+fn with_sign(flag: u8, value: i32) -> i32 {
+    if flag & 1 != 0 {
+        value
+    } else {
+        -value
+    }
+}
+
+// Reads a `UIntBase128`, a big-endian integer stored seven bits per byte, with the high bit set on
+// all but the last byte.
+fn read_uint_base_128(reader: &mut &[u8]) -> Result<u32, FontError> {
+    let mut value = 0u32;
+    for index in 0..5 {
+        let byte = try!(reader.read_u8().map_err(FontError::eof));
+
+        // Leading zeros and values that don't fit in 32 bits are invalid.
+        if (index == 0 && byte == 0x80) || value & 0xfe00_0000 != 0 {
+            return Err(FontError::Failed)
+        }
+
+        value = (value << 7) | (byte & 0x7f) as u32;
+        if byte & 0x80 == 0 {
+            return Ok(value)
+        }
+    }
+    Err(FontError::Failed)
+}
+
+// Reads a `255UInt16`, which stores small values in a single byte.
+fn read_255_u16(reader: &mut &[u8]) -> Result<u16, FontError> {
+    let code = try!(reader.read_u8().map_err(FontError::eof));
+    match code {
+        WORD_CODE => reader.read_u16::<BigEndian>().map_err(FontError::eof),
+        ONE_MORE_BYTE_CODE_1 => {
+            let value = try!(reader.read_u8().map_err(FontError::eof));
+            Ok(LOWEST_U_CODE + value as u16)
+        }
+        ONE_MORE_BYTE_CODE_2 => {
+            let value = try!(reader.read_u8().map_err(FontError::eof));
+            Ok(LOWEST_U_CODE * 2 + value as u16)
+        }
+        _ => Ok(code as u16),
+    }
+}
+
+// Splits the first `length` bytes off the reader.
+fn split_stream<'a>(reader: &mut &'a [u8], length: usize) -> Result<&'a [u8], FontError> {
+    if reader.len() < length {
+        return Err(FontError::UnexpectedEof)
+    }
+    let (stream, rest) = reader.split_at(length);
+    *reader = rest;
+    Ok(stream)
+}
+
+// Returns true if the bit for the given glyph is set in a bitmap, most significant bit first.
+#[inline]
+fn bitmap_contains(bitmap: &[u8], glyph_id: usize) -> bool {
+    bitmap.get(glyph_id / 8).map_or(false, |byte| byte & (0x80 >> (glyph_id % 8)) != 0)
+}
@@ -0,0 +1,112 @@
+// Copyright 2017 The Servo Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Conversions from glyph outlines to the path types of `lyon` and `kurbo`, with the features of
+//! the same names.
+//!
+//! Each contour becomes a move, then a line or curve to each on-curve point, and then a close.
+
+use euclid::Point2D;
+use font::{Outline, PointKind};
+
+#[cfg(feature = "kurbo")]
+use kurbo::{self, BezPath};
+#[cfg(feature = "lyon")]
+use lyon::math;
+#[cfg(feature = "lyon")]
+use lyon::path::Path;
+#[cfg(feature = "lyon")]
+use lyon::path::builder::{FlatPathBuilder, PathBuilder};
+
+// A command of a path, in font units.
+enum Command {
+    MoveTo(Point2D<f32>),
+    LineTo(Point2D<f32>),
+    QuadTo(Point2D<f32>, Point2D<f32>),
+    CubicTo(Point2D<f32>, Point2D<f32>, Point2D<f32>),
+    Close,
+}
+
+// Calls the callback with the commands that draw the outline.
+fn for_each_command<F>(outline: &Outline, mut callback: F) where F: FnMut(Command) {
+    for contour in &outline.contours {
+        let start = match contour.points.first() {
+            Some(start) => start.position,
+            None => continue,
+        };
+        callback(Command::MoveTo(to_f32(start)));
+
+        // The last point is usually the start again, which the close draws a line to. Lines back
+        // to the start elsewhere in the contour are kept.
+        let last_index = contour.points.len() - 1;
+        let mut control_points = vec![];
+        for (index, point) in contour.points.iter().enumerate().skip(1) {
+            if point.kind != PointKind::OnCurve {
+                control_points.push(to_f32(point.position));
+                continue
+            }
+            let position = to_f32(point.position);
+            match control_points.len() {
+                0 if index == last_index && point.position == start => {}
+                0 => callback(Command::LineTo(position)),
+                1 => callback(Command::QuadTo(control_points[0], position)),
+                _ => callback(Command::CubicTo(control_points[0], control_points[1], position)),
+            }
+            control_points.clear()
+        }
+        callback(Command::Close)
+    }
+}
+
+fn to_f32(point: Point2D<i16>) -> Point2D<f32> {
+    Point2D::new(point.x as f32, point.y as f32)
+}
+
+#[cfg(feature = "lyon")]
+impl<'a> From<&'a Outline> for Path {
+    fn from(outline: &'a Outline) -> Path {
+        let point = |point: Point2D<f32>| math::point(point.x, point.y);
+        let mut builder = Path::builder();
+        for_each_command(outline, |command| {
+            match command {
+                Command::MoveTo(to) => builder.move_to(point(to)),
+                Command::LineTo(to) => builder.line_to(point(to)),
+                Command::QuadTo(control, to) => {
+                    builder.quadratic_bezier_to(point(control), point(to))
+                }
+                Command::CubicTo(control_0, control_1, to) => {
+                    builder.cubic_bezier_to(point(control_0), point(control_1), point(to))
+                }
+                Command::Close => builder.close(),
+            }
+        });
+        builder.build()
+    }
+}
+
+#[cfg(feature = "kurbo")]
+impl<'a> From<&'a Outline> for BezPath {
+    fn from(outline: &'a Outline) -> BezPath {
+        let point = |point: Point2D<f32>| kurbo::Point::new(point.x as f64, point.y as f64);
+        let mut path = BezPath::new();
+        for_each_command(outline, |command| {
+            match command {
+                Command::MoveTo(to) => path.move_to(point(to)),
+                Command::LineTo(to) => path.line_to(point(to)),
+                Command::QuadTo(control, to) => path.quad_to(point(control), point(to)),
+                Command::CubicTo(control_0, control_1, to) => {
+                    path.curve_to(point(control_0), point(control_1), point(to))
+                }
+                Command::Close => path.close_path(),
+            }
+        });
+        path
+    }
+}
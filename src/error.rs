@@ -21,6 +21,8 @@ pub enum FontError {
     Failed,
     /// The file ended unexpectedly.
     UnexpectedEof,
+    /// The file ended unexpectedly while reading the given field.
+    UnexpectedEofIn(ErrorContext),
     /// There is no font with this index in this font collection.
     FontIndexOutOfBounds,
     /// The file declared that it was in a version of the format we don't support.
@@ -29,6 +31,14 @@ pub enum FontError {
     UnknownFormat,
     /// The font had a glyph format we don't support.
     UnsupportedGlyphFormat,
+    /// We don't support the declared version of the font's color bitmap data table.
+    UnsupportedCbdtVersion,
+    /// We don't support the declared version of the font's color bitmap location table.
+    UnsupportedCblcVersion,
+    /// The font's color bitmap location table has an index subtable of an unsupported format.
+    UnsupportedCblcIndexFormat,
+    /// We don't support the declared version of the font's embedded bitmap data table.
+    UnsupportedEbdtVersion,
     /// We don't support the declared version of the font's CFF outlines.
     UnsupportedCffVersion,
     /// We don't support the declared version of the font's character map.
@@ -37,24 +47,110 @@ pub enum FontError {
     UnsupportedCmapEncoding,
     /// The font character map has an unsupported format.
     UnsupportedCmapFormat,
+    /// We don't support the declared version of the font's color table.
+    UnsupportedColrVersion,
+    /// We don't support the declared version of the font's color palette table.
+    UnsupportedCpalVersion,
+    /// We don't support the declared version of the font's digital signature table.
+    UnsupportedDsigVersion,
+    /// We don't support the declared version of the font's font variations table.
+    UnsupportedFvarVersion,
+    /// We don't support the declared version of the font's grid-fitting and scan-conversion
+    /// procedure table.
+    UnsupportedGaspVersion,
+    /// We don't support the declared version of the font's glyph positioning table.
+    UnsupportedGposVersion,
+    /// We don't support the declared version of the font's glyph substitution table.
+    UnsupportedGsubVersion,
+    /// We don't support the declared version of the font's glyph variations table.
+    UnsupportedGvarVersion,
     /// We don't support the declared version of the font header.
     UnsupportedHeadVersion,
+    /// The font header had the wrong magic number.
+    BadHeadMagicNumber,
+    /// The font header's units per em was outside the valid range of 16 to 16384.
+    InvalidUnitsPerEm,
+    /// The font header's index-to-location format was neither short (0) nor long (1) offsets.
+    InvalidIndexToLocFormat,
     /// We don't support the declared version of the font's horizontal metrics.
     UnsupportedHheaVersion,
+    /// We don't support the declared version of the font's mathematical typesetting table.
+    UnsupportedMathVersion,
+    /// We don't support the declared version of the font's maximum profile.
+    UnsupportedMaxpVersion,
+    /// We don't support the declared version of the font's metadata table.
+    UnsupportedMetaVersion,
+    /// We don't support the declared version of the font's extended glyph metamorphosis table.
+    UnsupportedMorxVersion,
+    /// We don't support the format of the font's naming table.
+    UnsupportedNameFormat,
     /// We don't support the declared version of the font's OS/2 and Windows table.
     UnsupportedOs2Version,
+    /// We don't support the declared version of the font's PostScript table.
+    UnsupportedPostVersion,
+    /// We don't support the declared version of the font's standard bitmap graphics table.
+    UnsupportedSbixVersion,
+    /// We don't support the declared version of the font's style attributes table.
+    UnsupportedStatVersion,
+    /// We don't support the declared version of the font's SVG table.
+    UnsupportedSvgVersion,
+    /// We don't support the declared version of the font's vertical metrics.
+    UnsupportedVheaVersion,
+    /// We don't support the declared version of the font's vertical origin table.
+    UnsupportedVorgVersion,
     /// A required table is missing.
     RequiredTableMissing,
+    /// The font's table directory was malformed.
+    BadTableDirectory,
+    /// The table with the given tag didn't match its checksum in the table directory.
+    BadTableChecksum(u32),
+    /// The checksum adjustment in the font header didn't match the checksum of the whole font.
+    BadFontChecksum,
+    /// A glyph ID was out of range for this font.
+    NoSuchGlyph,
+    /// We can't subset this kind of font, such as one with only bitmap glyphs or a CID-keyed or
+    /// variable CFF font.
+    UnsupportedSubsetFormat,
+    /// The offsets in the `loca` table went backwards.
+    LocaMalformed,
+    /// A glyph in the `glyf` table was malformed.
+    GlyfMalformedGlyph,
+    /// Composite glyphs in the `glyf` table were nested too deeply.
+    GlyfCompositeNestingTooDeep,
+    /// A gzip-compressed SVG document was malformed.
+    SvgBadCompression,
+    /// The Brotli-compressed data in a WOFF 2.0 file was malformed.
+    Woff2BadCompression,
+    /// A WOFF 2.0 file used a table transform that we don't support.
+    Woff2UnsupportedTransform,
+    /// The transformed `glyf` table in a WOFF 2.0 file was malformed.
+    Woff2MalformedGlyf,
     /// An integer in a CFF DICT was not found.
     CffIntegerNotFound,
+    /// A real number in a CFF DICT was malformed.
+    CffBadRealNumber,
     /// The CFF Top DICT was not found.
     CffTopDictNotFound,
     /// A CFF `Offset` value was formatted incorrectly.
     CffBadOffset,
     /// The CFF evaluation stack overflowed.
     CffStackOverflow,
+    /// A CFF CharString operator was given the wrong number of operands.
+    CffMalformedCharString,
     /// An unimplemented CFF CharString operator was encountered.
     CffUnimplementedOperator,
+    /// A CFF CharString called a subroutine that doesn't exist.
+    CffSubrNotFound,
+    /// CFF subroutine calls were nested too deeply.
+    CffSubrNestingTooDeep,
+    /// A CID-keyed CFF font didn't map a glyph to a valid font DICT.
+    CffFontDictNotFound,
+    /// A CFF accented character referred to a missing or invalid base or accent glyph.
+    CffBadSeacComponent,
+    /// A Type 1 font file was malformed.
+    Type1MalformedFont,
+    /// A Type 1 CharString was malformed or used an unknown operator.
+    Type1MalformedCharString,
 }
 
 impl FontError {
@@ -63,6 +159,24 @@ impl FontError {
     pub fn eof<T>(_: T) -> FontError {
         FontError::UnexpectedEof
     }
+
+    /// Returns true if the file ended unexpectedly, whether or not we know where.
+    #[inline]
+    pub fn is_eof(&self) -> bool {
+        match *self {
+            FontError::UnexpectedEof | FontError::UnexpectedEofIn(_) => true,
+            _ => false,
+        }
+    }
+}
+
+/// Where in a font an error occurred.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct ErrorContext {
+    /// The tag of the table being read, such as `OS/2`.
+    pub table: u32,
+    /// The field of the table being read, such as "typo ascender".
+    pub field: &'static str,
 }
 
 
@@ -16,18 +16,51 @@ use containers::dfont;
 use containers::otf::{FontTables, SFNT_VERSIONS};
 use containers::ttc;
 use containers::woff;
+use containers::woff2;
 use error::FontError;
-use euclid::Point2D;
-use outline::GlyphBounds;
+use euclid::{Matrix2D, Point2D};
+use outline::{GlyphBounds, GlyphSubpixelBounds};
+use scanline::Accumulator;
+use std::borrow::Cow;
+use std::cmp;
+use std::collections::HashMap;
+use std::f32;
+use std::i16;
+use std::vec;
+use subset;
+use tables::cff::CffTable;
+use tables::gasp::GaspBehavior;
+use tables::gpos;
 use tables::hmtx::HorizontalMetrics;
+use tables::math::{GlyphAssembly, GlyphVariant, MathConstants};
+use tables::os_2::Panose;
+use tables::vmtx::VerticalMetrics;
+use util::{Flattener, Reader, SvgPathWriter};
+
+// How far, in pixels, the flattened outlines that `rasterize()` fills may stray from the curves.
+const RASTERIZATION_TOLERANCE: f32 = 0.1;
+
+// Color sources come first, then variable outlines before static ones.
+static DEFAULT_OUTLINE_SOURCE_PRIORITY: [OutlineSource; 5] = [
+    OutlineSource::Colr,
+    OutlineSource::Sbix,
+    OutlineSource::Cff2,
+    OutlineSource::Glyf,
+    OutlineSource::Cff,
+];
 
 /// A handle to a font backed by a byte buffer containing the contents of the file (`.ttf`,
 /// `.otf`), etc.
 ///
 /// For optimum performance, consider using the `memmap` crate to provide the byte buffer.
+///
+/// Glyph outlines are read from whichever of the `glyf`, `CFF `, or `CFF2` tables the font has, so
+/// callers don't need to know which kind of font they have. Fonts with more than one are read in
+/// the order of `outline_source_priority()`.
 pub struct Font<'a> {
     pub bytes: &'a [u8],
     tables: FontTables<'a>,
+    outline_source_priority: Vec<OutlineSource>,
 }
 
 #[doc(hidden)]
@@ -36,12 +69,58 @@ pub struct FontTable<'a> {
     pub bytes: &'a [u8],
 }
 
+impl<'a> FontTable<'a> {
+    /// Returns the `length` bytes of the table starting at `offset`.
+    ///
+    /// Returns `FontError::UnexpectedEof` if they don't all lie within the table.
+    pub fn subslice(&self, offset: usize, length: usize) -> Result<&'a [u8], FontError> {
+        match offset.checked_add(length) {
+            Some(end) if end <= self.bytes.len() => Ok(&self.bytes[offset..end]),
+            _ => Err(FontError::UnexpectedEof),
+        }
+    }
+}
+
+/// The table directory of an sfnt font (`.ttf` or `.otf`), which lists the tables in the font.
+///
+/// This is the step between the raw bytes of a font and its tables, for callers who want to read
+/// tables that `Font` doesn't expose. Use `Font` for everything else.
+#[derive(Clone, Copy, Debug)]
+pub struct TableDirectory<'a> {
+    // The whole file, which table offsets are relative to.
+    bytes: &'a [u8],
+    sfnt_version: u32,
+    // The offset subtable that precedes the table records.
+    header: &'a [u8],
+    // The table records, 16 bytes each.
+    records: &'a [u8],
+}
+
+/// An entry in the table directory of a font.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct TableRecord {
+    /// The table's tag, such as `glyf`.
+    pub tag: u32,
+    pub checksum: u32,
+    /// The offset of the table from the start of the file.
+    pub offset: u32,
+    /// The length of the table in bytes.
+    pub length: u32,
+}
+
+/// An iterator over the records in a table directory, in the order that the font lists them.
+#[derive(Clone)]
+pub struct TableRecords<'a> {
+    reader: Reader<'a>,
+}
+
 impl<'a> Font<'a> {
     #[doc(hidden)]
     pub fn from_tables<'b>(bytes: &'b [u8], tables: FontTables<'b>) -> Font<'b> {
         Font {
             bytes: bytes,
             tables: tables,
+            outline_source_priority: DEFAULT_OUTLINE_SOURCE_PRIORITY.to_vec(),
         }
     }
 
@@ -76,6 +155,7 @@ impl<'a> Font<'a> {
         match magic_number {
             ttc::MAGIC_NUMBER => Font::from_ttc_index(bytes, index),
             woff::MAGIC_NUMBER => Font::from_woff(bytes, buffer),
+            woff2::MAGIC_NUMBER => Font::from_woff2(bytes, buffer),
             dfont::MAGIC_NUMBER => Font::from_dfont_index(bytes, index),
             magic_number if SFNT_VERSIONS.contains(&magic_number) => Font::from_otf(bytes, 0),
             _ => Err(FontError::UnknownFormat),
@@ -91,42 +171,679 @@ impl<'a> Font<'a> {
         self.tables.cmap.glyph_mapping_for_codepoint_ranges(codepoint_ranges)
     }
 
+    /// Returns the glyph ID for the given character, or `None` if the font doesn't map it.
+    ///
+    /// Unmapped characters are typically drawn with glyph 0, the missing glyph.
+    #[inline]
+    pub fn glyph_for_char(&self, character: char) -> Result<Option<u16>, FontError> {
+        self.tables.cmap.glyph_for_char(character)
+    }
+
+    /// Returns the glyph ID for the given character followed by the given variation selector,
+    /// such as U+FE0E for text presentation or U+FE0F for emoji presentation.
+    ///
+    /// Returns `None` if the font doesn't support this variation sequence.
+    #[inline]
+    pub fn glyph_for_char_variant(&self, base: char, selector: char)
+                                  -> Result<Option<u16>, FontError> {
+        self.tables.cmap.glyph_for_char_variant(base, selector)
+    }
+
+    /// Returns a map from each glyph ID to a character that maps to it, which is useful for
+    /// recovering text from glyphs.
+    ///
+    /// When several characters map to the same glyph, the one with the lowest code point is used.
+    #[inline]
+    pub fn glyph_to_char_map(&self) -> Result<HashMap<u16, char>, FontError> {
+        self.tables.cmap.build_reverse()
+    }
+
+    /// Writes a new font file containing only the given glyphs and those that they depend on, so
+    /// that documents can embed just the glyphs that they use.
+    ///
+    /// Along with the given glyphs, the subset has `.notdef`, the components of composite and
+    /// accented glyphs, and any ligatures that the glyphs can form. Glyphs keep their order but
+    /// are renumbered from 0, and the character map only maps characters to glyphs that remain.
+    /// Tables that refer to glyphs but aren't rewritten, such as `GSUB` and `kern`, are left out.
+    ///
+    /// CID-keyed and variable CFF fonts and fonts without outlines aren't supported.
+    pub fn subset(&self, glyphs: &[u16]) -> Result<Vec<u8>, FontError> {
+        subset::subset(&self.tables, glyphs)
+    }
+
+    /// Returns the font's family name (for example, "Helvetica"), if it has one.
+    #[inline]
+    pub fn family_name(&self) -> Option<String> {
+        self.tables.name.and_then(|name| name.family_name())
+    }
+
+    /// Returns the font's PostScript name (for example, "Helvetica-Bold"), if it has one.
+    #[inline]
+    pub fn postscript_name(&self) -> Option<String> {
+        self.tables.name.and_then(|name| name.postscript_name())
+    }
+
+    /// Returns the languages and scripts that the font was primarily designed for, as
+    /// ScriptLangTags such as `Latn` or `zh-Hant`.
+    ///
+    /// The result is empty if the font doesn't say.
+    pub fn design_languages(&self) -> Vec<String> {
+        self.tables.meta.map(|meta| meta.design_languages()).unwrap_or_else(|| vec![])
+    }
+
+    /// Returns the languages and scripts that the font can display, as ScriptLangTags.
+    ///
+    /// This is useful for choosing fallback fonts. The result is empty if the font doesn't say,
+    /// in which case the character map is the only guide.
+    pub fn supported_languages(&self) -> Vec<String> {
+        self.tables.meta.map(|meta| meta.supported_languages()).unwrap_or_else(|| vec![])
+    }
+
+    /// Returns the metadata with the given tag from the font's metadata table, if any.
+    ///
+    /// The tag is four ASCII bytes in big-endian order. See `design_languages()` and
+    /// `supported_languages()` for the standard entries.
+    #[inline]
+    pub fn metadata(&self, tag: u32) -> Option<&'a [u8]> {
+        self.tables.meta.and_then(|meta| meta.get(tag))
+    }
+
+    /// Returns the PostScript name of the given glyph, if the font has one for it.
+    ///
+    /// Names come from the `post` table or, if it has none, from the CFF charset.
+    #[inline]
+    pub fn glyph_name(&self, glyph_id: u16) -> Option<&'a str> {
+        self.tables.post.and_then(|post| post.glyph_name(glyph_id)).or_else(|| {
+            self.tables.cff.and_then(|cff| cff.glyph_name(glyph_id))
+        })
+    }
+
+    /// Returns the ID of the first glyph with the given PostScript name, if there is one.
+    #[inline]
+    pub fn glyph_for_name(&self, name: &str) -> Option<u16> {
+        self.tables.post.and_then(|post| post.glyph_for_name(name)).or_else(|| {
+            self.tables.cff.and_then(|cff| cff.glyph_for_name(name))
+        })
+    }
+
+    /// Applies the glyph substitutions in the given OpenType features (such as `liga` for
+    /// standard ligatures) to the glyph sequence and returns the result.
+    ///
+    /// Each feature tag is the four ASCII bytes of the tag in big-endian order. If the font has
+    /// no glyph substitution table, the glyphs are returned unchanged.
+    pub fn substitute_glyphs(&self, glyph_ids: &[u16], feature_tags: &[u32])
+                             -> Result<Vec<u16>, FontError> {
+        match self.tables.gsub {
+            None => Ok(glyph_ids.to_vec()),
+            Some(gsub) => gsub.substitute(glyph_ids, feature_tags),
+        }
+    }
+
+    /// Applies the glyph substitutions of an Apple Advanced Typography font's extended glyph
+    /// metamorphosis table to the glyph sequence and returns the result.
+    ///
+    /// Substitutions that the font enables by default are applied, along with those of the given
+    /// features. Each feature is a pair of an AAT feature type and setting selector, such as
+    /// `(1, 2)` for rare ligatures. If the font has no metamorphosis table, the glyphs are returned
+    /// unchanged.
+    pub fn substitute_glyphs_aat(&self, glyph_ids: &[u16], features: &[(u16, u16)])
+                                 -> Result<Vec<u16>, FontError> {
+        match self.tables.morx {
+            None => Ok(glyph_ids.to_vec()),
+            Some(morx) => morx.substitute(glyph_ids, features),
+        }
+    }
+
+    /// Returns the color layers of the given glyph, bottommost first, as pairs of a layer glyph
+    /// ID and the color to fill it with.
+    ///
+    /// Each layer glyph's outline is an ordinary glyph and can be drawn with `for_each_point()`.
+    /// Returns `None` if the glyph isn't a color glyph; it should then be drawn in the text color
+    /// as usual.
+    #[inline]
+    pub fn color_layers(&self, base_glyph_id: u16) -> Option<Vec<(u16, ColorIndex)>> {
+        self.tables.colr.and_then(|colr| colr.color_layers(base_glyph_id))
+    }
+
+    /// Returns the RGBA color of the given entry in the given color palette. Palette 0 is the
+    /// default.
+    ///
+    /// Returns `None` if the font has no such palette or entry.
+    #[inline]
+    pub fn palette_color(&self, palette: u16, index: u16) -> Option<[u8; 4]> {
+        self.tables.cpal.and_then(|cpal| cpal.palette_color(palette, index))
+    }
+
+    /// Returns the sizes at which the font has embedded color bitmaps, such as emoji.
+    ///
+    /// Both Apple's `sbix` bitmaps and Google's `CBDT`/`CBLC` bitmaps are supported. The result is
+    /// empty if the font has no embedded bitmaps.
+    pub fn bitmap_strikes(&self) -> Vec<BitmapStrike> {
+        let strikes = match (self.tables.sbix, self.tables.cblc) {
+            (Some(sbix), _) => sbix.strikes(),
+            (None, Some(cblc)) => cblc.bitmap_strikes(),
+            (None, None) => Ok(vec![]),
+        };
+        strikes.unwrap_or_else(|_| vec![])
+    }
+
+    /// Returns the encoded image of the given glyph from the embedded bitmap strike best suited
+    /// to the given size, along with its format.
+    ///
+    /// The smallest strike at least `ppem` pixels per em is chosen, or the largest strike if all
+    /// of them are smaller. The image is returned undecoded. Returns `None` if the glyph has no
+    /// embedded bitmap.
+    pub fn glyph_bitmap(&self, glyph_id: u16, ppem: u16) -> Option<(BitmapFormat, &'a [u8])> {
+        let bitmap = match (self.tables.sbix, self.tables.cblc, self.tables.cbdt) {
+            (Some(sbix), _, _) => sbix.bitmap_data(glyph_id, ppem),
+            (None, Some(cblc), Some(cbdt)) => cbdt.glyph_bitmap(&cblc, glyph_id, ppem),
+            (None, _, _) => Ok(None),
+        };
+        bitmap.unwrap_or(None)
+    }
+
+    /// Returns the sizes at which the font has embedded monochrome or grayscale bitmaps, as many
+    /// CJK screen fonts do, from its `EBLC` table. The result is empty if it has none.
+    pub fn embedded_bitmap_strikes(&self) -> Vec<BitmapStrike> {
+        match self.tables.eblc {
+            None => vec![],
+            Some(eblc) => eblc.bitmap_strikes().unwrap_or_else(|_| vec![]),
+        }
+    }
+
+    /// Returns the monochrome or grayscale bitmap of the given glyph from the `EBDT` strike best
+    /// suited to the given size.
+    ///
+    /// The strike is chosen as for `glyph_bitmap()`. Returns `None` if the glyph has no embedded
+    /// bitmap in that strike, or if it's a composite of other glyphs' bitmaps.
+    pub fn embedded_bitmap(&self, glyph_id: u16, ppem: u16) -> Option<EmbeddedBitmap<'a>> {
+        match (self.tables.eblc, self.tables.ebdt) {
+            (Some(eblc), Some(ebdt)) => ebdt.glyph_bitmap(&eblc, glyph_id, ppem).unwrap_or(None),
+            _ => None,
+        }
+    }
+
+    /// Returns the SVG document containing the given glyph, if the font has one for it.
+    ///
+    /// Compressed documents are inflated. A document may contain several glyphs; this glyph is
+    /// the element with the ID `glyph<glyph_id>`, such as `glyph42`.
+    pub fn svg_document(&self, glyph_id: u16) -> Option<Cow<'a, [u8]>> {
+        match self.tables.svg {
+            None => None,
+            Some(svg) => svg.svg_document(glyph_id).unwrap_or(None),
+        }
+    }
+
+    /// Returns the global metrics for laying out mathematical formulas, if this is a math font.
+    pub fn math_constants(&self) -> Option<MathConstants> {
+        self.tables.math.and_then(|math| math.constants().ok())
+    }
+
+    /// Returns the italic correction of the given glyph in a math font, which is added after the
+    /// glyph when an upright glyph follows it.
+    pub fn math_italic_correction(&self, glyph_id: u16) -> Option<i16> {
+        self.tables.math.and_then(|math| math.italic_correction(glyph_id).unwrap_or(None))
+    }
+
+    /// Returns the horizontal position at which accents should be centered over the given glyph
+    /// in a math font.
+    ///
+    /// Returns `None` if the font doesn't specify one, in which case accents are centered over
+    /// the glyph's advance.
+    pub fn math_top_accent_attachment(&self, glyph_id: u16) -> Option<i16> {
+        self.tables.math.and_then(|math| math.top_accent_attachment(glyph_id).unwrap_or(None))
+    }
+
+    /// Returns the successively larger versions of the given glyph that a math font provides for
+    /// stretching it in the given direction.
+    ///
+    /// The result is empty if the glyph can't be stretched this way.
+    pub fn math_glyph_variants(&self, glyph_id: u16, direction: MathDirection)
+                               -> Vec<GlyphVariant> {
+        match self.tables.math {
+            None => vec![],
+            Some(math) => math.glyph_variants(glyph_id, direction).unwrap_or_else(|_| vec![]),
+        }
+    }
+
+    /// Returns the parts from which a math font builds the given glyph at sizes larger than any
+    /// of its variants, or `None` if it can't.
+    pub fn math_glyph_assembly(&self, glyph_id: u16, direction: MathDirection)
+                               -> Option<GlyphAssembly> {
+        self.tables.math.and_then(|math| {
+            math.glyph_assembly(glyph_id, direction).unwrap_or(None)
+        })
+    }
+
+    /// Returns the design axes of a variable font, such as weight or width.
+    ///
+    /// The result is empty if this isn't a variable font.
+    #[inline]
+    pub fn variation_axes(&self) -> &[VariationAxis] {
+        match self.tables.fvar {
+            None => &[],
+            Some(ref fvar) => fvar.axes(),
+        }
+    }
+
+    /// Returns the named instances of a variable font, which are predefined positions along its
+    /// design axes.
+    ///
+    /// The result is empty if this isn't a variable font.
+    #[inline]
+    pub fn named_instances(&self) -> &[NamedInstance] {
+        match self.tables.fvar {
+            None => &[],
+            Some(ref fvar) => fvar.named_instances(),
+        }
+    }
+
+    /// Returns the style axes of the font's family, such as weight or italic, if the font has a
+    /// style attributes table.
+    ///
+    /// Unlike `variation_axes()`, these include axes that this font doesn't vary along.
+    #[inline]
+    pub fn style_axes(&self) -> &[StyleAxis] {
+        match self.tables.stat {
+            None => &[],
+            Some(ref stat) => stat.axes(),
+        }
+    }
+
+    /// Returns the named values along the font's style axes.
+    #[inline]
+    pub fn axis_values(&self) -> &[AxisValue] {
+        match self.tables.stat {
+            None => &[],
+            Some(ref stat) => stat.axis_values(),
+        }
+    }
+
+    /// Returns the ID of the name to use for a style whose axis values are all elidable, such as
+    /// "Regular", if the font has a style attributes table.
+    #[inline]
+    pub fn elided_fallback_name_id(&self) -> Option<u16> {
+        self.tables.stat.as_ref().map(|stat| stat.elided_fallback_name_id())
+    }
+
+    /// Returns the name of the given value along the style axis with the given tag: for example,
+    /// "SemiBold" for a `wght` of 600.
+    pub fn style_value_name(&self, axis_tag: u32, value: f32) -> Option<String> {
+        let value_name_id = match self.tables.stat {
+            None => return None,
+            Some(ref stat) => stat.value_name_id(axis_tag, value),
+        };
+        value_name_id.and_then(|name_id| self.name(name_id))
+    }
+
+    /// Returns the name with the given ID from the naming table, such as one of the
+    /// font-specific names (ID 256 and up) that variable fonts use for axes and styles.
+    #[inline]
+    pub fn name(&self, name_id: u16) -> Option<String> {
+        self.tables.name.and_then(|name| name.get_by_id(name_id))
+    }
+
+    /// Returns the order in which the font's glyph sources are tried.
+    ///
+    /// By default, color layers come before color bitmaps, which come before `CFF2`, `glyf`, and
+    /// `CFF ` outlines, in that order.
+    #[inline]
+    pub fn outline_source_priority(&self) -> &[OutlineSource] {
+        &self.outline_source_priority
+    }
+
+    /// Sets the order in which the font's glyph sources are tried.
+    ///
+    /// `for_each_point()` and the functions built on it read outlines from the first outline
+    /// source in the list that the font has, and `outline_source()` reports the first source of
+    /// any kind that has a given glyph. Sources left out of the list are never used.
+    pub fn set_outline_source_priority(&mut self, priority: &[OutlineSource]) {
+        self.outline_source_priority = priority.to_vec()
+    }
+
+    /// Returns the source that the given glyph should be drawn from: the first one in
+    /// `outline_source_priority()` that has the glyph.
+    ///
+    /// `Colr` glyphs are drawn with `color_layers()`, `Sbix` glyphs with `glyph_bitmap()`, and the
+    /// rest with `for_each_point()`. Returns `None` if none of the sources in the list has the
+    /// glyph.
+    pub fn outline_source(&self, glyph_id: u16) -> Option<OutlineSource> {
+        if self.check_glyph_id(glyph_id).is_err() {
+            return None
+        }
+
+        self.outline_source_priority.iter().cloned().find(|&source| {
+            match source {
+                OutlineSource::Colr => self.color_layers(glyph_id).is_some(),
+                OutlineSource::Sbix => {
+                    self.tables.sbix.map_or(false, |sbix| {
+                        sbix.strikes().unwrap_or_else(|_| vec![]).iter().any(|strike| {
+                            match sbix.bitmap_data(glyph_id, strike.ppem) {
+                                Ok(Some(_)) => true,
+                                Ok(None) | Err(_) => false,
+                            }
+                        })
+                    })
+                }
+                OutlineSource::Cff2 | OutlineSource::Glyf | OutlineSource::Cff => {
+                    self.has_vector_outline_source(source)
+                }
+            }
+        })
+    }
+
     /// Calls the given callback for each point in the supplied glyph's contour.
     ///
-    /// This function is the primary method for accessing a glyph's outline.
+    /// This function is the primary method for accessing a glyph's outline. Positions are in font
+    /// units, of which there are `units_per_em()` to the em.
     #[inline]
     pub fn for_each_point<F>(&self, glyph_id: u16, callback: F) -> Result<(), FontError>
                              where F: FnMut(&Point) {
-        match (self.tables.glyf, self.tables.cff) {
-            (Some(glyf), None) => {
+        try!(self.check_glyph_id(glyph_id));
+        match (self.vector_outline_source(), self.tables.glyf, self.cff_outline_table()) {
+            (Some(OutlineSource::Glyf), Some(glyf), _) => {
+                let loca = match self.tables.loca {
+                    Some(ref loca) => loca,
+                    None => return Err(FontError::RequiredTableMissing),
+                };
+
+                glyf.for_each_point(loca, glyph_id, callback)
+            }
+            (_, _, Some(cff)) => cff.for_each_point(glyph_id, callback),
+            _ => Ok(()),
+        }
+    }
+
+    /// Returns the outline of the given glyph as owned contours of points.
+    ///
+    /// This produces the same points as `for_each_point()`. The result can be sent between
+    /// threads, so many glyphs can be decoded in parallel by sharing a `&Font`.
+    pub fn outline(&self, glyph_id: u16) -> Result<Outline, FontError> {
+        let mut points = vec![];
+        try!(self.for_each_point(glyph_id, |point| points.push(*point)));
+        Ok(Outline::from_points(points))
+    }
+
+    /// Like `for_each_point()`, but skips unsupported CFF CharString operators that don't affect
+    /// the outline instead of failing, returning the operators that were skipped.
+    ///
+    /// TrueType glyphs have no operators, so for them this is the same as `for_each_point()`.
+    pub fn for_each_point_leniently<F>(&self, glyph_id: u16, callback: F)
+                                       -> Result<Vec<u16>, FontError> where F: FnMut(&Point) {
+        try!(self.check_glyph_id(glyph_id));
+        match self.cff_outline_table() {
+            Some(cff) => cff.for_each_point_leniently(glyph_id, callback),
+            None => {
+                try!(self.for_each_point(glyph_id, callback));
+                Ok(vec![])
+            }
+        }
+    }
+
+    /// Like `for_each_point()`, but with the glyph's outline moved to the given position in the
+    /// design space of a variable font.
+    ///
+    /// `coordinates` holds the normalized position along each of the font's variation axes, in
+    /// the order of `variation_axes()`, from -1.0 to 1.0; `VariationAxis::normalize()` converts
+    /// design values to this form. Both TrueType (`gvar`) and CFF2 variations are applied. If the
+    /// font has no glyph variations, the default outline is produced.
+    pub fn for_each_point_with_variations<F>(&self,
+                                             glyph_id: u16,
+                                             coordinates: &[f32],
+                                             callback: F)
+                                             -> Result<(), FontError> where F: FnMut(&Point) {
+        try!(self.check_glyph_id(glyph_id));
+        match (self.vector_outline_source(),
+               self.tables.glyf,
+               self.tables.gvar,
+               self.cff_outline_table()) {
+            (Some(OutlineSource::Glyf), Some(glyf), Some(gvar), _) => {
                 let loca = match self.tables.loca {
                     Some(ref loca) => loca,
                     None => return Err(FontError::RequiredTableMissing),
                 };
 
-                glyf.for_each_point(&self.tables.head, loca, glyph_id, callback)
+                glyf.for_each_point_with_variations(loca,
+                                                    &gvar,
+                                                    coordinates,
+                                                    glyph_id,
+                                                    callback)
+            }
+            (_, _, _, Some(cff)) => {
+                cff.for_each_point_with_variations(glyph_id, coordinates, callback)
+            }
+            _ => self.for_each_point(glyph_id, callback),
+        }
+    }
+
+    /// Like `for_each_point()`, but maps each point through the given affine transform as it is
+    /// emitted.
+    ///
+    /// For example, `Matrix2D::create_scale(size / units_per_em, size / units_per_em)` scales the
+    /// outline to a pixel size, and `Matrix2D::row_major(1.0, 0.0, shear, 1.0, 0.0, 0.0)` slants it
+    /// for a synthetic oblique.
+    pub fn for_each_point_transformed<F>(&self,
+                                         glyph_id: u16,
+                                         transform: &Matrix2D<f32>,
+                                         mut callback: F)
+                                         -> Result<(), FontError>
+                                         where F: FnMut(&TransformedPoint) {
+        self.for_each_point(glyph_id, |point| callback(&point.transform(transform)))
+    }
+
+    /// Returns the outline of the given glyph with its curves flattened into line segments, none
+    /// of which is more than `tolerance` font units from the curve it replaces.
+    pub fn flatten(&self, glyph_id: u16, tolerance: f32)
+                   -> Result<Vec<Contour<Point2D<f32>>>, FontError> {
+        let mut flattener = Flattener::new(tolerance);
+        try!(self.for_each_point(glyph_id, |point| flattener.push(point)));
+        Ok(flattener.finish())
+    }
+
+    /// Rasterizes the given glyph on the CPU at the given size in pixels per em, returning its
+    /// coverage as an 8-bit alpha bitmap.
+    ///
+    /// The outline is scaled to pixels, then mapped through `transform`, which can add a subpixel
+    /// offset or a slant. Curves are flattened to within a tenth of a pixel, and each pixel's
+    /// coverage is the exact area of the flattened outline inside it. Empty glyphs
+    /// produce an empty bitmap.
+    ///
+    /// This is much slower than rendering on the GPU, and is meant for testing and for use where
+    /// there's no GPU.
+    pub fn rasterize(&self, glyph_id: u16, ppem: f32, transform: &Matrix2D<f32>)
+                     -> Result<GrayBitmap, FontError> {
+        let font_matrix = self.font_matrix();
+        let units_per_pixel = 1.0 / (ppem * font_matrix.m11.abs().max(font_matrix.m22.abs()));
+        let contours = try!(self.flatten(glyph_id, RASTERIZATION_TOLERANCE * units_per_pixel));
+        let contours: Vec<Vec<Point2D<f32>>> = contours.iter().map(|contour| {
+            contour.points.iter().map(|point| {
+                let position = font_matrix.transform_point(point);
+                transform.transform_point(&Point2D::new(position.x * ppem, position.y * ppem))
+            }).collect()
+        }).collect();
+
+        let mut min = Point2D::new(f32::MAX, f32::MAX);
+        let mut max = Point2D::new(f32::MIN, f32::MIN);
+        for point in contours.iter().flat_map(|contour| contour.iter()) {
+            min = Point2D::new(min.x.min(point.x), min.y.min(point.y));
+            max = Point2D::new(max.x.max(point.x), max.y.max(point.y));
+        }
+        if min.x > max.x {
+            return Ok(GrayBitmap {
+                width: 0,
+                height: 0,
+                left: 0,
+                top: 0,
+                coverage: vec![],
+            })
+        }
+
+        // Bitmap rows go down from the top.
+        let (left, top) = (min.x.floor() as i32, max.y.ceil() as i32);
+        let (width, height) = ((max.x.ceil() as i32 - left) as u32,
+                               (top - min.y.floor() as i32) as u32);
+        let to_bitmap = |point: &Point2D<f32>| {
+            Point2D::new(point.x - left as f32, top as f32 - point.y)
+        };
+        let mut accumulator = Accumulator::new(width, height);
+        for contour in &contours {
+            for (index, point) in contour.iter().enumerate() {
+                let next = &contour[(index + 1) % contour.len()];
+                accumulator.line(&to_bitmap(point), &to_bitmap(next))
             }
-            (None, Some(cff)) => cff.for_each_point(glyph_id, callback),
-            (Some(_), Some(_)) => Err(FontError::Failed),
-            (None, None) => Ok(()),
         }
+
+        Ok(GrayBitmap {
+            width: width,
+            height: height,
+            left: left,
+            top: top,
+            coverage: accumulator.finish(),
+        })
+    }
+
+    /// Returns the outline of the given glyph as the data of an SVG `<path>` element's `d`
+    /// attribute.
+    ///
+    /// Each contour begins with `M` and ends with `Z`, with `L` for lines, `Q` for the quadratic
+    /// curves of TrueType outlines, and `C` for the cubic curves of CFF outlines. Empty glyphs
+    /// produce an empty string.
+    pub fn to_svg_path(&self, glyph_id: u16, options: &SvgPathOptions)
+                       -> Result<String, FontError> {
+        let mut writer = SvgPathWriter::new(options);
+        try!(self.for_each_point(glyph_id, |point| writer.push(point)));
+        Ok(writer.finish())
     }
 
     /// Returns the boundaries of the given glyph in font units.
     #[inline]
     pub fn glyph_bounds(&self, glyph_id: u16) -> Result<GlyphBounds, FontError> {
-        match (self.tables.glyf, self.tables.cff) {
-            (Some(glyf), None) => {
+        try!(self.check_glyph_id(glyph_id));
+        match (self.vector_outline_source(), self.tables.glyf, self.cff_outline_table()) {
+            (Some(OutlineSource::Glyf), Some(glyf), _) => {
                 let loca = match self.tables.loca {
                     Some(ref loca) => loca,
                     None => return Err(FontError::RequiredTableMissing),
                 };
 
-                glyf.glyph_bounds(&self.tables.head, loca, glyph_id)
+                glyf.glyph_bounds(loca, glyph_id)
             }
-            (None, Some(cff)) => cff.glyph_bounds(glyph_id),
-            (Some(_), Some(_)) => Err(FontError::Failed),
-            (None, None) => Err(FontError::RequiredTableMissing),
+            (_, _, Some(cff)) => cff.glyph_bounds(glyph_id),
+            _ => Err(FontError::RequiredTableMissing),
+        }
+    }
+
+    /// Returns the boundaries of the given glyph in pixels, for the given size in pixels per em.
+    ///
+    /// This converts from font units with the font's own `units_per_em()`, which is usually 1000
+    /// for CFF fonts and 2048 for TrueType fonts, so bounds from different fonts can be compared.
+    pub fn scaled_glyph_bounds(&self, glyph_id: u16, ppem: f32)
+                               -> Result<GlyphSubpixelBounds, FontError> {
+        let bounds = try!(self.glyph_bounds(glyph_id));
+
+        // Multiply before dividing, so that exact results stay exact.
+        let units_per_em = self.units_per_em() as f32;
+        let scale = |units: i32| units as f32 * ppem / units_per_em;
+        Ok(GlyphSubpixelBounds {
+            left: scale(bounds.left),
+            bottom: scale(bounds.bottom),
+            right: scale(bounds.right),
+            top: scale(bounds.top),
+        })
+    }
+
+    /// Returns the combined ink bounds of a run of glyphs, in font units, given each glyph's pen
+    /// position in font units.
+    ///
+    /// Each glyph's bounds are moved to its position and rounded out to whole units. Glyphs with
+    /// no outline, such as spaces, don't contribute, and a run with no ink has empty bounds at the
+    /// origin.
+    pub fn run_bounds(&self, glyphs: &[(u16, Point2D<f32>)]) -> Result<GlyphBounds, FontError> {
+        let mut run_bounds: Option<GlyphBounds> = None;
+        for &(glyph_id, ref position) in glyphs {
+            let bounds = try!(self.glyph_bounds(glyph_id));
+            let size = bounds.size();
+            if size.width == 0 && size.height == 0 {
+                continue
+            }
+
+            let bounds = GlyphBounds {
+                left: (bounds.left as f32 + position.x).floor() as i32,
+                bottom: (bounds.bottom as f32 + position.y).floor() as i32,
+                right: (bounds.right as f32 + position.x).ceil() as i32,
+                top: (bounds.top as f32 + position.y).ceil() as i32,
+            };
+            run_bounds = Some(match run_bounds {
+                None => bounds,
+                Some(run_bounds) => {
+                    GlyphBounds {
+                        left: cmp::min(run_bounds.left, bounds.left),
+                        bottom: cmp::min(run_bounds.bottom, bounds.bottom),
+                        right: cmp::max(run_bounds.right, bounds.right),
+                        top: cmp::max(run_bounds.top, bounds.top),
+                    }
+                }
+            })
+        }
+        Ok(run_bounds.unwrap_or(GlyphBounds::default()))
+    }
+
+    /// Like `run_bounds()`, but with the glyphs set one after another on the baseline from the
+    /// origin, each advancing the pen by its advance width.
+    pub fn run_bounds_with_advances(&self, glyphs: &[u16]) -> Result<GlyphBounds, FontError> {
+        let mut positioned_glyphs = Vec::with_capacity(glyphs.len());
+        let mut pen = 0.0;
+        for &glyph_id in glyphs {
+            positioned_glyphs.push((glyph_id, Point2D::new(pen, 0.0)));
+            pen += try!(self.advance_width(glyph_id)) as f32
+        }
+        self.run_bounds(&positioned_glyphs)
+    }
+
+    /// Returns the number of glyphs in the font.
+    ///
+    /// Glyph IDs range from 0 up to, but not including, this number.
+    #[inline]
+    pub fn num_glyphs(&self) -> u16 {
+        self.tables.maxp.num_glyphs()
+    }
+
+    // Returns the first source in the priority list that `for_each_point()` can read outlines
+    // from.
+    fn vector_outline_source(&self) -> Option<OutlineSource> {
+        self.outline_source_priority.iter().cloned().find(|&source| {
+            self.has_vector_outline_source(source)
+        })
+    }
+
+    // Returns the `CFF2` or `CFF ` table that `for_each_point()` reads outlines from, if it reads
+    // them from either.
+    fn cff_outline_table(&self) -> Option<CffTable<'a>> {
+        match self.vector_outline_source() {
+            Some(OutlineSource::Cff2) => self.tables.cff2,
+            Some(OutlineSource::Cff) => self.tables.cff,
+            _ => None,
+        }
+    }
+
+    // Returns true if the font has outlines from the given source. Color sources have none.
+    fn has_vector_outline_source(&self, source: OutlineSource) -> bool {
+        match source {
+            OutlineSource::Cff2 => self.tables.cff2.is_some(),
+            OutlineSource::Glyf => self.tables.glyf.is_some(),
+            OutlineSource::Cff => self.tables.cff.is_some(),
+            OutlineSource::Colr | OutlineSource::Sbix => false,
+        }
+    }
+
+    // Returns `NoSuchGlyph` if the glyph ID is out of range, so that callers get a useful error
+    // instead of an unexpected EOF from deep inside the outline tables.
+    #[inline]
+    fn check_glyph_id(&self, glyph_id: u16) -> Result<(), FontError> {
+        if glyph_id < self.tables.maxp.num_glyphs() {
+            Ok(())
+        } else {
+            Err(FontError::NoSuchGlyph)
         }
     }
 
@@ -135,8 +852,8 @@ impl<'a> Font<'a> {
     pub fn shelf_height(&self, point_size: f32) -> u32 {
         // Add 2 to account for the border.
         self.tables.head
-            .max_glyph_bounds
-            .subpixel_bounds(self.tables.head.units_per_em, point_size)
+            .max_glyph_bounds()
+            .subpixel_bounds(self.tables.head.units_per_em(), point_size)
             .round_out()
             .size()
             .height as u32 + 2
@@ -149,7 +866,22 @@ impl<'a> Font<'a> {
     /// pixels, you can use an expression like `units * font_size / font.units_per_em()`.
     #[inline]
     pub fn units_per_em(&self) -> u16 {
-        self.tables.head.units_per_em
+        self.tables.head.units_per_em()
+    }
+
+    /// Returns the transform from the units of glyph outlines to ems.
+    ///
+    /// For TrueType fonts this is a scale of `1 / units_per_em()`. For CFF fonts it's the font's
+    /// `FontMatrix`, which usually, but not always, agrees with `units_per_em()`. Fonts with both
+    /// kinds of outlines use the matrix of the one that `for_each_point()` reads.
+    pub fn font_matrix(&self) -> Matrix2D<f32> {
+        match self.cff_outline_table() {
+            Some(cff) => cff.font_matrix(),
+            None => {
+                let scale = 1.0 / self.tables.head.units_per_em() as f32;
+                Matrix2D::create_scale(scale, scale)
+            }
+        }
     }
 
     /// Returns the horizontal metrics for the glyph with the given ID.
@@ -158,22 +890,185 @@ impl<'a> Font<'a> {
     /// advance the pen after typesetting a glyph.
     #[inline]
     pub fn metrics_for_glyph(&self, glyph_id: u16) -> Result<HorizontalMetrics, FontError> {
+        try!(self.check_glyph_id(glyph_id));
         self.tables.hmtx.metrics_for_glyph(&self.tables.hhea, glyph_id)
     }
 
+    /// Returns the number of font units to advance the pen after typesetting the glyph with the
+    /// given ID.
+    #[inline]
+    pub fn advance_width(&self, glyph_id: u16) -> Result<u16, FontError> {
+        self.metrics_for_glyph(glyph_id).map(|metrics| metrics.advance_width)
+    }
+
+    /// Returns the vertical metrics for the glyph with the given ID, for typesetting vertical
+    /// text.
+    ///
+    /// Returns `None` if the font has no vertical metrics, which is the case for most fonts
+    /// without CJK glyphs.
+    pub fn vertical_metrics_for_glyph(&self, glyph_id: u16)
+                                      -> Result<Option<VerticalMetrics>, FontError> {
+        try!(self.check_glyph_id(glyph_id));
+        match (&self.tables.vhea, self.tables.vmtx) {
+            (&Some(ref vhea), Some(vmtx)) => vmtx.metrics_for_glyph(vhea, glyph_id).map(Some),
+            _ => Ok(None),
+        }
+    }
+
+    /// Returns the number of font units to advance the pen downward after typesetting the glyph
+    /// with the given ID in vertical text, or `None` if the font has no vertical metrics.
+    #[inline]
+    pub fn advance_height(&self, glyph_id: u16) -> Result<Option<u16>, FontError> {
+        self.vertical_metrics_for_glyph(glyph_id).map(|metrics| {
+            metrics.map(|metrics| metrics.advance_height)
+        })
+    }
+
+    /// Returns the Y coordinate of the given glyph's origin in vertical text, in font units, if
+    /// the font has a vertical origin table.
+    ///
+    /// CFF-flavored fonts use this instead of deriving the origin from the glyph's top side
+    /// bearing and bounding box.
+    pub fn vertical_origin_y(&self, glyph_id: u16) -> Result<Option<i16>, FontError> {
+        try!(self.check_glyph_id(glyph_id));
+        Ok(self.tables.vorg.map(|vorg| vorg.vertical_origin_y(glyph_id)))
+    }
+
+    /// Returns whether glyphs should be grid-fitted and antialiased at the given size in pixels
+    /// per em, or `None` if the font doesn't say.
+    #[inline]
+    pub fn gasp_behavior(&self, ppem: u16) -> Option<GaspBehavior> {
+        self.tables.gasp.map(|gasp| gasp.behavior_for_ppem(ppem))
+    }
+
     /// Returns the kerning between the given two glyph IDs in font units.
     ///
     /// Positive values move glyphs farther apart; negative values move glyphs closer together.
     ///
-    /// Zero is returned if no kerning is available in the font.
+    /// The `kern` feature of the glyph positioning table is used if the font has one; otherwise,
+    /// the legacy kerning table is. Zero is returned if no kerning is available in the font.
     #[inline]
     pub fn kerning_for_glyph_pair(&self, left_glyph_id: u16, right_glyph_id: u16) -> i16 {
-        match self.tables.kern {
-            None => 0,
-            Some(kern) => kern.kerning_for_glyph_pair(left_glyph_id, right_glyph_id).unwrap_or(0),
+        match (self.tables.gpos, self.tables.kern) {
+            (Some(gpos), _) if gpos.has_kerning() => {
+                gpos.kerning_for_glyph_pair(left_glyph_id, right_glyph_id).unwrap_or(0)
+            }
+            (_, Some(kern)) => kern.kerning(left_glyph_id, right_glyph_id),
+            (_, None) => 0,
         }
     }
 
+    /// Returns the total advance of the given run of glyphs in font units, including the pair
+    /// adjustments between adjacent glyphs from the given layout features, such as `kern`.
+    ///
+    /// The glyphs are in logical order. Right-to-left runs move the pen leftward, so their advance
+    /// is negative.
+    ///
+    /// Pair adjustments come from the glyph positioning table if it has lookups for any of the
+    /// features. Otherwise, the legacy kerning table is used if `kern` is among the features.
+    pub fn measure(&self, glyph_ids: &[u16], feature_tags: &[u32], direction: TextDirection)
+                   -> Result<f32, FontError> {
+        let mut advance = 0.0;
+        for &glyph_id in glyph_ids {
+            advance += try!(self.advance_width(glyph_id)) as f32
+        }
+        for adjustment in try!(self.pair_adjustments(glyph_ids, feature_tags, direction)) {
+            advance += adjustment as f32
+        }
+
+        match direction {
+            TextDirection::LeftToRight => Ok(advance),
+            TextDirection::RightToLeft => Ok(-advance),
+        }
+    }
+
+    /// Shapes a run of text into glyphs positioned in font units, applying the given OpenType
+    /// layout features, such as `liga` and `kern`.
+    ///
+    /// Characters are mapped to glyphs through the character map, with `.notdef` standing in for
+    /// missing ones. The substitutions of the glyph substitution table are then applied, and the
+    /// advances between pairs of glyphs are adjusted as in `measure()`. Each glyph's cluster is
+    /// the byte offset in the text of the first character it was formed from.
+    ///
+    /// This is a minimal shaper for simple scripts. The text is treated as a single run without
+    /// bidirectional reordering, mirroring, or complex script shaping, and glyph offsets are
+    /// always zero, as mark positioning is not yet supported. Right-to-left runs are returned in
+    /// visual order, leftmost glyph first.
+    pub fn shape(&self, text: &str, feature_tags: &[u32], direction: TextDirection)
+                 -> Result<Vec<PositionedGlyph>, FontError> {
+        let mut glyphs = vec![];
+        for (cluster, character) in text.char_indices() {
+            glyphs.push((try!(self.glyph_for_char(character)).unwrap_or(0), cluster))
+        }
+        if let Some(gsub) = self.tables.gsub {
+            glyphs = try!(gsub.substitute_with_clusters(&glyphs, feature_tags))
+        }
+
+        let mut positioned_glyphs = Vec::with_capacity(glyphs.len());
+        for &(glyph_id, cluster) in &glyphs {
+            positioned_glyphs.push(PositionedGlyph {
+                glyph_id: glyph_id,
+                x_advance: try!(self.advance_width(glyph_id)) as i32,
+                x_offset: 0,
+                y_offset: 0,
+                cluster: cluster,
+            })
+        }
+
+        // An adjustment between a pair of glyphs goes to the advance of the left one, which is
+        // the second of the pair in right-to-left text.
+        let glyph_ids: Vec<u16> = glyphs.iter().map(|&(glyph_id, _)| glyph_id).collect();
+        let adjustments = try!(self.pair_adjustments(&glyph_ids, feature_tags, direction));
+        for (index, adjustment) in adjustments.into_iter().enumerate() {
+            let left_index = match direction {
+                TextDirection::LeftToRight => index,
+                TextDirection::RightToLeft => index + 1,
+            };
+            positioned_glyphs[left_index].x_advance += adjustment as i32
+        }
+
+        if direction == TextDirection::RightToLeft {
+            positioned_glyphs.reverse()
+        }
+        Ok(positioned_glyphs)
+    }
+
+    // Returns the pair adjustments between each glyph in the run and the next, in font units.
+    //
+    // See `measure()` for where they come from.
+    fn pair_adjustments(&self, glyph_ids: &[u16], feature_tags: &[u32], direction: TextDirection)
+                        -> Result<Vec<i16>, FontError> {
+        let gpos = self.tables.gpos.and_then(|gpos| {
+            if gpos.has_lookups_for_features(feature_tags) {
+                Some(gpos)
+            } else {
+                None
+            }
+        });
+        let kern = if feature_tags.contains(&gpos::FEATURE_TAG_KERN) {
+            self.tables.kern
+        } else {
+            None
+        };
+
+        let mut adjustments = Vec::with_capacity(glyph_ids.len());
+        for pair in glyph_ids.windows(2) {
+            let adjustment = match (gpos, kern) {
+                (Some(gpos), _) => try!(gpos.pair_adjustment(pair[0], pair[1], feature_tags)),
+                // The legacy kerning table is in visual order.
+                (None, Some(kern)) => {
+                    match direction {
+                        TextDirection::LeftToRight => kern.kerning(pair[0], pair[1]),
+                        TextDirection::RightToLeft => kern.kerning(pair[1], pair[0]),
+                    }
+                }
+                (None, None) => 0,
+            };
+            adjustments.push(adjustment)
+        }
+        Ok(adjustments)
+    }
+
     /// Returns the distance from the baseline to the top of the text box in font units.
     ///
     /// The following expression computes the baseline-to-baseline height:
@@ -201,6 +1096,64 @@ impl<'a> Font<'a> {
         self.tables.os_2.typo_line_gap
     }
 
+    /// Returns the four-character identifier of the font's vendor, such as `b"URW "`.
+    #[inline]
+    pub fn vendor_id(&self) -> [u8; 4] {
+        self.tables.os_2.vendor_id
+    }
+
+    /// Returns the PANOSE classification of the font, for matching similar-looking fonts.
+    #[inline]
+    pub fn panose(&self) -> Panose {
+        self.tables.os_2.panose
+    }
+
+    /// Returns true if the font's PANOSE classification says that it's monospaced.
+    #[inline]
+    pub fn is_monospaced(&self) -> bool {
+        self.tables.os_2.is_monospaced()
+    }
+
+    /// Returns what the font can do, as determined by which of its tables are present and valid.
+    ///
+    /// This is cheap, since the tables are parsed when the font is loaded.
+    pub fn capabilities(&self) -> FontCapabilities {
+        let outline_format = match (self.vector_outline_source(), self.tables.loca) {
+            (Some(OutlineSource::Glyf), Some(_)) => OutlineFormat::TrueType,
+            (Some(OutlineSource::Cff2), _) | (Some(OutlineSource::Cff), _) => OutlineFormat::Cff,
+            _ => OutlineFormat::None,
+        };
+        let has_kerning = self.tables.gpos.map_or(false, |gpos| gpos.has_kerning()) ||
+            self.tables.kern.is_some();
+        let has_color_bitmaps = self.tables.sbix.is_some() ||
+            (self.tables.cblc.is_some() && self.tables.cbdt.is_some());
+
+        FontCapabilities {
+            outline_format: outline_format,
+            color_layers: self.tables.colr.is_some(),
+            color_bitmaps: has_color_bitmaps,
+            svg_glyphs: self.tables.svg.is_some(),
+            variation_axis_count: self.variation_axes().len(),
+            named_instance_count: self.named_instances().len(),
+            kerning: has_kerning,
+            glyph_substitutions: self.tables.gsub.is_some(),
+            aat_glyph_substitutions: self.tables.morx.is_some(),
+            vertical_metrics: self.tables.vhea.is_some() && self.tables.vmtx.is_some(),
+            math: self.tables.math.is_some(),
+            hinting_programs: self.tables.fpgm.is_some() || self.tables.prep.is_some(),
+            signed: self.has_signature(),
+        }
+    }
+
+    /// Returns true if the font has a digital signature table containing a signature.
+    ///
+    /// Signatures aren't verified. Fonts with a "null" signature table, which has no signatures,
+    /// don't count as signed.
+    #[inline]
+    pub fn has_signature(&self) -> bool {
+        self.tables.dsig.map_or(false, |dsig| dsig.has_signature_block())
+    }
+
     /// Returns the Control Value Table of the font.
     #[inline]
     pub fn control_value_table(&self) -> &[u8] {
@@ -244,16 +1197,722 @@ pub struct Point {
     pub kind: PointKind,
 }
 
+impl Point {
+    /// Returns this point with its position mapped through the given affine transform.
+    #[inline]
+    pub fn transform(&self, transform: &Matrix2D<f32>) -> TransformedPoint {
+        let position = Point2D::new(self.position.x as f32, self.position.y as f32);
+        TransformedPoint {
+            position: transform.transform_point(&position),
+            index_in_contour: self.index_in_contour,
+            kind: self.kind,
+        }
+    }
+}
+
+/// A point on a glyph outline that has been mapped through an affine transform.
+///
+/// Positions are kept in floating point so that scaling down to pixel sizes loses no precision.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct TransformedPoint {
+    /// Where the point is located after transformation.
+    pub position: Point2D<f32>,
+
+    /// The index of the point in this contour.
+    pub index_in_contour: u16,
+
+    /// The kind of point this is.
+    pub kind: PointKind,
+}
+
 /// The type of point.
+///
+/// TrueType and CFF outlines are emitted through the same kinds. Quadratic curves, as in
+/// TrueType glyphs, have a single `QuadControl` point between on-curve points; cubic curves, as
+/// in CFF and Type 1 glyphs, have a `FirstCubicControl` point followed by a `SecondCubicControl`
+/// point. So an on-curve point ends a line if the point before it was also on the curve, and
+/// otherwise ends a curve through the control points since the last on-curve point.
 #[derive(Clone, Copy, PartialEq, Debug)]
 pub enum PointKind {
     /// The point is on the curve.
     OnCurve,
-    /// The point is a quadratic control point.
+    /// The point is the only control point of a quadratic curve.
     QuadControl,
-    /// The point is the first cubic control point.
+    /// The point is the first control point of a cubic curve.
     FirstCubicControl,
-    /// The point is the second cubic control point.
+    /// The point is the second control point of a cubic curve.
     SecondCubicControl,
 }
 
+/// A closed contour of a glyph outline.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Contour<P> {
+    /// The vertices of the contour, in order. The contour is closed by an implicit line from the
+    /// last vertex back to the first.
+    pub points: Vec<P>,
+}
+
+impl Contour<Point> {
+    /// Returns the signed area enclosed by the polygon through the contour's points, in square
+    /// font units. It's positive if the contour goes counterclockwise.
+    ///
+    /// Control points are treated as vertices, which is close enough to tell the direction.
+    pub fn signed_area(&self) -> f32 {
+        signed_area(&self.points) as f32 / 2.0
+    }
+
+    /// Returns true if the contour goes clockwise, as TrueType outer contours conventionally do.
+    #[inline]
+    pub fn is_clockwise(&self) -> bool {
+        signed_area(&self.points) < 0
+    }
+
+    /// Reverses the direction of the contour, keeping its first point.
+    pub fn reverse(&mut self) {
+        let len = self.points.len();
+        if len < 3 {
+            return
+        }
+
+        // A contour closed explicitly, as in CFF, also keeps its last point.
+        let (first, last) = (self.points[0], self.points[len - 1]);
+        if first.kind == PointKind::OnCurve && last.kind == PointKind::OnCurve &&
+                first.position == last.position {
+            reverse(&mut self.points)
+        } else {
+            reverse(&mut self.points[1..])
+        }
+        for (index, point) in self.points.iter_mut().enumerate() {
+            point.index_in_contour = index as u16
+        }
+    }
+}
+
+/// A glyph outline that owns its points, as returned by `Font::outline()`.
+///
+/// Unlike the callback-based methods, this doesn't borrow the font, so outlines can be decoded on
+/// other threads and sent back.
+///
+/// With the `lyon` or `kurbo` features, `&Outline` converts into a `lyon::path::Path` or a
+/// `kurbo::BezPath` with `From`.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Outline {
+    /// The contours of the outline, with their points in the order that `for_each_point()`
+    /// produces them.
+    pub contours: Vec<Contour<Point>>,
+}
+
+impl Outline {
+    /// Collects the given points, as produced by `for_each_point()`, into contours.
+    pub fn from_points<I>(points: I) -> Outline where I: IntoIterator<Item = Point> {
+        let mut contours: Vec<Contour<Point>> = vec![];
+        for point in points {
+            if point.index_in_contour == 0 || contours.is_empty() {
+                contours.push(Contour {
+                    points: vec![],
+                })
+            }
+            contours.last_mut().unwrap().points.push(point)
+        }
+        Outline {
+            contours: contours,
+        }
+    }
+
+    /// Calls the given callback for each point in the outline, just as `for_each_point()` would.
+    pub fn for_each_point<F>(&self, mut callback: F) where F: FnMut(&Point) {
+        for contour in &self.contours {
+            for point in &contour.points {
+                callback(point)
+            }
+        }
+    }
+
+    /// Returns the total number of points in all contours.
+    #[inline]
+    pub fn num_points(&self) -> usize {
+        self.contours.iter().map(|contour| contour.points.len()).sum()
+    }
+
+    /// Returns this outline in a canonical form, so that outlines describing the same shape
+    /// compare equal even if their contours start at different points or come in a different
+    /// order.
+    ///
+    /// A final on-curve point that repeats the first point of its contour is dropped, since CFF
+    /// outlines close their contours explicitly and TrueType outlines may not. Each contour then
+    /// starts at its lowest on-curve point, ordering by x and then by y, and the contours are
+    /// sorted by their first points. The direction of each contour is kept.
+    pub fn normalized(&self) -> Outline {
+        self.normalize(false)
+    }
+
+    /// Like `normalized()`, but also turns every contour counterclockwise, so that outlines that
+    /// differ only in winding direction compare equal.
+    ///
+    /// This loses the distinction between filled contours and holes, so it's only suitable for
+    /// comparing outlines.
+    pub fn normalized_ignoring_direction(&self) -> Outline {
+        self.normalize(true)
+    }
+
+    /// Returns true if both outlines have the same contours and points, with positions differing
+    /// by at most `tolerance` font units along each axis.
+    ///
+    /// Points are compared in order, so normalize both outlines first if their contours may start
+    /// at different points.
+    pub fn approx_eq(&self, other: &Outline, tolerance: i16) -> bool {
+        let near = |a: i16, b: i16| (a as i32 - b as i32).abs() <= tolerance as i32;
+        self.contours.len() == other.contours.len() &&
+            self.contours.iter().zip(other.contours.iter()).all(|(contour, other_contour)| {
+                contour.points.len() == other_contour.points.len() &&
+                    contour.points.iter().zip(other_contour.points.iter()).all(|(a, b)| {
+                        a.kind == b.kind &&
+                            near(a.position.x, b.position.x) &&
+                            near(a.position.y, b.position.y)
+                    })
+            })
+    }
+
+    /// Makes outer contours go counterclockwise and holes go clockwise, alternating with each
+    /// level of nesting, so that the outline fills the same way under both the nonzero and
+    /// even-odd fill rules.
+    ///
+    /// CFF outlines conventionally wind outer contours counterclockwise and TrueType outlines
+    /// clockwise, and some fonts mix the two. Contours that enclose no area are left alone.
+    pub fn fix_winding(&mut self) {
+        let depths: Vec<usize> = self.contours.iter().enumerate().map(|(index, contour)| {
+            let point = match contour.points.first() {
+                Some(point) => point.position,
+                None => return 0,
+            };
+            self.contours.iter().enumerate().filter(|&(other_index, other)| {
+                other_index != index && signed_area(&other.points) != 0 &&
+                    contains(&other.points, &point)
+            }).count()
+        }).collect();
+
+        for (contour, depth) in self.contours.iter_mut().zip(depths) {
+            let area = signed_area(&contour.points);
+            if area != 0 && (area < 0) == (depth % 2 == 0) {
+                contour.reverse()
+            }
+        }
+    }
+
+    /// Maps every point of the outline through the given affine transform, in place.
+    ///
+    /// On-curve and control points are transformed alike, and positions are rounded to whole
+    /// units. Contours keep their points and their direction: if the transform mirrors the
+    /// outline, each contour is reversed so that clockwise contours stay clockwise.
+    pub fn transform(&mut self, transform: &Matrix2D<f32>) {
+        let mirrors = transform.m11 * transform.m22 - transform.m12 * transform.m21 < 0.0;
+        for contour in &mut self.contours {
+            for point in &mut contour.points {
+                let position = point.transform(transform).position;
+                point.position = Point2D::new(round_to_i16(position.x), round_to_i16(position.y))
+            }
+            if mirrors {
+                contour.reverse()
+            }
+        }
+    }
+
+    /// Scales the outline from font units to pixels at the given size, in place.
+    ///
+    /// Positions are rounded to whole pixels, so small sizes lose detail; scale by a multiple of
+    /// `ppem` to keep subpixel precision.
+    #[inline]
+    pub fn scale_to_ppem(&mut self, units_per_em: u16, ppem: f32) {
+        let scale = ppem / units_per_em as f32;
+        self.transform(&Matrix2D::create_scale(scale, scale))
+    }
+
+    fn normalize(&self, ignore_direction: bool) -> Outline {
+        let mut contours: Vec<_> = self.contours.iter().map(|contour| {
+            let mut points = contour.points.clone();
+            if points.len() > 1 {
+                let (first, last) = (points[0], points[points.len() - 1]);
+                if first.kind == PointKind::OnCurve && last.kind == PointKind::OnCurve &&
+                        first.position == last.position {
+                    points.pop();
+                }
+            }
+
+            if ignore_direction && signed_area(&points) < 0 {
+                reverse(&mut points)
+            }
+
+            // Start at the lowest on-curve point, or the lowest point if there are none.
+            let key = |point: &Point| (point.kind != PointKind::OnCurve, point.position.x,
+                                       point.position.y);
+            let start = (0..points.len()).min_by_key(|&index| key(&points[index])).unwrap_or(0);
+            let mut points: Vec<_> = points[start..].iter().chain(points[..start].iter())
+                                                          .cloned()
+                                                          .collect();
+            for (index, point) in points.iter_mut().enumerate() {
+                point.index_in_contour = index as u16
+            }
+
+            Contour {
+                points: points,
+            }
+        }).collect();
+
+        contours.sort_by_key(|contour| {
+            contour.points.first().map(|point| (point.position.x, point.position.y))
+        });
+        Outline {
+            contours: contours,
+        }
+    }
+}
+
+// Returns twice the signed area of the polygon through the given points, which is positive if
+// they go counterclockwise.
+fn signed_area(points: &[Point]) -> i64 {
+    let mut area = 0;
+    for (index, point) in points.iter().enumerate() {
+        let next = &points[(index + 1) % points.len()];
+        area += point.position.x as i64 * next.position.y as i64 -
+            next.position.x as i64 * point.position.y as i64
+    }
+    area
+}
+
+// Reverses the order of the given points, swapping the roles of cubic control points to match.
+//
+// Indices within the contour are left for the caller to renumber.
+fn reverse(points: &mut [Point]) {
+    points.reverse();
+    for point in points {
+        point.kind = match point.kind {
+            PointKind::FirstCubicControl => PointKind::SecondCubicControl,
+            PointKind::SecondCubicControl => PointKind::FirstCubicControl,
+            kind => kind,
+        }
+    }
+}
+
+// Rounds the coordinate to the nearest whole unit, clamping it to the range of an `i16`.
+fn round_to_i16(value: f32) -> i16 {
+    f32::min(f32::max(value.round(), i16::MIN as f32), i16::MAX as f32) as i16
+}
+
+// Returns true if the given position is inside the polygon through the given points, by the
+// even-odd rule.
+fn contains(points: &[Point], position: &Point2D<i16>) -> bool {
+    let (x, y) = (position.x as i64, position.y as i64);
+    let mut inside = false;
+    for (index, point) in points.iter().enumerate() {
+        let next = &points[(index + 1) % points.len()];
+        let (x0, y0) = (point.position.x as i64, point.position.y as i64);
+        let (x1, y1) = (next.position.x as i64, next.position.y as i64);
+        if (y0 > y) != (y1 > y) {
+            // Whether the edge crosses the horizontal ray to the right of the position.
+            let cross = (x1 - x0) * (y - y0) - (x - x0) * (y1 - y0);
+            if (cross > 0) == (y1 > y0) {
+                inside = !inside
+            }
+        }
+    }
+    inside
+}
+
+/// A segment of a glyph outline, as produced by `CffTable::path_segments()`.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum PathSegment {
+    /// Begins a new contour at the given point.
+    MoveTo(Point2D<i16>),
+    /// Draws a line to the given point.
+    LineTo(Point2D<i16>),
+    /// Draws a cubic Bézier curve with the given two control points to the third point.
+    CurveTo(Point2D<i16>, Point2D<i16>, Point2D<i16>),
+    /// Closes the current contour with a line back to its first point, if necessary.
+    ClosePath,
+}
+
+/// An iterator over the segments of a glyph outline.
+#[derive(Clone, Debug)]
+pub struct PathSegments {
+    segments: vec::IntoIter<PathSegment>,
+}
+
+impl PathSegments {
+    #[doc(hidden)]
+    pub fn new(segments: Vec<PathSegment>) -> PathSegments {
+        PathSegments {
+            segments: segments.into_iter(),
+        }
+    }
+}
+
+impl Iterator for PathSegments {
+    type Item = PathSegment;
+
+    #[inline]
+    fn next(&mut self) -> Option<PathSegment> {
+        self.segments.next()
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.segments.size_hint()
+    }
+}
+
+/// A glyph rasterized on the CPU, as returned by `Font::rasterize()`.
+#[derive(Clone, PartialEq, Debug)]
+pub struct GrayBitmap {
+    /// The width of the bitmap in pixels.
+    pub width: u32,
+    /// The height of the bitmap in pixels.
+    pub height: u32,
+    /// The position of the left edge of the bitmap, in pixels to the right of the glyph origin.
+    pub left: i32,
+    /// The position of the top edge of the bitmap, in pixels above the glyph origin.
+    pub top: i32,
+    /// The coverage of each pixel, from 0 for none to 255 for full, in rows from the top.
+    pub coverage: Vec<u8>,
+}
+
+impl GrayBitmap {
+    /// Returns the coverage of the pixel in the given column and row, counting from the top left.
+    #[inline]
+    pub fn coverage_at(&self, x: u32, y: u32) -> u8 {
+        self.coverage[(y * self.width + x) as usize]
+    }
+}
+
+/// Options that control how `to_svg_path()` writes a glyph outline as SVG path data.
+#[derive(Clone, Copy, Debug)]
+pub struct SvgPathOptions {
+    /// The factor that coordinates are multiplied by, such as the pixel size divided by the units
+    /// per em. The default of 1.0 leaves them in font units.
+    pub scale: f32,
+
+    /// The most digits to write after the decimal point of each coordinate. Trailing zeros are
+    /// always dropped.
+    pub precision: usize,
+
+    /// Whether to negate y coordinates, since font units go up and SVG user units go down.
+    pub flip_y: bool,
+}
+
+impl Default for SvgPathOptions {
+    #[inline]
+    fn default() -> SvgPathOptions {
+        SvgPathOptions {
+            scale: 1.0,
+            precision: 2,
+            flip_y: false,
+        }
+    }
+}
+
+/// What a font can do. See `Font::capabilities()`.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct FontCapabilities {
+    /// The kind of outlines that the glyphs have.
+    pub outline_format: OutlineFormat,
+    /// Whether glyphs have color layers. See `Font::color_layers()`.
+    pub color_layers: bool,
+    /// Whether the font has embedded color bitmaps, such as emoji. See `Font::glyph_bitmap()`.
+    pub color_bitmaps: bool,
+    /// Whether glyphs have SVG documents. See `Font::svg_document()`.
+    pub svg_glyphs: bool,
+    /// The number of design axes, which is zero unless this is a variable font.
+    pub variation_axis_count: usize,
+    /// The number of named instances of a variable font.
+    pub named_instance_count: usize,
+    /// Whether the font has kerning, in either the glyph positioning or legacy kerning table.
+    pub kerning: bool,
+    /// Whether the font has OpenType glyph substitutions. See `Font::substitute_glyphs()`.
+    pub glyph_substitutions: bool,
+    /// Whether the font has Apple Advanced Typography glyph substitutions. See
+    /// `Font::substitute_glyphs_aat()`.
+    pub aat_glyph_substitutions: bool,
+    /// Whether the font has metrics for vertical text.
+    pub vertical_metrics: bool,
+    /// Whether this is a math font. See `Font::math_constants()`.
+    pub math: bool,
+    /// Whether the font has TrueType hinting programs.
+    pub hinting_programs: bool,
+    /// Whether the font has a digital signature. See `Font::has_signature()`.
+    pub signed: bool,
+}
+
+/// The kind of outlines that a font's glyphs have.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum OutlineFormat {
+    /// Quadratic outlines in the `glyf` table.
+    TrueType,
+    /// Cubic outlines in the `CFF ` or `CFF2` table.
+    Cff,
+    /// No usable outlines, as in fonts with only bitmaps.
+    None,
+}
+
+/// A table that glyphs can be drawn from. See `Font::set_outline_source_priority()`.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum OutlineSource {
+    /// Layers of other glyphs in palette colors, from the `COLR` table.
+    Colr,
+    /// Color bitmaps from the `sbix` table.
+    Sbix,
+    /// Cubic outlines from the `CFF2` table, which variable fonts use.
+    Cff2,
+    /// Quadratic outlines from the `glyf` table.
+    Glyf,
+    /// Cubic outlines from the `CFF ` table.
+    Cff,
+}
+
+/// The color of a layer of a color glyph.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ColorIndex {
+    /// The color is the given entry in the current color palette. See `Font::palette_color()`.
+    Palette(u16),
+    /// The color is the text color.
+    Foreground,
+}
+
+/// A size at which a font has embedded bitmaps.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct BitmapStrike {
+    /// The size of the bitmaps in pixels per em.
+    pub ppem: u16,
+    /// The resolution the bitmaps were designed for, in pixels per inch.
+    pub resolution: u16,
+}
+
+/// The encoding of an embedded bitmap.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum BitmapFormat {
+    /// A PNG image.
+    Png,
+    /// A JPEG image.
+    Jpeg,
+    /// A TIFF image.
+    Tiff,
+}
+
+/// The metrics of an embedded bitmap for one direction of text, in pixels.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct SmallGlyphMetrics {
+    /// The number of rows in the bitmap.
+    pub height: u8,
+    /// The number of columns in the bitmap.
+    pub width: u8,
+    /// The distance from the pen to the left edge of the bitmap.
+    pub bearing_x: i8,
+    /// The distance from the pen to the top edge of the bitmap.
+    pub bearing_y: i8,
+    /// How far to move the pen after drawing the bitmap.
+    pub advance: u8,
+}
+
+/// The metrics of an embedded bitmap for both horizontal and vertical text, in pixels.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct BigGlyphMetrics {
+    /// The number of rows in the bitmap.
+    pub height: u8,
+    /// The number of columns in the bitmap.
+    pub width: u8,
+    /// The distance from the pen to the left edge of the bitmap in horizontal text.
+    pub hori_bearing_x: i8,
+    /// The distance from the pen to the top edge of the bitmap in horizontal text.
+    pub hori_bearing_y: i8,
+    /// How far to move the pen right after drawing the bitmap in horizontal text.
+    pub hori_advance: u8,
+    /// The distance from the pen to the left edge of the bitmap in vertical text.
+    pub vert_bearing_x: i8,
+    /// The distance from the pen to the top edge of the bitmap in vertical text.
+    pub vert_bearing_y: i8,
+    /// How far to move the pen down after drawing the bitmap in vertical text.
+    pub vert_advance: u8,
+}
+
+/// The metrics of an embedded bitmap, as the font records them.
+///
+/// Small metrics apply to the direction of text that the bitmap's strike is for, which is
+/// usually horizontal.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum BitmapMetrics {
+    /// Metrics for one direction of text.
+    Small(SmallGlyphMetrics),
+    /// Metrics for both directions of text.
+    Big(BigGlyphMetrics),
+}
+
+impl BitmapMetrics {
+    /// The number of columns in the bitmap.
+    #[inline]
+    pub fn width(&self) -> u8 {
+        match *self {
+            BitmapMetrics::Small(ref metrics) => metrics.width,
+            BitmapMetrics::Big(ref metrics) => metrics.width,
+        }
+    }
+
+    /// The number of rows in the bitmap.
+    #[inline]
+    pub fn height(&self) -> u8 {
+        match *self {
+            BitmapMetrics::Small(ref metrics) => metrics.height,
+            BitmapMetrics::Big(ref metrics) => metrics.height,
+        }
+    }
+}
+
+/// A monochrome or grayscale glyph bitmap embedded in the font. See `Font::embedded_bitmap()`.
+///
+/// Pixels run from the top left, row by row, with the most significant bits of each byte first.
+/// A set bit in a monochrome bitmap is black; grayscale values run from white at zero to black.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct EmbeddedBitmap<'a> {
+    /// The size and placement of the bitmap.
+    pub metrics: BitmapMetrics,
+    /// The number of bits per pixel: 1 for monochrome, or 2, 4, or 8 for grayscale.
+    pub bit_depth: u8,
+    /// Whether each row follows straight on from the last, rather than starting a new byte.
+    pub bit_aligned: bool,
+    /// The packed pixels.
+    pub data: &'a [u8],
+}
+
+/// The direction in which a run of horizontal text is set. See `Font::measure()` and
+/// `Font::shape()`.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum TextDirection {
+    /// From left to right, as for Latin and Cyrillic.
+    LeftToRight,
+    /// From right to left, as for Arabic and Hebrew.
+    RightToLeft,
+}
+
+/// A glyph positioned by `Font::shape()`, in font units.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct PositionedGlyph {
+    /// The glyph to draw.
+    pub glyph_id: u16,
+    /// How far to move the pen to the right after drawing the glyph.
+    pub x_advance: i32,
+    /// How far to the right of the pen to draw the glyph.
+    pub x_offset: i32,
+    /// How far above the pen to draw the glyph.
+    pub y_offset: i32,
+    /// The byte offset in the text of the first character that the glyph was formed from.
+    pub cluster: usize,
+}
+
+/// The direction in which a math font stretches a glyph. See `Font::math_glyph_variants()`.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum MathDirection {
+    /// Taller, as for parentheses and integral signs.
+    Vertical,
+    /// Wider, as for arrows and overbraces.
+    Horizontal,
+}
+
+/// A design axis of a variable font.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct VariationAxis {
+    /// The axis tag, such as `wght` for weight, as four ASCII bytes in big-endian order.
+    pub tag: u32,
+    /// The minimum value along this axis, in design units.
+    pub min_value: f32,
+    /// The value along this axis used when no variation is requested.
+    pub default_value: f32,
+    /// The maximum value along this axis, in design units.
+    pub max_value: f32,
+    /// Flags for this axis. Bit 0 means the axis should be hidden from users.
+    pub flags: u16,
+    /// The ID of this axis's name in the naming table.
+    pub axis_name_id: u16,
+}
+
+impl VariationAxis {
+    /// Converts a value along this axis in design units to a normalized coordinate from -1.0 to
+    /// 1.0, where 0.0 is the default value.
+    ///
+    /// Values outside the axis's range are clamped to it. This doesn't apply any `avar` axis
+    /// variations.
+    pub fn normalize(&self, value: f32) -> f32 {
+        let value = value.max(self.min_value).min(self.max_value);
+        if value < self.default_value {
+            -(self.default_value - value) / (self.default_value - self.min_value)
+        } else if value > self.default_value {
+            (value - self.default_value) / (self.max_value - self.default_value)
+        } else {
+            0.0
+        }
+    }
+}
+
+/// A predefined position in the design space of a variable font.
+#[derive(Clone, PartialEq, Debug)]
+pub struct NamedInstance {
+    /// The position along each axis, in the order of `Font::variation_axes()`.
+    pub coordinates: Vec<f32>,
+    /// The ID of this instance's subfamily name (for example, "Bold") in the naming table.
+    pub subfamily_name_id: u16,
+    /// The ID of this instance's PostScript name in the naming table, if it has one.
+    pub postscript_name_id: Option<u16>,
+}
+
+/// An axis that a font family varies along, as described by the style attributes table.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct StyleAxis {
+    /// The axis tag, such as `wght` for weight, as four ASCII bytes in big-endian order.
+    pub tag: u32,
+    /// The ID of this axis's name in the naming table.
+    pub axis_name_id: u16,
+    /// Where this axis's value names go when building a style name. Lower values come first.
+    pub ordering: u16,
+}
+
+/// A named value along one or more style axes, such as "Bold" or "Condensed".
+#[derive(Clone, PartialEq, Debug)]
+pub struct AxisValue {
+    /// The value or values that this name applies to.
+    pub kind: AxisValueKind,
+    /// Flags for this value. Bit 0 means the value describes an older sibling font, and bit 1
+    /// means the name can be left out when building a style name, as "Regular" usually is.
+    pub flags: u16,
+    /// The ID of this value's name in the naming table.
+    pub value_name_id: u16,
+}
+
+impl AxisValue {
+    /// Whether this value's name can be left out of style names.
+    #[inline]
+    pub fn is_elidable(&self) -> bool {
+        (self.flags & 2) != 0
+    }
+}
+
+/// The values that an `AxisValue` names. Axis indices refer to `Font::style_axes()`.
+#[derive(Clone, PartialEq, Debug)]
+pub enum AxisValueKind {
+    /// A single value along an axis.
+    Single {
+        axis_index: u16,
+        value: f32,
+    },
+    /// A range of values along an axis, all called by the same name.
+    Range {
+        axis_index: u16,
+        nominal_value: f32,
+        min_value: f32,
+        max_value: f32,
+    },
+    /// A single value along an axis, with a related value such as the bold counterpart of a
+    /// regular weight.
+    Linked {
+        axis_index: u16,
+        value: f32,
+        linked_value: f32,
+    },
+    /// A combination of values along several axes, as pairs of axis index and value.
+    Combination(Vec<(u16, f32)>),
+}
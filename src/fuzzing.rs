@@ -0,0 +1,52 @@
+// Copyright 2017 The Servo Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Entry points for fuzzing the table parsers, which aren't otherwise public.
+//!
+//! The targets in `fuzz/` call these with arbitrary bytes. Malformed data must only ever produce
+//! a `FontError`; any panic is a bug.
+
+use error::FontError;
+use font::FontTable;
+use std::cmp;
+use tables::cff::CffTable;
+use tables::os_2::Os2Table;
+
+// The number of glyphs to evaluate in each CFF table, so that each input stays quick to run.
+const MAX_CFF_GLYPHS: u16 = 64;
+
+/// Parses the data as a `CFF ` or `CFF2` table, then evaluates the CharStrings of its first glyphs
+/// and the glyph just past its last.
+///
+/// Returns the error from parsing the table, if any. Errors from individual glyphs are expected
+/// and ignored.
+pub fn parse_cff(data: &[u8]) -> Result<(), FontError> {
+    let table = try!(CffTable::new(FontTable {
+        bytes: data,
+    }));
+    let glyph_count = try!(table.num_glyphs());
+
+    let mut glyph_ids: Vec<u16> = (0..cmp::min(glyph_count, MAX_CFF_GLYPHS)).collect();
+    glyph_ids.push(glyph_count);
+    for glyph_id in glyph_ids {
+        let _ = table.for_each_point(glyph_id, |_| {});
+        let _ = table.for_each_point_leniently(glyph_id, |_| {});
+        let _ = table.advance_width(glyph_id);
+    }
+    Ok(())
+}
+
+/// Parses the data as an `OS/2` table.
+pub fn parse_os_2(data: &[u8]) -> Result<(), FontError> {
+    try!(Os2Table::new(FontTable {
+        bytes: data,
+    }));
+    Ok(())
+}
@@ -0,0 +1,161 @@
+// Copyright 2017 The Servo Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! A cache of decoded glyph outlines, for text that uses the same glyphs over and over.
+
+use error::FontError;
+use font::{Font, Point};
+use outline::GlyphBounds;
+use std::cmp;
+use std::collections::HashMap;
+
+/// Remembers the points and bounds of recently used glyphs of a font, so that they're decoded
+/// only once.
+///
+/// The cache holds up to `capacity` glyphs, discarding the least recently used glyph to make room
+/// for a new one. Lookups take `&mut self`, so to share a cache between threads, wrap it in a
+/// `Mutex`; to avoid contention, give each thread its own cache instead.
+pub struct GlyphCache<'f, 'a: 'f> {
+    font: &'f Font<'a>,
+    capacity: usize,
+    glyphs: HashMap<u16, CachedGlyph>,
+    // Incremented on every lookup, to order glyphs by when they were last used.
+    clock: u64,
+    misses: u64,
+}
+
+struct CachedGlyph {
+    points: Vec<Point>,
+    // Computed the first time it's asked for, since many callers never need it.
+    bounds: Option<GlyphBounds>,
+    last_used: u64,
+}
+
+impl<'f, 'a: 'f> GlyphCache<'f, 'a> {
+    /// Creates an empty cache for the given font that holds up to `capacity` glyphs.
+    ///
+    /// A capacity of zero is treated as one.
+    pub fn new(font: &'f Font<'a>, capacity: usize) -> GlyphCache<'f, 'a> {
+        GlyphCache {
+            font: font,
+            capacity: cmp::max(capacity, 1),
+            glyphs: HashMap::new(),
+            clock: 0,
+            misses: 0,
+        }
+    }
+
+    /// Returns the font that this cache decodes glyphs from.
+    #[inline]
+    pub fn font(&self) -> &'f Font<'a> {
+        self.font
+    }
+
+    /// Returns the most glyphs that this cache will hold at once.
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Returns the number of glyphs in the cache.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.glyphs.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.glyphs.is_empty()
+    }
+
+    /// Returns the number of times a lookup couldn't be answered from the cache and had to go to
+    /// the font.
+    #[inline]
+    pub fn misses(&self) -> u64 {
+        self.misses
+    }
+
+    /// Discards every glyph in the cache.
+    pub fn clear(&mut self) {
+        self.glyphs.clear()
+    }
+
+    /// Returns the points of the given glyph's outline, in the order that
+    /// `Font::for_each_point()` produces them.
+    pub fn points(&mut self, glyph_id: u16) -> Result<&[Point], FontError> {
+        let glyph = try!(self.lookup(glyph_id));
+        Ok(&glyph.points)
+    }
+
+    /// Calls the given callback for each point in the given glyph's outline, like
+    /// `Font::for_each_point()`.
+    pub fn for_each_point<F>(&mut self, glyph_id: u16, mut callback: F) -> Result<(), FontError>
+                             where F: FnMut(&Point) {
+        for point in try!(self.points(glyph_id)) {
+            callback(point)
+        }
+        Ok(())
+    }
+
+    /// Returns the boundaries of the given glyph in font units, like `Font::glyph_bounds()`.
+    pub fn glyph_bounds(&mut self, glyph_id: u16) -> Result<GlyphBounds, FontError> {
+        let font = self.font;
+        let (bounds, missed) = {
+            let glyph = try!(self.lookup(glyph_id));
+            match glyph.bounds {
+                Some(bounds) => (bounds, false),
+                None => {
+                    let bounds = try!(font.glyph_bounds(glyph_id));
+                    glyph.bounds = Some(bounds);
+                    (bounds, true)
+                }
+            }
+        };
+        if missed {
+            self.misses += 1
+        }
+        Ok(bounds)
+    }
+
+    // Returns the cached glyph, decoding it first if necessary.
+    fn lookup(&mut self, glyph_id: u16) -> Result<&mut CachedGlyph, FontError> {
+        self.clock += 1;
+        if !self.glyphs.contains_key(&glyph_id) {
+            let mut points = vec![];
+            try!(self.font.for_each_point(glyph_id, |point| points.push(*point)));
+            self.misses += 1;
+
+            if self.glyphs.len() >= self.capacity {
+                self.evict_least_recently_used()
+            }
+            self.glyphs.insert(glyph_id, CachedGlyph {
+                points: points,
+                bounds: None,
+                last_used: 0,
+            });
+        }
+
+        let glyph = self.glyphs.get_mut(&glyph_id).unwrap();
+        glyph.last_used = self.clock;
+        Ok(glyph)
+    }
+
+    // Discards the glyph that was used longest ago. This is linear in the size of the cache, which
+    // is much cheaper than decoding a glyph for any reasonable capacity.
+    fn evict_least_recently_used(&mut self) {
+        let oldest = self.glyphs
+                         .iter()
+                         .min_by_key(|&(_, glyph)| glyph.last_used)
+                         .map(|(&glyph_id, _)| glyph_id);
+        if let Some(glyph_id) = oldest {
+            self.glyphs.remove(&glyph_id);
+        }
+    }
+}
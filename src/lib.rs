@@ -75,11 +75,16 @@
 
 #[macro_use]
 extern crate bitflags;
+extern crate brotli_decompressor;
 extern crate byteorder;
 extern crate compute_shader;
 extern crate euclid;
 extern crate flate2;
 extern crate gl;
+#[cfg(feature = "kurbo")]
+extern crate kurbo;
+#[cfg(feature = "lyon")]
+extern crate lyon;
 #[cfg(test)]
 extern crate memmap;
 extern crate num_traits;
@@ -87,6 +92,8 @@ extern crate num_traits;
 #[macro_use]
 extern crate quickcheck;
 #[cfg(test)]
+extern crate rayon;
+#[cfg(test)]
 extern crate test;
 
 pub mod atlas;
@@ -94,14 +101,22 @@ pub mod charmap;
 pub mod coverage;
 pub mod error;
 pub mod font;
+#[doc(hidden)]
+pub mod fuzzing;
+pub mod glyph_cache;
 pub mod hinting;
 pub mod outline;
 pub mod rasterizer;
 pub mod shaper;
+pub mod type1;
 pub mod typesetter;
 
 mod containers;
+#[cfg(any(feature = "kurbo", feature = "lyon"))]
+mod convert;
 mod rect_packer;
+mod scanline;
+mod subset;
 mod tables;
 mod util;
 
@@ -0,0 +1,108 @@
+// Copyright 2017 The Servo Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! A CPU scanline rasterizer that fills flattened outlines with exact area coverage.
+//!
+//! Each line adds the signed area it covers to the cells it crosses, as well as the change in
+//! coverage to the cell after. A running sum over each row then gives the coverage of every pixel.
+
+use euclid::Point2D;
+use std::f32;
+
+pub struct Accumulator {
+    width: usize,
+    height: usize,
+    cells: Vec<f32>,
+}
+
+impl Accumulator {
+    pub fn new(width: u32, height: u32) -> Accumulator {
+        let (width, height) = (width as usize, height as usize);
+
+        // Lines that touch the right edge spill into the next cell, which starts the next row.
+        Accumulator {
+            width: width,
+            height: height,
+            cells: vec![0.0; width * height + 2],
+        }
+    }
+
+    /// Adds a line between the given points, in pixels from the top left of the bitmap.
+    pub fn line(&mut self, from: &Point2D<f32>, to: &Point2D<f32>) {
+        if from.y == to.y {
+            return
+        }
+        let (direction, from, to) = if from.y < to.y { (1.0, from, to) } else { (-1.0, to, from) };
+        let dxdy = (to.x - from.x) / (to.y - from.y);
+
+        let mut x = from.x;
+        let y_start = f32::max(from.y, 0.0);
+        if from.y < 0.0 {
+            x -= from.y * dxdy
+        }
+        let y_end = f32::min(to.y.ceil(), self.height as f32) as usize;
+        for y in (y_start as usize)..y_end {
+            let row = y * self.width;
+            let dy = f32::min((y + 1) as f32, to.y) - f32::max(y as f32, from.y);
+            let x_next = x + dxdy * dy;
+            let delta = dy * direction;
+            let (x0, x1) = if x < x_next { (x, x_next) } else { (x_next, x) };
+            let (x0_floor, x1_ceil) = (x0.floor(), x1.ceil());
+            let (x0_index, x1_index) = (x0_floor.max(0.0) as usize, x1_ceil.max(0.0) as usize);
+
+            if x1_index <= x0_index + 1 {
+                // The line stays within one pixel of this row.
+                let x_mid = 0.5 * (x + x_next) - x0_floor;
+                self.add(row + x0_index, delta - delta * x_mid);
+                self.add(row + x0_index + 1, delta * x_mid);
+            } else {
+                // Split the area among the pixels the line crosses, which is triangular at the
+                // ends and uniform in between.
+                let slope = (x1 - x0).recip();
+                let x0_fraction = x0 - x0_floor;
+                let first_area = 0.5 * slope * (1.0 - x0_fraction) * (1.0 - x0_fraction);
+                let x1_fraction = x1 - x1_ceil + 1.0;
+                let last_area = 0.5 * slope * x1_fraction * x1_fraction;
+                self.add(row + x0_index, delta * first_area);
+                if x1_index == x0_index + 2 {
+                    self.add(row + x0_index + 1, delta * (1.0 - first_area - last_area));
+                } else {
+                    let second_area = slope * (1.5 - x0_fraction);
+                    self.add(row + x0_index + 1, delta * (second_area - first_area));
+                    for x_index in (x0_index + 2)..(x1_index - 1) {
+                        self.add(row + x_index, delta * slope);
+                    }
+                    let area = second_area + (x1_index - x0_index - 3) as f32 * slope;
+                    self.add(row + x1_index - 1, delta * (1.0 - area - last_area));
+                }
+                self.add(row + x1_index, delta * last_area);
+            }
+            x = x_next
+        }
+    }
+
+    /// Returns the coverage of each pixel, from 0 to 255, in rows from the top.
+    ///
+    /// Overlapping contours are filled with the nonzero rule, and coverage is clamped to full.
+    pub fn finish(self) -> Vec<u8> {
+        let mut coverage = 0.0;
+        self.cells[..self.width * self.height].iter().map(|&cell| {
+            coverage += cell;
+            (f32::min(coverage.abs(), 1.0) * 255.0 + 0.5) as u8
+        }).collect()
+    }
+
+    #[inline]
+    fn add(&mut self, index: usize, value: f32) {
+        if let Some(cell) = self.cells.get_mut(index) {
+            *cell += value
+        }
+    }
+}
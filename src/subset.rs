@@ -0,0 +1,160 @@
+// Copyright 2017 The Servo Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Font subsetting: writing a font that contains only some of another font's glyphs.
+
+use containers::otf::{self, FontTables};
+use error::FontError;
+use std::collections::BTreeSet;
+use tables::{cff, cmap, cvt, fpgm, gasp, glyf, head, hhea, hmtx, loca, maxp, name, os_2, post};
+use tables::prep;
+use util;
+
+const OTTO: u32 = ((b'O' as u32) << 24) |
+                  ((b'T' as u32) << 16) |
+                  ((b'T' as u32) << 8)  |
+                   (b'O' as u32);
+
+const TRUETYPE: u32 = 0x10000;
+
+// Tables that don't refer to glyphs, and so are copied into subsets as they are.
+static COPIED_TABLES: [u32; 6] = [os_2::TAG, cvt::TAG, fpgm::TAG, gasp::TAG, name::TAG, prep::TAG];
+
+// Only the header of a `post` table is kept, as version 3.0 has no glyph names.
+const POST_HEADER_SIZE: usize = 32;
+
+/// Writes a font containing only the given glyphs and those they depend on.
+///
+/// See `Font::subset()`.
+pub fn subset(tables: &FontTables, glyphs: &[u16]) -> Result<Vec<u8>, FontError> {
+    let num_glyphs = tables.maxp.num_glyphs();
+
+    // Gather the glyphs, `.notdef`, and the ligatures that they can form.
+    let mut closure = BTreeSet::new();
+    closure.insert(0);
+    closure.extend(glyphs.iter().cloned());
+    if let Some(ref gsub) = tables.gsub {
+        try!(gsub.close_over_ligatures(&mut closure))
+    }
+
+    // Add the components of composite and accented glyphs, which may themselves have components.
+    let mut pending: Vec<u16> = closure.iter().cloned().collect();
+    while let Some(glyph_id) = pending.pop() {
+        if glyph_id >= num_glyphs {
+            return Err(FontError::NoSuchGlyph)
+        }
+        for component in try!(component_glyphs(tables, glyph_id)) {
+            if closure.insert(component) {
+                pending.push(component)
+            }
+        }
+    }
+
+    // Glyphs keep their order, so this maps new glyph IDs to old ones.
+    let glyphs: Vec<u16> = closure.into_iter().collect();
+
+    let mut output = vec![];
+    let sfnt_version = match (tables.cff.as_ref(), tables.glyf.as_ref(), tables.loca.as_ref()) {
+        (Some(cff_table), _, _) => {
+            output.push((cff::TAG, try!(cff_table.subset(&glyphs))));
+            OTTO
+        }
+        (None, Some(glyf_table), Some(loca_table)) => {
+            let (glyf, loca) = try!(glyf_table.subset(loca_table, &glyphs));
+            output.push((glyf::TAG, glyf));
+            output.push((loca::TAG, loca));
+            TRUETYPE
+        }
+        _ => return Err(FontError::UnsupportedSubsetFormat),
+    };
+
+    // Map the characters of the remaining glyphs.
+    let mut mappings = vec![];
+    try!(tables.cmap.for_each_mapping(|character, glyph_id| {
+        if let Ok(new_glyph_id) = glyphs.binary_search(&glyph_id) {
+            mappings.push((character, new_glyph_id as u16))
+        }
+    }));
+    mappings.sort();
+    mappings.dedup();
+    output.push((cmap::TAG, cmap::write_cmap(&mappings)));
+
+    // Every glyph gets full metrics.
+    let mut hmtx = vec![];
+    for &glyph_id in &glyphs {
+        let metrics = try!(tables.hmtx.metrics(glyph_id, tables.hhea.number_of_h_metrics));
+        util::push_u16(&mut hmtx, metrics.advance_width);
+        util::push_u16(&mut hmtx, metrics.lsb as u16)
+    }
+    output.push((hmtx::TAG, hmtx));
+
+    let mut hhea = try!(copy_table(tables, hhea::TAG));
+    try!(set_u16(&mut hhea, 34, glyphs.len() as u16));
+    output.push((hhea::TAG, hhea));
+
+    let mut maxp = try!(copy_table(tables, maxp::TAG));
+    try!(set_u16(&mut maxp, 4, glyphs.len() as u16));
+    output.push((maxp::TAG, maxp));
+
+    // The checksum adjustment is filled in once the font is written.
+    let mut head = try!(copy_table(tables, head::TAG));
+    try!(set_u16(&mut head, 8, 0));
+    try!(set_u16(&mut head, 10, 0));
+    if sfnt_version == TRUETYPE {
+        try!(set_u16(&mut head, 50, 1))
+    }
+    output.push((head::TAG, head));
+
+    if let Some(post) = tables.unparsed_table(post::TAG) {
+        if post.bytes.len() >= POST_HEADER_SIZE {
+            let mut post = post.bytes[..POST_HEADER_SIZE].to_vec();
+            try!(set_u16(&mut post, 0, 3));
+            try!(set_u16(&mut post, 2, 0));
+            output.push((post::TAG, post))
+        }
+    }
+
+    for &tag in &COPIED_TABLES {
+        if let Some(table) = tables.unparsed_table(tag) {
+            output.push((tag, table.bytes.to_vec()))
+        }
+    }
+
+    Ok(otf::write_sfnt(sfnt_version, &mut output))
+}
+
+// Returns the glyphs that the given glyph is drawn with.
+fn component_glyphs(tables: &FontTables, glyph_id: u16) -> Result<Vec<u16>, FontError> {
+    match (tables.cff.as_ref(), tables.glyf.as_ref(), tables.loca.as_ref()) {
+        (Some(cff_table), _, _) => cff_table.component_glyphs(glyph_id),
+        (None, Some(glyf_table), Some(loca_table)) => {
+            glyf_table.component_glyphs(loca_table, glyph_id)
+        }
+        _ => Err(FontError::UnsupportedSubsetFormat),
+    }
+}
+
+// Returns a copy of the given table, which the font must have.
+fn copy_table(tables: &FontTables, tag: u32) -> Result<Vec<u8>, FontError> {
+    match tables.unparsed_table(tag) {
+        Some(table) => Ok(table.bytes.to_vec()),
+        None => Err(FontError::RequiredTableMissing),
+    }
+}
+
+// Overwrites the big-endian `u16` at the given offset in a table.
+fn set_u16(table: &mut [u8], offset: usize, value: u16) -> Result<(), FontError> {
+    if table.len() < offset + 2 {
+        return Err(FontError::UnexpectedEof)
+    }
+    table[offset] = (value >> 8) as u8;
+    table[offset + 1] = value as u8;
+    Ok(())
+}
@@ -0,0 +1,85 @@
+// Copyright 2017 The Servo Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+use byteorder::{BigEndian, ReadBytesExt};
+use error::FontError;
+use font::{BitmapFormat, FontTable};
+use tables::cblc::CblcTable;
+use util::Jump;
+
+pub const TAG: u32 = ((b'C' as u32) << 24) |
+                      ((b'B' as u32) << 16) |
+                      ((b'D' as u32) << 8)  |
+                       (b'T' as u32);
+
+const MIN_SUPPORTED_MAJOR_VERSION: u16 = 2;
+const MAX_SUPPORTED_MAJOR_VERSION: u16 = 3;
+
+// The glyph image formats that contain PNG data.
+const IMAGE_FORMAT_SMALL_METRICS_PNG: u16 = 17;
+const IMAGE_FORMAT_BIG_METRICS_PNG: u16 = 18;
+const IMAGE_FORMAT_PNG: u16 = 19;
+
+const SMALL_GLYPH_METRICS_SIZE: usize = 5;
+const BIG_GLYPH_METRICS_SIZE: usize = 8;
+
+#[derive(Clone, Copy, Debug)]
+pub struct CbdtTable<'a> {
+    table: FontTable<'a>,
+}
+
+impl<'a> CbdtTable<'a> {
+    pub fn new(table: FontTable<'a>) -> Result<CbdtTable<'a>, FontError> {
+        let mut reader = table.bytes;
+
+        // Check the version.
+        let major_version = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+        if major_version < MIN_SUPPORTED_MAJOR_VERSION ||
+                major_version > MAX_SUPPORTED_MAJOR_VERSION {
+            return Err(FontError::UnsupportedCbdtVersion)
+        }
+
+        Ok(CbdtTable {
+            table: table,
+        })
+    }
+
+    /// Returns the encoded image of the given glyph, along with its format, from the strike best
+    /// suited to the given size, using the index in the `CBLC` table.
+    ///
+    /// See `CblcTable::glyph_location()` for how the strike is chosen. The image is not decoded.
+    /// Returns `None` if the glyph has no bitmap in that strike or its bitmap isn't a PNG.
+    pub fn glyph_bitmap(&self, cblc_table: &CblcTable, glyph_id: u16, ppem: u16)
+                        -> Result<Option<(BitmapFormat, &'a [u8])>, FontError> {
+        let location = match try!(cblc_table.glyph_location(glyph_id, ppem)) {
+            None => return Ok(None),
+            Some(location) => location,
+        };
+
+        let mut reader = try!(self.table.subslice(location.offset as usize,
+                                                  location.length as usize));
+
+        match location.image_format {
+            IMAGE_FORMAT_SMALL_METRICS_PNG => {
+                try!(reader.jump(SMALL_GLYPH_METRICS_SIZE).map_err(FontError::eof))
+            }
+            IMAGE_FORMAT_BIG_METRICS_PNG => {
+                try!(reader.jump(BIG_GLYPH_METRICS_SIZE).map_err(FontError::eof))
+            }
+            IMAGE_FORMAT_PNG => {}
+            _ => return Ok(None),
+        }
+
+        let data_len = try!(reader.read_u32::<BigEndian>().map_err(FontError::eof)) as usize;
+        if data_len > reader.len() {
+            return Err(FontError::UnexpectedEof)
+        }
+        Ok(Some((BitmapFormat::Png, &reader[..data_len])))
+    }
+}
@@ -0,0 +1,242 @@
+// Copyright 2017 The Servo Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+use byteorder::{BigEndian, ReadBytesExt};
+use error::FontError;
+use font::{BigGlyphMetrics, BitmapStrike, FontTable, SmallGlyphMetrics};
+use std::mem;
+use tables::sbix;
+use util::Jump;
+
+pub const TAG: u32 = ((b'C' as u32) << 24) |
+                      ((b'B' as u32) << 16) |
+                      ((b'L' as u32) << 8)  |
+                       (b'C' as u32);
+
+// Version 2 is the original color bitmap table, and the version of the `EBLC` table that this
+// also parses; version 3 is identical apart from its meaning in CBDT when combined with a CBDT of
+// the same version.
+const MIN_SUPPORTED_MAJOR_VERSION: u16 = 2;
+const MAX_SUPPORTED_MAJOR_VERSION: u16 = 3;
+
+const HEADER_SIZE: usize = 8;
+const BITMAP_SIZE_RECORD_SIZE: usize = 48;
+const INDEX_SUBTABLE_RECORD_SIZE: usize = 8;
+
+// The offsets of the fields we need within a `BitmapSize` record.
+const BITMAP_SIZE_START_GLYPH_INDEX_OFFSET: usize = 40;
+const BITMAP_SIZE_BIT_DEPTH_OFFSET: usize = 46;
+
+// CBLC doesn't record the resolution its strikes were designed for, so we report the traditional
+// 72 pixels per inch.
+const DEFAULT_RESOLUTION: u16 = 72;
+
+#[derive(Clone, Copy, Debug)]
+pub struct CblcTable<'a> {
+    table: FontTable<'a>,
+    num_sizes: u32,
+}
+
+/// Where a glyph's bitmap is stored in the `CBDT` table.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct BitmapLocation {
+    /// The format of the glyph's entry in `CBDT`.
+    pub image_format: u16,
+    /// The offset of the glyph's entry from the start of `CBDT`.
+    pub offset: u32,
+    /// The length of the glyph's entry, including its metrics.
+    pub length: u32,
+    /// The number of bits per pixel in the strike's monochrome or grayscale bitmaps.
+    pub bit_depth: u8,
+    /// The metrics that every glyph in the index subtable shares, if it has them.
+    pub metrics: Option<BigGlyphMetrics>,
+}
+
+impl<'a> CblcTable<'a> {
+    pub fn new(table: FontTable<'a>) -> Result<CblcTable<'a>, FontError> {
+        let mut reader = table.bytes;
+
+        // Check the version.
+        let major_version = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+        if major_version < MIN_SUPPORTED_MAJOR_VERSION ||
+                major_version > MAX_SUPPORTED_MAJOR_VERSION {
+            return Err(FontError::UnsupportedCblcVersion)
+        }
+
+        let _minor_version = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+        let num_sizes = try!(reader.read_u32::<BigEndian>().map_err(FontError::eof));
+
+        Ok(CblcTable {
+            table: table,
+            num_sizes: num_sizes,
+        })
+    }
+
+    /// Returns the sizes at which bitmaps are available, in the order they appear in the font.
+    pub fn bitmap_strikes(&self) -> Result<Vec<BitmapStrike>, FontError> {
+        let mut strikes = vec![];
+        for size_index in 0..self.num_sizes {
+            let mut reader = try!(self.bitmap_size(size_index));
+            try!(reader.jump(BITMAP_SIZE_START_GLYPH_INDEX_OFFSET + mem::size_of::<u16>() * 2)
+                       .map_err(FontError::eof));
+            let _ppem_x = try!(reader.read_u8().map_err(FontError::eof));
+            let ppem_y = try!(reader.read_u8().map_err(FontError::eof));
+            strikes.push(BitmapStrike {
+                ppem: ppem_y as u16,
+                resolution: DEFAULT_RESOLUTION,
+            })
+        }
+        Ok(strikes)
+    }
+
+    /// Returns the location of the given glyph's bitmap in the `CBDT` or `EBDT` table, from the
+    /// strike best suited to the given size.
+    ///
+    /// That is the smallest strike at least `ppem` pixels per em, or the largest strike if all
+    /// are smaller. Returns `None` if the glyph has no bitmap in that strike.
+    pub fn glyph_location(&self, glyph_id: u16, ppem: u16)
+                          -> Result<Option<BitmapLocation>, FontError> {
+        let strikes = try!(self.bitmap_strikes());
+        let size_index = match sbix::best_strike(&strikes, ppem) {
+            None => return Ok(None),
+            Some(size_index) => size_index,
+        };
+
+        let mut reader = try!(self.bitmap_size(size_index as u32));
+        let index_subtable_array_offset = try!(reader.read_u32::<BigEndian>()
+                                                     .map_err(FontError::eof));
+        let _index_tables_size = try!(reader.read_u32::<BigEndian>().map_err(FontError::eof));
+        let number_of_index_subtables = try!(reader.read_u32::<BigEndian>()
+                                                   .map_err(FontError::eof));
+
+        let mut reader = try!(self.bitmap_size(size_index as u32));
+        try!(reader.jump(BITMAP_SIZE_BIT_DEPTH_OFFSET).map_err(FontError::eof));
+        let bit_depth = try!(reader.read_u8().map_err(FontError::eof));
+
+        let mut index_subtable_array = self.table.bytes;
+        try!(index_subtable_array.jump(index_subtable_array_offset as usize)
+                                 .map_err(FontError::eof));
+
+        // Find the index subtable covering the glyph. The records are sorted by glyph ID.
+        let (mut low, mut high) = (0, number_of_index_subtables as usize);
+        while low < high {
+            let mid = (low + high) / 2;
+            let mut record = index_subtable_array;
+            try!(record.jump(mid * INDEX_SUBTABLE_RECORD_SIZE).map_err(FontError::eof));
+            let first_glyph_index = try!(record.read_u16::<BigEndian>().map_err(FontError::eof));
+            let last_glyph_index = try!(record.read_u16::<BigEndian>().map_err(FontError::eof));
+            if glyph_id < first_glyph_index {
+                high = mid
+            } else if glyph_id > last_glyph_index {
+                low = mid + 1
+            } else {
+                let additional_offset = try!(record.read_u32::<BigEndian>()
+                                                   .map_err(FontError::eof));
+                let mut index_subtable = index_subtable_array;
+                try!(index_subtable.jump(additional_offset as usize).map_err(FontError::eof));
+                return glyph_location_in_index_subtable(index_subtable,
+                                                        glyph_id - first_glyph_index,
+                                                        bit_depth)
+            }
+        }
+        Ok(None)
+    }
+
+    // Returns the `BitmapSize` record with the given index.
+    fn bitmap_size(&self, size_index: u32) -> Result<&'a [u8], FontError> {
+        let mut reader = self.table.bytes;
+        try!(reader.jump(HEADER_SIZE + BITMAP_SIZE_RECORD_SIZE * size_index as usize)
+                   .map_err(FontError::eof));
+        Ok(reader)
+    }
+}
+
+// Returns the location of the glyph with the given index relative to the first glyph of the index
+// subtable.
+fn glyph_location_in_index_subtable(index_subtable: &[u8], glyph_index: u16, bit_depth: u8)
+                                    -> Result<Option<BitmapLocation>, FontError> {
+    let mut reader = index_subtable;
+    let index_format = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+    let image_format = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+    let image_data_offset = try!(reader.read_u32::<BigEndian>().map_err(FontError::eof));
+
+    let mut metrics = None;
+    let (start, end) = match index_format {
+        1 => {
+            // Variable-size glyphs with 32-bit offsets.
+            try!(reader.jump(mem::size_of::<u32>() * glyph_index as usize)
+                       .map_err(FontError::eof));
+            let start = try!(reader.read_u32::<BigEndian>().map_err(FontError::eof));
+            let end = try!(reader.read_u32::<BigEndian>().map_err(FontError::eof));
+            (start, end)
+        }
+        2 => {
+            // Glyphs that all have the same size and metrics.
+            let image_size = try!(reader.read_u32::<BigEndian>().map_err(FontError::eof));
+            metrics = Some(try!(read_big_glyph_metrics(&mut reader)));
+            let start = image_size.wrapping_mul(glyph_index as u32);
+            (start, start.wrapping_add(image_size))
+        }
+        3 => {
+            // Variable-size glyphs with 16-bit offsets.
+            try!(reader.jump(mem::size_of::<u16>() * glyph_index as usize)
+                       .map_err(FontError::eof));
+            let start = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+            let end = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+            (start as u32, end as u32)
+        }
+        _ => return Err(FontError::UnsupportedCblcIndexFormat),
+    };
+
+    // Glyphs without bitmaps have no data at all.
+    if end <= start {
+        return Ok(None)
+    }
+
+    Ok(Some(BitmapLocation {
+        image_format: image_format,
+        offset: image_data_offset.wrapping_add(start),
+        length: end - start,
+        bit_depth: bit_depth,
+        metrics: metrics,
+    }))
+}
+
+/// Reads a `SmallGlyphMetrics` record, as found in bitmap data.
+pub fn read_small_glyph_metrics(reader: &mut &[u8]) -> Result<SmallGlyphMetrics, FontError> {
+    let mut fields = [0; 5];
+    for field in &mut fields {
+        *field = try!(reader.read_u8().map_err(FontError::eof))
+    }
+    Ok(SmallGlyphMetrics {
+        height: fields[0],
+        width: fields[1],
+        bearing_x: fields[2] as i8,
+        bearing_y: fields[3] as i8,
+        advance: fields[4],
+    })
+}
+
+/// Reads a `BigGlyphMetrics` record, as found in bitmap data and format 2 index subtables.
+pub fn read_big_glyph_metrics(reader: &mut &[u8]) -> Result<BigGlyphMetrics, FontError> {
+    let mut fields = [0; 8];
+    for field in &mut fields {
+        *field = try!(reader.read_u8().map_err(FontError::eof))
+    }
+    Ok(BigGlyphMetrics {
+        height: fields[0],
+        width: fields[1],
+        hori_bearing_x: fields[2] as i8,
+        hori_bearing_y: fields[3] as i8,
+        hori_advance: fields[4],
+        vert_bearing_x: fields[5] as i8,
+        vert_bearing_y: fields[6] as i8,
+        vert_advance: fields[7],
+    })
+}
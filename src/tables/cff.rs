@@ -8,233 +8,1054 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
-use byteorder::{BigEndian, ReadBytesExt};
 use error::FontError;
-use euclid::Point2D;
-use font::{FontTable, Point, PointKind};
+use euclid::{Matrix2D, Point2D};
+use font::{Contour, FontTable, PathSegment, PathSegments, Point, PointKind, SvgPathOptions};
+use font::TransformedPoint;
 use outline::GlyphBounds;
+use std::borrow::Cow;
 use std::cmp;
+use std::f32;
+use std::i16;
+use std::i32;
+use std::str;
 use std::u16;
-use util::Jump;
+use std::u32;
+use tables::cff_encoding;
+use tables::cff_strings::{self, STANDARD_STRINGS};
+use util::{self, EofContext, Flattener, Reader, SvgPathWriter};
 
 pub const TAG: u32 = ((b'C' as u32) << 24) |
                       ((b'F' as u32) << 16) |
                       ((b'F' as u32) << 8)  |
                        (b' ' as u32);
 
+pub const CFF2_TAG: u32 = ((b'C' as u32) << 24) |
+                           ((b'F' as u32) << 16) |
+                           ((b'F' as u32) << 8)  |
+                            (b'2' as u32);
+
+// Set in the format byte of a custom encoding if supplements follow the encoding data.
+const ENCODING_HAS_SUPPLEMENTS: u8 = 0x80;
+
+// The maximum nesting depth of subroutine calls, per the Type 2 Charstring Format spec.
+const MAX_SUBR_NESTING: u8 = 10;
+
+// The maximum depth of the CharString argument stack in CFF and CFF2, respectively.
+const MAX_STACK_SIZE: usize = 48;
+const CFF2_MAX_STACK_SIZE: usize = 513;
+
+// The number of entries in the transient array that `put` and `get` use.
+const TRANSIENT_ARRAY_SIZE: usize = 32;
+
+// DICT operators.
+const OPERATOR_CHARSET: u16 = 15;
+const OPERATOR_ENCODING: u16 = 16;
+const OPERATOR_CHAR_STRINGS: u16 = 17;
+const OPERATOR_PRIVATE: u16 = 18;
+const OPERATOR_SUBRS: u16 = 19;
+const OPERATOR_DEFAULT_WIDTH_X: u16 = 20;
+const OPERATOR_NOMINAL_WIDTH_X: u16 = 21;
+const OPERATOR_VSINDEX: u16 = 22;
+const OPERATOR_VSTORE: u16 = 24;
+const OPERATOR_FONT_MATRIX: u16 = (7 << 8) | 12;
+const OPERATOR_ROS: u16 = (30 << 8) | 12;
+const OPERATOR_FD_ARRAY: u16 = (36 << 8) | 12;
+const OPERATOR_FD_SELECT: u16 = (37 << 8) | 12;
+
+// The size of a DICT integer in its 32-bit form, which we write offsets in so that a DICT's size
+// doesn't depend on them.
+const DICT_OFFSET_SIZE: usize = 5;
+
+// What subsetting replaces unused subroutines with: a bare `return`.
+static UNUSED_SUBR: [u8; 1] = [11];
+
 #[derive(Clone, Copy, Debug)]
 pub struct CffTable<'a> {
-    // The offset of the char strings INDEX.
-    char_strings: u32,
+    // The major version: 1 for CFF or 2 for CFF2.
+    major_version: u8,
+    // The CharStrings INDEX, with one CharString per glyph.
+    char_strings: Index<'a>,
+    // The number of glyphs, which is the number of CharStrings.
+    glyph_count: u16,
+    // The mapping from glyph IDs to SIDs (string IDs) or, for CID-keyed fonts, CIDs.
+    charset: Charset<'a>,
+    // The mapping from character codes to glyphs.
+    encoding: Encoding<'a>,
+    // The name INDEX, with one name per font in the FontSet. CFF2 tables don't have one.
+    names: Index<'a>,
+    // Which font of the FontSet this is.
+    font_index: u16,
+    // The top DICT for our font.
+    top_dict: &'a [u8],
+    // The mapping from CharString units to ems.
+    font_matrix: Matrix2D<f32>,
+    // The string INDEX, holding the strings with SIDs past the standard strings.
+    strings: Index<'a>,
+    // The global subroutine INDEX.
+    global_subrs: Index<'a>,
+    // The Private DICT referenced by the top DICT. CID-keyed fonts don't have one.
+    private_dict: PrivateDict<'a>,
+    // For CID-keyed fonts, the INDEX of font DICTs, each with its own Private DICT.
+    fd_array: Index<'a>,
+    // For CID-keyed fonts, the mapping from glyph IDs to entries in `fd_array`.
+    fd_select: Option<FdSelect<'a>>,
+    // For CFF2 fonts, the variation data that `blend` operators refer to.
+    variation_store: Option<VariationStore<'a>>,
     table: FontTable<'a>,
 }
 
 impl<'a> CffTable<'a> {
+    /// Parses a `CFF ` or `CFF2` table, using the first font if it contains several.
     #[inline]
     pub fn new(table: FontTable) -> Result<CffTable, FontError> {
-        let mut reader = table.bytes;
+        CffTable::with_font_index(table, 0)
+    }
+
+    /// Parses a `CFF ` or `CFF2` table, using the font with the given index in its FontSet.
+    ///
+    /// CFF tables in OpenType fonts contain only one font, but standalone CFF data may contain
+    /// several; see `font_names()`. Returns `FontError::FontIndexOutOfBounds` if there's no such
+    /// font. CFF2 tables always contain one font.
+    pub fn with_font_index(table: FontTable, font_index: u16) -> Result<CffTable, FontError> {
+        let mut reader = Reader::new(table.bytes);
 
         // Check version.
-        let major = try!(reader.read_u8().map_err(FontError::eof));
-        let minor = try!(reader.read_u8().map_err(FontError::eof));
-        if major != 1 || minor != 0 {
-            return Err(FontError::UnsupportedCffVersion)
+        let major = try!(reader.read_u8());
+        let minor = try!(reader.read_u8());
+        match (major, minor) {
+            (1, 0) => CffTable::new_cff(table, reader, font_index),
+            (2, 0) if font_index == 0 => CffTable::new_cff2(table, reader),
+            (2, 0) => Err(FontError::FontIndexOutOfBounds),
+            _ => Err(FontError::UnsupportedCffVersion),
         }
+    }
+
+    /// Parses a bare CFF font program, such as a standalone `.cff` file or the data of a PDF
+    /// `FontFile3` stream, whose header starts the buffer.
+    ///
+    /// This is the same as `new()`, for data that doesn't come from an sfnt table.
+    #[inline]
+    pub fn from_bytes(data: &'a [u8]) -> Result<CffTable<'a>, FontError> {
+        CffTable::new(FontTable {
+            bytes: data,
+        })
+    }
 
-        // Skip the header.
-        let hdr_size = try!(reader.read_u8().map_err(FontError::eof));
-        try!(reader.jump(hdr_size as usize - 3).map_err(FontError::eof));
+    // Parses the rest of a CFF table, starting after the version, for the font with the given
+    // index.
+    fn new_cff(table: FontTable<'a>, mut reader: Reader<'a>, font_index: u16)
+               -> Result<CffTable<'a>, FontError> {
+        // Skip the header, whose size includes the version.
+        let hdr_size = try!(reader.read_u8().eof_in(TAG, "header size"));
+        let mut reader = try!(Reader::at(table.bytes, hdr_size as usize).eof_in(TAG, "header"));
 
-        // Skip the name INDEX.
-        //
-        // TODO(pcwalton): What to do if there are multiple fonts here?
-        try!(skip_index(&mut reader));
+        // The name INDEX says how many fonts there are.
+        let names = try!(Index::new(&mut reader, "name INDEX"));
+        if font_index >= names.count {
+            return Err(FontError::FontIndexOutOfBounds)
+        }
 
         // Get the top DICT for our font.
-        if try!(find_in_index(&mut reader, 0)).is_none() {
-            return Err(FontError::CffTopDictNotFound)
+        let top_dict = match try!(try!(Index::new(&mut reader, "top DICT INDEX")).get(font_index)) {
+            Some(top_dict) => top_dict,
+            None => return Err(FontError::CffTopDictNotFound),
+        };
+
+        let strings = try!(Index::new(&mut reader, "string INDEX"));
+
+        // Read the global subr INDEX, which immediately follows.
+        let global_subrs = try!(Index::new(&mut reader, "global subr INDEX"));
+
+        let private_dict = try!(PrivateDict::new(table.bytes, top_dict, 1));
+
+        let mut reader = try!(dict_offset(table.bytes,
+                                          top_dict,
+                                          OPERATOR_CHAR_STRINGS,
+                                          TAG,
+                                          "CharStrings offset"));
+        let char_strings = try!(Index::new(&mut reader, "CharStrings INDEX"));
+        let glyph_count = char_strings.count;
+
+        // The charset defaults to ISOAdobe.
+        let charset = match try!(find_in_dict(top_dict, OPERATOR_CHARSET)) {
+            None => Charset::IsoAdobe,
+            Some(charset) => {
+                match charset.last().and_then(DictOperand::to_integer) {
+                    Some(0) => Charset::IsoAdobe,
+                    Some(1) => Charset::Expert,
+                    Some(2) => Charset::ExpertSubset,
+                    Some(charset_offset) if charset_offset > 0 => {
+                        let offset = charset_offset as usize;
+                        Charset::Custom(try!(Reader::at(table.bytes, offset)
+                                                 .eof_in(TAG, "charset offset")))
+                    }
+                    _ => return Err(FontError::CffBadOffset),
+                }
+            }
+        };
+
+        // The encoding defaults to Standard Encoding.
+        let encoding = match try!(find_in_dict(top_dict, OPERATOR_ENCODING)) {
+            None => Encoding::Standard,
+            Some(encoding) => {
+                match encoding.last().and_then(DictOperand::to_integer) {
+                    Some(0) => Encoding::Standard,
+                    Some(1) => Encoding::Expert,
+                    Some(encoding_offset) if encoding_offset > 1 => {
+                        let offset = encoding_offset as usize;
+                        Encoding::Custom(try!(Reader::at(table.bytes, offset)
+                                                  .eof_in(TAG, "encoding offset")))
+                    }
+                    _ => return Err(FontError::CffBadOffset),
+                }
+            }
+        };
+
+        // CID-keyed fonts select a font DICT, and thus a Private DICT, for each glyph.
+        let (mut fd_array, mut fd_select) = (Index::empty(), None);
+        if try!(find_in_dict(top_dict, OPERATOR_ROS)).is_some() {
+            let mut reader = try!(dict_offset(table.bytes,
+                                              top_dict,
+                                              OPERATOR_FD_ARRAY,
+                                              TAG,
+                                              "FDArray offset"));
+            fd_array = try!(Index::new(&mut reader, "font DICT INDEX"));
+
+            let reader = try!(dict_offset(table.bytes,
+                                          top_dict,
+                                          OPERATOR_FD_SELECT,
+                                          TAG,
+                                          "FDSelect offset"));
+            fd_select = Some(try!(FdSelect::new(reader, glyph_count)))
         }
 
-        // Find the CharStrings offset within the top DICT.
-        let char_strings = try!(get_integer_in_dict(&mut reader, 17));
+        Ok(CffTable {
+            major_version: 1,
+            char_strings: char_strings,
+            glyph_count: glyph_count,
+            charset: charset,
+            encoding: encoding,
+            names: names,
+            font_index: font_index,
+            top_dict: top_dict,
+            font_matrix: try!(read_font_matrix(top_dict)),
+            strings: strings,
+            global_subrs: global_subrs,
+            private_dict: private_dict,
+            fd_array: fd_array,
+            fd_select: fd_select,
+            variation_store: None,
+            table: table,
+        })
+    }
+
+    // Parses the rest of a CFF2 table, starting after the version.
+    //
+    // CFF2 drops the name and string INDEXes and stores the top DICT directly after the header.
+    // Every font has a font DICT array, but FDSelect is only needed if there's more than one.
+    fn new_cff2(table: FontTable<'a>, mut reader: Reader<'a>)
+                -> Result<CffTable<'a>, FontError> {
+        let hdr_size = try!(reader.read_u8().eof_in(CFF2_TAG, "header size"));
+        let top_dict_length = try!(reader.read_u16_be().eof_in(CFF2_TAG, "top DICT length"));
+
+        let mut reader = try!(Reader::at(table.bytes, hdr_size as usize)
+                                  .eof_in(CFF2_TAG, "header"));
+        let top_dict = try!(reader.subslice(top_dict_length as usize).eof_in(CFF2_TAG, "top DICT"));
+
+        // Read the global subr INDEX, which immediately follows.
+        let global_subrs = try!(Index::new_cff2(&mut reader, "global subr INDEX"));
 
-        // Skip the string INDEX.
-        try!(skip_index(&mut reader));
+        let mut reader = try!(dict_offset(table.bytes,
+                                          top_dict,
+                                          OPERATOR_CHAR_STRINGS,
+                                          CFF2_TAG,
+                                          "CharStrings offset"));
+        let char_strings = try!(Index::new_cff2(&mut reader, "CharStrings INDEX"));
+        let glyph_count = char_strings.count;
 
-        // Ignore the global subr INDEX for now.
-        //
-        // TODO(pcwalton): Support global subroutines.
+        let mut reader = try!(dict_offset(table.bytes,
+                                          top_dict,
+                                          OPERATOR_FD_ARRAY,
+                                          CFF2_TAG,
+                                          "FDArray offset"));
+        let fd_array = try!(Index::new_cff2(&mut reader, "font DICT INDEX"));
+        let private_dict = match try!(fd_array.get(0)) {
+            Some(font_dict) => try!(PrivateDict::new(table.bytes, font_dict, 2)),
+            None => return Err(FontError::CffFontDictNotFound),
+        };
+
+        let fd_select = match try!(find_in_dict(top_dict, OPERATOR_FD_SELECT)) {
+            None => None,
+            Some(_) => {
+                let reader = try!(dict_offset(table.bytes,
+                                              top_dict,
+                                              OPERATOR_FD_SELECT,
+                                              CFF2_TAG,
+                                              "FDSelect offset"));
+                Some(try!(FdSelect::new(reader, glyph_count)))
+            }
+        };
+
+        // The variation store begins with its length, which we don't need.
+        let variation_store = match try!(find_in_dict(top_dict, OPERATOR_VSTORE)) {
+            None => None,
+            Some(_) => {
+                let mut reader = try!(dict_offset(table.bytes,
+                                                  top_dict,
+                                                  OPERATOR_VSTORE,
+                                                  CFF2_TAG,
+                                                  "variation store offset"));
+                try!(reader.jump(2).eof_in(CFF2_TAG, "variation store length"));
+                Some(try!(VariationStore::new(reader.remaining())))
+            }
+        };
 
         Ok(CffTable {
-            char_strings: char_strings as u32,
+            major_version: 2,
+            char_strings: char_strings,
+            glyph_count: glyph_count,
+            charset: Charset::IsoAdobe,
+            encoding: Encoding::Standard,
+            names: Index::empty(),
+            font_index: 0,
+            top_dict: top_dict,
+            font_matrix: try!(read_font_matrix(top_dict)),
+            strings: Index::empty(),
+            global_subrs: global_subrs,
+            private_dict: private_dict,
+            fd_array: fd_array,
+            fd_select: fd_select,
+            variation_store: variation_store,
             table: table,
         })
     }
 
+    /// Returns the PostScript names of the fonts in this table's FontSet, in order, for use with
+    /// `with_font_index()`.
+    ///
+    /// Names are ASCII, but any invalid bytes are replaced. CFF2 tables have no names, so the
+    /// result is empty for them.
+    pub fn font_names(&self) -> Vec<String> {
+        (0..self.names.count).filter_map(|index| self.names.get(index).unwrap_or(None)).map(|name| {
+            String::from_utf8_lossy(name).into_owned()
+        }).collect()
+    }
+
+    /// Returns the number of glyphs in this font, which is the number of CharStrings.
+    #[inline]
+    pub fn num_glyphs(&self) -> Result<u16, FontError> {
+        Ok(self.glyph_count)
+    }
+
+    /// Returns the `FontMatrix` from the top DICT, which maps CharString units to ems.
+    ///
+    /// This is almost always a scale of 1/1000, which is the default when the font doesn't
+    /// specify one, but fonts may use a different scale or even add a skew.
+    #[inline]
+    pub fn font_matrix(&self) -> Matrix2D<f32> {
+        self.font_matrix
+    }
+
+    /// Returns the glyph for the given character code in the font's own encoding.
+    ///
+    /// OpenType fonts map characters with their `cmap` table instead, but bare CFF fonts have only
+    /// this. CID-keyed fonts and CFF2 fonts have no encoding, so this always returns `None` for
+    /// them.
+    pub fn glyph_for_code(&self, code: u8) -> Option<u16> {
+        if !self.is_name_keyed() {
+            return None
+        }
+
+        let sid = match self.encoding {
+            Encoding::Standard => cff_encoding::standard_encoding_sid(code),
+            Encoding::Expert => cff_encoding::expert_encoding_sid(code),
+            Encoding::Custom(reader) => {
+                return self.glyph_for_custom_code(reader, code).unwrap_or(None)
+            }
+        };
+        let sid = match sid {
+            Some(sid) => sid,
+            None => return None,
+        };
+
+        self.charset.glyph_id_for_sid(sid, self.glyph_count).unwrap_or(None)
+    }
+
+    // Looks up the given code in a custom encoding. The codes cover glyphs in order, starting
+    // after `.notdef`, and supplements map extra codes to SIDs.
+    fn glyph_for_custom_code(&self, mut reader: Reader, code: u8)
+                             -> Result<Option<u16>, FontError> {
+        let format = try!(reader.read_u8());
+        let mut result = None;
+        match format & !ENCODING_HAS_SUPPLEMENTS {
+            0 => {
+                let code_count = try!(reader.read_u8());
+                for glyph_id in 1..(code_count as u16 + 1) {
+                    if try!(reader.read_u8()) == code && result.is_none() {
+                        result = Some(glyph_id)
+                    }
+                }
+            }
+            1 => {
+                let range_count = try!(reader.read_u8());
+                let mut first_glyph_id = 1;
+                for _ in 0..range_count {
+                    let first_code = try!(reader.read_u8());
+                    let left_count = try!(reader.read_u8());
+                    if code >= first_code && code - first_code <= left_count && result.is_none() {
+                        result = Some(first_glyph_id + (code - first_code) as u16)
+                    }
+                    first_glyph_id += left_count as u16 + 1
+                }
+            }
+            _ => return Err(FontError::UnknownFormat),
+        }
+
+        if let Some(glyph_id) = result {
+            if glyph_id >= self.glyph_count {
+                return Ok(None)
+            }
+            return Ok(Some(glyph_id))
+        }
+
+        // The supplements follow the main encoding data.
+        if (format & ENCODING_HAS_SUPPLEMENTS) != 0 {
+            let supplement_count = try!(reader.read_u8());
+            for _ in 0..supplement_count {
+                let supplement_code = try!(reader.read_u8());
+                let sid = try!(reader.read_u16_be());
+                if supplement_code == code {
+                    return self.charset.glyph_id_for_sid(sid, self.glyph_count)
+                }
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Returns the name of the given glyph, as given by the charset.
+    ///
+    /// CID-keyed fonts and CFF2 fonts have no glyph names, so this always returns `None` for them.
+    pub fn glyph_name(&self, glyph_id: u16) -> Option<&'a str> {
+        if !self.is_name_keyed() {
+            return None
+        }
+
+        match self.charset.sid_for_glyph(glyph_id, self.glyph_count) {
+            Ok(Some(sid)) => self.string(sid).unwrap_or(None),
+            Ok(None) | Err(_) => None,
+        }
+    }
+
+    /// Returns the glyph with the given name, if there is one.
+    pub fn glyph_for_name(&self, name: &str) -> Option<u16> {
+        if !self.is_name_keyed() {
+            return None
+        }
+
+        let sid = match STANDARD_STRINGS.iter().position(|string| *string == name) {
+            Some(sid) => sid as u16,
+            None => {
+                let index = (0..self.strings.count).position(|index| {
+                    match self.strings.get(index) {
+                        Ok(Some(string)) => string == name.as_bytes(),
+                        Ok(None) | Err(_) => false,
+                    }
+                });
+                match index {
+                    Some(index) if index + STANDARD_STRINGS.len() <= u16::MAX as usize => {
+                        (index + STANDARD_STRINGS.len()) as u16
+                    }
+                    _ => return None,
+                }
+            }
+        };
+
+        self.charset.glyph_id_for_sid(sid, self.glyph_count).unwrap_or(None)
+    }
+
+    /// Returns the string with the given SID (string ID), or `None` if there's no such string.
+    ///
+    /// SIDs below 391 are the standard strings; higher ones index the font's string INDEX. Font
+    /// strings that aren't valid UTF-8 have their invalid bytes replaced.
+    pub fn string_for_sid(&self, sid: u16) -> Option<Cow<'a, str>> {
+        if let Some(string) = cff_strings::standard_string(sid) {
+            return Some(Cow::Borrowed(string))
+        }
+
+        match self.strings.get(sid - STANDARD_STRINGS.len() as u16) {
+            Ok(Some(string)) => Some(String::from_utf8_lossy(string)),
+            Ok(None) | Err(_) => None,
+        }
+    }
+
+    // Returns true if this is a name-keyed CFF font, whose charset maps glyphs to SIDs, and thus
+    // names, rather than CIDs.
+    fn is_name_keyed(&self) -> bool {
+        self.major_version == 1 && self.fd_select.is_none()
+    }
+
+    // Returns the string with the given SID, or `None` if there's no such string or it isn't
+    // valid UTF-8.
+    fn string(&self, sid: u16) -> Result<Option<&'a str>, FontError> {
+        if let Some(string) = cff_strings::standard_string(sid) {
+            return Ok(Some(string))
+        }
+
+        let index = sid - STANDARD_STRINGS.len() as u16;
+        Ok(try!(self.strings.get(index)).and_then(|string| str::from_utf8(string).ok()))
+    }
+
     pub fn for_each_point<F>(&self, glyph_id: u16, mut callback: F)
                              -> Result<(), FontError> where F: FnMut(&Point) {
-        let mut reader = self.table.bytes;
-        try!(reader.jump(self.char_strings as usize).map_err(FontError::eof));
+        try!(self.evaluate_glyph(glyph_id, &[], false, &mut callback));
+        Ok(())
+    }
 
-        let char_string_length = match try!(find_in_index(&mut reader, glyph_id)) {
-            Some(char_string_length) => char_string_length,
-            None => return Err(FontError::UnexpectedEof),
-        };
+    /// Like `for_each_point()`, but skips CharString operators that aren't supported instead of
+    /// failing, discarding their operands, and returns the skipped operators in the order they
+    /// were encountered.
+    ///
+    /// Escaped operators are returned as `(b1 << 8) | 12`, as for DICT operators. Skipping is only
+    /// safe for operators that don't move the current point, such as hints; malformed
+    /// CharStrings are still rejected.
+    pub fn for_each_point_leniently<F>(&self, glyph_id: u16, mut callback: F)
+                                       -> Result<Vec<u16>, FontError> where F: FnMut(&Point) {
+        let state = try!(self.evaluate_glyph(glyph_id, &[], true, &mut callback));
+        Ok(state.skipped_operators.unwrap_or(vec![]))
+    }
 
-        let mut reader = &reader[0..char_string_length as usize];
-        let mut stack = EvaluationStack::new();
-        let (mut start, mut pos) = (Point2D::new(0, 0), Point2D::new(0, 0));
+    /// Like `for_each_point()`, but with each cubic curve approximated by one or more quadratic
+    /// curves that stay within `tolerance` font units of it.
+    ///
+    /// Curves are emitted as they are for TrueType glyphs, with a single `QuadControl` point per
+    /// quadratic, so that the same code can consume both kinds of outline.
+    pub fn for_each_point_as_quadratic<F>(&self, glyph_id: u16, tolerance: f32, mut callback: F)
+                                          -> Result<(), FontError> where F: FnMut(&Point) {
         let mut index_in_contour = 0;
-        let mut hint_count = 0;
+        let mut last_on_curve = Point2D::new(0, 0);
+        let mut control_points = [Point2D::new(0, 0); 2];
+        let mut last_kind = PointKind::OnCurve;
+        self.for_each_point(glyph_id, |point| {
+            match point.kind {
+                PointKind::FirstCubicControl => control_points[0] = point.position,
+                PointKind::SecondCubicControl => control_points[1] = point.position,
+                PointKind::OnCurve if last_kind == PointKind::SecondCubicControl => {
+                    let cubic = [last_on_curve,
+                                 control_points[0],
+                                 control_points[1],
+                                 point.position];
+                    for &(control, end) in &cubic_to_quadratics(&cubic, tolerance) {
+                        callback(&Point {
+                            position: control,
+                            index_in_contour: index_in_contour,
+                            kind: PointKind::QuadControl,
+                        });
+                        callback(&Point {
+                            position: end,
+                            index_in_contour: index_in_contour.wrapping_add(1),
+                            kind: PointKind::OnCurve,
+                        });
+                        index_in_contour = index_in_contour.wrapping_add(2)
+                    }
+                }
+                PointKind::OnCurve | PointKind::QuadControl => {
+                    if point.index_in_contour == 0 {
+                        index_in_contour = 0
+                    }
+                    callback(&Point {
+                        index_in_contour: index_in_contour,
+                        ..*point
+                    });
+                    index_in_contour = index_in_contour.wrapping_add(1)
+                }
+            }
+
+            if point.kind == PointKind::OnCurve {
+                last_on_curve = point.position
+            }
+            last_kind = point.kind
+        })
+    }
 
-        // FIXME(pcwalton): This shouldn't panic on stack bounds check failures.
+    /// Like `for_each_point()`, but maps each point through the given affine transform as it is
+    /// emitted.
+    pub fn for_each_point_transformed<F>(&self,
+                                         glyph_id: u16,
+                                         transform: &Matrix2D<f32>,
+                                         mut callback: F)
+                                         -> Result<(), FontError>
+                                         where F: FnMut(&TransformedPoint) {
+        self.for_each_point(glyph_id, |point| callback(&point.transform(transform)))
+    }
+
+    /// Returns the outline of the given glyph with each curve flattened into line segments no more
+    /// than `tolerance` font units from it.
+    pub fn flatten(&self, glyph_id: u16, tolerance: f32)
+                   -> Result<Vec<Contour<Point2D<f32>>>, FontError> {
+        let mut flattener = Flattener::new(tolerance);
+        try!(self.for_each_point(glyph_id, |point| flattener.push(point)));
+        Ok(flattener.finish())
+    }
+
+    /// Returns the outline of the given glyph as SVG path data. See `Font::to_svg_path()`.
+    pub fn to_svg_path(&self, glyph_id: u16, options: &SvgPathOptions)
+                       -> Result<String, FontError> {
+        let mut writer = SvgPathWriter::new(options);
+        try!(self.for_each_point(glyph_id, |point| writer.push(point)));
+        Ok(writer.finish())
+    }
+
+    /// Returns the outline of the given glyph as a sequence of path segments.
+    ///
+    /// Each contour begins with `MoveTo` and ends with `ClosePath`. This is often more convenient
+    /// than `for_each_point()` for building paths, since there's no need to track the kinds of
+    /// points seen so far.
+    pub fn path_segments(&self, glyph_id: u16) -> Result<PathSegments, FontError> {
+        let mut segments = vec![];
+        let (mut start, mut control_points) = (None, vec![]);
+        try!(self.for_each_point(glyph_id, |point| {
+            match point.kind {
+                PointKind::FirstCubicControl | PointKind::SecondCubicControl => {
+                    control_points.push(point.position)
+                }
+                PointKind::QuadControl => {}
+                PointKind::OnCurve if point.index_in_contour == 0 => {
+                    close_contour(&mut segments, start);
+                    segments.push(PathSegment::MoveTo(point.position));
+                    start = Some(point.position)
+                }
+                PointKind::OnCurve => {
+                    segments.push(if control_points.len() == 2 {
+                        PathSegment::CurveTo(control_points[0], control_points[1], point.position)
+                    } else {
+                        PathSegment::LineTo(point.position)
+                    });
+                    control_points.clear()
+                }
+            }
+        }));
+        close_contour(&mut segments, start);
+        Ok(PathSegments::new(segments))
+    }
+
+    /// Calls the given callback with each contour of the given glyph in turn.
+    ///
+    /// The points are those that `for_each_point()` produces, so each contour ends with a point
+    /// back at its start, where the CharString closes it.
+    pub fn for_each_contour<F>(&self, glyph_id: u16, mut callback: F) -> Result<(), FontError>
+                               where F: FnMut(&Contour<Point>) {
+        let mut contour = Contour {
+            points: vec![],
+        };
+        try!(self.for_each_point(glyph_id, |point| {
+            if point.index_in_contour == 0 && !contour.points.is_empty() {
+                callback(&contour);
+                contour.points.clear()
+            }
+            contour.points.push(*point)
+        }));
+        if !contour.points.is_empty() {
+            callback(&contour)
+        }
+        Ok(())
+    }
+
+    /// Returns the number of contours in the given glyph.
+    pub fn num_contours(&self, glyph_id: u16) -> Result<usize, FontError> {
+        let mut num_contours = 0;
+        try!(self.for_each_point(glyph_id, |point| {
+            if point.index_in_contour == 0 {
+                num_contours += 1
+            }
+        }));
+        Ok(num_contours)
+    }
+
+    /// Like `for_each_point()`, but with the `blend` operators in a CFF2 glyph evaluated at the
+    /// given normalized variation coordinates.
+    ///
+    /// CFF fonts have no variations, so the coordinates have no effect on them.
+    pub fn for_each_point_with_variations<F>(&self,
+                                             glyph_id: u16,
+                                             coordinates: &[f32],
+                                             mut callback: F)
+                                             -> Result<(), FontError> where F: FnMut(&Point) {
+        try!(self.evaluate_glyph(glyph_id, coordinates, false, &mut callback));
+        Ok(())
+    }
+
+    /// Returns the advance width of the given glyph in font units.
+    ///
+    /// This is the width encoded in the glyph's CharString, relative to `nominalWidthX`, or
+    /// `defaultWidthX` if the CharString doesn't specify one.
+    pub fn advance_width(&self, glyph_id: u16) -> Result<f32, FontError> {
+        let private_dict = try!(self.private_dict_for_glyph(glyph_id));
+        let state = try!(self.evaluate_glyph(glyph_id, &[], false, &mut |_: &Point| {}));
+        match state.width {
+            Some(width) => Ok(private_dict.nominal_width_x + width as f32),
+            None => Ok(private_dict.default_width_x),
+        }
+    }
+
+    /// Returns the base and accent glyphs that the given glyph is built from if it's a
+    /// `seac`-style accented character, or nothing if it isn't one.
+    pub fn component_glyphs(&self, glyph_id: u16) -> Result<Vec<u16>, FontError> {
+        Ok(try!(self.dependencies(glyph_id)).glyphs)
+    }
+
+    /// Writes a CFF table containing only the given glyphs, so that `glyphs[i]` becomes glyph
+    /// `i`. The first glyph should be `.notdef`, glyph 0.
+    ///
+    /// Subroutines that none of the glyphs use are emptied, which leaves the numbers of the others
+    /// unchanged. Glyph names are kept, but the encoding reverts to Standard Encoding. Other fonts
+    /// in the FontSet are left out. CFF2 and CID-keyed fonts aren't supported.
+    pub fn subset(&self, glyphs: &[u16]) -> Result<Vec<u8>, FontError> {
+        if self.major_version != 1 || self.fd_select.is_some() {
+            return Err(FontError::UnsupportedSubsetFormat)
+        }
+
+        // Gather the CharStrings, the subroutines they use, and their names.
+        let mut used_local_subrs = vec![false; self.private_dict.local_subrs.count as usize];
+        let mut used_global_subrs = vec![false; self.global_subrs.count as usize];
+        let mut char_strings = Vec::with_capacity(glyphs.len());
+        let mut charset = vec![0];
+        for (index, &glyph_id) in glyphs.iter().enumerate() {
+            let dependencies = try!(self.dependencies(glyph_id));
+            for &subr_index in &dependencies.local_subrs {
+                used_local_subrs[subr_index as usize] = true
+            }
+            for &subr_index in &dependencies.global_subrs {
+                used_global_subrs[subr_index as usize] = true
+            }
+            char_strings.push(try!(self.char_string(glyph_id)));
+
+            // The charset leaves out `.notdef`.
+            if index > 0 {
+                match try!(self.charset.sid_for_glyph(glyph_id, self.glyph_count)) {
+                    Some(sid) => util::push_u16(&mut charset, sid),
+                    None => return Err(FontError::UnsupportedSubsetFormat),
+                }
+            }
+        }
+        let char_strings = write_index(&char_strings);
+        let global_subrs = try!(subset_subrs(&self.global_subrs, &used_global_subrs));
+        let local_subrs = try!(subset_subrs(&self.private_dict.local_subrs, &used_local_subrs));
+
+        // Only this font's name is kept. The string INDEX is copied as it is, so that SIDs stay
+        // the same.
+        let name_index = match try!(self.names.get(self.font_index)) {
+            Some(name) => write_index(&[name]),
+            None => return Err(FontError::CffTopDictNotFound),
+        };
+        let mut reader = try!(Reader::at(self.table.bytes, self.table.bytes[2] as usize)
+                                  .eof_in(TAG, "header"));
+        try!(skip_index(&mut reader, "name INDEX"));
+        try!(skip_index(&mut reader, "top DICT INDEX"));
+        let string_index = try!(index_bytes(&mut reader, "string INDEX"));
+
+        // The local subr INDEX follows the Private DICT.
+        let mut private_dict = vec![];
+        try!(copy_dict(self.private_dict.dict, &[OPERATOR_SUBRS], &mut private_dict));
+        if self.private_dict.local_subrs.count > 0 {
+            let subrs_offset = private_dict.len() + DICT_OFFSET_SIZE + 1;
+            push_dict_offset(&mut private_dict, subrs_offset);
+            private_dict.push(OPERATOR_SUBRS as u8)
+        }
+
+        // Lay out the top DICT, which points to everything after the global subr INDEX.
+        let mut top_dict_entries = vec![];
+        let rewritten_operators =
+            [OPERATOR_CHARSET, OPERATOR_ENCODING, OPERATOR_CHAR_STRINGS, OPERATOR_PRIVATE];
+        try!(copy_dict(self.top_dict, &rewritten_operators, &mut top_dict_entries));
+        let top_dict_index = |charset_offset, char_strings_offset, private_offset| {
+            let mut top_dict = top_dict_entries.clone();
+            push_dict_offset(&mut top_dict, charset_offset);
+            top_dict.push(OPERATOR_CHARSET as u8);
+            push_dict_offset(&mut top_dict, char_strings_offset);
+            top_dict.push(OPERATOR_CHAR_STRINGS as u8);
+            push_dict_offset(&mut top_dict, private_dict.len());
+            push_dict_offset(&mut top_dict, private_offset);
+            top_dict.push(OPERATOR_PRIVATE as u8);
+            write_index(&[&top_dict[..]])
+        };
+        let charset_offset = 4 + name_index.len() + top_dict_index(0, 0, 0).len() +
+            string_index.len() + global_subrs.len();
+        let char_strings_offset = charset_offset + charset.len();
+        let private_offset = char_strings_offset + char_strings.len();
+
+        // Offsets are 4 bytes.
+        let mut cff = vec![1, 0, 4, 4];
+        cff.extend_from_slice(&name_index);
+        cff.extend_from_slice(&top_dict_index(charset_offset, char_strings_offset, private_offset));
+        cff.extend_from_slice(string_index);
+        cff.extend_from_slice(&global_subrs);
+        cff.extend_from_slice(&charset);
+        cff.extend_from_slice(&char_strings);
+        cff.extend_from_slice(&private_dict);
+        cff.extend_from_slice(&local_subrs);
+        Ok(cff)
+    }
+
+    // Evaluates the CharString of the given glyph to find the subroutines and `seac` components
+    // that it uses. Unsupported operators are skipped, as they can't call anything.
+    fn dependencies(&self, glyph_id: u16) -> Result<Dependencies, FontError> {
+        let char_string = try!(self.char_string(glyph_id));
+        let private_dict = try!(self.private_dict_for_glyph(glyph_id));
+        let mut state = if self.major_version == 2 {
+            EvaluationState::new_cff2(private_dict.vsindex, &[])
+        } else {
+            EvaluationState::new()
+        };
+        state.skipped_operators = Some(vec![]);
+        state.dependencies = Some(Dependencies::default());
+        try!(self.evaluate_char_string(Reader::new(char_string),
+                                       &private_dict,
+                                       &mut state,
+                                       0,
+                                       &mut |_: &Point| {}));
+        Ok(state.dependencies.take().unwrap_or_else(Dependencies::default))
+    }
+
+    // Evaluates the CharString of the given glyph at the given variation coordinates, calling the
+    // callback for each point, and returns the final evaluation state. If `lenient` is true,
+    // unsupported operators are skipped and recorded in the state.
+    fn evaluate_glyph<F>(&self,
+                         glyph_id: u16,
+                         coordinates: &[f32],
+                         lenient: bool,
+                         callback: &mut F)
+                         -> Result<EvaluationState, FontError> where F: FnMut(&Point) {
+        let char_string = try!(self.char_string(glyph_id));
+        let private_dict = try!(self.private_dict_for_glyph(glyph_id));
+        let mut state = if self.major_version == 2 {
+            EvaluationState::new_cff2(private_dict.vsindex, coordinates)
+        } else {
+            EvaluationState::new()
+        };
+        if lenient {
+            state.skipped_operators = Some(vec![])
+        }
+        try!(self.evaluate_char_string(Reader::new(char_string),
+                                       &private_dict,
+                                       &mut state,
+                                       0,
+                                       callback));
+
+        close_path_if_necessary(&state.start, state.index_in_contour, &mut *callback);
+        Ok(state)
+    }
+
+    /// Returns the CharString for the given glyph.
+    ///
+    /// The CharStrings INDEX was read up front, so this takes constant time.
+    pub fn char_string(&self, glyph_id: u16) -> Result<&'a [u8], FontError> {
+        match try!(self.char_strings.get(glyph_id)) {
+            Some(char_string) => Ok(char_string),
+            None => Err(FontError::UnexpectedEof),
+        }
+    }
+
+    // Returns the Private DICT that applies to the given glyph.
+    fn private_dict_for_glyph(&self, glyph_id: u16) -> Result<PrivateDict<'a>, FontError> {
+        let fd_select = match self.fd_select {
+            None => return Ok(self.private_dict),
+            Some(ref fd_select) => fd_select,
+        };
+
+        let font_dict_index = try!(fd_select.font_dict_index(glyph_id));
+        match try!(self.fd_array.get(font_dict_index as u16)) {
+            Some(font_dict) => PrivateDict::new(self.table.bytes, font_dict, self.major_version),
+            None => Err(FontError::CffFontDictNotFound),
+        }
+    }
+
+    // Evaluates a CharString or a subroutine. Returns true if `endchar` was reached, in which case
+    // evaluation of the glyph is finished.
+    fn evaluate_char_string<F>(&self,
+                               mut reader: Reader,
+                               private_dict: &PrivateDict<'a>,
+                               state: &mut EvaluationState,
+                               depth: u8,
+                               callback: &mut F)
+                               -> Result<bool, FontError> where F: FnMut(&Point) {
         while let Ok(b0) = reader.read_u8() {
+            let operand_count = state.stack.size;
+
+            // Stem hints can only come before everything else, though subroutines may supply
+            // them and CFF2 may blend them.
+            let in_stem_hints = state.in_stem_hints;
+            match b0 {
+                1 | 3 | 10 | 11 | 15 | 16 | 18 | 23 | 28 | 29 | 32...255 => {}
+                _ => state.in_stem_hints = false,
+            }
+
             match b0 {
-                32...246 => try!(stack.push(b0 as i32 - 139)),
+                32...246 => try!(state.stack.push(b0 as i32 - 139)),
                 247...250 => {
-                    let b1 = try!(reader.read_u8().map_err(FontError::eof));
-                    try!(stack.push((b0 as i32 - 247) * 256 + b1 as i32 + 108))
+                    let b1 = try!(reader.read_u8());
+                    try!(state.stack.push((b0 as i32 - 247) * 256 + b1 as i32 + 108))
                 }
                 251...254 => {
-                    let b1 = try!(reader.read_u8().map_err(FontError::eof));
-                    try!(stack.push((b0 as i32 - 251) * -256 - b1 as i32 - 108))
+                    let b1 = try!(reader.read_u8());
+                    try!(state.stack.push((b0 as i32 - 251) * -256 - b1 as i32 - 108))
                 }
                 255 => {
-                    // FIXME(pcwalton): Don't truncate the lower 16 bits.
-                    try!(stack.push(try!(reader.read_i32::<BigEndian>().map_err(FontError::eof)) >>
-                         16))
+                    // This is a 16.16 fixed-point number. Our points are integral, so round it
+                    // to the nearest integer. This loses precision for fonts that accumulate
+                    // fractional deltas, but it's much closer than discarding the fraction.
+                    let number = try!(reader.read_i32_be());
+                    try!(state.stack.push(((number as i64 + 0x8000) >> 16) as i32))
                 }
                 28 => {
-                    let number = try!(reader.read_i16::<BigEndian>().map_err(FontError::eof)) as i32;
-                    try!(stack.push(number))
+                    let number = try!(reader.read_i16_be()) as i32;
+                    try!(state.stack.push(number))
                 }
 
                 4 => {
                     // |- dy1 vmoveto
-                    close_path_if_necessary(&start, index_in_contour, &mut callback);
-                    pos.y += stack.array[0] as i16;
+                    state.parse_width(operand_count > 1);
+                    try!(state.stack.check_operand_count(1, 0));
+                    close_path_if_necessary(&state.start, state.index_in_contour, &mut *callback);
+                    state.pos.y = state.pos.y.wrapping_add(state.stack.array[0] as i16);
                     callback(&Point {
-                        position: pos,
+                        position: state.pos,
                         index_in_contour: 0,
                         kind: PointKind::OnCurve,
                     });
-                    start = pos;
-                    index_in_contour = 1;
-                    stack.clear()
+                    state.start = state.pos;
+                    state.index_in_contour = 1;
+                    state.stack.clear()
                 }
                 5 => {
                     // |- {dxa dya}+ rlineto
-                    for points in stack.array[0..stack.size as usize].chunks(2) {
-                        pos = pos + Point2D::new(points[0] as i16, points[1] as i16);
+                    try!(state.stack.check_operand_count(2, 2));
+                    for points in state.stack.array[0..state.stack.size as usize].chunks(2) {
+                        state.pos = translate(state.pos, points[0] as i16, points[1] as i16);
                         callback(&Point {
-                            position: pos,
-                            index_in_contour: index_in_contour,
+                            position: state.pos,
+                            index_in_contour: state.index_in_contour,
                             kind: PointKind::OnCurve,
                         });
-                        index_in_contour += 1
+                        state.index_in_contour = state.index_in_contour.wrapping_add(1)
                     }
-                    stack.clear()
+                    state.stack.clear()
                 }
                 6 => {
                     // |- dx1 {dya dxb}* hlineto
                     // |- {dxa dyb}* hlineto
-                    for (i, length) in stack.array[0..stack.size as usize].iter().enumerate() {
+                    try!(state.stack.check_operand_count(1, 1));
+                    for (i, length) in state.stack.array[0..state.stack.size as usize]
+                                            .iter()
+                                            .enumerate() {
                         if i % 2 == 0 {
-                            pos.x += *length as i16
+                            state.pos.x = state.pos.x.wrapping_add(*length as i16)
                         } else {
-                            pos.y += *length as i16
+                            state.pos.y = state.pos.y.wrapping_add(*length as i16)
                         }
                         callback(&Point {
-                            position: pos,
-                            index_in_contour: index_in_contour,
+                            position: state.pos,
+                            index_in_contour: state.index_in_contour,
                             kind: PointKind::OnCurve,
                         });
-                        index_in_contour += 1
+                        state.index_in_contour = state.index_in_contour.wrapping_add(1)
                     }
-                    stack.clear()
+                    state.stack.clear()
                 }
                 7 => {
                     // |- dy1 {dxa dyb}* vlineto
                     // |- {dya dxb}* vlineto
-                    for (i, length) in stack.array[0..stack.size as usize].iter().enumerate() {
+                    try!(state.stack.check_operand_count(1, 1));
+                    for (i, length) in state.stack.array[0..state.stack.size as usize]
+                                            .iter()
+                                            .enumerate() {
                         if i % 2 == 0 {
-                            pos.y += *length as i16
+                            state.pos.y = state.pos.y.wrapping_add(*length as i16)
                         } else {
-                            pos.x += *length as i16
+                            state.pos.x = state.pos.x.wrapping_add(*length as i16)
                         }
                         callback(&Point {
-                            position: pos,
-                            index_in_contour: index_in_contour,
+                            position: state.pos,
+                            index_in_contour: state.index_in_contour,
                             kind: PointKind::OnCurve,
                         });
-                        index_in_contour += 1
+                        state.index_in_contour = state.index_in_contour.wrapping_add(1)
                     }
-                    stack.clear()
+                    state.stack.clear()
                 }
                 8 => {
                     // |- {dxa dya dxb dyb dxc dyc}+ rrcurveto (8)
-                    for chunk in stack.array[0..stack.size as usize].chunks(6) {
+                    try!(state.stack.check_operand_count(6, 6));
+                    for chunk in state.stack.array[0..state.stack.size as usize].chunks(6) {
                         add_curve(chunk[0] as i16, chunk[1] as i16,
                                   chunk[2] as i16, chunk[3] as i16,
                                   chunk[4] as i16, chunk[5] as i16,
-                                  &mut pos,
-                                  &mut index_in_contour,
-                                  &mut callback)
+                                  &mut state.pos,
+                                  &mut state.index_in_contour,
+                                  &mut *callback)
                     }
-                    stack.clear()
+                    state.stack.clear()
                 }
                 24 => {
                     // |- {dxa dya dxb dyb dxc dyc}+ dxd dyd rcurveline (24)
-                    for chunk in stack.array[0..stack.size as usize - 2].chunks(6) {
+                    try!(state.stack.check_operand_count(8, 6));
+                    let size = state.stack.size as usize;
+                    for chunk in state.stack.array[0..size - 2].chunks(6) {
                         add_curve(chunk[0] as i16, chunk[1] as i16,
                                   chunk[2] as i16, chunk[3] as i16,
                                   chunk[4] as i16, chunk[5] as i16,
-                                  &mut pos,
-                                  &mut index_in_contour,
-                                  &mut callback)
+                                  &mut state.pos,
+                                  &mut state.index_in_contour,
+                                  &mut *callback)
                     }
-                    pos = pos + Point2D::new(stack.array[stack.size as usize - 2] as i16,
-                                             stack.array[stack.size as usize - 1] as i16);
+                    state.pos = translate(state.pos,
+                                          state.stack.array[size - 2] as i16,
+                                          state.stack.array[size - 1] as i16);
                     callback(&Point {
-                        position: pos,
-                        index_in_contour: index_in_contour,
+                        position: state.pos,
+                        index_in_contour: state.index_in_contour,
                         kind: PointKind::OnCurve,
                     });
-                    index_in_contour += 1;
-                    stack.clear()
+                    state.index_in_contour = state.index_in_contour.wrapping_add(1);
+                    state.stack.clear()
                 }
                 25 => {
                     // |- {dxa dya}+ dxb dyb dxc dyc dxd dyd rlinecurve (25)
-                    for chunk in stack.array[0..stack.size as usize - 6].chunks(2) {
-                        pos = pos + Point2D::new(chunk[0] as i16, chunk[1] as i16);
+                    try!(state.stack.check_operand_count(8, 2));
+                    let size = state.stack.size as usize;
+                    for chunk in state.stack.array[0..size - 6].chunks(2) {
+                        state.pos = translate(state.pos, chunk[0] as i16, chunk[1] as i16);
                         callback(&Point {
-                            position: pos,
-                            index_in_contour: index_in_contour,
+                            position: state.pos,
+                            index_in_contour: state.index_in_contour,
                             kind: PointKind::OnCurve,
                         });
-                        index_in_contour += 1;
-                    }
-                    add_curve(stack.array[stack.size as usize - 6] as i16,
-                              stack.array[stack.size as usize - 5] as i16,
-                              stack.array[stack.size as usize - 4] as i16,
-                              stack.array[stack.size as usize - 3] as i16,
-                              stack.array[stack.size as usize - 2] as i16,
-                              stack.array[stack.size as usize - 1] as i16,
-                              &mut pos,
-                              &mut index_in_contour,
-                              &mut callback);
-                    stack.clear()
+                        state.index_in_contour = state.index_in_contour.wrapping_add(1);
+                    }
+                    add_curve(state.stack.array[size - 6] as i16,
+                              state.stack.array[size - 5] as i16,
+                              state.stack.array[size - 4] as i16,
+                              state.stack.array[size - 3] as i16,
+                              state.stack.array[size - 2] as i16,
+                              state.stack.array[size - 1] as i16,
+                              &mut state.pos,
+                              &mut state.index_in_contour,
+                              &mut *callback);
+                    state.stack.clear()
                 }
                 30 => {
                     // |- dy1 dx2 dy2 dx3 {dxa dxb dyb dyc dyd dxe dye dxf}* dyf? vhcurveto (30)
                     // |- {dya dxb dyb dxc dxd dxe dye dyf}+ dxf? vhcurveto (30)
-                    for (i, chunk) in stack.array[0..stack.size as usize].chunks(4).enumerate() {
+                    try!(state.stack.check_operand_count(4, 1));
+                    let size = state.stack.size as usize;
+                    for (i, chunk) in state.stack.array[0..size].chunks(4).enumerate() {
                         if chunk.len() != 4 {
                             break
                         }
 
-                        let dxyf = if i * 4 + 5 == stack.size as usize {
-                            stack.array[stack.size as usize - 1]
+                        let dxyf = if i * 4 + 5 == size {
+                            state.stack.array[size - 1]
                         } else {
                             0
                         };
@@ -243,30 +1064,32 @@ impl<'a> CffTable<'a> {
                             add_curve(0, chunk[0] as i16,
                                       chunk[1] as i16, chunk[2] as i16,
                                       chunk[3] as i16, dxyf as i16,
-                                      &mut pos,
-                                      &mut index_in_contour,
-                                      &mut callback)
+                                      &mut state.pos,
+                                      &mut state.index_in_contour,
+                                      &mut *callback)
                         } else {
                             add_curve(chunk[0] as i16, 0,
                                       chunk[1] as i16, chunk[2] as i16,
                                       dxyf as i16, chunk[3] as i16,
-                                      &mut pos,
-                                      &mut index_in_contour,
-                                      &mut callback)
+                                      &mut state.pos,
+                                      &mut state.index_in_contour,
+                                      &mut *callback)
                         }
                     }
-                    stack.clear()
+                    state.stack.clear()
                 }
                 31 => {
                     // |- dx1 dx2 dy2 dy3 {dya dxb dyb dxc dxd dxe dye dyf}* dxf? hvcurveto (31)
                     // |- {dxa dxb dyb dyc dyd dxe dye dxf}+ dyf? hvcurveto (31)
-                    for (i, chunk) in stack.array[0..stack.size as usize].chunks(4).enumerate() {
+                    try!(state.stack.check_operand_count(4, 1));
+                    let size = state.stack.size as usize;
+                    for (i, chunk) in state.stack.array[0..size].chunks(4).enumerate() {
                         if chunk.len() != 4 {
                             break
                         }
 
-                        let dxyf = if i * 4 + 5 == stack.size as usize {
-                            stack.array[stack.size as usize - 1]
+                        let dxyf = if i * 4 + 5 == size {
+                            state.stack.array[size - 1]
                         } else {
                             0
                         };
@@ -275,138 +1098,412 @@ impl<'a> CffTable<'a> {
                             add_curve(chunk[0] as i16, 0,
                                       chunk[1] as i16, chunk[2] as i16,
                                       dxyf as i16, chunk[3] as i16,
-                                      &mut pos,
-                                      &mut index_in_contour,
-                                      &mut callback)
+                                      &mut state.pos,
+                                      &mut state.index_in_contour,
+                                      &mut *callback)
                         } else {
                             add_curve(0, chunk[0] as i16,
                                       chunk[1] as i16, chunk[2] as i16,
                                       chunk[3] as i16, dxyf as i16,
-                                      &mut pos,
-                                      &mut index_in_contour,
-                                      &mut callback)
+                                      &mut state.pos,
+                                      &mut state.index_in_contour,
+                                      &mut *callback)
                         }
                     }
-                    stack.clear()
+                    state.stack.clear()
                 }
                 26 => {
                     // |- dx1? {dya dxb dyb dyc}+ vvcurveto (26)
-                    let start;
-                    if stack.size % 2 == 0 {
-                        start = 0
-                    } else {
-                        pos.x += stack.array[0] as i16;
-                        start = 1
+                    let start = state.stack.size as usize % 2;
+                    try!(state.stack.check_operand_count(start + 4, 4));
+                    if start == 1 {
+                        state.pos.x = state.pos.x.wrapping_add(state.stack.array[0] as i16)
                     }
 
-                    for chunk in stack.array[start..stack.size as usize].chunks(4) {
+                    for chunk in state.stack.array[start..state.stack.size as usize].chunks(4) {
                         add_curve(0, chunk[0] as i16,
                                   chunk[1] as i16, chunk[2] as i16,
                                   0, chunk[3] as i16,
-                                  &mut pos,
-                                  &mut index_in_contour,
-                                  &mut callback)
+                                  &mut state.pos,
+                                  &mut state.index_in_contour,
+                                  &mut *callback)
                     }
-                    stack.clear()
+                    state.stack.clear()
                 }
                 27 => {
                     // |- dy1? {dxa dxb dyb dxc}+ hhcurveto (27)
-                    let start;
-                    if stack.size % 2 == 0 {
-                        start = 0
-                    } else {
-                        pos.y += stack.array[0] as i16;
-                        start = 1
+                    let start = state.stack.size as usize % 2;
+                    try!(state.stack.check_operand_count(start + 4, 4));
+                    if start == 1 {
+                        state.pos.y = state.pos.y.wrapping_add(state.stack.array[0] as i16)
                     }
 
-                    for chunk in stack.array[start..stack.size as usize].chunks(4) {
+                    for chunk in state.stack.array[start..state.stack.size as usize].chunks(4) {
                         add_curve(chunk[0] as i16, 0,
                                   chunk[1] as i16, chunk[2] as i16,
                                   chunk[3] as i16, 0,
-                                  &mut pos,
-                                  &mut index_in_contour,
-                                  &mut callback)
+                                  &mut state.pos,
+                                  &mut state.index_in_contour,
+                                  &mut *callback)
+                    }
+                    state.stack.clear()
+                }
+                10 | 29 => {
+                    // subr# callsubr (10)
+                    // globalsubr# callgsubr (29)
+                    if depth >= MAX_SUBR_NESTING {
+                        return Err(FontError::CffSubrNestingTooDeep)
+                    }
+
+                    let subrs = if b0 == 10 {
+                        &private_dict.local_subrs
+                    } else {
+                        &self.global_subrs
+                    };
+
+                    let subr_index = match state.stack.pop() {
+                        Some(subr_number) => subr_number + subrs.bias(),
+                        None => return Err(FontError::CffSubrNotFound),
+                    };
+                    if subr_index < 0 || subr_index > u16::MAX as i32 {
+                        return Err(FontError::CffSubrNotFound)
+                    }
+                    if let Some(ref mut dependencies) = state.dependencies {
+                        if b0 == 10 {
+                            dependencies.local_subrs.push(subr_index as u16)
+                        } else {
+                            dependencies.global_subrs.push(subr_index as u16)
+                        }
+                    }
+
+                    let subr = match try!(subrs.get(subr_index as u16)) {
+                        Some(subr) => subr,
+                        None => return Err(FontError::CffSubrNotFound),
+                    };
+
+                    if try!(self.evaluate_char_string(Reader::new(subr),
+                                                      private_dict,
+                                                      state,
+                                                      depth + 1,
+                                                      callback)) {
+                        return Ok(true)
                     }
-                    stack.clear()
+                }
+                11 => {
+                    // return
+                    return Ok(false)
+                }
+                15 if self.major_version == 2 => {
+                    // ivs vsindex (CFF2 only)
+                    match state.stack.pop() {
+                        Some(vsindex) if vsindex >= 0 && vsindex <= u16::MAX as i32 => {
+                            state.vsindex = vsindex as u16;
+                            state.scalars = None
+                        }
+                        _ => return Err(FontError::CffMalformedCharString),
+                    }
+                    state.stack.clear()
+                }
+                16 if self.major_version == 2 => {
+                    // num(0)..num(n-1) delta(0,0)..delta(k-1,n-1) n blend (CFF2 only)
+                    try!(self.blend(state))
                 }
                 14 => {
                     // endchar
-                    break
+                    //
+                    // With four operands, this is the deprecated `seac` accented character
+                    // construction: |- adx ady bchar achar endchar
+                    state.parse_width(operand_count == 1 || operand_count == 5);
+                    if state.stack.size == 4 {
+                        let mut args = [0; 4];
+                        args.copy_from_slice(&state.stack.array[..4]);
+                        state.stack.clear();
+                        try!(self.evaluate_seac(args[0], args[1],
+                                                args[2], args[3],
+                                                state,
+                                                callback))
+                    }
+                    return Ok(true)
                 }
                 1 | 18 => {
                     // hstem hint (ignored)
-                    hint_count += stack.size as u16 / 2;
-                    stack.clear()
+                    state.parse_width(operand_count % 2 == 1);
+                    state.hint_count = state.hint_count.saturating_add(state.stack.size / 2);
+                    state.stack.clear()
                 }
                 3 | 23 => {
                     // vstem hint (ignored)
-                    hint_count += stack.size as u16 / 2;
-                    stack.clear()
+                    state.parse_width(operand_count % 2 == 1);
+                    state.hint_count = state.hint_count.saturating_add(state.stack.size / 2);
+                    state.stack.clear()
                 }
                 19 => {
                     // hintmask (ignored)
                     //
-                    // First, process an implicit vstem hint.
-                    //
-                    // FIXME(pcwalton): Should only do that if we're in the header.
-                    hint_count += stack.size as u16 / 2;
-                    stack.clear();
+                    // First, process an implicit vstem hint. Only the first `hintmask` can have
+                    // one, right after the other stem hints.
+                    state.parse_width(operand_count % 2 == 1);
+                    if in_stem_hints {
+                        state.hint_count = state.hint_count.saturating_add(state.stack.size / 2);
+                    }
+                    state.stack.clear();
 
                     // Now skip ⌈hint_count / 8⌉ bytes.
-                    let hint_byte_count = (hint_count as usize + 7) / 8;
-                    try!(reader.jump(hint_byte_count).map_err(FontError::eof));
+                    let hint_byte_count = (state.hint_count as usize + 7) / 8;
+                    try!(reader.jump(hint_byte_count));
                 }
                 20 => {
-                    // Skip ⌈hint_count / 8⌉ bytes.
-                    stack.clear();
-                    let hint_byte_count = (hint_count as usize + 7) / 8;
-                    try!(reader.jump(hint_byte_count).map_err(FontError::eof));
+                    // cntrmask (ignored)
+                    //
+                    // Unlike `hintmask`, this never takes an implicit vstem, so it leaves the
+                    // hint count alone and discards any operands. Its mask has the same size.
+                    state.stack.clear();
+                    let hint_byte_count = (state.hint_count as usize + 7) / 8;
+                    try!(reader.jump(hint_byte_count));
                 }
                 21 => {
                     // |- dx1 dy1 rmoveto
-                    close_path_if_necessary(&start, index_in_contour, &mut callback);
-                    pos = pos + Point2D::new(stack.array[0] as i16, stack.array[1] as i16);
+                    state.parse_width(operand_count > 2);
+                    try!(state.stack.check_operand_count(2, 0));
+                    close_path_if_necessary(&state.start, state.index_in_contour, &mut *callback);
+                    state.pos = translate(state.pos,
+                                          state.stack.array[0] as i16,
+                                          state.stack.array[1] as i16);
                     callback(&Point {
-                        position: pos,
+                        position: state.pos,
                         index_in_contour: 0,
                         kind: PointKind::OnCurve,
                     });
-                    start = pos;
-                    index_in_contour = 1;
-                    stack.clear()
+                    state.start = state.pos;
+                    state.index_in_contour = 1;
+                    state.stack.clear()
                 }
                 22 => {
                     // |- dx1 hmoveto
-                    close_path_if_necessary(&start, index_in_contour, &mut callback);
-                    pos.x += stack.array[0] as i16;
+                    state.parse_width(operand_count > 1);
+                    try!(state.stack.check_operand_count(1, 0));
+                    close_path_if_necessary(&state.start, state.index_in_contour, &mut *callback);
+                    state.pos.x = state.pos.x.wrapping_add(state.stack.array[0] as i16);
                     callback(&Point {
-                        position: pos,
+                        position: state.pos,
                         index_in_contour: 0,
                         kind: PointKind::OnCurve,
                     });
-                    start = pos;
-                    index_in_contour = 1;
-                    stack.clear()
+                    state.start = state.pos;
+                    state.index_in_contour = 1;
+                    state.stack.clear()
                 }
 
                 12 => {
-                    // TODO(pcwalton): Support these extended operators.
-                    let _operator = (12 << 8) |
-                        (try!(reader.read_u8().map_err(FontError::eof)) as u32);
-                    stack.clear();
-                    return Err(FontError::CffUnimplementedOperator)
+                    let b1 = try!(reader.read_u8());
+                    if try!(state.evaluate_arithmetic_operator(b1)) {
+                        continue
+                    }
+
+                    // The flex operators take at most 13 operands, so copy only those rather than
+                    // the whole stack.
+                    let mut args = [0; 13];
+                    let arg_count = cmp::min(state.stack.size as usize, args.len());
+                    args[..arg_count].copy_from_slice(&state.stack.array[..arg_count]);
+                    match b1 {
+                        0 => {
+                            // - dotsection (12 0) -
+                            //
+                            // This deprecated Type 1 hint has no effect on the outline.
+                        }
+                        34 => {
+                            // |- dx1 dx2 dy2 dx3 dx4 dx5 dx6 hflex (12 34)
+                            try!(state.stack.check_operand_count(7, 0));
+                            add_curve(args[0] as i16, 0,
+                                      args[1] as i16, args[2] as i16,
+                                      args[3] as i16, 0,
+                                      &mut state.pos,
+                                      &mut state.index_in_contour,
+                                      &mut *callback);
+                            add_curve(args[4] as i16, 0,
+                                      args[5] as i16, -args[2] as i16,
+                                      args[6] as i16, 0,
+                                      &mut state.pos,
+                                      &mut state.index_in_contour,
+                                      &mut *callback)
+                        }
+                        35 => {
+                            // |- dx1 dy1 dx2 dy2 dx3 dy3 dx4 dy4 dx5 dy5 dx6 dy6 fd flex (12 35)
+                            //
+                            // We always render flex hints as curves, so `fd` is ignored.
+                            try!(state.stack.check_operand_count(13, 0));
+                            for chunk in args[0..12].chunks(6) {
+                                add_curve(chunk[0] as i16, chunk[1] as i16,
+                                          chunk[2] as i16, chunk[3] as i16,
+                                          chunk[4] as i16, chunk[5] as i16,
+                                          &mut state.pos,
+                                          &mut state.index_in_contour,
+                                          &mut *callback)
+                            }
+                        }
+                        36 => {
+                            // |- dx1 dy1 dx2 dy2 dx3 dx4 dx5 dy5 dx6 hflex1 (12 36)
+                            try!(state.stack.check_operand_count(9, 0));
+                            add_curve(args[0] as i16, args[1] as i16,
+                                      args[2] as i16, args[3] as i16,
+                                      args[4] as i16, 0,
+                                      &mut state.pos,
+                                      &mut state.index_in_contour,
+                                      &mut *callback);
+                            add_curve(args[5] as i16, 0,
+                                      args[6] as i16, args[7] as i16,
+                                      args[8] as i16, -(args[1] + args[3] + args[7]) as i16,
+                                      &mut state.pos,
+                                      &mut state.index_in_contour,
+                                      &mut *callback)
+                        }
+                        37 => {
+                            // |- dx1 dy1 dx2 dy2 dx3 dy3 dx4 dy4 dx5 dy5 d6 flex1 (12 37)
+                            //
+                            // The last point returns to the starting point's coordinate along
+                            // whichever axis the curve moved less in.
+                            try!(state.stack.check_operand_count(11, 0));
+                            let (mut dx, mut dy) = (0, 0);
+                            for delta in args[0..10].chunks(2) {
+                                dx += delta[0];
+                                dy += delta[1];
+                            }
+                            let (dx6, dy6) = if dx.abs() > dy.abs() {
+                                (args[10], -dy)
+                            } else {
+                                (-dx, args[10])
+                            };
+
+                            add_curve(args[0] as i16, args[1] as i16,
+                                      args[2] as i16, args[3] as i16,
+                                      args[4] as i16, args[5] as i16,
+                                      &mut state.pos,
+                                      &mut state.index_in_contour,
+                                      &mut *callback);
+                            add_curve(args[6] as i16, args[7] as i16,
+                                      args[8] as i16, args[9] as i16,
+                                      dx6 as i16, dy6 as i16,
+                                      &mut state.pos,
+                                      &mut state.index_in_contour,
+                                      &mut *callback)
+                        }
+                        _ => {
+                            // TODO(pcwalton): Support the remaining extended operators.
+                            try!(state.skip_operator(((b1 as u16) << 8) | 12))
+                        }
+                    }
+                    state.stack.clear()
                 }
-                _ => {
-                    stack.clear();
-                    return Err(FontError::CffUnimplementedOperator)
+                _ => try!(state.skip_operator(b0 as u16)),
+            }
+        }
+
+        Ok(false)
+    }
+
+    // Replaces the `n` default values and `n * k` deltas of a `blend` operator on the stack with
+    // the `n` blended values, where `k` is the number of regions in the current variation data.
+    fn blend(&self, state: &mut EvaluationState) -> Result<(), FontError> {
+        let scalars = match state.scalars.take() {
+            Some(scalars) => scalars,
+            None => {
+                match self.variation_store {
+                    Some(ref variation_store) => {
+                        try!(variation_store.region_scalars(state.vsindex, &state.coordinates))
+                    }
+                    None => return Err(FontError::CffMalformedCharString),
                 }
             }
+        };
+
+        let value_count = match state.stack.pop() {
+            Some(value_count) if value_count >= 0 => value_count as usize,
+            _ => return Err(FontError::CffMalformedCharString),
+        };
+        let region_count = scalars.len();
+        let size = state.stack.size as usize;
+        let operand_count = match value_count.checked_mul(region_count + 1) {
+            Some(operand_count) if operand_count <= size => operand_count,
+            _ => return Err(FontError::CffMalformedCharString),
+        };
+
+        let first = size - operand_count;
+        for i in 0..value_count {
+            let mut value = state.stack.array[first + i] as f32;
+            let deltas_start = first + value_count + i * region_count;
+            for (j, &scalar) in scalars.iter().enumerate() {
+                value += state.stack.array[deltas_start + j] as f32 * scalar
+            }
+            state.stack.array[first + i] = round_operand(value as f64)
         }
+        state.stack.size = (first + value_count) as u16;
 
-        close_path_if_necessary(&start, index_in_contour, &mut callback);
+        state.scalars = Some(scalars);
         Ok(())
     }
 
+    // Draws the base and accent characters with the given Standard Encoding codes, offsetting the
+    // accent by (adx, ady).
+    fn evaluate_seac<F>(&self,
+                        adx: i32,
+                        ady: i32,
+                        bchar: i32,
+                        achar: i32,
+                        state: &mut EvaluationState,
+                        callback: &mut F)
+                        -> Result<(), FontError> where F: FnMut(&Point) {
+        // The components can't themselves be accented characters.
+        if state.in_seac {
+            return Err(FontError::CffBadSeacComponent)
+        }
+
+        close_path_if_necessary(&state.start, state.index_in_contour, &mut *callback);
+        state.index_in_contour = 0;
+
+        for &(code, origin) in &[(bchar, Point2D::new(0, 0)),
+                                 (achar, Point2D::new(adx as i16, ady as i16))] {
+            let glyph_id = try!(self.glyph_id_for_standard_code(code));
+            if let Some(ref mut dependencies) = state.dependencies {
+                dependencies.glyphs.push(glyph_id)
+            }
+            let char_string = try!(self.char_string(glyph_id));
+            let private_dict = try!(self.private_dict_for_glyph(glyph_id));
+
+            let mut component_state = EvaluationState::new();
+            component_state.pos = origin;
+            component_state.in_seac = true;
+            component_state.skipped_operators = state.skipped_operators.take();
+            try!(self.evaluate_char_string(Reader::new(char_string),
+                                           &private_dict,
+                                           &mut component_state,
+                                           0,
+                                           callback));
+            state.skipped_operators = component_state.skipped_operators.take();
+            close_path_if_necessary(&component_state.start,
+                                    component_state.index_in_contour,
+                                    &mut *callback)
+        }
+
+        Ok(())
+    }
+
+    // Returns the glyph for the given Standard Encoding character code.
+    fn glyph_id_for_standard_code(&self, code: i32) -> Result<u16, FontError> {
+        let sid = match code {
+            0...255 => cff_encoding::standard_encoding_sid(code as u8),
+            _ => None,
+        };
+        let sid = match sid {
+            Some(sid) => sid,
+            None => return Err(FontError::CffBadSeacComponent),
+        };
+
+        match try!(self.charset.glyph_id_for_sid(sid, self.glyph_count)) {
+            Some(glyph_id) => Ok(glyph_id),
+            None => Err(FontError::CffBadSeacComponent),
+        }
+    }
+
     // TODO(pcwalton): Do some caching, perhaps?
     // TODO(pcwalton): Compute this at the same time as `for_each_point`, perhaps?
     pub fn glyph_bounds(&self, glyph_id: u16) -> Result<GlyphBounds, FontError> {
@@ -419,121 +1516,1118 @@ impl<'a> CffTable<'a> {
         }));
         Ok(bounds)
     }
+
+    /// Returns the exact bounding box of the glyph's outline.
+    ///
+    /// Unlike `glyph_bounds()`, which includes every control point, this includes only the
+    /// on-curve points and the extrema of each curve. It's slower, but never overestimates.
+    pub fn glyph_bounds_tight(&self, glyph_id: u16) -> Result<GlyphBounds, FontError> {
+        let (mut min, mut max) = (Point2D::new(f32::INFINITY, f32::INFINITY),
+                                  Point2D::new(f32::NEG_INFINITY, f32::NEG_INFINITY));
+        {
+            let mut include = |point: Point2D<f32>| {
+                min = Point2D::new(min.x.min(point.x), min.y.min(point.y));
+                max = Point2D::new(max.x.max(point.x), max.y.max(point.y));
+            };
+
+            let mut last_on_curve = Point2D::new(0.0, 0.0);
+            let mut control_points = [Point2D::new(0.0, 0.0); 2];
+            let mut last_kind = PointKind::OnCurve;
+            try!(self.for_each_point(glyph_id, |point| {
+                let position = Point2D::new(point.position.x as f32, point.position.y as f32);
+                match point.kind {
+                    PointKind::FirstCubicControl => control_points[0] = position,
+                    PointKind::SecondCubicControl => control_points[1] = position,
+                    PointKind::QuadControl => {}
+                    PointKind::OnCurve => {
+                        if last_kind == PointKind::SecondCubicControl {
+                            let xs = [last_on_curve.x, control_points[0].x, control_points[1].x,
+                                      position.x];
+                            let ys = [last_on_curve.y, control_points[0].y, control_points[1].y,
+                                      position.y];
+                            for &t in cubic_extrema(&xs).iter().chain(cubic_extrema(&ys).iter()) {
+                                if let Some(t) = t {
+                                    include(Point2D::new(evaluate_cubic(&xs, t),
+                                                         evaluate_cubic(&ys, t)))
+                                }
+                            }
+                        }
+                        include(position);
+                        last_on_curve = position
+                    }
+                }
+                last_kind = point.kind
+            }));
+        }
+
+        if min.x > max.x {
+            return Ok(GlyphBounds::default())
+        }
+        Ok(GlyphBounds {
+            left: min.x.floor() as i32,
+            bottom: min.y.floor() as i32,
+            right: max.x.ceil() as i32,
+            top: max.y.ceil() as i32,
+        })
+    }
 }
 
-// Moves the reader to the location of the given element in the index. Returns the length of the
-// element if the element was found or `None` otherwise.
-fn find_in_index(reader: &mut &[u8], index: u16) -> Result<Option<u32>, FontError> {
-    let count = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
-    if count == 0 {
-        return Ok(None)
+// Returns the parameters in (0, 1) at which the derivative of the given one-dimensional cubic
+// Bézier curve vanishes.
+fn cubic_extrema(p: &[f32; 4]) -> [Option<f32>; 2] {
+    // The derivative, divided by 3, is `a t² + b t + c`.
+    let a = -p[0] + 3.0 * p[1] - 3.0 * p[2] + p[3];
+    let b = 2.0 * (p[0] - 2.0 * p[1] + p[2]);
+    let c = p[1] - p[0];
+
+    let in_range = |t: f32| if t > 0.0 && t < 1.0 { Some(t) } else { None };
+    if a.abs() < 1e-6 {
+        if b.abs() < 1e-6 {
+            return [None, None]
+        }
+        return [in_range(-c / b), None]
     }
 
-    let off_size = try!(reader.read_u8().map_err(FontError::eof));
+    let discriminant = b * b - 4.0 * a * c;
+    if discriminant < 0.0 {
+        return [None, None]
+    }
+    let root = discriminant.sqrt();
+    [in_range((-b + root) / (2.0 * a)), in_range((-b - root) / (2.0 * a))]
+}
 
-    let mut offset_reader = *reader;
-    try!(offset_reader.jump(off_size as usize * cmp::min(index, count) as usize)
-                      .map_err(FontError::eof));
-    let offset = try!(read_offset(&mut offset_reader, off_size));
+// The most quadratic curves that `cubic_to_quadratics()` will split a cubic curve into.
+const MAX_QUADRATICS_PER_CUBIC: f32 = 32.0;
 
-    let next_offset = if index < count {
-        Some(try!(read_offset(&mut offset_reader, off_size)) - offset)
-    } else {
-        None
-    };
+// Approximates the given cubic Bézier curve with quadratic curves that stay within `tolerance` of
+// it, returning the control point and end point of each.
+//
+// A single quadratic with the control point `(3 (p1 + p2) - p0 - p3) / 4` differs from the cubic
+// by at most √3/36 times the length of `p3 - 3 p2 + 3 p1 - p0`, and splitting the cubic into `n`
+// pieces divides that by `n³`.
+fn cubic_to_quadratics(cubic: &[Point2D<i16>; 4], tolerance: f32)
+                       -> Vec<(Point2D<i16>, Point2D<i16>)> {
+    let xs = [cubic[0].x as f32, cubic[1].x as f32, cubic[2].x as f32, cubic[3].x as f32];
+    let ys = [cubic[0].y as f32, cubic[1].y as f32, cubic[2].y as f32, cubic[3].y as f32];
+
+    let (dx, dy) = (xs[3] - 3.0 * xs[2] + 3.0 * xs[1] - xs[0],
+                    ys[3] - 3.0 * ys[2] + 3.0 * ys[1] - ys[0]);
+    let error = (dx * dx + dy * dy).sqrt() * 3.0f32.sqrt() / 36.0;
+    let count = (error / tolerance).cbrt().ceil().max(1.0).min(MAX_QUADRATICS_PER_CUBIC) as u32;
+
+    let round = |x: f32, y: f32| Point2D::new(x.round() as i16, y.round() as i16);
+    let mut quadratics = Vec::with_capacity(count as usize);
+    for i in 0..count {
+        let (t0, t1) = (i as f32 / count as f32, (i + 1) as f32 / count as f32);
+
+        // Find the control point of the quadratic from the control points of this piece of the
+        // cubic, which follow from the derivatives at its ends.
+        let scale = (t1 - t0) / 3.0;
+        let (x0, y0) = (evaluate_cubic(&xs, t0), evaluate_cubic(&ys, t0));
+        let (x3, y3) = (evaluate_cubic(&xs, t1), evaluate_cubic(&ys, t1));
+        let (x1, y1) = (x0 + evaluate_cubic_derivative(&xs, t0) * scale,
+                        y0 + evaluate_cubic_derivative(&ys, t0) * scale);
+        let (x2, y2) = (x3 - evaluate_cubic_derivative(&xs, t1) * scale,
+                        y3 - evaluate_cubic_derivative(&ys, t1) * scale);
+        let control = round((3.0 * (x1 + x2) - x0 - x3) / 4.0, (3.0 * (y1 + y2) - y0 - y3) / 4.0);
+
+        let end = if i + 1 == count {
+            cubic[3]
+        } else {
+            round(x3, y3)
+        };
+        quadratics.push((control, end))
+    }
+    quadratics
+}
 
-    try!(reader.jump(off_size as usize * (count as usize + 1) + offset as usize - 1)
-               .map_err(FontError::eof));
-    return Ok(next_offset)
+// Evaluates the derivative of the given one-dimensional cubic Bézier curve at `t`.
+fn evaluate_cubic_derivative(p: &[f32; 4], t: f32) -> f32 {
+    let mt = 1.0 - t;
+    3.0 * (mt * mt * (p[1] - p[0]) + 2.0 * mt * t * (p[2] - p[1]) + t * t * (p[3] - p[2]))
+}
+
+// Evaluates the given one-dimensional cubic Bézier curve at `t`.
+fn evaluate_cubic(p: &[f32; 4], t: f32) -> f32 {
+    let mt = 1.0 - t;
+    mt * mt * mt * p[0] + 3.0 * mt * mt * t * p[1] + 3.0 * mt * t * t * p[2] + t * t * t * p[3]
 }
 
 // Skips over an INDEX by reading the last element in the offset array and seeking the appropriate
 // number of bytes forward.
-fn skip_index(reader: &mut &[u8]) -> Result<(), FontError> {
-    find_in_index(reader, u16::MAX).map(drop)
+fn skip_index(reader: &mut Reader, field: &'static str) -> Result<(), FontError> {
+    Index::new(reader, field).map(drop)
+}
+
+// Returns the last integer operand of the given operator in the DICT.
+fn get_integer_in_dict(dict: &[u8], operator: u16) -> Result<i32, FontError> {
+    let operands = try!(find_in_dict(dict, operator));
+    match operands.as_ref().and_then(|operands| operands.last()).and_then(DictOperand::to_integer) {
+        Some(integer) => Ok(integer),
+        None => Err(FontError::CffIntegerNotFound),
+    }
+}
+
+// Returns the table data starting at the offset given by the last integer operand of the given
+// operator in the DICT. If the offset is past the end of the table, the error names the table
+// with the given tag and the given field.
+fn dict_offset<'a>(table: &'a [u8], dict: &[u8], operator: u16, tag: u32, field: &'static str)
+                   -> Result<Reader<'a>, FontError> {
+    let offset = try!(get_integer_in_dict(dict, operator));
+    if offset < 0 {
+        return Err(FontError::CffBadOffset)
+    }
+
+    Reader::at(table, offset as usize).eof_in(tag, field)
+}
+
+// Reads the `FontMatrix` from the top DICT, falling back to the default scale of 1/1000.
+fn read_font_matrix(top_dict: &[u8]) -> Result<Matrix2D<f32>, FontError> {
+    match try!(get_reals_in_dict(top_dict, OPERATOR_FONT_MATRIX)) {
+        None => Ok(Matrix2D::create_scale(0.001, 0.001)),
+        Some(ref values) if values.len() == 6 => {
+            Ok(Matrix2D::row_major(values[0] as f32,
+                                   values[1] as f32,
+                                   values[2] as f32,
+                                   values[3] as f32,
+                                   values[4] as f32,
+                                   values[5] as f32))
+        }
+        Some(_) => Err(FontError::CffBadRealNumber),
+    }
+}
+
+// Returns the operands of the given operator in the DICT as real numbers, or `None` if the
+// operator isn't present.
+fn get_reals_in_dict(dict: &[u8], operator: u16) -> Result<Option<Vec<f64>>, FontError> {
+    let operands = try!(find_in_dict(dict, operator));
+    Ok(operands.map(|operands| operands.iter().map(DictOperand::to_real).collect()))
 }
 
-// Returns the integer with the given operator.
-fn get_integer_in_dict(reader: &mut &[u8], operator: u16) -> Result<i32, FontError> {
-    let mut last_integer_operand = None;
-    loop {
-        let b0 = try!(reader.read_u8().map_err(FontError::eof));
+// Returns the operands of the given operator in the DICT, or `None` if the operator isn't
+// present.
+fn find_in_dict(dict: &[u8], operator: u16) -> Result<Option<Vec<DictOperand>>, FontError> {
+    let mut reader = Reader::new(dict);
+    let mut operands = vec![];
+    while let Ok(b0) = reader.read_u8() {
         match b0 {
-            32...246 => last_integer_operand = Some(b0 as i32 - 139),
+            32...246 => operands.push(DictOperand::Integer(b0 as i32 - 139)),
             247...250 => {
-                let b1 = try!(reader.read_u8().map_err(FontError::eof));
-                last_integer_operand = Some((b0 as i32 - 247) * 256 + b1 as i32 + 108)
+                let b1 = try!(reader.read_u8());
+                operands.push(DictOperand::Integer((b0 as i32 - 247) * 256 + b1 as i32 + 108))
             }
             251...254 => {
-                let b1 = try!(reader.read_u8().map_err(FontError::eof));
-                last_integer_operand = Some(-(b0 as i32 - 251) * 256 - b1 as i32 - 108)
+                let b1 = try!(reader.read_u8());
+                operands.push(DictOperand::Integer(-(b0 as i32 - 251) * 256 - b1 as i32 - 108))
             }
             28 => {
-                last_integer_operand =
-                    Some(try!(reader.read_i16::<BigEndian>().map_err(FontError::eof)) as i32)
+                let integer = try!(reader.read_i16_be());
+                operands.push(DictOperand::Integer(integer as i32))
             }
             29 => {
-                last_integer_operand =
-                    Some(try!(reader.read_i32::<BigEndian>().map_err(FontError::eof)) as i32)
-            }
-            30 => {
-                // TODO(pcwalton): Real numbers.
-                while (try!(reader.read_u8().map_err(FontError::eof)) & 0xf) != 0xf {}
+                let integer = try!(reader.read_i32_be());
+                operands.push(DictOperand::Integer(integer))
             }
+            30 => operands.push(DictOperand::Real(try!(read_real(&mut reader)))),
             12 => {
-                let b1 = try!(reader.read_u8().map_err(FontError::eof));
+                let b1 = try!(reader.read_u8());
                 if operator == (((b1 as u16) << 8) | (b0 as u16)) {
-                    match last_integer_operand {
-                        Some(last_integer_operand) => return Ok(last_integer_operand),
-                        None => return Err(FontError::CffIntegerNotFound),
-                    }
+                    return Ok(Some(operands))
                 }
-                last_integer_operand = None
+                operands.clear()
             }
             _ => {
                 if operator == b0 as u16 {
-                    match last_integer_operand {
-                        Some(last_integer_operand) => return Ok(last_integer_operand),
-                        None => return Err(FontError::CffIntegerNotFound),
+                    return Ok(Some(operands))
+                }
+                operands.clear()
+            }
+        }
+    }
+
+    Ok(None)
+}
+
+// Copies the entries of a DICT, except those with the given operators.
+fn copy_dict(dict: &[u8], skipped_operators: &[u16], output: &mut Vec<u8>)
+             -> Result<(), FontError> {
+    let mut reader = Reader::new(dict);
+    let mut entry = reader;
+    while let Ok(b0) = reader.read_u8() {
+        // Skip over the operands.
+        let operator = match b0 {
+            32...246 => continue,
+            247...254 => {
+                try!(reader.read_u8());
+                continue
+            }
+            28 => {
+                try!(reader.read_i16_be());
+                continue
+            }
+            29 => {
+                try!(reader.read_i32_be());
+                continue
+            }
+            30 => {
+                try!(read_real(&mut reader));
+                continue
+            }
+            12 => ((try!(reader.read_u8()) as u16) << 8) | 12,
+            _ => b0 as u16,
+        };
+
+        let entry_length = entry.len() - reader.len();
+        let entry_bytes = try!(entry.subslice(entry_length));
+        if !skipped_operators.contains(&operator) {
+            output.extend_from_slice(entry_bytes)
+        }
+    }
+    Ok(())
+}
+
+// Appends an offset to a DICT, as a 32-bit integer.
+fn push_dict_offset(dict: &mut Vec<u8>, offset: usize) {
+    dict.push(29);
+    util::push_u32(dict, offset as u32)
+}
+
+// Returns the bytes of the INDEX at the reader and moves the reader past it.
+fn index_bytes<'a>(reader: &mut Reader<'a>, field: &'static str)
+                   -> Result<&'a [u8], FontError> {
+    let mut start = *reader;
+    try!(skip_index(reader, field));
+    let length = start.len() - reader.len();
+    start.subslice(length)
+}
+
+// Writes an INDEX of the given objects, with the smallest offset size that fits.
+fn write_index(objects: &[&[u8]]) -> Vec<u8> {
+    let mut index = vec![];
+    util::push_u16(&mut index, objects.len() as u16);
+    if objects.is_empty() {
+        return index
+    }
+
+    let data_length: usize = objects.iter().map(|object| object.len()).sum();
+    let off_size = match data_length + 1 {
+        0...0xff => 1,
+        0x100...0xffff => 2,
+        0x10000...0xffffff => 3,
+        _ => 4,
+    };
+    index.push(off_size as u8);
+
+    let mut offset = 1;
+    push_index_offset(&mut index, offset, off_size);
+    for object in objects {
+        offset += object.len();
+        push_index_offset(&mut index, offset, off_size)
+    }
+    for object in objects {
+        index.extend_from_slice(object)
+    }
+    index
+}
+
+// Appends an INDEX offset of the given size.
+fn push_index_offset(index: &mut Vec<u8>, offset: usize, off_size: usize) {
+    for shift in (0..off_size).rev() {
+        index.push((offset >> (8 * shift)) as u8)
+    }
+}
+
+// Writes a copy of the given subr INDEX in which the subroutines that aren't used just return,
+// so that the ones that are keep their numbers.
+fn subset_subrs(subrs: &Index, used: &[bool]) -> Result<Vec<u8>, FontError> {
+    let mut objects = Vec::with_capacity(used.len());
+    for (subr_index, &used) in used.iter().enumerate() {
+        if !used {
+            objects.push(&UNUSED_SUBR[..]);
+            continue
+        }
+        match try!(subrs.get(subr_index as u16)) {
+            Some(subr) => objects.push(subr),
+            None => return Err(FontError::CffSubrNotFound),
+        }
+    }
+    Ok(write_index(&objects))
+}
+
+/// Reads a real number DICT operand, not including the initial `30` byte.
+///
+/// Real numbers are encoded as a sequence of nibbles, terminated by `0xf`.
+pub fn read_real(reader: &mut Reader) -> Result<f64, FontError> {
+    let mut string = String::new();
+    'bytes: loop {
+        let byte = try!(reader.read_u8());
+        for &nibble in &[byte >> 4, byte & 0xf] {
+            match nibble {
+                0...9 => string.push((b'0' + nibble) as char),
+                0xa => string.push('.'),
+                0xb => string.push('E'),
+                0xc => string.push_str("E-"),
+                0xe => string.push('-'),
+                0xf => break 'bytes,
+                _ => return Err(FontError::CffBadRealNumber),
+            }
+        }
+    }
+
+    string.parse().map_err(|_| FontError::CffBadRealNumber)
+}
+
+// The parts of a Private DICT that we use.
+#[derive(Clone, Copy, Debug)]
+struct PrivateDict<'a> {
+    // The DICT itself, which is empty if there's no Private DICT.
+    dict: &'a [u8],
+    // The local subroutine INDEX, if there is one.
+    local_subrs: Index<'a>,
+    // The advance width of glyphs whose CharStrings don't specify one.
+    default_width_x: f32,
+    // The value that CharString widths are relative to.
+    nominal_width_x: f32,
+    // In CFF2, the item variation data that `blend` operators use unless a CharString selects
+    // another with `vsindex`.
+    vsindex: u16,
+}
+
+impl<'a> PrivateDict<'a> {
+    // Reads the Private DICT referenced by the given top DICT or font DICT of a font with the given
+    // major version. If there's no reference, returns an empty Private DICT.
+    fn new(table: &'a [u8], dict: &[u8], major_version: u8)
+           -> Result<PrivateDict<'a>, FontError> {
+        let mut private_dict = PrivateDict {
+            dict: &[],
+            local_subrs: Index::empty(),
+            default_width_x: 0.0,
+            nominal_width_x: 0.0,
+            vsindex: 0,
+        };
+
+        let private = match try!(find_in_dict(dict, OPERATOR_PRIVATE)) {
+            Some(private) => private,
+            None => return Ok(private_dict),
+        };
+        let (private_size, private_offset) =
+            match (private.get(0).and_then(DictOperand::to_integer),
+                   private.get(1).and_then(DictOperand::to_integer)) {
+                (Some(private_size), Some(private_offset)) => (private_size, private_offset),
+                _ => return Err(FontError::CffIntegerNotFound),
+            };
+        if private_size < 0 || private_offset < 0 {
+            return Err(FontError::CffBadOffset)
+        }
+
+        let tag = if major_version == 2 { CFF2_TAG } else { TAG };
+        let mut reader = try!(Reader::at(table, private_offset as usize)
+                                  .eof_in(tag, "Private DICT offset"));
+        let dict = try!(reader.subslice(private_size as usize).eof_in(tag, "Private DICT"));
+        private_dict.dict = dict;
+
+        if let Some(widths) = try!(get_reals_in_dict(dict, OPERATOR_DEFAULT_WIDTH_X)) {
+            private_dict.default_width_x = widths.last().cloned().unwrap_or(0.0) as f32
+        }
+        if let Some(widths) = try!(get_reals_in_dict(dict, OPERATOR_NOMINAL_WIDTH_X)) {
+            private_dict.nominal_width_x = widths.last().cloned().unwrap_or(0.0) as f32
+        }
+
+        if let Some(vsindex) = try!(find_in_dict(dict, OPERATOR_VSINDEX)) {
+            private_dict.vsindex = match vsindex.last().and_then(DictOperand::to_integer) {
+                Some(vsindex) if vsindex >= 0 && vsindex <= u16::MAX as i32 => vsindex as u16,
+                _ => return Err(FontError::CffIntegerNotFound),
+            }
+        }
+
+        // The local subr INDEX offset is relative to the start of the Private DICT.
+        if let Some(subrs) = try!(find_in_dict(dict, OPERATOR_SUBRS)) {
+            let subrs_offset = match subrs.last().and_then(DictOperand::to_integer) {
+                Some(subrs_offset) if subrs_offset >= 0 => subrs_offset,
+                _ => return Err(FontError::CffBadOffset),
+            };
+            let offset = match (private_offset as usize).checked_add(subrs_offset as usize) {
+                Some(offset) => offset,
+                None => return Err(FontError::CffBadOffset),
+            };
+            let mut reader = try!(Reader::at(table, offset).eof_in(tag, "local subr offset"));
+            private_dict.local_subrs = if major_version == 2 {
+                try!(Index::new_cff2(&mut reader, "local subr INDEX"))
+            } else {
+                try!(Index::new(&mut reader, "local subr INDEX"))
+            }
+        }
+
+        Ok(private_dict)
+    }
+}
+
+// The FDSelect structure of a CID-keyed font, which maps each glyph to a font DICT.
+#[derive(Clone, Copy, Debug)]
+enum FdSelect<'a> {
+    // Format 0: one font DICT index per glyph.
+    Format0(&'a [u8]),
+    // Format 3: three-byte ranges, each a first glyph ID and a font DICT index, followed by the
+    // sentinel glyph ID that ends the last range.
+    Format3(&'a [u8], u16),
+}
+
+impl<'a> FdSelect<'a> {
+    fn new(mut reader: Reader<'a>, glyph_count: u16) -> Result<FdSelect<'a>, FontError> {
+        match try!(reader.read_u8()) {
+            0 => Ok(FdSelect::Format0(try!(reader.subslice(glyph_count as usize)))),
+            3 => {
+                let range_count = try!(reader.read_u16_be());
+                let ranges = try!(reader.subslice(range_count as usize * 3));
+                let sentinel = try!(reader.read_u16_be());
+                Ok(FdSelect::Format3(ranges, sentinel))
+            }
+            _ => Err(FontError::UnknownFormat),
+        }
+    }
+
+    // Returns the index of the font DICT for the given glyph.
+    fn font_dict_index(&self, glyph_id: u16) -> Result<u8, FontError> {
+        match *self {
+            FdSelect::Format0(font_dict_indices) => {
+                match font_dict_indices.get(glyph_id as usize) {
+                    Some(&font_dict_index) => Ok(font_dict_index),
+                    None => Err(FontError::CffFontDictNotFound),
+                }
+            }
+            FdSelect::Format3(ranges, sentinel) => {
+                if glyph_id >= sentinel {
+                    return Err(FontError::CffFontDictNotFound)
+                }
+
+                // Find the last range starting at or before the glyph.
+                let (mut low, mut high) = (0, ranges.len() / 3);
+                while low < high {
+                    let mid = (low + high) / 2;
+                    let first = try!(try!(Reader::at(ranges, mid * 3)).read_u16_be());
+                    if first <= glyph_id {
+                        low = mid + 1
+                    } else {
+                        high = mid
                     }
                 }
-                last_integer_operand = None
+                if low == 0 {
+                    return Err(FontError::CffFontDictNotFound)
+                }
+                Ok(ranges[(low - 1) * 3 + 2])
             }
         }
     }
 }
 
-// Reads an Offset with the given size.
-fn read_offset(reader: &mut &[u8], size: u8) -> Result<u32, FontError> {
-    match size {
-        1 => Ok(try!(reader.read_u8().map_err(FontError::eof)) as u32),
-        2 => Ok(try!(reader.read_u16::<BigEndian>().map_err(FontError::eof)) as u32),
-        3 => {
-            let hi = try!(reader.read_u8().map_err(FontError::eof)) as u32;
-            let lo = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof)) as u32;
-            Ok((hi << 16) | lo)
+// The charset, which maps glyph IDs to SIDs.
+#[derive(Clone, Copy, Debug)]
+enum Charset<'a> {
+    // Glyph IDs are SIDs.
+    IsoAdobe,
+    Expert,
+    ExpertSubset,
+    // A charset in the font, starting with its format byte.
+    Custom(Reader<'a>),
+}
+
+impl<'a> Charset<'a> {
+    // Returns the glyph with the given SID, if there is one.
+    fn glyph_id_for_sid(&self, sid: u16, glyph_count: u16) -> Result<Option<u16>, FontError> {
+        // Glyph 0 is always `.notdef`.
+        if sid == 0 {
+            return Ok(Some(0))
+        }
+
+        let mut reader = match *self {
+            Charset::IsoAdobe if sid < glyph_count => return Ok(Some(sid)),
+            Charset::IsoAdobe => return Ok(None),
+            // TODO(pcwalton): Support the predefined expert charsets.
+            Charset::Expert | Charset::ExpertSubset => return Ok(None),
+            Charset::Custom(reader) => reader,
+        };
+
+        let format = try!(reader.read_u8());
+        let mut glyph_id = 1;
+        while glyph_id < glyph_count {
+            let (first_sid, left_count) = try!(read_charset_range(&mut reader, format));
+            if sid >= first_sid && sid - first_sid <= left_count {
+                let sid_glyph_id = glyph_id as u32 + (sid - first_sid) as u32;
+                if sid_glyph_id >= glyph_count as u32 {
+                    return Ok(None)
+                }
+                return Ok(Some(sid_glyph_id as u16))
+            }
+            glyph_id = glyph_id.saturating_add(left_count).saturating_add(1)
+        }
+
+        Ok(None)
+    }
+
+    // Returns the SID of the given glyph, if it's in range.
+    fn sid_for_glyph(&self, glyph_id: u16, glyph_count: u16) -> Result<Option<u16>, FontError> {
+        if glyph_id >= glyph_count {
+            return Ok(None)
+        }
+        if glyph_id == 0 {
+            return Ok(Some(0))
+        }
+
+        let mut reader = match *self {
+            Charset::IsoAdobe => return Ok(Some(glyph_id)),
+            // TODO(pcwalton): Support the predefined expert charsets.
+            Charset::Expert | Charset::ExpertSubset => return Ok(None),
+            Charset::Custom(reader) => reader,
+        };
+
+        // Ranges cover glyphs in order, starting after `.notdef`.
+        let format = try!(reader.read_u8());
+        let mut first_glyph_id = 1;
+        while first_glyph_id <= glyph_id {
+            let (first_sid, left_count) = try!(read_charset_range(&mut reader, format));
+            if glyph_id - first_glyph_id <= left_count {
+                return Ok(first_sid.checked_add(glyph_id - first_glyph_id))
+            }
+            first_glyph_id = first_glyph_id.saturating_add(left_count).saturating_add(1)
+        }
+
+        Ok(None)
+    }
+}
+
+// Reads a range of the charset with the given format: its first SID and the number of SIDs that
+// follow it. Format 0 charsets have one SID per glyph, so their ranges are all single SIDs.
+fn read_charset_range(reader: &mut Reader, format: u8) -> Result<(u16, u16), FontError> {
+    let first_sid = try!(reader.read_u16_be());
+    let left_count = match format {
+        0 => 0,
+        1 => try!(reader.read_u8()) as u16,
+        2 => try!(reader.read_u16_be()),
+        _ => return Err(FontError::UnknownFormat),
+    };
+    Ok((first_sid, left_count))
+}
+
+// The ItemVariationStore of a CFF2 font, which has sets of variation regions that `blend` operators
+// refer to by index.
+#[derive(Clone, Copy, Debug)]
+struct VariationStore<'a> {
+    // The store, starting with its format.
+    data: &'a [u8],
+}
+
+impl<'a> VariationStore<'a> {
+    fn new(data: &'a [u8]) -> Result<VariationStore<'a>, FontError> {
+        let format = try!(Reader::new(data).read_u16_be());
+        if format != 1 {
+            return Err(FontError::UnknownFormat)
+        }
+
+        Ok(VariationStore {
+            data: data,
+        })
+    }
+
+    // Returns the scalar of each region of the item variation data with the given index, at the
+    // given normalized coordinates.
+    fn region_scalars(&self, vsindex: u16, coordinates: &[f32]) -> Result<Vec<f32>, FontError> {
+        let mut reader = try!(Reader::at(self.data, 2));
+        let region_list_offset = try!(reader.read_u32_be());
+        let data_count = try!(reader.read_u16_be());
+        if vsindex >= data_count {
+            return Err(FontError::CffMalformedCharString)
+        }
+        try!(reader.jump(vsindex as usize * 4));
+        let data_offset = try!(reader.read_u32_be());
+
+        let mut region_list = try!(Reader::at(self.data, region_list_offset as usize));
+        let axis_count = try!(region_list.read_u16_be());
+        let region_count = try!(region_list.read_u16_be());
+
+        // Skip the item count and short delta count; CFF2 keeps its deltas in the CharStrings.
+        let data_offset = match (data_offset as usize).checked_add(4) {
+            Some(data_offset) => data_offset,
+            None => return Err(FontError::CffBadOffset),
+        };
+        let mut reader = try!(Reader::at(self.data, data_offset));
+        let region_index_count = try!(reader.read_u16_be());
+
+        let mut scalars = Vec::with_capacity(region_index_count as usize);
+        for _ in 0..region_index_count {
+            let region_index = try!(reader.read_u16_be());
+            if region_index >= region_count {
+                return Err(FontError::CffMalformedCharString)
+            }
+
+            // Each region has a start, peak, and end F2DOT14 coordinate per axis.
+            let mut region = region_list;
+            let region_offset = (region_index as usize).checked_mul(axis_count as usize)
+                                                       .and_then(|offset| offset.checked_mul(6));
+            match region_offset {
+                Some(region_offset) => try!(region.jump(region_offset)),
+                None => return Err(FontError::CffBadOffset),
+            }
+            let mut scalar = 1.0;
+            for axis_index in 0..(axis_count as usize) {
+                let mut values = [0.0; 3];
+                for value in &mut values {
+                    let raw = try!(region.read_i16_be());
+                    *value = raw as f32 / 16384.0
+                }
+                let coordinate = coordinates.get(axis_index).cloned().unwrap_or(0.0);
+                scalar *= axis_scalar(coordinate, values[0], values[1], values[2])
+            }
+            scalars.push(scalar)
+        }
+
+        Ok(scalars)
+    }
+}
+
+// Returns how much a variation region with the given start, peak, and end applies at the given
+// coordinate along one axis, from 0.0 to 1.0.
+fn axis_scalar(coordinate: f32, start: f32, peak: f32, end: f32) -> f32 {
+    // Invalid regions, and regions that don't vary along this axis, are ignored.
+    if start > peak || peak > end || (start < 0.0 && end > 0.0 && peak != 0.0) {
+        return 1.0
+    }
+    if peak == 0.0 || coordinate == peak {
+        return 1.0
+    }
+
+    if coordinate <= start || coordinate >= end {
+        return 0.0
+    }
+    if coordinate < peak {
+        (coordinate - start) / (peak - start)
+    } else {
+        (end - coordinate) / (end - peak)
+    }
+}
+
+// The encoding, which maps character codes to glyphs.
+#[derive(Clone, Copy, Debug)]
+enum Encoding<'a> {
+    // Codes map to SIDs through the predefined encodings.
+    Standard,
+    Expert,
+    // An encoding in the font, starting with its format byte.
+    Custom(Reader<'a>),
+}
+
+// An operand in a DICT.
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum DictOperand {
+    Integer(i32),
+    Real(f64),
+}
+
+impl DictOperand {
+    // Returns the value of this operand if it's an integer.
+    //
+    // Some fonts write integral values, such as `CharstringType`, as reals, so those count too.
+    fn to_integer(&self) -> Option<i32> {
+        match *self {
+            DictOperand::Integer(integer) => Some(integer),
+            DictOperand::Real(real) if real.fract() == 0.0 && real.abs() <= i32::MAX as f64 => {
+                Some(real as i32)
+            }
+            DictOperand::Real(_) => None,
+        }
+    }
+
+    fn to_real(&self) -> f64 {
+        match *self {
+            DictOperand::Integer(integer) => integer as f64,
+            DictOperand::Real(real) => real,
+        }
+    }
+}
+
+// A CFF INDEX: an array of variable-sized objects.
+#[derive(Clone, Copy, Debug)]
+struct Index<'a> {
+    count: u16,
+    off_size: u8,
+    // The offset array, `count + 1` entries of `off_size` bytes each.
+    offsets: &'a [u8],
+    // The object data. Offsets are relative to the byte preceding this.
+    data: &'a [u8],
+}
+
+impl<'a> Index<'a> {
+    fn empty() -> Index<'a> {
+        Index {
+            count: 0,
+            off_size: 1,
+            offsets: &[],
+            data: &[],
+        }
+    }
+
+    // Reads an INDEX and moves the reader past it. If the INDEX runs past the end of the table,
+    // the error names it as `field`.
+    fn new(reader: &mut Reader<'a>, field: &'static str) -> Result<Index<'a>, FontError> {
+        let count = try!(reader.read_u16_be().eof_in(TAG, field));
+        Index::with_count(reader, count, TAG, field)
+    }
+
+    // Reads a CFF2 INDEX, which has a 32-bit count, and moves the reader past it.
+    fn new_cff2(reader: &mut Reader<'a>, field: &'static str) -> Result<Index<'a>, FontError> {
+        let count = try!(reader.read_u32_be().eof_in(CFF2_TAG, field));
+
+        // Glyph IDs and subroutine numbers can't address any more objects than this.
+        if count > u16::MAX as u32 {
+            return Err(FontError::CffBadOffset)
+        }
+        Index::with_count(reader, count as u16, CFF2_TAG, field)
+    }
+
+    // Reads the rest of an INDEX with the given count, in the table with the given tag.
+    fn with_count(reader: &mut Reader<'a>, count: u16, tag: u32, field: &'static str)
+                  -> Result<Index<'a>, FontError> {
+        if count == 0 {
+            return Ok(Index::empty())
+        }
+
+        let off_size = try!(reader.read_u8().eof_in(tag, field));
+        if off_size < 1 || off_size > 4 {
+            return Err(FontError::CffBadOffset)
+        }
+
+        // The last offset determines the size of the object data. Read it from a copy, so that
+        // `reader` is left untouched if the INDEX turns out to be malformed.
+        let mut index_reader = *reader;
+        let offsets_length = match (count as usize + 1).checked_mul(off_size as usize) {
+            Some(offsets_length) => offsets_length,
+            None => return Err(FontError::CffBadOffset),
+        };
+        let offsets = try!(index_reader.subslice(offsets_length).eof_in(tag, field));
+        let mut last_offset_reader = try!(Reader::at(offsets, offsets_length - off_size as usize));
+        let data_length = match (try!(last_offset_reader.read_offset(off_size)) as usize)
+                                    .checked_sub(1) {
+            Some(data_length) => data_length,
+            None => return Err(FontError::CffBadOffset),
+        };
+        let data = try!(index_reader.subslice(data_length).eof_in(tag, field));
+
+        *reader = index_reader;
+        Ok(Index {
+            count: count,
+            off_size: off_size,
+            offsets: offsets,
+            data: data,
+        })
+    }
+
+    // Returns the object with the given index, or `None` if the index is out of bounds.
+    fn get(&self, index: u16) -> Result<Option<&'a [u8]>, FontError> {
+        if index >= self.count {
+            return Ok(None)
+        }
+
+        let offset = match (index as usize).checked_mul(self.off_size as usize) {
+            Some(offset) => offset,
+            None => return Err(FontError::CffBadOffset),
+        };
+        let mut reader = try!(Reader::at(self.offsets, offset));
+
+        // Offsets count from 1, and each object must lie within the object data.
+        let start = try!(reader.read_offset(self.off_size)) as usize;
+        let end = try!(reader.read_offset(self.off_size)) as usize;
+        match (start.checked_sub(1), end.checked_sub(1)) {
+            (Some(start), Some(end)) if start <= end && end <= self.data.len() => {
+                Ok(Some(&self.data[start..end]))
+            }
+            _ => Err(FontError::CffBadOffset),
+        }
+    }
+
+    // The number to add to subroutine numbers to get the index into this INDEX.
+    fn bias(&self) -> i32 {
+        if self.count < 1240 {
+            107
+        } else if self.count < 33900 {
+            1131
+        } else {
+            32768
+        }
+    }
+}
+
+// The state of CharString evaluation, which persists across subroutine calls.
+struct EvaluationState {
+    stack: EvaluationStack,
+    start: Point2D<i16>,
+    pos: Point2D<i16>,
+    index_in_contour: u16,
+    hint_count: u16,
+    // Whether every operator so far has been a stem hint, so that operands before a `hintmask`
+    // are an implicit `vstem`.
+    in_stem_hints: bool,
+    // Whether the first stack-clearing operator, which may carry the width, has been seen.
+    width_parsed: bool,
+    // The width operand, relative to `nominalWidthX`, if the CharString has one.
+    width: Option<i32>,
+    // Whether this is a component of a `seac`-style accented character.
+    in_seac: bool,
+    // The normalized variation coordinates that CFF2 `blend` operators are evaluated at.
+    coordinates: Vec<f32>,
+    // The item variation data that `blend` operators use.
+    vsindex: u16,
+    // The scalars of the regions of `vsindex`, once a `blend` operator has needed them.
+    scalars: Option<Vec<f32>>,
+    // In lenient mode, the unsupported operators that have been skipped so far.
+    skipped_operators: Option<Vec<u16>>,
+    // When subsetting, the subroutines and components that the CharString has used so far.
+    dependencies: Option<Dependencies>,
+    // The values stored by `put`.
+    transient_array: [i32; TRANSIENT_ARRAY_SIZE],
+    // The state of the generator behind `random`.
+    random_state: u32,
+}
+
+// The subroutines and `seac` components that a CharString uses.
+#[derive(Default)]
+struct Dependencies {
+    local_subrs: Vec<u16>,
+    global_subrs: Vec<u16>,
+    glyphs: Vec<u16>,
+}
+
+impl EvaluationState {
+    fn new() -> EvaluationState {
+        EvaluationState {
+            stack: EvaluationStack::new(),
+            start: Point2D::new(0, 0),
+            pos: Point2D::new(0, 0),
+            index_in_contour: 0,
+            hint_count: 0,
+            in_stem_hints: true,
+            width_parsed: false,
+            width: None,
+            in_seac: false,
+            coordinates: vec![],
+            vsindex: 0,
+            scalars: None,
+            skipped_operators: None,
+            dependencies: None,
+            transient_array: [0; TRANSIENT_ARRAY_SIZE],
+            random_state: 1,
+        }
+    }
+
+    // CFF2 CharStrings have no width and may use a deeper argument stack.
+    fn new_cff2(vsindex: u16, coordinates: &[f32]) -> EvaluationState {
+        let mut state = EvaluationState::new();
+        state.stack.limit = CFF2_MAX_STACK_SIZE;
+        state.width_parsed = true;
+        state.coordinates = coordinates.to_vec();
+        state.vsindex = vsindex;
+        state
+    }
+
+    // Discards the operands of an unsupported operator. In lenient mode, the operator is recorded
+    // and evaluation continues; otherwise, it's an error.
+    fn skip_operator(&mut self, operator: u16) -> Result<(), FontError> {
+        self.stack.clear();
+        match self.skipped_operators {
+            Some(ref mut skipped_operators) => {
+                skipped_operators.push(operator);
+                Ok(())
+            }
+            None => Err(FontError::CffUnimplementedOperator),
+        }
+    }
+
+    // Evaluates the arithmetic or storage operator `12 operator`, which leaves its results on the
+    // stack for the next operator. Returns false if it's some other operator.
+    //
+    // The stack holds only integers, so results are rounded.
+    fn evaluate_arithmetic_operator(&mut self, operator: u8) -> Result<bool, FontError> {
+        match operator {
+            3 => {
+                // num1 num2 and (12 3) 1_or_0
+                let (num1, num2) = try!(self.stack.pop_pair());
+                try!(self.stack.push((num1 != 0 && num2 != 0) as i32))
+            }
+            4 => {
+                // num1 num2 or (12 4) 1_or_0
+                let (num1, num2) = try!(self.stack.pop_pair());
+                try!(self.stack.push((num1 != 0 || num2 != 0) as i32))
+            }
+            5 => {
+                // num1 not (12 5) 1_or_0
+                let num1 = try!(self.stack.pop_operand());
+                try!(self.stack.push((num1 == 0) as i32))
+            }
+            9 => {
+                // num abs (12 9) num2
+                let num = try!(self.stack.pop_operand());
+                try!(self.stack.push(round_operand((num as f64).abs())))
+            }
+            10 => {
+                // num1 num2 add (12 10) sum
+                let (num1, num2) = try!(self.stack.pop_pair());
+                try!(self.stack.push(round_operand(num1 as f64 + num2 as f64)))
+            }
+            11 => {
+                // num1 num2 sub (12 11) difference
+                let (num1, num2) = try!(self.stack.pop_pair());
+                try!(self.stack.push(round_operand(num1 as f64 - num2 as f64)))
+            }
+            12 => {
+                // num1 num2 div (12 12) quotient
+                let (num1, num2) = try!(self.stack.pop_pair());
+                if num2 == 0 {
+                    return Err(FontError::CffMalformedCharString)
+                }
+                try!(self.stack.push(round_operand(num1 as f64 / num2 as f64)))
+            }
+            14 => {
+                // num neg (12 14) num2
+                let num = try!(self.stack.pop_operand());
+                try!(self.stack.push(round_operand(-(num as f64))))
+            }
+            15 => {
+                // num1 num2 eq (12 15) 1_or_0
+                let (num1, num2) = try!(self.stack.pop_pair());
+                try!(self.stack.push((num1 == num2) as i32))
+            }
+            18 => {
+                // num drop (12 18)
+                try!(self.stack.pop_operand());
+            }
+            20 => {
+                // val i put (12 20)
+                let (value, i) = try!(self.stack.pop_pair());
+                *try!(self.transient_entry(i)) = value
+            }
+            21 => {
+                // i get (12 21) val
+                let i = try!(self.stack.pop_operand());
+                let value = *try!(self.transient_entry(i));
+                try!(self.stack.push(value))
+            }
+            22 => {
+                // s1 s2 v1 v2 ifelse (12 22) s1_or_s2
+                let (v1, v2) = try!(self.stack.pop_pair());
+                let (s1, s2) = try!(self.stack.pop_pair());
+                try!(self.stack.push(if v1 <= v2 { s1 } else { s2 }))
+            }
+            23 => {
+                // random (12 23) num2
+                //
+                // This is a xorshift generator, so that glyphs always look the same. Its values
+                // are in (0, 1].
+                let mut x = self.random_state;
+                x ^= x << 13;
+                x ^= x >> 17;
+                x ^= x << 5;
+                self.random_state = x;
+                try!(self.stack.push(round_operand(x as f64 / u32::MAX as f64)))
+            }
+            24 => {
+                // num1 num2 mul (12 24) product
+                let (num1, num2) = try!(self.stack.pop_pair());
+                try!(self.stack.push(round_operand(num1 as f64 * num2 as f64)))
+            }
+            26 => {
+                // num sqrt (12 26) num2
+                let num = try!(self.stack.pop_operand());
+                if num < 0 {
+                    return Err(FontError::CffMalformedCharString)
+                }
+                try!(self.stack.push(round_operand((num as f64).sqrt())))
+            }
+            27 => {
+                // any dup (12 27) any any
+                let value = try!(self.stack.pop_operand());
+                try!(self.stack.push(value));
+                try!(self.stack.push(value))
+            }
+            28 => {
+                // num1 num2 exch (12 28) num2 num1
+                let (num1, num2) = try!(self.stack.pop_pair());
+                try!(self.stack.push(num2));
+                try!(self.stack.push(num1))
+            }
+            29 => {
+                // numX ... num0 i index (12 29) numX ... num0 numi
+                //
+                // A negative index copies the top element.
+                let i = cmp::max(try!(self.stack.pop_operand()), 0) as usize;
+                let size = self.stack.size as usize;
+                if i >= size {
+                    return Err(FontError::CffMalformedCharString)
+                }
+                let value = self.stack.array[size - 1 - i];
+                try!(self.stack.push(value))
+            }
+            30 => {
+                // num(N-1) ... num0 N J roll (12 30) num((J-1) mod N) ... num0 num(N-1) ...
+                // num(J mod N)
+                //
+                // The top `N` elements move `J` places towards the top, wrapping around.
+                let (n, j) = try!(self.stack.pop_pair());
+                let size = self.stack.size as usize;
+                if n <= 0 || n as usize > size {
+                    return Err(FontError::CffMalformedCharString)
+                }
+                let elements = &mut self.stack.array[(size - n as usize)..size];
+                let shift = (j % n + n) % n;
+                elements.reverse();
+                elements[..shift as usize].reverse();
+                elements[shift as usize..].reverse();
+            }
+            _ => return Ok(false),
+        }
+        Ok(true)
+    }
+
+    // Returns the entry of the transient array at the given index.
+    fn transient_entry(&mut self, index: i32) -> Result<&mut i32, FontError> {
+        if index < 0 {
+            return Err(FontError::CffMalformedCharString)
+        }
+        match self.transient_array.get_mut(index as usize) {
+            Some(entry) => Ok(entry),
+            None => Err(FontError::CffMalformedCharString),
+        }
+    }
+
+    // Called at each operator that can be the first stack-clearing one. If this is the first one
+    // and `has_width` is true, removes the width from the bottom of the stack.
+    fn parse_width(&mut self, has_width: bool) {
+        if self.width_parsed {
+            return
+        }
+        self.width_parsed = true;
+        if has_width {
+            self.width = self.stack.shift()
         }
-        4 => Ok(try!(reader.read_u32::<BigEndian>().map_err(FontError::eof))),
-        _ => Err(FontError::CffBadOffset),
     }
 }
 
 // The CFF evaluation stack used during CharString reading.
 struct EvaluationStack {
-    array: [i32; 48],
-    size: u8,
+    array: [i32; CFF2_MAX_STACK_SIZE],
+    size: u16,
+    // The maximum number of values the stack may hold.
+    limit: usize,
 }
 
 impl EvaluationStack {
     fn new() -> EvaluationStack {
         EvaluationStack {
-            array: [0; 48],
+            array: [0; CFF2_MAX_STACK_SIZE],
             size: 0,
+            limit: MAX_STACK_SIZE,
         }
     }
 
     fn push(&mut self, value: i32) -> Result<(), FontError> {
-        if (self.size as usize) < self.array.len() {
+        if (self.size as usize) < self.limit {
             self.array[self.size as usize] = value;
             self.size += 1;
             Ok(())
@@ -542,13 +2636,90 @@ impl EvaluationStack {
         }
     }
 
+    fn pop(&mut self) -> Option<i32> {
+        if self.size == 0 {
+            return None
+        }
+        self.size -= 1;
+        Some(self.array[self.size as usize])
+    }
+
+    // Removes and returns the value at the bottom of the stack.
+    fn shift(&mut self) -> Option<i32> {
+        if self.size == 0 {
+            return None
+        }
+        let value = self.array[0];
+        for i in 1..(self.size as usize) {
+            self.array[i - 1] = self.array[i]
+        }
+        self.size -= 1;
+        Some(value)
+    }
+
+    // Removes and returns the operand at the top of the stack, which must be there.
+    fn pop_operand(&mut self) -> Result<i32, FontError> {
+        match self.pop() {
+            Some(value) => Ok(value),
+            None => Err(FontError::CffMalformedCharString),
+        }
+    }
+
+    // Removes and returns the top two operands, in the order they were pushed.
+    fn pop_pair(&mut self) -> Result<(i32, i32), FontError> {
+        let second = try!(self.pop_operand());
+        let first = try!(self.pop_operand());
+        Ok((first, second))
+    }
+
+    fn last(&self) -> Option<i32> {
+        if self.size == 0 {
+            None
+        } else {
+            Some(self.array[self.size as usize - 1])
+        }
+    }
+
+    // Checks that there are at least `min` operands on the stack and that any operands beyond
+    // the first `min` come in groups of `step`. A `step` of zero permits no extra operands.
+    fn check_operand_count(&self, min: usize, step: usize) -> Result<(), FontError> {
+        let size = self.size as usize;
+        if size == min || (size > min && step != 0 && (size - min) % step == 0) {
+            Ok(())
+        } else {
+            Err(FontError::CffMalformedCharString)
+        }
+    }
+
     fn clear(&mut self) {
         self.size = 0
     }
 }
 
-fn close_path_if_necessary<F>(start: &Point2D<i16>, index_in_contour: u16, mut callback: F)
-                              where F: FnMut(&Point) {
+// Rounds a computed CharString operand to the nearest integer, saturating at the limits of the
+// 16.16 fixed-point numbers that CharStrings hold. Keeping operands in this range means that the
+// operators can't overflow when they add them up.
+fn round_operand(value: f64) -> i32 {
+    value.round().max(i16::MIN as f64).min(i16::MAX as f64) as i32
+}
+
+// Ends the contour that began at `start`, if there is one. Contours end with a line back to their
+// start, which `ClosePath` makes redundant.
+fn close_contour(segments: &mut Vec<PathSegment>, start: Option<Point2D<i16>>) {
+    let start = match start {
+        Some(start) => start,
+        None => return,
+    };
+    if segments.last() == Some(&PathSegment::LineTo(start)) {
+        segments.pop();
+    }
+    segments.push(PathSegment::ClosePath)
+}
+
+// Emits the final point of the contour that began at `start`, if there is one. Type 1 fonts use
+// this too.
+pub fn close_path_if_necessary<F>(start: &Point2D<i16>, index_in_contour: u16, mut callback: F)
+                                  where F: FnMut(&Point) {
     if index_in_contour == 0 {
         // No path to close.
         return
@@ -561,6 +2732,14 @@ fn close_path_if_necessary<F>(start: &Point2D<i16>, index_in_contour: u16, mut c
     });
 }
 
+// Moves a point by the given deltas. Malformed CharStrings can move points out of range, so
+// coordinates wrap around rather than overflowing.
+fn translate(point: Point2D<i16>, dx: i16, dy: i16) -> Point2D<i16> {
+    Point2D::new(point.x.wrapping_add(dx), point.y.wrapping_add(dy))
+}
+
+// Emits a cubic curve from `pos` through the given relative control points and end point, moving
+// `pos` to the end.
 fn add_curve<F>(dx0: i16, dy0: i16,
                 dx1: i16, dy1: i16,
                 dx2: i16, dy2: i16,
@@ -568,30 +2747,30 @@ fn add_curve<F>(dx0: i16, dy0: i16,
                 index_in_contour: &mut u16,
                 mut callback: F)
                 where F: FnMut(&Point) {
-    pos.x += dx0;
-    pos.y += dy0;
+    pos.x = pos.x.wrapping_add(dx0);
+    pos.y = pos.y.wrapping_add(dy0);
     callback(&Point {
         position: *pos,
-        index_in_contour: *index_in_contour + 0,
+        index_in_contour: *index_in_contour,
         kind: PointKind::FirstCubicControl,
     });
 
-    pos.x += dx1;
-    pos.y += dy1;
+    pos.x = pos.x.wrapping_add(dx1);
+    pos.y = pos.y.wrapping_add(dy1);
     callback(&Point {
         position: *pos,
-        index_in_contour: *index_in_contour + 1,
+        index_in_contour: index_in_contour.wrapping_add(1),
         kind: PointKind::SecondCubicControl,
     });
 
-    pos.x += dx2;
-    pos.y += dy2;
+    pos.x = pos.x.wrapping_add(dx2);
+    pos.y = pos.y.wrapping_add(dy2);
     callback(&Point {
         position: *pos,
-        index_in_contour: *index_in_contour + 2,
+        index_in_contour: index_in_contour.wrapping_add(2),
         kind: PointKind::OnCurve,
     });
 
-    *index_in_contour += 3
+    *index_in_contour = index_in_contour.wrapping_add(3)
 }
 
@@ -0,0 +1,94 @@
+// Copyright 2017 The Servo Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! The predefined CFF encodings.
+
+/// The Standard Encoding, mapping character codes to SIDs (string IDs). Code 0 means the
+/// character is not encoded.
+///
+/// This is used to resolve the base and accent characters of `seac`-style accented glyphs, and
+/// it's the encoding of fonts that don't specify another.
+pub static STANDARD_ENCODING: [u8; 256] = [
+      0,   0,   0,   0,   0,   0,   0,   0,   0,   0,   0,   0,   0,   0,   0,   0,  // 0x00
+      0,   0,   0,   0,   0,   0,   0,   0,   0,   0,   0,   0,   0,   0,   0,   0,  // 0x10
+      1,   2,   3,   4,   5,   6,   7,   8,   9,  10,  11,  12,  13,  14,  15,  16,  // 0x20
+     17,  18,  19,  20,  21,  22,  23,  24,  25,  26,  27,  28,  29,  30,  31,  32,  // 0x30
+     33,  34,  35,  36,  37,  38,  39,  40,  41,  42,  43,  44,  45,  46,  47,  48,  // 0x40
+     49,  50,  51,  52,  53,  54,  55,  56,  57,  58,  59,  60,  61,  62,  63,  64,  // 0x50
+     65,  66,  67,  68,  69,  70,  71,  72,  73,  74,  75,  76,  77,  78,  79,  80,  // 0x60
+     81,  82,  83,  84,  85,  86,  87,  88,  89,  90,  91,  92,  93,  94,  95,   0,  // 0x70
+      0,   0,   0,   0,   0,   0,   0,   0,   0,   0,   0,   0,   0,   0,   0,   0,  // 0x80
+      0,   0,   0,   0,   0,   0,   0,   0,   0,   0,   0,   0,   0,   0,   0,   0,  // 0x90
+      0,  96,  97,  98,  99, 100, 101, 102, 103, 104, 105, 106, 107, 108, 109, 110,  // 0xa0
+      0, 111, 112, 113, 114,   0, 115, 116, 117, 118, 119, 120, 121, 122,   0, 123,  // 0xb0
+      0, 124, 125, 126, 127, 128, 129, 130, 131,   0, 132, 133,   0, 134, 135, 136,  // 0xc0
+    137,   0,   0,   0,   0,   0,   0,   0,   0,   0,   0,   0,   0,   0,   0,   0,  // 0xd0
+      0, 138,   0, 139,   0,   0,   0,   0, 140, 141, 142, 143,   0,   0,   0,   0,  // 0xe0
+      0, 144,   0,   0,   0, 145,   0,   0, 146, 147, 148, 149,   0,   0,   0,   0,  // 0xf0
+];
+
+/// The Expert Encoding, mapping character codes to SIDs. Code 0 means the character is not
+/// encoded.
+pub static EXPERT_ENCODING: [u16; 256] = [
+      0,   0,   0,   0,   0,   0,   0,   0,   0,   0,   0,   0,   0,   0,   0,   0,  // 0x00
+      0,   0,   0,   0,   0,   0,   0,   0,   0,   0,   0,   0,   0,   0,   0,   0,  // 0x10
+      1, 229, 230,   0, 231, 232, 233, 234, 235, 236, 237, 238,  13,  14,  15,  99,  // 0x20
+    239, 240, 241, 242, 243, 244, 245, 246, 247, 248,  27,  28, 249, 250, 251, 252,  // 0x30
+      0, 253, 254, 255, 256, 257,   0,   0,   0, 258,   0,   0, 259, 260, 261, 262,  // 0x40
+      0,   0, 263, 264, 265,   0, 266, 109, 110, 267, 268, 269,   0, 270, 271, 272,  // 0x50
+    273, 274, 275, 276, 277, 278, 279, 280, 281, 282, 283, 284, 285, 286, 287, 288,  // 0x60
+    289, 290, 291, 292, 293, 294, 295, 296, 297, 298, 299, 300, 301, 302, 303,   0,  // 0x70
+      0,   0,   0,   0,   0,   0,   0,   0,   0,   0,   0,   0,   0,   0,   0,   0,  // 0x80
+      0,   0,   0,   0,   0,   0,   0,   0,   0,   0,   0,   0,   0,   0,   0,   0,  // 0x90
+      0, 304, 305, 306,   0,   0, 307, 308, 309, 310, 311,   0, 312,   0,   0, 313,  // 0xa0
+      0,   0, 314, 315,   0,   0, 316, 317, 318,   0,   0,   0, 158, 155, 163, 319,  // 0xb0
+    320, 321, 322, 323, 324, 325,   0,   0, 326, 150, 164, 169, 327, 328, 329, 330,  // 0xc0
+    331, 332, 333, 334, 335, 336, 337, 338, 339, 340, 341, 342, 343, 344, 345, 346,  // 0xd0
+    347, 348, 349, 350, 351, 352, 353, 354, 355, 356, 357, 358, 359, 360, 361, 362,  // 0xe0
+    363, 364, 365, 366, 367, 368, 369, 370, 371, 372, 373, 374, 375, 376, 377, 378,  // 0xf0
+];
+
+/// Returns the SID that the Standard Encoding gives the character code, or `None` if the code
+/// isn't encoded.
+#[inline]
+pub fn standard_encoding_sid(code: u8) -> Option<u16> {
+    match STANDARD_ENCODING[code as usize] {
+        0 => None,
+        sid => Some(sid as u16),
+    }
+}
+
+/// Returns the character code that has the given SID in the Standard Encoding, or `None` if the
+/// encoding doesn't include it.
+pub fn standard_encoding_code(sid: u16) -> Option<u8> {
+    if sid == 0 {
+        return None
+    }
+    STANDARD_ENCODING.iter().position(|&code_sid| code_sid as u16 == sid).map(|code| code as u8)
+}
+
+/// Returns the SID that the Expert Encoding gives the character code, or `None` if the code isn't
+/// encoded.
+#[inline]
+pub fn expert_encoding_sid(code: u8) -> Option<u16> {
+    match EXPERT_ENCODING[code as usize] {
+        0 => None,
+        sid => Some(sid),
+    }
+}
+
+/// Returns the character code that has the given SID in the Expert Encoding, or `None` if the
+/// encoding doesn't include it.
+pub fn expert_encoding_code(sid: u16) -> Option<u8> {
+    if sid == 0 {
+        return None
+    }
+    EXPERT_ENCODING.iter().position(|&code_sid| code_sid == sid).map(|code| code as u8)
+}
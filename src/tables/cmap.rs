@@ -12,10 +12,12 @@ use byteorder::{BigEndian, ReadBytesExt};
 use charmap::{CodepointRange, GlyphMapping, GlyphRange, MappedGlyphRange};
 use error::FontError;
 use font::FontTable;
+use std::char;
 use std::cmp;
+use std::collections::HashMap;
 use std::mem;
 use std::u16;
-use util::Jump;
+use util::{self, Jump};
 
 pub const TAG: u32 = ((b'c' as u32) << 24) |
                       ((b'm' as u32) << 16) |
@@ -25,14 +27,23 @@ pub const TAG: u32 = ((b'c' as u32) << 24) |
 const PLATFORM_ID_UNICODE: u16 = 0;
 const PLATFORM_ID_MICROSOFT: u16 = 3;
 
+const UNICODE_ENCODING_ID_UNICODE_2_0_FULL: u16 = 4;
+const UNICODE_ENCODING_ID_UNICODE_VARIATION_SEQUENCES: u16 = 5;
+const UNICODE_ENCODING_ID_UNICODE_FULL: u16 = 6;
+
 const MICROSOFT_ENCODING_ID_UNICODE_BMP: u16 = 1;
 const MICROSOFT_ENCODING_ID_UNICODE_UCS4: u16 = 10;
 
 const FORMAT_SEGMENT_MAPPING_TO_DELTA_VALUES: u16 = 4;
 const FORMAT_SEGMENTED_COVERAGE: u16 = 12;
+const FORMAT_UNICODE_VARIATION_SEQUENCES: u16 = 14;
 
 const MISSING_GLYPH: u16 = 0;
 
+// The size of a variation selector record in a format 14 table: a 24-bit selector and two 32-bit
+// offsets.
+const VARIATION_SELECTOR_RECORD_SIZE: usize = 11;
+
 #[derive(Clone, Copy)]
 pub struct CmapTable<'a> {
     table: FontTable<'a>,
@@ -45,8 +56,172 @@ impl<'a> CmapTable<'a> {
         }
     }
 
+    /// Returns the glyph for the given character, or `None` if the character isn't mapped.
+    ///
+    /// Unmapped characters should usually be drawn with the missing glyph, glyph 0 (`.notdef`).
+    pub fn glyph_for_char(&self, character: char) -> Result<Option<u16>, FontError> {
+        let codepoint_ranges = [CodepointRange::new(character as u32, character as u32)];
+        let glyph_mapping = try!(self.glyph_mapping_for_codepoint_ranges(&codepoint_ranges));
+        match glyph_mapping.glyph_for(character as u32) {
+            None | Some(MISSING_GLYPH) => Ok(None),
+            Some(glyph_id) => Ok(Some(glyph_id)),
+        }
+    }
+
+    /// Returns the glyph for the given character followed by the given variation selector (for
+    /// example, U+FE0E or U+FE0F to request text or emoji presentation).
+    ///
+    /// If the font maps this variation sequence to the character's usual glyph, that glyph is
+    /// returned. If the font doesn't support the sequence, returns `None`.
+    pub fn glyph_for_char_variant(&self, base: char, selector: char)
+                                  -> Result<Option<u16>, FontError> {
+        let subtable = match try!(self.variation_sequence_subtable()) {
+            None => return Ok(None),
+            Some(subtable) => subtable,
+        };
+        let (codepoint, selector) = (base as u32, selector as u32);
+
+        // Read the header.
+        let mut reader = subtable;
+        let _format = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+        let _length = try!(reader.read_u32::<BigEndian>().map_err(FontError::eof));
+        let num_var_selector_records = try!(reader.read_u32::<BigEndian>()
+                                                  .map_err(FontError::eof));
+
+        // Binary search to find the variation selector record.
+        let (mut low, mut high) = (0, num_var_selector_records);
+        while low < high {
+            let mid = (low + high) / 2;
+
+            let mut record = reader;
+            try!(record.jump(mid as usize * VARIATION_SELECTOR_RECORD_SIZE)
+                       .map_err(FontError::eof));
+            let var_selector = try!(record.read_u24::<BigEndian>().map_err(FontError::eof));
+            if selector < var_selector {
+                high = mid;
+                continue
+            }
+            if selector > var_selector {
+                low = mid + 1;
+                continue
+            }
+
+            let default_uvs_offset = try!(record.read_u32::<BigEndian>().map_err(FontError::eof));
+            let non_default_uvs_offset = try!(record.read_u32::<BigEndian>()
+                                                    .map_err(FontError::eof));
+
+            // Sequences with their own glyphs are in the non-default UVS table.
+            if non_default_uvs_offset != 0 {
+                let mut mappings = subtable;
+                try!(mappings.jump(non_default_uvs_offset as usize).map_err(FontError::eof));
+                let num_mappings = try!(mappings.read_u32::<BigEndian>().map_err(FontError::eof));
+                let (mut low, mut high) = (0, num_mappings);
+                while low < high {
+                    let mid = (low + high) / 2;
+                    let mut mapping = mappings;
+                    try!(mapping.jump(mid as usize * 5).map_err(FontError::eof));
+                    let unicode_value = try!(mapping.read_u24::<BigEndian>()
+                                                    .map_err(FontError::eof));
+                    if codepoint < unicode_value {
+                        high = mid
+                    } else if codepoint > unicode_value {
+                        low = mid + 1
+                    } else {
+                        let glyph_id = try!(mapping.read_u16::<BigEndian>()
+                                                   .map_err(FontError::eof));
+                        return Ok(Some(glyph_id))
+                    }
+                }
+            }
+
+            // Sequences that use the character's usual glyph are in the default UVS table.
+            if default_uvs_offset != 0 {
+                let mut ranges = subtable;
+                try!(ranges.jump(default_uvs_offset as usize).map_err(FontError::eof));
+                let num_ranges = try!(ranges.read_u32::<BigEndian>().map_err(FontError::eof));
+                let (mut low, mut high) = (0, num_ranges);
+                while low < high {
+                    let mid = (low + high) / 2;
+                    let mut range = ranges;
+                    try!(range.jump(mid as usize * 4).map_err(FontError::eof));
+                    let start = try!(range.read_u24::<BigEndian>().map_err(FontError::eof));
+                    let additional_count = try!(range.read_u8().map_err(FontError::eof));
+                    if codepoint < start {
+                        high = mid
+                    } else if codepoint > start + additional_count as u32 {
+                        low = mid + 1
+                    } else {
+                        return self.glyph_for_char(base)
+                    }
+                }
+            }
+
+            return Ok(None)
+        }
+
+        Ok(None)
+    }
+
     pub fn glyph_mapping_for_codepoint_ranges(&self, codepoint_ranges: &[CodepointRange])
                                               -> Result<GlyphMapping, FontError> {
+        let mut cmap_reader = try!(self.unicode_subtable());
+
+        // Check the mapping table format.
+        let format = try!(cmap_reader.read_u16::<BigEndian>().map_err(FontError::eof));
+        match format {
+            FORMAT_SEGMENT_MAPPING_TO_DELTA_VALUES => {
+                self.glyph_mapping_for_codepoint_ranges_segment_mapping_format(cmap_reader,
+                                                                               codepoint_ranges)
+            }
+            FORMAT_SEGMENTED_COVERAGE => {
+                self.glyph_mapping_for_codepoint_ranges_segmented_coverage(cmap_reader,
+                                                                           codepoint_ranges)
+            }
+            _ => Err(FontError::UnsupportedCmapFormat),
+        }
+    }
+
+    /// Returns a map from each mapped glyph back to the character that maps to it.
+    ///
+    /// When several characters map to the same glyph, the lowest code point wins.
+    pub fn build_reverse(&self) -> Result<HashMap<u16, char>, FontError> {
+        let mut reverse = HashMap::new();
+        try!(self.for_each_mapping(|character, glyph_id| {
+            let entry = reverse.entry(glyph_id).or_insert(character);
+            *entry = cmp::min(*entry, character)
+        }));
+        Ok(reverse)
+    }
+
+    /// Calls the given function with every character that maps to a glyph other than the
+    /// missing glyph, along with that glyph, in the order that the mapping table lists them.
+    pub fn for_each_mapping<F>(&self, mut callback: F) -> Result<(), FontError>
+                               where F: FnMut(char, u16) {
+        let mut cmap_reader = try!(self.unicode_subtable());
+        let mut mapping = |codepoint: u32, glyph_id: u16| {
+            match char::from_u32(codepoint) {
+                Some(character) if glyph_id != MISSING_GLYPH => callback(character, glyph_id),
+                _ => {}
+            }
+        };
+
+        let format = try!(cmap_reader.read_u16::<BigEndian>().map_err(FontError::eof));
+        match format {
+            FORMAT_SEGMENT_MAPPING_TO_DELTA_VALUES => {
+                self.for_each_mapping_segment_mapping_format(cmap_reader, &mut mapping)
+            }
+            FORMAT_SEGMENTED_COVERAGE => {
+                self.for_each_mapping_segmented_coverage(cmap_reader, &mut mapping)
+            }
+            _ => Err(FontError::UnsupportedCmapFormat),
+        }
+    }
+
+    // Returns the best Unicode mapping table that we support, positioned at its format.
+    //
+    // Full-repertoire tables are preferred to BMP-only ones, and Microsoft tables are preferred to
+    // Unicode platform ones.
+    fn unicode_subtable(&self) -> Result<&'a [u8], FontError> {
         let mut cmap_reader = self.table.bytes;
 
         // Check version.
@@ -56,44 +231,72 @@ impl<'a> CmapTable<'a> {
 
         let num_tables = try!(cmap_reader.read_u16::<BigEndian>().map_err(FontError::eof));
 
-        // Check platform ID and encoding.
-        // TODO(pcwalton): Handle more.
-        let mut table_found = false;
+        // Check platform ID and encoding, and rank the tables. Lower ranks are better.
+        let (mut best_subtable, mut unicode_subtable_found) = (None, false);
         for _ in 0..num_tables {
             let platform_id = try!(cmap_reader.read_u16::<BigEndian>().map_err(FontError::eof));
             let encoding_id = try!(cmap_reader.read_u16::<BigEndian>().map_err(FontError::eof));
             let offset = try!(cmap_reader.read_u32::<BigEndian>().map_err(FontError::eof));
-            match (platform_id, encoding_id) {
-                (PLATFORM_ID_UNICODE, _) |
-                (PLATFORM_ID_MICROSOFT, MICROSOFT_ENCODING_ID_UNICODE_BMP) |
-                (PLATFORM_ID_MICROSOFT, MICROSOFT_ENCODING_ID_UNICODE_UCS4) => {
-                    // Move to the mapping table.
-                    cmap_reader = self.table.bytes;
-                    try!(cmap_reader.jump(offset as usize).map_err(FontError::eof));
-                    table_found = true;
-                    break
-                }
-                _ => {}
+
+            let rank = match (platform_id, encoding_id) {
+                (PLATFORM_ID_MICROSOFT, MICROSOFT_ENCODING_ID_UNICODE_UCS4) => 0,
+                (PLATFORM_ID_UNICODE, UNICODE_ENCODING_ID_UNICODE_2_0_FULL) |
+                (PLATFORM_ID_UNICODE, UNICODE_ENCODING_ID_UNICODE_FULL) => 1,
+                (PLATFORM_ID_MICROSOFT, MICROSOFT_ENCODING_ID_UNICODE_BMP) => 2,
+                (PLATFORM_ID_UNICODE, _) => 3,
+                _ => continue,
+            };
+
+            // Skip tables in formats we don't support, such as variation sequences.
+            unicode_subtable_found = true;
+            let mut subtable = self.table.bytes;
+            try!(subtable.jump(offset as usize).map_err(FontError::eof));
+            let mut format_reader = subtable;
+            match try!(format_reader.read_u16::<BigEndian>().map_err(FontError::eof)) {
+                FORMAT_SEGMENT_MAPPING_TO_DELTA_VALUES | FORMAT_SEGMENTED_COVERAGE => {}
+                _ => continue,
+            }
+
+            match best_subtable {
+                Some((best_rank, _)) if best_rank <= rank => {}
+                _ => best_subtable = Some((rank, subtable)),
             }
         }
 
-        if !table_found {
-            return Err(FontError::UnsupportedCmapEncoding)
+        match best_subtable {
+            Some((_, subtable)) => Ok(subtable),
+            None if unicode_subtable_found => Err(FontError::UnsupportedCmapFormat),
+            None => Err(FontError::UnsupportedCmapEncoding),
         }
+    }
 
-        // Check the mapping table format.
-        let format = try!(cmap_reader.read_u16::<BigEndian>().map_err(FontError::eof));
-        match format {
-            FORMAT_SEGMENT_MAPPING_TO_DELTA_VALUES => {
-                self.glyph_mapping_for_codepoint_ranges_segment_mapping_format(cmap_reader,
-                                                                               codepoint_ranges)
+    // Returns the Unicode Variation Sequences table, if there is one.
+    fn variation_sequence_subtable(&self) -> Result<Option<&'a [u8]>, FontError> {
+        let mut cmap_reader = self.table.bytes;
+        if try!(cmap_reader.read_u16::<BigEndian>().map_err(FontError::eof)) != 0 {
+            return Err(FontError::UnsupportedCmapVersion)
+        }
+
+        let num_tables = try!(cmap_reader.read_u16::<BigEndian>().map_err(FontError::eof));
+        for _ in 0..num_tables {
+            let platform_id = try!(cmap_reader.read_u16::<BigEndian>().map_err(FontError::eof));
+            let encoding_id = try!(cmap_reader.read_u16::<BigEndian>().map_err(FontError::eof));
+            let offset = try!(cmap_reader.read_u32::<BigEndian>().map_err(FontError::eof));
+            if (platform_id, encoding_id) !=
+                    (PLATFORM_ID_UNICODE, UNICODE_ENCODING_ID_UNICODE_VARIATION_SEQUENCES) {
+                continue
             }
-            FORMAT_SEGMENTED_COVERAGE => {
-                self.glyph_mapping_for_codepoint_ranges_segmented_coverage(cmap_reader,
-                                                                           codepoint_ranges)
+
+            let mut subtable = self.table.bytes;
+            try!(subtable.jump(offset as usize).map_err(FontError::eof));
+            let mut format_reader = subtable;
+            let format = try!(format_reader.read_u16::<BigEndian>().map_err(FontError::eof));
+            if format == FORMAT_UNICODE_VARIATION_SEQUENCES {
+                return Ok(Some(subtable))
             }
-            _ => Err(FontError::UnsupportedCmapFormat),
         }
+
+        Ok(None)
     }
 
     fn glyph_mapping_for_codepoint_ranges_segment_mapping_format(
@@ -254,6 +457,85 @@ impl<'a> CmapTable<'a> {
         Ok(glyph_mapping)
     }
 
+    // Calls the given function with every character and glyph mapped by a format 4 subtable.
+    fn for_each_mapping_segment_mapping_format<F>(&self, mut cmap_reader: &[u8], callback: &mut F)
+                                                  -> Result<(), FontError>
+                                                  where F: FnMut(u32, u16) {
+        let _length = try!(cmap_reader.read_u16::<BigEndian>().map_err(FontError::eof));
+        let _language = try!(cmap_reader.read_u16::<BigEndian>().map_err(FontError::eof));
+        let seg_count = try!(cmap_reader.read_u16::<BigEndian>().map_err(FontError::eof)) / 2;
+        try!(cmap_reader.jump(3 * mem::size_of::<u16>()).map_err(FontError::eof));
+
+        // Set up parallel array pointers, as in the forward lookup.
+        let array_size = seg_count as usize * mem::size_of::<u16>();
+        let end_codes = cmap_reader;
+        let mut start_codes = end_codes;
+        try!(start_codes.jump(array_size + mem::size_of::<u16>()).map_err(FontError::eof));
+        let mut id_deltas = start_codes;
+        try!(id_deltas.jump(array_size).map_err(FontError::eof));
+        let mut id_range_offsets = id_deltas;
+        try!(id_range_offsets.jump(array_size).map_err(FontError::eof));
+
+        let (mut end_code, mut start_code) = (end_codes, start_codes);
+        let (mut id_delta, mut id_range_offset) = (id_deltas, id_range_offsets);
+        for segment_index in 0..(seg_count as usize) {
+            let end = try!(end_code.read_u16::<BigEndian>().map_err(FontError::eof));
+            let start = try!(start_code.read_u16::<BigEndian>().map_err(FontError::eof));
+            let delta = try!(id_delta.read_i16::<BigEndian>().map_err(FontError::eof));
+            let range_offset = try!(id_range_offset.read_u16::<BigEndian>()
+                                                   .map_err(FontError::eof));
+            if start > end {
+                continue
+            }
+
+            for codepoint in (start as u32)..(end as u32 + 1) {
+                let glyph_id = if range_offset == 0 {
+                    (codepoint as i16).wrapping_add(delta) as u16
+                } else {
+                    let mut reader = id_range_offsets;
+                    try!(reader.jump(segment_index * 2 + (codepoint - start as u32) as usize * 2 +
+                                     range_offset as usize).map_err(FontError::eof));
+                    match try!(reader.read_u16::<BigEndian>().map_err(FontError::eof)) {
+                        MISSING_GLYPH => continue,
+                        glyph_id => (glyph_id as i16).wrapping_add(delta) as u16,
+                    }
+                };
+                callback(codepoint, glyph_id)
+            }
+        }
+        Ok(())
+    }
+
+    // Calls the given function with every character and glyph mapped by a format 12 subtable.
+    fn for_each_mapping_segmented_coverage<F>(&self, mut cmap_reader: &[u8], callback: &mut F)
+                                              -> Result<(), FontError>
+                                              where F: FnMut(u32, u16) {
+        let _reserved = try!(cmap_reader.read_u16::<BigEndian>().map_err(FontError::eof));
+        let _length = try!(cmap_reader.read_u32::<BigEndian>().map_err(FontError::eof));
+        let _language = try!(cmap_reader.read_u32::<BigEndian>().map_err(FontError::eof));
+        let num_groups = try!(cmap_reader.read_u32::<BigEndian>().map_err(FontError::eof));
+
+        for _ in 0..num_groups {
+            let start_char_code = try!(cmap_reader.read_u32::<BigEndian>()
+                                                  .map_err(FontError::eof));
+            let end_char_code = try!(cmap_reader.read_u32::<BigEndian>().map_err(FontError::eof));
+            let start_glyph_id = try!(cmap_reader.read_u32::<BigEndian>()
+                                                 .map_err(FontError::eof));
+
+            // Stop at the end of Unicode or of the glyph IDs, so that bogus groups can't run on.
+            let end_char_code = cmp::min(end_char_code, char::MAX as u32);
+            let mut glyph_id = start_glyph_id;
+            for codepoint in start_char_code..end_char_code.saturating_add(1) {
+                if glyph_id > u16::MAX as u32 {
+                    break
+                }
+                callback(codepoint, glyph_id as u16);
+                glyph_id += 1
+            }
+        }
+        Ok(())
+    }
+
     fn glyph_mapping_for_codepoint_ranges_segmented_coverage(&self,
                                                              mut cmap_reader: &[u8],
                                                              codepoint_ranges: &[CodepointRange])
@@ -335,3 +617,92 @@ struct Segment {
     start_glyph_id: u32,
 }
 
+
+/// Writes a `cmap` table for the given mappings from characters to glyphs, which must be sorted
+/// by character.
+///
+/// The table has a Microsoft BMP subtable in format 4 and a Microsoft full-repertoire subtable in
+/// format 12, so that it works both where only BMP tables are understood and for characters
+/// outside the BMP.
+pub fn write_cmap(mappings: &[(char, u16)]) -> Vec<u8> {
+    // Find the runs of consecutive characters that map to consecutive glyphs.
+    let mut groups: Vec<(u32, u32, u16)> = vec![];
+    for &(character, glyph_id) in mappings {
+        let codepoint = character as u32;
+        if let Some(group) = groups.last_mut() {
+            let (start, end, start_glyph_id) = *group;
+            let next_glyph_id = start_glyph_id as u32 + codepoint - start;
+            if codepoint == end + 1 && glyph_id as u32 == next_glyph_id {
+                group.1 = codepoint;
+                continue
+            }
+        }
+        groups.push((codepoint, codepoint, glyph_id))
+    }
+
+    // Format 4 segments can't cover U+FFFF, since that's the final segment.
+    let mut segments: Vec<(u16, u16, u16)> = vec![];
+    for &(start, end, start_glyph_id) in &groups {
+        if start >= u16::MAX as u32 {
+            break
+        }
+        let end = cmp::min(end, u16::MAX as u32 - 1);
+        segments.push((start as u16, end as u16, start_glyph_id.wrapping_sub(start as u16)))
+    }
+    segments.push((u16::MAX, u16::MAX, 1));
+
+    let seg_count = segments.len() as u16;
+    let mut format_4 = vec![];
+    let (search_range, entry_selector, range_shift) =
+        util::binary_search_parameters(seg_count, mem::size_of::<u16>() as u16);
+    for &value in &[FORMAT_SEGMENT_MAPPING_TO_DELTA_VALUES,
+                    16 + 8 * seg_count,
+                    0,
+                    seg_count * 2,
+                    search_range,
+                    entry_selector,
+                    range_shift] {
+        util::push_u16(&mut format_4, value)
+    }
+    for &(_, end, _) in &segments {
+        util::push_u16(&mut format_4, end)
+    }
+    util::push_u16(&mut format_4, 0);
+    for &(start, _, _) in &segments {
+        util::push_u16(&mut format_4, start)
+    }
+    for &(_, _, id_delta) in &segments {
+        util::push_u16(&mut format_4, id_delta)
+    }
+    for _ in &segments {
+        util::push_u16(&mut format_4, 0)
+    }
+
+    let mut format_12 = vec![];
+    util::push_u16(&mut format_12, FORMAT_SEGMENTED_COVERAGE);
+    util::push_u16(&mut format_12, 0);
+    util::push_u32(&mut format_12, 16 + 12 * groups.len() as u32);
+    util::push_u32(&mut format_12, 0);
+    util::push_u32(&mut format_12, groups.len() as u32);
+    for &(start, end, start_glyph_id) in &groups {
+        util::push_u32(&mut format_12, start);
+        util::push_u32(&mut format_12, end);
+        util::push_u32(&mut format_12, start_glyph_id as u32)
+    }
+
+    // The header, then the encoding records in order of platform and encoding.
+    let mut cmap = vec![];
+    util::push_u16(&mut cmap, 0);
+    util::push_u16(&mut cmap, 2);
+    let format_4_offset = 4 + 2 * 8;
+    for &(encoding_id, offset) in &[(MICROSOFT_ENCODING_ID_UNICODE_BMP, format_4_offset),
+                                    (MICROSOFT_ENCODING_ID_UNICODE_UCS4,
+                                     format_4_offset + format_4.len())] {
+        util::push_u16(&mut cmap, PLATFORM_ID_MICROSOFT);
+        util::push_u16(&mut cmap, encoding_id);
+        util::push_u32(&mut cmap, offset as u32)
+    }
+    cmap.extend_from_slice(&format_4);
+    cmap.extend_from_slice(&format_12);
+    cmap
+}
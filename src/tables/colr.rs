@@ -0,0 +1,124 @@
+// Copyright 2017 The Servo Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+use byteorder::{BigEndian, ReadBytesExt};
+use error::FontError;
+use font::{ColorIndex, FontTable};
+use util::Jump;
+
+pub const TAG: u32 = ((b'C' as u32) << 24) |
+                      ((b'O' as u32) << 16) |
+                      ((b'L' as u32) << 8)  |
+                       (b'R' as u32);
+
+// Version 1 extends version 0 without changing its header or records, so we can read the layered
+// glyphs of both.
+const MAX_SUPPORTED_VERSION: u16 = 1;
+
+const BASE_GLYPH_RECORD_SIZE: usize = 6;
+const LAYER_RECORD_SIZE: usize = 4;
+
+// The palette index that means "use the text color".
+const PALETTE_INDEX_FOREGROUND: u16 = 0xffff;
+
+#[derive(Clone, Copy, Debug)]
+pub struct ColrTable<'a> {
+    base_glyph_records: &'a [u8],
+    num_base_glyph_records: u16,
+    layer_records: &'a [u8],
+    num_layer_records: u16,
+}
+
+impl<'a> ColrTable<'a> {
+    pub fn new(table: FontTable<'a>) -> Result<ColrTable<'a>, FontError> {
+        let mut reader = table.bytes;
+
+        // Check the version.
+        let version = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+        if version > MAX_SUPPORTED_VERSION {
+            return Err(FontError::UnsupportedColrVersion)
+        }
+
+        let num_base_glyph_records = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+        let base_glyph_records_offset = try!(reader.read_u32::<BigEndian>()
+                                                   .map_err(FontError::eof));
+        let layer_records_offset = try!(reader.read_u32::<BigEndian>().map_err(FontError::eof));
+        let num_layer_records = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+
+        let mut base_glyph_records = table.bytes;
+        try!(base_glyph_records.jump(base_glyph_records_offset as usize).map_err(FontError::eof));
+        let mut layer_records = table.bytes;
+        try!(layer_records.jump(layer_records_offset as usize).map_err(FontError::eof));
+
+        Ok(ColrTable {
+            base_glyph_records: base_glyph_records,
+            num_base_glyph_records: num_base_glyph_records,
+            layer_records: layer_records,
+            num_layer_records: num_layer_records,
+        })
+    }
+
+    /// Returns the layers that make up the given color glyph, bottommost first, as pairs of a
+    /// glyph ID and the color to fill that glyph's outline with.
+    ///
+    /// Returns `None` if the glyph has no color layers, in which case it should be drawn with
+    /// its own outline in the text color.
+    pub fn color_layers(&self, base_glyph_id: u16) -> Option<Vec<(u16, ColorIndex)>> {
+        self.color_layers_for_glyph(base_glyph_id).unwrap_or(None)
+    }
+
+    fn color_layers_for_glyph(&self, base_glyph_id: u16)
+                              -> Result<Option<Vec<(u16, ColorIndex)>>, FontError> {
+        let (first_layer_index, num_layers) = match try!(self.base_glyph_record(base_glyph_id)) {
+            None => return Ok(None),
+            Some(record) => record,
+        };
+        if first_layer_index as u32 + num_layers as u32 > self.num_layer_records as u32 {
+            return Err(FontError::UnexpectedEof)
+        }
+
+        let mut reader = self.layer_records;
+        try!(reader.jump(LAYER_RECORD_SIZE * first_layer_index as usize).map_err(FontError::eof));
+
+        let mut layers = Vec::with_capacity(num_layers as usize);
+        for _ in 0..num_layers {
+            let glyph_id = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+            let palette_index = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+            let color = match palette_index {
+                PALETTE_INDEX_FOREGROUND => ColorIndex::Foreground,
+                palette_index => ColorIndex::Palette(palette_index),
+            };
+            layers.push((glyph_id, color))
+        }
+        Ok(Some(layers))
+    }
+
+    // Returns the first layer index and the number of layers for the given base glyph.
+    fn base_glyph_record(&self, base_glyph_id: u16) -> Result<Option<(u16, u16)>, FontError> {
+        // Binary search the base glyph records, which are sorted by glyph ID.
+        let (mut low, mut high) = (0, self.num_base_glyph_records as usize);
+        while low < high {
+            let mid = (low + high) / 2;
+            let mut reader = self.base_glyph_records;
+            try!(reader.jump(mid * BASE_GLYPH_RECORD_SIZE).map_err(FontError::eof));
+            let glyph_id = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+            if base_glyph_id < glyph_id {
+                high = mid
+            } else if base_glyph_id > glyph_id {
+                low = mid + 1
+            } else {
+                let first_layer_index = try!(reader.read_u16::<BigEndian>()
+                                                   .map_err(FontError::eof));
+                let num_layers = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+                return Ok(Some((first_layer_index, num_layers)))
+            }
+        }
+        Ok(None)
+    }
+}
@@ -0,0 +1,98 @@
+// Copyright 2017 The Servo Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+use byteorder::{BigEndian, ReadBytesExt};
+use error::FontError;
+use font::FontTable;
+use std::mem;
+use util::Jump;
+
+pub const TAG: u32 = ((b'C' as u32) << 24) |
+                      ((b'P' as u32) << 16) |
+                      ((b'A' as u32) << 8)  |
+                       (b'L' as u32);
+
+// Version 1 only appends offsets to optional arrays after the version 0 header.
+const MAX_SUPPORTED_VERSION: u16 = 1;
+
+const COLOR_RECORD_SIZE: usize = 4;
+
+#[derive(Clone, Copy, Debug)]
+pub struct CpalTable<'a> {
+    num_palette_entries: u16,
+    num_palettes: u16,
+    num_color_records: u16,
+    color_record_indices: &'a [u8],
+    color_records: &'a [u8],
+}
+
+impl<'a> CpalTable<'a> {
+    pub fn new(table: FontTable<'a>) -> Result<CpalTable<'a>, FontError> {
+        let mut reader = table.bytes;
+
+        // Check the version.
+        let version = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+        if version > MAX_SUPPORTED_VERSION {
+            return Err(FontError::UnsupportedCpalVersion)
+        }
+
+        let num_palette_entries = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+        let num_palettes = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+        let num_color_records = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+        let color_records_offset = try!(reader.read_u32::<BigEndian>().map_err(FontError::eof));
+
+        let mut color_records = table.bytes;
+        try!(color_records.jump(color_records_offset as usize).map_err(FontError::eof));
+
+        Ok(CpalTable {
+            num_palette_entries: num_palette_entries,
+            num_palettes: num_palettes,
+            num_color_records: num_color_records,
+            color_record_indices: reader,
+            color_records: color_records,
+        })
+    }
+
+    /// The number of palettes in the font. Palette 0 is the default.
+    #[inline]
+    pub fn num_palettes(&self) -> u16 {
+        self.num_palettes
+    }
+
+    /// Returns the color of the given entry in the given palette as RGBA, with unpremultiplied
+    /// alpha.
+    ///
+    /// Returns `None` if there is no such palette or entry.
+    pub fn palette_color(&self, palette: u16, index: u16) -> Option<[u8; 4]> {
+        if palette >= self.num_palettes || index >= self.num_palette_entries {
+            return None
+        }
+        self.color_record(palette, index).ok()
+    }
+
+    fn color_record(&self, palette: u16, index: u16) -> Result<[u8; 4], FontError> {
+        let mut reader = self.color_record_indices;
+        try!(reader.jump(mem::size_of::<u16>() * palette as usize).map_err(FontError::eof));
+        let first_color_index = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+
+        let color_index = first_color_index as usize + index as usize;
+        if color_index >= self.num_color_records as usize {
+            return Err(FontError::UnexpectedEof)
+        }
+
+        // Color records are stored as BGRA.
+        let mut reader = self.color_records;
+        try!(reader.jump(COLOR_RECORD_SIZE * color_index).map_err(FontError::eof));
+        let blue = try!(reader.read_u8().map_err(FontError::eof));
+        let green = try!(reader.read_u8().map_err(FontError::eof));
+        let red = try!(reader.read_u8().map_err(FontError::eof));
+        let alpha = try!(reader.read_u8().map_err(FontError::eof));
+        Ok([red, green, blue, alpha])
+    }
+}
@@ -0,0 +1,91 @@
+// Copyright 2017 The Servo Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! The digital signature table.
+//!
+//! We only look at the structure of the table; signatures aren't verified.
+//!
+//! See Microsoft's spec: https://www.microsoft.com/typography/otspec/dsig.htm
+
+use error::FontError;
+use font::FontTable;
+use util::{EofContext, Reader};
+
+pub const TAG: u32 = ((b'D' as u32) << 24) |
+                      ((b'S' as u32) << 16) |
+                      ((b'I' as u32) << 8)  |
+                       (b'G' as u32);
+
+const VERSION: u32 = 1;
+
+#[derive(Clone, Copy, Debug)]
+pub struct DsigTable {
+    version: u32,
+    num_signatures: u16,
+    flags: u16,
+    has_signature_block: bool,
+}
+
+impl DsigTable {
+    pub fn new(table: FontTable) -> Result<DsigTable, FontError> {
+        let mut reader = Reader::new(table.bytes);
+
+        // Check the version.
+        let version = try!(reader.read_u32_be().eof_in(TAG, "version"));
+        if version != VERSION {
+            return Err(FontError::UnsupportedDsigVersion)
+        }
+
+        let num_signatures = try!(reader.read_u16_be().eof_in(TAG, "number of signatures"));
+        let flags = try!(reader.read_u16_be().eof_in(TAG, "flags"));
+
+        // A signature block is present if any record points to a nonempty block inside the table.
+        let mut has_signature_block = false;
+        for _ in 0..num_signatures {
+            let _format = try!(reader.read_u32_be().eof_in(TAG, "signature format"));
+            let length = try!(reader.read_u32_be().eof_in(TAG, "signature length"));
+            let offset = try!(reader.read_u32_be().eof_in(TAG, "signature offset"));
+            if length > 0 && offset as u64 + length as u64 <= table.bytes.len() as u64 {
+                has_signature_block = true
+            }
+        }
+
+        Ok(DsigTable {
+            version: version,
+            num_signatures: num_signatures,
+            flags: flags,
+            has_signature_block: has_signature_block,
+        })
+    }
+
+    #[inline]
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+
+    /// The number of signature records. Fonts that only need a `DSIG` table to be present often
+    /// have a "null" signature table with none.
+    #[inline]
+    pub fn num_signatures(&self) -> u16 {
+        self.num_signatures
+    }
+
+    /// The permission flags. Bit 0 means that the font may not be resigned.
+    #[inline]
+    pub fn flags(&self) -> u16 {
+        self.flags
+    }
+
+    /// Whether the table contains an actual signature, rather than being a null signature table.
+    #[inline]
+    pub fn has_signature_block(&self) -> bool {
+        self.has_signature_block
+    }
+}
@@ -0,0 +1,114 @@
+// Copyright 2017 The Servo Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! The embedded bitmap data table, which holds monochrome and grayscale glyph bitmaps.
+//!
+//! See Microsoft's spec: https://www.microsoft.com/typography/otspec/ebdt.htm
+
+use byteorder::{BigEndian, ReadBytesExt};
+use error::{ErrorContext, FontError};
+use font::{BitmapMetrics, EmbeddedBitmap, FontTable};
+use tables::cblc::{self, CblcTable};
+use util::EofContext;
+
+pub const TAG: u32 = ((b'E' as u32) << 24) |
+                      ((b'B' as u32) << 16) |
+                      ((b'D' as u32) << 8)  |
+                       (b'T' as u32);
+
+const SUPPORTED_MAJOR_VERSION: u16 = 2;
+
+// The glyph image formats that hold a single bitmap. The others are composites of other glyphs.
+const IMAGE_FORMAT_SMALL_METRICS_BYTE_ALIGNED: u16 = 1;
+const IMAGE_FORMAT_SMALL_METRICS_BIT_ALIGNED: u16 = 2;
+const IMAGE_FORMAT_BIT_ALIGNED: u16 = 5;
+const IMAGE_FORMAT_BIG_METRICS_BYTE_ALIGNED: u16 = 6;
+const IMAGE_FORMAT_BIG_METRICS_BIT_ALIGNED: u16 = 7;
+
+#[derive(Clone, Copy, Debug)]
+pub struct EbdtTable<'a> {
+    table: FontTable<'a>,
+}
+
+impl<'a> EbdtTable<'a> {
+    pub fn new(table: FontTable<'a>) -> Result<EbdtTable<'a>, FontError> {
+        let mut reader = table.bytes;
+
+        // Check the version.
+        let major_version = try!(reader.read_u16::<BigEndian>().eof_in(TAG, "major version"));
+        if major_version != SUPPORTED_MAJOR_VERSION {
+            return Err(FontError::UnsupportedEbdtVersion)
+        }
+
+        Ok(EbdtTable {
+            table: table,
+        })
+    }
+
+    /// Returns the bitmap of the given glyph, along with its metrics, from the strike best suited
+    /// to the given size, using the index in the `EBLC` table.
+    ///
+    /// See `CblcTable::glyph_location()` for how the strike is chosen. The pixels are left packed
+    /// as they are in the font. Returns `None` if the glyph has no bitmap in that strike or its
+    /// bitmap is a composite.
+    pub fn glyph_bitmap(&self, eblc_table: &CblcTable, glyph_id: u16, ppem: u16)
+                        -> Result<Option<EmbeddedBitmap<'a>>, FontError> {
+        let location = match try!(eblc_table.glyph_location(glyph_id, ppem)) {
+            None => return Ok(None),
+            Some(location) => location,
+        };
+
+        let mut reader = try!(self.table
+                                  .subslice(location.offset as usize, location.length as usize)
+                                  .eof_in(TAG, "glyph bitmap"));
+
+        let (metrics, bit_aligned) = match location.image_format {
+            IMAGE_FORMAT_SMALL_METRICS_BYTE_ALIGNED | IMAGE_FORMAT_SMALL_METRICS_BIT_ALIGNED => {
+                let metrics = try!(cblc::read_small_glyph_metrics(&mut reader));
+                (BitmapMetrics::Small(metrics),
+                 location.image_format == IMAGE_FORMAT_SMALL_METRICS_BIT_ALIGNED)
+            }
+            IMAGE_FORMAT_BIG_METRICS_BYTE_ALIGNED | IMAGE_FORMAT_BIG_METRICS_BIT_ALIGNED => {
+                let metrics = try!(cblc::read_big_glyph_metrics(&mut reader));
+                (BitmapMetrics::Big(metrics),
+                 location.image_format == IMAGE_FORMAT_BIG_METRICS_BIT_ALIGNED)
+            }
+            // Every glyph in the index subtable shares the metrics that it records.
+            IMAGE_FORMAT_BIT_ALIGNED => {
+                match location.metrics {
+                    None => return Ok(None),
+                    Some(metrics) => (BitmapMetrics::Big(metrics), true),
+                }
+            }
+            _ => return Ok(None),
+        };
+
+        let (width, height) = (metrics.width() as usize, metrics.height() as usize);
+        let bit_depth = location.bit_depth as usize;
+        let data_len = if bit_aligned {
+            (width * height * bit_depth + 7) / 8
+        } else {
+            (width * bit_depth + 7) / 8 * height
+        };
+        if data_len > reader.len() {
+            return Err(FontError::UnexpectedEofIn(ErrorContext {
+                table: TAG,
+                field: "glyph bitmap data",
+            }))
+        }
+
+        Ok(Some(EmbeddedBitmap {
+            metrics: metrics,
+            bit_depth: location.bit_depth,
+            bit_aligned: bit_aligned,
+            data: &reader[..data_len],
+        }))
+    }
+}
@@ -0,0 +1,138 @@
+// Copyright 2017 The Servo Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+use byteorder::{BigEndian, ReadBytesExt};
+use error::FontError;
+use font::{FontTable, NamedInstance, VariationAxis};
+use std::mem;
+use util::Jump;
+
+pub const TAG: u32 = ((b'f' as u32) << 24) |
+                      ((b'v' as u32) << 16) |
+                      ((b'a' as u32) << 8)  |
+                       (b'r' as u32);
+
+const MAJOR_VERSION: u16 = 1;
+
+// The size of an axis record, which later minor versions may extend.
+const MIN_AXIS_SIZE: usize = 20;
+
+// The size of an instance record without its coordinates or optional PostScript name ID.
+const INSTANCE_HEADER_SIZE: usize = 4;
+
+// A PostScript name ID that means the instance has no PostScript name.
+const NO_POSTSCRIPT_NAME_ID: u16 = 0xffff;
+
+#[derive(Clone, Debug)]
+pub struct FvarTable {
+    axes: Vec<VariationAxis>,
+    named_instances: Vec<NamedInstance>,
+}
+
+impl FvarTable {
+    pub fn new(table: FontTable) -> Result<FvarTable, FontError> {
+        let mut reader = table.bytes;
+
+        // Check the version.
+        let major_version = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+        let _minor_version = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+        if major_version != MAJOR_VERSION {
+            return Err(FontError::UnsupportedFvarVersion)
+        }
+
+        let axes_array_offset = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+        let _reserved = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+        let axis_count = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+        let axis_size = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+        let instance_count = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+        let instance_size = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+
+        let coordinates_size = axis_count as usize * mem::size_of::<i32>();
+        if (axis_size as usize) < MIN_AXIS_SIZE ||
+                (instance_size as usize) < INSTANCE_HEADER_SIZE + coordinates_size {
+            return Err(FontError::Failed)
+        }
+        let has_postscript_name_ids = instance_size as usize >=
+            INSTANCE_HEADER_SIZE + coordinates_size + mem::size_of::<u16>();
+
+        // Read the axes.
+        let mut reader = table.bytes;
+        try!(reader.jump(axes_array_offset as usize).map_err(FontError::eof));
+        let mut axes = Vec::with_capacity(axis_count as usize);
+        for _ in 0..axis_count {
+            let mut axis = reader;
+            try!(reader.jump(axis_size as usize).map_err(FontError::eof));
+
+            let tag = try!(axis.read_u32::<BigEndian>().map_err(FontError::eof));
+            let min_value = try!(read_fixed(&mut axis));
+            let default_value = try!(read_fixed(&mut axis));
+            let max_value = try!(read_fixed(&mut axis));
+            let flags = try!(axis.read_u16::<BigEndian>().map_err(FontError::eof));
+            let axis_name_id = try!(axis.read_u16::<BigEndian>().map_err(FontError::eof));
+            axes.push(VariationAxis {
+                tag: tag,
+                min_value: min_value,
+                default_value: default_value,
+                max_value: max_value,
+                flags: flags,
+                axis_name_id: axis_name_id,
+            })
+        }
+
+        // Read the named instances, which immediately follow the axes.
+        let mut named_instances = Vec::with_capacity(instance_count as usize);
+        for _ in 0..instance_count {
+            let mut instance = reader;
+            try!(reader.jump(instance_size as usize).map_err(FontError::eof));
+
+            let subfamily_name_id = try!(instance.read_u16::<BigEndian>().map_err(FontError::eof));
+            let _flags = try!(instance.read_u16::<BigEndian>().map_err(FontError::eof));
+            let mut coordinates = Vec::with_capacity(axis_count as usize);
+            for _ in 0..axis_count {
+                coordinates.push(try!(read_fixed(&mut instance)))
+            }
+            let postscript_name_id = if has_postscript_name_ids {
+                match try!(instance.read_u16::<BigEndian>().map_err(FontError::eof)) {
+                    NO_POSTSCRIPT_NAME_ID => None,
+                    name_id => Some(name_id),
+                }
+            } else {
+                None
+            };
+            named_instances.push(NamedInstance {
+                coordinates: coordinates,
+                subfamily_name_id: subfamily_name_id,
+                postscript_name_id: postscript_name_id,
+            })
+        }
+
+        Ok(FvarTable {
+            axes: axes,
+            named_instances: named_instances,
+        })
+    }
+
+    /// The font's design axes, in the order that variation coordinates refer to them.
+    #[inline]
+    pub fn axes(&self) -> &[VariationAxis] {
+        &self.axes
+    }
+
+    /// The predefined positions in the design space, such as "Bold" or "Condensed Light".
+    #[inline]
+    pub fn named_instances(&self) -> &[NamedInstance] {
+        &self.named_instances
+    }
+}
+
+// Reads a 16.16 fixed-point number.
+fn read_fixed(reader: &mut &[u8]) -> Result<f32, FontError> {
+    let value = try!(reader.read_i32::<BigEndian>().map_err(FontError::eof));
+    Ok(value as f32 / 65536.0)
+}
@@ -0,0 +1,90 @@
+// Copyright 2017 The Servo Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! The grid-fitting and scan-conversion procedure table, which says how glyphs should be
+//! rendered at each size.
+//!
+//! See Microsoft's spec: https://www.microsoft.com/typography/otspec/gasp.htm
+
+use byteorder::{BigEndian, ByteOrder, ReadBytesExt};
+use error::{ErrorContext, FontError};
+use font::FontTable;
+use util::EofContext;
+
+pub const TAG: u32 = ((b'g' as u32) << 24) |
+                      ((b'a' as u32) << 16) |
+                      ((b's' as u32) << 8)  |
+                       (b'p' as u32);
+
+// The size of a `GaspRange` record: the largest ppem in the range and its behavior.
+const RANGE_RECORD_SIZE: usize = 4;
+
+bitflags! {
+    pub flags GaspBehavior: u16 {
+        const GRIDFIT = 1 << 0,
+        const DOGRAY = 1 << 1,
+        // Version 1 only.
+        const SYMMETRIC_GRIDFIT = 1 << 2,
+        const SYMMETRIC_SMOOTHING = 1 << 3,
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct GaspTable<'a> {
+    version: u16,
+    // The `GaspRange` records, sorted by `rangeMaxPPEM`.
+    ranges: &'a [u8],
+}
+
+impl<'a> GaspTable<'a> {
+    pub fn new(table: FontTable<'a>) -> Result<GaspTable<'a>, FontError> {
+        let mut reader = table.bytes;
+
+        // Check the version.
+        let version = try!(reader.read_u16::<BigEndian>().eof_in(TAG, "version"));
+        if version > 1 {
+            return Err(FontError::UnsupportedGaspVersion)
+        }
+
+        // Check the length up front so that lookups can't fail.
+        let num_ranges = try!(reader.read_u16::<BigEndian>().eof_in(TAG, "number of ranges"));
+        let ranges_size = num_ranges as usize * RANGE_RECORD_SIZE;
+        if reader.len() < ranges_size {
+            return Err(FontError::UnexpectedEofIn(ErrorContext {
+                table: TAG,
+                field: "ranges",
+            }))
+        }
+
+        Ok(GaspTable {
+            version: version,
+            ranges: &reader[0..ranges_size],
+        })
+    }
+
+    /// Returns how glyphs should be rendered at the given size in pixels per em.
+    ///
+    /// Each range covers the sizes above the previous range up to and including its maximum; the
+    /// last range conventionally has a maximum of 0xFFFF and so covers every larger size. Sizes
+    /// past the last range get no flags.
+    pub fn behavior_for_ppem(&self, ppem: u16) -> GaspBehavior {
+        for range in self.ranges.chunks(RANGE_RECORD_SIZE) {
+            if ppem <= BigEndian::read_u16(range) {
+                let behavior = GaspBehavior::from_bits_truncate(BigEndian::read_u16(&range[2..]));
+                return if self.version == 0 {
+                    behavior & (GRIDFIT | DOGRAY)
+                } else {
+                    behavior
+                }
+            }
+        }
+        GaspBehavior::empty()
+    }
+}
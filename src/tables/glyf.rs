@@ -10,19 +10,23 @@
 
 use byteorder::{BigEndian, ReadBytesExt};
 use error::FontError;
-use euclid::Point2D;
-use font::{FontTable, Point, PointKind};
+use euclid::{Matrix2D, Point2D};
+use font::{Contour, FontTable, Point, PointKind, SvgPathOptions, TransformedPoint};
 use outline::GlyphBounds;
 use std::mem;
-use tables::head::HeadTable;
+use std::u16;
+use tables::gvar::GvarTable;
 use tables::loca::LocaTable;
-use util::{F2DOT14_ONE, F2DOT14_ZERO, F2Dot14, Jump};
+use util::{self, F2DOT14_ONE, F2DOT14_ZERO, F2Dot14, Flattener, Jump, SvgPathWriter};
 
 pub const TAG: u32 = ((b'g' as u32) << 24) |
                       ((b'l' as u32) << 16) |
                       ((b'y' as u32) << 8)  |
                        (b'f' as u32);
 
+// The maximum nesting depth of composite glyphs.
+const MAX_COMPOSITE_NESTING: u8 = 16;
+
 bitflags! {
     flags SimpleFlags: u8 {
         const ON_CURVE = 1 << 0,
@@ -61,14 +65,84 @@ impl<'a> GlyfTable<'a> {
     }
 
     pub fn for_each_point<F>(&self,
-                             head_table: &HeadTable,
                              loca_table: &LocaTable,
                              glyph_id: u16,
-                             callback: F)
+                             mut callback: F)
                              -> Result<(), FontError> where F: FnMut(&Point) {
+        self.for_each_point_in_glyph(loca_table, None, glyph_id, 0, &mut callback)
+    }
+
+    /// Like `for_each_point()`, but maps each point through the given affine transform as it is
+    /// emitted.
+    pub fn for_each_point_transformed<F>(&self,
+                                         loca_table: &LocaTable,
+                                         glyph_id: u16,
+                                         transform: &Matrix2D<f32>,
+                                         mut callback: F)
+                                         -> Result<(), FontError>
+                                         where F: FnMut(&TransformedPoint) {
+        self.for_each_point(loca_table, glyph_id, |point| {
+            callback(&point.transform(transform))
+        })
+    }
+
+    /// Returns the outline of the given glyph with each curve flattened into line segments no more
+    /// than `tolerance` font units from it.
+    pub fn flatten(&self,
+                   loca_table: &LocaTable,
+                   glyph_id: u16,
+                   tolerance: f32)
+                   -> Result<Vec<Contour<Point2D<f32>>>, FontError> {
+        let mut flattener = Flattener::new(tolerance);
+        try!(self.for_each_point(loca_table, glyph_id, |point| flattener.push(point)));
+        Ok(flattener.finish())
+    }
+
+    /// Returns the outline of the given glyph as SVG path data. See `Font::to_svg_path()`.
+    pub fn to_svg_path(&self,
+                       loca_table: &LocaTable,
+                       glyph_id: u16,
+                       options: &SvgPathOptions)
+                       -> Result<String, FontError> {
+        let mut writer = SvgPathWriter::new(options);
+        try!(self.for_each_point(loca_table, glyph_id, |point| writer.push(point)));
+        Ok(writer.finish())
+    }
+
+    /// Like `for_each_point()`, but first moves the points to the given position in the design
+    /// space of a variable font, using the deltas in the `gvar` table.
+    ///
+    /// `coordinates` holds the normalized position along each axis. See
+    /// `GvarTable::apply_deltas()`.
+    pub fn for_each_point_with_variations<F>(&self,
+                                             loca_table: &LocaTable,
+                                             gvar_table: &GvarTable,
+                                             coordinates: &[f32],
+                                             glyph_id: u16,
+                                             mut callback: F)
+                                             -> Result<(), FontError> where F: FnMut(&Point) {
+        self.for_each_point_in_glyph(loca_table,
+                                     Some((gvar_table, coordinates)),
+                                     glyph_id,
+                                     0,
+                                     &mut callback)
+    }
+
+    // Calls the callback for each point in the given glyph, which is a component nested `depth`
+    // levels deep in composite glyphs.
+    //
+    // This takes a trait object because each level of composite glyph wraps the callback in
+    // another closure.
+    fn for_each_point_in_glyph(&self,
+                               loca_table: &LocaTable,
+                               variations: Option<(&GvarTable, &[f32])>,
+                               glyph_id: u16,
+                               depth: u8,
+                               callback: &mut FnMut(&Point))
+                               -> Result<(), FontError> {
         let mut reader = self.table.bytes;
 
-        match try!(loca_table.location_of(head_table, glyph_id)) {
+        match try!(loca_table.location_of(glyph_id)) {
             None => {
                 // No points.
                 return Ok(())
@@ -79,153 +153,69 @@ impl<'a> GlyfTable<'a> {
         let glyph_start = reader;
         let number_of_contours = try!(reader.read_i16::<BigEndian>().map_err(FontError::eof));
         if number_of_contours >= 0 {
-            self.for_each_point_in_simple_glyph(glyph_start, callback)
+            self.for_each_point_in_simple_glyph(glyph_start, variations, glyph_id, callback)
         } else {
-            self.for_each_point_in_composite_glyph(glyph_start, head_table, loca_table, callback)
+            self.for_each_point_in_composite_glyph(glyph_start,
+                                                   loca_table,
+                                                   variations,
+                                                   glyph_id,
+                                                   depth,
+                                                   callback)
         }
     }
 
-    fn for_each_point_in_simple_glyph<F>(&self, mut reader: &[u8], mut callback: F)
+    fn for_each_point_in_simple_glyph<F>(&self,
+                                         reader: &[u8],
+                                         variations: Option<(&GvarTable, &[f32])>,
+                                         glyph_id: u16,
+                                         mut callback: F)
                                          -> Result<(), FontError> where F: FnMut(&Point) {
-        // Determine how many contours we have.
-        let number_of_contours = try!(reader.read_i16::<BigEndian>().map_err(FontError::eof));
-        if number_of_contours == 0 {
-            return Ok(())
-        }
-
-        // Skip over the rest of the header.
-        try!(reader.jump(mem::size_of::<i16>() * 4).map_err(FontError::eof));
-
-        // Find out how many points we have.
-        let mut endpoints_reader = reader;
-        try!(reader.jump(mem::size_of::<u16>() as usize * (number_of_contours as usize - 1))
-                   .map_err(FontError::eof));
-        let number_of_points = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof)) + 1;
-
-        // Skip over hinting instructions.
-        let instruction_length = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
-        try!(reader.jump(instruction_length as usize).map_err(FontError::eof));
-
-        // Find the offsets of the X and Y coordinates.
-        let flags_reader = reader;
-        let x_coordinate_length = try!(calculate_size_of_x_coordinates(&mut reader,
-                                                                       number_of_points));
-
-        // Set up the streams.
-        let mut flag_parser = try!(FlagParser::new(flags_reader));
-        let mut x_coordinate_reader = reader;
-        try!(reader.jump(x_coordinate_length as usize).map_err(FontError::eof));
-        let mut y_coordinate_reader = reader;
-
-        // Now parse the contours.
-        let (mut position, mut point_index) = (Point2D::new(0, 0), 0);
-        for _ in 0..number_of_contours {
-            let contour_point_count =
-                try!(endpoints_reader.read_u16::<BigEndian>().map_err(FontError::eof)) -
-                point_index + 1;
-
-            let mut first_on_curve_point = None;
-            let mut initial_off_curve_point = None;
-            let mut last_point_was_off_curve = false;
-            let mut point_index_in_contour = 0;
-
-            for _ in 0..contour_point_count {
-                let flags = SimpleFlags::from_bits_truncate(*flag_parser.current);
-                try!(flag_parser.next());
-
-                let mut delta = Point2D::new(0, 0);
-                if flags.contains(X_SHORT_VECTOR) {
-                    delta.x = try!(x_coordinate_reader.read_u8().map_err(FontError::eof)) as i16;
-                    if !flags.contains(THIS_X_IS_SAME) {
-                        delta.x = -delta.x
-                    }
-                } else if !flags.contains(THIS_X_IS_SAME) {
-                    delta.x = try!(x_coordinate_reader.read_i16::<BigEndian>()
-                                                      .map_err(FontError::eof))
-                }
-                if flags.contains(Y_SHORT_VECTOR) {
-                    delta.y = try!(y_coordinate_reader.read_u8().map_err(FontError::eof)) as i16;
-                    if !flags.contains(THIS_Y_IS_SAME) {
-                        delta.y = -delta.y
-                    }
-                } else if !flags.contains(THIS_Y_IS_SAME) {
-                    delta.y = try!(y_coordinate_reader.read_i16::<BigEndian>()
-                                                      .map_err(FontError::eof))
-                }
-
-                if last_point_was_off_curve && !flags.contains(ON_CURVE) {
-                    let position = position + delta / 2;
+        let mut glyph = match try!(SimpleGlyph::new(reader)) {
+            None => return Ok(()),
+            Some(glyph) => glyph,
+        };
 
-                    // An important edge case!
-                    if first_on_curve_point.is_none() {
-                        first_on_curve_point = Some(position)
+        let (gvar_table, coordinates) = match variations {
+            None => {
+                // Emit the points as we decode them.
+                for _ in 0..glyph.number_of_contours {
+                    let contour_point_count = try!(glyph.next_contour_point_count());
+                    let mut contour = ContourEmitter::new();
+                    for _ in 0..contour_point_count {
+                        let (position, on_curve) = try!(glyph.next_point());
+                        contour.push(position, on_curve, &mut callback)
                     }
-
-                    callback(&Point {
-                        position: position,
-                        index_in_contour: point_index_in_contour,
-                        kind: PointKind::OnCurve,
-                    });
-                    point_index_in_contour += 1
-                }
-
-                position = position + delta;
-
-                if flags.contains(ON_CURVE) && first_on_curve_point.is_none() {
-                    first_on_curve_point = Some(position)
-                }
-
-                // Sometimes the initial point is an off curve point. In that case, save it so we
-                // can emit it later when closing the path.
-                if !flags.contains(ON_CURVE) && first_on_curve_point.is_none() {
-                    debug_assert!(initial_off_curve_point.is_none());
-                    initial_off_curve_point = Some(position)
-                } else {
-                    callback(&Point {
-                        position: position,
-                        kind: if flags.contains(ON_CURVE) {
-                            PointKind::OnCurve
-                        } else {
-                            PointKind::QuadControl
-                        },
-                        index_in_contour: point_index_in_contour,
-                    });
-                    point_index_in_contour += 1
+                    contour.close(&mut callback)
                 }
-
-                last_point_was_off_curve = !flags.contains(ON_CURVE);
-                point_index += 1;
+                return Ok(())
             }
+            Some(variations) => variations,
+        };
 
-            // We're about to close the path. Emit the initial off curve point if there was one.
-            if let Some(initial_off_curve_point) = initial_off_curve_point {
-                if last_point_was_off_curve {
-                    // Another important edge case!
-                    let position = position + (initial_off_curve_point - position) / 2;
-                    callback(&Point {
-                        position: position,
-                        index_in_contour: point_index_in_contour,
-                        kind: PointKind::OnCurve,
-                    });
-                    point_index_in_contour += 1
-                }
-
-                callback(&Point {
-                    position: initial_off_curve_point,
-                    kind: PointKind::QuadControl,
-                    index_in_contour: point_index_in_contour,
-                });
-                point_index_in_contour += 1
+        // Decode all the points so that we can vary them, then emit them.
+        let mut positions = Vec::with_capacity(glyph.number_of_points as usize);
+        let mut on_curve_flags = Vec::with_capacity(glyph.number_of_points as usize);
+        let mut contour_endpoints = Vec::with_capacity(glyph.number_of_contours as usize);
+        for _ in 0..glyph.number_of_contours {
+            let contour_point_count = try!(glyph.next_contour_point_count());
+            for _ in 0..contour_point_count {
+                let (position, on_curve) = try!(glyph.next_point());
+                positions.push(position);
+                on_curve_flags.push(on_curve)
             }
+            contour_endpoints.push(positions.len() as u16 - 1)
+        }
 
-            // Close the path.
-            if let Some(first_on_curve_point) = first_on_curve_point {
-                callback(&Point {
-                    position: first_on_curve_point,
-                    kind: PointKind::OnCurve,
-                    index_in_contour: point_index_in_contour,
-                })
+        try!(gvar_table.apply_deltas(glyph_id, coordinates, &mut positions, &contour_endpoints));
+
+        let mut point_index = 0;
+        for &contour_endpoint in &contour_endpoints {
+            let mut contour = ContourEmitter::new();
+            while point_index <= contour_endpoint as usize {
+                contour.push(positions[point_index], on_curve_flags[point_index], &mut callback);
+                point_index += 1
             }
+            contour.close(&mut callback)
         }
 
         Ok(())
@@ -233,14 +223,21 @@ impl<'a> GlyfTable<'a> {
 
     // TODO(pcwalton): Consider rasterizing pieces of composite glyphs independently and
     // compositing them together.
-    fn for_each_point_in_composite_glyph<F>(&self,
-                                            mut reader: &[u8],
-                                            head_table: &HeadTable,
-                                            loca_table: &LocaTable,
-                                            mut callback: F)
-                                            -> Result<(), FontError> where F: FnMut(&Point) {
+    fn for_each_point_in_composite_glyph(&self,
+                                         mut reader: &[u8],
+                                         loca_table: &LocaTable,
+                                         variations: Option<(&GvarTable, &[f32])>,
+                                         glyph_id: u16,
+                                         depth: u8,
+                                         callback: &mut FnMut(&Point))
+                                         -> Result<(), FontError> {
+        if depth >= MAX_COMPOSITE_NESTING {
+            return Err(FontError::GlyfCompositeNestingTooDeep)
+        }
+
         try!(reader.jump(mem::size_of::<i16>() * 5).map_err(FontError::eof));
 
+        let mut components = vec![];
         loop {
             let flags = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
             let flags = CompositeFlags::from_bits_truncate(flags);
@@ -255,6 +252,10 @@ impl<'a> GlyfTable<'a> {
                 arg1 = try!(reader.read_i8().map_err(FontError::eof)) as i16;
             }
 
+            // Offsets are already whole font units, so `ROUND_XY_TO_GRID` needs no rounding
+            // here. `USE_MY_METRICS` affects only metrics, not placement.
+            //
+            // TODO(pcwalton): Support positioning components by matching points.
             let mut transform = Mat3x2::identity();
             if flags.contains(ARGS_ARE_XY_VALUES) {
                 transform.m02 = arg0;
@@ -281,27 +282,47 @@ impl<'a> GlyfTable<'a> {
                                                    .map_err(FontError::eof)));
             }
 
-            if let Some(offset) = try!(loca_table.location_of(head_table, glyph_index)) {
-                let mut reader = self.table.bytes;
-                try!(reader.jump(offset as usize).map_err(FontError::eof));
-                try!(self.for_each_point_in_simple_glyph(reader, |point| {
-                    callback(&transform.transform(&point))
-                }));
-            }
+            components.push((glyph_index, flags, transform));
 
             if !flags.contains(MORE_COMPONENTS) {
                 break
             }
         }
 
+        // In a variable font, each component's offset is a point that can move.
+        if let Some((gvar_table, coordinates)) = variations {
+            let mut offsets: Vec<_> = components.iter().map(|&(_, _, transform)| {
+                Point2D::new(transform.m02, transform.m12)
+            }).collect();
+            try!(gvar_table.apply_deltas(glyph_id, coordinates, &mut offsets, &[]));
+            for (&mut (_, flags, ref mut transform), offset) in components.iter_mut()
+                                                                          .zip(offsets) {
+                if flags.contains(ARGS_ARE_XY_VALUES) {
+                    transform.m02 = offset.x;
+                    transform.m12 = offset.y;
+                }
+            }
+        }
+
+        // Components may themselves be composite glyphs.
+        for &(glyph_index, _, transform) in &components {
+            try!(self.for_each_point_in_glyph(loca_table,
+                                              variations,
+                                              glyph_index,
+                                              depth + 1,
+                                              &mut |point: &Point| {
+                callback(&transform.transform(point))
+            }));
+        }
+
         Ok(())
     }
 
-    pub fn glyph_bounds(&self, head_table: &HeadTable, loca_table: &LocaTable, glyph_id: u16)
+    pub fn glyph_bounds(&self, loca_table: &LocaTable, glyph_id: u16)
                         -> Result<GlyphBounds, FontError> {
         let mut reader = self.table.bytes;
 
-        match try!(loca_table.location_of(head_table, glyph_id)) {
+        match try!(loca_table.location_of(glyph_id)) {
             None => {
                 // No outlines.
                 return Ok(GlyphBounds {
@@ -328,6 +349,100 @@ impl<'a> GlyfTable<'a> {
             top: y_max as i32,
         })
     }
+
+    /// Returns the glyphs that the given glyph is built from if it's a composite glyph, or
+    /// nothing if it's a simple glyph.
+    ///
+    /// Components may themselves be composite glyphs.
+    pub fn component_glyphs(&self, loca_table: &LocaTable, glyph_id: u16)
+                            -> Result<Vec<u16>, FontError> {
+        let glyph = try!(self.glyph_data(loca_table, glyph_id));
+        let mut component_glyphs = vec![];
+        for offset in try!(component_glyph_id_offsets(glyph)) {
+            let mut reader = &glyph[offset..];
+            component_glyphs.push(try!(reader.read_u16::<BigEndian>().map_err(FontError::eof)))
+        }
+        Ok(component_glyphs)
+    }
+
+    /// Writes the `glyf` and `loca` tables of a font containing only the given glyphs, so that
+    /// `glyphs[i]` becomes glyph `i`. The glyphs must be sorted.
+    ///
+    /// The `loca` table uses the long format. Composite glyphs refer to their components by their
+    /// new IDs, so every component must be among the glyphs.
+    pub fn subset(&self, loca_table: &LocaTable, glyphs: &[u16])
+                  -> Result<(Vec<u8>, Vec<u8>), FontError> {
+        let (mut glyf, mut loca) = (vec![], vec![]);
+        for &glyph_id in glyphs {
+            util::push_u32(&mut loca, glyf.len() as u32);
+
+            let glyph_start = glyf.len();
+            let glyph = try!(self.glyph_data(loca_table, glyph_id));
+            glyf.extend_from_slice(glyph);
+            for offset in try!(component_glyph_id_offsets(glyph)) {
+                let mut reader = &glyph[offset..];
+                let component = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+                let new_component = match glyphs.binary_search(&component) {
+                    Ok(new_component) => new_component as u16,
+                    Err(_) => return Err(FontError::NoSuchGlyph),
+                };
+                glyf[glyph_start + offset] = (new_component >> 8) as u8;
+                glyf[glyph_start + offset + 1] = new_component as u8
+            }
+
+            // Keep each glyph 4-byte aligned.
+            while glyf.len() % 4 != 0 {
+                glyf.push(0)
+            }
+        }
+        util::push_u32(&mut loca, glyf.len() as u32);
+        Ok((glyf, loca))
+    }
+
+    // Returns the data of the given glyph, which is empty if the glyph has no outline.
+    fn glyph_data(&self, loca_table: &LocaTable, glyph_id: u16)
+                  -> Result<&'a [u8], FontError> {
+        match try!(loca_table.range_of(glyph_id)) {
+            None => Ok(&[]),
+            Some((start, end)) if start <= end => {
+                self.table.subslice(start as usize, (end - start) as usize)
+            }
+            Some(_) => Err(FontError::LocaMalformed),
+        }
+    }
+}
+
+// Returns the offsets of the component glyph IDs within the given composite glyph, or nothing if
+// it's a simple glyph.
+fn component_glyph_id_offsets(glyph: &[u8]) -> Result<Vec<usize>, FontError> {
+    let mut reader = glyph;
+    let mut offsets = vec![];
+    if reader.is_empty() || try!(reader.read_i16::<BigEndian>().map_err(FontError::eof)) >= 0 {
+        return Ok(offsets)
+    }
+
+    try!(reader.jump(mem::size_of::<i16>() * 4).map_err(FontError::eof));
+    loop {
+        let flags = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+        let flags = CompositeFlags::from_bits_truncate(flags);
+        offsets.push(glyph.len() - reader.len());
+
+        // Skip the glyph ID, the offset or point numbers, and the transform.
+        let mut component_size = mem::size_of::<u16>();
+        component_size += if flags.contains(ARG_1_AND_2_ARE_WORDS) { 4 } else { 2 };
+        if flags.contains(WE_HAVE_A_SCALE) {
+            component_size += 2
+        } else if flags.contains(WE_HAVE_AN_X_AND_Y_SCALE) {
+            component_size += 4
+        } else if flags.contains(WE_HAVE_A_TWO_BY_TWO) {
+            component_size += 8
+        }
+        try!(reader.jump(component_size).map_err(FontError::eof));
+
+        if !flags.contains(MORE_COMPONENTS) {
+            return Ok(offsets)
+        }
+    }
 }
 
 // Given a reader pointing to the start of the list of flags, returns the size in bytes of the list
@@ -357,6 +472,196 @@ fn calculate_size_of_x_coordinates<'a, 'b>(reader: &'a mut &'b [u8], number_of_p
     Ok(x_coordinate_length)
 }
 
+// Decodes the points of a simple glyph in order.
+struct SimpleGlyph<'a> {
+    number_of_contours: u16,
+    number_of_points: u16,
+    endpoints_reader: &'a [u8],
+    flag_parser: FlagParser<'a>,
+    x_coordinate_reader: &'a [u8],
+    y_coordinate_reader: &'a [u8],
+    next_contour_start: u16,
+    position: Point2D<i16>,
+}
+
+impl<'a> SimpleGlyph<'a> {
+    // Reads the header of the simple glyph at the start of `reader`. Returns `None` if the glyph
+    // has no contours.
+    fn new(mut reader: &'a [u8]) -> Result<Option<SimpleGlyph<'a>>, FontError> {
+        // Determine how many contours we have.
+        let number_of_contours = try!(reader.read_i16::<BigEndian>().map_err(FontError::eof));
+        if number_of_contours == 0 {
+            return Ok(None)
+        }
+
+        // Skip over the rest of the header.
+        try!(reader.jump(mem::size_of::<i16>() * 4).map_err(FontError::eof));
+
+        // Find out how many points we have.
+        let endpoints_reader = reader;
+        try!(reader.jump(mem::size_of::<u16>() as usize * (number_of_contours as usize - 1))
+                   .map_err(FontError::eof));
+        let last_endpoint = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+        if last_endpoint == u16::MAX {
+            return Err(FontError::GlyfMalformedGlyph)
+        }
+        let number_of_points = last_endpoint + 1;
+
+        // Skip over hinting instructions.
+        let instruction_length = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+        try!(reader.jump(instruction_length as usize).map_err(FontError::eof));
+
+        // Find the offsets of the X and Y coordinates.
+        let flags_reader = reader;
+        let x_coordinate_length = try!(calculate_size_of_x_coordinates(&mut reader,
+                                                                       number_of_points));
+
+        // Set up the streams.
+        let flag_parser = try!(FlagParser::new(flags_reader));
+        let x_coordinate_reader = reader;
+        try!(reader.jump(x_coordinate_length as usize).map_err(FontError::eof));
+        let y_coordinate_reader = reader;
+
+        Ok(Some(SimpleGlyph {
+            number_of_contours: number_of_contours as u16,
+            number_of_points: number_of_points,
+            endpoints_reader: endpoints_reader,
+            flag_parser: flag_parser,
+            x_coordinate_reader: x_coordinate_reader,
+            y_coordinate_reader: y_coordinate_reader,
+            next_contour_start: 0,
+            position: Point2D::new(0, 0),
+        }))
+    }
+
+    // Returns the number of points in the next contour.
+    fn next_contour_point_count(&mut self) -> Result<u16, FontError> {
+        let endpoint = try!(self.endpoints_reader.read_u16::<BigEndian>()
+                                                 .map_err(FontError::eof));
+        if endpoint < self.next_contour_start || endpoint >= self.number_of_points {
+            return Err(FontError::GlyfMalformedGlyph)
+        }
+        let contour_point_count = endpoint - self.next_contour_start + 1;
+        self.next_contour_start = endpoint + 1;
+        Ok(contour_point_count)
+    }
+
+    // Returns the position of the next point and whether it's on the curve.
+    fn next_point(&mut self) -> Result<(Point2D<i16>, bool), FontError> {
+        let flags = SimpleFlags::from_bits_truncate(*self.flag_parser.current);
+        try!(self.flag_parser.next());
+
+        let mut delta = Point2D::new(0, 0);
+        if flags.contains(X_SHORT_VECTOR) {
+            delta.x = try!(self.x_coordinate_reader.read_u8().map_err(FontError::eof)) as i16;
+            if !flags.contains(THIS_X_IS_SAME) {
+                delta.x = -delta.x
+            }
+        } else if !flags.contains(THIS_X_IS_SAME) {
+            delta.x = try!(self.x_coordinate_reader.read_i16::<BigEndian>()
+                                                   .map_err(FontError::eof))
+        }
+        if flags.contains(Y_SHORT_VECTOR) {
+            delta.y = try!(self.y_coordinate_reader.read_u8().map_err(FontError::eof)) as i16;
+            if !flags.contains(THIS_Y_IS_SAME) {
+                delta.y = -delta.y
+            }
+        } else if !flags.contains(THIS_Y_IS_SAME) {
+            delta.y = try!(self.y_coordinate_reader.read_i16::<BigEndian>()
+                                                   .map_err(FontError::eof))
+        }
+
+        self.position = self.position + delta;
+        Ok((self.position, flags.contains(ON_CURVE)))
+    }
+}
+
+// Turns the on- and off-curve points of a TrueType contour into a closed path, inserting the
+// on-curve points implied between consecutive off-curve points.
+struct ContourEmitter {
+    position: Point2D<i16>,
+    first_on_curve_point: Option<Point2D<i16>>,
+    initial_off_curve_point: Option<Point2D<i16>>,
+    last_point_was_off_curve: bool,
+    point_index_in_contour: u16,
+}
+
+impl ContourEmitter {
+    fn new() -> ContourEmitter {
+        ContourEmitter {
+            position: Point2D::new(0, 0),
+            first_on_curve_point: None,
+            initial_off_curve_point: None,
+            last_point_was_off_curve: false,
+            point_index_in_contour: 0,
+        }
+    }
+
+    fn push<F>(&mut self, position: Point2D<i16>, on_curve: bool, callback: &mut F)
+               where F: FnMut(&Point) {
+        if self.last_point_was_off_curve && !on_curve {
+            let position = self.position + (position - self.position) / 2;
+
+            // An important edge case!
+            if self.first_on_curve_point.is_none() {
+                self.first_on_curve_point = Some(position)
+            }
+
+            self.emit(position, PointKind::OnCurve, callback)
+        }
+
+        self.position = position;
+
+        if on_curve && self.first_on_curve_point.is_none() {
+            self.first_on_curve_point = Some(position)
+        }
+
+        // Sometimes the initial point is an off curve point. In that case, save it so we can emit
+        // it later when closing the path.
+        if !on_curve && self.first_on_curve_point.is_none() {
+            debug_assert!(self.initial_off_curve_point.is_none());
+            self.initial_off_curve_point = Some(position)
+        } else {
+            let kind = if on_curve {
+                PointKind::OnCurve
+            } else {
+                PointKind::QuadControl
+            };
+            self.emit(position, kind, callback)
+        }
+
+        self.last_point_was_off_curve = !on_curve
+    }
+
+    fn close<F>(mut self, callback: &mut F) where F: FnMut(&Point) {
+        // We're about to close the path. Emit the initial off curve point if there was one.
+        if let Some(initial_off_curve_point) = self.initial_off_curve_point {
+            if self.last_point_was_off_curve {
+                // Another important edge case!
+                let position = self.position + (initial_off_curve_point - self.position) / 2;
+                self.emit(position, PointKind::OnCurve, callback)
+            }
+
+            self.emit(initial_off_curve_point, PointKind::QuadControl, callback)
+        }
+
+        // Close the path.
+        if let Some(first_on_curve_point) = self.first_on_curve_point {
+            self.emit(first_on_curve_point, PointKind::OnCurve, callback)
+        }
+    }
+
+    fn emit<F>(&mut self, position: Point2D<i16>, kind: PointKind, callback: &mut F)
+               where F: FnMut(&Point) {
+        callback(&Point {
+            position: position,
+            index_in_contour: self.point_index_in_contour,
+            kind: kind,
+        });
+        self.point_index_in_contour += 1
+    }
+}
+
 struct FlagParser<'a> {
     next: &'a [u8],
     current: &'a u8,
@@ -366,6 +671,10 @@ struct FlagParser<'a> {
 impl<'a> FlagParser<'a> {
     #[inline]
     fn new(buffer: &[u8]) -> Result<FlagParser, FontError> {
+        if buffer.is_empty() {
+            return Err(FontError::UnexpectedEof)
+        }
+
         let mut parser = FlagParser {
             next: buffer,
             current: &buffer[0],
@@ -0,0 +1,224 @@
+// Copyright 2017 The Servo Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! The glyph positioning table.
+//!
+//! Only pair adjustment (kerning) is currently supported.
+
+use byteorder::{BigEndian, ReadBytesExt};
+use error::FontError;
+use font::FontTable;
+use std::mem;
+use tables::layout::{self, ClassDef, Coverage, LayoutTable};
+use util::Jump;
+
+pub const TAG: u32 = ((b'G' as u32) << 24) |
+                      ((b'P' as u32) << 16) |
+                      ((b'O' as u32) << 8)  |
+                       (b'S' as u32);
+
+pub const FEATURE_TAG_KERN: u32 = ((b'k' as u32) << 24) |
+                                   ((b'e' as u32) << 16) |
+                                   ((b'r' as u32) << 8)  |
+                                    (b'n' as u32);
+
+const LOOKUP_TYPE_PAIR_ADJUSTMENT: u16 = 2;
+const LOOKUP_TYPE_EXTENSION_POSITIONING: u16 = 9;
+
+bitflags! {
+    flags ValueFormat: u16 {
+        const X_PLACEMENT = 1 << 0,
+        const Y_PLACEMENT = 1 << 1,
+        const X_ADVANCE = 1 << 2,
+        const Y_ADVANCE = 1 << 3,
+        const X_PLACEMENT_DEVICE = 1 << 4,
+        const Y_PLACEMENT_DEVICE = 1 << 5,
+        const X_ADVANCE_DEVICE = 1 << 6,
+        const Y_ADVANCE_DEVICE = 1 << 7,
+    }
+}
+
+impl ValueFormat {
+    // The size of a value record in this format. Every field is 16 bits.
+    #[inline]
+    fn size(&self) -> usize {
+        self.bits().count_ones() as usize * mem::size_of::<u16>()
+    }
+
+    // Reads the X advance adjustment out of a value record in this format.
+    fn x_advance(&self, mut record: &[u8]) -> Result<i16, FontError> {
+        if !self.contains(X_ADVANCE) {
+            return Ok(0)
+        }
+        let offset = (*self & (X_PLACEMENT | Y_PLACEMENT)).size();
+        try!(record.jump(offset).map_err(FontError::eof));
+        record.read_i16::<BigEndian>().map_err(FontError::eof)
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct GposTable<'a> {
+    layout: LayoutTable<'a>,
+}
+
+impl<'a> GposTable<'a> {
+    pub fn new(table: FontTable<'a>) -> Result<GposTable<'a>, FontError> {
+        let mut reader = table.bytes;
+
+        // Check the version. Version 1.1 only adds feature variations, which we ignore.
+        let major_version = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+        if major_version != 1 {
+            return Err(FontError::UnsupportedGposVersion)
+        }
+
+        Ok(GposTable {
+            layout: try!(LayoutTable::new(table)),
+        })
+    }
+
+    /// Returns the X advance adjustment for the given pair of glyphs in font units, according to
+    /// the `kern` feature.
+    #[inline]
+    pub fn kerning_for_glyph_pair(&self, left_glyph_id: u16, right_glyph_id: u16)
+                                  -> Result<i16, FontError> {
+        self.pair_adjustment(left_glyph_id, right_glyph_id, &[FEATURE_TAG_KERN])
+    }
+
+    /// Returns the X advance adjustment for the given pair of glyphs in font units, summed over
+    /// the pair adjustment lookups of the given features.
+    ///
+    /// The glyphs are in logical order, so in right-to-left text the first is to the right.
+    pub fn pair_adjustment(&self, first_glyph_id: u16, second_glyph_id: u16, feature_tags: &[u32])
+                           -> Result<i16, FontError> {
+        let mut adjustment = 0i16;
+        for lookup_index in try!(self.layout.lookup_indices_for_features(feature_tags)) {
+            let value = try!(self.pair_adjustment_in_lookup(lookup_index,
+                                                            first_glyph_id,
+                                                            second_glyph_id));
+            if let Some(value) = value {
+                adjustment = adjustment.wrapping_add(value)
+            }
+        }
+        Ok(adjustment)
+    }
+
+    /// Returns true if the font has any lookups in a `kern` feature.
+    #[inline]
+    pub fn has_kerning(&self) -> bool {
+        self.has_lookups_for_features(&[FEATURE_TAG_KERN])
+    }
+
+    /// Returns true if the font has any lookups in any of the given features.
+    pub fn has_lookups_for_features(&self, feature_tags: &[u32]) -> bool {
+        self.layout
+            .lookup_indices_for_features(feature_tags)
+            .map(|indices| !indices.is_empty())
+            .unwrap_or(false)
+    }
+
+    // Returns the adjustment from the first subtable in the lookup that applies to the pair, if
+    // any does.
+    fn pair_adjustment_in_lookup(&self, lookup_index: u16, left_glyph_id: u16, right_glyph_id: u16)
+                                 -> Result<Option<i16>, FontError> {
+        let lookup = match try!(self.layout.lookup(lookup_index)) {
+            None => return Ok(None),
+            Some(lookup) => lookup,
+        };
+
+        for (lookup_type, pair_pos) in try!(lookup.subtables(LOOKUP_TYPE_EXTENSION_POSITIONING)) {
+            if lookup_type != LOOKUP_TYPE_PAIR_ADJUSTMENT {
+                continue
+            }
+            let value = try!(pair_adjustment_in_subtable(pair_pos,
+                                                         left_glyph_id,
+                                                         right_glyph_id));
+            if value.is_some() {
+                return Ok(value)
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+// Returns a pair adjustment positioning subtable's X advance adjustment for the first glyph of
+// the pair, or `None` if the subtable doesn't apply to the pair.
+fn pair_adjustment_in_subtable(pair_pos: &[u8], left_glyph_id: u16, right_glyph_id: u16)
+                               -> Result<Option<i16>, FontError> {
+    let mut reader = pair_pos;
+    let format = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+    let coverage_offset = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+    let value_format_1 = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+    let value_format_2 = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+    let value_format_1 = ValueFormat::from_bits_truncate(value_format_1);
+    let value_format_2 = ValueFormat::from_bits_truncate(value_format_2);
+
+    let coverage = try!(Coverage::new(pair_pos, coverage_offset));
+    let coverage_index = match try!(coverage.coverage_index(left_glyph_id)) {
+        None => return Ok(None),
+        Some(coverage_index) => coverage_index,
+    };
+
+    match format {
+        1 => {
+            // Find the pair set for the first glyph.
+            let pair_set_count = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+            if coverage_index >= pair_set_count {
+                return Ok(None)
+            }
+            try!(reader.jump(mem::size_of::<u16>() * coverage_index as usize)
+                       .map_err(FontError::eof));
+            let pair_set_offset = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+            let mut pair_set = try!(layout::subtable(pair_pos, pair_set_offset as usize));
+            let pair_value_count = try!(pair_set.read_u16::<BigEndian>()
+                                                .map_err(FontError::eof));
+
+            // Binary search to find the second glyph.
+            let record_size = mem::size_of::<u16>() + value_format_1.size() +
+                value_format_2.size();
+            let (mut low, mut high) = (0, pair_value_count as usize);
+            while low < high {
+                let mid = (low + high) / 2;
+                let mut record = pair_set;
+                try!(record.jump(mid * record_size).map_err(FontError::eof));
+                let second_glyph = try!(record.read_u16::<BigEndian>().map_err(FontError::eof));
+                if right_glyph_id < second_glyph {
+                    high = mid
+                } else if right_glyph_id > second_glyph {
+                    low = mid + 1
+                } else {
+                    return Ok(Some(try!(value_format_1.x_advance(record))))
+                }
+            }
+
+            Ok(None)
+        }
+        2 => {
+            let class_def_1_offset = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+            let class_def_2_offset = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+            let class_1_count = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+            let class_2_count = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+
+            let class_def_1 = try!(ClassDef::new(pair_pos, class_def_1_offset));
+            let class_def_2 = try!(ClassDef::new(pair_pos, class_def_2_offset));
+            let class_1 = try!(class_def_1.class_of(left_glyph_id));
+            let class_2 = try!(class_def_2.class_of(right_glyph_id));
+            if class_1 >= class_1_count || class_2 >= class_2_count {
+                return Ok(None)
+            }
+
+            let record_size = value_format_1.size() + value_format_2.size();
+            let record_index = class_1 as usize * class_2_count as usize + class_2 as usize;
+            try!(reader.jump(record_index * record_size).map_err(FontError::eof));
+            Ok(Some(try!(value_format_1.x_advance(reader))))
+        }
+        _ => Ok(None),
+    }
+}
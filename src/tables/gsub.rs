@@ -0,0 +1,270 @@
+// Copyright 2017 The Servo Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! The glyph substitution table.
+//!
+//! Only single and ligature substitutions are currently supported.
+
+use byteorder::{BigEndian, ReadBytesExt};
+use error::FontError;
+use font::FontTable;
+use std::collections::BTreeSet;
+use std::mem;
+use tables::layout::{self, Coverage, LayoutTable};
+use util::Jump;
+
+pub const TAG: u32 = ((b'G' as u32) << 24) |
+                      ((b'S' as u32) << 16) |
+                      ((b'U' as u32) << 8)  |
+                       (b'B' as u32);
+
+const LOOKUP_TYPE_SINGLE_SUBSTITUTION: u16 = 1;
+const LOOKUP_TYPE_LIGATURE_SUBSTITUTION: u16 = 4;
+const LOOKUP_TYPE_EXTENSION_SUBSTITUTION: u16 = 7;
+
+#[derive(Clone, Copy, Debug)]
+pub struct GsubTable<'a> {
+    layout: LayoutTable<'a>,
+}
+
+impl<'a> GsubTable<'a> {
+    pub fn new(table: FontTable<'a>) -> Result<GsubTable<'a>, FontError> {
+        let mut reader = table.bytes;
+
+        // Check the version. Version 1.1 only adds feature variations, which we ignore.
+        let major_version = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+        if major_version != 1 {
+            return Err(FontError::UnsupportedGsubVersion)
+        }
+
+        Ok(GsubTable {
+            layout: try!(LayoutTable::new(table)),
+        })
+    }
+
+    /// Applies the lookups in the features with the given tags to the glyph sequence and returns
+    /// the substituted sequence.
+    ///
+    /// Lookups are applied one after another, each across the whole sequence. Lookup types other
+    /// than single and ligature substitution are skipped, and lookup flags such as "ignore
+    /// marks" are not yet respected.
+    pub fn substitute(&self, glyphs: &[u16], feature_tags: &[u32])
+                      -> Result<Vec<u16>, FontError> {
+        let glyphs: Vec<(u16, usize)> = glyphs.iter().cloned().zip(0..).collect();
+        let glyphs = try!(self.substitute_with_clusters(&glyphs, feature_tags));
+        Ok(glyphs.into_iter().map(|(glyph, _)| glyph).collect())
+    }
+
+    /// Like `substitute()`, but each glyph is paired with the cluster it belongs to, such as the
+    /// offset of the character it came from, and the substituted glyphs keep their clusters.
+    ///
+    /// A ligature takes the cluster of its first component.
+    pub fn substitute_with_clusters(&self, glyphs: &[(u16, usize)], feature_tags: &[u32])
+                                    -> Result<Vec<(u16, usize)>, FontError> {
+        let (mut glyphs, mut clusters): (Vec<u16>, Vec<usize>) = glyphs.iter().cloned().unzip();
+        for lookup_index in try!(self.layout.lookup_indices_for_features(feature_tags)) {
+            let lookup = match try!(self.layout.lookup(lookup_index)) {
+                None => continue,
+                Some(lookup) => lookup,
+            };
+            let subtables = try!(lookup.subtables(LOOKUP_TYPE_EXTENSION_SUBSTITUTION));
+
+            let mut output = Vec::with_capacity(glyphs.len());
+            let mut output_clusters = Vec::with_capacity(clusters.len());
+            let mut index = 0;
+            while index < glyphs.len() {
+                // The first subtable that applies at this position wins.
+                let mut substitution = None;
+                for &(lookup_type, subtable) in &subtables {
+                    substitution = match lookup_type {
+                        LOOKUP_TYPE_SINGLE_SUBSTITUTION => {
+                            try!(single_substitution(subtable, glyphs[index])).map(|glyph| {
+                                (glyph, 1)
+                            })
+                        }
+                        LOOKUP_TYPE_LIGATURE_SUBSTITUTION => {
+                            try!(ligature_substitution(subtable, &glyphs[index..]))
+                        }
+                        _ => None,
+                    };
+                    if substitution.is_some() {
+                        break
+                    }
+                }
+
+                output_clusters.push(clusters[index]);
+                match substitution {
+                    Some((glyph, length)) => {
+                        output.push(glyph);
+                        index += length
+                    }
+                    None => {
+                        output.push(glyphs[index]);
+                        index += 1
+                    }
+                }
+            }
+            glyphs = output;
+            clusters = output_clusters
+        }
+        Ok(glyphs.into_iter().zip(clusters).collect())
+    }
+
+    /// Adds to the set every ligature glyph that can be formed from glyphs in it, repeatedly, so
+    /// that a subset of the font containing the set keeps its ligatures.
+    ///
+    /// All ligature substitutions are considered, whatever features they belong to.
+    pub fn close_over_ligatures(&self, glyphs: &mut BTreeSet<u16>) -> Result<(), FontError> {
+        loop {
+            let mut ligatures = vec![];
+            for lookup in try!(self.layout.lookups()) {
+                let lookup = try!(lookup);
+                for (lookup_type, subtable) in
+                        try!(lookup.subtables(LOOKUP_TYPE_EXTENSION_SUBSTITUTION)) {
+                    if lookup_type != LOOKUP_TYPE_LIGATURE_SUBSTITUTION {
+                        continue
+                    }
+                    for &glyph_id in glyphs.iter() {
+                        try!(ligatures_in_set(subtable, glyph_id, glyphs, &mut ligatures))
+                    }
+                }
+            }
+
+            let glyph_count = glyphs.len();
+            glyphs.extend(ligatures);
+            if glyphs.len() == glyph_count {
+                return Ok(())
+            }
+        }
+    }
+}
+
+// Returns the replacement for the glyph in a single substitution subtable, or `None` if the
+// subtable doesn't cover it.
+fn single_substitution(single_subst: &[u8], glyph_id: u16) -> Result<Option<u16>, FontError> {
+    let mut reader = single_subst;
+    let format = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+    let coverage_offset = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+    let coverage = try!(Coverage::new(single_subst, coverage_offset));
+    let coverage_index = match try!(coverage.coverage_index(glyph_id)) {
+        None => return Ok(None),
+        Some(coverage_index) => coverage_index,
+    };
+
+    match format {
+        1 => {
+            // Addition is modulo 65536.
+            let delta_glyph_id = try!(reader.read_i16::<BigEndian>().map_err(FontError::eof));
+            Ok(Some(glyph_id.wrapping_add(delta_glyph_id as u16)))
+        }
+        2 => {
+            let glyph_count = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+            if coverage_index >= glyph_count {
+                return Ok(None)
+            }
+            try!(reader.jump(mem::size_of::<u16>() * coverage_index as usize)
+                       .map_err(FontError::eof));
+            Ok(Some(try!(reader.read_u16::<BigEndian>().map_err(FontError::eof))))
+        }
+        _ => Ok(None),
+    }
+}
+
+// Returns the ligature glyph that replaces the start of the glyph sequence and the number of
+// glyphs it replaces, or `None` if no ligature in the subtable matches.
+fn ligature_substitution(ligature_subst: &[u8], glyphs: &[u16])
+                         -> Result<Option<(u16, usize)>, FontError> {
+    // Find the ligature set for the first glyph.
+    let ligature_set = match try!(ligature_set(ligature_subst, glyphs[0])) {
+        None => return Ok(None),
+        Some(ligature_set) => ligature_set,
+    };
+
+    // Ligatures are in order of preference, so take the first one that matches.
+    let mut reader = ligature_set;
+    let ligature_count = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+    for _ in 0..ligature_count {
+        let ligature_offset = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+        let mut ligature = try!(layout::subtable(ligature_set, ligature_offset as usize));
+        let ligature_glyph = try!(ligature.read_u16::<BigEndian>().map_err(FontError::eof));
+        let component_count = try!(ligature.read_u16::<BigEndian>().map_err(FontError::eof));
+        if component_count == 0 || component_count as usize > glyphs.len() {
+            continue
+        }
+
+        let mut matches = true;
+        for &glyph in &glyphs[1..component_count as usize] {
+            let component = try!(ligature.read_u16::<BigEndian>().map_err(FontError::eof));
+            if glyph != component {
+                matches = false;
+                break
+            }
+        }
+        if matches {
+            return Ok(Some((ligature_glyph, component_count as usize)))
+        }
+    }
+
+    Ok(None)
+}
+
+// Returns the set of ligatures that start with the given glyph in a ligature substitution
+// subtable, or `None` if the subtable doesn't cover the glyph.
+fn ligature_set(ligature_subst: &[u8], glyph_id: u16) -> Result<Option<&[u8]>, FontError> {
+    let mut reader = ligature_subst;
+    let format = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+    if format != 1 {
+        return Ok(None)
+    }
+
+    let coverage_offset = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+    let coverage = try!(Coverage::new(ligature_subst, coverage_offset));
+    let coverage_index = match try!(coverage.coverage_index(glyph_id)) {
+        None => return Ok(None),
+        Some(coverage_index) => coverage_index,
+    };
+    let ligature_set_count = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+    if coverage_index >= ligature_set_count {
+        return Ok(None)
+    }
+    try!(reader.jump(mem::size_of::<u16>() * coverage_index as usize).map_err(FontError::eof));
+    let ligature_set_offset = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+    layout::subtable(ligature_subst, ligature_set_offset as usize).map(Some)
+}
+
+// Adds to `ligatures` the ligature glyphs in a ligature substitution subtable that start with the
+// given glyph and whose other components are all in the set.
+fn ligatures_in_set(ligature_subst: &[u8],
+                    glyph_id: u16,
+                    glyphs: &BTreeSet<u16>,
+                    ligatures: &mut Vec<u16>)
+                    -> Result<(), FontError> {
+    let ligature_set = match try!(ligature_set(ligature_subst, glyph_id)) {
+        None => return Ok(()),
+        Some(ligature_set) => ligature_set,
+    };
+
+    let mut reader = ligature_set;
+    let ligature_count = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+    'ligatures: for _ in 0..ligature_count {
+        let ligature_offset = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+        let mut ligature = try!(layout::subtable(ligature_set, ligature_offset as usize));
+        let ligature_glyph = try!(ligature.read_u16::<BigEndian>().map_err(FontError::eof));
+        let component_count = try!(ligature.read_u16::<BigEndian>().map_err(FontError::eof));
+        for _ in 1..component_count {
+            let component = try!(ligature.read_u16::<BigEndian>().map_err(FontError::eof));
+            if !glyphs.contains(&component) {
+                continue 'ligatures
+            }
+        }
+        ligatures.push(ligature_glyph)
+    }
+    Ok(())
+}
@@ -0,0 +1,421 @@
+// Copyright 2017 The Servo Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+use byteorder::{BigEndian, ReadBytesExt};
+use error::FontError;
+use euclid::Point2D;
+use font::FontTable;
+use std::mem;
+use util::Jump;
+
+pub const TAG: u32 = ((b'g' as u32) << 24) |
+                      ((b'v' as u32) << 16) |
+                      ((b'a' as u32) << 8)  |
+                       (b'r' as u32);
+
+const MAJOR_VERSION: u16 = 1;
+
+// Every glyph has four phantom points after its own points, giving its horizontal and vertical
+// metrics. We don't vary metrics, but the phantom points still count towards "all points".
+const PHANTOM_POINT_COUNT: usize = 4;
+
+const FLAG_LONG_OFFSETS: u16 = 1 << 0;
+
+const TUPLE_COUNT_SHARED_POINT_NUMBERS: u16 = 0x8000;
+const TUPLE_COUNT_MASK: u16 = 0x0fff;
+
+const TUPLE_INDEX_EMBEDDED_PEAK_TUPLE: u16 = 0x8000;
+const TUPLE_INDEX_INTERMEDIATE_REGION: u16 = 0x4000;
+const TUPLE_INDEX_PRIVATE_POINT_NUMBERS: u16 = 0x2000;
+const TUPLE_INDEX_MASK: u16 = 0x0fff;
+
+const POINT_COUNT_IS_WORD: u8 = 0x80;
+const POINTS_ARE_WORDS: u8 = 0x80;
+const POINT_RUN_COUNT_MASK: u8 = 0x7f;
+
+const DELTAS_ARE_ZERO: u8 = 0x80;
+const DELTAS_ARE_WORDS: u8 = 0x40;
+const DELTA_RUN_COUNT_MASK: u8 = 0x3f;
+
+#[derive(Clone, Copy, Debug)]
+pub struct GvarTable<'a> {
+    axis_count: u16,
+    shared_tuples: &'a [u8],
+    shared_tuple_count: u16,
+    glyph_count: u16,
+    long_offsets: bool,
+    glyph_variation_data_offsets: &'a [u8],
+    glyph_variation_data_array: &'a [u8],
+}
+
+impl<'a> GvarTable<'a> {
+    pub fn new(table: FontTable<'a>) -> Result<GvarTable<'a>, FontError> {
+        let mut reader = table.bytes;
+
+        // Check the version.
+        let major_version = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+        let _minor_version = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+        if major_version != MAJOR_VERSION {
+            return Err(FontError::UnsupportedGvarVersion)
+        }
+
+        let axis_count = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+        let shared_tuple_count = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+        let shared_tuples_offset = try!(reader.read_u32::<BigEndian>().map_err(FontError::eof));
+        let glyph_count = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+        let flags = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+        let glyph_variation_data_array_offset = try!(reader.read_u32::<BigEndian>()
+                                                           .map_err(FontError::eof));
+
+        let mut shared_tuples = table.bytes;
+        try!(shared_tuples.jump(shared_tuples_offset as usize).map_err(FontError::eof));
+        let mut glyph_variation_data_array = table.bytes;
+        try!(glyph_variation_data_array.jump(glyph_variation_data_array_offset as usize)
+                                       .map_err(FontError::eof));
+
+        Ok(GvarTable {
+            axis_count: axis_count,
+            shared_tuples: shared_tuples,
+            shared_tuple_count: shared_tuple_count,
+            glyph_count: glyph_count,
+            long_offsets: (flags & FLAG_LONG_OFFSETS) != 0,
+            glyph_variation_data_offsets: reader,
+            glyph_variation_data_array: glyph_variation_data_array,
+        })
+    }
+
+    /// Moves the points of the given glyph to their positions at the given variation.
+    ///
+    /// `coordinates` holds the normalized position along each axis, from -1.0 to 1.0, in the
+    /// order of the `fvar` table; missing coordinates are taken to be 0 (the default). For a
+    /// simple glyph, `points` are its outline points and `contour_endpoints` the index of the
+    /// last point of each contour; points without explicit deltas are then moved by interpolating
+    /// their neighbors' deltas. For a composite glyph, `points` are the components' offsets and
+    /// `contour_endpoints` is empty.
+    pub fn apply_deltas(&self,
+                        glyph_id: u16,
+                        coordinates: &[f32],
+                        points: &mut [Point2D<i16>],
+                        contour_endpoints: &[u16])
+                        -> Result<(), FontError> {
+        let data = match try!(self.glyph_variation_data(glyph_id)) {
+            None => return Ok(()),
+            Some(data) => data,
+        };
+
+        let mut reader = data;
+        let tuple_variation_count = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+        let data_offset = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+        let mut serialized_data = data;
+        try!(serialized_data.jump(data_offset as usize).map_err(FontError::eof));
+
+        let shared_point_numbers =
+            if (tuple_variation_count & TUPLE_COUNT_SHARED_POINT_NUMBERS) != 0 {
+                try!(read_packed_point_numbers(&mut serialized_data))
+            } else {
+                None
+            };
+
+        let all_point_count = points.len() + PHANTOM_POINT_COUNT;
+        let mut total_deltas = vec![Point2D::new(0.0, 0.0); points.len()];
+        for _ in 0..(tuple_variation_count & TUPLE_COUNT_MASK) {
+            let variation_data_size = try!(reader.read_u16::<BigEndian>()
+                                                 .map_err(FontError::eof));
+            let tuple_index = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+
+            // Find out how much this tuple applies at these coordinates.
+            let peak = if (tuple_index & TUPLE_INDEX_EMBEDDED_PEAK_TUPLE) != 0 {
+                try!(self.read_tuple(&mut reader))
+            } else {
+                try!(self.shared_tuple(tuple_index & TUPLE_INDEX_MASK))
+            };
+            let intermediate_region = if (tuple_index & TUPLE_INDEX_INTERMEDIATE_REGION) != 0 {
+                let start = try!(self.read_tuple(&mut reader));
+                let end = try!(self.read_tuple(&mut reader));
+                Some((start, end))
+            } else {
+                None
+            };
+
+            let mut tuple_data = serialized_data;
+            try!(serialized_data.jump(variation_data_size as usize).map_err(FontError::eof));
+
+            let scalar = tuple_scalar(coordinates, &peak, &intermediate_region);
+            if scalar == 0.0 {
+                continue
+            }
+
+            let private_point_numbers;
+            let point_numbers = if (tuple_index & TUPLE_INDEX_PRIVATE_POINT_NUMBERS) != 0 {
+                private_point_numbers = try!(read_packed_point_numbers(&mut tuple_data));
+                &private_point_numbers
+            } else {
+                &shared_point_numbers
+            };
+
+            match *point_numbers {
+                None => {
+                    // The tuple moves every point.
+                    let x_deltas = try!(read_packed_deltas(&mut tuple_data, all_point_count));
+                    let y_deltas = try!(read_packed_deltas(&mut tuple_data, all_point_count));
+                    for (point_index, total_delta) in total_deltas.iter_mut().enumerate() {
+                        total_delta.x += x_deltas[point_index] as f32 * scalar;
+                        total_delta.y += y_deltas[point_index] as f32 * scalar;
+                    }
+                }
+                Some(ref point_numbers) => {
+                    let x_deltas = try!(read_packed_deltas(&mut tuple_data, point_numbers.len()));
+                    let y_deltas = try!(read_packed_deltas(&mut tuple_data, point_numbers.len()));
+
+                    let mut deltas = vec![None; points.len()];
+                    for (index, &point_number) in point_numbers.iter().enumerate() {
+                        if let Some(delta) = deltas.get_mut(point_number as usize) {
+                            *delta = Some(Point2D::new(x_deltas[index] as f32,
+                                                       y_deltas[index] as f32))
+                        }
+                    }
+                    try!(infer_deltas(points, contour_endpoints, &mut deltas));
+
+                    for (total_delta, delta) in total_deltas.iter_mut().zip(deltas.iter()) {
+                        if let Some(delta) = *delta {
+                            total_delta.x += delta.x * scalar;
+                            total_delta.y += delta.y * scalar;
+                        }
+                    }
+                }
+            }
+        }
+
+        for (point, total_delta) in points.iter_mut().zip(total_deltas.iter()) {
+            point.x = (point.x as f32 + total_delta.x).round() as i16;
+            point.y = (point.y as f32 + total_delta.y).round() as i16;
+        }
+        Ok(())
+    }
+
+    // Returns the glyph variation data for the given glyph, or `None` if it has none.
+    fn glyph_variation_data(&self, glyph_id: u16) -> Result<Option<&'a [u8]>, FontError> {
+        if glyph_id >= self.glyph_count {
+            return Ok(None)
+        }
+
+        let mut reader = self.glyph_variation_data_offsets;
+        let (start, end) = if self.long_offsets {
+            try!(reader.jump(mem::size_of::<u32>() * glyph_id as usize).map_err(FontError::eof));
+            let start = try!(reader.read_u32::<BigEndian>().map_err(FontError::eof));
+            let end = try!(reader.read_u32::<BigEndian>().map_err(FontError::eof));
+            (start as usize, end as usize)
+        } else {
+            try!(reader.jump(mem::size_of::<u16>() * glyph_id as usize).map_err(FontError::eof));
+            let start = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+            let end = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+            (start as usize * 2, end as usize * 2)
+        };
+
+        if end <= start {
+            return Ok(None)
+        }
+        if end > self.glyph_variation_data_array.len() {
+            return Err(FontError::UnexpectedEof)
+        }
+        Ok(Some(&self.glyph_variation_data_array[start..end]))
+    }
+
+    fn shared_tuple(&self, index: u16) -> Result<Vec<f32>, FontError> {
+        if index >= self.shared_tuple_count {
+            return Err(FontError::Failed)
+        }
+        let mut reader = self.shared_tuples;
+        try!(reader.jump(mem::size_of::<i16>() * self.axis_count as usize * index as usize)
+                   .map_err(FontError::eof));
+        self.read_tuple(&mut reader)
+    }
+
+    fn read_tuple(&self, reader: &mut &[u8]) -> Result<Vec<f32>, FontError> {
+        let mut tuple = Vec::with_capacity(self.axis_count as usize);
+        for _ in 0..self.axis_count {
+            let value = try!(reader.read_i16::<BigEndian>().map_err(FontError::eof));
+            tuple.push(value as f32 / 16384.0)
+        }
+        Ok(tuple)
+    }
+}
+
+// Returns how much a tuple variation with the given peak and optional intermediate region applies
+// at the given coordinates, from 0.0 to 1.0.
+fn tuple_scalar(coordinates: &[f32],
+                peak: &[f32],
+                intermediate_region: &Option<(Vec<f32>, Vec<f32>)>)
+                -> f32 {
+    let mut scalar = 1.0;
+    for (axis_index, &peak) in peak.iter().enumerate() {
+        let coordinate = coordinates.get(axis_index).cloned().unwrap_or(0.0);
+        if peak == 0.0 || coordinate == peak {
+            continue
+        }
+
+        match *intermediate_region {
+            None => {
+                if coordinate == 0.0 || coordinate < peak.min(0.0) || coordinate > peak.max(0.0) {
+                    return 0.0
+                }
+                scalar *= coordinate / peak
+            }
+            Some((ref start, ref end)) => {
+                let (start, end) = (start[axis_index], end[axis_index]);
+
+                // Invalid regions are ignored.
+                if start > peak || peak > end || (start < 0.0 && end > 0.0) {
+                    continue
+                }
+
+                if coordinate <= start || coordinate >= end {
+                    return 0.0
+                }
+                scalar *= if coordinate < peak {
+                    (coordinate - start) / (peak - start)
+                } else {
+                    (end - coordinate) / (end - peak)
+                }
+            }
+        }
+    }
+    scalar
+}
+
+// Reads packed point numbers, returning `None` if they refer to all points.
+fn read_packed_point_numbers(reader: &mut &[u8]) -> Result<Option<Vec<u16>>, FontError> {
+    let first_byte = try!(reader.read_u8().map_err(FontError::eof));
+    let count = if (first_byte & POINT_COUNT_IS_WORD) != 0 {
+        let second_byte = try!(reader.read_u8().map_err(FontError::eof));
+        (((first_byte & !POINT_COUNT_IS_WORD) as u16) << 8) | second_byte as u16
+    } else {
+        first_byte as u16
+    };
+    if count == 0 {
+        return Ok(None)
+    }
+
+    // Point numbers are stored as runs of differences from the previous point number.
+    let mut point_numbers = Vec::with_capacity(count as usize);
+    let mut point_number = 0u16;
+    while point_numbers.len() < count as usize {
+        let control = try!(reader.read_u8().map_err(FontError::eof));
+        let run_count = (control & POINT_RUN_COUNT_MASK) as usize + 1;
+        for _ in 0..run_count {
+            let difference = if (control & POINTS_ARE_WORDS) != 0 {
+                try!(reader.read_u16::<BigEndian>().map_err(FontError::eof))
+            } else {
+                try!(reader.read_u8().map_err(FontError::eof)) as u16
+            };
+            point_number = point_number.wrapping_add(difference);
+            point_numbers.push(point_number)
+        }
+    }
+    point_numbers.truncate(count as usize);
+    Ok(Some(point_numbers))
+}
+
+// Reads `count` packed deltas.
+fn read_packed_deltas(reader: &mut &[u8], count: usize) -> Result<Vec<i16>, FontError> {
+    let mut deltas = Vec::with_capacity(count);
+    while deltas.len() < count {
+        let control = try!(reader.read_u8().map_err(FontError::eof));
+        let run_count = (control & DELTA_RUN_COUNT_MASK) as usize + 1;
+        for _ in 0..run_count {
+            let delta = if (control & DELTAS_ARE_ZERO) != 0 {
+                0
+            } else if (control & DELTAS_ARE_WORDS) != 0 {
+                try!(reader.read_i16::<BigEndian>().map_err(FontError::eof))
+            } else {
+                try!(reader.read_i8().map_err(FontError::eof)) as i16
+            };
+            deltas.push(delta)
+        }
+    }
+    deltas.truncate(count);
+    Ok(deltas)
+}
+
+// Fills in the deltas of the points in each contour that have none by interpolating between the
+// nearest points on either side that do ("IUP" in TrueType hinting terms).
+fn infer_deltas(points: &[Point2D<i16>],
+                contour_endpoints: &[u16],
+                deltas: &mut [Option<Point2D<f32>>])
+                -> Result<(), FontError> {
+    let mut contour_start = 0;
+    for &contour_endpoint in contour_endpoints {
+        let contour_end = contour_endpoint as usize + 1;
+        if contour_end <= contour_start || contour_end > points.len() {
+            return Err(FontError::GlyfMalformedGlyph)
+        }
+
+        let referenced: Vec<usize> = (contour_start..contour_end).filter(|&point_index| {
+            deltas[point_index].is_some()
+        }).collect();
+
+        // Walk the gaps between each pair of consecutive referenced points, wrapping around at the
+        // end of the contour.
+        for (index, &prev) in referenced.iter().enumerate() {
+            let next = referenced[(index + 1) % referenced.len()];
+            let (prev_point, next_point) = (points[prev], points[next]);
+            let (prev_delta, next_delta) = (deltas[prev].unwrap(), deltas[next].unwrap());
+
+            let mut point_index = prev;
+            loop {
+                point_index = if point_index + 1 == contour_end {
+                    contour_start
+                } else {
+                    point_index + 1
+                };
+                if point_index == next {
+                    break
+                }
+
+                let point = points[point_index];
+                let x = interpolate_delta((prev_point.x, prev_delta.x),
+                                          (next_point.x, next_delta.x),
+                                          point.x);
+                let y = interpolate_delta((prev_point.y, prev_delta.y),
+                                          (next_point.y, next_delta.y),
+                                          point.y);
+                deltas[point_index] = Some(Point2D::new(x, y))
+            }
+        }
+
+        contour_start = contour_end
+    }
+    Ok(())
+}
+
+// Interpolates the delta of a point at `coordinate` between two points, given as coordinates and
+// deltas along one axis. Points outside the range take the delta of the nearer point.
+fn interpolate_delta((a, a_delta): (i16, f32), (b, b_delta): (i16, f32), coordinate: i16) -> f32 {
+    if a == b {
+        return if a_delta == b_delta {
+            a_delta
+        } else {
+            0.0
+        }
+    }
+
+    let ((low, low_delta), (high, high_delta)) = if a < b {
+        ((a, a_delta), (b, b_delta))
+    } else {
+        ((b, b_delta), (a, a_delta))
+    };
+    if coordinate <= low {
+        low_delta
+    } else if coordinate >= high {
+        high_delta
+    } else {
+        let t = (coordinate as i32 - low as i32) as f32 / (high as i32 - low as i32) as f32;
+        low_delta + t * (high_delta - low_delta)
+    }
+}
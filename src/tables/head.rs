@@ -22,11 +22,16 @@ pub const TAG: u32 = ((b'h' as u32) << 24) |
 
 const MAGIC_NUMBER: u32 = 0x5f0f3cf5;
 
+const MIN_UNITS_PER_EM: u16 = 16;
+const MAX_UNITS_PER_EM: u16 = 16384;
+
 #[derive(Clone, Debug)]
 pub struct HeadTable {
-    pub units_per_em: u16,
-    pub index_to_loc_format: i16,
-    pub max_glyph_bounds: GlyphBounds,
+    units_per_em: u16,
+    index_to_loc_format: i16,
+    max_glyph_bounds: GlyphBounds,
+    mac_style: u16,
+    flags: u16,
 }
 
 impl HeadTable {
@@ -44,12 +49,15 @@ impl HeadTable {
         try!(reader.jump(mem::size_of::<u32>() * 2).map_err(FontError::eof));
         let magic_number = try!(reader.read_u32::<BigEndian>().map_err(FontError::eof));
         if magic_number != MAGIC_NUMBER {
-            return Err(FontError::UnknownFormat)
+            return Err(FontError::BadHeadMagicNumber)
         }
 
-        // Read the units per em.
-        try!(reader.jump(mem::size_of::<u16>()).map_err(FontError::eof));
+        // Read the flags and units per em.
+        let flags = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
         let units_per_em = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+        if units_per_em < MIN_UNITS_PER_EM || units_per_em > MAX_UNITS_PER_EM {
+            return Err(FontError::InvalidUnitsPerEm)
+        }
 
         // Read the maximum bounds.
         try!(reader.jump(mem::size_of::<i64>() * 2).map_err(FontError::eof));
@@ -64,10 +72,13 @@ impl HeadTable {
             top: y_max as i32,
         };
 
-        // Read the index-to-location format.
-        try!(reader.jump(mem::size_of::<u16>() * 2 + mem::size_of::<i16>())
-                   .map_err(FontError::eof));
+        // Read the Mac style and the index-to-location format.
+        let mac_style = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+        try!(reader.jump(mem::size_of::<u16>() + mem::size_of::<i16>()).map_err(FontError::eof));
         let index_to_loc_format = try!(reader.read_i16::<BigEndian>().map_err(FontError::eof));
+        if index_to_loc_format != 0 && index_to_loc_format != 1 {
+            return Err(FontError::InvalidIndexToLocFormat)
+        }
 
         // Check the glyph data format.
         let glyph_data_format = try!(reader.read_i16::<BigEndian>().map_err(FontError::eof));
@@ -79,6 +90,40 @@ impl HeadTable {
             units_per_em: units_per_em,
             index_to_loc_format: index_to_loc_format,
             max_glyph_bounds: max_glyph_bounds,
+            mac_style: mac_style,
+            flags: flags,
         })
     }
+
+    /// The number of font units per em, which glyph coordinates are expressed in.
+    ///
+    /// This is always between 16 and 16384, so it's safe to divide by.
+    #[inline]
+    pub fn units_per_em(&self) -> u16 {
+        self.units_per_em
+    }
+
+    /// Whether the `loca` table uses short (0) or long (1) offsets.
+    #[inline]
+    pub fn index_to_loc_format(&self) -> i16 {
+        self.index_to_loc_format
+    }
+
+    /// The union of the bounds of every glyph in the font, in font units.
+    #[inline]
+    pub fn max_glyph_bounds(&self) -> GlyphBounds {
+        self.max_glyph_bounds
+    }
+
+    /// The `macStyle` bits: bold, italic, and so on.
+    #[inline]
+    pub fn mac_style(&self) -> u16 {
+        self.mac_style
+    }
+
+    /// The `head` table's flags, such as whether the baseline is at y = 0.
+    #[inline]
+    pub fn flags(&self) -> u16 {
+        self.flags
+    }
 }
@@ -34,23 +34,27 @@ impl<'a> HmtxTable<'a> {
 
     pub fn metrics_for_glyph(&self, hhea_table: &HheaTable, glyph_id: u16)
                              -> Result<HorizontalMetrics, FontError> {
-        let mut reader = self.table.bytes;
-
-        // Read the advance width.
-        let advance_width;
-        if glyph_id < hhea_table.number_of_h_metrics {
-            try!(reader.jump(mem::size_of::<u16>() * 2 * glyph_id as usize).map_err(FontError::eof));
-            advance_width = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof))
-        } else {
-            try!(reader.jump(mem::size_of::<u16>() * 2 *
-                             (hhea_table.number_of_h_metrics - 1) as usize).map_err(FontError::eof));
-            advance_width = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
-            try!(reader.jump(mem::size_of::<i16>() * glyph_id as usize).map_err(FontError::eof));
-        }
+        self.metrics(glyph_id, hhea_table.number_of_h_metrics)
+    }
 
-        // Read the left-side bearing.
-        let lsb = try!(reader.read_i16::<BigEndian>().map_err(FontError::eof));
+    /// Returns the advance width of the given glyph, given `numberOfHMetrics` from the `hhea`
+    /// table.
+    #[inline]
+    pub fn advance_width(&self, glyph_id: u16, number_of_h_metrics: u16)
+                         -> Result<u16, FontError> {
+        self.metrics(glyph_id, number_of_h_metrics).map(|metrics| metrics.advance_width)
+    }
 
+    /// Returns the advance width and left side bearing of the given glyph, given
+    /// `numberOfHMetrics` from the `hhea` table.
+    ///
+    /// Glyphs past `numberOfHMetrics` share the last advance width but have their own left side
+    /// bearings, which follow the full metrics.
+    pub fn metrics(&self, glyph_id: u16, number_of_h_metrics: u16)
+                   -> Result<HorizontalMetrics, FontError> {
+        let (advance_width, lsb) = try!(read_long_metrics(self.table.bytes,
+                                                          glyph_id,
+                                                          number_of_h_metrics));
         Ok(HorizontalMetrics {
             advance_width: advance_width,
             lsb: lsb,
@@ -58,6 +62,36 @@ impl<'a> HmtxTable<'a> {
     }
 }
 
+/// Reads the advance and side bearing of the given glyph from a table laid out like `hmtx` and
+/// `vmtx`: full metrics for the first `number_of_long_metrics` glyphs, then side bearings alone.
+pub fn read_long_metrics(bytes: &[u8], glyph_id: u16, number_of_long_metrics: u16)
+                         -> Result<(u16, i16), FontError> {
+    if number_of_long_metrics == 0 {
+        return Err(FontError::UnexpectedEof)
+    }
+
+    let mut reader = bytes;
+
+    // Read the advance.
+    let advance;
+    if glyph_id < number_of_long_metrics {
+        try!(reader.jump(mem::size_of::<u16>() * 2 * glyph_id as usize).map_err(FontError::eof));
+        advance = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof))
+    } else {
+        try!(reader.jump(mem::size_of::<u16>() * 2 * (number_of_long_metrics - 1) as usize)
+                   .map_err(FontError::eof));
+        advance = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+
+        // Skip the last side bearing in the full metrics, then find ours.
+        let bearing_index = (glyph_id - number_of_long_metrics) as usize;
+        try!(reader.jump(mem::size_of::<i16>() * (1 + bearing_index)).map_err(FontError::eof));
+    }
+
+    // Read the side bearing.
+    let bearing = try!(reader.read_i16::<BigEndian>().map_err(FontError::eof));
+    Ok((advance, bearing))
+}
+
 #[derive(Clone, Copy, Default, Debug)]
 pub struct HorizontalMetrics {
     pub advance_width: u16,
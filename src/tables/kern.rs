@@ -30,7 +30,8 @@ bitflags! {
 
 #[derive(Clone, Copy)]
 pub struct KernTable<'a> {
-    horizontal_table: &'a [u8],
+    subtables: &'a [u8],
+    n_tables: u16,
 }
 
 impl<'a> KernTable<'a> {
@@ -42,8 +43,61 @@ impl<'a> KernTable<'a> {
         }
 
         let n_tables = try!(kern_reader.read_u16::<BigEndian>().map_err(FontError::eof));
-        let mut horizontal_table = None;
-        for _ in 0..n_tables {
+        let kern_table = KernTable {
+            subtables: kern_reader,
+            n_tables: n_tables,
+        };
+
+        // Make sure there's at least one subtable we can use.
+        let mut has_horizontal_table = false;
+        try!(kern_table.for_each_horizontal_subtable(|_, _| {
+            has_horizontal_table = true;
+            Ok(())
+        }));
+        if !has_horizontal_table {
+            return Err(FontError::UnknownFormat)
+        }
+
+        Ok(kern_table)
+    }
+
+    /// Returns the horizontal kerning for the given pair of glyphs in font units.
+    ///
+    /// Values from all horizontal format 0 subtables are summed, except that a subtable with the
+    /// override flag set replaces the sum so far.
+    pub fn kerning_for_glyph_pair(&self, left_glyph_id: u16, right_glyph_id: u16)
+                                  -> Result<i16, FontError> {
+        let mut kerning = 0i16;
+        try!(self.for_each_horizontal_subtable(|table_reader, coverage| {
+            let value = match try!(kerning_in_subtable(table_reader,
+                                                       left_glyph_id,
+                                                       right_glyph_id)) {
+                None => return Ok(()),
+                Some(value) => value,
+            };
+            if coverage.contains(OVERRIDE) {
+                kerning = value
+            } else {
+                kerning = kerning.wrapping_add(value)
+            }
+            Ok(())
+        }));
+        Ok(kerning)
+    }
+
+    /// Returns the horizontal kerning for the given pair of glyphs in font units, or zero if
+    /// there is none or the table is malformed.
+    #[inline]
+    pub fn kerning(&self, left_glyph_id: u16, right_glyph_id: u16) -> i16 {
+        self.kerning_for_glyph_pair(left_glyph_id, right_glyph_id).unwrap_or(0)
+    }
+
+    // Calls the callback with the contents and coverage of each format 0 subtable containing
+    // horizontal kerning values.
+    fn for_each_horizontal_subtable<F>(&self, mut callback: F) -> Result<(), FontError>
+                                       where F: FnMut(&'a [u8], Coverage) -> Result<(), FontError> {
+        let mut kern_reader = self.subtables;
+        for _ in 0..self.n_tables {
             let mut table_reader = kern_reader;
             let _version = try!(table_reader.read_u16::<BigEndian>().map_err(FontError::eof));
             let length = try!(table_reader.read_u16::<BigEndian>().map_err(FontError::eof));
@@ -52,50 +106,43 @@ impl<'a> KernTable<'a> {
 
             if coverage_flags.contains(HORIZONTAL) && !coverage_flags.contains(MINIMUM) &&
                     !coverage_flags.contains(CROSS_STREAM) && (coverage >> 8) == 0 {
-                let length = length as usize - mem::size_of::<u16>() * 3;
-                horizontal_table = Some(&table_reader[0..length]);
-                break
+                try!(callback(table_reader, coverage_flags))
             }
 
-            try!(kern_reader.jump(length as usize).map_err(FontError::eof));
-        }
-
-        match horizontal_table {
-            Some(horizontal_table) => {
-                Ok(KernTable {
-                    horizontal_table: horizontal_table,
-                })
+            // Some fonts have subtables too large for their 16-bit length field, so don't insist
+            // on the length being correct beyond the last subtable.
+            if kern_reader.jump(length as usize).is_err() {
+                break
             }
-            None => Err(FontError::UnknownFormat),
         }
+        Ok(())
     }
+}
 
-    pub fn kerning_for_glyph_pair(&self, left_glyph_id: u16, right_glyph_id: u16)
-                                  -> Result<i16, FontError> {
-        let mut table_reader = self.horizontal_table;
-        let n_pairs = try!(table_reader.read_u16::<BigEndian>().map_err(FontError::eof));
-        try!(table_reader.jump(mem::size_of::<[u16; 3]>()).map_err(FontError::eof));
-
-        let (mut low, mut high) = (0, n_pairs as u32);
-        while low < high {
-            let mut reader = table_reader;
-            let mid = (low + high) / 2;
-
-            try!(reader.jump(mid as usize * mem::size_of::<[u16; 3]>()).map_err(FontError::eof));
-            let left = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
-            let right = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
-            let value = try!(reader.read_i16::<BigEndian>().map_err(FontError::eof));
-
-            if left_glyph_id < left || (left_glyph_id == left && right_glyph_id < right) {
-                high = mid
-            } else if left_glyph_id > left || (left_glyph_id == left && right_glyph_id > right) {
-                low = mid + 1
-            } else {
-                return Ok(value)
-            }
-        }
+// Looks up a pair in the sorted pair list of a format 0 subtable.
+fn kerning_in_subtable(mut table_reader: &[u8], left_glyph_id: u16, right_glyph_id: u16)
+                       -> Result<Option<i16>, FontError> {
+    let n_pairs = try!(table_reader.read_u16::<BigEndian>().map_err(FontError::eof));
+    try!(table_reader.jump(mem::size_of::<[u16; 3]>()).map_err(FontError::eof));
+
+    let (mut low, mut high) = (0, n_pairs as u32);
+    while low < high {
+        let mut reader = table_reader;
+        let mid = (low + high) / 2;
+
+        try!(reader.jump(mid as usize * mem::size_of::<[u16; 3]>()).map_err(FontError::eof));
+        let left = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+        let right = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+        let value = try!(reader.read_i16::<BigEndian>().map_err(FontError::eof));
 
-        Ok(0)
+        if left_glyph_id < left || (left_glyph_id == left && right_glyph_id < right) {
+            high = mid
+        } else if left_glyph_id > left || (left_glyph_id == left && right_glyph_id > right) {
+            low = mid + 1
+        } else {
+            return Ok(Some(value))
+        }
     }
-}
 
+    Ok(None)
+}
@@ -0,0 +1,421 @@
+// Copyright 2017 The Servo Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! The OpenType Layout common table formats, shared by the `GPOS` and `GSUB` tables.
+//!
+//! See Microsoft's spec: https://www.microsoft.com/typography/otspec/chapter2.htm
+
+use byteorder::{BigEndian, ReadBytesExt};
+use error::FontError;
+use font::FontTable;
+use std::mem;
+use util::Jump;
+
+const SCRIPT_TAG_DEFAULT: u32 = ((b'D' as u32) << 24) |
+                                 ((b'F' as u32) << 16) |
+                                 ((b'L' as u32) << 8)  |
+                                  (b'T' as u32);
+const SCRIPT_TAG_LATIN: u32 = ((b'l' as u32) << 24) |
+                               ((b'a' as u32) << 16) |
+                               ((b't' as u32) << 8)  |
+                                (b'n' as u32);
+
+/// The script, feature, and lookup lists of a `GPOS` or `GSUB` table.
+#[derive(Clone, Copy, Debug)]
+pub struct LayoutTable<'a> {
+    script_list: &'a [u8],
+    feature_list: &'a [u8],
+    lookup_list: &'a [u8],
+}
+
+impl<'a> LayoutTable<'a> {
+    /// Reads the header of a `GPOS` or `GSUB` table. The caller is responsible for checking the
+    /// version first.
+    pub fn new(table: FontTable<'a>) -> Result<LayoutTable<'a>, FontError> {
+        let mut reader = table.bytes;
+        try!(reader.jump(mem::size_of::<u16>() * 2).map_err(FontError::eof));
+        let script_list_offset = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+        let feature_list_offset = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+        let lookup_list_offset = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+
+        Ok(LayoutTable {
+            script_list: try!(subtable(table.bytes, script_list_offset as usize)),
+            feature_list: try!(subtable(table.bytes, feature_list_offset as usize)),
+            lookup_list: try!(subtable(table.bytes, lookup_list_offset as usize)),
+        })
+    }
+
+    /// Returns an iterator over the scripts in the script list, yielding each script's tag and
+    /// Script table.
+    #[inline]
+    pub fn scripts(&self) -> Result<TaggedRecords<'a>, FontError> {
+        TaggedRecords::new(self.script_list)
+    }
+
+    /// Returns an iterator over the features in the feature list, yielding each feature's tag
+    /// and Feature table. Indices into the feature list count the items of this iterator.
+    #[inline]
+    pub fn features(&self) -> Result<TaggedRecords<'a>, FontError> {
+        TaggedRecords::new(self.feature_list)
+    }
+
+    /// Returns an iterator over the lookups in the lookup list, in order.
+    pub fn lookups(&self) -> Result<Lookups<'a>, FontError> {
+        let mut reader = self.lookup_list;
+        let count = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+        Ok(Lookups {
+            lookup_list: self.lookup_list,
+            reader: reader,
+            remaining: count,
+        })
+    }
+
+    /// Returns the lookup with the given index, or `None` if there is no such lookup.
+    pub fn lookup(&self, lookup_index: u16) -> Result<Option<Lookup<'a>>, FontError> {
+        let mut reader = self.lookup_list;
+        let lookup_count = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+        if lookup_index >= lookup_count {
+            return Ok(None)
+        }
+        try!(reader.jump(mem::size_of::<u16>() * lookup_index as usize).map_err(FontError::eof));
+        let lookup_offset = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+        Lookup::new(try!(subtable(self.lookup_list, lookup_offset as usize))).map(Some)
+    }
+
+    /// Returns the indices of the lookups in features with any of the given tags, in lookup
+    /// list order, which is the order in which they should be applied.
+    ///
+    /// The default script's default language system is used, or the Latin script's if there is
+    /// no default script. If neither exists, all features with matching tags apply.
+    pub fn lookup_indices_for_features(&self, feature_tags: &[u32])
+                                       -> Result<Vec<u16>, FontError> {
+        let feature_indices = try!(self.default_feature_indices());
+
+        let mut lookup_indices = vec![];
+        for (feature_index, feature) in try!(self.features()).enumerate() {
+            let (feature_tag, feature) = try!(feature);
+            if !feature_tags.contains(&feature_tag) {
+                continue
+            }
+            if let Some(ref feature_indices) = feature_indices {
+                if !feature_indices.contains(&(feature_index as u16)) {
+                    continue
+                }
+            }
+
+            let mut reader = feature;
+            let _feature_params = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+            let lookup_index_count = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+            for _ in 0..lookup_index_count {
+                lookup_indices.push(try!(reader.read_u16::<BigEndian>().map_err(FontError::eof)))
+            }
+        }
+
+        lookup_indices.sort();
+        lookup_indices.dedup();
+        Ok(lookup_indices)
+    }
+
+    // Returns the indices of the feature records that the default language system of the
+    // preferred script refers to, or `None` if there is no such language system.
+    fn default_feature_indices(&self) -> Result<Option<Vec<u16>>, FontError> {
+        let mut script = None;
+        for record in try!(self.scripts()) {
+            let (script_tag, script_table) = try!(record);
+            if script_tag == SCRIPT_TAG_DEFAULT {
+                script = Some(script_table);
+                break
+            }
+            if script_tag == SCRIPT_TAG_LATIN {
+                script = Some(script_table)
+            }
+        }
+
+        let script = match script {
+            None => return Ok(None),
+            Some(script) => script,
+        };
+        let mut reader = script;
+        let default_lang_sys_offset = try!(reader.read_u16::<BigEndian>()
+                                                 .map_err(FontError::eof));
+        if default_lang_sys_offset == 0 {
+            return Ok(None)
+        }
+
+        let mut lang_sys = try!(subtable(script, default_lang_sys_offset as usize));
+        let _lookup_order = try!(lang_sys.read_u16::<BigEndian>().map_err(FontError::eof));
+        let required_feature_index = try!(lang_sys.read_u16::<BigEndian>()
+                                                  .map_err(FontError::eof));
+        let feature_index_count = try!(lang_sys.read_u16::<BigEndian>()
+                                               .map_err(FontError::eof));
+
+        let mut feature_indices = vec![];
+        if required_feature_index != 0xffff {
+            feature_indices.push(required_feature_index)
+        }
+        for _ in 0..feature_index_count {
+            feature_indices.push(try!(lang_sys.read_u16::<BigEndian>().map_err(FontError::eof)))
+        }
+        Ok(Some(feature_indices))
+    }
+}
+
+/// An iterator over the records of a script or feature list.
+///
+/// Every call to `next()` returns the record's tag and the table that it points to. Iteration
+/// stops after the first error.
+#[derive(Clone)]
+pub struct TaggedRecords<'a> {
+    list: &'a [u8],
+    reader: &'a [u8],
+    remaining: u16,
+}
+
+impl<'a> TaggedRecords<'a> {
+    fn new(list: &'a [u8]) -> Result<TaggedRecords<'a>, FontError> {
+        let mut reader = list;
+        let count = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+        Ok(TaggedRecords {
+            list: list,
+            reader: reader,
+            remaining: count,
+        })
+    }
+
+    fn read_record(&mut self) -> Result<(u32, &'a [u8]), FontError> {
+        let tag = try!(self.reader.read_u32::<BigEndian>().map_err(FontError::eof));
+        let offset = try!(self.reader.read_u16::<BigEndian>().map_err(FontError::eof));
+        Ok((tag, try!(subtable(self.list, offset as usize))))
+    }
+}
+
+impl<'a> Iterator for TaggedRecords<'a> {
+    type Item = Result<(u32, &'a [u8]), FontError>;
+
+    fn next(&mut self) -> Option<Result<(u32, &'a [u8]), FontError>> {
+        if self.remaining == 0 {
+            return None
+        }
+
+        let record = self.read_record();
+        self.remaining = if record.is_ok() {
+            self.remaining - 1
+        } else {
+            0
+        };
+        Some(record)
+    }
+}
+
+/// An iterator over the lookups in a lookup list. Iteration stops after the first error.
+#[derive(Clone)]
+pub struct Lookups<'a> {
+    lookup_list: &'a [u8],
+    reader: &'a [u8],
+    remaining: u16,
+}
+
+impl<'a> Iterator for Lookups<'a> {
+    type Item = Result<Lookup<'a>, FontError>;
+
+    fn next(&mut self) -> Option<Result<Lookup<'a>, FontError>> {
+        if self.remaining == 0 {
+            return None
+        }
+
+        let lookup_list = self.lookup_list;
+        let lookup = self.reader.read_u16::<BigEndian>().map_err(FontError::eof).and_then(|offset| {
+            subtable(lookup_list, offset as usize).and_then(Lookup::new)
+        });
+        self.remaining = if lookup.is_ok() {
+            self.remaining - 1
+        } else {
+            0
+        };
+        Some(lookup)
+    }
+}
+
+/// A single lookup in the lookup list.
+#[derive(Clone, Copy, Debug)]
+pub struct Lookup<'a> {
+    pub lookup_type: u16,
+    pub lookup_flag: u16,
+    table: &'a [u8],
+}
+
+impl<'a> Lookup<'a> {
+    fn new(table: &'a [u8]) -> Result<Lookup<'a>, FontError> {
+        let mut reader = table;
+        let lookup_type = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+        let lookup_flag = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+        Ok(Lookup {
+            lookup_type: lookup_type,
+            lookup_flag: lookup_flag,
+            table: table,
+        })
+    }
+
+    /// Returns the type and contents of each of this lookup's subtables, in order.
+    ///
+    /// If this is an extension lookup (`extension_lookup_type`), the extension subtables are
+    /// unwrapped, and the types returned are those of the subtables they point to.
+    pub fn subtables(&self, extension_lookup_type: u16)
+                     -> Result<Vec<(u16, &'a [u8])>, FontError> {
+        let mut reader = self.table;
+        try!(reader.jump(mem::size_of::<u16>() * 2).map_err(FontError::eof));
+        let subtable_count = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+
+        let mut subtables = vec![];
+        for _ in 0..subtable_count {
+            let subtable_offset = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+            let lookup_subtable = try!(subtable(self.table, subtable_offset as usize));
+            if self.lookup_type != extension_lookup_type {
+                subtables.push((self.lookup_type, lookup_subtable));
+                continue
+            }
+
+            let mut extension = lookup_subtable;
+            let _format = try!(extension.read_u16::<BigEndian>().map_err(FontError::eof));
+            let lookup_type = try!(extension.read_u16::<BigEndian>().map_err(FontError::eof));
+            let extension_offset = try!(extension.read_u32::<BigEndian>()
+                                                 .map_err(FontError::eof));
+            subtables.push((lookup_type,
+                            try!(subtable(lookup_subtable, extension_offset as usize))))
+        }
+        Ok(subtables)
+    }
+}
+
+/// A Coverage table, which lists glyphs and assigns each an index.
+#[derive(Clone, Copy, Debug)]
+pub struct Coverage<'a> {
+    table: &'a [u8],
+}
+
+impl<'a> Coverage<'a> {
+    /// Wraps the Coverage table at the given offset from the start of `parent`.
+    #[inline]
+    pub fn new(parent: &'a [u8], offset: u16) -> Result<Coverage<'a>, FontError> {
+        Ok(Coverage {
+            table: try!(subtable(parent, offset as usize)),
+        })
+    }
+
+    /// Returns the index of the glyph in this table, or `None` if it isn't covered.
+    pub fn coverage_index(&self, glyph_id: u16) -> Result<Option<u16>, FontError> {
+        let mut reader = self.table;
+        let format = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+        let count = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+        let (mut low, mut high) = (0, count as usize);
+        match format {
+            1 => {
+                // Binary search the glyph array.
+                while low < high {
+                    let mid = (low + high) / 2;
+                    let mut glyph = reader;
+                    try!(glyph.jump(mid * mem::size_of::<u16>()).map_err(FontError::eof));
+                    let glyph = try!(glyph.read_u16::<BigEndian>().map_err(FontError::eof));
+                    if glyph_id < glyph {
+                        high = mid
+                    } else if glyph_id > glyph {
+                        low = mid + 1
+                    } else {
+                        return Ok(Some(mid as u16))
+                    }
+                }
+                Ok(None)
+            }
+            2 => {
+                // Binary search the range records.
+                while low < high {
+                    let mid = (low + high) / 2;
+                    let mut range = reader;
+                    try!(range.jump(mid * mem::size_of::<[u16; 3]>()).map_err(FontError::eof));
+                    let start = try!(range.read_u16::<BigEndian>().map_err(FontError::eof));
+                    let end = try!(range.read_u16::<BigEndian>().map_err(FontError::eof));
+                    let start_coverage_index = try!(range.read_u16::<BigEndian>()
+                                                         .map_err(FontError::eof));
+                    if glyph_id < start {
+                        high = mid
+                    } else if glyph_id > end {
+                        low = mid + 1
+                    } else {
+                        return Ok(Some(start_coverage_index.wrapping_add(glyph_id - start)))
+                    }
+                }
+                Ok(None)
+            }
+            _ => Ok(None),
+        }
+    }
+}
+
+/// A Class Definition table, which assigns glyphs to classes.
+#[derive(Clone, Copy, Debug)]
+pub struct ClassDef<'a> {
+    table: &'a [u8],
+}
+
+impl<'a> ClassDef<'a> {
+    /// Wraps the Class Definition table at the given offset from the start of `parent`.
+    #[inline]
+    pub fn new(parent: &'a [u8], offset: u16) -> Result<ClassDef<'a>, FontError> {
+        Ok(ClassDef {
+            table: try!(subtable(parent, offset as usize)),
+        })
+    }
+
+    /// Returns the class of the glyph. Glyphs not mentioned in the table are in class 0.
+    pub fn class_of(&self, glyph_id: u16) -> Result<u16, FontError> {
+        let mut reader = self.table;
+        let format = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+        match format {
+            1 => {
+                let start_glyph_id = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+                let glyph_count = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+                if glyph_id < start_glyph_id || glyph_id - start_glyph_id >= glyph_count {
+                    return Ok(0)
+                }
+                try!(reader.jump(mem::size_of::<u16>() * (glyph_id - start_glyph_id) as usize)
+                           .map_err(FontError::eof));
+                reader.read_u16::<BigEndian>().map_err(FontError::eof)
+            }
+            2 => {
+                let class_range_count = try!(reader.read_u16::<BigEndian>()
+                                                   .map_err(FontError::eof));
+                let (mut low, mut high) = (0, class_range_count as usize);
+                while low < high {
+                    let mid = (low + high) / 2;
+                    let mut range = reader;
+                    try!(range.jump(mid * mem::size_of::<[u16; 3]>()).map_err(FontError::eof));
+                    let start = try!(range.read_u16::<BigEndian>().map_err(FontError::eof));
+                    let end = try!(range.read_u16::<BigEndian>().map_err(FontError::eof));
+                    if glyph_id < start {
+                        high = mid
+                    } else if glyph_id > end {
+                        low = mid + 1
+                    } else {
+                        return range.read_u16::<BigEndian>().map_err(FontError::eof)
+                    }
+                }
+                Ok(0)
+            }
+            _ => Ok(0),
+        }
+    }
+}
+
+/// Returns the part of the table starting at the given offset.
+pub fn subtable(table: &[u8], offset: usize) -> Result<&[u8], FontError> {
+    let mut reader = table;
+    try!(reader.jump(offset).map_err(FontError::eof));
+    Ok(reader)
+}
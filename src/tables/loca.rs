@@ -8,56 +8,111 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
+//! The glyph location table, which gives the offset of each glyph's data in the `glyf` table.
+//!
+//! See Microsoft's spec: https://www.microsoft.com/typography/otspec/loca.htm
+
 use byteorder::{BigEndian, ReadBytesExt};
 use error::FontError;
 use font::FontTable;
+use std::mem;
+use std::ops::Range;
 use tables::head::HeadTable;
-use util::Jump;
 
 pub const TAG: u32 = ((b'l' as u32) << 24) |
                       ((b'o' as u32) << 16) |
                       ((b'c' as u32) << 8)  |
                        (b'a' as u32);
 
+#[derive(Clone, Copy, Debug)]
 pub struct LocaTable<'a> {
-    table: FontTable<'a>,
+    offsets: &'a [u8],
+    offset_size: usize,
 }
 
 impl<'a> LocaTable<'a> {
-    pub fn new(loca_table: FontTable<'a>) -> Result<LocaTable<'a>, FontError> {
-        Ok(LocaTable {
-            table: loca_table,
-        })
-    }
+    /// Parses a `loca` table, whose offsets are short or long according to the `head` table's
+    /// `indexToLocFormat`.
+    ///
+    /// Fails if the offsets ever go backwards.
+    pub fn new(loca_table: FontTable<'a>, head_table: &HeadTable)
+               -> Result<LocaTable<'a>, FontError> {
+        // `HeadTable` only accepts formats 0 and 1.
+        let offset_size = if head_table.index_to_loc_format() == 0 {
+            mem::size_of::<u16>()
+        } else {
+            mem::size_of::<u32>()
+        };
+        let table = LocaTable {
+            offsets: loca_table.bytes,
+            offset_size: offset_size,
+        };
 
-    pub fn location_of(&self, head_table: &HeadTable, glyph_id: u16)
-                       -> Result<Option<u32>, FontError> {
-        let mut reader = self.table.bytes;
-        let (this_location, next_location) = match head_table.index_to_loc_format {
-            0 => {
-                try!(reader.jump(glyph_id as usize * 2).map_err(FontError::eof));
-                let this_location =
-                    try!(reader.read_u16::<BigEndian>().map_err(FontError::eof)) as u32 * 2;
-                let next_location = match reader.read_u16::<BigEndian>() {
-                    Ok(next_location) => Ok(next_location as u32 * 2),
-                    Err(_) => Err(FontError::UnexpectedEof),
-                };
-                (this_location, next_location)
-            }
-            1 => {
-                try!(reader.jump(glyph_id as usize * 4).map_err(FontError::eof));
-                let this_location = try!(reader.read_u32::<BigEndian>().map_err(FontError::eof));
-                let next_location = reader.read_u32::<BigEndian>().map_err(FontError::eof);
-                (this_location, next_location)
+        let mut last_location = 0;
+        for index in 0..table.len() {
+            let location = try!(table.location(index));
+            if location < last_location {
+                return Err(FontError::LocaMalformed)
             }
-            _ => return Err(FontError::UnknownFormat),
-        };
+            last_location = location
+        }
+        Ok(table)
+    }
 
-        if next_location == Ok(this_location) {
+    pub fn location_of(&self, glyph_id: u16) -> Result<Option<u32>, FontError> {
+        let this_location = try!(self.location(glyph_id as usize));
+        if self.location(glyph_id as usize + 1) == Ok(this_location) {
             Ok(None)
         } else {
             Ok(Some(this_location))
         }
     }
-}
 
+    /// Returns the start and end offsets of the given glyph's data in the `glyf` table, or `None`
+    /// if the glyph has no outline.
+    pub fn range_of(&self, glyph_id: u16) -> Result<Option<(u32, u32)>, FontError> {
+        let this_location = try!(self.location(glyph_id as usize));
+        let next_location = try!(self.location(glyph_id as usize + 1));
+        if next_location == this_location {
+            Ok(None)
+        } else {
+            Ok(Some((this_location, next_location)))
+        }
+    }
+
+    /// Returns the byte range of the given glyph's data in the `glyf` table, which is empty if
+    /// the glyph has no outline, or `None` if the table has no such glyph.
+    pub fn glyph_range(&self, glyph_id: u16) -> Option<Range<usize>> {
+        match (self.location(glyph_id as usize), self.location(glyph_id as usize + 1)) {
+            (Ok(start), Ok(end)) => Some((start as usize)..(end as usize)),
+            _ => None,
+        }
+    }
+
+    /// Returns true if the given glyph has no outline, as spaces usually don't.
+    ///
+    /// Glyphs that the table doesn't have aren't empty.
+    #[inline]
+    pub fn is_empty_glyph(&self, glyph_id: u16) -> bool {
+        self.glyph_range(glyph_id).map_or(false, |range| range.start == range.end)
+    }
+
+    // Returns the number of offsets in the table, one more than the number of glyphs.
+    #[inline]
+    fn len(&self) -> usize {
+        self.offsets.len() / self.offset_size
+    }
+
+    // Returns the offset at the given index, which is read directly from where it is in the table.
+    fn location(&self, index: usize) -> Result<u32, FontError> {
+        if index >= self.len() {
+            return Err(FontError::UnexpectedEof)
+        }
+        let mut reader = &self.offsets[(index * self.offset_size)..];
+        if self.offset_size == mem::size_of::<u16>() {
+            Ok(try!(reader.read_u16::<BigEndian>().map_err(FontError::eof)) as u32 * 2)
+        } else {
+            reader.read_u32::<BigEndian>().map_err(FontError::eof)
+        }
+    }
+}
@@ -0,0 +1,422 @@
+// Copyright 2017 The Servo Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! The mathematical typesetting table, which holds the metrics needed to lay out formulas.
+//!
+//! Device tables, which adjust values at particular sizes, and math kerning are not supported.
+//!
+//! See Microsoft's spec: https://www.microsoft.com/typography/otspec/math.htm
+
+use byteorder::{BigEndian, ReadBytesExt};
+use error::{ErrorContext, FontError};
+use font::{FontTable, MathDirection};
+use std::mem;
+use tables::layout::{self, Coverage};
+use util::{EofContext, Jump};
+
+pub const TAG: u32 = ((b'M' as u32) << 24) |
+                      ((b'A' as u32) << 16) |
+                      ((b'T' as u32) << 8)  |
+                       (b'H' as u32);
+
+// The size of a `MathValueRecord`: a value and an offset to a device table.
+const MATH_VALUE_RECORD_SIZE: usize = 4;
+// The size of a `MathGlyphVariantRecord`: a glyph ID and its advance.
+const VARIANT_RECORD_SIZE: usize = 4;
+// The size of a `GlyphPartRecord`.
+const PART_RECORD_SIZE: usize = 10;
+
+// Set in the flags of a glyph part that can be repeated to make an assembly longer.
+const PART_FLAG_EXTENDER: u16 = 0x0001;
+
+#[derive(Clone, Copy, Debug)]
+pub struct MathTable<'a> {
+    table: FontTable<'a>,
+    math_constants_offset: u16,
+    math_glyph_info_offset: u16,
+    math_variants_offset: u16,
+}
+
+impl<'a> MathTable<'a> {
+    pub fn new(table: FontTable<'a>) -> Result<MathTable<'a>, FontError> {
+        let mut reader = table.bytes;
+
+        // Check the version.
+        let major_version = try!(reader.read_u16::<BigEndian>().eof_in(TAG, "major version"));
+        let minor_version = try!(reader.read_u16::<BigEndian>().eof_in(TAG, "minor version"));
+        if (major_version, minor_version) != (1, 0) {
+            return Err(FontError::UnsupportedMathVersion)
+        }
+
+        let math_constants_offset = try!(reader.read_u16::<BigEndian>()
+                                               .eof_in(TAG, "math constants offset"));
+        let math_glyph_info_offset = try!(reader.read_u16::<BigEndian>()
+                                                .eof_in(TAG, "math glyph info offset"));
+        let math_variants_offset = try!(reader.read_u16::<BigEndian>()
+                                              .eof_in(TAG, "math variants offset"));
+
+        Ok(MathTable {
+            table: table,
+            math_constants_offset: math_constants_offset,
+            math_glyph_info_offset: math_glyph_info_offset,
+            math_variants_offset: math_variants_offset,
+        })
+    }
+
+    /// Returns the global metrics used to position the parts of formulas.
+    pub fn constants(&self) -> Result<MathConstants, FontError> {
+        let mut reader = try!(layout::subtable(self.table.bytes,
+                                               self.math_constants_offset as usize));
+        let reader = &mut reader;
+        Ok(MathConstants {
+            script_percent_scale_down: try!(reader.read_i16::<BigEndian>()
+                                                  .eof_in(TAG, "math constants")),
+            script_script_percent_scale_down: try!(reader.read_i16::<BigEndian>()
+                                                         .eof_in(TAG, "math constants")),
+            delimited_sub_formula_min_height: try!(reader.read_u16::<BigEndian>()
+                                                         .eof_in(TAG, "math constants")),
+            display_operator_min_height: try!(reader.read_u16::<BigEndian>()
+                                                    .eof_in(TAG, "math constants")),
+            math_leading: try!(read_math_value(reader)),
+            axis_height: try!(read_math_value(reader)),
+            accent_base_height: try!(read_math_value(reader)),
+            flattened_accent_base_height: try!(read_math_value(reader)),
+            subscript_shift_down: try!(read_math_value(reader)),
+            subscript_top_max: try!(read_math_value(reader)),
+            subscript_baseline_drop_min: try!(read_math_value(reader)),
+            superscript_shift_up: try!(read_math_value(reader)),
+            superscript_shift_up_cramped: try!(read_math_value(reader)),
+            superscript_bottom_min: try!(read_math_value(reader)),
+            superscript_baseline_drop_max: try!(read_math_value(reader)),
+            sub_superscript_gap_min: try!(read_math_value(reader)),
+            superscript_bottom_max_with_subscript: try!(read_math_value(reader)),
+            space_after_script: try!(read_math_value(reader)),
+            upper_limit_gap_min: try!(read_math_value(reader)),
+            upper_limit_baseline_rise_min: try!(read_math_value(reader)),
+            lower_limit_gap_min: try!(read_math_value(reader)),
+            lower_limit_baseline_drop_min: try!(read_math_value(reader)),
+            stack_top_shift_up: try!(read_math_value(reader)),
+            stack_top_display_style_shift_up: try!(read_math_value(reader)),
+            stack_bottom_shift_down: try!(read_math_value(reader)),
+            stack_bottom_display_style_shift_down: try!(read_math_value(reader)),
+            stack_gap_min: try!(read_math_value(reader)),
+            stack_display_style_gap_min: try!(read_math_value(reader)),
+            stretch_stack_top_shift_up: try!(read_math_value(reader)),
+            stretch_stack_bottom_shift_down: try!(read_math_value(reader)),
+            stretch_stack_gap_above_min: try!(read_math_value(reader)),
+            stretch_stack_gap_below_min: try!(read_math_value(reader)),
+            fraction_numerator_shift_up: try!(read_math_value(reader)),
+            fraction_numerator_display_style_shift_up: try!(read_math_value(reader)),
+            fraction_denominator_shift_down: try!(read_math_value(reader)),
+            fraction_denominator_display_style_shift_down: try!(read_math_value(reader)),
+            fraction_numerator_gap_min: try!(read_math_value(reader)),
+            fraction_num_display_style_gap_min: try!(read_math_value(reader)),
+            fraction_rule_thickness: try!(read_math_value(reader)),
+            fraction_denominator_gap_min: try!(read_math_value(reader)),
+            fraction_denom_display_style_gap_min: try!(read_math_value(reader)),
+            skewed_fraction_horizontal_gap: try!(read_math_value(reader)),
+            skewed_fraction_vertical_gap: try!(read_math_value(reader)),
+            overbar_vertical_gap: try!(read_math_value(reader)),
+            overbar_rule_thickness: try!(read_math_value(reader)),
+            overbar_extra_ascender: try!(read_math_value(reader)),
+            underbar_vertical_gap: try!(read_math_value(reader)),
+            underbar_rule_thickness: try!(read_math_value(reader)),
+            underbar_extra_descender: try!(read_math_value(reader)),
+            radical_vertical_gap: try!(read_math_value(reader)),
+            radical_display_style_vertical_gap: try!(read_math_value(reader)),
+            radical_rule_thickness: try!(read_math_value(reader)),
+            radical_extra_ascender: try!(read_math_value(reader)),
+            radical_kern_before_degree: try!(read_math_value(reader)),
+            radical_kern_after_degree: try!(read_math_value(reader)),
+            radical_degree_bottom_raise_percent: try!(reader.read_i16::<BigEndian>()
+                                                            .eof_in(TAG, "math constants")),
+        })
+    }
+
+    /// Returns the italic correction of the given glyph, to be added after it when it's followed
+    /// by an upright glyph, or `None` if the font doesn't give one.
+    pub fn italic_correction(&self, glyph_id: u16) -> Result<Option<i16>, FontError> {
+        self.glyph_info_value(0, glyph_id)
+    }
+
+    /// Returns the horizontal position at which accents should be centered over the given glyph,
+    /// or `None` if the font doesn't give one. Accents are then centered over the glyph's advance.
+    pub fn top_accent_attachment(&self, glyph_id: u16) -> Result<Option<i16>, FontError> {
+        self.glyph_info_value(1, glyph_id)
+    }
+
+    /// Returns the overlap that the connectors of adjacent parts of a glyph assembly must have at
+    /// least.
+    pub fn min_connector_overlap(&self) -> Result<u16, FontError> {
+        let mut reader = try!(layout::subtable(self.table.bytes,
+                                               self.math_variants_offset as usize));
+        reader.read_u16::<BigEndian>().eof_in(TAG, "min connector overlap")
+    }
+
+    /// Returns the larger versions of the given glyph that the font provides for stretching it
+    /// in the given direction, such as for tall parentheses, in increasing order of size.
+    ///
+    /// The result begins with the glyph itself if the font lists it and is empty if the glyph
+    /// can't be stretched.
+    pub fn glyph_variants(&self, glyph_id: u16, direction: MathDirection)
+                          -> Result<Vec<GlyphVariant>, FontError> {
+        let mut reader = match try!(self.glyph_construction(glyph_id, direction)) {
+            None => return Ok(vec![]),
+            Some(glyph_construction) => glyph_construction,
+        };
+        try!(reader.jump(mem::size_of::<u16>()).eof_in(TAG, "glyph assembly offset"));
+        let variant_count = try!(reader.read_u16::<BigEndian>().eof_in(TAG, "variant count"));
+        if reader.len() < variant_count as usize * VARIANT_RECORD_SIZE {
+            return Err(FontError::UnexpectedEofIn(ErrorContext {
+                table: TAG,
+                field: "glyph variants",
+            }))
+        }
+
+        let mut variants = Vec::with_capacity(variant_count as usize);
+        for _ in 0..variant_count {
+            variants.push(GlyphVariant {
+                glyph_id: try!(reader.read_u16::<BigEndian>().eof_in(TAG, "variant glyph ID")),
+                advance: try!(reader.read_u16::<BigEndian>().eof_in(TAG, "variant advance")),
+            })
+        }
+        Ok(variants)
+    }
+
+    /// Returns the pieces from which the given glyph can be built to any size in the given
+    /// direction, for when none of its variants are big enough, or `None` if the font doesn't
+    /// have any.
+    pub fn glyph_assembly(&self, glyph_id: u16, direction: MathDirection)
+                          -> Result<Option<GlyphAssembly>, FontError> {
+        let glyph_construction = match try!(self.glyph_construction(glyph_id, direction)) {
+            None => return Ok(None),
+            Some(glyph_construction) => glyph_construction,
+        };
+        let mut reader = glyph_construction;
+        let glyph_assembly_offset = try!(reader.read_u16::<BigEndian>()
+                                               .eof_in(TAG, "glyph assembly offset"));
+        if glyph_assembly_offset == 0 {
+            return Ok(None)
+        }
+
+        let mut reader = try!(layout::subtable(glyph_construction,
+                                               glyph_assembly_offset as usize));
+        let italics_correction = try!(read_math_value(&mut reader));
+        let part_count = try!(reader.read_u16::<BigEndian>().eof_in(TAG, "part count"));
+        if reader.len() < part_count as usize * PART_RECORD_SIZE {
+            return Err(FontError::UnexpectedEofIn(ErrorContext {
+                table: TAG,
+                field: "glyph parts",
+            }))
+        }
+
+        let mut parts = Vec::with_capacity(part_count as usize);
+        for _ in 0..part_count {
+            let glyph_id = try!(reader.read_u16::<BigEndian>().eof_in(TAG, "part glyph ID"));
+            let start_connector_length = try!(reader.read_u16::<BigEndian>()
+                                                    .eof_in(TAG, "start connector length"));
+            let end_connector_length = try!(reader.read_u16::<BigEndian>()
+                                                  .eof_in(TAG, "end connector length"));
+            let full_advance = try!(reader.read_u16::<BigEndian>().eof_in(TAG, "full advance"));
+            let part_flags = try!(reader.read_u16::<BigEndian>().eof_in(TAG, "part flags"));
+            parts.push(GlyphPart {
+                glyph_id: glyph_id,
+                start_connector_length: start_connector_length,
+                end_connector_length: end_connector_length,
+                full_advance: full_advance,
+                is_extender: (part_flags & PART_FLAG_EXTENDER) != 0,
+            })
+        }
+
+        Ok(Some(GlyphAssembly {
+            italics_correction: italics_correction,
+            parts: parts,
+        }))
+    }
+
+    // Looks up the given glyph in a subtable of the `MathGlyphInfo` table that holds a coverage
+    // table and one `MathValueRecord` per covered glyph. Index 0 is the italics correction table
+    // and index 1 is the top accent attachment table.
+    fn glyph_info_value(&self, subtable_index: usize, glyph_id: u16)
+                        -> Result<Option<i16>, FontError> {
+        let glyph_info = try!(layout::subtable(self.table.bytes,
+                                               self.math_glyph_info_offset as usize));
+        let mut reader = glyph_info;
+        try!(reader.jump(mem::size_of::<u16>() * subtable_index)
+                   .eof_in(TAG, "glyph info subtable offsets"));
+        let subtable_offset = try!(reader.read_u16::<BigEndian>()
+                                         .eof_in(TAG, "glyph info subtable offset"));
+        if subtable_offset == 0 {
+            return Ok(None)
+        }
+
+        let subtable = try!(layout::subtable(glyph_info, subtable_offset as usize));
+        let mut reader = subtable;
+        let coverage_offset = try!(reader.read_u16::<BigEndian>().eof_in(TAG, "coverage offset"));
+        let count = try!(reader.read_u16::<BigEndian>().eof_in(TAG, "value count"));
+        let coverage_index = match try!(try!(Coverage::new(subtable, coverage_offset))
+                                            .coverage_index(glyph_id)) {
+            Some(coverage_index) if coverage_index < count => coverage_index,
+            _ => return Ok(None),
+        };
+
+        try!(reader.jump(MATH_VALUE_RECORD_SIZE * coverage_index as usize)
+                   .eof_in(TAG, "math value records"));
+        read_math_value(&mut reader).map(Some)
+    }
+
+    // Returns the `MathGlyphConstruction` table for the given glyph in the given direction, if
+    // the glyph is covered.
+    fn glyph_construction(&self, glyph_id: u16, direction: MathDirection)
+                          -> Result<Option<&'a [u8]>, FontError> {
+        let math_variants = try!(layout::subtable(self.table.bytes,
+                                                  self.math_variants_offset as usize));
+        let mut reader = math_variants;
+        try!(reader.jump(mem::size_of::<u16>()).eof_in(TAG, "min connector overlap"));
+        let vert_glyph_coverage_offset = try!(reader.read_u16::<BigEndian>()
+                                                    .eof_in(TAG, "vertical coverage offset"));
+        let horiz_glyph_coverage_offset = try!(reader.read_u16::<BigEndian>()
+                                                     .eof_in(TAG, "horizontal coverage offset"));
+        let vert_glyph_count = try!(reader.read_u16::<BigEndian>()
+                                          .eof_in(TAG, "vertical glyph count"));
+        let horiz_glyph_count = try!(reader.read_u16::<BigEndian>()
+                                           .eof_in(TAG, "horizontal glyph count"));
+
+        // The horizontal constructions follow the vertical ones.
+        let (coverage_offset, count, first_index) = match direction {
+            MathDirection::Vertical => (vert_glyph_coverage_offset, vert_glyph_count, 0),
+            MathDirection::Horizontal => {
+                (horiz_glyph_coverage_offset, horiz_glyph_count, vert_glyph_count)
+            }
+        };
+        if coverage_offset == 0 {
+            return Ok(None)
+        }
+
+        let coverage_index = match try!(try!(Coverage::new(math_variants, coverage_offset))
+                                            .coverage_index(glyph_id)) {
+            Some(coverage_index) if coverage_index < count => coverage_index,
+            _ => return Ok(None),
+        };
+
+        let index = first_index as usize + coverage_index as usize;
+        try!(reader.jump(mem::size_of::<u16>() * index).eof_in(TAG, "glyph construction offsets"));
+        let glyph_construction_offset = try!(reader.read_u16::<BigEndian>()
+                                                   .eof_in(TAG, "glyph construction offset"));
+        layout::subtable(math_variants, glyph_construction_offset as usize).map(Some)
+    }
+}
+
+// Reads the value of a `MathValueRecord`, ignoring its device table.
+fn read_math_value(reader: &mut &[u8]) -> Result<i16, FontError> {
+    let value = try!(reader.read_i16::<BigEndian>().eof_in(TAG, "math value record"));
+    try!(reader.jump(mem::size_of::<u16>()).eof_in(TAG, "math value record"));
+    Ok(value)
+}
+
+/// A larger version of a glyph, for stretching it.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct GlyphVariant {
+    /// The glyph to draw.
+    pub glyph_id: u16,
+    /// The size of the glyph in the direction of stretching, in font units.
+    pub advance: u16,
+}
+
+/// The recipe for building a glyph of any size out of parts.
+#[derive(Clone, PartialEq, Debug)]
+pub struct GlyphAssembly {
+    /// The italic correction of the assembled glyph.
+    pub italics_correction: i16,
+    /// The parts, from left to right or bottom to top.
+    pub parts: Vec<GlyphPart>,
+}
+
+/// A part of a glyph assembly.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct GlyphPart {
+    /// The glyph to draw.
+    pub glyph_id: u16,
+    /// How much of the start of this part may overlap the previous part.
+    pub start_connector_length: u16,
+    /// How much of the end of this part may overlap the next part.
+    pub end_connector_length: u16,
+    /// The size of the part in the direction of stretching, in font units.
+    pub full_advance: u16,
+    /// Whether this part may be repeated, or left out, to change the size of the assembly.
+    pub is_extender: bool,
+}
+
+/// The global metrics of a math font, in font units unless otherwise noted.
+///
+/// See the spec for the meaning of each field.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct MathConstants {
+    /// The percentage to scale down by for the first level of superscripts and subscripts.
+    pub script_percent_scale_down: i16,
+    /// The percentage to scale down by for the second level of superscripts and subscripts.
+    pub script_script_percent_scale_down: i16,
+    pub delimited_sub_formula_min_height: u16,
+    pub display_operator_min_height: u16,
+    pub math_leading: i16,
+    /// The height of the math axis, on which fraction bars and operators like + are centered.
+    pub axis_height: i16,
+    pub accent_base_height: i16,
+    pub flattened_accent_base_height: i16,
+    pub subscript_shift_down: i16,
+    pub subscript_top_max: i16,
+    pub subscript_baseline_drop_min: i16,
+    pub superscript_shift_up: i16,
+    pub superscript_shift_up_cramped: i16,
+    pub superscript_bottom_min: i16,
+    pub superscript_baseline_drop_max: i16,
+    pub sub_superscript_gap_min: i16,
+    pub superscript_bottom_max_with_subscript: i16,
+    pub space_after_script: i16,
+    pub upper_limit_gap_min: i16,
+    pub upper_limit_baseline_rise_min: i16,
+    pub lower_limit_gap_min: i16,
+    pub lower_limit_baseline_drop_min: i16,
+    pub stack_top_shift_up: i16,
+    pub stack_top_display_style_shift_up: i16,
+    pub stack_bottom_shift_down: i16,
+    pub stack_bottom_display_style_shift_down: i16,
+    pub stack_gap_min: i16,
+    pub stack_display_style_gap_min: i16,
+    pub stretch_stack_top_shift_up: i16,
+    pub stretch_stack_bottom_shift_down: i16,
+    pub stretch_stack_gap_above_min: i16,
+    pub stretch_stack_gap_below_min: i16,
+    pub fraction_numerator_shift_up: i16,
+    pub fraction_numerator_display_style_shift_up: i16,
+    pub fraction_denominator_shift_down: i16,
+    pub fraction_denominator_display_style_shift_down: i16,
+    pub fraction_numerator_gap_min: i16,
+    pub fraction_num_display_style_gap_min: i16,
+    pub fraction_rule_thickness: i16,
+    pub fraction_denominator_gap_min: i16,
+    pub fraction_denom_display_style_gap_min: i16,
+    pub skewed_fraction_horizontal_gap: i16,
+    pub skewed_fraction_vertical_gap: i16,
+    pub overbar_vertical_gap: i16,
+    pub overbar_rule_thickness: i16,
+    pub overbar_extra_ascender: i16,
+    pub underbar_vertical_gap: i16,
+    pub underbar_rule_thickness: i16,
+    pub underbar_extra_descender: i16,
+    pub radical_vertical_gap: i16,
+    pub radical_display_style_vertical_gap: i16,
+    pub radical_rule_thickness: i16,
+    pub radical_extra_ascender: i16,
+    pub radical_kern_before_degree: i16,
+    pub radical_kern_after_degree: i16,
+    /// The height of the bottom of a radical's degree, as a percentage of the radical's height.
+    pub radical_degree_bottom_raise_percent: i16,
+}
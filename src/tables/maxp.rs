@@ -0,0 +1,51 @@
+// Copyright 2017 The Servo Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use byteorder::{BigEndian, ReadBytesExt};
+use error::FontError;
+use font::FontTable;
+
+pub const TAG: u32 = ((b'm' as u32) << 24) |
+                      ((b'a' as u32) << 16) |
+                      ((b'x' as u32) << 8)  |
+                       (b'p' as u32);
+
+// Version 0.5 is used by CFF fonts; version 1.0 adds TrueType-specific fields that we don't need.
+const VERSION_0_5: u32 = 0x00005000;
+const VERSION_1_0: u32 = 0x00010000;
+
+#[derive(Clone, Debug)]
+pub struct MaxpTable {
+    num_glyphs: u16,
+}
+
+impl MaxpTable {
+    pub fn new(table: FontTable) -> Result<MaxpTable, FontError> {
+        let mut reader = table.bytes;
+
+        // Check the version.
+        let version = try!(reader.read_u32::<BigEndian>().map_err(FontError::eof));
+        if version != VERSION_0_5 && version != VERSION_1_0 {
+            return Err(FontError::UnsupportedMaxpVersion)
+        }
+
+        let num_glyphs = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+
+        Ok(MaxpTable {
+            num_glyphs: num_glyphs,
+        })
+    }
+
+    /// The number of glyphs in the font. Valid glyph IDs are less than this.
+    #[inline]
+    pub fn num_glyphs(&self) -> u16 {
+        self.num_glyphs
+    }
+}
@@ -0,0 +1,120 @@
+// Copyright 2017 The Servo Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! The metadata table, which holds miscellaneous data about the font keyed by tag.
+//!
+//! See Microsoft's spec: https://www.microsoft.com/typography/otspec/meta.htm
+
+use byteorder::{BigEndian, ReadBytesExt};
+use error::{ErrorContext, FontError};
+use font::FontTable;
+use std::mem;
+use std::str;
+use util::{EofContext, Jump};
+
+pub const TAG: u32 = ((b'm' as u32) << 24) |
+                      ((b'e' as u32) << 16) |
+                      ((b't' as u32) << 8)  |
+                       (b'a' as u32);
+
+/// The tag of the languages and scripts that the font was primarily designed for.
+pub const DESIGN_LANGUAGES_TAG: u32 = ((b'd' as u32) << 24) |
+                                       ((b'l' as u32) << 16) |
+                                       ((b'n' as u32) << 8)  |
+                                        (b'g' as u32);
+
+/// The tag of the languages and scripts that the font can display.
+pub const SUPPORTED_LANGUAGES_TAG: u32 = ((b's' as u32) << 24) |
+                                          ((b'l' as u32) << 16) |
+                                          ((b'n' as u32) << 8)  |
+                                           (b'g' as u32);
+
+#[derive(Clone, Copy, Debug)]
+pub struct MetaTable<'a> {
+    table: FontTable<'a>,
+    data_maps_count: u32,
+    data_maps: &'a [u8],
+}
+
+impl<'a> MetaTable<'a> {
+    pub fn new(table: FontTable<'a>) -> Result<MetaTable<'a>, FontError> {
+        let mut reader = table.bytes;
+
+        // Check the version.
+        let version = try!(reader.read_u32::<BigEndian>().eof_in(TAG, "version"));
+        if version != 1 {
+            return Err(FontError::UnsupportedMetaVersion)
+        }
+
+        // Skip the flags and reserved field.
+        try!(reader.jump(mem::size_of::<u32>() * 2).eof_in(TAG, "flags"));
+        let data_maps_count = try!(reader.read_u32::<BigEndian>().eof_in(TAG, "data maps count"));
+
+        Ok(MetaTable {
+            table: table,
+            data_maps_count: data_maps_count,
+            data_maps: reader,
+        })
+    }
+
+    /// Returns the metadata with the given tag, if the font has any.
+    pub fn get(&self, tag: u32) -> Option<&'a [u8]> {
+        self.find(tag).unwrap_or(None)
+    }
+
+    /// Returns the ScriptLangTags, such as `Latn` or `zh-Hant`, of the languages and scripts that
+    /// the font was primarily designed for.
+    pub fn design_languages(&self) -> Vec<String> {
+        self.language_tags(DESIGN_LANGUAGES_TAG)
+    }
+
+    /// Returns the ScriptLangTags of the languages and scripts that the font can display, which
+    /// include the ones it was designed for.
+    pub fn supported_languages(&self) -> Vec<String> {
+        self.language_tags(SUPPORTED_LANGUAGES_TAG)
+    }
+
+    fn find(&self, tag: u32) -> Result<Option<&'a [u8]>, FontError> {
+        // The data maps aren't necessarily sorted, so search them all.
+        let mut reader = self.data_maps;
+        for _ in 0..self.data_maps_count {
+            let data_map_tag = try!(reader.read_u32::<BigEndian>().eof_in(TAG, "data map tag"));
+            let data_offset = try!(reader.read_u32::<BigEndian>().eof_in(TAG, "data offset"));
+            let data_length = try!(reader.read_u32::<BigEndian>().eof_in(TAG, "data length"));
+            if data_map_tag != tag {
+                continue
+            }
+
+            let mut data = self.table.bytes;
+            try!(data.jump(data_offset as usize).eof_in(TAG, "data offset"));
+            if data.len() < data_length as usize {
+                return Err(FontError::UnexpectedEofIn(ErrorContext {
+                    table: TAG,
+                    field: "data",
+                }))
+            }
+            return Ok(Some(&data[0..data_length as usize]))
+        }
+        Ok(None)
+    }
+
+    // Splits the comma-separated list of ScriptLangTags with the given tag.
+    fn language_tags(&self, tag: u32) -> Vec<String> {
+        let data = match self.get(tag).and_then(|data| str::from_utf8(data).ok()) {
+            None => return vec![],
+            Some(data) => data,
+        };
+        data.split(',')
+            .map(|language_tag| language_tag.trim())
+            .filter(|language_tag| !language_tag.is_empty())
+            .map(|language_tag| language_tag.to_owned())
+            .collect()
+    }
+}
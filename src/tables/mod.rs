@@ -9,6 +9,10 @@
 // except according to those terms.
 
 //! OpenType fonts.
+//!
+//! Tables that are looked up by glyph, such as `hmtx` and `loca`, work out their layout once
+//! when they're created and keep a slice of their data. A lookup then reads the glyph's entry
+//! straight from its offset in the slice, in constant time, rather than walking the table.
 
 // These tables need no parsing and so don't need separate files.
 pub mod cvt {
@@ -18,6 +22,15 @@ pub mod cvt {
                            (b' ' as u32);
 }
 
+// The embedded bitmap location table has the same layout as `CBLC`, which `cblc::CblcTable`
+// parses.
+pub mod eblc {
+    pub const TAG: u32 = ((b'E' as u32) << 24) |
+                          ((b'B' as u32) << 16) |
+                          ((b'L' as u32) << 8)  |
+                           (b'C' as u32);
+}
+
 pub mod fpgm {
     pub const TAG: u32 = ((b'f' as u32) << 24) |
                           ((b'p' as u32) << 16) |
@@ -32,13 +45,40 @@ pub mod prep {
                            (b'p' as u32);
 }
 
+pub mod cbdt;
+pub mod cblc;
 pub mod cff;
+pub mod cff_encoding;
+pub mod cff_strings;
 pub mod cmap;
+pub mod colr;
+pub mod cpal;
+pub mod dsig;
+pub mod ebdt;
+pub mod fvar;
+pub mod gasp;
 pub mod glyf;
+pub mod gpos;
+pub mod gsub;
+pub mod gvar;
 pub mod head;
 pub mod hhea;
 pub mod hmtx;
 pub mod kern;
+pub mod layout;
 pub mod loca;
+pub mod math;
+pub mod maxp;
+pub mod meta;
+pub mod morx;
+pub mod name;
 pub mod os_2;
+pub mod post;
+pub mod post_names;
+pub mod sbix;
+pub mod stat;
+pub mod svg;
+pub mod vhea;
+pub mod vmtx;
+pub mod vorg;
 
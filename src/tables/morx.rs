@@ -0,0 +1,400 @@
+// Copyright 2017 The Servo Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! The extended glyph metamorphosis table, which Apple Advanced Typography fonts use in place of
+//! the glyph substitution table.
+//!
+//! Only noncontextual and ligature subtables are currently supported.
+//!
+//! See Apple's spec: https://developer.apple.com/fonts/TrueType-Reference-Manual/RM06/Chap6morx.html
+
+use error::FontError;
+use font::FontTable;
+use util::{EofContext, Reader};
+
+pub const TAG: u32 = ((b'm' as u32) << 24) |
+                      ((b'o' as u32) << 16) |
+                      ((b'r' as u32) << 8)  |
+                       (b'x' as u32);
+
+const SUBTABLE_HEADER_SIZE: usize = 12;
+const STATE_TABLE_HEADER_SIZE: usize = 16;
+const LIGATURE_ENTRY_SIZE: usize = 6;
+
+const SUBTABLE_TYPE_LIGATURE: u32 = 2;
+const SUBTABLE_TYPE_NONCONTEXTUAL: u32 = 4;
+
+// Bits of a subtable's coverage. The low byte is the subtable type.
+const COVERAGE_VERTICAL: u32 = 0x8000_0000;
+const COVERAGE_DESCENDING: u32 = 0x4000_0000;
+const COVERAGE_ANY_ORIENTATION: u32 = 0x2000_0000;
+const COVERAGE_TYPE_MASK: u32 = 0xff;
+
+// Classes that every state machine has, whatever its class lookup table says.
+const CLASS_END_OF_TEXT: u16 = 0;
+const CLASS_OUT_OF_BOUNDS: u16 = 1;
+const CLASS_DELETED_GLYPH: u16 = 2;
+
+const STATE_START_OF_TEXT: u16 = 0;
+
+// Flags of a ligature subtable entry.
+const LIGATURE_SET_COMPONENT: u16 = 0x8000;
+const LIGATURE_DONT_ADVANCE: u16 = 0x4000;
+const LIGATURE_PERFORM_ACTION: u16 = 0x2000;
+
+// Bits of a ligature action. The rest are a signed offset into the component table.
+const ACTION_LAST: u32 = 0x8000_0000;
+const ACTION_STORE: u32 = 0x4000_0000;
+const ACTION_OFFSET_MASK: u32 = 0x3fff_ffff;
+
+// Stands in for the glyphs that ligatures absorb until every chain has run.
+const DELETED_GLYPH: u16 = 0xffff;
+
+// Ligature subtables remember this many components, as Apple's implementation does.
+const MAX_COMPONENT_STACK_DEPTH: usize = 32;
+
+// Bounds the number of steps a state machine can take without advancing, so that malformed
+// subtables can't loop forever.
+const MAX_STEPS_PER_GLYPH: usize = 16;
+
+#[derive(Clone, Copy, Debug)]
+pub struct MorxTable<'a> {
+    chains: &'a [u8],
+    chain_count: u32,
+}
+
+impl<'a> MorxTable<'a> {
+    pub fn new(table: FontTable<'a>) -> Result<MorxTable<'a>, FontError> {
+        let mut reader = Reader::new(table.bytes);
+
+        // Check the version. Version 3 only adds glyph coverage tables, which we don't need.
+        let version = try!(reader.read_u16_be().eof_in(TAG, "version"));
+        if version != 2 && version != 3 {
+            return Err(FontError::UnsupportedMorxVersion)
+        }
+        try!(reader.jump(2).eof_in(TAG, "unused"));
+        let chain_count = try!(reader.read_u32_be().eof_in(TAG, "chain count"));
+
+        Ok(MorxTable {
+            chains: reader.remaining(),
+            chain_count: chain_count,
+        })
+    }
+
+    /// Runs the glyph sequence through the subtables of every chain that are enabled by default
+    /// or by the given features, and returns the substituted sequence.
+    ///
+    /// Each feature is a pair of an AAT feature type and setting selector, such as `(1, 2)` for
+    /// rare ligatures. Subtables other than noncontextual and ligature substitution are skipped,
+    /// as are those that only apply to vertical text.
+    pub fn substitute(&self, glyphs: &[u16], features: &[(u16, u16)])
+                      -> Result<Vec<u16>, FontError> {
+        let mut glyphs = glyphs.to_vec();
+        let mut reader = Reader::new(self.chains);
+        for _ in 0..self.chain_count {
+            let mut chain = reader;
+            let default_flags = try!(chain.read_u32_be().eof_in(TAG, "chain default flags"));
+            let chain_length = try!(chain.read_u32_be().eof_in(TAG, "chain length"));
+            let feature_count = try!(chain.read_u32_be().eof_in(TAG, "chain feature count"));
+            let subtable_count = try!(chain.read_u32_be().eof_in(TAG, "chain subtable count"));
+            try!(reader.jump(chain_length as usize).eof_in(TAG, "chain"));
+
+            // Turn the subtables that the requested features select on and off.
+            let mut flags = default_flags;
+            for _ in 0..feature_count {
+                let feature_type = try!(chain.read_u16_be().eof_in(TAG, "feature type"));
+                let setting = try!(chain.read_u16_be().eof_in(TAG, "feature setting"));
+                let enable_flags = try!(chain.read_u32_be().eof_in(TAG, "feature enable flags"));
+                let disable_flags = try!(chain.read_u32_be().eof_in(TAG, "feature disable flags"));
+                if features.contains(&(feature_type, setting)) {
+                    flags = (flags & disable_flags) | enable_flags
+                }
+            }
+
+            for _ in 0..subtable_count {
+                let mut header = chain;
+                let length = try!(header.read_u32_be().eof_in(TAG, "subtable length"));
+                let coverage = try!(header.read_u32_be().eof_in(TAG, "subtable coverage"));
+                let feature_flags = try!(header.read_u32_be().eof_in(TAG, "subtable flags"));
+                try!(chain.jump(length as usize).eof_in(TAG, "subtable"));
+
+                let vertical_only = coverage & COVERAGE_VERTICAL != 0 &&
+                    coverage & COVERAGE_ANY_ORIENTATION == 0;
+                if feature_flags & flags == 0 || vertical_only {
+                    continue
+                }
+
+                // Offsets within the subtable are from the end of its header.
+                let body_length = (length as usize).saturating_sub(SUBTABLE_HEADER_SIZE);
+                let subtable = try!(header.subslice(body_length).eof_in(TAG, "subtable"));
+
+                let descending = coverage & COVERAGE_DESCENDING != 0;
+                if descending {
+                    glyphs.reverse()
+                }
+                match coverage & COVERAGE_TYPE_MASK {
+                    SUBTABLE_TYPE_LIGATURE => try!(ligature_substitution(subtable, &mut glyphs)),
+                    SUBTABLE_TYPE_NONCONTEXTUAL => {
+                        try!(noncontextual_substitution(subtable, &mut glyphs))
+                    }
+                    _ => {}
+                }
+                if descending {
+                    glyphs.reverse()
+                }
+            }
+        }
+
+        glyphs.retain(|&glyph_id| glyph_id != DELETED_GLYPH);
+        Ok(glyphs)
+    }
+}
+
+// Replaces each glyph that the subtable's lookup table maps.
+fn noncontextual_substitution(subtable: &[u8], glyphs: &mut [u16]) -> Result<(), FontError> {
+    for glyph_id in glyphs.iter_mut().filter(|glyph_id| **glyph_id != DELETED_GLYPH) {
+        if let Some(replacement) = try!(lookup(subtable, *glyph_id)) {
+            *glyph_id = replacement
+        }
+    }
+    Ok(())
+}
+
+// Runs a ligature subtable's state machine over the glyphs. Components are remembered on a stack
+// as the machine says, and its actions pop them to find the ligature that replaces them.
+fn ligature_substitution(subtable: &[u8], glyphs: &mut [u16]) -> Result<(), FontError> {
+    let state_table = try!(StateTable::new(subtable));
+    let mut reader = try!(Reader::at(subtable, STATE_TABLE_HEADER_SIZE)
+                              .eof_in(TAG, "ligature subtable"));
+    let action_offset = try!(reader.read_u32_be().eof_in(TAG, "ligature action offset"));
+    let component_offset = try!(reader.read_u32_be().eof_in(TAG, "component offset"));
+    let ligature_offset = try!(reader.read_u32_be().eof_in(TAG, "ligature offset"));
+    let actions = try!(Reader::at(subtable, action_offset as usize)
+                           .eof_in(TAG, "ligature actions"));
+    let components = try!(Reader::at(subtable, component_offset as usize)
+                              .eof_in(TAG, "components"));
+    let ligatures = try!(Reader::at(subtable, ligature_offset as usize)
+                             .eof_in(TAG, "ligatures"));
+
+    let mut stack: Vec<usize> = vec![];
+    let mut state = STATE_START_OF_TEXT;
+    let mut index = 0;
+    let mut steps_left = (glyphs.len() + 1) * MAX_STEPS_PER_GLYPH;
+    while steps_left > 0 {
+        let class = if index == glyphs.len() {
+            CLASS_END_OF_TEXT
+        } else {
+            try!(state_table.class(glyphs[index]))
+        };
+        let mut entry = try!(state_table.entry(state, class, LIGATURE_ENTRY_SIZE));
+        let new_state = try!(entry.read_u16_be().eof_in(TAG, "entry new state"));
+        let flags = try!(entry.read_u16_be().eof_in(TAG, "entry flags"));
+        let action_index = try!(entry.read_u16_be().eof_in(TAG, "entry action index"));
+
+        // A glyph that the machine doesn't advance past may be marked again.
+        if flags & LIGATURE_SET_COMPONENT != 0 && index < glyphs.len() &&
+                stack.last() != Some(&index) {
+            if stack.len() == MAX_COMPONENT_STACK_DEPTH {
+                stack.remove(0);
+            }
+            stack.push(index)
+        }
+
+        if flags & LIGATURE_PERFORM_ACTION != 0 {
+            let mut actions = try!(Reader::at(actions.remaining(), action_index as usize * 4)
+                                       .eof_in(TAG, "ligature action"));
+            let mut ligature_index = 0u16;
+            let mut cursor = stack.len();
+            loop {
+                if cursor == 0 {
+                    // The actions want more components than were marked.
+                    stack.clear();
+                    break
+                }
+                cursor -= 1;
+                let position = stack[cursor];
+
+                let action = try!(actions.read_u32_be().eof_in(TAG, "ligature action"));
+                let offset = (((action & ACTION_OFFSET_MASK) << 2) as i32) >> 2;
+                let component_index = glyphs[position] as i32 + offset;
+                if component_index < 0 {
+                    return Err(FontError::Failed)
+                }
+                let mut component = try!(Reader::at(components.remaining(),
+                                                    component_index as usize * 2)
+                                             .eof_in(TAG, "component"));
+                let component = try!(component.read_u16_be().eof_in(TAG, "component"));
+                ligature_index = ligature_index.wrapping_add(component);
+
+                // The ligature replaces this component, and absorbs the ones after it.
+                if action & (ACTION_STORE | ACTION_LAST) != 0 {
+                    let mut ligature = try!(Reader::at(ligatures.remaining(),
+                                                       ligature_index as usize * 2)
+                                                .eof_in(TAG, "ligature"));
+                    glyphs[position] = try!(ligature.read_u16_be().eof_in(TAG, "ligature"));
+                    for &absorbed in &stack[(cursor + 1)..] {
+                        glyphs[absorbed] = DELETED_GLYPH
+                    }
+                    stack.truncate(cursor + 1)
+                }
+                if action & ACTION_LAST != 0 {
+                    break
+                }
+            }
+        }
+
+        state = new_state;
+        if index == glyphs.len() {
+            break
+        }
+        if flags & LIGATURE_DONT_ADVANCE == 0 {
+            index += 1
+        }
+        steps_left -= 1
+    }
+    Ok(())
+}
+
+// The extended state table at the start of a contextual subtable, which drives its state machine.
+#[derive(Clone, Copy)]
+struct StateTable<'a> {
+    class_count: u32,
+    class_table: &'a [u8],
+    state_array: &'a [u8],
+    entry_table: &'a [u8],
+}
+
+impl<'a> StateTable<'a> {
+    fn new(subtable: &'a [u8]) -> Result<StateTable<'a>, FontError> {
+        let mut reader = Reader::new(subtable);
+        let class_count = try!(reader.read_u32_be().eof_in(TAG, "class count"));
+        let class_table_offset = try!(reader.read_u32_be().eof_in(TAG, "class table offset"));
+        let state_array_offset = try!(reader.read_u32_be().eof_in(TAG, "state array offset"));
+        let entry_table_offset = try!(reader.read_u32_be().eof_in(TAG, "entry table offset"));
+
+        let at = |offset: u32, field: &'static str| {
+            Reader::at(subtable, offset as usize)
+                .map(|reader| reader.remaining())
+                .eof_in(TAG, field)
+        };
+        Ok(StateTable {
+            class_count: class_count,
+            class_table: try!(at(class_table_offset, "class table")),
+            state_array: try!(at(state_array_offset, "state array")),
+            entry_table: try!(at(entry_table_offset, "entry table")),
+        })
+    }
+
+    // Returns the class of the glyph. Glyphs that the class table doesn't mention are out of
+    // bounds.
+    fn class(&self, glyph_id: u16) -> Result<u16, FontError> {
+        if glyph_id == DELETED_GLYPH {
+            return Ok(CLASS_DELETED_GLYPH)
+        }
+        let class = try!(lookup(self.class_table, glyph_id)).unwrap_or(CLASS_OUT_OF_BOUNDS);
+        if class as u32 >= self.class_count {
+            Ok(CLASS_OUT_OF_BOUNDS)
+        } else {
+            Ok(class)
+        }
+    }
+
+    // Returns a reader positioned at the entry for a glyph of the given class in the given state.
+    fn entry(&self, state: u16, class: u16, entry_size: usize) -> Result<Reader<'a>, FontError> {
+        let index = state as usize * self.class_count as usize + class as usize;
+        let mut state_array = try!(Reader::at(self.state_array, index * 2)
+                                       .eof_in(TAG, "state array"));
+        let entry_index = try!(state_array.read_u16_be().eof_in(TAG, "state array"));
+        Reader::at(self.entry_table, entry_index as usize * entry_size).eof_in(TAG, "entry")
+    }
+}
+
+// Looks up the glyph in an AAT lookup table of 16-bit values, returning `None` if the table
+// doesn't mention it.
+fn lookup(table: &[u8], glyph_id: u16) -> Result<Option<u16>, FontError> {
+    let mut reader = Reader::new(table);
+    let format = try!(reader.read_u16_be().eof_in(TAG, "lookup format"));
+    match format {
+        0 => {
+            // A value for every glyph, however many there are.
+            Ok(Reader::at(reader.remaining(), glyph_id as usize * 2).and_then(|mut values| {
+                values.read_u16_be()
+            }).ok())
+        }
+        2 | 4 => {
+            // Segments of consecutive glyphs, with one value for all of them in format 2 and the
+            // offset of an array of values from the start of the table in format 4.
+            let (unit_size, unit_count) = try!(read_binary_search_header(&mut reader, 6));
+            for _ in 0..unit_count {
+                let mut segment = reader;
+                try!(reader.jump(unit_size).eof_in(TAG, "lookup segment"));
+                let last_glyph = try!(segment.read_u16_be().eof_in(TAG, "segment last glyph"));
+                let first_glyph = try!(segment.read_u16_be().eof_in(TAG, "segment first glyph"));
+                let value = try!(segment.read_u16_be().eof_in(TAG, "segment value"));
+                if glyph_id < first_glyph || glyph_id > last_glyph {
+                    continue
+                }
+                if format == 2 {
+                    return Ok(Some(value))
+                }
+                let offset = value as usize + (glyph_id - first_glyph) as usize * 2;
+                let mut values = try!(Reader::at(table, offset).eof_in(TAG, "segment values"));
+                return values.read_u16_be().eof_in(TAG, "segment values").map(Some)
+            }
+            Ok(None)
+        }
+        6 => {
+            // Single glyphs and their values.
+            let (unit_size, unit_count) = try!(read_binary_search_header(&mut reader, 4));
+            for _ in 0..unit_count {
+                let mut entry = reader;
+                try!(reader.jump(unit_size).eof_in(TAG, "lookup entry"));
+                if try!(entry.read_u16_be().eof_in(TAG, "lookup entry glyph")) == glyph_id {
+                    return entry.read_u16_be().eof_in(TAG, "lookup entry value").map(Some)
+                }
+            }
+            Ok(None)
+        }
+        8 | 10 => {
+            // A value for each of a range of glyphs. Format 10 gives the size of the values.
+            let value_size = if format == 10 {
+                try!(reader.read_u16_be().eof_in(TAG, "lookup value size"))
+            } else {
+                2
+            };
+            if value_size < 1 || value_size > 4 {
+                return Err(FontError::Failed)
+            }
+            let first_glyph = try!(reader.read_u16_be().eof_in(TAG, "lookup first glyph"));
+            let glyph_count = try!(reader.read_u16_be().eof_in(TAG, "lookup glyph count"));
+            if glyph_id < first_glyph || glyph_id - first_glyph >= glyph_count {
+                return Ok(None)
+            }
+            let offset = (glyph_id - first_glyph) as usize * value_size as usize;
+            try!(reader.jump(offset).eof_in(TAG, "lookup values"));
+            let value = try!(reader.read_offset(value_size as u8).eof_in(TAG, "lookup value"));
+            Ok(Some(value as u16))
+        }
+        _ => Err(FontError::Failed),
+    }
+}
+
+// Reads the header that precedes the units of a segmented lookup table, returning their size and
+// number. Units must be at least the given size.
+fn read_binary_search_header(reader: &mut Reader, min_unit_size: u16)
+                             -> Result<(usize, u16), FontError> {
+    let unit_size = try!(reader.read_u16_be().eof_in(TAG, "lookup unit size"));
+    let unit_count = try!(reader.read_u16_be().eof_in(TAG, "lookup unit count"));
+    try!(reader.jump(6).eof_in(TAG, "lookup search parameters"));
+    if unit_size < min_unit_size {
+        return Err(FontError::Failed)
+    }
+    Ok((unit_size as usize, unit_count))
+}
@@ -0,0 +1,227 @@
+// Copyright 2017 The Servo Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use byteorder::{BigEndian, ReadBytesExt};
+use error::FontError;
+use font::FontTable;
+use std::char;
+use util::Jump;
+
+pub const TAG: u32 = ((b'n' as u32) << 24) |
+                      ((b'a' as u32) << 16) |
+                      ((b'm' as u32) << 8)  |
+                       (b'e' as u32);
+
+const PLATFORM_ID_UNICODE: u16 = 0;
+const PLATFORM_ID_MACINTOSH: u16 = 1;
+const PLATFORM_ID_MICROSOFT: u16 = 3;
+
+const MACINTOSH_ENCODING_ID_ROMAN: u16 = 0;
+const MACINTOSH_LANGUAGE_ID_ENGLISH: u16 = 0;
+
+const MICROSOFT_ENCODING_ID_SYMBOL: u16 = 0;
+const MICROSOFT_ENCODING_ID_UNICODE_BMP: u16 = 1;
+const MICROSOFT_ENCODING_ID_UNICODE_FULL: u16 = 10;
+const MICROSOFT_LANGUAGE_ID_ENGLISH_UNITED_STATES: u16 = 0x0409;
+
+// The size of a name record: six `u16` values.
+const NAME_RECORD_SIZE: usize = 12;
+
+/// The Unicode values of Mac Roman characters 0x80 to 0xff. The lower half is ASCII.
+static MAC_ROMAN: [u16; 128] = [
+    0x00c4, 0x00c5, 0x00c7, 0x00c9, 0x00d1, 0x00d6, 0x00dc, 0x00e1,
+    0x00e0, 0x00e2, 0x00e4, 0x00e3, 0x00e5, 0x00e7, 0x00e9, 0x00e8,
+    0x00ea, 0x00eb, 0x00ed, 0x00ec, 0x00ee, 0x00ef, 0x00f1, 0x00f3,
+    0x00f2, 0x00f4, 0x00f6, 0x00f5, 0x00fa, 0x00f9, 0x00fb, 0x00fc,
+    0x2020, 0x00b0, 0x00a2, 0x00a3, 0x00a7, 0x2022, 0x00b6, 0x00df,
+    0x00ae, 0x00a9, 0x2122, 0x00b4, 0x00a8, 0x2260, 0x00c6, 0x00d8,
+    0x221e, 0x00b1, 0x2264, 0x2265, 0x00a5, 0x00b5, 0x2202, 0x2211,
+    0x220f, 0x03c0, 0x222b, 0x00aa, 0x00ba, 0x03a9, 0x00e6, 0x00f8,
+    0x00bf, 0x00a1, 0x00ac, 0x221a, 0x0192, 0x2248, 0x2206, 0x00ab,
+    0x00bb, 0x2026, 0x00a0, 0x00c0, 0x00c3, 0x00d5, 0x0152, 0x0153,
+    0x2013, 0x2014, 0x201c, 0x201d, 0x2018, 0x2019, 0x00f7, 0x25ca,
+    0x00ff, 0x0178, 0x2044, 0x20ac, 0x2039, 0x203a, 0xfb01, 0xfb02,
+    0x2021, 0x00b7, 0x201a, 0x201e, 0x2030, 0x00c2, 0x00ca, 0x00c1,
+    0x00cb, 0x00c8, 0x00cd, 0x00ce, 0x00cf, 0x00cc, 0x00d3, 0x00d4,
+    0xf8ff, 0x00d2, 0x00da, 0x00db, 0x00d9, 0x0131, 0x02c6, 0x02dc,
+    0x00af, 0x02d8, 0x02d9, 0x02da, 0x00b8, 0x02dd, 0x02db, 0x02c7,
+];
+
+/// The IDs of the standard names in the `name` table.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum NameId {
+    Copyright = 0,
+    FamilyName = 1,
+    SubfamilyName = 2,
+    UniqueId = 3,
+    FullName = 4,
+    Version = 5,
+    PostScriptName = 6,
+    Trademark = 7,
+    Manufacturer = 8,
+    Designer = 9,
+    Description = 10,
+    VendorUrl = 11,
+    DesignerUrl = 12,
+    License = 13,
+    LicenseUrl = 14,
+    TypographicFamilyName = 16,
+    TypographicSubfamilyName = 17,
+    SampleText = 19,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct NameTable<'a> {
+    records: &'a [u8],
+    count: u16,
+    strings: &'a [u8],
+}
+
+impl<'a> NameTable<'a> {
+    pub fn new(table: FontTable<'a>) -> Result<NameTable<'a>, FontError> {
+        let mut reader = table.bytes;
+
+        // Check the format. Format 1 adds language tags after the records, which we ignore.
+        let format = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+        if format > 1 {
+            return Err(FontError::UnsupportedNameFormat)
+        }
+
+        let count = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+        let string_offset = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+        let records = reader;
+
+        let mut strings = table.bytes;
+        try!(strings.jump(string_offset as usize).map_err(FontError::eof));
+
+        Ok(NameTable {
+            records: records,
+            count: count,
+            strings: strings,
+        })
+    }
+
+    /// Returns the name with the given ID, decoded from the best available record.
+    ///
+    /// American English Microsoft records are preferred, then English Macintosh records, then
+    /// Unicode platform records, then records in any other language. Records in encodings we
+    /// can't decode are skipped.
+    pub fn get(&self, name_id: NameId) -> Option<String> {
+        self.find(name_id as u16).unwrap_or(None)
+    }
+
+    /// Like `get()`, but takes a raw name ID, which may be a font-specific one.
+    #[inline]
+    pub fn get_by_id(&self, name_id: u16) -> Option<String> {
+        self.find(name_id).unwrap_or(None)
+    }
+
+    #[inline]
+    pub fn family_name(&self) -> Option<String> {
+        self.get(NameId::FamilyName)
+    }
+
+    #[inline]
+    pub fn subfamily_name(&self) -> Option<String> {
+        self.get(NameId::SubfamilyName)
+    }
+
+    #[inline]
+    pub fn full_name(&self) -> Option<String> {
+        self.get(NameId::FullName)
+    }
+
+    #[inline]
+    pub fn postscript_name(&self) -> Option<String> {
+        self.get(NameId::PostScriptName)
+    }
+
+    #[inline]
+    pub fn version(&self) -> Option<String> {
+        self.get(NameId::Version)
+    }
+
+    fn find(&self, name_id: u16) -> Result<Option<String>, FontError> {
+        let mut best: Option<(u8, u16, &'a [u8])> = None;
+
+        let mut reader = self.records;
+        for _ in 0..self.count {
+            let platform_id = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+            let encoding_id = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+            let language_id = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+            let record_name_id = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+            let length = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+            let offset = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+            if record_name_id != name_id {
+                continue
+            }
+
+            let rank = match (platform_id, encoding_id, language_id) {
+                (PLATFORM_ID_MICROSOFT, MICROSOFT_ENCODING_ID_SYMBOL, _) |
+                (PLATFORM_ID_MICROSOFT, MICROSOFT_ENCODING_ID_UNICODE_BMP, _) |
+                (PLATFORM_ID_MICROSOFT, MICROSOFT_ENCODING_ID_UNICODE_FULL, _) => {
+                    if language_id == MICROSOFT_LANGUAGE_ID_ENGLISH_UNITED_STATES {
+                        0
+                    } else {
+                        3
+                    }
+                }
+                (PLATFORM_ID_MACINTOSH, MACINTOSH_ENCODING_ID_ROMAN, _) => {
+                    if language_id == MACINTOSH_LANGUAGE_ID_ENGLISH {
+                        1
+                    } else {
+                        3
+                    }
+                }
+                (PLATFORM_ID_UNICODE, _, _) => 2,
+                _ => continue,
+            };
+            if let Some((best_rank, _, _)) = best {
+                if best_rank <= rank {
+                    continue
+                }
+            }
+
+            let start = offset as usize;
+            let end = start + length as usize;
+            if end > self.strings.len() {
+                continue
+            }
+            best = Some((rank, platform_id, &self.strings[start..end]))
+        }
+
+        match best {
+            None => Ok(None),
+            Some((_, PLATFORM_ID_MACINTOSH, bytes)) => Ok(Some(decode_mac_roman(bytes))),
+            Some((_, _, bytes)) => Ok(decode_utf16_be(bytes)),
+        }
+    }
+}
+
+fn decode_mac_roman(bytes: &[u8]) -> String {
+    bytes.iter().map(|&byte| {
+        if byte < 0x80 {
+            byte as char
+        } else {
+            char::from_u32(MAC_ROMAN[byte as usize - 0x80] as u32).unwrap()
+        }
+    }).collect()
+}
+
+// Returns `None` if the string has an odd length or is otherwise malformed.
+fn decode_utf16_be(bytes: &[u8]) -> Option<String> {
+    if bytes.len() % 2 != 0 {
+        return None
+    }
+    let code_units: Vec<u16> = bytes.chunks(2).map(|pair| {
+        ((pair[0] as u16) << 8) | (pair[1] as u16)
+    }).collect();
+    String::from_utf16(&code_units).ok()
+}
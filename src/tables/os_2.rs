@@ -8,53 +8,458 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
-use byteorder::{BigEndian, ReadBytesExt};
 use error::FontError;
 use font::FontTable;
 use std::mem;
-use util::Jump;
+use util::{EofContext, Reader};
 
 pub const TAG: u32 = ((b'O' as u32) << 24) |
                       ((b'S' as u32) << 16) |
                       ((b'/' as u32) << 8)  |
                        (b'2' as u32);
 
+/// The code page range bit for code page 1252, Latin 1.
+pub const CODE_PAGE_LATIN_1: u8 = 0;
+/// The code page range bit for code page 932, Japanese Shift-JIS.
+pub const CODE_PAGE_SHIFT_JIS: u8 = 17;
+
+bitflags! {
+    pub flags FsSelection: u16 {
+        const ITALIC = 1 << 0,
+        const BOLD = 1 << 5,
+        const REGULAR = 1 << 6,
+        const USE_TYPO_METRICS = 1 << 7,
+        const OBLIQUE = 1 << 9,
+    }
+}
+
+bitflags! {
+    pub flags EmbeddingRestrictions: u16 {
+        const RESTRICTED_LICENSE_EMBEDDING = 1 << 1,
+        const PREVIEW_AND_PRINT_EMBEDDING = 1 << 2,
+        const EDITABLE_EMBEDDING = 1 << 3,
+        const NO_SUBSETTING = 1 << 8,
+        const BITMAP_EMBEDDING_ONLY = 1 << 9,
+    }
+}
+
+impl EmbeddingRestrictions {
+    /// Whether the font may be embedded and permanently installed on the remote system.
+    #[inline]
+    pub fn is_installable(&self) -> bool {
+        !self.intersects(RESTRICTED_LICENSE_EMBEDDING | PREVIEW_AND_PRINT_EMBEDDING |
+                         EDITABLE_EMBEDDING)
+    }
+
+    /// Whether the font may be subsetted before embedding.
+    #[inline]
+    pub fn allows_subsetting(&self) -> bool {
+        !self.contains(NO_SUBSETTING)
+    }
+
+    /// Whether documents with the font embedded may be edited, not just viewed and printed.
+    #[inline]
+    pub fn allows_editing(&self) -> bool {
+        self.is_installable() || self.contains(EDITABLE_EMBEDDING)
+    }
+}
+
+/// Unicode blocks that a font can claim to support via the OS/2 Unicode range bits.
+///
+/// Each variant's value is its bit number.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum UnicodeBlock {
+    BasicLatin = 0,
+    Latin1Supplement = 1,
+    LatinExtendedA = 2,
+    LatinExtendedB = 3,
+    IpaExtensions = 4,
+    SpacingModifierLetters = 5,
+    CombiningDiacriticalMarks = 6,
+    GreekAndCoptic = 7,
+    Coptic = 8,
+    Cyrillic = 9,
+    Armenian = 10,
+    Hebrew = 11,
+    Vai = 12,
+    Arabic = 13,
+    NKo = 14,
+    Devanagari = 15,
+    Bengali = 16,
+    Gurmukhi = 17,
+    Gujarati = 18,
+    Oriya = 19,
+    Tamil = 20,
+    Telugu = 21,
+    Kannada = 22,
+    Malayalam = 23,
+    Thai = 24,
+    Lao = 25,
+    Georgian = 26,
+    Balinese = 27,
+    HangulJamo = 28,
+    LatinExtendedAdditional = 29,
+    GreekExtended = 30,
+    GeneralPunctuation = 31,
+    SuperscriptsAndSubscripts = 32,
+    CurrencySymbols = 33,
+    CombiningDiacriticalMarksForSymbols = 34,
+    LetterlikeSymbols = 35,
+    NumberForms = 36,
+    Arrows = 37,
+    MathematicalOperators = 38,
+    MiscellaneousTechnical = 39,
+    ControlPictures = 40,
+    OpticalCharacterRecognition = 41,
+    EnclosedAlphanumerics = 42,
+    BoxDrawing = 43,
+    BlockElements = 44,
+    GeometricShapes = 45,
+    MiscellaneousSymbols = 46,
+    Dingbats = 47,
+    CjkSymbolsAndPunctuation = 48,
+    Hiragana = 49,
+    Katakana = 50,
+    Bopomofo = 51,
+    HangulCompatibilityJamo = 52,
+    PhagsPa = 53,
+    EnclosedCjkLettersAndMonths = 54,
+    CjkCompatibility = 55,
+    HangulSyllables = 56,
+    NonPlane0 = 57,
+    Phoenician = 58,
+    CjkUnifiedIdeographs = 59,
+    PrivateUseArea = 60,
+    CjkStrokes = 61,
+    AlphabeticPresentationForms = 62,
+    ArabicPresentationFormsA = 63,
+    CombiningHalfMarks = 64,
+    VerticalForms = 65,
+    SmallFormVariants = 66,
+    ArabicPresentationFormsB = 67,
+    HalfwidthAndFullwidthForms = 68,
+    Specials = 69,
+    Tibetan = 70,
+    Syriac = 71,
+    Thaana = 72,
+    Sinhala = 73,
+    Myanmar = 74,
+    Ethiopic = 75,
+    Cherokee = 76,
+    UnifiedCanadianAboriginalSyllabics = 77,
+    Ogham = 78,
+    Runic = 79,
+    Khmer = 80,
+    Mongolian = 81,
+    BraillePatterns = 82,
+    Yi = 83,
+}
+
+/// Metrics for synthesizing subscripts and superscripts and for drawing strikeout lines, in font
+/// units.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct SubSuperStrikeMetrics {
+    pub subscript_x_size: i16,
+    pub subscript_y_size: i16,
+    pub subscript_x_offset: i16,
+    pub subscript_y_offset: i16,
+    pub superscript_x_size: i16,
+    pub superscript_y_size: i16,
+    pub superscript_x_offset: i16,
+    pub superscript_y_offset: i16,
+    pub strikeout_size: i16,
+    pub strikeout_position: i16,
+}
+
+/// The PANOSE classification of the font's visual characteristics.
+///
+/// The meaning of each digit after the first depends on the family type. See the OpenType
+/// specification for the values. Zero means "any" and one means "no fit".
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Panose {
+    pub family_type: u8,
+    pub serif_style: u8,
+    pub weight: u8,
+    pub proportion: u8,
+    pub contrast: u8,
+    pub stroke_variation: u8,
+    pub arm_style: u8,
+    pub letterform: u8,
+    pub midline: u8,
+    pub x_height: u8,
+}
+
+/// Fields that only later versions of the table have are `None` in earlier versions. Versions 0
+/// through 5 are known; later versions are assumed to extend version 5.
 #[derive(Clone, Debug)]
 pub struct Os2Table {
+    /// The version of the table, which determines which of the optional fields are present.
+    pub version: u16,
+    /// The average advance width of the font's non-zero-width glyphs, in font units.
+    ///
+    /// This is only an estimate, good for guessing how wide text will be without shaping it.
+    pub avg_char_width: i16,
+    pub weight_class: u16,
+    pub width_class: u16,
+    pub embedding: EmbeddingRestrictions,
+    pub sub_super_strike: SubSuperStrikeMetrics,
+    pub panose: Panose,
+    pub unicode_range: [u32; 4],
+    /// The four-character identifier of the font's vendor, such as `b"URW "`. This is all zeros
+    /// in version 0 tables.
+    pub vendor_id: [u8; 4],
+    pub fs_selection: FsSelection,
     pub typo_ascender: i16,
     pub typo_descender: i16,
     pub typo_line_gap: i16,
+    /// The Windows ascent, in font units: the height above the baseline that Windows clips glyphs
+    /// to, and uses with `win_descent` as the line height.
+    ///
+    /// Unlike the typographic metrics, which describe the design's line spacing, the Windows
+    /// metrics are meant to cover every glyph in the font, so they're usually larger and include
+    /// no separate line gap. Browsers use them for line boxes unless `use_typo_metrics()` is set.
+    pub win_ascent: u16,
+    /// The Windows descent, in font units. As opposed to `typo_descender`, this is positive for
+    /// glyphs that extend below the baseline.
+    pub win_descent: u16,
+    /// The code page range bitmaps, which appear in version 1 and later.
+    pub code_page_range: Option<[u32; 2]>,
+    /// The height of lowercase letters above the baseline, in font units. Version 2 and later.
+    pub x_height: Option<i16>,
+    /// The height of uppercase letters above the baseline, in font units. Version 2 and later.
+    pub cap_height: Option<i16>,
+    /// The character to show for characters the font doesn't have, or 0 for glyph 0. Version 2
+    /// and later.
+    pub default_char: Option<u16>,
+    /// The character that breaks words, usually the space. Version 2 and later.
+    pub break_char: Option<u16>,
+    /// The most characters that any feature looks at once, such as 2 for pair kerning. Version 2
+    /// and later.
+    pub max_context: Option<u16>,
+    /// The range of point sizes that the font is designed for, in twentieths of a point, with an
+    /// exclusive upper end. Version 5 and later.
+    pub optical_point_size_range: Option<(u16, u16)>,
 }
 
 impl Os2Table {
     pub fn new(table: FontTable) -> Result<Os2Table, FontError> {
-        let mut reader = table.bytes;
+        let mut reader = Reader::new(table.bytes);
 
         // We should be compatible with all versions. If this is greater than version 5, follow
         // Postel's law and hope for the best.
-        let version = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+        let version = try!(reader.read_u16_be().eof_in(TAG, "version"));
 
-        // Skip to the line gap.
-        try!(reader.jump(mem::size_of::<u16>() * 15).map_err(FontError::eof));
-        try!(reader.jump(10).map_err(FontError::eof));
+        // Read the average character width and the weight and width classes.
+        let avg_char_width = try!(reader.read_i16_be().eof_in(TAG, "average character width"));
+        let weight_class = try!(reader.read_u16_be().eof_in(TAG, "weight class"));
+        let width_class = try!(reader.read_u16_be().eof_in(TAG, "width class"));
+
+        // Read the embedding permissions.
+        let fs_type = try!(reader.read_u16_be().eof_in(TAG, "embedding permissions"));
+        let embedding = EmbeddingRestrictions::from_bits_truncate(fs_type);
+
+        // Read the subscript, superscript, and strikeout metrics.
+        let mut metrics = [0; 10];
+        for metric in &mut metrics {
+            *metric = try!(reader.read_i16_be()
+                                 .eof_in(TAG, "subscript, superscript, and strikeout metrics"));
+        }
+        let sub_super_strike = SubSuperStrikeMetrics {
+            subscript_x_size: metrics[0],
+            subscript_y_size: metrics[1],
+            subscript_x_offset: metrics[2],
+            subscript_y_offset: metrics[3],
+            superscript_x_size: metrics[4],
+            superscript_y_size: metrics[5],
+            superscript_x_offset: metrics[6],
+            superscript_y_offset: metrics[7],
+            strikeout_size: metrics[8],
+            strikeout_position: metrics[9],
+        };
+
+        // Read the PANOSE classification.
+        try!(reader.jump(mem::size_of::<i16>()).eof_in(TAG, "family class"));
+        let mut digits = [0; 10];
+        for digit in &mut digits {
+            *digit = try!(reader.read_u8().eof_in(TAG, "PANOSE classification"));
+        }
+        let panose = Panose {
+            family_type: digits[0],
+            serif_style: digits[1],
+            weight: digits[2],
+            proportion: digits[3],
+            contrast: digits[4],
+            stroke_variation: digits[5],
+            arm_style: digits[6],
+            letterform: digits[7],
+            midline: digits[8],
+            x_height: digits[9],
+        };
+
+        // Read the Unicode ranges. Version 0 tables are shorter here, and we don't trust them to
+        // contain the ranges, so they claim no blocks.
+        let (mut unicode_range, mut vendor_id) = ([0; 4], [0; 4]);
         if version == 0 {
-            try!(reader.jump(mem::size_of::<u32>() * 2).map_err(FontError::eof));
+            try!(reader.jump(mem::size_of::<u32>() * 2).eof_in(TAG, "Unicode ranges"));
         } else {
-            try!(reader.jump(mem::size_of::<u32>() * 5).map_err(FontError::eof));
+            for range in &mut unicode_range {
+                *range = try!(reader.read_u32_be().eof_in(TAG, "Unicode ranges"));
+            }
+            let vendor = try!(reader.subslice(vendor_id.len()).eof_in(TAG, "vendor ID"));
+            vendor_id.copy_from_slice(vendor);
         }
-        try!(reader.jump(mem::size_of::<u16>() * 3).map_err(FontError::eof));
+
+        // Read the style flags.
+        let fs_selection = try!(reader.read_u16_be().eof_in(TAG, "style flags"));
+        let fs_selection = FsSelection::from_bits_truncate(fs_selection);
+
+        // Skip to the line gap.
+        try!(reader.jump(mem::size_of::<u16>() * 2).eof_in(TAG, "first and last characters"));
 
         // Read the line spacing information.
-        let typo_ascender = try!(reader.read_i16::<BigEndian>().map_err(FontError::eof));
-        let typo_descender = try!(reader.read_i16::<BigEndian>().map_err(FontError::eof));
-        let typo_line_gap = try!(reader.read_i16::<BigEndian>().map_err(FontError::eof));
+        let typo_ascender = try!(reader.read_i16_be().eof_in(TAG, "typo ascender"));
+        let typo_descender = try!(reader.read_i16_be().eof_in(TAG, "typo descender"));
+        let typo_line_gap = try!(reader.read_i16_be().eof_in(TAG, "typo line gap"));
+        let win_ascent = try!(reader.read_u16_be().eof_in(TAG, "Windows ascent"));
+        let win_descent = try!(reader.read_u16_be().eof_in(TAG, "Windows descent"));
+
+        // Read the code page ranges, which appear in version 1 and later.
+        let code_page_range = if version >= 1 {
+            let mut code_page_range = [0; 2];
+            for range in &mut code_page_range {
+                *range = try!(reader.read_u32_be().eof_in(TAG, "code page ranges"));
+            }
+            Some(code_page_range)
+        } else {
+            None
+        };
+
+        // Read the x-height, cap height, default and break characters, and maximum context, which
+        // appear in version 2 and later. These are at the same offsets in all later versions.
+        let (mut x_height, mut cap_height) = (None, None);
+        let (mut default_char, mut break_char, mut max_context) = (None, None, None);
+        if version >= 2 {
+            x_height = Some(try!(reader.read_i16_be().eof_in(TAG, "x-height")));
+            cap_height = Some(try!(reader.read_i16_be().eof_in(TAG, "cap height")));
+            default_char = Some(try!(reader.read_u16_be().eof_in(TAG, "default character")));
+            break_char = Some(try!(reader.read_u16_be().eof_in(TAG, "break character")));
+            max_context = Some(try!(reader.read_u16_be().eof_in(TAG, "maximum context")));
+        }
+
+        // Read the optical point size range, which appears in version 5 and later.
+        let optical_point_size_range = if version >= 5 {
+            let lower = try!(reader.read_u16_be().eof_in(TAG, "lower optical point size"));
+            let upper = try!(reader.read_u16_be().eof_in(TAG, "upper optical point size"));
+            Some((lower, upper))
+        } else {
+            None
+        };
 
         Ok(Os2Table {
+            version: version,
+            avg_char_width: avg_char_width,
+            weight_class: weight_class,
+            width_class: width_class,
+            embedding: embedding,
+            sub_super_strike: sub_super_strike,
+            panose: panose,
+            unicode_range: unicode_range,
+            vendor_id: vendor_id,
+            fs_selection: fs_selection,
             typo_ascender: typo_ascender,
             typo_descender: typo_descender,
             typo_line_gap: typo_line_gap,
+            win_ascent: win_ascent,
+            win_descent: win_descent,
+            code_page_range: code_page_range,
+            x_height: x_height,
+            cap_height: cap_height,
+            default_char: default_char,
+            break_char: break_char,
+            max_context: max_context,
+            optical_point_size_range: optical_point_size_range,
         })
     }
-}
+    /// Whether the font claims to support the Unicode range with the given bit number.
+    ///
+    /// See the OpenType specification for the bit assignments. Out-of-range bits are never set.
+    #[inline]
+    pub fn supports_unicode_range(&self, bit: u8) -> bool {
+        if bit >= 128 {
+            return false
+        }
+        (self.unicode_range[bit as usize / 32] >> (bit % 32)) & 1 != 0
+    }
+
+    /// Whether the font claims to support the given Unicode block.
+    #[inline]
+    pub fn covers_block(&self, block: UnicodeBlock) -> bool {
+        self.supports_unicode_range(block as u8)
+    }
 
+    /// Whether the font claims to support the code page with the given bit number.
+    ///
+    /// See the OpenType specification for the bit assignments. Version 0 tables have no code page
+    /// ranges, so they claim no code pages.
+    #[inline]
+    pub fn supports_code_page(&self, bit: u8) -> bool {
+        match self.code_page_range {
+            Some(ref code_page_range) if bit < 64 => {
+                (code_page_range[bit as usize / 32] >> (bit % 32)) & 1 != 0
+            }
+            _ => false,
+        }
+    }
+
+    /// Whether the font claims to support code page 1252, Latin 1.
+    #[inline]
+    pub fn supports_latin1(&self) -> bool {
+        self.supports_code_page(CODE_PAGE_LATIN_1)
+    }
 
+    /// Whether the font claims to support code page 932, Japanese Shift-JIS.
+    #[inline]
+    pub fn supports_shift_jis(&self) -> bool {
+        self.supports_code_page(CODE_PAGE_SHIFT_JIS)
+    }
+
+    /// Whether the font is italic.
+    #[inline]
+    pub fn is_italic(&self) -> bool {
+        self.fs_selection.contains(ITALIC)
+    }
+
+    /// Whether the font is bold.
+    #[inline]
+    pub fn is_bold(&self) -> bool {
+        self.fs_selection.contains(BOLD)
+    }
+
+    /// Whether the font is the regular (neither bold nor italic) face of its family.
+    #[inline]
+    pub fn is_regular(&self) -> bool {
+        self.fs_selection.contains(REGULAR)
+    }
+
+    /// Whether the font is oblique, as opposed to a true italic.
+    #[inline]
+    pub fn is_oblique(&self) -> bool {
+        self.fs_selection.contains(OBLIQUE)
+    }
+
+    /// Whether the font's PANOSE classification says that it's monospaced.
+    ///
+    /// Fonts that leave the classification unset report that they aren't, so this is only a
+    /// hint; check the advance widths to be sure.
+    #[inline]
+    pub fn is_monospaced(&self) -> bool {
+        self.panose.proportion == 9
+    }
+
+    /// Whether the typographic ascender, descender, and line gap should be used for line spacing
+    /// instead of the Windows metrics.
+    #[inline]
+    pub fn use_typo_metrics(&self) -> bool {
+        self.fs_selection.contains(USE_TYPO_METRICS)
+    }
+}
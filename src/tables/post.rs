@@ -0,0 +1,160 @@
+// Copyright 2017 The Servo Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use byteorder::{BigEndian, ReadBytesExt};
+use error::FontError;
+use font::FontTable;
+use std::mem;
+use std::str;
+use tables::post_names::STANDARD_MAC_GLYPH_NAMES;
+use util::Jump;
+
+pub const TAG: u32 = ((b'p' as u32) << 24) |
+                      ((b'o' as u32) << 16) |
+                      ((b's' as u32) << 8)  |
+                       (b't' as u32);
+
+const VERSION_1_0: u32 = 0x00010000;
+const VERSION_2_0: u32 = 0x00020000;
+const VERSION_2_5: u32 = 0x00025000;
+const VERSION_3_0: u32 = 0x00030000;
+
+// The size of the header common to all versions.
+const HEADER_SIZE: usize = 32;
+
+#[derive(Clone, Copy, Debug)]
+pub struct PostTable<'a> {
+    version: u32,
+    table: FontTable<'a>,
+}
+
+impl<'a> PostTable<'a> {
+    pub fn new(table: FontTable<'a>) -> Result<PostTable<'a>, FontError> {
+        let mut reader = table.bytes;
+
+        // Check the version.
+        let version = try!(reader.read_u32::<BigEndian>().map_err(FontError::eof));
+        match version {
+            VERSION_1_0 | VERSION_2_0 | VERSION_2_5 | VERSION_3_0 => {}
+            _ => return Err(FontError::UnsupportedPostVersion),
+        }
+
+        if table.bytes.len() < HEADER_SIZE {
+            return Err(FontError::UnexpectedEof)
+        }
+
+        Ok(PostTable {
+            version: version,
+            table: table,
+        })
+    }
+
+    /// Returns the PostScript name of the given glyph, if it has one.
+    ///
+    /// Version 3.0 tables (and the deprecated version 2.5) contain no names, so this always
+    /// returns `None` for them. Malformed names are also treated as missing.
+    pub fn glyph_name(&self, glyph_id: u16) -> Option<&'a str> {
+        match self.version {
+            VERSION_1_0 => STANDARD_MAC_GLYPH_NAMES.get(glyph_id as usize).map(|name| *name),
+            VERSION_2_0 => self.glyph_name_version_2(glyph_id).unwrap_or(None),
+            _ => None,
+        }
+    }
+
+    /// Returns the first glyph with the given PostScript name, if there is one.
+    pub fn glyph_for_name(&self, name: &str) -> Option<u16> {
+        match self.version {
+            VERSION_1_0 => {
+                STANDARD_MAC_GLYPH_NAMES.iter()
+                                        .position(|standard_name| *standard_name == name)
+                                        .map(|glyph_id| glyph_id as u16)
+            }
+            VERSION_2_0 => self.glyph_for_name_version_2(name).unwrap_or(None),
+            _ => None,
+        }
+    }
+
+    // Returns the glyph name index array, the number of glyphs, and the names in Pascal string
+    // format, for version 2.0 tables.
+    fn version_2_arrays(&self) -> Result<(&'a [u8], u16, &'a [u8]), FontError> {
+        let mut reader = self.table.bytes;
+        try!(reader.jump(HEADER_SIZE).map_err(FontError::eof));
+        let num_glyphs = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+        let glyph_name_indices = reader;
+        try!(reader.jump(mem::size_of::<u16>() * num_glyphs as usize).map_err(FontError::eof));
+        Ok((glyph_name_indices, num_glyphs, reader))
+    }
+
+    fn glyph_name_version_2(&self, glyph_id: u16) -> Result<Option<&'a str>, FontError> {
+        let (mut glyph_name_indices, num_glyphs, mut names) = try!(self.version_2_arrays());
+        if glyph_id >= num_glyphs {
+            return Ok(None)
+        }
+
+        try!(glyph_name_indices.jump(mem::size_of::<u16>() * glyph_id as usize)
+                               .map_err(FontError::eof));
+        let name_index = try!(glyph_name_indices.read_u16::<BigEndian>().map_err(FontError::eof));
+        if (name_index as usize) < STANDARD_MAC_GLYPH_NAMES.len() {
+            return Ok(Some(STANDARD_MAC_GLYPH_NAMES[name_index as usize]))
+        }
+
+        // Skip over the custom names that precede this one.
+        for _ in 0..(name_index as usize - STANDARD_MAC_GLYPH_NAMES.len()) {
+            let length = try!(names.read_u8().map_err(FontError::eof));
+            try!(names.jump(length as usize).map_err(FontError::eof));
+        }
+
+        let length = try!(names.read_u8().map_err(FontError::eof)) as usize;
+        if names.len() < length {
+            return Err(FontError::UnexpectedEof)
+        }
+        Ok(str::from_utf8(&names[0..length]).ok())
+    }
+
+    fn glyph_for_name_version_2(&self, name: &str) -> Result<Option<u16>, FontError> {
+        let (mut glyph_name_indices, num_glyphs, mut names) = try!(self.version_2_arrays());
+
+        // Find the name index that the glyph will refer to: either that of a standard name or
+        // that of a custom one.
+        let standard_index = STANDARD_MAC_GLYPH_NAMES.iter()
+                                                     .position(|standard_name| {
+                                                         *standard_name == name
+                                                     });
+        let mut custom_index = None;
+        let mut index = STANDARD_MAC_GLYPH_NAMES.len();
+        while !names.is_empty() {
+            let length = try!(names.read_u8().map_err(FontError::eof)) as usize;
+            if names.len() < length {
+                break
+            }
+            if &names[0..length] == name.as_bytes() {
+                custom_index = Some(index);
+                break
+            }
+            names = &names[length..];
+            index += 1
+        }
+
+        if standard_index.is_none() && custom_index.is_none() {
+            return Ok(None)
+        }
+
+        for glyph_id in 0..num_glyphs {
+            let name_index = try!(glyph_name_indices.read_u16::<BigEndian>()
+                                                    .map_err(FontError::eof));
+            let name_index = Some(name_index as usize);
+            if name_index == standard_index || name_index == custom_index {
+                return Ok(Some(glyph_id))
+            }
+        }
+
+        Ok(None)
+    }
+}
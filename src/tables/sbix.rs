@@ -0,0 +1,196 @@
+// Copyright 2017 The Servo Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+use byteorder::{BigEndian, ReadBytesExt};
+use error::FontError;
+use font::{BitmapFormat, BitmapStrike, FontTable};
+use std::mem;
+use util::Jump;
+
+pub const TAG: u32 = ((b's' as u32) << 24) |
+                      ((b'b' as u32) << 16) |
+                      ((b'i' as u32) << 8)  |
+                       (b'x' as u32);
+
+const GRAPHIC_TYPE_DUPE: u32 = ((b'd' as u32) << 24) |
+                                ((b'u' as u32) << 16) |
+                                ((b'p' as u32) << 8)  |
+                                 (b'e' as u32);
+const GRAPHIC_TYPE_JPG: u32 = ((b'j' as u32) << 24) |
+                               ((b'p' as u32) << 16) |
+                               ((b'g' as u32) << 8)  |
+                                (b' ' as u32);
+const GRAPHIC_TYPE_PNG: u32 = ((b'p' as u32) << 24) |
+                               ((b'n' as u32) << 16) |
+                               ((b'g' as u32) << 8)  |
+                                (b' ' as u32);
+const GRAPHIC_TYPE_TIFF: u32 = ((b't' as u32) << 24) |
+                                ((b'i' as u32) << 16) |
+                                ((b'f' as u32) << 8)  |
+                                 (b'f' as u32);
+
+const VERSION: u16 = 1;
+
+// The version, flags, and strike count.
+const HEADER_SIZE: usize = 8;
+// The pixels per em and resolution that precede the glyph data offsets of each strike.
+const STRIKE_HEADER_SIZE: usize = 4;
+// The origin offsets and graphic type that precede the image data of each glyph.
+const GLYPH_DATA_HEADER_SIZE: usize = 8;
+
+#[derive(Clone, Copy, Debug)]
+pub struct SbixTable<'a> {
+    table: FontTable<'a>,
+    num_strikes: u32,
+    num_glyphs: u16,
+}
+
+impl<'a> SbixTable<'a> {
+    /// Reads the table header. `num_glyphs` comes from the `maxp` table and determines the size
+    /// of each strike's glyph data offset array.
+    pub fn new(table: FontTable<'a>, num_glyphs: u16) -> Result<SbixTable<'a>, FontError> {
+        let mut reader = table.bytes;
+
+        // Check the version.
+        let version = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+        if version != VERSION {
+            return Err(FontError::UnsupportedSbixVersion)
+        }
+
+        let _flags = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+        let num_strikes = try!(reader.read_u32::<BigEndian>().map_err(FontError::eof));
+
+        Ok(SbixTable {
+            table: table,
+            num_strikes: num_strikes,
+            num_glyphs: num_glyphs,
+        })
+    }
+
+    /// Returns the sizes at which bitmaps are available, in the order they appear in the font.
+    pub fn strikes(&self) -> Result<Vec<BitmapStrike>, FontError> {
+        let mut strikes = vec![];
+        for strike_index in 0..self.num_strikes {
+            let mut reader = try!(self.strike(strike_index));
+            let ppem = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+            let resolution = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+            strikes.push(BitmapStrike {
+                ppem: ppem,
+                resolution: resolution,
+            })
+        }
+        Ok(strikes)
+    }
+
+    /// Returns the image data of the given glyph, along with its format, from the strike best
+    /// suited to the given size.
+    ///
+    /// That is the smallest strike at least `ppem` pixels per em, or the largest strike if all
+    /// are smaller. The image is not decoded. Returns `None` if the glyph has no bitmap in that
+    /// strike.
+    pub fn bitmap_data(&self, glyph_id: u16, ppem: u16)
+                       -> Result<Option<(BitmapFormat, &'a [u8])>, FontError> {
+        let strikes = try!(self.strikes());
+        let strike_index = match best_strike(&strikes, ppem) {
+            None => return Ok(None),
+            Some(strike_index) => strike_index,
+        };
+        let strike = try!(self.strike(strike_index as u32));
+
+        let (graphic_type, data) = match try!(self.glyph_data(strike, glyph_id)) {
+            None => return Ok(None),
+            Some(glyph_data) => glyph_data,
+        };
+
+        // A `dupe` glyph shares the bitmap of the glyph whose ID is its data.
+        let (graphic_type, data) = if graphic_type == GRAPHIC_TYPE_DUPE {
+            let mut reader = data;
+            let glyph_id = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+            match try!(self.glyph_data(strike, glyph_id)) {
+                None => return Ok(None),
+                Some(glyph_data) => glyph_data,
+            }
+        } else {
+            (graphic_type, data)
+        };
+
+        let format = match graphic_type {
+            GRAPHIC_TYPE_PNG => BitmapFormat::Png,
+            GRAPHIC_TYPE_JPG => BitmapFormat::Jpeg,
+            GRAPHIC_TYPE_TIFF => BitmapFormat::Tiff,
+            _ => return Ok(None),
+        };
+        Ok(Some((format, data)))
+    }
+
+    // Returns the strike with the given index, starting at its header.
+    fn strike(&self, strike_index: u32) -> Result<&'a [u8], FontError> {
+        let mut reader = self.table.bytes;
+        try!(reader.jump(HEADER_SIZE + mem::size_of::<u32>() * strike_index as usize)
+                   .map_err(FontError::eof));
+        let strike_offset = try!(reader.read_u32::<BigEndian>().map_err(FontError::eof));
+
+        let mut strike = self.table.bytes;
+        try!(strike.jump(strike_offset as usize).map_err(FontError::eof));
+        Ok(strike)
+    }
+
+    // Returns the graphic type and image data of the given glyph in the given strike.
+    fn glyph_data(&self, strike: &'a [u8], glyph_id: u16)
+                  -> Result<Option<(u32, &'a [u8])>, FontError> {
+        if glyph_id >= self.num_glyphs {
+            return Ok(None)
+        }
+
+        let mut reader = strike;
+        try!(reader.jump(STRIKE_HEADER_SIZE + mem::size_of::<u32>() * glyph_id as usize)
+                   .map_err(FontError::eof));
+        let start = try!(reader.read_u32::<BigEndian>().map_err(FontError::eof)) as usize;
+        let end = try!(reader.read_u32::<BigEndian>().map_err(FontError::eof)) as usize;
+
+        // Glyphs without bitmaps have no data at all.
+        if end <= start {
+            return Ok(None)
+        }
+        if end - start < GLYPH_DATA_HEADER_SIZE || end > strike.len() {
+            return Err(FontError::UnexpectedEof)
+        }
+
+        let mut reader = &strike[start..end];
+        let _origin_offset_x = try!(reader.read_i16::<BigEndian>().map_err(FontError::eof));
+        let _origin_offset_y = try!(reader.read_i16::<BigEndian>().map_err(FontError::eof));
+        let graphic_type = try!(reader.read_u32::<BigEndian>().map_err(FontError::eof));
+        Ok(Some((graphic_type, reader)))
+    }
+}
+
+/// Returns the index of the smallest strike at least `ppem` pixels per em, or the largest strike
+/// if there is no such strike.
+pub fn best_strike(strikes: &[BitmapStrike], ppem: u16) -> Option<usize> {
+    let mut best: Option<usize> = None;
+    for (strike_index, strike) in strikes.iter().enumerate() {
+        best = match best {
+            None => Some(strike_index),
+            Some(best_index) => {
+                let best_ppem = strikes[best_index].ppem;
+                let better = if best_ppem >= ppem {
+                    strike.ppem >= ppem && strike.ppem < best_ppem
+                } else {
+                    strike.ppem > best_ppem
+                };
+                if better {
+                    Some(strike_index)
+                } else {
+                    Some(best_index)
+                }
+            }
+        }
+    }
+    best
+}
@@ -0,0 +1,220 @@
+// Copyright 2017 The Servo Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! The style attributes table.
+//!
+//! See Microsoft's spec: https://www.microsoft.com/typography/otspec/stat.htm
+
+use error::FontError;
+use font::{AxisValue, AxisValueKind, FontTable, StyleAxis};
+use util::{EofContext, Reader};
+
+pub const TAG: u32 = ((b'S' as u32) << 24) |
+                      ((b'T' as u32) << 16) |
+                      ((b'A' as u32) << 8)  |
+                       (b'T' as u32);
+
+const MAJOR_VERSION: u16 = 1;
+
+// The size of a design axis record, which later minor versions may extend.
+const MIN_DESIGN_AXIS_SIZE: usize = 8;
+
+// Used when the table predates `elidedFallbackNameID`: the font's subfamily name.
+const DEFAULT_ELIDED_FALLBACK_NAME_ID: u16 = 2;
+
+#[derive(Clone, Debug)]
+pub struct StatTable {
+    axes: Vec<StyleAxis>,
+    axis_values: Vec<AxisValue>,
+    elided_fallback_name_id: u16,
+}
+
+impl StatTable {
+    pub fn new(table: FontTable) -> Result<StatTable, FontError> {
+        let mut reader = Reader::new(table.bytes);
+
+        // Check the version.
+        let major_version = try!(reader.read_u16_be().eof_in(TAG, "major version"));
+        let minor_version = try!(reader.read_u16_be().eof_in(TAG, "minor version"));
+        if major_version != MAJOR_VERSION {
+            return Err(FontError::UnsupportedStatVersion)
+        }
+
+        let design_axis_size = try!(reader.read_u16_be().eof_in(TAG, "design axis size"));
+        let design_axis_count = try!(reader.read_u16_be().eof_in(TAG, "design axis count"));
+        let design_axes_offset = try!(reader.read_u32_be().eof_in(TAG, "design axes offset"));
+        let axis_value_count = try!(reader.read_u16_be().eof_in(TAG, "axis value count"));
+        let axis_value_offsets_offset =
+            try!(reader.read_u32_be().eof_in(TAG, "axis value offsets offset"));
+        let elided_fallback_name_id = if minor_version >= 1 {
+            try!(reader.read_u16_be().eof_in(TAG, "elided fallback name ID"))
+        } else {
+            DEFAULT_ELIDED_FALLBACK_NAME_ID
+        };
+
+        if design_axis_count > 0 && (design_axis_size as usize) < MIN_DESIGN_AXIS_SIZE {
+            return Err(FontError::Failed)
+        }
+
+        // Read the design axes.
+        let mut axes = Vec::with_capacity(design_axis_count as usize);
+        if design_axis_count > 0 {
+            let mut reader = try!(Reader::at(table.bytes, design_axes_offset as usize)
+                                      .eof_in(TAG, "design axes"));
+            for _ in 0..design_axis_count {
+                let mut axis = reader;
+                try!(reader.jump(design_axis_size as usize).eof_in(TAG, "design axis"));
+                axes.push(StyleAxis {
+                    tag: try!(axis.read_u32_be().eof_in(TAG, "axis tag")),
+                    axis_name_id: try!(axis.read_u16_be().eof_in(TAG, "axis name ID")),
+                    ordering: try!(axis.read_u16_be().eof_in(TAG, "axis ordering")),
+                })
+            }
+        }
+
+        // Read the axis values. Their offsets are from the start of the offset array.
+        let mut axis_values = Vec::with_capacity(axis_value_count as usize);
+        if axis_value_count > 0 {
+            let offsets = try!(Reader::at(table.bytes, axis_value_offsets_offset as usize)
+                                   .eof_in(TAG, "axis value offsets"));
+            let mut reader = offsets;
+            for _ in 0..axis_value_count {
+                let offset = try!(reader.read_u16_be().eof_in(TAG, "axis value offset"));
+                let value = try!(Reader::at(offsets.remaining(), offset as usize)
+                                     .eof_in(TAG, "axis value"));
+                if let Some(axis_value) = try!(read_axis_value(value)) {
+                    axis_values.push(axis_value)
+                }
+            }
+        }
+
+        Ok(StatTable {
+            axes: axes,
+            axis_values: axis_values,
+            elided_fallback_name_id: elided_fallback_name_id,
+        })
+    }
+
+    /// The axes that the font family varies along, which may include axes such as italic that
+    /// aren't variation axes of this font.
+    #[inline]
+    pub fn axes(&self) -> &[StyleAxis] {
+        &self.axes
+    }
+
+    /// The named values along the axes, such as "Bold" for a weight of 700.
+    #[inline]
+    pub fn axis_values(&self) -> &[AxisValue] {
+        &self.axis_values
+    }
+
+    /// The name ID to use when every axis value of a style is elidable, such as "Regular".
+    #[inline]
+    pub fn elided_fallback_name_id(&self) -> u16 {
+        self.elided_fallback_name_id
+    }
+
+    /// Returns the name ID of the axis value that names the given value along the axis with the
+    /// given tag.
+    ///
+    /// Single values that match exactly take precedence over ranges that contain the value.
+    /// Combinations of values along several axes are never returned.
+    pub fn value_name_id(&self, axis_tag: u32, value: f32) -> Option<u16> {
+        let axis_index = match self.axes.iter().position(|axis| axis.tag == axis_tag) {
+            Some(axis_index) => axis_index as u16,
+            None => return None,
+        };
+
+        let exact = self.axis_values.iter().find(|axis_value| {
+            match axis_value.kind {
+                AxisValueKind::Single { axis_index: index, value: single_value } |
+                AxisValueKind::Linked { axis_index: index, value: single_value, .. } => {
+                    index == axis_index && single_value == value
+                }
+                _ => false,
+            }
+        });
+        let range = || {
+            self.axis_values.iter().find(|axis_value| {
+                match axis_value.kind {
+                    AxisValueKind::Range { axis_index: index, min_value, max_value, .. } => {
+                        index == axis_index && min_value <= value && value <= max_value
+                    }
+                    _ => false,
+                }
+            })
+        };
+        exact.or_else(range).map(|axis_value| axis_value.value_name_id)
+    }
+}
+
+// Reads an axis value table, returning `None` for formats that we don't know about.
+fn read_axis_value(mut reader: Reader) -> Result<Option<AxisValue>, FontError> {
+    let format = try!(reader.read_u16_be().eof_in(TAG, "axis value format"));
+    if format < 1 || format > 4 {
+        return Ok(None)
+    }
+    if format == 4 {
+        // Format 4 has values along several axes.
+        let axis_count = try!(reader.read_u16_be().eof_in(TAG, "axis count"));
+        let flags = try!(reader.read_u16_be().eof_in(TAG, "axis value flags"));
+        let value_name_id = try!(reader.read_u16_be().eof_in(TAG, "value name ID"));
+        let mut values = Vec::with_capacity(axis_count as usize);
+        for _ in 0..axis_count {
+            let axis_index = try!(reader.read_u16_be().eof_in(TAG, "axis index"));
+            let value = try!(read_fixed(&mut reader, "axis value"));
+            values.push((axis_index, value))
+        }
+        return Ok(Some(AxisValue {
+            kind: AxisValueKind::Combination(values),
+            flags: flags,
+            value_name_id: value_name_id,
+        }))
+    }
+
+    let axis_index = try!(reader.read_u16_be().eof_in(TAG, "axis index"));
+    let flags = try!(reader.read_u16_be().eof_in(TAG, "axis value flags"));
+    let value_name_id = try!(reader.read_u16_be().eof_in(TAG, "value name ID"));
+    let kind = match format {
+        1 => {
+            AxisValueKind::Single {
+                axis_index: axis_index,
+                value: try!(read_fixed(&mut reader, "value")),
+            }
+        }
+        2 => {
+            AxisValueKind::Range {
+                axis_index: axis_index,
+                nominal_value: try!(read_fixed(&mut reader, "nominal value")),
+                min_value: try!(read_fixed(&mut reader, "range minimum value")),
+                max_value: try!(read_fixed(&mut reader, "range maximum value")),
+            }
+        }
+        3 => {
+            AxisValueKind::Linked {
+                axis_index: axis_index,
+                value: try!(read_fixed(&mut reader, "value")),
+                linked_value: try!(read_fixed(&mut reader, "linked value")),
+            }
+        }
+        _ => unreachable!(),
+    };
+    Ok(Some(AxisValue {
+        kind: kind,
+        flags: flags,
+        value_name_id: value_name_id,
+    }))
+}
+
+// Reads a 16.16 fixed-point number.
+fn read_fixed(reader: &mut Reader, field: &'static str) -> Result<f32, FontError> {
+    let value = try!(reader.read_i32_be().eof_in(TAG, field));
+    Ok(value as f32 / 65536.0)
+}
@@ -0,0 +1,103 @@
+// Copyright 2017 The Servo Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+use byteorder::{BigEndian, ReadBytesExt};
+use error::FontError;
+use flate2::FlateReadExt;
+use font::FontTable;
+use std::borrow::Cow;
+use std::io::Read;
+use util::Jump;
+
+pub const TAG: u32 = ((b'S' as u32) << 24) |
+                      ((b'V' as u32) << 16) |
+                      ((b'G' as u32) << 8)  |
+                       (b' ' as u32);
+
+const VERSION: u16 = 0;
+
+const DOCUMENT_RECORD_SIZE: usize = 12;
+
+// The gzip magic number followed by the "deflate" compression method.
+static GZIP_HEADER: [u8; 3] = [0x1f, 0x8b, 0x08];
+
+#[derive(Clone, Copy, Debug)]
+pub struct SvgTable<'a> {
+    document_list: &'a [u8],
+}
+
+impl<'a> SvgTable<'a> {
+    pub fn new(table: FontTable<'a>) -> Result<SvgTable<'a>, FontError> {
+        let mut reader = table.bytes;
+
+        // Check the version.
+        let version = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+        if version != VERSION {
+            return Err(FontError::UnsupportedSvgVersion)
+        }
+
+        let document_list_offset = try!(reader.read_u32::<BigEndian>().map_err(FontError::eof));
+        let mut document_list = table.bytes;
+        try!(document_list.jump(document_list_offset as usize).map_err(FontError::eof));
+
+        Ok(SvgTable {
+            document_list: document_list,
+        })
+    }
+
+    /// Returns the SVG document that contains the given glyph.
+    ///
+    /// Documents that are stored gzip-compressed are inflated. A single document may contain
+    /// several glyphs, each as the element with the ID `glyph<glyph_id>`. Returns `None` if the
+    /// glyph has no SVG document.
+    pub fn svg_document(&self, glyph_id: u16) -> Result<Option<Cow<'a, [u8]>>, FontError> {
+        let document = match try!(self.raw_svg_document(glyph_id)) {
+            None => return Ok(None),
+            Some(document) => document,
+        };
+        if !document.starts_with(&GZIP_HEADER) {
+            return Ok(Some(Cow::Borrowed(document)))
+        }
+
+        let mut inflated = vec![];
+        let mut decoder = try!(document.gz_decode().map_err(|_| FontError::SvgBadCompression));
+        try!(decoder.read_to_end(&mut inflated).map_err(|_| FontError::SvgBadCompression));
+        Ok(Some(Cow::Owned(inflated)))
+    }
+
+    // Returns the document that contains the given glyph as stored in the font.
+    fn raw_svg_document(&self, glyph_id: u16) -> Result<Option<&'a [u8]>, FontError> {
+        let mut reader = self.document_list;
+        let num_entries = try!(reader.read_u16::<BigEndian>().map_err(FontError::eof));
+
+        // Binary search the document records, which are sorted by glyph ID.
+        let (mut low, mut high) = (0, num_entries as usize);
+        while low < high {
+            let mid = (low + high) / 2;
+            let mut record = reader;
+            try!(record.jump(mid * DOCUMENT_RECORD_SIZE).map_err(FontError::eof));
+            let start_glyph_id = try!(record.read_u16::<BigEndian>().map_err(FontError::eof));
+            let end_glyph_id = try!(record.read_u16::<BigEndian>().map_err(FontError::eof));
+            if glyph_id < start_glyph_id {
+                high = mid
+            } else if glyph_id > end_glyph_id {
+                low = mid + 1
+            } else {
+                let offset = try!(record.read_u32::<BigEndian>().map_err(FontError::eof));
+                let length = try!(record.read_u32::<BigEndian>().map_err(FontError::eof));
+                let (start, end) = (offset as usize, offset as usize + length as usize);
+                if end > self.document_list.len() {
+                    return Err(FontError::UnexpectedEof)
+                }
+                return Ok(Some(&self.document_list[start..end]))
+            }
+        }
+        Ok(None)
+    }
+}
@@ -0,0 +1,62 @@
+// Copyright 2017 The Servo Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use byteorder::{BigEndian, ReadBytesExt};
+use error::FontError;
+use font::FontTable;
+use std::mem;
+use util::{EofContext, Jump};
+
+pub const TAG: u32 = ((b'v' as u32) << 24) |
+                      ((b'h' as u32) << 16) |
+                      ((b'e' as u32) << 8)  |
+                       (b'a' as u32);
+
+#[derive(Clone, Debug)]
+pub struct VheaTable {
+    pub vert_ascender: i16,
+    pub vert_descender: i16,
+    pub vert_line_gap: i16,
+    pub advance_height_max: u16,
+    pub number_of_v_metrics: u16,
+}
+
+impl VheaTable {
+    pub fn new(table: FontTable) -> Result<VheaTable, FontError> {
+        let mut reader = table.bytes;
+
+        // Check the version. Version 1.1 only renames the fields.
+        let version = try!(reader.read_u32::<BigEndian>().eof_in(TAG, "version"));
+        if version != 0x00010000 && version != 0x00011000 {
+            return Err(FontError::UnsupportedVheaVersion)
+        }
+
+        // Read the height-related metrics.
+        let vert_ascender = try!(reader.read_i16::<BigEndian>().eof_in(TAG, "vertical ascender"));
+        let vert_descender = try!(reader.read_i16::<BigEndian>()
+                                        .eof_in(TAG, "vertical descender"));
+        let vert_line_gap = try!(reader.read_i16::<BigEndian>().eof_in(TAG, "vertical line gap"));
+        let advance_height_max = try!(reader.read_u16::<BigEndian>()
+                                            .eof_in(TAG, "maximum advance height"));
+
+        // Read the number of `vmtx` entries.
+        try!(reader.jump(mem::size_of::<u16>() * 11).eof_in(TAG, "caret and reserved fields"));
+        let number_of_v_metrics = try!(reader.read_u16::<BigEndian>()
+                                             .eof_in(TAG, "number of vertical metrics"));
+
+        Ok(VheaTable {
+            vert_ascender: vert_ascender,
+            vert_descender: vert_descender,
+            vert_line_gap: vert_line_gap,
+            advance_height_max: advance_height_max,
+            number_of_v_metrics: number_of_v_metrics,
+        })
+    }
+}
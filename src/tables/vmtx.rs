@@ -0,0 +1,66 @@
+// Copyright 2017 The Servo Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use error::FontError;
+use font::FontTable;
+use tables::hmtx;
+use tables::vhea::VheaTable;
+
+pub const TAG: u32 = ((b'v' as u32) << 24) |
+                      ((b'm' as u32) << 16) |
+                      ((b't' as u32) << 8)  |
+                       (b'x' as u32);
+
+#[derive(Clone, Copy)]
+pub struct VmtxTable<'a> {
+    table: FontTable<'a>,
+}
+
+impl<'a> VmtxTable<'a> {
+    pub fn new(table: FontTable) -> VmtxTable {
+        VmtxTable {
+            table: table,
+        }
+    }
+
+    pub fn metrics_for_glyph(&self, vhea_table: &VheaTable, glyph_id: u16)
+                             -> Result<VerticalMetrics, FontError> {
+        self.metrics(glyph_id, vhea_table.number_of_v_metrics)
+    }
+
+    /// Returns the advance height of the given glyph, given `numberOfLongVerMetrics` from the
+    /// `vhea` table.
+    #[inline]
+    pub fn advance_height(&self, glyph_id: u16, number_of_v_metrics: u16)
+                          -> Result<u16, FontError> {
+        self.metrics(glyph_id, number_of_v_metrics).map(|metrics| metrics.advance_height)
+    }
+
+    /// Returns the advance height and top side bearing of the given glyph, given
+    /// `numberOfLongVerMetrics` from the `vhea` table.
+    ///
+    /// As in `hmtx`, glyphs past `numberOfLongVerMetrics` share the last advance height.
+    pub fn metrics(&self, glyph_id: u16, number_of_v_metrics: u16)
+                   -> Result<VerticalMetrics, FontError> {
+        let (advance_height, tsb) = try!(hmtx::read_long_metrics(self.table.bytes,
+                                                                 glyph_id,
+                                                                 number_of_v_metrics));
+        Ok(VerticalMetrics {
+            advance_height: advance_height,
+            tsb: tsb,
+        })
+    }
+}
+
+#[derive(Clone, Copy, Default, Debug)]
+pub struct VerticalMetrics {
+    pub advance_height: u16,
+    pub tsb: i16,
+}
@@ -0,0 +1,86 @@
+// Copyright 2017 The Servo Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! The vertical origin table, which gives the Y coordinate of each glyph's origin in vertical
+//! text for CFF-flavored fonts.
+//!
+//! See Microsoft's spec: https://www.microsoft.com/typography/otspec/vorg.htm
+
+use byteorder::{BigEndian, ByteOrder, ReadBytesExt};
+use error::{ErrorContext, FontError};
+use font::FontTable;
+use util::EofContext;
+
+pub const TAG: u32 = ((b'V' as u32) << 24) |
+                      ((b'O' as u32) << 16) |
+                      ((b'R' as u32) << 8)  |
+                       (b'G' as u32);
+
+// The size of a `VertOriginYMetrics` record: a glyph ID and its origin.
+const METRICS_RECORD_SIZE: usize = 4;
+
+#[derive(Clone, Copy, Debug)]
+pub struct VorgTable<'a> {
+    default_vert_origin_y: i16,
+    // The `VertOriginYMetrics` records, sorted by glyph ID.
+    metrics: &'a [u8],
+}
+
+impl<'a> VorgTable<'a> {
+    pub fn new(table: FontTable<'a>) -> Result<VorgTable<'a>, FontError> {
+        let mut reader = table.bytes;
+
+        // Check the version.
+        let major_version = try!(reader.read_u16::<BigEndian>().eof_in(TAG, "major version"));
+        let minor_version = try!(reader.read_u16::<BigEndian>().eof_in(TAG, "minor version"));
+        if (major_version, minor_version) != (1, 0) {
+            return Err(FontError::UnsupportedVorgVersion)
+        }
+
+        let default_vert_origin_y = try!(reader.read_i16::<BigEndian>()
+                                               .eof_in(TAG, "default vertical origin"));
+        let num_vert_origin_y_metrics = try!(reader.read_u16::<BigEndian>()
+                                                   .eof_in(TAG, "number of vertical origins"));
+
+        // Check the length up front so that lookups can't fail.
+        let metrics_size = num_vert_origin_y_metrics as usize * METRICS_RECORD_SIZE;
+        if reader.len() < metrics_size {
+            return Err(FontError::UnexpectedEofIn(ErrorContext {
+                table: TAG,
+                field: "vertical origins",
+            }))
+        }
+
+        Ok(VorgTable {
+            default_vert_origin_y: default_vert_origin_y,
+            metrics: &reader[0..metrics_size],
+        })
+    }
+
+    /// Returns the Y coordinate of the given glyph's vertical origin in font units.
+    ///
+    /// This is the glyph's own value if the table lists it and `defaultVertOriginY` otherwise.
+    pub fn vertical_origin_y(&self, glyph_id: u16) -> i16 {
+        let (mut low, mut high) = (0, self.metrics.len() / METRICS_RECORD_SIZE);
+        while low < high {
+            let mid = (low + high) / 2;
+            let record = &self.metrics[(mid * METRICS_RECORD_SIZE)..];
+            let record_glyph_id = BigEndian::read_u16(record);
+            if glyph_id < record_glyph_id {
+                high = mid
+            } else if glyph_id > record_glyph_id {
+                low = mid + 1
+            } else {
+                return BigEndian::read_i16(&record[2..])
+            }
+        }
+        self.default_vert_origin_y
+    }
+}
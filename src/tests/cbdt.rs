@@ -0,0 +1,129 @@
+/* Any copyright is dedicated to the Public Domain.
+ * http://creativecommons.org/publicdomain/zero/1.0/ */
+
+use font::{BitmapFormat, BitmapStrike, FontTable};
+use tables::cbdt::CbdtTable;
+use tables::cblc::CblcTable;
+use util::{push_u16, push_u32};
+
+static PNG_GRINNING_FACE: &'static [u8] = b"\x89PNG grinning face";
+static PNG_THUMBS_UP: &'static [u8] = b"\x89PNG thumbs up";
+static PNG_THUMBS_DOWN: &'static [u8] = b"\x89PNG thumbs dn";
+
+const CBDT_HEADER_SIZE: u32 = 4;
+const SMALL_GLYPH_METRICS: [u8; 5] = [136, 128, 0, 101, 136];
+
+// A `CBDT` entry in format 17 (small metrics and PNG data).
+fn small_metrics_png(png: &[u8]) -> Vec<u8> {
+    let mut bytes = SMALL_GLYPH_METRICS.to_vec();
+    push_u32(&mut bytes, png.len() as u32);
+    bytes.extend_from_slice(png);
+    bytes
+}
+
+// A `CBDT` entry in format 19 (PNG data with metrics in `CBLC`).
+fn png(png: &[u8]) -> Vec<u8> {
+    let mut bytes = vec![];
+    push_u32(&mut bytes, png.len() as u32);
+    bytes.extend_from_slice(png);
+    bytes
+}
+
+// Builds a strike at 109 pixels per em. Glyphs 1 and 2 are in a format 1 index subtable, with
+// glyph 2 having no bitmap; glyphs 3 and 4 are in a format 2 index subtable.
+fn color_bitmap_tables() -> (Vec<u8>, Vec<u8>) {
+    let grinning_face = small_metrics_png(PNG_GRINNING_FACE);
+    let thumbs_up = png(PNG_THUMBS_UP);
+    let thumbs_down = png(PNG_THUMBS_DOWN);
+    assert_eq!(thumbs_up.len(), thumbs_down.len());
+
+    let mut cbdt = vec![];
+    push_u16(&mut cbdt, 3);
+    push_u16(&mut cbdt, 0);
+    for glyph in &[&grinning_face, &thumbs_up, &thumbs_down] {
+        cbdt.extend_from_slice(glyph)
+    }
+
+    // Index subtable format 1.
+    let mut index_subtable_1 = vec![];
+    push_u16(&mut index_subtable_1, 1);
+    push_u16(&mut index_subtable_1, 17);
+    push_u32(&mut index_subtable_1, CBDT_HEADER_SIZE);
+    for &offset in &[0, grinning_face.len() as u32, grinning_face.len() as u32] {
+        push_u32(&mut index_subtable_1, offset)
+    }
+
+    // Index subtable format 2.
+    let mut index_subtable_2 = vec![];
+    push_u16(&mut index_subtable_2, 2);
+    push_u16(&mut index_subtable_2, 19);
+    push_u32(&mut index_subtable_2, CBDT_HEADER_SIZE + grinning_face.len() as u32);
+    push_u32(&mut index_subtable_2, thumbs_up.len() as u32);
+    index_subtable_2.extend_from_slice(&[136, 128, 0, 101, 0, 0, 0, 0]);
+
+    // Header and bitmap size record.
+    let mut cblc = vec![];
+    push_u16(&mut cblc, 3);
+    push_u16(&mut cblc, 0);
+    push_u32(&mut cblc, 1);
+    push_u32(&mut cblc, 56);
+    push_u32(&mut cblc, (16 + index_subtable_1.len() + index_subtable_2.len()) as u32);
+    push_u32(&mut cblc, 2);
+    push_u32(&mut cblc, 0);
+    cblc.extend_from_slice(&[0; 24]);
+    push_u16(&mut cblc, 1);
+    push_u16(&mut cblc, 4);
+    cblc.extend_from_slice(&[109, 109, 32, 1]);
+
+    // Index subtable array.
+    for &(first, last, offset) in &[(1, 2, 16), (3, 4, 16 + index_subtable_1.len() as u32)] {
+        push_u16(&mut cblc, first);
+        push_u16(&mut cblc, last);
+        push_u32(&mut cblc, offset)
+    }
+    cblc.extend_from_slice(&index_subtable_1);
+    cblc.extend_from_slice(&index_subtable_2);
+
+    (cblc, cbdt)
+}
+
+#[test]
+fn bitmap_strikes() {
+    let (cblc_bytes, _) = color_bitmap_tables();
+    let cblc = CblcTable::new(FontTable {
+        bytes: &cblc_bytes,
+    }).unwrap();
+    assert_eq!(cblc.bitmap_strikes(), Ok(vec![BitmapStrike {
+        ppem: 109,
+        resolution: 72,
+    }]));
+}
+
+#[test]
+fn index_subtable_format_1() {
+    let (cblc_bytes, cbdt_bytes) = color_bitmap_tables();
+    let cblc = CblcTable::new(FontTable {
+        bytes: &cblc_bytes,
+    }).unwrap();
+    let cbdt = CbdtTable::new(FontTable {
+        bytes: &cbdt_bytes,
+    }).unwrap();
+    assert_eq!(cbdt.glyph_bitmap(&cblc, 1, 109), Ok(Some((BitmapFormat::Png, PNG_GRINNING_FACE))));
+    assert_eq!(cbdt.glyph_bitmap(&cblc, 1, 20), Ok(Some((BitmapFormat::Png, PNG_GRINNING_FACE))));
+    assert_eq!(cbdt.glyph_bitmap(&cblc, 2, 109), Ok(None));
+    assert_eq!(cbdt.glyph_bitmap(&cblc, 0, 109), Ok(None));
+}
+
+#[test]
+fn index_subtable_format_2() {
+    let (cblc_bytes, cbdt_bytes) = color_bitmap_tables();
+    let cblc = CblcTable::new(FontTable {
+        bytes: &cblc_bytes,
+    }).unwrap();
+    let cbdt = CbdtTable::new(FontTable {
+        bytes: &cbdt_bytes,
+    }).unwrap();
+    assert_eq!(cbdt.glyph_bitmap(&cblc, 3, 109), Ok(Some((BitmapFormat::Png, PNG_THUMBS_UP))));
+    assert_eq!(cbdt.glyph_bitmap(&cblc, 4, 109), Ok(Some((BitmapFormat::Png, PNG_THUMBS_DOWN))));
+    assert_eq!(cbdt.glyph_bitmap(&cblc, 5, 109), Ok(None));
+}
@@ -0,0 +1,1588 @@
+/* Any copyright is dedicated to the Public Domain.
+ * http://creativecommons.org/publicdomain/zero/1.0/ */
+
+use error::{ErrorContext, FontError};
+use euclid::{Matrix2D, Point2D};
+use font::{Contour, FontTable, PathSegment, Point, PointKind, SvgPathOptions};
+use tables::cff::{self, CffTable};
+use tables::cff_encoding;
+use tables::cff_strings::STANDARD_STRINGS;
+use test::Bencher;
+use util::Reader;
+
+use self::Token::{Esc, Fixed, Mask, N, Op};
+
+// CharString operators.
+const RMOVETO: u8 = 21;
+const RLINETO: u8 = 5;
+const CALLSUBR: u8 = 10;
+const CALLGSUBR: u8 = 29;
+const RETURN: u8 = 11;
+const ENDCHAR: u8 = 14;
+const RRCURVETO: u8 = 8;
+const VMOVETO: u8 = 4;
+const HLINETO: u8 = 6;
+const VLINETO: u8 = 7;
+const RCURVELINE: u8 = 24;
+const RLINECURVE: u8 = 25;
+const VVCURVETO: u8 = 26;
+const HHCURVETO: u8 = 27;
+const HMOVETO: u8 = 22;
+const VHCURVETO: u8 = 30;
+const HVCURVETO: u8 = 31;
+const HSTEM: u8 = 1;
+const HSTEMHM: u8 = 18;
+const VSTEMHM: u8 = 23;
+const HINTMASK: u8 = 19;
+const CNTRMASK: u8 = 20;
+const VSINDEX: u8 = 15;
+const BLEND: u8 = 16;
+const RESERVED: u8 = 9;
+
+// Escaped CharString operators.
+const HFLEX: u8 = 34;
+const FLEX: u8 = 35;
+const HFLEX1: u8 = 36;
+const FLEX1: u8 = 37;
+const DOTSECTION: u8 = 0;
+const ADD: u8 = 10;
+const SUB: u8 = 11;
+const DIV: u8 = 12;
+const NEG: u8 = 14;
+const PUT: u8 = 20;
+const GET: u8 = 21;
+const MUL: u8 = 24;
+const DUP: u8 = 27;
+const EXCH: u8 = 28;
+const INDEX: u8 = 29;
+const ROLL: u8 = 30;
+const ESCAPED_RESERVED: u8 = 38;
+
+// A piece of a CharString: a number, a 16.16 fixed-point number, an operator, an escaped
+// (`12 x`) operator, or a byte of a hint mask.
+#[derive(Clone, Copy)]
+enum Token {
+    N(i32),
+    Fixed(i32),
+    Op(u8),
+    Esc(u8),
+    Mask(u8),
+}
+
+fn char_string(tokens: &[Token]) -> Vec<u8> {
+    let mut bytes = vec![];
+    for token in tokens {
+        match *token {
+            N(value) if value >= -107 && value <= 107 => bytes.push((value + 139) as u8),
+            N(value) if value >= 108 && value <= 1131 => {
+                bytes.push(((value - 108) / 256 + 247) as u8);
+                bytes.push(((value - 108) % 256) as u8)
+            }
+            N(value) if value >= -1131 && value <= -108 => {
+                bytes.push(((-value - 108) / 256 + 251) as u8);
+                bytes.push(((-value - 108) % 256) as u8)
+            }
+            N(value) => bytes.extend_from_slice(&[28, (value >> 8) as u8, value as u8]),
+            Fixed(value) => {
+                bytes.extend_from_slice(&[255,
+                                          (value >> 24) as u8,
+                                          (value >> 16) as u8,
+                                          (value >> 8) as u8,
+                                          value as u8])
+            }
+            Op(operator) => bytes.push(operator),
+            Esc(operator) => bytes.extend_from_slice(&[12, operator]),
+            Mask(byte) => bytes.push(byte),
+        }
+    }
+    bytes
+}
+
+// Encodes a DICT integer in the five-byte form so that DICT sizes are predictable.
+fn dict_int(value: usize) -> Vec<u8> {
+    vec![29, (value >> 24) as u8, (value >> 16) as u8, (value >> 8) as u8, value as u8]
+}
+
+fn index(objects: &[Vec<u8>]) -> Vec<u8> {
+    index_with_off_size(objects, 4)
+}
+
+// Builds an INDEX whose offsets are `off_size` bytes each.
+fn index_with_off_size(objects: &[Vec<u8>], off_size: u8) -> Vec<u8> {
+    let mut bytes = vec![(objects.len() >> 8) as u8, objects.len() as u8];
+    if objects.is_empty() {
+        return bytes
+    }
+
+    bytes.push(off_size);
+    let mut offset = 1;
+    push_offset(&mut bytes, offset, off_size);
+    for object in objects {
+        offset += object.len();
+        push_offset(&mut bytes, offset, off_size);
+    }
+    for object in objects {
+        bytes.extend_from_slice(object)
+    }
+    bytes
+}
+
+fn push_offset(bytes: &mut Vec<u8>, offset: usize, off_size: u8) {
+    for byte in (0..off_size).rev() {
+        bytes.push((offset >> (byte as usize * 8)) as u8)
+    }
+}
+
+// Builds a Private DICT starting with the given operators and the local subr INDEX that
+// immediately follows it.
+fn private_dict(operators: &[u8], local_subrs: &[Vec<u8>]) -> (Vec<u8>, Vec<u8>) {
+    let mut dict = operators.to_vec();
+    if local_subrs.is_empty() {
+        return (dict, vec![])
+    }
+    let subrs_offset = dict.len() + 6;
+    dict.extend(dict_int(subrs_offset));
+    dict.push(19);
+    (dict, index(local_subrs))
+}
+
+// Assembles a minimal single-font CFF table. If `fd_select` is set, the font is CID-keyed, with
+// one font DICT per entry of `font_dict_local_subrs`.
+#[derive(Default)]
+pub struct CffBuilder {
+    pub char_strings: Vec<Vec<u8>>,
+    pub global_subrs: Vec<Vec<u8>>,
+    pub local_subrs: Vec<Vec<u8>>,
+    pub top_dict_operators: Vec<u8>,
+    pub private_dict_operators: Vec<u8>,
+    pub fd_select: Option<Vec<u8>>,
+    pub font_dict_local_subrs: Vec<Vec<Vec<u8>>>,
+    pub strings: Vec<Vec<u8>>,
+    pub charset: Option<Vec<u8>>,
+    pub encoding: Option<Vec<u8>>,
+    // The size of the CharStrings INDEX's offsets, if not 4.
+    pub char_strings_off_size: Option<u8>,
+}
+
+impl CffBuilder {
+    fn top_dict(&self,
+                char_strings_offset: usize,
+                charset_offset: usize,
+                encoding_offset: usize,
+                private_size: usize,
+                private_offset: usize,
+                fd_array_offset: usize,
+                fd_select_offset: usize)
+                -> Vec<u8> {
+        let mut top_dict = vec![];
+        if self.fd_select.is_some() {
+            // ROS
+            top_dict.extend(dict_int(0));
+            top_dict.extend(dict_int(0));
+            top_dict.extend(dict_int(0));
+            top_dict.extend_from_slice(&[12, 30]);
+        }
+        top_dict.extend(dict_int(char_strings_offset));
+        top_dict.push(17);
+        if self.charset.is_some() {
+            top_dict.extend(dict_int(charset_offset));
+            top_dict.push(15);
+        }
+        if self.encoding.is_some() {
+            top_dict.extend(dict_int(encoding_offset));
+            top_dict.push(16);
+        }
+        if self.fd_select.is_some() {
+            top_dict.extend(dict_int(fd_array_offset));
+            top_dict.extend_from_slice(&[12, 36]);
+            top_dict.extend(dict_int(fd_select_offset));
+            top_dict.extend_from_slice(&[12, 37]);
+        } else {
+            top_dict.extend(dict_int(private_size));
+            top_dict.extend(dict_int(private_offset));
+            top_dict.push(18);
+        }
+        top_dict.extend_from_slice(&self.top_dict_operators);
+        top_dict
+    }
+
+    pub fn build(&self) -> Vec<u8> {
+        let name_index = index(&[b"Test".to_vec()]);
+        let string_index = index(&self.strings);
+        let global_subr_index = index(&self.global_subrs);
+        let char_strings_index = index_with_off_size(&self.char_strings,
+                                                     self.char_strings_off_size.unwrap_or(4));
+        let private_dicts: Vec<_> = match self.fd_select {
+            None => vec![private_dict(&self.private_dict_operators, &self.local_subrs)],
+            Some(_) => {
+                self.font_dict_local_subrs
+                    .iter()
+                    .map(|subrs| private_dict(&self.private_dict_operators, subrs))
+                    .collect()
+            }
+        };
+
+        // All DICT integers are five bytes, so DICT sizes are known up front.
+        let top_dict_index_length = index(&[self.top_dict(0, 0, 0, 0, 0, 0, 0)]).len();
+        let char_strings_offset = 4 + name_index.len() + top_dict_index_length +
+            string_index.len() + global_subr_index.len();
+        let charset_offset = char_strings_offset + char_strings_index.len();
+        let charset = self.charset.clone().unwrap_or(vec![]);
+        let encoding_offset = charset_offset + charset.len();
+        let encoding = self.encoding.clone().unwrap_or(vec![]);
+        let fd_select_offset = encoding_offset + encoding.len();
+        let fd_select = self.fd_select.clone().unwrap_or(vec![]);
+        let fd_array_offset = fd_select_offset + fd_select.len();
+
+        let mut private_offset = fd_array_offset;
+        if self.fd_select.is_some() {
+            private_offset += index(&vec![vec![0; 11]; private_dicts.len()]).len();
+        }
+
+        let mut font_dicts = vec![];
+        let mut private_data = vec![];
+        for &(ref dict, ref local_subr_index) in &private_dicts {
+            let mut font_dict = dict_int(dict.len());
+            font_dict.extend(dict_int(private_offset + private_data.len()));
+            font_dict.push(18);
+            font_dicts.push(font_dict);
+            private_data.extend_from_slice(dict);
+            private_data.extend_from_slice(local_subr_index);
+        }
+
+        let top_dict = self.top_dict(char_strings_offset,
+                                     charset_offset,
+                                     encoding_offset,
+                                     private_dicts[0].0.len(),
+                                     private_offset,
+                                     fd_array_offset,
+                                     fd_select_offset);
+
+        let mut cff = vec![1, 0, 4, 4];
+        cff.extend(name_index);
+        cff.extend(index(&[top_dict]));
+        cff.extend(string_index);
+        cff.extend(global_subr_index);
+        cff.extend(char_strings_index);
+        cff.extend(charset);
+        cff.extend(encoding);
+        if self.fd_select.is_some() {
+            cff.extend(fd_select);
+            cff.extend(index(&font_dicts));
+        }
+        cff.extend(private_data);
+        cff
+    }
+}
+
+fn glyph_points(cff: &[u8], glyph_id: u16) -> Result<Vec<Point>, FontError> {
+    let table = try!(CffTable::new(FontTable {
+        bytes: cff,
+    }));
+    let mut points = vec![];
+    try!(table.for_each_point(glyph_id, |point| points.push(*point)));
+    Ok(points)
+}
+
+#[test]
+fn subroutines_match_inlined_char_string() {
+    let mut builder = CffBuilder::default();
+    builder.char_strings.push(char_string(&[
+        N(100), N(100), Op(RMOVETO),
+        N(50), N(0), N(0), N(50), N(-50), N(0), Op(RLINETO),
+        Op(ENDCHAR),
+    ]));
+    builder.char_strings.push(char_string(&[
+        N(100), N(100), Op(RMOVETO),
+        N(-107), Op(CALLGSUBR),
+        Op(ENDCHAR),
+    ]));
+    builder.global_subrs.push(char_string(&[
+        N(50), N(0), N(-107), Op(CALLSUBR), N(-50), N(0), Op(RLINETO), Op(RETURN),
+    ]));
+    builder.local_subrs.push(char_string(&[N(0), N(50), Op(RETURN)]));
+
+    let cff = builder.build();
+    let inlined = glyph_points(&cff, 0).unwrap();
+    assert_eq!(inlined.len(), 5);
+    assert_eq!(glyph_points(&cff, 1).unwrap(), inlined);
+}
+
+#[test]
+fn recursive_subroutines_are_rejected() {
+    let mut builder = CffBuilder::default();
+    builder.char_strings.push(char_string(&[N(-107), Op(CALLGSUBR), Op(ENDCHAR)]));
+    builder.global_subrs.push(char_string(&[N(-107), Op(CALLGSUBR), Op(RETURN)]));
+
+    assert_eq!(glyph_points(&builder.build(), 0), Err(FontError::CffSubrNestingTooDeep));
+}
+
+#[test]
+fn unknown_operators_are_skipped_leniently() {
+    let mut builder = CffBuilder::default();
+    builder.char_strings.push(char_string(&[
+        N(100), N(100), Op(RMOVETO),
+        N(50), N(0), N(0), N(50), Op(RLINETO),
+        N(1), N(2), Esc(ESCAPED_RESERVED),
+        N(1), N(2), Op(RESERVED),
+        N(-50), N(0), Op(RLINETO),
+        Op(ENDCHAR),
+    ]));
+    builder.char_strings.push(char_string(&[
+        N(100), N(100), Op(RMOVETO),
+        N(50), N(0), N(0), N(50), N(-50), N(0), Op(RLINETO),
+        Op(ENDCHAR),
+    ]));
+    let cff = builder.build();
+    assert_eq!(glyph_points(&cff, 0), Err(FontError::CffUnimplementedOperator));
+
+    let table = CffTable::new(FontTable {
+        bytes: &cff,
+    }).unwrap();
+    let mut points = vec![];
+    let skipped = table.for_each_point_leniently(0, |point| points.push(*point)).unwrap();
+    assert_eq!(skipped, vec![((ESCAPED_RESERVED as u16) << 8) | 12, RESERVED as u16]);
+    assert_eq!(points, glyph_points(&cff, 1).unwrap());
+
+    let mut points = vec![];
+    assert_eq!(table.for_each_point_leniently(1, |point| points.push(*point)), Ok(vec![]));
+    assert_eq!(points.len(), 5);
+}
+
+#[test]
+fn dotsection_is_ignored() {
+    let mut builder = CffBuilder::default();
+    builder.char_strings.push(char_string(&[
+        N(100), N(100), Op(RMOVETO),
+        Esc(DOTSECTION),
+        N(50), N(0), N(0), N(50), Op(RLINETO),
+        Esc(DOTSECTION),
+        N(-50), N(0), Op(RLINETO),
+        Esc(DOTSECTION),
+        Op(ENDCHAR),
+    ]));
+    builder.char_strings.push(char_string(&[
+        N(100), N(100), Op(RMOVETO),
+        N(50), N(0), N(0), N(50), N(-50), N(0), Op(RLINETO),
+        Op(ENDCHAR),
+    ]));
+    let cff = builder.build();
+    let points = glyph_points(&cff, 0).unwrap();
+    assert_eq!(points.len(), 5);
+    assert_eq!(points, glyph_points(&cff, 1).unwrap());
+}
+
+#[test]
+fn arithmetic_operators() {
+    let mut builder = CffBuilder::default();
+    builder.char_strings.push(char_string(&[
+        N(-50), N(3), Esc(PUT),
+        N(30), N(70), Esc(ADD), N(130), N(30), Esc(SUB), Op(RMOVETO),
+        N(10), N(5), Esc(MUL), N(0), N(0), N(150), N(3), Esc(DIV),
+        N(3), Esc(GET), N(0), Op(RLINETO),
+        Op(ENDCHAR),
+    ]));
+    builder.char_strings.push(char_string(&[
+        N(100), N(100), Op(RMOVETO),
+        N(50), N(0), N(0), N(50), N(-50), N(0), Op(RLINETO),
+        Op(ENDCHAR),
+    ]));
+    let cff = builder.build();
+    assert_eq!(glyph_points(&cff, 0).unwrap(), glyph_points(&cff, 1).unwrap());
+
+    // Quotients are rounded to the nearest integer.
+    builder.char_strings[0] = char_string(&[
+        N(7), N(2), Esc(DIV), N(-7), N(2), Esc(DIV), Op(RMOVETO),
+        Op(ENDCHAR),
+    ]);
+    let points = glyph_points(&builder.build(), 0).unwrap();
+    assert_eq!(points[0].position, Point2D::new(4, -4));
+
+    // The transient array has 32 entries, and nothing may be divided by zero.
+    builder.char_strings[0] = char_string(&[N(1), N(32), Esc(PUT), Op(ENDCHAR)]);
+    assert_eq!(glyph_points(&builder.build(), 0), Err(FontError::CffMalformedCharString));
+    builder.char_strings[0] = char_string(&[N(1), N(0), Esc(DIV), Op(ENDCHAR)]);
+    assert_eq!(glyph_points(&builder.build(), 0), Err(FontError::CffMalformedCharString));
+}
+
+#[test]
+fn stack_operators() {
+    let mut builder = CffBuilder::default();
+    builder.char_strings.push(char_string(&[
+        N(100), Esc(DUP), Op(RMOVETO),
+        N(0), N(50), N(0), N(-50), Esc(EXCH), N(1), Esc(INDEX), Esc(NEG), N(0),
+        N(6), N(-4), Esc(ROLL), Op(RLINETO),
+        Op(ENDCHAR),
+    ]));
+    builder.char_strings.push(char_string(&[
+        N(100), N(100), Op(RMOVETO),
+        N(50), N(0), N(0), N(50), N(-50), N(0), Op(RLINETO),
+        Op(ENDCHAR),
+    ]));
+    let cff = builder.build();
+    assert_eq!(glyph_points(&cff, 0).unwrap(), glyph_points(&cff, 1).unwrap());
+
+    // Only elements on the stack can be rolled.
+    builder.char_strings[0] = char_string(&[N(1), N(2), N(3), N(1), Esc(ROLL), Op(ENDCHAR)]);
+    assert_eq!(glyph_points(&builder.build(), 0), Err(FontError::CffMalformedCharString));
+}
+
+#[test]
+fn out_of_range_values_saturate_or_wrap() {
+    // Arithmetic saturates at the limits of 16.16 fixed-point numbers, and coordinates wrap.
+    let mut builder = CffBuilder::default();
+    builder.char_strings.push(char_string(&[
+        N(30000), N(30000), Esc(MUL), N(0), Op(RMOVETO),
+        N(30000), N(0), Op(RLINETO),
+        Op(ENDCHAR),
+    ]));
+    let points = glyph_points(&builder.build(), 0).unwrap();
+    assert_eq!(points[0].position, Point2D::new(32767, 0));
+    assert_eq!(points[1].position, Point2D::new(-2769, 0));
+}
+
+// The square that the hinted glyphs below draw.
+fn unhinted_square() -> Vec<u8> {
+    char_string(&[
+        N(100), N(100), Op(RMOVETO),
+        N(200), N(0), Op(RLINETO),
+        N(0), N(200), Op(RLINETO),
+        Op(ENDCHAR),
+    ])
+}
+
+// Hint masks have one bit per stem, so these bytes must be skipped exactly. Mask bytes that are
+// read as CharString data by mistake move the points or fail.
+#[test]
+fn hint_masks_skip_one_bit_per_stem() {
+    let mut builder = CffBuilder::default();
+    builder.char_strings.push(unhinted_square());
+
+    // A width, four horizontal stems, then five implicit vertical stems: nine in all, so two
+    // bytes per mask. The second mask must not count the stems again.
+    builder.char_strings.push(char_string(&[
+        N(500), N(10), N(20), N(40), N(20), N(70), N(20), N(100), N(20), Op(HSTEMHM),
+        N(10), N(20), N(40), N(20), N(70), N(20), N(100), N(20), N(130), N(20), Op(HINTMASK),
+        Mask(0xff), Mask(0x80),
+        N(100), N(100), Op(RMOVETO),
+        N(200), N(0), Op(RLINETO),
+        Op(HINTMASK), Mask(0x15), Mask(0x80),
+        N(0), N(200), Op(RLINETO),
+        Op(ENDCHAR),
+    ]));
+
+    // Eight explicit stems and one implicit one, with the horizontal stems in a subroutine.
+    builder.char_strings.push(char_string(&[
+        N(-107), Op(CALLSUBR),
+        N(10), N(20), N(40), N(20), N(70), N(20), N(100), N(20), Op(VSTEMHM),
+        N(130), N(20), Op(HINTMASK), Mask(0x15), Mask(0x80),
+        N(100), N(100), Op(RMOVETO),
+        N(200), N(0), Op(RLINETO),
+        N(0), N(200), Op(RLINETO),
+        Op(HINTMASK), Mask(0x15), Mask(0x80),
+        Op(ENDCHAR),
+    ]));
+    builder.local_subrs.push(char_string(&[
+        N(10), N(20), N(40), N(20), N(70), N(20), N(100), N(20), Op(HSTEMHM), Op(RETURN),
+    ]));
+
+    // Only a width and an implicit stem, so one byte per mask.
+    builder.char_strings.push(char_string(&[
+        N(500), N(10), N(20), Op(HINTMASK), Mask(0x80),
+        N(100), N(100), Op(RMOVETO),
+        N(200), N(0), Op(RLINETO),
+        N(0), N(200), Op(RLINETO),
+        Op(ENDCHAR),
+    ]));
+
+    let cff = builder.build();
+    let unhinted = glyph_points(&cff, 0).unwrap();
+    assert_eq!(unhinted.len(), 4);
+    for glyph_id in 1..4 {
+        assert_eq!(glyph_points(&cff, glyph_id).unwrap(), unhinted);
+    }
+}
+
+#[test]
+fn counter_masks_skip_without_adding_stems() {
+    let mut builder = CffBuilder::default();
+    builder.char_strings.push(unhinted_square());
+
+    // Four horizontal stems and four implicit vertical ones, so one byte per mask. If a
+    // `cntrmask` changed the hint count, the masks after it would be misread.
+    builder.char_strings.push(char_string(&[
+        N(10), N(20), N(40), N(20), N(70), N(20), N(100), N(20), Op(HSTEMHM),
+        N(10), N(20), N(40), N(20), N(70), N(20), N(100), N(20), Op(HINTMASK), Mask(0xff),
+        Op(CNTRMASK), Mask(0xf0),
+        N(100), N(100), Op(RMOVETO),
+        N(200), N(0), Op(RLINETO),
+        Op(HINTMASK), Mask(0x15),
+        Op(CNTRMASK), Mask(0x0f),
+        N(0), N(200), Op(RLINETO),
+        Op(ENDCHAR),
+    ]));
+
+    // Eight explicit stems. The operands before the `cntrmask` aren't a ninth, which would make
+    // the masks two bytes long.
+    builder.char_strings.push(char_string(&[
+        N(10), N(20), N(40), N(20), N(70), N(20), N(100), N(20), Op(HSTEMHM),
+        N(10), N(20), N(40), N(20), N(70), N(20), N(100), N(20), Op(VSTEMHM),
+        N(130), N(20), Op(CNTRMASK), Mask(0xf0),
+        Op(HINTMASK), Mask(0xff),
+        N(100), N(100), Op(RMOVETO),
+        N(200), N(0), Op(RLINETO),
+        N(0), N(200), Op(RLINETO),
+        Op(ENDCHAR),
+    ]));
+
+    let cff = builder.build();
+    let unhinted = glyph_points(&cff, 0).unwrap();
+    for glyph_id in 1..3 {
+        assert_eq!(glyph_points(&cff, glyph_id).unwrap(), unhinted);
+    }
+}
+
+// Checks that each flex variant draws the same two curves as the equivalent `rrcurveto`.
+#[test]
+fn flex_matches_rrcurveto() {
+    let mut builder = CffBuilder::default();
+    let moveto = [N(10), N(20), Op(RMOVETO)];
+    let flexes = [
+        vec![N(10), N(20), N(-5), N(30), N(40), N(50), N(60), Esc(HFLEX)],
+        vec![N(10), N(5), N(20), N(-5), N(30), N(1), N(40), N(2), N(50), N(-3), N(60), N(0),
+             N(50), Esc(FLEX)],
+        vec![N(10), N(5), N(20), N(-5), N(30), N(40), N(50), N(7), N(60), Esc(HFLEX1)],
+        vec![N(10), N(5), N(20), N(-5), N(30), N(1), N(40), N(2), N(50), N(-3), N(60),
+             Esc(FLEX1)],
+    ];
+    let curves = [
+        [N(10), N(0), N(20), N(-5), N(30), N(0), N(40), N(0), N(50), N(5), N(60), N(0)],
+        [N(10), N(5), N(20), N(-5), N(30), N(1), N(40), N(2), N(50), N(-3), N(60), N(0)],
+        [N(10), N(5), N(20), N(-5), N(30), N(0), N(40), N(0), N(50), N(7), N(60), N(-7)],
+        [N(10), N(5), N(20), N(-5), N(30), N(1), N(40), N(2), N(50), N(-3), N(60), N(0)],
+    ];
+
+    for (flex, curve) in flexes.iter().zip(curves.iter()) {
+        let mut tokens = moveto.to_vec();
+        tokens.extend_from_slice(flex);
+        tokens.push(Op(ENDCHAR));
+        builder.char_strings.push(char_string(&tokens));
+
+        let mut tokens = moveto.to_vec();
+        tokens.extend_from_slice(curve);
+        tokens.extend_from_slice(&[Op(RRCURVETO), Op(ENDCHAR)]);
+        builder.char_strings.push(char_string(&tokens));
+    }
+
+    let cff = builder.build();
+    for glyph_id in 0..4 {
+        let flex = glyph_points(&cff, glyph_id * 2).unwrap();
+        assert_eq!(flex.len(), 8);
+        assert_eq!(flex[6].index_in_contour, 6);
+        assert_eq!(flex, glyph_points(&cff, glyph_id * 2 + 1).unwrap());
+    }
+}
+
+#[test]
+fn real_dict_operands() {
+    fn real(bytes: &[u8]) -> Result<f64, FontError> {
+        let mut reader = Reader::new(bytes);
+        let value = try!(cff::read_real(&mut reader));
+        assert!(reader.is_empty());
+        Ok(value)
+    }
+
+    assert_eq!(real(&[0x1a, 0x5f]), Ok(1.5));
+    assert_eq!(real(&[0xea, 0x00, 0x1f]), Ok(-0.001));
+    assert_eq!(real(&[0x1a, 0x2b, 0x4f]), Ok(1.2e4));
+    assert_eq!(real(&[0x2a, 0x5c, 0x3f]), Ok(2.5e-3));
+    assert_eq!(real(&[0x12, 0xff]), Ok(12.0));
+    assert_eq!(real(&[0x1d, 0xff]), Err(FontError::CffBadRealNumber));
+    assert_eq!(real(&[0x1a]), Err(FontError::UnexpectedEof));
+}
+
+#[test]
+fn font_matrix() {
+    let mut builder = CffBuilder::default();
+    builder.char_strings.push(char_string(&[Op(ENDCHAR)]));
+    let cff = builder.build();
+    let table = CffTable::new(FontTable {
+        bytes: &cff,
+    }).unwrap();
+    assert_eq!(table.font_matrix(), Matrix2D::create_scale(0.001, 0.001));
+
+    // [.00048828125 0 .25 .00048828125 0 0] FontMatrix: 2048 units per em, with a skew.
+    let scale = [30, 0xa0, 0x00, 0x48, 0x82, 0x81, 0x25, 0xff];
+    builder.top_dict_operators.extend_from_slice(&scale);
+    builder.top_dict_operators.extend_from_slice(&[139, 30, 0xa2, 0x5f]);
+    builder.top_dict_operators.extend_from_slice(&scale);
+    builder.top_dict_operators.extend_from_slice(&[139, 139, 12, 7]);
+    let cff = builder.build();
+    let table = CffTable::new(FontTable {
+        bytes: &cff,
+    }).unwrap();
+    assert_eq!(table.font_matrix(),
+               Matrix2D::row_major(1.0 / 2048.0, 0.0, 0.25, 1.0 / 2048.0, 0.0, 0.0));
+}
+
+#[test]
+fn fixed_operands_are_rounded() {
+    let mut builder = CffBuilder::default();
+    builder.char_strings.push(char_string(&[
+        Fixed(0x0001_c000), Fixed(0x0002_4000), Op(RMOVETO),
+        Fixed(-0x0000_c000), Fixed(0x0000_8000), Op(RLINETO),
+        Op(ENDCHAR),
+    ]));
+
+    // 1.75 rounds up to 2 and 2.25 down to 2; then -0.75 and 0.5 round to -1 and 1.
+    let points = glyph_points(&builder.build(), 0).unwrap();
+    assert_eq!((points[0].position.x, points[0].position.y), (2, 2));
+    assert_eq!((points[1].position.x, points[1].position.y), (1, 3));
+}
+
+// Feeds every path operator each truncated prefix of a valid operand list.
+#[test]
+fn truncated_operands_are_rejected() {
+    let operators = [
+        (Op(VMOVETO), 1), (Op(RLINETO), 2), (Op(HLINETO), 1), (Op(VLINETO), 1),
+        (Op(RRCURVETO), 6), (Op(RCURVELINE), 8), (Op(RLINECURVE), 8), (Op(VVCURVETO), 4),
+        (Op(HHCURVETO), 4), (Op(RMOVETO), 2), (Op(HMOVETO), 1), (Op(VHCURVETO), 4),
+        (Op(HVCURVETO), 4), (Esc(HFLEX), 7), (Esc(FLEX), 13), (Esc(HFLEX1), 9),
+        (Esc(FLEX1), 11),
+    ];
+
+    let mut builder = CffBuilder::default();
+    for &(operator, operand_count) in &operators {
+        for truncated_count in 0..(operand_count + 1) {
+            let mut tokens = vec![N(1); truncated_count];
+            tokens.extend_from_slice(&[operator, Op(ENDCHAR)]);
+            builder.char_strings.push(char_string(&tokens));
+        }
+    }
+
+    let cff = builder.build();
+    let mut glyph_id = 0;
+    for &(_, operand_count) in &operators {
+        for truncated_count in 0..(operand_count + 1) {
+            let result = glyph_points(&cff, glyph_id);
+            if truncated_count == operand_count {
+                assert!(result.is_ok())
+            } else {
+                assert_eq!(result, Err(FontError::CffMalformedCharString))
+            }
+            glyph_id += 1
+        }
+    }
+}
+
+// Each glyph calls local subr 0, which draws a line whose length depends on the font DICT.
+fn cid_keyed_builder(fd_select: Vec<u8>) -> CffBuilder {
+    let mut builder = CffBuilder::default();
+    for _ in 0..4 {
+        builder.char_strings.push(char_string(&[
+            N(0), N(0), Op(RMOVETO), N(-107), Op(CALLSUBR), Op(ENDCHAR),
+        ]));
+    }
+    for length in &[10, 20, 30] {
+        builder.font_dict_local_subrs.push(vec![
+            char_string(&[N(*length), N(0), Op(RLINETO), Op(RETURN)]),
+        ]);
+    }
+    builder.fd_select = Some(fd_select);
+    builder
+}
+
+fn cid_keyed_line_lengths(builder: &CffBuilder) -> Vec<i16> {
+    let cff = builder.build();
+    (0..4).map(|glyph_id| glyph_points(&cff, glyph_id).unwrap()[1].position.x).collect()
+}
+
+#[test]
+fn cid_keyed_fd_select_format_0() {
+    let builder = cid_keyed_builder(vec![0, 2, 1, 1, 0]);
+    assert_eq!(cid_keyed_line_lengths(&builder), vec![30, 20, 20, 10]);
+}
+
+#[test]
+fn cid_keyed_fd_select_format_3() {
+    // Glyph 0 uses font DICT 1, glyphs 1 and 2 use font DICT 2, and glyph 3 uses font DICT 0.
+    let builder = cid_keyed_builder(vec![3, 0, 3, 0, 0, 1, 0, 1, 2, 0, 3, 0, 0, 4]);
+    assert_eq!(cid_keyed_line_lengths(&builder), vec![20, 30, 30, 10]);
+
+    // Glyphs past the sentinel have no font DICT.
+    let builder = cid_keyed_builder(vec![3, 0, 1, 0, 0, 0, 0, 2]);
+    let cff = builder.build();
+    assert!(glyph_points(&cff, 1).is_ok());
+    assert_eq!(glyph_points(&cff, 2), Err(FontError::CffFontDictNotFound));
+}
+
+#[test]
+fn advance_widths() {
+    let mut builder = CffBuilder::default();
+    builder.private_dict_operators.extend(dict_int(600));
+    builder.private_dict_operators.push(20);
+    builder.private_dict_operators.extend(dict_int(500));
+    builder.private_dict_operators.push(21);
+
+    let moveto = [N(10), N(20), Op(RMOVETO), N(30), N(0), Op(RLINETO), Op(ENDCHAR)];
+    builder.char_strings.push(char_string(&moveto));
+    let mut tokens = vec![N(-50)];
+    tokens.extend_from_slice(&moveto);
+    builder.char_strings.push(char_string(&tokens));
+    builder.char_strings.push(char_string(&[N(120), N(0), N(10), Op(HSTEM), N(5), Op(HMOVETO),
+                                            Op(ENDCHAR)]));
+    builder.char_strings.push(char_string(&[N(20), Op(ENDCHAR)]));
+
+    let cff = builder.build();
+    let table = CffTable::new(FontTable {
+        bytes: &cff,
+    }).unwrap();
+    assert_eq!(table.advance_width(0), Ok(600.0));
+    assert_eq!(table.advance_width(1), Ok(450.0));
+    assert_eq!(table.advance_width(2), Ok(620.0));
+    assert_eq!(table.advance_width(3), Ok(520.0));
+
+    // The width isn't treated as a path operand.
+    assert_eq!(glyph_points(&cff, 0).unwrap(), glyph_points(&cff, 1).unwrap());
+}
+
+// With the default ISOAdobe charset, glyph IDs are SIDs, so "e" is glyph 70 and "acute" is glyph
+// 125.
+fn seac_builder(accent: Vec<u8>) -> CffBuilder {
+    let mut builder = CffBuilder::default();
+    builder.char_strings.push(char_string(&[N(30), N(200), N(101), N(194), Op(ENDCHAR)]));
+    while builder.char_strings.len() < 126 {
+        builder.char_strings.push(char_string(&[Op(ENDCHAR)]));
+    }
+    builder.char_strings[70] = char_string(&[
+        N(10), N(0), Op(RMOVETO), N(100), N(0), N(0), N(100), N(-100), N(0), Op(RLINETO),
+        Op(ENDCHAR),
+    ]);
+    builder.char_strings[125] = accent;
+    builder
+}
+
+#[test]
+fn seac_composes_base_and_accent() {
+    let cff = seac_builder(char_string(&[
+        N(40), N(20), Op(RMOVETO), N(20), N(30), Op(RLINETO), Op(ENDCHAR),
+    ])).build();
+
+    let mut expected = glyph_points(&cff, 70).unwrap();
+    for mut point in glyph_points(&cff, 125).unwrap() {
+        point.position.x += 30;
+        point.position.y += 200;
+        expected.push(point)
+    }
+    assert_eq!(expected.len(), 7);
+    assert_eq!(glyph_points(&cff, 0).unwrap(), expected);
+}
+
+#[test]
+fn nested_seac_is_rejected() {
+    let cff = seac_builder(char_string(&[N(0), N(0), N(101), N(194), Op(ENDCHAR)])).build();
+    assert_eq!(glyph_points(&cff, 0), Err(FontError::CffBadSeacComponent));
+}
+
+#[test]
+fn num_glyphs_is_char_strings_count() {
+    let mut builder = CffBuilder::default();
+    for _ in 0..3 {
+        builder.char_strings.push(char_string(&[Op(ENDCHAR)]));
+    }
+    let cff = builder.build();
+    let table = CffTable::new(FontTable {
+        bytes: &cff,
+    }).unwrap();
+    assert_eq!(table.num_glyphs(), Ok(3));
+    assert!(glyph_points(&cff, 2).is_ok());
+}
+
+#[test]
+fn tight_bounds_exclude_control_points() {
+    let mut builder = CffBuilder::default();
+    builder.char_strings.push(char_string(&[
+        N(10), N(10), Op(RMOVETO),
+        N(0), N(300), N(100), N(0), N(0), N(-300), Op(RRCURVETO),
+        Op(ENDCHAR),
+    ]));
+    let cff = builder.build();
+    let table = CffTable::new(FontTable {
+        bytes: &cff,
+    }).unwrap();
+
+    let loose = table.glyph_bounds(0).unwrap();
+    assert_eq!(loose.top, 310);
+
+    // The curve peaks at t = 0.5, three-quarters of the way to its control points.
+    let tight = table.glyph_bounds_tight(0).unwrap();
+    assert_eq!((tight.left, tight.bottom, tight.right, tight.top), (10, 10, 110, 235));
+}
+
+#[test]
+fn bare_cff_font_programs() {
+    let mut builder = CffBuilder::default();
+    builder.char_strings.push(char_string(&[
+        N(10), N(20), Op(RMOVETO),
+        N(100), N(0), Op(RLINETO),
+        Op(ENDCHAR),
+    ]));
+    let cff = builder.build();
+    let table = CffTable::from_bytes(&cff).unwrap();
+    let mut points = vec![];
+    table.for_each_point(0, |point| points.push(*point)).unwrap();
+    assert_eq!(points, glyph_points(&cff, 0).unwrap());
+    assert_eq!(points.iter().map(|point| point.position).collect::<Vec<_>>(),
+               vec![Point2D::new(10, 20), Point2D::new(110, 20), Point2D::new(10, 20)]);
+
+    // An sfnt wrapper isn't a CFF header.
+    let mut wrapped = vec![0, 1, 0, 0];
+    wrapped.extend_from_slice(&cff);
+    assert_eq!(CffTable::from_bytes(&wrapped).err(), Some(FontError::UnsupportedCffVersion));
+}
+
+// A FontSet of two fonts, "First" with one glyph and "Second" with two. Each glyph is a line
+// from the origin to the right: 10 units long in "First", and 20 and 30 units in "Second".
+fn font_set() -> Vec<u8> {
+    let names = [b"First".to_vec(), b"Second".to_vec()];
+    let line_lengths: [&[i32]; 2] = [&[10], &[20, 30]];
+    let char_strings: Vec<_> = line_lengths.iter().map(|lengths| {
+        let char_strings: Vec<_> = lengths.iter().map(|&length| {
+            char_string(&[N(0), N(0), Op(RMOVETO), N(length), N(0), Op(RLINETO), Op(ENDCHAR)])
+        }).collect();
+        index(&char_strings)
+    }).collect();
+
+    // The top DICTs only point to the CharStrings, and are the same size whatever the offsets.
+    let name_index = index(&names);
+    let top_dict = |char_strings_offset| {
+        let mut top_dict = dict_int(char_strings_offset);
+        top_dict.push(17);
+        top_dict
+    };
+    let top_dict_index_length = index(&vec![top_dict(0); char_strings.len()]).len();
+    let mut offset = 4 + name_index.len() + top_dict_index_length + 2 * index(&[]).len();
+    let mut top_dicts = vec![];
+    for char_strings in &char_strings {
+        top_dicts.push(top_dict(offset));
+        offset += char_strings.len()
+    }
+
+    let mut cff = vec![1, 0, 4, 4];
+    cff.extend(name_index);
+    cff.extend(index(&top_dicts));
+    cff.extend(index(&[]));
+    cff.extend(index(&[]));
+    for char_strings in char_strings {
+        cff.extend(char_strings)
+    }
+    cff
+}
+
+#[test]
+fn font_set_fonts() {
+    let cff = font_set();
+    let line_end = |table: &CffTable, glyph_id| {
+        let mut points = vec![];
+        table.for_each_point(glyph_id, |point| points.push(point.position)).unwrap();
+        points[1]
+    };
+
+    // The first font is the default.
+    let first = CffTable::from_bytes(&cff).unwrap();
+    assert_eq!(first.font_names(), vec!["First", "Second"]);
+    assert_eq!(first.num_glyphs(), Ok(1));
+    assert_eq!(line_end(&first, 0), Point2D::new(10, 0));
+
+    let second = CffTable::with_font_index(FontTable {
+        bytes: &cff,
+    }, 1).unwrap();
+    assert_eq!(second.font_names(), vec!["First", "Second"]);
+    assert_eq!(second.num_glyphs(), Ok(2));
+    assert_eq!(line_end(&second, 1), Point2D::new(30, 0));
+
+    // Subsets keep only their own font.
+    let subset = second.subset(&[0, 1]).unwrap();
+    let subset = CffTable::from_bytes(&subset).unwrap();
+    assert_eq!(subset.font_names(), vec!["Second"]);
+    assert_eq!(line_end(&subset, 1), Point2D::new(30, 0));
+
+    assert_eq!(CffTable::with_font_index(FontTable {
+        bytes: &cff,
+    }, 2).err(), Some(FontError::FontIndexOutOfBounds));
+}
+
+#[test]
+fn path_segments() {
+    let mut builder = CffBuilder::default();
+    builder.char_strings.push(char_string(&[
+        N(10), N(10), Op(RMOVETO),
+        N(100), N(0), Op(RLINETO),
+        N(0), N(50), N(-50), N(50), N(-50), N(0), Op(RRCURVETO),
+        N(20), Op(HMOVETO),
+        N(0), N(-10), Op(RLINETO),
+        Op(ENDCHAR),
+    ]));
+    let cff = builder.build();
+    let table = CffTable::new(FontTable {
+        bytes: &cff,
+    }).unwrap();
+
+    // The first contour ends away from its start, so it has an implicit closing line; the second
+    // has one line, so closing it returns along that line.
+    let segments: Vec<_> = table.path_segments(0).unwrap().collect();
+    assert_eq!(segments, vec![
+        PathSegment::MoveTo(Point2D::new(10, 10)),
+        PathSegment::LineTo(Point2D::new(110, 10)),
+        PathSegment::CurveTo(Point2D::new(110, 60), Point2D::new(60, 110), Point2D::new(10, 110)),
+        PathSegment::ClosePath,
+        PathSegment::MoveTo(Point2D::new(30, 110)),
+        PathSegment::LineTo(Point2D::new(30, 100)),
+        PathSegment::ClosePath,
+    ]);
+}
+
+#[test]
+fn svg_paths() {
+    let mut builder = CffBuilder::default();
+    builder.char_strings.push(char_string(&[
+        N(10), N(10), Op(RMOVETO),
+        N(100), N(0), Op(RLINETO),
+        N(0), N(50), N(-50), N(50), N(-50), N(0), Op(RRCURVETO),
+        N(20), Op(HMOVETO),
+        N(0), N(-10), Op(RLINETO),
+        Op(ENDCHAR),
+    ]));
+    builder.char_strings.push(char_string(&[Op(ENDCHAR)]));
+    let cff = builder.build();
+    let table = CffTable::new(FontTable {
+        bytes: &cff,
+    }).unwrap();
+
+    // The lines back to the start of each contour are left to `Z`.
+    assert_eq!(table.to_svg_path(0, &SvgPathOptions::default()).unwrap(),
+               "M10 10L110 10C110 60 60 110 10 110ZM30 110L30 100Z");
+
+    let options = SvgPathOptions {
+        scale: 1.0 / 3.0,
+        precision: 1,
+        flip_y: true,
+    };
+    assert_eq!(table.to_svg_path(0, &options).unwrap(),
+               "M3.3 -3.3L36.7 -3.3C36.7 -20 20 -36.7 3.3 -36.7ZM10 -36.7L10 -33.3Z");
+
+    assert_eq!(table.to_svg_path(1, &SvgPathOptions::default()).unwrap(), "");
+}
+
+#[test]
+fn empty_glyphs_have_no_path_segments() {
+    let mut builder = CffBuilder::default();
+    builder.char_strings.push(char_string(&[Op(ENDCHAR)]));
+    let cff = builder.build();
+    let table = CffTable::new(FontTable {
+        bytes: &cff,
+    }).unwrap();
+    assert_eq!(table.path_segments(0).unwrap().next(), None);
+}
+
+#[test]
+fn contours_of_an_i() {
+    // A stem, and a dot above it.
+    let mut builder = CffBuilder::default();
+    builder.char_strings.push(char_string(&[
+        N(100), N(0), Op(RMOVETO),
+        N(80), N(0), N(0), N(500), N(-80), N(0), Op(RLINETO),
+        N(0), N(100), Op(RMOVETO),
+        N(80), N(0), N(0), N(80), N(-80), N(0), Op(RLINETO),
+        Op(ENDCHAR),
+    ]));
+    builder.char_strings.push(char_string(&[Op(ENDCHAR)]));
+    let cff = builder.build();
+    let table = CffTable::new(FontTable {
+        bytes: &cff,
+    }).unwrap();
+    assert_eq!(table.num_contours(0), Ok(2));
+    assert_eq!(table.num_contours(1), Ok(0));
+
+    let mut contours = vec![];
+    table.for_each_contour(0, |contour| contours.push(contour.clone())).unwrap();
+    let positions: Vec<Vec<(i16, i16)>> = contours.iter().map(|contour| {
+        contour.points.iter().map(|point| (point.position.x, point.position.y)).collect()
+    }).collect();
+    assert_eq!(positions, vec![
+        vec![(100, 0), (180, 0), (180, 500), (100, 500), (100, 0)],
+        vec![(100, 600), (180, 600), (180, 680), (100, 680), (100, 600)],
+    ]);
+
+    let mut count = 0;
+    table.for_each_contour(1, |_| count += 1).unwrap();
+    assert_eq!(count, 0);
+}
+
+// Evaluates the curve drawn by `quadratic_builder()` at `t`.
+fn quadratic_builder_cubic(t: f32) -> (f32, f32) {
+    let mt = 1.0 - t;
+    (400.0 * (3.0 * mt * t * t + t * t * t), 400.0 * (3.0 * mt * mt * t + 3.0 * mt * t * t))
+}
+
+fn quadratic_builder() -> CffBuilder {
+    let mut builder = CffBuilder::default();
+    builder.char_strings.push(char_string(&[
+        N(0), N(0), Op(RMOVETO),
+        N(0), N(400), N(400), N(0), N(0), N(-400), Op(RRCURVETO),
+        Op(ENDCHAR),
+    ]));
+    builder
+}
+
+#[test]
+fn quadratic_approximation_stays_within_tolerance() {
+    let cff = quadratic_builder().build();
+    let table = CffTable::new(FontTable {
+        bytes: &cff,
+    }).unwrap();
+
+    let mut quadratic_counts = vec![];
+    for &tolerance in &[50.0, 4.0, 1.0, 0.25] {
+        let mut points = vec![];
+        table.for_each_point_as_quadratic(0, tolerance, |point| points.push(*point)).unwrap();
+        assert!(points.iter().enumerate().all(|(i, point)| point.index_in_contour == i as u16));
+
+        // The start point and the point closing the contour surround the quadratics.
+        assert_eq!((points[0].position.x, points[0].position.y), (0, 0));
+        let quadratics = &points[1..(points.len() - 1)];
+        assert_eq!(quadratics.last().map(|point| (point.position.x, point.position.y)),
+                   Some((400, 0)));
+
+        let count = quadratics.len() / 2;
+        for (i, quadratic) in quadratics.chunks(2).enumerate() {
+            assert_eq!((quadratic[0].kind, quadratic[1].kind),
+                       (PointKind::QuadControl, PointKind::OnCurve));
+            let start = if i == 0 {
+                points[0].position
+            } else {
+                quadratics[i * 2 - 1].position
+            };
+            let (control, end) = (quadratic[0].position, quadratic[1].position);
+
+            // Compare against the cubic at the same parameter. Points are rounded to integers,
+            // which accounts for up to one more unit of error.
+            for step in 0..11 {
+                let s = step as f32 / 10.0;
+                let ms = 1.0 - s;
+                let x = ms * ms * start.x as f32 + 2.0 * ms * s * control.x as f32 +
+                    s * s * end.x as f32;
+                let y = ms * ms * start.y as f32 + 2.0 * ms * s * control.y as f32 +
+                    s * s * end.y as f32;
+                let (cubic_x, cubic_y) = quadratic_builder_cubic((i as f32 + s) / count as f32);
+                let (dx, dy) = (x - cubic_x, y - cubic_y);
+                let error = (dx * dx + dy * dy).sqrt();
+                assert!(error <= tolerance + 1.0, "{} is out of tolerance {}", error, tolerance);
+            }
+        }
+        quadratic_counts.push(count)
+    }
+
+    assert_eq!(quadratic_counts[0], 1);
+    assert!(quadratic_counts.windows(2).all(|counts| counts[0] < counts[1]));
+}
+
+#[test]
+fn transformed_points_follow_the_matrix() {
+    let cff = quadratic_builder().build();
+    let table = CffTable::new(FontTable {
+        bytes: &cff,
+    }).unwrap();
+
+    let transform = Matrix2D::row_major(0.5, 0.0, 0.25, 0.5, 10.0, 0.0);
+    let mut points = vec![];
+    table.for_each_point_transformed(0, &transform, |point| points.push(*point)).unwrap();
+
+    let kinds: Vec<_> = points.iter().map(|point| point.kind).collect();
+    assert_eq!(kinds, vec![
+        PointKind::OnCurve,
+        PointKind::FirstCubicControl,
+        PointKind::SecondCubicControl,
+        PointKind::OnCurve,
+        PointKind::OnCurve,
+    ]);
+    let positions: Vec<_> = points.iter().map(|point| point.position).collect();
+    assert_eq!(positions, vec![
+        Point2D::new(10.0, 0.0),
+        Point2D::new(110.0, 200.0),
+        Point2D::new(310.0, 200.0),
+        Point2D::new(210.0, 0.0),
+        Point2D::new(10.0, 0.0),
+    ]);
+}
+
+#[test]
+fn flattening_stays_within_tolerance() {
+    let cff = quadratic_builder().build();
+    let table = CffTable::new(FontTable {
+        bytes: &cff,
+    }).unwrap();
+
+    let mut point_counts = vec![];
+    for &tolerance in &[10.0, 1.0, 0.1] {
+        let contours = table.flatten(0, tolerance).unwrap();
+        assert_eq!(contours.len(), 1);
+
+        // The on-curve endpoints are kept exactly, and the closing point is implicit.
+        let points = &contours[0].points;
+        assert_eq!(points.first(), Some(&Point2D::new(0.0, 0.0)));
+        assert_eq!(points.last(), Some(&Point2D::new(400.0, 0.0)));
+
+        let count = points.len() - 1;
+        for (i, point) in points.iter().enumerate() {
+            let (x, y) = quadratic_builder_cubic(i as f32 / count as f32);
+            let (dx, dy) = (point.x - x, point.y - y);
+            assert!((dx * dx + dy * dy).sqrt() < 0.01);
+        }
+        point_counts.push(points.len())
+    }
+
+    assert!(point_counts.windows(2).all(|counts| counts[0] < counts[1]));
+}
+
+#[test]
+fn flattening_skips_degenerate_segments() {
+    let mut builder = CffBuilder::default();
+    builder.char_strings.push(char_string(&[
+        N(10), N(10), Op(RMOVETO),
+        N(100), N(0), N(0), N(0), N(0), N(100), Op(RLINETO),
+        N(0), N(0), N(0), N(0), N(0), N(0), Op(RRCURVETO),
+        Op(ENDCHAR),
+    ]));
+    builder.char_strings.push(char_string(&[
+        N(10), N(10), Op(RMOVETO),
+        N(0), N(0), Op(RLINETO),
+        Op(ENDCHAR),
+    ]));
+    let cff = builder.build();
+    let table = CffTable::new(FontTable {
+        bytes: &cff,
+    }).unwrap();
+
+    assert_eq!(table.flatten(0, 1.0), Ok(vec![Contour {
+        points: vec![
+            Point2D::new(10.0, 10.0), Point2D::new(110.0, 10.0), Point2D::new(110.0, 110.0),
+        ],
+    }]));
+    assert_eq!(table.flatten(1, 1.0), Ok(vec![]));
+}
+
+// Glyph `n` moves to (n, 0). Glyphs past 107 need longer operands, so the CharStrings vary in
+// length.
+fn many_glyphs_builder(glyph_count: i32) -> CffBuilder {
+    let mut builder = CffBuilder::default();
+    for glyph_id in 0..glyph_count {
+        builder.char_strings.push(char_string(&[N(glyph_id), N(0), Op(RMOVETO), Op(ENDCHAR)]));
+    }
+    builder
+}
+
+#[test]
+fn char_string_lookup_matches_lone_char_strings() {
+    let cff = many_glyphs_builder(300).build();
+    for &glyph_id in &[299, 0, 108, 107, 200, 1] {
+        let mut lone_builder = CffBuilder::default();
+        lone_builder.char_strings.push(char_string(&[N(glyph_id as i32), N(0), Op(RMOVETO),
+                                                     Op(ENDCHAR)]));
+        let points = glyph_points(&cff, glyph_id).unwrap();
+        assert_eq!(points, glyph_points(&lone_builder.build(), 0).unwrap());
+        assert_eq!(points[0].position.x, glyph_id as i16);
+    }
+    assert_eq!(glyph_points(&cff, 300), Err(FontError::UnexpectedEof));
+}
+
+// Finds a CharString the slow way, by walking the offset array of a CharStrings INDEX from the
+// start.
+fn scan_for_char_string(index: &[u8], glyph_id: u16) -> Option<&[u8]> {
+    let count = ((index[0] as usize) << 8) | index[1] as usize;
+    let off_size = index[2] as usize;
+    let offsets = &index[3..(3 + (count + 1) * off_size)];
+
+    // Offsets count from the byte before the object data.
+    let data_base = 2 + offsets.len();
+    let mut start = 0;
+    for (object_index, offset) in offsets.chunks(off_size).enumerate() {
+        let end = offset.iter().fold(0, |value, &byte| (value << 8) | byte as usize);
+        if object_index == glyph_id as usize + 1 {
+            return Some(&index[(data_base + start)..(data_base + end)])
+        }
+        start = end
+    }
+    None
+}
+
+#[test]
+fn char_string_lookup_matches_scanning_for_each_offset_size() {
+    // Single-byte offsets can only reach 255 bytes of CharStrings.
+    for &(off_size, glyph_count) in &[(1, 50), (2, 300), (3, 300), (4, 300)] {
+        let mut builder = many_glyphs_builder(glyph_count);
+        builder.char_strings_off_size = Some(off_size);
+        let cff = builder.build();
+        let table = CffTable::new(FontTable {
+            bytes: &cff,
+        }).unwrap();
+
+        let char_strings_index = index_with_off_size(&builder.char_strings, off_size);
+        for glyph_id in 0..(glyph_count as u16) {
+            let char_string = table.char_string(glyph_id).unwrap();
+            assert_eq!(Some(char_string), scan_for_char_string(&char_strings_index, glyph_id));
+        }
+        assert_eq!(scan_for_char_string(&char_strings_index, glyph_count as u16), None);
+        assert_eq!(table.char_string(glyph_count as u16), Err(FontError::UnexpectedEof));
+    }
+}
+
+#[test]
+fn out_of_range_index_offsets_are_rejected() {
+    let mut builder = CffBuilder::default();
+    builder.char_strings.push(char_string(&[Op(ENDCHAR)]));
+    builder.char_strings.push(char_string(&[Op(ENDCHAR)]));
+    let cff = builder.build();
+
+    // Find the CharStrings INDEX, whose offsets are four bytes each.
+    let header = [0, 2, 4, 0, 0, 0, 1];
+    let offsets = cff.windows(header.len()).position(|window| window == header).unwrap() + 3;
+    assert_eq!(glyph_points(&cff, 1), Ok(vec![]));
+
+    // An offset past the object data ends one object and starts the next.
+    let mut huge_offset = cff.clone();
+    huge_offset[(offsets + 4)..(offsets + 8)].copy_from_slice(&[0xff, 0xff, 0xff, 0xff]);
+    assert_eq!(glyph_points(&huge_offset, 0), Err(FontError::CffBadOffset));
+    assert_eq!(glyph_points(&huge_offset, 1), Err(FontError::CffBadOffset));
+
+    // Offsets start at 1, so a last offset of 0 leaves no room for the object data.
+    let mut zero_offset = cff.clone();
+    zero_offset[(offsets + 8)..(offsets + 12)].copy_from_slice(&[0, 0, 0, 0]);
+    assert_eq!(glyph_points(&zero_offset, 0), Err(FontError::CffBadOffset));
+}
+
+#[test]
+fn char_strings_past_the_end_of_the_table_are_rejected() {
+    let mut builder = CffBuilder::default();
+    builder.char_strings.push(char_string(&[N(100), N(100), Op(RMOVETO), Op(ENDCHAR)]));
+    let mut cff = builder.build();
+
+    // Make the CharString claim 64K bytes, far more than the rest of the table.
+    let header = [0, 1, 4, 0, 0, 0, 1];
+    let offsets = cff.windows(header.len()).rposition(|window| window == header).unwrap() + 3;
+    cff[(offsets + 4)..(offsets + 8)].copy_from_slice(&[0, 1, 0, 0]);
+    assert_eq!(glyph_points(&cff, 0), Err(FontError::UnexpectedEofIn(ErrorContext {
+        table: cff::TAG,
+        field: "CharStrings INDEX",
+    })));
+}
+
+#[test]
+fn huge_index_counts_are_rejected() {
+    // A Subrs INDEX claiming 65535 subroutines with four-byte offsets runs past the table.
+    let mut builder = CffBuilder::default();
+    builder.char_strings.push(char_string(&[Op(ENDCHAR)]));
+    builder.local_subrs.push(char_string(&[Op(RETURN)]));
+    let mut cff = builder.build();
+    let subrs = cff.len() - index(&builder.local_subrs).len();
+    cff[subrs..(subrs + 2)].copy_from_slice(&[0xff, 0xff]);
+    assert_eq!(glyph_points(&cff, 0), Err(FontError::UnexpectedEofIn(ErrorContext {
+        table: cff::TAG,
+        field: "local subr INDEX",
+    })));
+
+    // So does a Subrs offset far beyond the Private DICT.
+    builder.private_dict_operators = dict_int(0x7fff_ffff);
+    builder.private_dict_operators.push(19);
+    assert_eq!(glyph_points(&builder.build(), 0), Err(FontError::UnexpectedEofIn(ErrorContext {
+        table: cff::TAG,
+        field: "local subr offset",
+    })));
+}
+
+#[test]
+fn predefined_encodings() {
+    let standard_a = cff_encoding::standard_encoding_sid(b'A').unwrap();
+    assert_eq!(STANDARD_STRINGS[standard_a as usize], "A");
+    assert_eq!(cff_encoding::standard_encoding_code(standard_a), Some(b'A'));
+    let quoteright = cff_encoding::standard_encoding_sid(b'\'').unwrap();
+    assert_eq!(STANDARD_STRINGS[quoteright as usize], "quoteright");
+    let germandbls = cff_encoding::standard_encoding_sid(0xfb).unwrap();
+    assert_eq!(STANDARD_STRINGS[germandbls as usize], "germandbls");
+    assert_eq!(cff_encoding::standard_encoding_sid(0), None);
+    assert_eq!(cff_encoding::standard_encoding_sid(0x80), None);
+    assert_eq!(cff_encoding::standard_encoding_code(0), None);
+
+    // The Expert Encoding shares the space and some punctuation, but puts superior letters where
+    // the capitals would be.
+    assert_eq!(cff_encoding::expert_encoding_sid(b' '), Some(1));
+    assert_eq!(cff_encoding::expert_encoding_sid(b','), Some(13));
+    assert_eq!(cff_encoding::expert_encoding_sid(b'A'), Some(253));
+    assert_eq!(STANDARD_STRINGS[253], "asuperior");
+    assert_eq!(cff_encoding::expert_encoding_code(253), Some(b'A'));
+    assert_eq!(cff_encoding::expert_encoding_code(standard_a), None);
+}
+
+#[test]
+fn strings_for_sids() {
+    let mut builder = CffBuilder::default();
+    builder.char_strings.push(char_string(&[Op(ENDCHAR)]));
+    builder.strings = vec![b"Custom".to_vec(), vec![b'C', 0xff]];
+    let cff = builder.build();
+    let table = CffTable::new(FontTable {
+        bytes: &cff,
+    }).unwrap();
+
+    assert_eq!(table.string_for_sid(0).unwrap(), ".notdef");
+    assert_eq!(table.string_for_sid(34).unwrap(), "A");
+    assert_eq!(table.string_for_sid(390).unwrap(), "Semibold");
+    assert_eq!(table.string_for_sid(391).unwrap(), "Custom");
+    assert_eq!(table.string_for_sid(392).unwrap(), "C\u{fffd}");
+    assert_eq!(table.string_for_sid(393), None);
+}
+
+// Times looking up the CharString of the first or last of a thousand glyphs, which should take as
+// long either way.
+fn bench_char_string(bencher: &mut Bencher, last: bool) {
+    let cff = many_glyphs_builder(1000).build();
+    let table = CffTable::new(FontTable {
+        bytes: &cff,
+    }).unwrap();
+    let glyph_id = if last { 999 } else { 0 };
+    bencher.iter(|| table.char_string(glyph_id).unwrap().len());
+}
+
+#[bench]
+fn bench_char_string_of_first_glyph(bencher: &mut Bencher) {
+    bench_char_string(bencher, false)
+}
+
+#[bench]
+fn bench_char_string_of_last_glyph(bencher: &mut Bencher) {
+    bench_char_string(bencher, true)
+}
+
+// The baseline for the benchmarks above: walking the offset array to the last glyph, which takes
+// time proportional to the glyph ID.
+#[bench]
+fn bench_scan_for_char_string_of_last_glyph(bencher: &mut Bencher) {
+    let char_strings_index = index(&many_glyphs_builder(1000).char_strings);
+    bencher.iter(|| scan_for_char_string(&char_strings_index, 999).unwrap().len());
+}
+
+#[bench]
+fn bench_for_each_point(bencher: &mut Bencher) {
+    let cff = many_glyphs_builder(1000).build();
+    let table = CffTable::new(FontTable {
+        bytes: &cff,
+    }).unwrap();
+    let glyph_count = table.num_glyphs().unwrap();
+
+    bencher.iter(|| {
+        let mut point_count = 0;
+        for glyph_id in 0..glyph_count {
+            table.for_each_point(glyph_id, |_| point_count += 1).unwrap()
+        }
+        point_count
+    });
+}
+
+// Four glyphs named `.notdef`, `A`, `B`, and the custom string `Custom`.
+fn named_glyphs_builder(charset: Vec<u8>) -> CffBuilder {
+    let mut builder = CffBuilder::default();
+    for _ in 0..4 {
+        builder.char_strings.push(char_string(&[Op(ENDCHAR)]));
+    }
+    builder.strings = vec![b"Custom".to_vec()];
+    builder.charset = Some(charset);
+    builder
+}
+
+fn assert_glyph_names(cff: &[u8]) {
+    let table = CffTable::new(FontTable {
+        bytes: cff,
+    }).unwrap();
+    assert_eq!(table.glyph_name(0), Some(".notdef"));
+    assert_eq!(table.glyph_name(1), Some("A"));
+    assert_eq!(table.glyph_name(2), Some("B"));
+    assert_eq!(table.glyph_name(3), Some("Custom"));
+    assert_eq!(table.glyph_name(4), None);
+    assert_eq!(table.glyph_for_name("B"), Some(2));
+    assert_eq!(table.glyph_for_name("Custom"), Some(3));
+    assert_eq!(table.glyph_for_name("C"), None);
+    assert_eq!(table.glyph_for_name("Missing"), None);
+}
+
+#[test]
+fn charset_format_0_glyph_names() {
+    // SIDs 34 (`A`), 35 (`B`), and 391 (the first custom string).
+    let cff = named_glyphs_builder(vec![0, 0, 34, 0, 35, 1, 135]).build();
+    assert_glyph_names(&cff);
+}
+
+#[test]
+fn charset_format_1_glyph_names() {
+    // A range of SIDs 34-35, then SID 391.
+    let cff = named_glyphs_builder(vec![1, 0, 34, 1, 1, 135, 0]).build();
+    assert_glyph_names(&cff);
+}
+
+#[test]
+fn cid_keyed_fonts_have_no_glyph_names() {
+    let cff = cid_keyed_builder(vec![0, 2, 1, 1, 0]).build();
+    let table = CffTable::new(FontTable {
+        bytes: &cff,
+    }).unwrap();
+    assert_eq!(table.glyph_name(1), None);
+}
+
+#[test]
+fn standard_encoding_is_the_default() {
+    let mut builder = CffBuilder::default();
+    for _ in 0..40 {
+        builder.char_strings.push(char_string(&[Op(ENDCHAR)]));
+    }
+    let cff = builder.build();
+    let table = CffTable::new(FontTable {
+        bytes: &cff,
+    }).unwrap();
+
+    // `A` is SID 34, which is glyph 34 in the ISOAdobe charset.
+    assert_eq!(table.glyph_for_code(b'A'), Some(34));
+    assert_eq!(table.glyph_for_code(b'z'), None);
+    assert_eq!(table.glyph_for_code(0), None);
+}
+
+#[test]
+fn custom_encoding_format_0() {
+    let mut builder = named_glyphs_builder(vec![0, 0, 34, 0, 35, 1, 135]);
+    builder.encoding = Some(vec![0, 3, b'x', b'y', b'z']);
+    let cff = builder.build();
+    let table = CffTable::new(FontTable {
+        bytes: &cff,
+    }).unwrap();
+    assert_eq!(table.glyph_for_code(b'x'), Some(1));
+    assert_eq!(table.glyph_for_code(b'y'), Some(2));
+    assert_eq!(table.glyph_for_code(b'z'), Some(3));
+    assert_eq!(table.glyph_for_code(b'A'), None);
+}
+
+#[test]
+fn custom_encoding_format_1_with_supplements() {
+    // Codes `a`-`c` cover glyphs 1-3, and the supplement also maps `@` to `B` (SID 35).
+    let mut builder = named_glyphs_builder(vec![0, 0, 34, 0, 35, 1, 135]);
+    builder.encoding = Some(vec![0x81, 1, b'a', 2, 1, b'@', 0, 35]);
+    let cff = builder.build();
+    let table = CffTable::new(FontTable {
+        bytes: &cff,
+    }).unwrap();
+    assert_eq!(table.glyph_for_code(b'a'), Some(1));
+    assert_eq!(table.glyph_for_code(b'c'), Some(3));
+    assert_eq!(table.glyph_for_code(b'd'), None);
+    assert_eq!(table.glyph_for_code(b'@'), Some(2));
+}
+
+fn cff2_index(objects: &[Vec<u8>]) -> Vec<u8> {
+    let mut bytes = vec![0, 0];
+    bytes.extend(index(objects));
+    bytes
+}
+
+// Assembles a CFF2 table with one axis and two sets of item variation data: set 0 uses a region
+// peaking at 1.0, and set 1 uses that region and one peaking at -1.0.
+fn cff2_table(char_strings: &[Vec<u8>]) -> Vec<u8> {
+    let mut store = vec![0, 1, 0, 0, 0, 16, 0, 2, 0, 0, 0, 32, 0, 0, 0, 40];
+    // Region list.
+    store.extend_from_slice(&[0, 1, 0, 2]);
+    store.extend_from_slice(&[0, 0, 0x40, 0, 0x40, 0]);
+    store.extend_from_slice(&[0xc0, 0, 0xc0, 0, 0, 0]);
+    // Item variation data.
+    store.extend_from_slice(&[0, 0, 0, 0, 0, 1, 0, 0]);
+    store.extend_from_slice(&[0, 0, 0, 0, 0, 2, 0, 0, 0, 1]);
+    let mut variation_store = vec![0, store.len() as u8];
+    variation_store.extend(store);
+
+    // The top DICT has three five-byte offsets and their operators.
+    let global_subr_index = cff2_index(&[]);
+    let variation_store_offset = 5 + 19 + global_subr_index.len();
+    let char_strings_offset = variation_store_offset + variation_store.len();
+    let char_strings_index = cff2_index(char_strings);
+    let fd_array_offset = char_strings_offset + char_strings_index.len();
+    let mut font_dict = dict_int(0);
+    font_dict.extend(dict_int(fd_array_offset + cff2_index(&[vec![0; 11]]).len()));
+    font_dict.push(18);
+
+    let mut top_dict = dict_int(char_strings_offset);
+    top_dict.push(17);
+    top_dict.extend(dict_int(fd_array_offset));
+    top_dict.extend_from_slice(&[12, 36]);
+    top_dict.extend(dict_int(variation_store_offset));
+    top_dict.push(24);
+
+    let mut cff2 = vec![2, 0, 5, 0, top_dict.len() as u8];
+    cff2.extend(top_dict);
+    cff2.extend(global_subr_index);
+    cff2.extend(variation_store);
+    cff2.extend(char_strings_index);
+    cff2.extend(cff2_index(&[font_dict]));
+    cff2
+}
+
+fn cff2_glyph_positions(cff2: &[u8], glyph_id: u16, coordinates: &[f32]) -> Vec<(i16, i16)> {
+    let table = CffTable::new(FontTable {
+        bytes: cff2,
+    }).unwrap();
+    let mut positions = vec![];
+    table.for_each_point_with_variations(glyph_id, coordinates, |point| {
+        positions.push((point.position.x, point.position.y))
+    }).unwrap();
+    positions
+}
+
+// Moves to (10, 20) at the default position and (110, -30) at the peak of region 0, then draws a
+// line that doesn't vary. There's no `endchar` in CFF2.
+fn cff2_blended_char_string() -> Vec<u8> {
+    char_string(&[
+        N(10), N(20), N(100), N(-50), N(2), Op(BLEND), Op(RMOVETO),
+        N(100), N(0), Op(RLINETO),
+    ])
+}
+
+#[test]
+fn cff2_default_outline() {
+    let cff2 = cff2_table(&[cff2_blended_char_string()]);
+    let table = CffTable::new(FontTable {
+        bytes: &cff2,
+    }).unwrap();
+    assert_eq!(table.num_glyphs(), Ok(1));
+
+    let mut points = vec![];
+    table.for_each_point(0, |point| points.push((point.position.x, point.position.y))).unwrap();
+    assert_eq!(points, vec![(10, 20), (110, 20), (10, 20)]);
+    assert_eq!(cff2_glyph_positions(&cff2, 0, &[0.0]), points);
+    assert_eq!(cff2_glyph_positions(&cff2, 0, &[-1.0]), points);
+}
+
+#[test]
+fn cff2_blend_at_axis_positions() {
+    let cff2 = cff2_table(&[cff2_blended_char_string()]);
+    assert_eq!(cff2_glyph_positions(&cff2, 0, &[1.0]), vec![(110, -30), (210, -30), (110, -30)]);
+    assert_eq!(cff2_glyph_positions(&cff2, 0, &[0.5]), vec![(60, -5), (160, -5), (60, -5)]);
+}
+
+#[test]
+fn cff2_vsindex_selects_item_variation_data() {
+    let cff2 = cff2_table(&[char_string(&[
+        N(1), Op(VSINDEX),
+        N(10), N(20), N(100), N(-100), N(0), N(0), N(2), Op(BLEND), Op(RMOVETO),
+    ])]);
+    assert_eq!(cff2_glyph_positions(&cff2, 0, &[1.0])[0], (110, 20));
+    assert_eq!(cff2_glyph_positions(&cff2, 0, &[-1.0])[0], (-90, 20));
+}
+
+#[test]
+fn blend_is_rejected_in_cff() {
+    let mut builder = CffBuilder::default();
+    builder.char_strings.push(char_string(&[N(10), N(100), N(1), Op(BLEND), Op(ENDCHAR)]));
+    assert_eq!(glyph_points(&builder.build(), 0), Err(FontError::CffUnimplementedOperator));
+}
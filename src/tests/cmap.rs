@@ -0,0 +1,184 @@
+/* Any copyright is dedicated to the Public Domain.
+ * http://creativecommons.org/publicdomain/zero/1.0/ */
+
+use font::{Font, FontTable};
+use memmap::{Mmap, Protection};
+use tables::cmap::CmapTable;
+use util::{push_u16, push_u32};
+
+static TEST_FONT_PATH: &'static str = "resources/tests/nimbus-sans/NimbusSanL-Regu.ttf";
+
+// Builds a `cmap` table with the given encoding records, in order, each pointing to the given
+// subtable.
+fn cmap(subtables: &[(u16, u16, Vec<u8>)]) -> Vec<u8> {
+    let mut bytes = vec![];
+    push_u16(&mut bytes, 0);
+    push_u16(&mut bytes, subtables.len() as u16);
+
+    let mut offset = 4 + 8 * subtables.len();
+    for &(platform_id, encoding_id, ref subtable) in subtables {
+        push_u16(&mut bytes, platform_id);
+        push_u16(&mut bytes, encoding_id);
+        push_u32(&mut bytes, offset as u32);
+        offset += subtable.len();
+    }
+    for &(_, _, ref subtable) in subtables {
+        bytes.extend_from_slice(subtable)
+    }
+    bytes
+}
+
+// A format 4 subtable mapping "ABC" to glyphs 1-3.
+fn format_4() -> Vec<u8> {
+    let mut bytes = vec![];
+    for &value in &[4, 32, 0, 4, 4, 1, 0, 0x43, 0xffff, 0, 0x41, 0xffff, (1 - 0x41) as u16, 1, 0,
+                    0] {
+        push_u16(&mut bytes, value)
+    }
+    bytes
+}
+
+// A format 12 subtable mapping "ABC" to glyphs 4-6 and U+1F600 to glyph 7.
+fn format_12() -> Vec<u8> {
+    let mut bytes = vec![];
+    push_u16(&mut bytes, 12);
+    push_u16(&mut bytes, 0);
+    for &value in &[40, 0, 2, 0x41, 0x43, 4, 0x1f600, 0x1f600, 7] {
+        push_u32(&mut bytes, value)
+    }
+    bytes
+}
+
+fn push_u24(bytes: &mut Vec<u8>, value: u32) {
+    bytes.extend_from_slice(&[(value >> 16) as u8, (value >> 8) as u8, value as u8])
+}
+
+// A format 14 subtable mapping U+1F600 with the text selector U+FE0E to glyph 8, and listing it
+// with the emoji selector U+FE0F as a default variation sequence.
+fn format_14() -> Vec<u8> {
+    let mut bytes = vec![];
+    push_u16(&mut bytes, 14);
+    push_u32(&mut bytes, 49);
+    push_u32(&mut bytes, 2);
+
+    // Variation selector records.
+    push_u24(&mut bytes, 0xfe0e);
+    push_u32(&mut bytes, 0);
+    push_u32(&mut bytes, 32);
+    push_u24(&mut bytes, 0xfe0f);
+    push_u32(&mut bytes, 41);
+    push_u32(&mut bytes, 0);
+
+    // Non-default UVS table for U+FE0E.
+    push_u32(&mut bytes, 1);
+    push_u24(&mut bytes, 0x1f600);
+    push_u16(&mut bytes, 8);
+
+    // Default UVS table for U+FE0F.
+    push_u32(&mut bytes, 1);
+    push_u24(&mut bytes, 0x1f600);
+    bytes.push(0);
+    bytes
+}
+
+#[test]
+fn glyph_for_char_format_4() {
+    let bytes = cmap(&[(3, 1, format_4())]);
+    let cmap = CmapTable::new(FontTable {
+        bytes: &bytes,
+    });
+    assert_eq!(cmap.glyph_for_char('A'), Ok(Some(1)));
+    assert_eq!(cmap.glyph_for_char('C'), Ok(Some(3)));
+    assert_eq!(cmap.glyph_for_char('D'), Ok(None));
+    assert_eq!(cmap.glyph_for_char('\u{1f600}'), Ok(None));
+}
+
+// The 3/10 table should be chosen even though the BMP-only one comes first.
+#[test]
+fn glyph_for_char_prefers_full_unicode_subtable() {
+    let bytes = cmap(&[(3, 1, format_4()), (3, 10, format_12())]);
+    let cmap = CmapTable::new(FontTable {
+        bytes: &bytes,
+    });
+    assert_eq!(cmap.glyph_for_char('B'), Ok(Some(5)));
+    assert_eq!(cmap.glyph_for_char('\u{1f600}'), Ok(Some(7)));
+    assert_eq!(cmap.glyph_for_char('\u{1f601}'), Ok(None));
+}
+
+#[test]
+fn glyph_for_char_ascii() {
+    let file = Mmap::open_path(TEST_FONT_PATH, Protection::Read).expect("Couldn't open test font");
+    let mut buffer = vec![];
+    let font = Font::new(unsafe { file.as_slice() }, &mut buffer).unwrap();
+    for character in (b'!'..(b'~' + 1)).map(|byte| byte as char) {
+        assert!(font.glyph_for_char(character).unwrap().is_some())
+    }
+}
+
+#[test]
+fn glyph_for_char_variant_format_14() {
+    let bytes = cmap(&[(0, 5, format_14()), (3, 10, format_12())]);
+    let cmap = CmapTable::new(FontTable {
+        bytes: &bytes,
+    });
+    assert_eq!(cmap.glyph_for_char('\u{1f600}'), Ok(Some(7)));
+    assert_eq!(cmap.glyph_for_char_variant('\u{1f600}', '\u{fe0e}'), Ok(Some(8)));
+    assert_eq!(cmap.glyph_for_char_variant('\u{1f600}', '\u{fe0f}'), Ok(Some(7)));
+    assert_eq!(cmap.glyph_for_char_variant('A', '\u{fe0f}'), Ok(None));
+    assert_eq!(cmap.glyph_for_char_variant('\u{1f600}', '\u{fe00}'), Ok(None));
+}
+
+#[test]
+fn glyph_for_char_variant_without_format_14() {
+    let bytes = cmap(&[(3, 10, format_12())]);
+    let cmap = CmapTable::new(FontTable {
+        bytes: &bytes,
+    });
+    assert_eq!(cmap.glyph_for_char_variant('\u{1f600}', '\u{fe0e}'), Ok(None));
+}
+
+// Both 'A' and 'a' map to glyph 1, the latter through the glyph ID array.
+#[test]
+fn build_reverse_format_4_prefers_lowest_codepoint() {
+    let mut subtable = vec![];
+    for &value in &[4, 42, 0, 6, 4, 1, 2, 0x43, 0x61, 0xffff, 0, 0x41, 0x61, 0xffff,
+                    (1 - 0x41) as u16, 0, 1, 0, 4, 0, 1] {
+        push_u16(&mut subtable, value)
+    }
+    let bytes = cmap(&[(3, 1, subtable)]);
+    let cmap = CmapTable::new(FontTable {
+        bytes: &bytes,
+    });
+    assert_eq!(cmap.glyph_for_char('a'), Ok(Some(1)));
+
+    let reverse = cmap.build_reverse().unwrap();
+    assert_eq!(reverse.len(), 3);
+    assert_eq!(reverse.get(&1), Some(&'A'));
+    assert_eq!(reverse.get(&2), Some(&'B'));
+    assert_eq!(reverse.get(&3), Some(&'C'));
+}
+
+#[test]
+fn build_reverse_format_12() {
+    let bytes = cmap(&[(3, 10, format_12())]);
+    let cmap = CmapTable::new(FontTable {
+        bytes: &bytes,
+    });
+    let reverse = cmap.build_reverse().unwrap();
+    assert_eq!(reverse.len(), 4);
+    assert_eq!(reverse.get(&4), Some(&'A'));
+    assert_eq!(reverse.get(&6), Some(&'C'));
+    assert_eq!(reverse.get(&7), Some(&'\u{1f600}'));
+    assert_eq!(reverse.get(&0), None);
+}
+
+#[test]
+fn glyph_to_char_map_round_trips() {
+    let file = Mmap::open_path(TEST_FONT_PATH, Protection::Read).expect("Couldn't open test font");
+    let mut buffer = vec![];
+    let font = Font::new(unsafe { file.as_slice() }, &mut buffer).unwrap();
+    let reverse = font.glyph_to_char_map().unwrap();
+    for (&glyph_id, &character) in &reverse {
+        assert_eq!(font.glyph_for_char(character).unwrap(), Some(glyph_id))
+    }
+}
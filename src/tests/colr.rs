@@ -0,0 +1,114 @@
+/* Any copyright is dedicated to the Public Domain.
+ * http://creativecommons.org/publicdomain/zero/1.0/ */
+
+use error::FontError;
+use font::{ColorIndex, FontTable};
+use tables::colr::ColrTable;
+use tables::cpal::CpalTable;
+use util::{push_u16, push_u32};
+
+const GLYPH_SMILEY: u16 = 10;
+const GLYPH_SMILEY_FACE: u16 = 11;
+const GLYPH_SMILEY_EYES: u16 = 12;
+const GLYPH_HEART: u16 = 20;
+const GLYPH_HEART_SHAPE: u16 = 21;
+const GLYPH_PLAIN: u16 = 30;
+
+// A smiley with a face in palette entry 0 and eyes in the text color, and a heart in palette
+// entry 1.
+fn colr_table_bytes(version: u16) -> Vec<u8> {
+    let mut bytes = vec![];
+    push_u16(&mut bytes, version);
+    push_u16(&mut bytes, 2);
+    push_u32(&mut bytes, 14);
+    push_u32(&mut bytes, 26);
+    push_u16(&mut bytes, 3);
+
+    for &value in &[GLYPH_SMILEY, 0, 2, GLYPH_HEART, 2, 1] {
+        push_u16(&mut bytes, value)
+    }
+    for &value in &[GLYPH_SMILEY_FACE, 0, GLYPH_SMILEY_EYES, 0xffff, GLYPH_HEART_SHAPE, 1] {
+        push_u16(&mut bytes, value)
+    }
+    bytes
+}
+
+// Two palettes of two entries each: yellow and red, then a translucent gray and pink.
+fn cpal_table_bytes() -> Vec<u8> {
+    let mut bytes = vec![];
+    for &value in &[0, 2, 2, 4] {
+        push_u16(&mut bytes, value)
+    }
+    push_u32(&mut bytes, 16);
+    push_u16(&mut bytes, 0);
+    push_u16(&mut bytes, 2);
+
+    // The color records, in BGRA order.
+    bytes.extend_from_slice(&[0x00, 0xff, 0xff, 0xff]);
+    bytes.extend_from_slice(&[0x00, 0x00, 0xff, 0xff]);
+    bytes.extend_from_slice(&[0x80, 0x80, 0x80, 0x80]);
+    bytes.extend_from_slice(&[0xcb, 0xc0, 0xff, 0xff]);
+    bytes
+}
+
+#[test]
+fn two_layer_color_glyph() {
+    let colr_bytes = colr_table_bytes(0);
+    let colr = ColrTable::new(FontTable {
+        bytes: &colr_bytes,
+    }).unwrap();
+    let cpal_bytes = cpal_table_bytes();
+    let cpal = CpalTable::new(FontTable {
+        bytes: &cpal_bytes,
+    }).unwrap();
+
+    let layers = colr.color_layers(GLYPH_SMILEY).unwrap();
+    assert_eq!(layers, vec![(GLYPH_SMILEY_FACE, ColorIndex::Palette(0)),
+                            (GLYPH_SMILEY_EYES, ColorIndex::Foreground)]);
+
+    let colors: Vec<Option<[u8; 4]>> = layers.iter().map(|&(_, color)| {
+        match color {
+            ColorIndex::Palette(index) => cpal.palette_color(0, index),
+            ColorIndex::Foreground => None,
+        }
+    }).collect();
+    assert_eq!(colors, vec![Some([0xff, 0xff, 0x00, 0xff]), None]);
+
+    assert_eq!(colr.color_layers(GLYPH_HEART),
+               Some(vec![(GLYPH_HEART_SHAPE, ColorIndex::Palette(1))]));
+    assert_eq!(colr.color_layers(GLYPH_PLAIN), None);
+    assert_eq!(colr.color_layers(GLYPH_SMILEY_FACE), None);
+}
+
+#[test]
+fn palettes() {
+    let bytes = cpal_table_bytes();
+    let cpal = CpalTable::new(FontTable {
+        bytes: &bytes,
+    }).unwrap();
+    assert_eq!(cpal.num_palettes(), 2);
+    assert_eq!(cpal.palette_color(0, 1), Some([0xff, 0x00, 0x00, 0xff]));
+    assert_eq!(cpal.palette_color(1, 0), Some([0x80, 0x80, 0x80, 0x80]));
+    assert_eq!(cpal.palette_color(1, 1), Some([0xff, 0xc0, 0xcb, 0xff]));
+    assert_eq!(cpal.palette_color(0, 2), None);
+    assert_eq!(cpal.palette_color(2, 0), None);
+}
+
+#[test]
+fn version_1_layers() {
+    let bytes = colr_table_bytes(1);
+    let colr = ColrTable::new(FontTable {
+        bytes: &bytes,
+    }).unwrap();
+    assert_eq!(colr.color_layers(GLYPH_HEART),
+               Some(vec![(GLYPH_HEART_SHAPE, ColorIndex::Palette(1))]));
+}
+
+#[test]
+fn unsupported_version() {
+    let bytes = colr_table_bytes(2);
+    let colr = ColrTable::new(FontTable {
+        bytes: &bytes,
+    });
+    assert_eq!(colr.err(), Some(FontError::UnsupportedColrVersion));
+}
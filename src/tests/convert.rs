@@ -0,0 +1,77 @@
+/* Any copyright is dedicated to the Public Domain.
+ * http://creativecommons.org/publicdomain/zero/1.0/ */
+
+use euclid::Point2D;
+use font::{Font, Outline, Point, PointKind, SvgPathOptions};
+#[cfg(feature = "kurbo")]
+use kurbo::{self, BezPath};
+#[cfg(feature = "lyon")]
+use lyon::math;
+#[cfg(feature = "lyon")]
+use lyon::path::Path;
+#[cfg(feature = "lyon")]
+use lyon::path::builder::{FlatPathBuilder, PathBuilder};
+use memmap::{Mmap, Protection};
+
+static TEST_FONT_PATH: &'static str = "resources/tests/nimbus-sans/NimbusSanL-Regu.ttf";
+
+// Returns the outline of "O" in the test font, and the number of commands in its SVG path, which
+// has the same moves, lines, curves, and closes as the converted paths.
+fn outline_and_command_count() -> (Outline, usize) {
+    let file = Mmap::open_path(TEST_FONT_PATH, Protection::Read).expect("Couldn't open test font");
+    let mut buffer = vec![];
+    let font = Font::new(unsafe { file.as_slice() }, &mut buffer).unwrap();
+    let glyph_id = font.glyph_for_char('O').unwrap().unwrap();
+    let path = font.to_svg_path(glyph_id, &SvgPathOptions::default()).unwrap();
+    let command_count = path.chars().filter(|character| "MLQCZ".contains(*character)).count();
+    assert!(command_count > 0);
+    (font.outline(glyph_id).unwrap(), command_count)
+}
+
+// A bowtie that passes back through its start in the middle, then ends there.
+fn bowtie() -> Outline {
+    let positions = [(0, 0), (10, 10), (0, 0), (10, -10), (0, 0)];
+    Outline::from_points(positions.iter().enumerate().map(|(index, &(x, y))| {
+        Point {
+            position: Point2D::new(x, y),
+            index_in_contour: index as u16,
+            kind: PointKind::OnCurve,
+        }
+    }))
+}
+
+#[cfg(feature = "lyon")]
+#[test]
+fn outlines_convert_to_lyon_paths() {
+    let (outline, command_count) = outline_and_command_count();
+    let path = Path::from(&outline);
+    assert_eq!(path.iter().count(), command_count);
+
+    // Only the final line back to the start is left to the close.
+    let mut builder = Path::builder();
+    builder.move_to(math::point(0.0, 0.0));
+    builder.line_to(math::point(10.0, 10.0));
+    builder.line_to(math::point(0.0, 0.0));
+    builder.line_to(math::point(10.0, -10.0));
+    builder.close();
+    let expected = builder.build();
+    assert_eq!(Path::from(&bowtie()).iter().collect::<Vec<_>>(),
+               expected.iter().collect::<Vec<_>>());
+}
+
+#[cfg(feature = "kurbo")]
+#[test]
+fn outlines_convert_to_kurbo_paths() {
+    let (outline, command_count) = outline_and_command_count();
+    let path = BezPath::from(&outline);
+    assert_eq!(path.elements().len(), command_count);
+
+    // Only the final line back to the start is left to the close.
+    let mut expected = BezPath::new();
+    expected.move_to(kurbo::Point::new(0.0, 0.0));
+    expected.line_to(kurbo::Point::new(10.0, 10.0));
+    expected.line_to(kurbo::Point::new(0.0, 0.0));
+    expected.line_to(kurbo::Point::new(10.0, -10.0));
+    expected.close_path();
+    assert_eq!(BezPath::from(&bowtie()).elements(), expected.elements());
+}
@@ -0,0 +1,64 @@
+/* Any copyright is dedicated to the Public Domain.
+ * http://creativecommons.org/publicdomain/zero/1.0/ */
+
+use error::FontError;
+use font::FontTable;
+use tables::dsig::DsigTable;
+use util::{push_u16, push_u32};
+
+// A version 1 table with a signature block of the given length for each record.
+fn dsig_table_bytes(block_lengths: &[u32]) -> Vec<u8> {
+    let mut bytes = vec![];
+    push_u32(&mut bytes, 1);
+    push_u16(&mut bytes, block_lengths.len() as u16);
+    push_u16(&mut bytes, 1);
+
+    let mut offset = 8 + 12 * block_lengths.len() as u32;
+    for &length in block_lengths {
+        push_u32(&mut bytes, 1);
+        push_u32(&mut bytes, length);
+        push_u32(&mut bytes, offset);
+        offset += length
+    }
+    for &length in block_lengths {
+        bytes.extend(vec![0; length as usize])
+    }
+    bytes
+}
+
+fn dsig_table(bytes: &[u8]) -> Result<DsigTable, FontError> {
+    DsigTable::new(FontTable {
+        bytes: bytes,
+    })
+}
+
+#[test]
+fn signed_table() {
+    let bytes = dsig_table_bytes(&[24]);
+    let dsig = dsig_table(&bytes).unwrap();
+    assert_eq!((dsig.version(), dsig.num_signatures(), dsig.flags()), (1, 1, 1));
+    assert!(dsig.has_signature_block());
+}
+
+#[test]
+fn null_signature_table() {
+    let dsig = dsig_table(&dsig_table_bytes(&[])).unwrap();
+    assert_eq!(dsig.num_signatures(), 0);
+    assert!(!dsig.has_signature_block());
+
+    // A record whose block lies outside the table doesn't count either.
+    let mut bytes = dsig_table_bytes(&[24]);
+    let length = bytes.len();
+    bytes.truncate(length - 24);
+    assert!(!dsig_table(&bytes).unwrap().has_signature_block());
+}
+
+#[test]
+fn malformed_tables() {
+    let mut bytes = dsig_table_bytes(&[]);
+    bytes[3] = 2;
+    assert_eq!(dsig_table(&bytes).err(), Some(FontError::UnsupportedDsigVersion));
+
+    let bytes = dsig_table_bytes(&[24]);
+    assert!(dsig_table(&bytes[0..12]).is_err());
+}
@@ -0,0 +1,181 @@
+/* Any copyright is dedicated to the Public Domain.
+ * http://creativecommons.org/publicdomain/zero/1.0/ */
+
+use error::{ErrorContext, FontError};
+use font::{BigGlyphMetrics, BitmapMetrics, BitmapStrike, EmbeddedBitmap, FontTable};
+use font::SmallGlyphMetrics;
+use tables::cblc::CblcTable;
+use tables::ebdt::{self, EbdtTable};
+use util::{push_u16, push_u32};
+
+const EBDT_HEADER_SIZE: u32 = 4;
+
+// A 5 by 3 pixel "T", byte-aligned and bit-aligned.
+const BYTE_ALIGNED_T: [u8; 3] = [0xf8, 0x20, 0x20];
+const BIT_ALIGNED_T: [u8; 2] = [0xf9, 0x08];
+
+const SMALL_GLYPH_METRICS: [u8; 5] = [3, 5, 0, 3, 6];
+const BIG_GLYPH_METRICS: [u8; 8] = [3, 5, 0, 3, 6, 253, 0, 4];
+
+// A format 1 index subtable for one glyph in the given image format.
+fn index_subtable_format_1(image_format: u16, image_data_offset: u32, length: u32) -> Vec<u8> {
+    let mut bytes = vec![];
+    push_u16(&mut bytes, 1);
+    push_u16(&mut bytes, image_format);
+    push_u32(&mut bytes, image_data_offset);
+    push_u32(&mut bytes, 0);
+    push_u32(&mut bytes, length);
+    bytes
+}
+
+// Builds a monochrome strike at 12 pixels per em. Glyph 1 is in image format 1 (small metrics,
+// byte-aligned), glyph 2 in format 2 (small metrics, bit-aligned), and glyph 3 in format 5
+// (bit-aligned, with metrics in a format 2 index subtable).
+fn monochrome_bitmap_tables(glyph_1_height: u8) -> (Vec<u8>, Vec<u8>) {
+    let mut glyph_1 = SMALL_GLYPH_METRICS.to_vec();
+    glyph_1[0] = glyph_1_height;
+    glyph_1.extend_from_slice(&BYTE_ALIGNED_T);
+    let mut glyph_2 = SMALL_GLYPH_METRICS.to_vec();
+    glyph_2.extend_from_slice(&BIT_ALIGNED_T);
+    let glyph_3 = BIT_ALIGNED_T.to_vec();
+
+    let mut ebdt = vec![];
+    push_u16(&mut ebdt, 2);
+    push_u16(&mut ebdt, 0);
+    for glyph in &[&glyph_1, &glyph_2, &glyph_3] {
+        ebdt.extend_from_slice(glyph)
+    }
+
+    let glyph_2_offset = EBDT_HEADER_SIZE + glyph_1.len() as u32;
+    let glyph_3_offset = glyph_2_offset + glyph_2.len() as u32;
+    let index_subtable_1 = index_subtable_format_1(1, EBDT_HEADER_SIZE, glyph_1.len() as u32);
+    let index_subtable_2 = index_subtable_format_1(2, glyph_2_offset, glyph_2.len() as u32);
+    let mut index_subtable_3 = vec![];
+    push_u16(&mut index_subtable_3, 2);
+    push_u16(&mut index_subtable_3, 5);
+    push_u32(&mut index_subtable_3, glyph_3_offset);
+    push_u32(&mut index_subtable_3, glyph_3.len() as u32);
+    index_subtable_3.extend_from_slice(&BIG_GLYPH_METRICS);
+
+    // Header and bitmap size record.
+    let mut eblc = vec![];
+    push_u16(&mut eblc, 2);
+    push_u16(&mut eblc, 0);
+    push_u32(&mut eblc, 1);
+    push_u32(&mut eblc, 56);
+    push_u32(&mut eblc, (24 + index_subtable_1.len() * 2 + index_subtable_3.len()) as u32);
+    push_u32(&mut eblc, 3);
+    push_u32(&mut eblc, 0);
+    eblc.extend_from_slice(&[0; 24]);
+    push_u16(&mut eblc, 1);
+    push_u16(&mut eblc, 3);
+    eblc.extend_from_slice(&[12, 12, 1, 1]);
+
+    // Index subtable array.
+    let mut offset = 24;
+    for (glyph_id, index_subtable) in (1..).zip(&[&index_subtable_1,
+                                                  &index_subtable_2,
+                                                  &index_subtable_3]) {
+        push_u16(&mut eblc, glyph_id);
+        push_u16(&mut eblc, glyph_id);
+        push_u32(&mut eblc, offset);
+        offset += index_subtable.len() as u32
+    }
+    for index_subtable in &[&index_subtable_1, &index_subtable_2, &index_subtable_3] {
+        eblc.extend_from_slice(index_subtable)
+    }
+
+    (eblc, ebdt)
+}
+
+// Unpacks a monochrome bitmap into rows of "#" for set pixels and "." for clear ones.
+fn rows(bitmap: &EmbeddedBitmap) -> Vec<String> {
+    let (width, height) = (bitmap.metrics.width() as usize, bitmap.metrics.height() as usize);
+    let row_bits = if bitmap.bit_aligned { width } else { (width + 7) / 8 * 8 };
+    (0..height).map(|y| {
+        (0..width).map(|x| {
+            let bit = y * row_bits + x;
+            if bitmap.data[bit / 8] & (0x80 >> (bit % 8)) != 0 { '#' } else { '.' }
+        }).collect()
+    }).collect()
+}
+
+#[test]
+fn bitmap_strikes() {
+    let (eblc_bytes, _) = monochrome_bitmap_tables(3);
+    let eblc = CblcTable::new(FontTable {
+        bytes: &eblc_bytes,
+    }).unwrap();
+    assert_eq!(eblc.bitmap_strikes(), Ok(vec![BitmapStrike {
+        ppem: 12,
+        resolution: 72,
+    }]));
+}
+
+#[test]
+fn byte_and_bit_aligned_glyphs() {
+    let (eblc_bytes, ebdt_bytes) = monochrome_bitmap_tables(3);
+    let eblc = CblcTable::new(FontTable {
+        bytes: &eblc_bytes,
+    }).unwrap();
+    let ebdt = EbdtTable::new(FontTable {
+        bytes: &ebdt_bytes,
+    }).unwrap();
+    let small_metrics = BitmapMetrics::Small(SmallGlyphMetrics {
+        height: 3,
+        width: 5,
+        bearing_x: 0,
+        bearing_y: 3,
+        advance: 6,
+    });
+
+    let byte_aligned = ebdt.glyph_bitmap(&eblc, 1, 12).unwrap().unwrap();
+    assert_eq!(byte_aligned, EmbeddedBitmap {
+        metrics: small_metrics,
+        bit_depth: 1,
+        bit_aligned: false,
+        data: &BYTE_ALIGNED_T,
+    });
+    assert_eq!(rows(&byte_aligned), vec!["#####", "..#..", "..#.."]);
+
+    let bit_aligned = ebdt.glyph_bitmap(&eblc, 2, 12).unwrap().unwrap();
+    assert_eq!(bit_aligned, EmbeddedBitmap {
+        metrics: small_metrics,
+        bit_depth: 1,
+        bit_aligned: true,
+        data: &BIT_ALIGNED_T,
+    });
+    assert_eq!(rows(&bit_aligned), rows(&byte_aligned));
+
+    // Format 5 glyphs take their metrics from the index subtable.
+    let shared_metrics = ebdt.glyph_bitmap(&eblc, 3, 12).unwrap().unwrap();
+    assert_eq!(shared_metrics.metrics, BitmapMetrics::Big(BigGlyphMetrics {
+        height: 3,
+        width: 5,
+        hori_bearing_x: 0,
+        hori_bearing_y: 3,
+        hori_advance: 6,
+        vert_bearing_x: -3,
+        vert_bearing_y: 0,
+        vert_advance: 4,
+    }));
+    assert_eq!(rows(&shared_metrics), rows(&byte_aligned));
+
+    assert_eq!(ebdt.glyph_bitmap(&eblc, 0, 12), Ok(None));
+    assert_eq!(ebdt.glyph_bitmap(&eblc, 4, 12), Ok(None));
+}
+
+#[test]
+fn truncated_bitmaps_are_rejected() {
+    let (eblc_bytes, ebdt_bytes) = monochrome_bitmap_tables(4);
+    let eblc = CblcTable::new(FontTable {
+        bytes: &eblc_bytes,
+    }).unwrap();
+    let ebdt = EbdtTable::new(FontTable {
+        bytes: &ebdt_bytes,
+    }).unwrap();
+    assert_eq!(ebdt.glyph_bitmap(&eblc, 1, 12), Err(FontError::UnexpectedEofIn(ErrorContext {
+        table: ebdt::TAG,
+        field: "glyph bitmap data",
+    })));
+}
@@ -0,0 +1,1147 @@
+/* Any copyright is dedicated to the Public Domain.
+ * http://creativecommons.org/publicdomain/zero/1.0/ */
+
+use error::FontError;
+use euclid::{Matrix2D, Point2D};
+use font::{Contour, Font, GrayBitmap, Outline, Point, PointKind, SvgPathOptions, TableDirectory};
+use font::{FontCapabilities, FontTable, OutlineFormat, OutlineSource, PositionedGlyph};
+use font::TextDirection;
+use memmap::{Mmap, Protection};
+use outline::GlyphBounds;
+use rayon::prelude::*;
+use std::cmp;
+use std::i16;
+use std::usize;
+use tests::cff::CffBuilder;
+use util::{push_u16, push_u32};
+
+const OTTO: u32 = 0x4f54544f;
+
+static TEST_FONT_PATH: &'static str = "resources/tests/nimbus-sans/NimbusSanL-Regu.ttf";
+
+// Builds an sfnt containing the given tables, to be placed at the given offset in the file.
+fn sfnt(base_offset: usize, version: u32, tables: &[(&[u8; 4], Vec<u8>)]) -> Vec<u8> {
+    let mut bytes = vec![];
+    push_u32(&mut bytes, version);
+    push_u16(&mut bytes, tables.len() as u16);
+    push_u16(&mut bytes, 0);
+    push_u16(&mut bytes, 0);
+    push_u16(&mut bytes, 0);
+
+    let mut offset = base_offset + 12 + 16 * tables.len();
+    for &(tag, ref table) in tables {
+        bytes.extend_from_slice(tag);
+        push_u32(&mut bytes, 0);
+        push_u32(&mut bytes, offset as u32);
+        push_u32(&mut bytes, table.len() as u32);
+        offset += table.len();
+    }
+    for &(_, ref table) in tables {
+        bytes.extend_from_slice(table)
+    }
+    bytes
+}
+
+// Returns the tables that every font needs, for a font with a 500-unit-wide `.notdef` glyph and a
+// glyph for "A" with the given width.
+fn required_tables(advance_width: u16) -> Vec<(&'static [u8; 4], Vec<u8>)> {
+    // A format 12 subtable mapping "A" to glyph 1.
+    let mut cmap = vec![];
+    for &value in &[0, 1, 3, 10] {
+        push_u16(&mut cmap, value)
+    }
+    push_u32(&mut cmap, 12);
+    push_u16(&mut cmap, 12);
+    push_u16(&mut cmap, 0);
+    for &value in &[28, 0, 1, 0x41, 0x41, 1] {
+        push_u32(&mut cmap, value)
+    }
+
+    // Version 1.0, with 1000 units per em and long `loca` offsets.
+    let mut head = vec![];
+    for &value in &[0x10000, 0, 0, 0x5f0f3cf5] {
+        push_u32(&mut head, value)
+    }
+    push_u16(&mut head, 0);
+    push_u16(&mut head, 1000);
+    head.extend_from_slice(&[0; 16]);
+    for &value in &[0, 0, 100, 100, 0, 0, 0, 1, 0] {
+        push_u16(&mut head, value)
+    }
+
+    let mut hhea = vec![];
+    push_u32(&mut hhea, 0x10000);
+    hhea.extend_from_slice(&[0; 30]);
+    push_u16(&mut hhea, 2);
+
+    let mut hmtx = vec![];
+    for &value in &[500, 0, advance_width, 0] {
+        push_u16(&mut hmtx, value)
+    }
+
+    let mut maxp = vec![];
+    push_u32(&mut maxp, 0x5000);
+    push_u16(&mut maxp, 2);
+
+    let mut os_2 = vec![];
+    push_u16(&mut os_2, 1);
+    os_2.extend_from_slice(&[0; 84]);
+
+    vec![(b"OS/2", os_2),
+         (b"cmap", cmap),
+         (b"head", head),
+         (b"hhea", hhea),
+         (b"hmtx", hmtx),
+         (b"maxp", maxp)]
+}
+
+// The tables of a TrueType font whose "A" is a 100-unit square.
+fn truetype_tables(advance_width: u16) -> Vec<(&'static [u8; 4], Vec<u8>)> {
+    truetype_square_tables(advance_width, 100)
+}
+
+// The tables of a TrueType font whose "A" is a square of the given size.
+fn truetype_square_tables(advance_width: u16, size: i16)
+                          -> Vec<(&'static [u8; 4], Vec<u8>)> {
+    let mut glyf = vec![];
+    for &value in &[1, 0, 0, size, size, 3, 0] {
+        push_u16(&mut glyf, value as u16)
+    }
+    glyf.extend_from_slice(&[1, 1, 1, 1]);
+    for &value in &[0, size, 0, -size, 0, 0, size, 0] {
+        push_u16(&mut glyf, value as u16)
+    }
+
+    let mut loca = vec![];
+    for &value in &[0, 0, glyf.len() as u32] {
+        push_u32(&mut loca, value)
+    }
+
+    let mut tables = required_tables(advance_width);
+    tables.push((b"glyf", glyf));
+    tables.push((b"loca", loca));
+    tables
+}
+
+// The tables of a CFF-flavored OpenType font whose "A" is the same square as in
+// `truetype_tables()`.
+fn cff_tables(advance_width: u16) -> Vec<(&'static [u8; 4], Vec<u8>)> {
+    cff_square_tables(advance_width, 100)
+}
+
+// A CharString drawing a square of the given size, which must be less than 1132 units:
+// `0 0 rmoveto size 0 0 size -size 0 rlineto`.
+fn square_char_string(size: i16) -> Vec<u8> {
+    fn push_number(bytes: &mut Vec<u8>, value: i16) {
+        match value {
+            -107...107 => bytes.push((value + 139) as u8),
+            108...1131 => bytes.extend_from_slice(&[((value - 108) / 256 + 247) as u8,
+                                                     ((value - 108) % 256) as u8]),
+            _ => bytes.extend_from_slice(&[((-value - 108) / 256 + 251) as u8,
+                                            ((-value - 108) % 256) as u8]),
+        }
+    }
+
+    let mut square = vec![];
+    for &value in &[0, 0] {
+        push_number(&mut square, value)
+    }
+    square.push(21);
+    for &value in &[size, 0, 0, size, -size, 0] {
+        push_number(&mut square, value)
+    }
+    square.push(5);
+    square
+}
+
+// The tables of a CFF-flavored OpenType font whose "A" is a square of the given size, which must
+// be less than 1132 units.
+fn cff_square_tables(advance_width: u16, size: i16) -> Vec<(&'static [u8; 4], Vec<u8>)> {
+    // Header and name INDEX.
+    let mut cff = vec![1, 0, 4, 1, 0, 1, 1, 1, 2, b'T'];
+
+    // Top DICT INDEX, with the CharStrings following the empty string and global subr INDEXes.
+    cff.extend_from_slice(&[0, 1, 1, 1, 7, 29, 0, 0, 0, 25, 17]);
+    cff.extend_from_slice(&[0, 0, 0, 0]);
+
+    // CharStrings INDEX: `endchar`, then the square and `endchar`.
+    let mut square = square_char_string(size);
+    square.push(14);
+    cff.extend_from_slice(&[0, 2, 1, 1, 2, 2 + square.len() as u8, 14]);
+    cff.extend_from_slice(&square);
+
+    let mut tables = required_tables(advance_width);
+    tables.insert(0, (b"CFF ", cff));
+    tables
+}
+
+// A `CFF2` table whose glyph 1 is a square of the given size, which must be less than 1132 units.
+fn cff2_square_table(size: i16) -> (&'static [u8; 4], Vec<u8>) {
+    let square = square_char_string(size);
+
+    // The header and top DICT, with five-byte offsets to the CharStrings and font DICT INDEXes,
+    // then an empty global subr INDEX.
+    let char_strings_offset = 5 + 13 + 4;
+    let fd_array_offset = char_strings_offset + 8 + square.len();
+    let mut cff2 = vec![2, 0, 5, 0, 13, 29];
+    push_u32(&mut cff2, char_strings_offset as u32);
+    cff2.extend_from_slice(&[17, 29]);
+    push_u32(&mut cff2, fd_array_offset as u32);
+    cff2.extend_from_slice(&[12, 36]);
+    push_u32(&mut cff2, 0);
+
+    // CharStrings INDEX: an empty `.notdef`, then the square. There's no `endchar` in CFF2.
+    push_u32(&mut cff2, 2);
+    cff2.extend_from_slice(&[1, 1, 1, 1 + square.len() as u8]);
+    cff2.extend_from_slice(&square);
+
+    // Font DICT INDEX, whose one font DICT has an empty Private DICT at the end of the table.
+    let private_offset = fd_array_offset + 18;
+    push_u32(&mut cff2, 1);
+    cff2.extend_from_slice(&[1, 1, 12, 29, 0, 0, 0, 0, 29]);
+    push_u32(&mut cff2, private_offset as u32);
+    cff2.push(18);
+    (b"CFF2", cff2)
+}
+
+// Changes the units per em in the `head` table of the given tables.
+fn set_units_per_em(tables: &mut [(&'static [u8; 4], Vec<u8>)], units_per_em: u16) {
+    for &mut (tag, ref mut table) in tables {
+        if tag == b"head" {
+            table[18] = (units_per_em >> 8) as u8;
+            table[19] = units_per_em as u8
+        }
+    }
+}
+
+fn glyph_points(font: &Font, glyph_id: u16) -> Vec<Point> {
+    let mut points = vec![];
+    font.for_each_point(glyph_id, |point| points.push(*point)).unwrap();
+    points
+}
+
+#[test]
+fn outline_format_is_detected() {
+    let truetype_bytes = sfnt(0, 0x10000, &truetype_tables(600));
+    let cff_bytes = sfnt(0, OTTO, &cff_tables(600));
+    let (mut truetype_buffer, mut cff_buffer) = (vec![], vec![]);
+    let truetype = Font::new(&truetype_bytes, &mut truetype_buffer).unwrap();
+    let cff = Font::new(&cff_bytes, &mut cff_buffer).unwrap();
+
+    for &font in &[&truetype, &cff] {
+        assert_eq!(font.glyph_for_char('A'), Ok(Some(1)));
+        assert_eq!(glyph_points(font, 0), vec![]);
+        let bounds = font.glyph_bounds(1).unwrap();
+        assert_eq!((bounds.left, bounds.bottom, bounds.right, bounds.top), (0, 0, 100, 100));
+        assert_eq!(font.advance_width(0), Ok(500));
+        assert_eq!(font.advance_width(1), Ok(600));
+    }
+
+    let positions: Vec<_> = glyph_points(&cff, 1).iter().map(|point| {
+        (point.index_in_contour, point.position.x, point.position.y)
+    }).collect();
+    assert_eq!(positions, vec![(0, 0, 0), (1, 100, 0), (2, 100, 100), (3, 0, 100), (4, 0, 0)]);
+    assert_eq!(glyph_points(&truetype, 1), glyph_points(&cff, 1));
+}
+
+#[test]
+fn supported_languages() {
+    let mut meta = vec![];
+    for &value in &[1, 0, 0, 1] {
+        push_u32(&mut meta, value)
+    }
+    meta.extend_from_slice(b"slng");
+    push_u32(&mut meta, 28);
+    push_u32(&mut meta, 14);
+    meta.extend_from_slice(b"Latn,Cyrl,Grek");
+
+    let mut tables = truetype_tables(600);
+    tables.push((b"meta", meta));
+    let bytes = sfnt(0, 0x10000, &tables);
+    let mut buffer = vec![];
+    let font = Font::new(&bytes, &mut buffer).unwrap();
+    assert_eq!(font.supported_languages(), vec!["Latn", "Cyrl", "Grek"]);
+    assert!(font.design_languages().is_empty());
+    assert_eq!(font.metadata(0x736c6e67), Some(&b"Latn,Cyrl,Grek"[..]));
+
+    let bytes = sfnt(0, 0x10000, &truetype_tables(600));
+    let mut buffer = vec![];
+    assert!(Font::new(&bytes, &mut buffer).unwrap().supported_languages().is_empty());
+}
+
+#[test]
+fn capabilities_of_variable_color_font() {
+    // One weight axis from 100 to 900, with a named bold instance.
+    let mut fvar = vec![];
+    for &value in &[1, 0, 16, 2, 1, 20, 1, 8] {
+        push_u16(&mut fvar, value)
+    }
+    fvar.extend_from_slice(b"wght");
+    for &value in &[100, 400, 900] {
+        push_u32(&mut fvar, value << 16)
+    }
+    for &value in &[0, 256, 257, 0] {
+        push_u16(&mut fvar, value)
+    }
+    push_u32(&mut fvar, 700 << 16);
+
+    // "A" is drawn as a single layer in the first palette color.
+    let mut colr = vec![];
+    push_u16(&mut colr, 0);
+    push_u16(&mut colr, 1);
+    push_u32(&mut colr, 14);
+    push_u32(&mut colr, 20);
+    for &value in &[1, 1, 0, 1, 1, 0] {
+        push_u16(&mut colr, value)
+    }
+
+    let mut tables = truetype_tables(600);
+    tables.insert(0, (b"COLR", colr));
+    tables.push((b"fvar", fvar));
+    let bytes = sfnt(0, 0x10000, &tables);
+    let mut buffer = vec![];
+    let font = Font::new(&bytes, &mut buffer).unwrap();
+    assert_eq!(font.capabilities(), FontCapabilities {
+        outline_format: OutlineFormat::TrueType,
+        color_layers: true,
+        color_bitmaps: false,
+        svg_glyphs: false,
+        variation_axis_count: 1,
+        named_instance_count: 1,
+        kerning: false,
+        glyph_substitutions: false,
+        aat_glyph_substitutions: false,
+        vertical_metrics: false,
+        math: false,
+        hinting_programs: false,
+        signed: false,
+    });
+
+    let bytes = sfnt(0, OTTO, &cff_tables(600));
+    let mut buffer = vec![];
+    let capabilities = Font::new(&bytes, &mut buffer).unwrap().capabilities();
+    assert_eq!(capabilities.outline_format, OutlineFormat::Cff);
+    assert!(!capabilities.color_layers);
+    assert_eq!(capabilities.variation_axis_count, 0);
+
+    let bytes = sfnt(0, 0x10000, &required_tables(600));
+    let mut buffer = vec![];
+    let capabilities = Font::new(&bytes, &mut buffer).unwrap().capabilities();
+    assert_eq!(capabilities.outline_format, OutlineFormat::None);
+}
+
+#[test]
+fn outline_source_priority() {
+    // "A" is a 100-unit square in `glyf`, a 200-unit square in `CFF `, and a single color layer.
+    let mut colr = vec![];
+    push_u16(&mut colr, 0);
+    push_u16(&mut colr, 1);
+    push_u32(&mut colr, 14);
+    push_u32(&mut colr, 20);
+    for &value in &[1, 1, 0, 1, 1, 0] {
+        push_u16(&mut colr, value)
+    }
+
+    let mut tables = truetype_square_tables(600, 100);
+    tables.insert(0, cff_square_tables(600, 200).remove(0));
+    tables.insert(1, (b"COLR", colr));
+    let bytes = sfnt(0, 0x10000, &tables);
+    let mut buffer = vec![];
+    let mut font = Font::new(&bytes, &mut buffer).unwrap();
+
+    // By default, color layers win, then `glyf` outlines over `CFF ` ones.
+    assert_eq!(font.outline_source(0), Some(OutlineSource::Glyf));
+    assert_eq!(font.outline_source(1), Some(OutlineSource::Colr));
+    assert_eq!(font.glyph_bounds(1).unwrap().right, 100);
+    assert_eq!(font.capabilities().outline_format, OutlineFormat::TrueType);
+
+    font.set_outline_source_priority(&[OutlineSource::Cff, OutlineSource::Colr]);
+    assert_eq!(font.outline_source_priority(), &[OutlineSource::Cff, OutlineSource::Colr]);
+    assert_eq!(font.outline_source(1), Some(OutlineSource::Cff));
+    assert_eq!(font.glyph_bounds(1).unwrap().right, 200);
+    assert_eq!(glyph_points(&font, 1).len(), 5);
+    assert_eq!(font.capabilities().outline_format, OutlineFormat::Cff);
+
+    // Sources left out of the list are never used.
+    font.set_outline_source_priority(&[OutlineSource::Sbix, OutlineSource::Cff2]);
+    assert_eq!(font.outline_source(1), None);
+    assert_eq!(glyph_points(&font, 1), vec![]);
+    assert_eq!(font.capabilities().outline_format, OutlineFormat::None);
+}
+
+#[test]
+fn outline_source_priority_with_cff2() {
+    // "A" is a 300-unit square in `CFF2`, and a 100-unit one in `glyf`.
+    let mut tables = truetype_square_tables(600, 100);
+    tables.insert(0, cff2_square_table(300));
+    let bytes = sfnt(0, 0x10000, &tables);
+    let mut buffer = vec![];
+    let mut font = Font::new(&bytes, &mut buffer).unwrap();
+
+    // Variable outlines win by default.
+    assert_eq!(font.outline_source(1), Some(OutlineSource::Cff2));
+    assert_eq!(font.glyph_bounds(1).unwrap().right, 300);
+    assert_eq!(glyph_points(&font, 1).len(), 5);
+    assert_eq!(font.capabilities().outline_format, OutlineFormat::Cff);
+
+    font.set_outline_source_priority(&[OutlineSource::Glyf, OutlineSource::Cff2]);
+    assert_eq!(font.outline_source(1), Some(OutlineSource::Glyf));
+    assert_eq!(font.glyph_bounds(1).unwrap().right, 100);
+    assert_eq!(font.capabilities().outline_format, OutlineFormat::TrueType);
+
+    // With both `CFF ` and `CFF2`, each is read when it comes first. Here "A" is 200 units in
+    // `CFF `.
+    let mut tables = cff_square_tables(600, 200);
+    tables.insert(1, cff2_square_table(300));
+    let bytes = sfnt(0, OTTO, &tables);
+    let mut buffer = vec![];
+    let mut font = Font::new(&bytes, &mut buffer).unwrap();
+    assert_eq!(font.outline_source(1), Some(OutlineSource::Cff2));
+    assert_eq!(font.glyph_bounds(1).unwrap().right, 300);
+
+    font.set_outline_source_priority(&[OutlineSource::Cff, OutlineSource::Cff2]);
+    assert_eq!(font.outline_source(1), Some(OutlineSource::Cff));
+    assert_eq!(font.glyph_bounds(1).unwrap().right, 200);
+    assert_eq!(glyph_points(&font, 1)[2].position, Point2D::new(200, 200));
+    assert_eq!(font.outline_source(0), Some(OutlineSource::Cff));
+}
+
+#[test]
+fn capabilities_of_test_font() {
+    let file = Mmap::open_path(TEST_FONT_PATH, Protection::Read).expect("Couldn't open test font");
+    let mut buffer = vec![];
+    let font = Font::new(unsafe { file.as_slice() }, &mut buffer).unwrap();
+    let capabilities = font.capabilities();
+    assert_eq!(capabilities.outline_format, OutlineFormat::TrueType);
+    assert!(capabilities.hinting_programs);
+    assert!(capabilities.vertical_metrics);
+    assert!(!capabilities.kerning);
+    assert!(!capabilities.color_layers && !capabilities.color_bitmaps && !capabilities.svg_glyphs);
+}
+
+#[test]
+fn collection_faces() {
+    // A TTC header with two fonts, followed by a TrueType and a CFF font.
+    let mut bytes = b"ttcf".to_vec();
+    push_u32(&mut bytes, 0x10000);
+    push_u32(&mut bytes, 2);
+    push_u32(&mut bytes, 20);
+    let truetype = sfnt(20, 0x10000, &truetype_tables(600));
+    push_u32(&mut bytes, 20 + truetype.len() as u32);
+    let cff_offset = bytes.len() + truetype.len();
+    bytes.extend(truetype);
+    bytes.extend(sfnt(cff_offset, OTTO, &cff_tables(700)));
+
+    let mut buffer = vec![];
+    let truetype = Font::from_collection_index(&bytes, 0, &mut buffer).unwrap();
+    assert_eq!(truetype.advance_width(1), Ok(600));
+    let mut buffer = vec![];
+    let cff = Font::from_collection_index(&bytes, 1, &mut buffer).unwrap();
+    assert_eq!(cff.advance_width(1), Ok(700));
+    assert_eq!(glyph_points(&truetype, 1), glyph_points(&cff, 1));
+
+    // `Font::new()` opens the first face.
+    let mut buffer = vec![];
+    assert_eq!(Font::new(&bytes, &mut buffer).unwrap().advance_width(1), Ok(600));
+
+    let mut buffer = vec![];
+    assert_eq!(Font::from_collection_index(&bytes, 2, &mut buffer).err(),
+               Some(FontError::FontIndexOutOfBounds));
+}
+
+#[test]
+fn outlines_decode_in_parallel() {
+    fn assert_send<T: Send>() {}
+    assert_send::<Outline>();
+
+    let file = Mmap::open_path(TEST_FONT_PATH, Protection::Read).expect("Couldn't open test font");
+    let mut buffer = vec![];
+    let font = Font::new(unsafe { file.as_slice() }, &mut buffer).unwrap();
+    let glyph_ids: Vec<u16> = (0..font.num_glyphs()).collect();
+
+    let serial: Vec<_> = glyph_ids.iter().map(|&id| font.outline(id).unwrap()).collect();
+    let parallel: Vec<_> = glyph_ids.par_iter().map(|&id| font.outline(id).unwrap()).collect();
+    assert_eq!(parallel, serial);
+
+    // Each outline holds the same points as `for_each_point()`, split into contours.
+    let glyph_id = font.glyph_for_char('o').unwrap().unwrap();
+    let outline = &serial[glyph_id as usize];
+    assert_eq!(outline.contours.len(), 2);
+    let mut points = vec![];
+    outline.for_each_point(|point| points.push(*point));
+    assert_eq!(points.len(), outline.num_points());
+    assert_eq!(points, glyph_points(&font, glyph_id));
+    assert!(outline.contours.iter().all(|contour| contour.points[0].index_in_contour == 0));
+}
+
+// Builds an outline with one contour through the given on-curve and quadratic control points.
+fn quad_outline(points: &[(i16, i16, bool)]) -> Outline {
+    Outline::from_points(points.iter().enumerate().map(|(index, &(x, y, on_curve))| {
+        Point {
+            position: Point2D::new(x, y),
+            index_in_contour: index as u16,
+            kind: if on_curve { PointKind::OnCurve } else { PointKind::QuadControl },
+        }
+    }))
+}
+
+#[test]
+fn normalized_outlines() {
+    // A counterclockwise shape with one curved side, starting at different points, and closed
+    // explicitly as in CFF.
+    let a = quad_outline(&[(0, 0, true), (100, 0, true), (150, 50, false), (100, 100, true),
+                           (0, 100, true)]);
+    let b = quad_outline(&[(100, 100, true), (0, 100, true), (0, 0, true), (100, 0, true),
+                           (150, 50, false), (100, 100, true)]);
+    assert!(a != b);
+    assert_eq!(a.normalized(), b.normalized());
+    assert_eq!(a.normalized().contours[0].points[0].position, Point2D::new(0, 0));
+
+    // The same shape, clockwise.
+    let c = quad_outline(&[(0, 100, true), (100, 100, true), (150, 50, false), (100, 0, true),
+                           (0, 0, true)]);
+    assert!(a.normalized() != c.normalized());
+    assert_eq!(a.normalized_ignoring_direction(), c.normalized_ignoring_direction());
+
+    // Contour order doesn't matter either.
+    let square = quad_outline(&[(200, 0, true), (300, 0, true), (300, 100, true),
+                                (200, 100, true)]);
+    let mut ab = Outline {
+        contours: vec![square.contours[0].clone(), a.contours[0].clone()],
+    };
+    let ba = Outline {
+        contours: vec![Contour {
+            points: b.contours[0].points.clone(),
+        }, square.contours[0].clone()],
+    };
+    assert_eq!(ab.normalized(), ba.normalized());
+
+    // Nudging a point is within tolerance, but changing its kind isn't.
+    ab.contours[0].points[1].position.x += 2;
+    assert!(!ab.normalized().approx_eq(&ba.normalized(), 1));
+    assert!(ab.normalized().approx_eq(&ba.normalized(), 2));
+    ab.contours[0].points[1].kind = PointKind::QuadControl;
+    assert!(!ab.normalized().approx_eq(&ba.normalized(), 2));
+}
+
+#[test]
+fn contour_winding() {
+    let square = quad_outline(&[(0, 0, true), (100, 0, true), (100, 100, true), (0, 100, true)]);
+    let mut contour = square.contours[0].clone();
+    assert_eq!(contour.signed_area(), 10000.0);
+    assert!(!contour.is_clockwise());
+
+    contour.reverse();
+    assert_eq!(contour.signed_area(), -10000.0);
+    assert!(contour.is_clockwise());
+    assert_eq!(contour.points[0], square.contours[0].points[0]);
+    assert_eq!(contour.points[1].position, Point2D::new(0, 100));
+    assert_eq!(contour.points[1].index_in_contour, 1);
+
+    // Explicitly closed contours stay closed.
+    let closed = quad_outline(&[(0, 0, true), (100, 0, true), (100, 100, true), (0, 0, true)]);
+    let mut contour = closed.contours[0].clone();
+    contour.reverse();
+    assert_eq!(contour.points[1].position, Point2D::new(100, 100));
+    assert_eq!(contour.points[3].position, Point2D::new(0, 0));
+}
+
+#[test]
+fn fix_winding_alternates_with_nesting() {
+    // Three nested squares, all clockwise.
+    let square = |min: i16, max: i16| {
+        quad_outline(&[(min, min, true), (min, max, true), (max, max, true), (max, min, true)])
+            .contours[0].clone()
+    };
+    let mut outline = Outline {
+        contours: vec![square(40, 60), square(0, 100), square(20, 80)],
+    };
+    outline.fix_winding();
+    assert!(!outline.contours[0].is_clockwise());
+    assert!(!outline.contours[1].is_clockwise());
+    assert!(outline.contours[2].is_clockwise());
+
+    // Already fixed outlines are unchanged.
+    let fixed = outline.clone();
+    outline.fix_winding();
+    assert_eq!(outline, fixed);
+}
+
+#[test]
+fn fix_winding_on_glyph_with_counter() {
+    let file = Mmap::open_path(TEST_FONT_PATH, Protection::Read).expect("Couldn't open test font");
+    let mut buffer = vec![];
+    let font = Font::new(unsafe { file.as_slice() }, &mut buffer).unwrap();
+    let glyph_id = font.glyph_for_char('O').unwrap().unwrap();
+    let mut outline = font.outline(glyph_id).unwrap();
+    assert_eq!(outline.contours.len(), 2);
+
+    // TrueType winds the outer contour clockwise, and the counter the other way.
+    let outer = if outline.contours[0].signed_area().abs() >
+            outline.contours[1].signed_area().abs() {
+        0
+    } else {
+        1
+    };
+    let inner = 1 - outer;
+    assert!(outline.contours[outer].is_clockwise());
+    assert!(!outline.contours[inner].is_clockwise());
+
+    outline.fix_winding();
+    assert!(!outline.contours[outer].is_clockwise());
+    assert!(outline.contours[inner].is_clockwise());
+
+    // Making the counter wind the same way as the outer contour is undone too.
+    outline.contours[inner].reverse();
+    outline.fix_winding();
+    assert!(outline.contours[inner].is_clockwise());
+    assert_eq!(outline.normalized_ignoring_direction(),
+               font.outline(glyph_id).unwrap().normalized_ignoring_direction());
+}
+
+// Returns the left, bottom, right, and top edges of the points of the outline.
+fn outline_bounds(outline: &Outline) -> (i16, i16, i16, i16) {
+    let mut bounds = (i16::MAX, i16::MAX, i16::MIN, i16::MIN);
+    outline.for_each_point(|point| {
+        bounds = (cmp::min(bounds.0, point.position.x),
+                  cmp::min(bounds.1, point.position.y),
+                  cmp::max(bounds.2, point.position.x),
+                  cmp::max(bounds.3, point.position.y))
+    });
+    bounds
+}
+
+#[test]
+fn outlines_scale_to_ppem() {
+    let file = Mmap::open_path(TEST_FONT_PATH, Protection::Read).expect("Couldn't open test font");
+    let mut buffer = vec![];
+    let font = Font::new(unsafe { file.as_slice() }, &mut buffer).unwrap();
+    assert_eq!(font.units_per_em(), 1000);
+    let outline = font.outline(font.glyph_for_char('O').unwrap().unwrap()).unwrap();
+    let mut scaled = outline.clone();
+    scaled.scale_to_ppem(font.units_per_em(), 16.0);
+
+    // Every point is scaled and rounded, whatever its kind, so the bounds are too.
+    let (left, bottom, right, top) = outline_bounds(&outline);
+    let (scaled_left, scaled_bottom, scaled_right, scaled_top) = outline_bounds(&scaled);
+    for &(original, scaled) in &[(left, scaled_left), (bottom, scaled_bottom),
+                                 (right, scaled_right), (top, scaled_top)] {
+        assert_eq!(scaled, (original as f32 * 16.0 / 1000.0).round() as i16)
+    }
+
+    assert_eq!(scaled.contours.len(), outline.contours.len());
+    for (contour, scaled_contour) in outline.contours.iter().zip(scaled.contours.iter()) {
+        let kinds = |contour: &Contour<Point>| -> Vec<_> {
+            contour.points.iter().map(|point| (point.index_in_contour, point.kind)).collect()
+        };
+        assert_eq!(kinds(scaled_contour), kinds(contour));
+        assert_eq!(scaled_contour.is_clockwise(), contour.is_clockwise());
+    }
+}
+
+#[test]
+fn mirrored_outlines_keep_their_winding() {
+    let square = [(0, 0, true), (100, 0, true), (100, 100, true), (0, 100, true)];
+    let mut outline = quad_outline(&square);
+    outline.transform(&Matrix2D::row_major(1.0, 0.0, 0.0, -1.0, 10.0, 0.0));
+    assert_eq!(outline, quad_outline(&[(10, 0, true), (10, -100, true), (110, -100, true),
+                                       (110, 0, true)]));
+    assert!(!outline.contours[0].is_clockwise());
+
+    // Transforms that don't mirror keep the points in order.
+    let mut outline = quad_outline(&square);
+    outline.transform(&Matrix2D::create_scale(0.5, 2.0));
+    assert_eq!(outline, quad_outline(&[(0, 0, true), (50, 0, true), (50, 200, true),
+                                       (0, 200, true)]));
+}
+
+#[test]
+fn truetype_and_cff_outlines_normalize_equal() {
+    let truetype_bytes = sfnt(0, 0x10000, &truetype_tables(600));
+    let cff_bytes = sfnt(0, OTTO, &cff_tables(600));
+    let (mut truetype_buffer, mut cff_buffer) = (vec![], vec![]);
+    let truetype = Font::new(&truetype_bytes, &mut truetype_buffer).unwrap();
+    let cff = Font::new(&cff_bytes, &mut cff_buffer).unwrap();
+    assert_eq!(truetype.outline(1).unwrap().normalized(), cff.outline(1).unwrap().normalized());
+    assert_eq!(cff.outline(1).unwrap().normalized().num_points(), 4);
+}
+
+#[test]
+fn svg_paths_of_truetype_and_cff_glyphs() {
+    let truetype_bytes = sfnt(0, 0x10000, &truetype_tables(600));
+    let cff_bytes = sfnt(0, OTTO, &cff_tables(600));
+    let (mut truetype_buffer, mut cff_buffer) = (vec![], vec![]);
+    let truetype = Font::new(&truetype_bytes, &mut truetype_buffer).unwrap();
+    let cff = Font::new(&cff_bytes, &mut cff_buffer).unwrap();
+    let options = SvgPathOptions {
+        flip_y: true,
+        ..SvgPathOptions::default()
+    };
+    assert_eq!(truetype.to_svg_path(1, &options).unwrap(), "M0 0L100 0L100 -100L0 -100Z");
+    assert_eq!(truetype.to_svg_path(0, &options).unwrap(), "");
+
+    // The CFF square is the same as the TrueType one.
+    let default = SvgPathOptions::default();
+    assert_eq!(cff.to_svg_path(1, &default).unwrap(), "M0 0L100 0L100 100L0 100Z");
+    assert_eq!(cff.to_svg_path(1, &default).unwrap(), truetype.to_svg_path(1, &default).unwrap());
+}
+
+// The tables of a TrueType font whose "A" is a diamond 100 units across.
+fn truetype_diamond_tables() -> Vec<(&'static [u8; 4], Vec<u8>)> {
+    let mut glyf = vec![];
+    for &value in &[1, 0, 0, 100, 100, 3, 0] {
+        push_u16(&mut glyf, value as u16)
+    }
+    glyf.extend_from_slice(&[1, 1, 1, 1]);
+    for &value in &[50, 50, -50, -50, 0, 50, 50, -50] {
+        push_u16(&mut glyf, value as i16 as u16)
+    }
+
+    let mut loca = vec![];
+    for &value in &[0, 0, glyf.len() as u32] {
+        push_u32(&mut loca, value)
+    }
+
+    let mut tables = required_tables(600);
+    tables.push((b"glyf", glyf));
+    tables.push((b"loca", loca));
+    tables
+}
+
+#[test]
+fn rasterized_coverage() {
+    let bytes = sfnt(0, 0x10000, &truetype_diamond_tables());
+    let mut buffer = vec![];
+    let font = Font::new(&bytes, &mut buffer).unwrap();
+
+    // At 200 pixels per em, the diamond is 20 pixels across.
+    let bitmap = font.rasterize(1, 200.0, &Matrix2D::identity()).unwrap();
+    assert_eq!((bitmap.width, bitmap.height, bitmap.left, bitmap.top), (20, 20, 0, 20));
+    assert_eq!(bitmap.coverage.len(), 400);
+    for &(x, y) in &[(0, 0), (19, 0), (0, 19), (19, 19)] {
+        assert_eq!(bitmap.coverage_at(x, y), 0)
+    }
+    for &(x, y) in &[(9, 9), (10, 10), (5, 9), (14, 10)] {
+        assert_eq!(bitmap.coverage_at(x, y), 255)
+    }
+
+    // Pixels on the edges are half covered.
+    assert!((bitmap.coverage_at(0, 9) as i32 - 128).abs() <= 1);
+
+    // The total coverage is the area, so it grows with the square of the size.
+    let total = |bitmap: &GrayBitmap| {
+        bitmap.coverage.iter().map(|&coverage| coverage as f32 / 255.0).sum::<f32>()
+    };
+    assert!((total(&bitmap) - 200.0).abs() < 1.0);
+    let larger = font.rasterize(1, 400.0, &Matrix2D::identity()).unwrap();
+    assert!((total(&larger) / total(&bitmap) - 4.0).abs() < 0.01);
+
+    // Subpixel offsets move the bitmap without changing the area.
+    let offset = font.rasterize(1, 200.0, &Matrix2D::create_translation(0.5, 0.0)).unwrap();
+    assert_eq!((offset.width, offset.left), (21, 0));
+    assert!((total(&offset) - 200.0).abs() < 1.0);
+
+    let empty = font.rasterize(0, 200.0, &Matrix2D::identity()).unwrap();
+    assert_eq!((empty.width, empty.height), (0, 0));
+}
+
+#[test]
+fn rasterized_counters_are_empty() {
+    let file = Mmap::open_path(TEST_FONT_PATH, Protection::Read).expect("Couldn't open test font");
+    let mut buffer = vec![];
+    let font = Font::new(unsafe { file.as_slice() }, &mut buffer).unwrap();
+    let glyph_id = font.glyph_for_char('O').unwrap().unwrap();
+    let bitmap = font.rasterize(glyph_id, 64.0, &Matrix2D::identity()).unwrap();
+    assert_eq!(bitmap.coverage_at(0, 0), 0);
+    assert_eq!(bitmap.coverage_at(bitmap.width / 2, bitmap.height / 2), 0);
+    // The stroke is several pixels thick at this size.
+    assert_eq!(bitmap.coverage_at(bitmap.width / 2, 2), 255);
+}
+
+#[test]
+fn glyph_bounds_scale_by_units_per_em() {
+    // The same 1/8-em square, in a 1000-unit CFF font and a 2048-unit TrueType font.
+    let cff_bytes = sfnt(0, OTTO, &cff_square_tables(600, 125));
+    let mut truetype_tables = truetype_square_tables(600, 256);
+    set_units_per_em(&mut truetype_tables, 2048);
+    let truetype_bytes = sfnt(0, 0x10000, &truetype_tables);
+
+    let (mut cff_buffer, mut truetype_buffer) = (vec![], vec![]);
+    let cff = Font::new(&cff_bytes, &mut cff_buffer).unwrap();
+    let truetype = Font::new(&truetype_bytes, &mut truetype_buffer).unwrap();
+    assert_eq!((cff.units_per_em(), truetype.units_per_em()), (1000, 2048));
+    assert_eq!(cff.glyph_bounds(1).unwrap().right, 125);
+    assert_eq!(truetype.glyph_bounds(1).unwrap().right, 256);
+
+    for &ppem in &[12.0, 16.0, 64.0] {
+        let (cff_bounds, truetype_bounds) = (cff.scaled_glyph_bounds(1, ppem).unwrap(),
+                                             truetype.scaled_glyph_bounds(1, ppem).unwrap());
+        for &(cff_value, truetype_value) in &[(cff_bounds.left, truetype_bounds.left),
+                                              (cff_bounds.bottom, truetype_bounds.bottom),
+                                              (cff_bounds.right, truetype_bounds.right),
+                                              (cff_bounds.top, truetype_bounds.top)] {
+            assert_eq!(cff_value, truetype_value)
+        }
+        assert_eq!(cff_bounds.right, ppem / 8.0);
+    }
+
+    assert_eq!(cff.scaled_glyph_bounds(2, 16.0).err(), Some(FontError::NoSuchGlyph));
+}
+
+#[test]
+fn run_bounds() {
+    let truetype_bytes = sfnt(0, 0x10000, &truetype_tables(600));
+    let cff_bytes = sfnt(0, OTTO, &cff_tables(600));
+    let (mut truetype_buffer, mut cff_buffer) = (vec![], vec![]);
+    let truetype = Font::new(&truetype_bytes, &mut truetype_buffer).unwrap();
+    let cff = Font::new(&cff_bytes, &mut cff_buffer).unwrap();
+    let edges = |bounds: GlyphBounds| (bounds.left, bounds.bottom, bounds.right, bounds.top);
+
+    for font in &[truetype, cff] {
+        // Two squares, the second raised and rounded out to whole units.
+        let glyphs = [(1, Point2D::new(10.5, -20.0)), (1, Point2D::new(300.0, 50.25))];
+        assert_eq!(edges(font.run_bounds(&glyphs).unwrap()), (10, -20, 400, 151));
+
+        // "AA" is two squares 600 units apart. The empty `.notdef` only moves the pen.
+        assert_eq!(edges(font.run_bounds_with_advances(&[1, 1]).unwrap()), (0, 0, 700, 100));
+        assert_eq!(edges(font.run_bounds_with_advances(&[0, 1]).unwrap()), (500, 0, 600, 100));
+        assert_eq!(edges(font.run_bounds_with_advances(&[0]).unwrap()), (0, 0, 0, 0));
+        assert_eq!(font.run_bounds_with_advances(&[1, 2]).err(), Some(FontError::NoSuchGlyph));
+    }
+}
+
+// The tables of a TrueType font with an empty 600-unit-wide "A" and 550-unit-wide "V", which
+// are kerned by -80 units as "AV" and -40 units as "VA".
+fn kerned_tables() -> Vec<(&'static [u8; 4], Vec<u8>)> {
+    // A format 12 subtable mapping "A" to glyph 1 and "V" to glyph 2.
+    let mut cmap = vec![];
+    for &value in &[0, 1, 3, 10] {
+        push_u16(&mut cmap, value)
+    }
+    push_u32(&mut cmap, 12);
+    push_u16(&mut cmap, 12);
+    push_u16(&mut cmap, 0);
+    for &value in &[40, 0, 2, 0x41, 0x41, 1, 0x56, 0x56, 2] {
+        push_u32(&mut cmap, value)
+    }
+
+    let mut hmtx = vec![];
+    for &value in &[500, 0, 600, 0, 550, 0] {
+        push_u16(&mut hmtx, value)
+    }
+
+    let mut loca = vec![];
+    for _ in 0..4 {
+        push_u32(&mut loca, 0)
+    }
+
+    // A version 0 table with one horizontal format 0 subtable.
+    let pairs = [(1, 2, -80i16), (2, 1, -40)];
+    let mut kern = vec![];
+    for &value in &[0, 1, 0, 14 + 6 * pairs.len() as u16, 1, pairs.len() as u16, 12, 1, 0] {
+        push_u16(&mut kern, value)
+    }
+    for &(left, right, value) in &pairs {
+        for &value in &[left, right, value as u16] {
+            push_u16(&mut kern, value)
+        }
+    }
+
+    let mut tables = required_tables(600);
+    for &mut (tag, ref mut table) in &mut tables {
+        match tag {
+            b"cmap" => *table = cmap.clone(),
+            b"hmtx" => *table = hmtx.clone(),
+            b"hhea" => table[35] = 3,
+            b"maxp" => table[5] = 3,
+            _ => {}
+        }
+    }
+    tables.push((b"glyf", vec![]));
+    tables.push((b"kern", kern));
+    tables.push((b"loca", loca));
+    tables
+}
+
+#[test]
+fn measured_advances() {
+    let bytes = sfnt(0, 0x10000, &kerned_tables());
+    let mut buffer = vec![];
+    let font = Font::new(&bytes, &mut buffer).unwrap();
+    let kern = ((b'k' as u32) << 24) | ((b'e' as u32) << 16) | ((b'r' as u32) << 8) | (b'n' as u32);
+    let (a, v) = (font.glyph_for_char('A').unwrap().unwrap(), 2);
+    assert_eq!(font.glyph_for_char('V'), Ok(Some(v)));
+
+    assert_eq!(font.measure(&[a, v], &[], TextDirection::LeftToRight), Ok(1150.0));
+    assert_eq!(font.measure(&[a, v], &[kern], TextDirection::LeftToRight), Ok(1070.0));
+    assert_eq!(font.measure(&[v, a], &[kern], TextDirection::LeftToRight), Ok(1110.0));
+    assert_eq!(font.measure(&[a, v, a], &[kern], TextDirection::LeftToRight), Ok(1630.0));
+
+    // Right to left, "AV" in logical order is set as "VA".
+    assert_eq!(font.measure(&[a, v], &[kern], TextDirection::RightToLeft), Ok(-1110.0));
+    assert_eq!(font.measure(&[v, a], &[], TextDirection::RightToLeft), Ok(-1150.0));
+
+    assert_eq!(font.measure(&[], &[kern], TextDirection::LeftToRight), Ok(0.0));
+    assert_eq!(font.measure(&[a, 3], &[kern], TextDirection::LeftToRight).err(),
+               Some(FontError::NoSuchGlyph));
+}
+
+// The tables of a TrueType font with empty glyphs for "f", "i", and their ligature, which are
+// 300, 250, and 520 units wide, and a GSUB table forming the ligature in the `liga` feature.
+fn ligature_tables() -> Vec<(&'static [u8; 4], Vec<u8>)> {
+    // A format 12 subtable mapping "f" to glyph 1 and "i" to glyph 2.
+    let mut cmap = vec![];
+    for &value in &[0, 1, 3, 10] {
+        push_u16(&mut cmap, value)
+    }
+    push_u32(&mut cmap, 12);
+    push_u16(&mut cmap, 12);
+    push_u16(&mut cmap, 0);
+    for &value in &[40, 0, 2, 0x66, 0x66, 1, 0x69, 0x69, 2] {
+        push_u32(&mut cmap, value)
+    }
+
+    let mut hmtx = vec![];
+    for &value in &[500, 0, 300, 0, 250, 0, 520, 0] {
+        push_u16(&mut hmtx, value)
+    }
+
+    let mut loca = vec![];
+    for _ in 0..5 {
+        push_u32(&mut loca, 0)
+    }
+
+    // A default script and language system with a `liga` feature, whose one lookup substitutes
+    // glyph 3 for glyphs 1 and 2.
+    let mut gsub = vec![];
+    for &value in &[
+        1, 0, 10, 30, 44,                           // header
+        1, 0x4446, 0x4c54, 8, 4, 0, 0, 0xffff, 1, 0, // script list
+        1, 0x6c69, 0x6761, 8, 0, 1, 0,              // feature list
+        1, 4, 4, 0, 1, 8,                           // lookup list
+        1, 18, 1, 8, 1, 4, 3, 2, 2, 1, 1, 1,        // ligature substitution subtable
+    ] {
+        push_u16(&mut gsub, value)
+    }
+
+    let mut tables = required_tables(300);
+    for &mut (tag, ref mut table) in &mut tables {
+        match tag {
+            b"cmap" => *table = cmap.clone(),
+            b"hmtx" => *table = hmtx.clone(),
+            b"hhea" => table[35] = 4,
+            b"maxp" => table[5] = 4,
+            _ => {}
+        }
+    }
+    tables.insert(0, (b"GSUB", gsub));
+    tables.push((b"glyf", vec![]));
+    tables.push((b"loca", loca));
+    tables
+}
+
+fn positioned_glyph(glyph_id: u16, x_advance: i32, cluster: usize) -> PositionedGlyph {
+    PositionedGlyph {
+        glyph_id: glyph_id,
+        x_advance: x_advance,
+        x_offset: 0,
+        y_offset: 0,
+        cluster: cluster,
+    }
+}
+
+#[test]
+fn shaped_ligatures() {
+    let bytes = sfnt(0, 0x10000, &ligature_tables());
+    let mut buffer = vec![];
+    let font = Font::new(&bytes, &mut buffer).unwrap();
+    let liga = ((b'l' as u32) << 24) | ((b'i' as u32) << 16) | ((b'g' as u32) << 8) | (b'a' as u32);
+
+    assert_eq!(font.shape("fi", &[liga], TextDirection::LeftToRight),
+               Ok(vec![positioned_glyph(3, 520, 0)]));
+    assert_eq!(font.shape("fi", &[], TextDirection::LeftToRight),
+               Ok(vec![positioned_glyph(1, 300, 0), positioned_glyph(2, 250, 1)]));
+
+    // Unmapped characters become `.notdef`, and clusters are byte offsets.
+    assert_eq!(font.shape("\u{e9}fif", &[liga], TextDirection::LeftToRight),
+               Ok(vec![positioned_glyph(0, 500, 0),
+                       positioned_glyph(3, 520, 2),
+                       positioned_glyph(1, 300, 4)]));
+    assert_eq!(font.shape("", &[liga], TextDirection::LeftToRight), Ok(vec![]));
+}
+
+#[test]
+fn shaped_kerning() {
+    let bytes = sfnt(0, 0x10000, &kerned_tables());
+    let mut buffer = vec![];
+    let font = Font::new(&bytes, &mut buffer).unwrap();
+    let kern = ((b'k' as u32) << 24) | ((b'e' as u32) << 16) | ((b'r' as u32) << 8) | (b'n' as u32);
+
+    assert_eq!(font.shape("AVA", &[kern], TextDirection::LeftToRight),
+               Ok(vec![positioned_glyph(1, 520, 0),
+                       positioned_glyph(2, 510, 1),
+                       positioned_glyph(1, 600, 2)]));
+    assert_eq!(font.shape("AV", &[], TextDirection::LeftToRight),
+               Ok(vec![positioned_glyph(1, 600, 0), positioned_glyph(2, 550, 1)]));
+
+    // Right to left, "AV" in logical order is set as "VA", kerned on the "V".
+    assert_eq!(font.shape("AV", &[kern], TextDirection::RightToLeft),
+               Ok(vec![positioned_glyph(2, 510, 1), positioned_glyph(1, 600, 0)]));
+}
+
+#[test]
+fn digital_signatures() {
+    // A signature record pointing to a 16-byte block.
+    let mut dsig = vec![];
+    push_u32(&mut dsig, 1);
+    push_u16(&mut dsig, 1);
+    push_u16(&mut dsig, 0);
+    for &value in &[1, 16, 20] {
+        push_u32(&mut dsig, value)
+    }
+    dsig.extend_from_slice(&[0; 16]);
+
+    // A null signature table, with no signature records.
+    let mut null_dsig = vec![];
+    push_u32(&mut null_dsig, 1);
+    push_u32(&mut null_dsig, 0);
+
+    let mut signed_tables = truetype_tables(600);
+    signed_tables.insert(0, (b"DSIG", dsig));
+    let mut null_signed_tables = truetype_tables(600);
+    null_signed_tables.insert(0, (b"DSIG", null_dsig));
+
+    for &(ref tables, signed) in &[(signed_tables, true),
+                                   (null_signed_tables, false),
+                                   (truetype_tables(600), false)] {
+        let bytes = sfnt(0, 0x10000, tables);
+        let mut buffer = vec![];
+        let font = Font::new(&bytes, &mut buffer).unwrap();
+        assert_eq!(font.has_signature(), signed);
+    }
+}
+
+// The tables of a CFF-flavored OpenType font mapping "ABC" to glyphs 1-3. "A" is a square drawn by
+// a global subroutine, "B" is a triangle drawn by a local subroutine, and "C" is a smaller
+// triangle drawn directly.
+fn cff_subset_tables() -> Vec<(&'static [u8; 4], Vec<u8>)> {
+    let cff = CffBuilder {
+        // `.notdef`, then "A" and "B" calling subroutine 0 (-107 with the bias), then "C".
+        char_strings: vec![vec![14],
+                           vec![32, 29, 14],
+                           vec![32, 10, 14],
+                           vec![139, 139, 21, 189, 139, 114, 189, 5, 14]],
+        // `0 0 rmoveto 100 0 0 100 -100 0 rlineto return` and
+        // `0 0 rmoveto 100 0 -50 100 rlineto return`.
+        global_subrs: vec![vec![139, 139, 21, 239, 139, 139, 239, 39, 139, 5, 11]],
+        local_subrs: vec![vec![139, 139, 21, 239, 139, 89, 239, 5, 11]],
+        // The SIDs of "A", "B", and "C".
+        charset: Some(vec![0, 0, 34, 0, 35, 0, 36]),
+        ..CffBuilder::default()
+    }.build();
+
+    // A format 12 subtable mapping "ABC" to glyphs 1-3.
+    let mut cmap = vec![];
+    for &value in &[0, 1, 3, 10] {
+        push_u16(&mut cmap, value)
+    }
+    push_u32(&mut cmap, 12);
+    push_u16(&mut cmap, 12);
+    push_u16(&mut cmap, 0);
+    for &value in &[28, 0, 1, 0x41, 0x43, 1] {
+        push_u32(&mut cmap, value)
+    }
+
+    let mut hhea = vec![];
+    push_u32(&mut hhea, 0x10000);
+    hhea.extend_from_slice(&[0; 30]);
+    push_u16(&mut hhea, 4);
+
+    let mut hmtx = vec![];
+    for &advance_width in &[500, 600, 700, 800] {
+        push_u16(&mut hmtx, advance_width);
+        push_u16(&mut hmtx, 0)
+    }
+
+    let mut maxp = vec![];
+    push_u32(&mut maxp, 0x5000);
+    push_u16(&mut maxp, 4);
+
+    let mut tables = required_tables(0);
+    for &mut (tag, ref mut table) in &mut tables {
+        match tag {
+            b"cmap" => *table = cmap.clone(),
+            b"hhea" => *table = hhea.clone(),
+            b"hmtx" => *table = hmtx.clone(),
+            b"maxp" => *table = maxp.clone(),
+            _ => {}
+        }
+    }
+    tables.insert(0, (b"CFF ", cff));
+    tables
+}
+
+#[test]
+fn cff_subset_renumbers_glyphs() {
+    let bytes = sfnt(0, OTTO, &cff_subset_tables());
+    let mut buffer = vec![];
+    let font = Font::new(&bytes, &mut buffer).unwrap();
+
+    let subset_bytes = font.subset(&[1, 3]).unwrap();
+    assert_eq!(TableDirectory::new(&subset_bytes).unwrap().verify_checksums(), Ok(()));
+    let mut subset_buffer = vec![];
+    let subset = Font::new(&subset_bytes, &mut subset_buffer).unwrap();
+
+    // `.notdef` is always kept.
+    assert_eq!(subset.num_glyphs(), 3);
+    assert_eq!(subset.glyph_for_char('A'), Ok(Some(1)));
+    assert_eq!(subset.glyph_for_char('B'), Ok(None));
+    assert_eq!(subset.glyph_for_char('C'), Ok(Some(2)));
+    for &(glyph_id, subset_glyph_id) in &[(0, 0), (1, 1), (3, 2)] {
+        assert_eq!(subset.outline(subset_glyph_id), font.outline(glyph_id));
+        assert_eq!(subset.advance_width(subset_glyph_id), font.advance_width(glyph_id));
+        assert_eq!(subset.glyph_name(subset_glyph_id), font.glyph_name(glyph_id));
+    }
+    assert_eq!(subset.glyph_name(1), Some("A"));
+}
+
+#[test]
+fn truetype_subset_keeps_outlines_and_metrics() {
+    let file = Mmap::open_path(TEST_FONT_PATH, Protection::Read).expect("Couldn't open test font");
+    let mut buffer = vec![];
+    let font = Font::new(unsafe { file.as_slice() }, &mut buffer).unwrap();
+    let glyphs: Vec<u16> = "ABC".chars().map(|character| {
+        font.glyph_for_char(character).unwrap().unwrap()
+    }).collect();
+
+    let subset_bytes = font.subset(&glyphs).unwrap();
+    let mut subset_buffer = vec![];
+    let subset = Font::new(&subset_bytes, &mut subset_buffer).unwrap();
+    assert!(subset.num_glyphs() < font.num_glyphs());
+    for (character, &glyph_id) in "ABC".chars().zip(&glyphs) {
+        let subset_glyph_id = subset.glyph_for_char(character).unwrap().unwrap();
+        assert_eq!(subset.outline(subset_glyph_id), font.outline(glyph_id));
+        assert_eq!(subset.advance_width(subset_glyph_id), font.advance_width(glyph_id));
+    }
+    assert_eq!(subset.glyph_for_char('D'), Ok(None));
+}
+
+#[test]
+fn subset_rejects_missing_glyphs() {
+    let bytes = sfnt(0, 0x10000, &truetype_tables(600));
+    let mut buffer = vec![];
+    let font = Font::new(&bytes, &mut buffer).unwrap();
+    assert_eq!(font.subset(&[2]).err(), Some(FontError::NoSuchGlyph));
+}
+
+#[test]
+fn font_table_subslices() {
+    let table = FontTable {
+        bytes: &[1, 2, 3, 4],
+    };
+    assert_eq!(table.subslice(1, 2), Ok(&[2, 3][..]));
+    assert_eq!(table.subslice(4, 0), Ok(&[][..]));
+    assert_eq!(table.subslice(3, 2), Err(FontError::UnexpectedEof));
+    assert_eq!(table.subslice(5, 0), Err(FontError::UnexpectedEof));
+    assert_eq!(table.subslice(2, usize::MAX), Err(FontError::UnexpectedEof));
+}
@@ -0,0 +1,52 @@
+/* Any copyright is dedicated to the Public Domain.
+ * http://creativecommons.org/publicdomain/zero/1.0/ */
+
+use error::FontError;
+use fuzzing;
+use std::fs::{self, File};
+use std::io::Read;
+
+static CORPUS_PATH: &'static str = "fuzz/corpus";
+
+// Reads the seed inputs of the given fuzz target.
+fn corpus(target: &str) -> Vec<Vec<u8>> {
+    let mut inputs = vec![];
+    for entry in fs::read_dir(format!("{}/{}", CORPUS_PATH, target)).unwrap() {
+        let mut input = vec![];
+        File::open(entry.unwrap().path()).unwrap().read_to_end(&mut input).unwrap();
+        inputs.push(input)
+    }
+    assert!(!inputs.is_empty());
+    inputs
+}
+
+// Runs the parser over each seed, every truncation of it, and copies with one byte replaced by
+// each of a few values that tend to break length and offset arithmetic. None of these may panic.
+fn check_corpus(target: &str, parse: fn(&[u8]) -> Result<(), FontError>) {
+    for input in corpus(target) {
+        assert_eq!(parse(&input), Ok(()));
+
+        for length in 0..input.len() {
+            let _ = parse(&input[..length]);
+        }
+
+        let mut mutated = input.clone();
+        for i in 0..input.len() {
+            for &byte in &[0x00, 0x01, 0x7f, 0x80, 0xff] {
+                mutated[i] = byte;
+                let _ = parse(&mutated);
+            }
+            mutated[i] = input[i]
+        }
+    }
+}
+
+#[test]
+fn cff_corpus() {
+    check_corpus("cff", fuzzing::parse_cff)
+}
+
+#[test]
+fn os_2_corpus() {
+    check_corpus("os_2", fuzzing::parse_os_2)
+}
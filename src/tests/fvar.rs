@@ -0,0 +1,102 @@
+/* Any copyright is dedicated to the Public Domain.
+ * http://creativecommons.org/publicdomain/zero/1.0/ */
+
+use error::FontError;
+use font::{Font, FontTable, NamedInstance, VariationAxis};
+use memmap::{Mmap, Protection};
+use tables::fvar::FvarTable;
+use util::{push_fixed, push_u16, tag};
+
+static TEST_FONT_PATH: &'static str = "resources/tests/nimbus-sans/NimbusSanL-Regu.ttf";
+
+// A font with weight and width axes and "Bold" and "Condensed" instances, only the first of
+// which has a PostScript name.
+fn fvar_table_bytes(major_version: u16) -> Vec<u8> {
+    let mut bytes = vec![];
+    for &value in &[major_version, 0, 16, 2, 2, 20, 2, 14] {
+        push_u16(&mut bytes, value)
+    }
+
+    for &(axis_tag, min, default, max, name_id) in &[(b"wght", 100.0, 400.0, 900.0, 256),
+                                                     (b"wdth", 75.0, 100.0, 100.0, 257)] {
+        bytes.extend_from_slice(axis_tag);
+        push_fixed(&mut bytes, min);
+        push_fixed(&mut bytes, default);
+        push_fixed(&mut bytes, max);
+        push_u16(&mut bytes, 0);
+        push_u16(&mut bytes, name_id)
+    }
+
+    for &(subfamily_name_id, weight, width, postscript_name_id) in &[(258, 700.0, 100.0, 259),
+                                                                     (260, 400.0, 75.0, 0xffff)] {
+        push_u16(&mut bytes, subfamily_name_id);
+        push_u16(&mut bytes, 0);
+        push_fixed(&mut bytes, weight);
+        push_fixed(&mut bytes, width);
+        push_u16(&mut bytes, postscript_name_id)
+    }
+    bytes
+}
+
+#[test]
+fn weight_and_width_axes() {
+    let bytes = fvar_table_bytes(1);
+    let fvar = FvarTable::new(FontTable {
+        bytes: &bytes,
+    }).unwrap();
+    assert_eq!(fvar.axes(), &[
+        VariationAxis {
+            tag: tag(b"wght"),
+            min_value: 100.0,
+            default_value: 400.0,
+            max_value: 900.0,
+            flags: 0,
+            axis_name_id: 256,
+        },
+        VariationAxis {
+            tag: tag(b"wdth"),
+            min_value: 75.0,
+            default_value: 100.0,
+            max_value: 100.0,
+            flags: 0,
+            axis_name_id: 257,
+        },
+    ]);
+}
+
+#[test]
+fn named_instances() {
+    let bytes = fvar_table_bytes(1);
+    let fvar = FvarTable::new(FontTable {
+        bytes: &bytes,
+    }).unwrap();
+    assert_eq!(fvar.named_instances(), &[
+        NamedInstance {
+            coordinates: vec![700.0, 100.0],
+            subfamily_name_id: 258,
+            postscript_name_id: Some(259),
+        },
+        NamedInstance {
+            coordinates: vec![400.0, 75.0],
+            subfamily_name_id: 260,
+            postscript_name_id: None,
+        },
+    ]);
+}
+
+#[test]
+fn unsupported_version() {
+    let bytes = fvar_table_bytes(2);
+    assert_eq!(FvarTable::new(FontTable {
+        bytes: &bytes,
+    }).err(), Some(FontError::UnsupportedFvarVersion));
+}
+
+#[test]
+fn static_font_has_no_axes() {
+    let file = Mmap::open_path(TEST_FONT_PATH, Protection::Read).expect("Couldn't open test font");
+    let mut buffer = vec![];
+    let font = Font::new(unsafe { file.as_slice() }, &mut buffer).unwrap();
+    assert!(font.variation_axes().is_empty());
+    assert!(font.named_instances().is_empty());
+}
@@ -0,0 +1,65 @@
+/* Any copyright is dedicated to the Public Domain.
+ * http://creativecommons.org/publicdomain/zero/1.0/ */
+
+use error::{ErrorContext, FontError};
+use font::{Font, FontTable};
+use memmap::{Mmap, Protection};
+use tables::gasp::{self, GaspTable};
+
+static TEST_FONT_PATH: &'static str = "resources/tests/nimbus-sans/NimbusSanL-Regu.ttf";
+
+// Version 1: gridfit up to 8 ppem, symmetric smoothing up to 20, and everything above that.
+static GASP: [u8; 16] = [0, 1, 0, 3, 0, 8, 0, 0x05, 0, 20, 0, 0x0a, 0xff, 0xff, 0, 0x0f];
+
+#[test]
+fn behavior_changes_at_range_boundaries() {
+    let table = GaspTable::new(FontTable {
+        bytes: &GASP,
+    }).unwrap();
+    assert_eq!(table.behavior_for_ppem(0), gasp::GRIDFIT | gasp::SYMMETRIC_GRIDFIT);
+    assert_eq!(table.behavior_for_ppem(8), gasp::GRIDFIT | gasp::SYMMETRIC_GRIDFIT);
+    assert_eq!(table.behavior_for_ppem(9), gasp::DOGRAY | gasp::SYMMETRIC_SMOOTHING);
+    assert_eq!(table.behavior_for_ppem(20), gasp::DOGRAY | gasp::SYMMETRIC_SMOOTHING);
+    assert_eq!(table.behavior_for_ppem(21), gasp::GaspBehavior::all());
+    assert_eq!(table.behavior_for_ppem(0xffff), gasp::GaspBehavior::all());
+}
+
+#[test]
+fn sizes_past_the_last_range_have_no_flags() {
+    let table = GaspTable::new(FontTable {
+        bytes: &GASP[0..12],
+    });
+    assert_eq!(table.err(), Some(FontError::UnexpectedEofIn(ErrorContext {
+        table: gasp::TAG,
+        field: "ranges",
+    })));
+
+    let mut bytes = GASP[0..12].to_vec();
+    bytes[3] = 2;
+    let table = GaspTable::new(FontTable {
+        bytes: &bytes,
+    }).unwrap();
+    assert_eq!(table.behavior_for_ppem(21), gasp::GaspBehavior::empty());
+}
+
+// Version 0 tables only define the first two flags.
+#[test]
+fn version_0_ignores_symmetric_flags() {
+    let mut bytes = GASP.to_vec();
+    bytes[1] = 0;
+    let table = GaspTable::new(FontTable {
+        bytes: &bytes,
+    }).unwrap();
+    assert_eq!(table.behavior_for_ppem(8), gasp::GRIDFIT);
+    assert_eq!(table.behavior_for_ppem(100), gasp::GRIDFIT | gasp::DOGRAY);
+}
+
+#[test]
+fn test_font_gasp_behavior() {
+    let file = Mmap::open_path(TEST_FONT_PATH, Protection::Read).expect("Couldn't open test font");
+    let mut buffer = vec![];
+    let font = Font::new(unsafe { file.as_slice() }, &mut buffer).unwrap();
+    assert_eq!(font.gasp_behavior(8), Some(gasp::DOGRAY));
+    assert_eq!(font.gasp_behavior(12), Some(gasp::GRIDFIT));
+    assert_eq!(font.gasp_behavior(72), Some(gasp::GRIDFIT | gasp::DOGRAY));
+}
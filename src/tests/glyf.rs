@@ -0,0 +1,222 @@
+/* Any copyright is dedicated to the Public Domain.
+ * http://creativecommons.org/publicdomain/zero/1.0/ */
+
+use charmap::CodepointRange;
+use error::FontError;
+use euclid::Matrix2D;
+use font::{Font, FontTable, Point, PointKind};
+use memmap::{Mmap, Protection};
+use tables::glyf::GlyfTable;
+use tables::loca::LocaTable;
+use tests::head::head_table;
+
+static TEST_FONT_PATH: &'static str = "resources/tests/nimbus-sans/NimbusSanL-Regu.ttf";
+
+// A glyph with a square and a triangle, exercising the repeat flag and the short, same, and long
+// coordinate encodings.
+static TWO_CONTOUR_GLYPH: [u8; 33] = [
+    0, 2,                               // numberOfContours
+    0, 0, 0, 0, 0, 100, 0, 100,         // xMin, yMin, xMax, yMax
+    0, 3, 0, 6,                         // endPtsOfContours
+    0, 0,                               // instructionLength
+    49, 51, 53, 35, 31, 1, 3,           // flags
+    100, 100, 30, 40, 20,               // xCoordinates
+    100, 70, 10, 0, 40,                 // yCoordinates
+];
+
+// A glyph with one contour of two on-curve and two off-curve points, which imply another on-curve
+// point between them.
+static QUADRATIC_GLYPH: [u8; 21] = [
+    0, 1,                               // numberOfContours
+    0, 0, 0, 0, 0, 100, 0, 100,         // xMin, yMin, xMax, yMax
+    0, 3,                               // endPtsOfContours
+    0, 0,                               // instructionLength
+    49, 50, 52, 35,                     // flags
+    100, 100,                           // xCoordinates
+    100,                                // yCoordinates
+];
+
+// Returns the points of a glyph in a `glyf` table containing the given glyphs.
+fn glyph_points(glyphs: &[&[u8]], glyph_id: u16) -> Result<Vec<Point>, FontError> {
+    let (mut glyf, mut loca) = (vec![], vec![0, 0, 0, 0]);
+    for glyph in glyphs {
+        glyf.extend_from_slice(glyph);
+        let offset = glyf.len();
+        loca.extend_from_slice(&[(offset >> 24) as u8,
+                                 (offset >> 16) as u8,
+                                 (offset >> 8) as u8,
+                                 offset as u8]);
+    }
+
+    let loca_table = try!(LocaTable::new(FontTable {
+        bytes: &loca,
+    }, &head_table(1)));
+    let glyf_table = GlyfTable::new(FontTable {
+        bytes: &glyf,
+    });
+
+    let mut points = vec![];
+    try!(glyf_table.for_each_point(&loca_table, glyph_id, |point| {
+        points.push(*point)
+    }));
+    Ok(points)
+}
+
+#[test]
+fn simple_glyph_coordinates() {
+    let positions: Vec<_> = glyph_points(&[&TWO_CONTOUR_GLYPH], 0).unwrap().iter().map(|point| {
+        assert_eq!(point.kind, PointKind::OnCurve);
+        (point.index_in_contour, point.position.x, point.position.y)
+    }).collect();
+    assert_eq!(positions, vec![
+        (0, 0, 0), (1, 100, 0), (2, 100, 100), (3, 0, 100), (4, 0, 0),
+        (0, 30, 30), (1, 70, 30), (2, 70, 20), (3, 50, 60), (4, 30, 30),
+    ]);
+}
+
+#[test]
+fn off_curve_points_are_quadratic_controls() {
+    let points: Vec<_> = glyph_points(&[&QUADRATIC_GLYPH], 0).unwrap().iter().map(|point| {
+        (point.kind, point.position.x, point.position.y)
+    }).collect();
+    assert_eq!(points, vec![
+        (PointKind::OnCurve, 0, 0),
+        (PointKind::QuadControl, 100, 0),
+        (PointKind::OnCurve, 100, 50),
+        (PointKind::QuadControl, 100, 100),
+        (PointKind::OnCurve, 0, 100),
+        (PointKind::OnCurve, 0, 0),
+    ]);
+}
+
+#[test]
+fn flattened_simple_glyph() {
+    let loca = [0, 0, 0, 0, 0, 0, 0, 33];
+    let loca_table = LocaTable::new(FontTable {
+        bytes: &loca,
+    }, &head_table(1)).unwrap();
+    let glyf_table = GlyfTable::new(FontTable {
+        bytes: &TWO_CONTOUR_GLYPH,
+    });
+
+    let contours = glyf_table.flatten(&loca_table, 0, 1.0).unwrap();
+    let points: Vec<Vec<_>> = contours.iter().map(|contour| {
+        contour.points.iter().map(|point| (point.x, point.y)).collect()
+    }).collect();
+    assert_eq!(points, vec![
+        vec![(0.0, 0.0), (100.0, 0.0), (100.0, 100.0), (0.0, 100.0)],
+        vec![(30.0, 30.0), (70.0, 30.0), (70.0, 20.0), (50.0, 60.0)],
+    ]);
+}
+
+#[test]
+fn transformed_simple_glyph() {
+    let loca = [0, 0, 0, 0, 0, 0, 0, 33];
+    let loca_table = LocaTable::new(FontTable {
+        bytes: &loca,
+    }, &head_table(1)).unwrap();
+    let glyf_table = GlyfTable::new(FontTable {
+        bytes: &TWO_CONTOUR_GLYPH,
+    });
+
+    let transformed_positions = |transform: &Matrix2D<f32>| {
+        let mut positions = vec![];
+        glyf_table.for_each_point_transformed(&loca_table, 0, transform, |point| {
+            positions.push((point.position.x, point.position.y))
+        }).unwrap();
+        positions
+    };
+
+    // Scaling from 2048 units per em to 16 pixels per em keeps fractional positions.
+    let scaled = transformed_positions(&Matrix2D::create_scale(16.0 / 2048.0, 16.0 / 2048.0));
+    assert_eq!(&scaled[5..9], &[
+        (0.234375, 0.234375), (0.546875, 0.234375), (0.546875, 0.15625), (0.390625, 0.46875),
+    ]);
+
+    // A shear for a synthetic oblique moves points right in proportion to their height.
+    let sheared = transformed_positions(&Matrix2D::row_major(1.0, 0.0, 0.25, 1.0, 0.0, 0.0));
+    assert_eq!(&sheared[0..4], &[(0.0, 0.0), (100.0, 0.0), (125.0, 100.0), (25.0, 100.0)]);
+}
+
+#[test]
+fn empty_glyph_has_no_points() {
+    assert_eq!(glyph_points(&[&TWO_CONTOUR_GLYPH, &[]], 1), Ok(vec![]));
+}
+
+#[test]
+fn glyphs_past_the_end_of_the_table_are_rejected() {
+    let loca = [0, 0, 0, 0, 0, 0, 0, 33, 0, 0, 0, 100];
+    let loca_table = LocaTable::new(FontTable {
+        bytes: &loca,
+    }, &head_table(1)).unwrap();
+    let glyf_table = GlyfTable::new(FontTable {
+        bytes: &TWO_CONTOUR_GLYPH,
+    });
+    assert!(glyf_table.for_each_point(&loca_table, 0, |_| {}).is_ok());
+    assert_eq!(glyf_table.for_each_point(&loca_table, 1, |_| {}), Err(FontError::UnexpectedEof));
+}
+
+#[test]
+fn malformed_endpoints_are_rejected() {
+    // Make the second contour end before the first.
+    let mut glyph = TWO_CONTOUR_GLYPH;
+    glyph[13] = 1;
+    assert_eq!(glyph_points(&[&glyph], 0), Err(FontError::GlyfMalformedGlyph));
+}
+
+// Each contour of "o" in the test font closes back at its first point.
+#[test]
+fn real_glyph_contours_are_closed() {
+    let file = Mmap::open_path(TEST_FONT_PATH, Protection::Read).expect("Couldn't open test font");
+    let mut buffer = vec![];
+    let font = Font::new(unsafe { file.as_slice() }, &mut buffer).unwrap();
+    let codepoint_ranges = [CodepointRange::new('o' as u32, 'o' as u32)];
+    let glyph_mapping = font.glyph_mapping_for_codepoint_ranges(&codepoint_ranges).unwrap();
+    let (_, glyph_id) = glyph_mapping.iter().next().unwrap();
+
+    let mut contours: Vec<Vec<Point>> = vec![];
+    font.for_each_point(glyph_id, |point| {
+        if point.index_in_contour == 0 {
+            contours.push(vec![])
+        }
+        contours.last_mut().unwrap().push(*point)
+    }).unwrap();
+
+    assert_eq!(contours.len(), 2);
+    for contour in &contours {
+        let first_on_curve = contour.iter().find(|point| point.kind == PointKind::OnCurve).unwrap();
+        let last = contour.last().unwrap();
+        assert_eq!((last.kind, last.position), (PointKind::OnCurve, first_on_curve.position));
+    }
+}
+
+#[test]
+fn composite_glyphs_transform_components() {
+    // Glyph 1 is glyph 0 plus a half-size copy of it offset by (10, 200), like an accent. Glyph 2
+    // nests glyph 1 and offsets it by (-5, 5); glyph 3 refers to itself.
+    let composite = [
+        0xff, 0xff, 0, 0, 0, 0, 0, 0, 0, 0,
+        0, 34, 0, 0, 0, 0,                      // ARGS_ARE_XY_VALUES | MORE_COMPONENTS
+        0, 11, 0, 0, 0, 10, 0, 200, 0x20, 0,    // ARG_1_AND_2_ARE_WORDS | ... | WE_HAVE_A_SCALE
+    ];
+    let nested = [0xff, 0xff, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2, 0, 1, 0xfb, 5];
+    let recursive = [0xff, 0xff, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2, 0, 3, 0, 0];
+    let glyphs: [&[u8]; 4] = [&TWO_CONTOUR_GLYPH, &composite, &nested, &recursive];
+
+    let base = glyph_points(&glyphs, 0).unwrap();
+    let mut expected = base.clone();
+    for mut point in base {
+        point.position.x = point.position.x / 2 + 10;
+        point.position.y = point.position.y / 2 + 200;
+        expected.push(point)
+    }
+    assert_eq!(glyph_points(&glyphs, 1).unwrap(), expected);
+
+    for point in &mut expected {
+        point.position.x -= 5;
+        point.position.y += 5;
+    }
+    assert_eq!(glyph_points(&glyphs, 2).unwrap(), expected);
+
+    assert_eq!(glyph_points(&glyphs, 3), Err(FontError::GlyfCompositeNestingTooDeep));
+}
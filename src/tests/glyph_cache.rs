@@ -0,0 +1,65 @@
+/* Any copyright is dedicated to the Public Domain.
+ * http://creativecommons.org/publicdomain/zero/1.0/ */
+
+use font::{Font, Point};
+use glyph_cache::GlyphCache;
+use memmap::{Mmap, Protection};
+
+static TEST_FONT_PATH: &'static str = "resources/tests/nimbus-sans/NimbusSanL-Regu.ttf";
+
+fn glyph_points(font: &Font, glyph_id: u16) -> Vec<Point> {
+    let mut points = vec![];
+    font.for_each_point(glyph_id, |point| points.push(*point)).unwrap();
+    points
+}
+
+#[test]
+fn repeated_lookups_are_cached() {
+    let file = Mmap::open_path(TEST_FONT_PATH, Protection::Read).expect("Couldn't open test font");
+    let mut buffer = vec![];
+    let font = Font::new(unsafe { file.as_slice() }, &mut buffer).unwrap();
+    let glyph_id = font.glyph_for_char('g').unwrap().unwrap();
+
+    let mut cache = GlyphCache::new(&font, 16);
+    assert_eq!(cache.points(glyph_id).unwrap(), &glyph_points(&font, glyph_id)[..]);
+    assert_eq!(cache.misses(), 1);
+
+    let mut points = vec![];
+    cache.for_each_point(glyph_id, |point| points.push(*point)).unwrap();
+    assert_eq!(points, glyph_points(&font, glyph_id));
+    assert_eq!(cache.misses(), 1);
+
+    let bounds = font.glyph_bounds(glyph_id).unwrap();
+    for _ in 0..2 {
+        let cached = cache.glyph_bounds(glyph_id).unwrap();
+        assert_eq!((cached.left, cached.bottom, cached.right, cached.top),
+                   (bounds.left, bounds.bottom, bounds.right, bounds.top));
+    }
+    assert_eq!(cache.misses(), 2);
+    assert_eq!(cache.len(), 1);
+}
+
+#[test]
+fn least_recently_used_glyphs_are_evicted() {
+    let file = Mmap::open_path(TEST_FONT_PATH, Protection::Read).expect("Couldn't open test font");
+    let mut buffer = vec![];
+    let font = Font::new(unsafe { file.as_slice() }, &mut buffer).unwrap();
+
+    let mut cache = GlyphCache::new(&font, 2);
+    for &glyph_id in &[1, 2, 1, 3] {
+        cache.points(glyph_id).unwrap();
+    }
+    assert_eq!((cache.len(), cache.misses()), (2, 3));
+
+    // Glyph 2 was used longest ago, so it made room for glyph 3.
+    cache.points(1).unwrap();
+    cache.points(3).unwrap();
+    assert_eq!(cache.misses(), 3);
+    cache.points(2).unwrap();
+    assert_eq!(cache.misses(), 4);
+
+    cache.clear();
+    assert!(cache.is_empty());
+    assert!(cache.points(font.num_glyphs()).is_err());
+    assert!(cache.is_empty());
+}
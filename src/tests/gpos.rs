@@ -0,0 +1,218 @@
+/* Any copyright is dedicated to the Public Domain.
+ * http://creativecommons.org/publicdomain/zero/1.0/ */
+
+use font::FontTable;
+use tables::gpos::GposTable;
+use util::push_u16;
+
+const GLYPH_A: u16 = 36;
+const GLYPH_T: u16 = 55;
+const GLYPH_V: u16 = 57;
+const GLYPH_Y: u16 = 60;
+const GLYPH_A_LOWER: u16 = 68;
+const GLYPH_B_LOWER: u16 = 69;
+const GLYPH_E_LOWER: u16 = 72;
+const GLYPH_O_LOWER: u16 = 82;
+
+const VALUE_FORMAT_X_ADVANCE: u16 = 0x0004;
+const VALUE_FORMAT_X_PLACEMENT_AND_ADVANCE: u16 = 0x0005;
+
+// Builds a GPOS table with a single script whose default language system has a `kern` feature
+// containing one pair adjustment lookup per subtable.
+fn gpos_table_bytes(script_tag: &[u8; 4], subtables: &[Vec<u8>]) -> Vec<u8> {
+    let lookup_count = subtables.len() as u16;
+
+    // Header.
+    let mut bytes = vec![];
+    let feature_list_offset = 10 + 20;
+    let lookup_list_offset = feature_list_offset + 12 + 2 * lookup_count;
+    for &value in &[1, 0, 10, feature_list_offset, lookup_list_offset] {
+        push_u16(&mut bytes, value)
+    }
+
+    // Script list, script, and language system.
+    push_u16(&mut bytes, 1);
+    bytes.extend_from_slice(script_tag);
+    for &value in &[8, 4, 0, 0, 0xffff, 1, 0] {
+        push_u16(&mut bytes, value)
+    }
+
+    // Feature list and feature.
+    push_u16(&mut bytes, 1);
+    bytes.extend_from_slice(b"kern");
+    for &value in &[8, 0, lookup_count] {
+        push_u16(&mut bytes, value)
+    }
+    for lookup_index in 0..lookup_count {
+        push_u16(&mut bytes, lookup_index)
+    }
+
+    // Lookup list and lookups.
+    push_u16(&mut bytes, lookup_count);
+    let mut lookup_offset = 2 + 2 * lookup_count;
+    for subtable in subtables {
+        push_u16(&mut bytes, lookup_offset);
+        lookup_offset += 8 + subtable.len() as u16
+    }
+    for subtable in subtables {
+        for &value in &[2, 0, 1, 8] {
+            push_u16(&mut bytes, value)
+        }
+        bytes.extend_from_slice(subtable)
+    }
+    bytes
+}
+
+// Builds a format 1 pair adjustment subtable from (first glyph, second glyph, X advance) pairs,
+// which must be sorted. The first value records also contain a zero X placement.
+fn pair_pos_format_1(pairs: &[(u16, u16, i16)]) -> Vec<u8> {
+    let mut first_glyphs: Vec<u16> = pairs.iter().map(|&(first, _, _)| first).collect();
+    first_glyphs.dedup();
+
+    let mut pair_sets = vec![];
+    let mut pair_set_offsets = vec![];
+    let mut offset = 10 + 2 * first_glyphs.len();
+    for &first_glyph in &first_glyphs {
+        let pairs: Vec<_> = pairs.iter().filter(|&&(first, _, _)| first == first_glyph).collect();
+        let mut pair_set = vec![];
+        push_u16(&mut pair_set, pairs.len() as u16);
+        for &&(_, second, x_advance) in &pairs {
+            push_u16(&mut pair_set, second);
+            push_u16(&mut pair_set, 0);
+            push_u16(&mut pair_set, x_advance as u16)
+        }
+        pair_set_offsets.push(offset as u16);
+        offset += pair_set.len();
+        pair_sets.extend_from_slice(&pair_set)
+    }
+
+    let mut bytes = vec![];
+    for &value in &[1, offset as u16, VALUE_FORMAT_X_PLACEMENT_AND_ADVANCE, 0,
+                    first_glyphs.len() as u16] {
+        push_u16(&mut bytes, value)
+    }
+    for &pair_set_offset in &pair_set_offsets {
+        push_u16(&mut bytes, pair_set_offset)
+    }
+    bytes.extend_from_slice(&pair_sets);
+
+    // Format 1 coverage.
+    push_u16(&mut bytes, 1);
+    push_u16(&mut bytes, first_glyphs.len() as u16);
+    for &first_glyph in &first_glyphs {
+        push_u16(&mut bytes, first_glyph)
+    }
+    bytes
+}
+
+// Builds a format 2 pair adjustment subtable. The first glyphs are covered by a single range
+// and classified by a format 2 class definition; the second glyphs are classified by a format 1
+// class definition starting at `a`.
+fn pair_pos_format_2(covered_range: (u16, u16),
+                     first_classes: &[(u16, u16, u16)],
+                     second_classes: &[u16],
+                     x_advances: &[&[i16]])
+                     -> Vec<u8> {
+    let class_1_count = x_advances.len() as u16;
+    let class_2_count = x_advances[0].len() as u16;
+    let class_def_1_offset = 16 + 2 * class_1_count * class_2_count;
+    let class_def_2_offset = class_def_1_offset + 4 + 6 * first_classes.len() as u16;
+    let coverage_offset = class_def_2_offset + 6 + 2 * second_classes.len() as u16;
+
+    let mut bytes = vec![];
+    for &value in &[2, coverage_offset, VALUE_FORMAT_X_ADVANCE, 0, class_def_1_offset,
+                    class_def_2_offset, class_1_count, class_2_count] {
+        push_u16(&mut bytes, value)
+    }
+    for class_2_values in x_advances {
+        for &x_advance in class_2_values.iter() {
+            push_u16(&mut bytes, x_advance as u16)
+        }
+    }
+
+    // Format 2 class definition.
+    push_u16(&mut bytes, 2);
+    push_u16(&mut bytes, first_classes.len() as u16);
+    for &(start, end, class) in first_classes {
+        push_u16(&mut bytes, start);
+        push_u16(&mut bytes, end);
+        push_u16(&mut bytes, class)
+    }
+
+    // Format 1 class definition.
+    push_u16(&mut bytes, 1);
+    push_u16(&mut bytes, GLYPH_A_LOWER);
+    push_u16(&mut bytes, second_classes.len() as u16);
+    for &class in second_classes {
+        push_u16(&mut bytes, class)
+    }
+
+    // Format 2 coverage.
+    for &value in &[2, 1, covered_range.0, covered_range.1, 0] {
+        push_u16(&mut bytes, value)
+    }
+    bytes
+}
+
+// Classes of the lowercase letters a through o: 1 for round letters, 0 otherwise.
+fn round_letter_classes() -> Vec<u16> {
+    (GLYPH_A_LOWER..(GLYPH_O_LOWER + 1)).map(|glyph| {
+        match glyph {
+            GLYPH_A_LOWER | GLYPH_E_LOWER | GLYPH_O_LOWER => 1,
+            _ => 0,
+        }
+    }).collect()
+}
+
+#[test]
+fn pair_pos_format_1_kerning() {
+    let bytes = gpos_table_bytes(b"DFLT", &[
+        pair_pos_format_1(&[(GLYPH_A, GLYPH_T, -50), (GLYPH_A, GLYPH_V, -80),
+                            (GLYPH_T, GLYPH_O_LOWER, -60)]),
+    ]);
+    let gpos = GposTable::new(FontTable {
+        bytes: &bytes,
+    }).unwrap();
+    assert!(gpos.has_kerning());
+    assert_eq!(gpos.kerning_for_glyph_pair(GLYPH_A, GLYPH_V), Ok(-80));
+    assert_eq!(gpos.kerning_for_glyph_pair(GLYPH_A, GLYPH_T), Ok(-50));
+    assert_eq!(gpos.kerning_for_glyph_pair(GLYPH_T, GLYPH_O_LOWER), Ok(-60));
+    assert_eq!(gpos.kerning_for_glyph_pair(GLYPH_V, GLYPH_A), Ok(0));
+    assert_eq!(gpos.kerning_for_glyph_pair(GLYPH_T, GLYPH_A), Ok(0));
+}
+
+#[test]
+fn pair_pos_format_2_kerning() {
+    let bytes = gpos_table_bytes(b"latn", &[
+        pair_pos_format_2((GLYPH_A, GLYPH_Y),
+                          &[(GLYPH_T, GLYPH_T, 1), (GLYPH_V, GLYPH_Y, 2)],
+                          &round_letter_classes(),
+                          &[&[0, 0], &[0, -70], &[0, -40]]),
+    ]);
+    let gpos = GposTable::new(FontTable {
+        bytes: &bytes,
+    }).unwrap();
+    assert_eq!(gpos.kerning_for_glyph_pair(GLYPH_T, GLYPH_O_LOWER), Ok(-70));
+    assert_eq!(gpos.kerning_for_glyph_pair(GLYPH_T, GLYPH_E_LOWER), Ok(-70));
+    assert_eq!(gpos.kerning_for_glyph_pair(GLYPH_Y, GLYPH_A_LOWER), Ok(-40));
+    assert_eq!(gpos.kerning_for_glyph_pair(GLYPH_T, GLYPH_B_LOWER), Ok(0));
+    assert_eq!(gpos.kerning_for_glyph_pair(GLYPH_A, GLYPH_O_LOWER), Ok(0));
+    assert_eq!(gpos.kerning_for_glyph_pair(GLYPH_A_LOWER, GLYPH_O_LOWER), Ok(0));
+}
+
+// Adjustments from separate lookups accumulate.
+#[test]
+fn multiple_lookups() {
+    let bytes = gpos_table_bytes(b"DFLT", &[
+        pair_pos_format_1(&[(GLYPH_T, GLYPH_O_LOWER, -30)]),
+        pair_pos_format_2((GLYPH_T, GLYPH_T),
+                          &[(GLYPH_T, GLYPH_T, 1)],
+                          &round_letter_classes(),
+                          &[&[0, 0], &[0, -70]]),
+    ]);
+    let gpos = GposTable::new(FontTable {
+        bytes: &bytes,
+    }).unwrap();
+    assert_eq!(gpos.kerning_for_glyph_pair(GLYPH_T, GLYPH_O_LOWER), Ok(-100));
+    assert_eq!(gpos.kerning_for_glyph_pair(GLYPH_T, GLYPH_A_LOWER), Ok(-70));
+}
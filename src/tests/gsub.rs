@@ -0,0 +1,216 @@
+/* Any copyright is dedicated to the Public Domain.
+ * http://creativecommons.org/publicdomain/zero/1.0/ */
+
+use font::FontTable;
+use tables::gsub::GsubTable;
+use util::{push_u16, tag};
+
+const GLYPH_A: u16 = 68;
+const GLYPH_B: u16 = 69;
+const GLYPH_F: u16 = 73;
+const GLYPH_I: u16 = 76;
+const GLYPH_L: u16 = 79;
+const GLYPH_FI: u16 = 192;
+const GLYPH_FL: u16 = 193;
+const GLYPH_FFI: u16 = 300;
+const GLYPH_A_SMALL_CAP: u16 = 400;
+const GLYPH_B_SMALL_CAP: u16 = 401;
+const GLYPH_A_ALTERNATE: u16 = 500;
+
+const LOOKUP_TYPE_SINGLE_SUBSTITUTION: u16 = 1;
+const LOOKUP_TYPE_LIGATURE_SUBSTITUTION: u16 = 4;
+
+// Builds a GSUB table with a default script whose default language system enables one feature
+// per (feature tag, lookup type, subtable) entry, each with a single lookup.
+fn gsub_table_bytes(lookups: &[(&[u8; 4], u16, Vec<u8>)]) -> Vec<u8> {
+    let count = lookups.len() as u16;
+
+    // Header.
+    let mut bytes = vec![];
+    let feature_list_offset = 10 + 18 + 2 * count;
+    let lookup_list_offset = feature_list_offset + 2 + 12 * count;
+    for &value in &[1, 0, 10, feature_list_offset, lookup_list_offset] {
+        push_u16(&mut bytes, value)
+    }
+
+    // Script list, script, and language system.
+    push_u16(&mut bytes, 1);
+    bytes.extend_from_slice(b"DFLT");
+    for &value in &[8, 4, 0, 0, 0xffff, count] {
+        push_u16(&mut bytes, value)
+    }
+    for feature_index in 0..count {
+        push_u16(&mut bytes, feature_index)
+    }
+
+    // Feature list and features.
+    push_u16(&mut bytes, count);
+    for (index, &(feature_tag, _, _)) in lookups.iter().enumerate() {
+        bytes.extend_from_slice(feature_tag);
+        push_u16(&mut bytes, 2 + 6 * count + 6 * index as u16)
+    }
+    for lookup_index in 0..count {
+        for &value in &[0, 1, lookup_index] {
+            push_u16(&mut bytes, value)
+        }
+    }
+
+    // Lookup list and lookups.
+    push_u16(&mut bytes, count);
+    let mut lookup_offset = 2 + 2 * count;
+    for &(_, _, ref subtable) in lookups {
+        push_u16(&mut bytes, lookup_offset);
+        lookup_offset += 8 + subtable.len() as u16
+    }
+    for &(_, lookup_type, ref subtable) in lookups {
+        for &value in &[lookup_type, 0, 1, 8] {
+            push_u16(&mut bytes, value)
+        }
+        bytes.extend_from_slice(subtable)
+    }
+    bytes
+}
+
+fn coverage_format_1(glyphs: &[u16]) -> Vec<u8> {
+    let mut bytes = vec![];
+    push_u16(&mut bytes, 1);
+    push_u16(&mut bytes, glyphs.len() as u16);
+    for &glyph in glyphs {
+        push_u16(&mut bytes, glyph)
+    }
+    bytes
+}
+
+// A format 1 single substitution subtable that adds a delta to every glyph in a range.
+fn single_subst_format_1(first: u16, last: u16, delta: i16) -> Vec<u8> {
+    let mut bytes = vec![];
+    for &value in &[1, 6, delta as u16, 2, 1, first, last, 0] {
+        push_u16(&mut bytes, value)
+    }
+    bytes
+}
+
+// A format 2 single substitution subtable from (glyph, substitute) pairs sorted by glyph.
+fn single_subst_format_2(substitutions: &[(u16, u16)]) -> Vec<u8> {
+    let mut bytes = vec![];
+    push_u16(&mut bytes, 2);
+    push_u16(&mut bytes, 6 + 2 * substitutions.len() as u16);
+    push_u16(&mut bytes, substitutions.len() as u16);
+    for &(_, substitute) in substitutions {
+        push_u16(&mut bytes, substitute)
+    }
+    let glyphs: Vec<u16> = substitutions.iter().map(|&(glyph, _)| glyph).collect();
+    bytes.extend_from_slice(&coverage_format_1(&glyphs));
+    bytes
+}
+
+// A ligature substitution subtable from (components, ligature) pairs, grouped by first component
+// in glyph order and in order of preference within each group.
+fn ligature_subst(ligatures: &[(&[u16], u16)]) -> Vec<u8> {
+    let mut first_glyphs: Vec<u16> = ligatures.iter().map(|&(components, _)| components[0])
+                                                     .collect();
+    first_glyphs.dedup();
+
+    let mut ligature_sets = vec![];
+    let mut ligature_set_offsets = vec![];
+    for &first_glyph in &first_glyphs {
+        let ligatures: Vec<_> = ligatures.iter().filter(|&&(components, _)| {
+            components[0] == first_glyph
+        }).collect();
+
+        let mut ligature_set = vec![];
+        push_u16(&mut ligature_set, ligatures.len() as u16);
+        let mut ligature_offset = 2 + 2 * ligatures.len() as u16;
+        for &&(components, _) in &ligatures {
+            push_u16(&mut ligature_set, ligature_offset);
+            ligature_offset += 2 + 2 * components.len() as u16
+        }
+        for &&(components, ligature) in &ligatures {
+            push_u16(&mut ligature_set, ligature);
+            push_u16(&mut ligature_set, components.len() as u16);
+            for &component in &components[1..] {
+                push_u16(&mut ligature_set, component)
+            }
+        }
+
+        ligature_set_offsets.push(ligature_sets.len());
+        ligature_sets.extend_from_slice(&ligature_set)
+    }
+
+    let header_size = 6 + 2 * first_glyphs.len();
+    let mut bytes = vec![];
+    push_u16(&mut bytes, 1);
+    push_u16(&mut bytes, (header_size + ligature_sets.len()) as u16);
+    push_u16(&mut bytes, first_glyphs.len() as u16);
+    for &offset in &ligature_set_offsets {
+        push_u16(&mut bytes, (header_size + offset) as u16)
+    }
+    bytes.extend_from_slice(&ligature_sets);
+    bytes.extend_from_slice(&coverage_format_1(&first_glyphs));
+    bytes
+}
+
+fn test_gsub_table_bytes() -> Vec<u8> {
+    gsub_table_bytes(&[
+        (b"liga", LOOKUP_TYPE_LIGATURE_SUBSTITUTION, ligature_subst(&[
+            (&[GLYPH_F, GLYPH_F, GLYPH_I], GLYPH_FFI),
+            (&[GLYPH_F, GLYPH_I], GLYPH_FI),
+            (&[GLYPH_F, GLYPH_L], GLYPH_FL),
+        ])),
+        (b"smcp", LOOKUP_TYPE_SINGLE_SUBSTITUTION,
+         single_subst_format_1(GLYPH_A, GLYPH_B, (GLYPH_A_SMALL_CAP - GLYPH_A) as i16)),
+        (b"salt", LOOKUP_TYPE_SINGLE_SUBSTITUTION,
+         single_subst_format_2(&[(GLYPH_A, GLYPH_A_ALTERNATE)])),
+    ])
+}
+
+#[test]
+fn liga_produces_fi_ligature() {
+    let bytes = test_gsub_table_bytes();
+    let gsub = GsubTable::new(FontTable {
+        bytes: &bytes,
+    }).unwrap();
+    let liga = [tag(b"liga")];
+
+    assert_eq!(gsub.substitute(&[GLYPH_F, GLYPH_I], &liga), Ok(vec![GLYPH_FI]));
+    assert_eq!(gsub.substitute(&[GLYPH_A, GLYPH_F, GLYPH_I, GLYPH_B], &liga),
+               Ok(vec![GLYPH_A, GLYPH_FI, GLYPH_B]));
+    assert_eq!(gsub.substitute(&[GLYPH_F, GLYPH_F, GLYPH_I], &liga), Ok(vec![GLYPH_FFI]));
+    assert_eq!(gsub.substitute(&[GLYPH_F, GLYPH_F, GLYPH_L], &liga),
+               Ok(vec![GLYPH_F, GLYPH_FL]));
+    assert_eq!(gsub.substitute(&[GLYPH_F], &liga), Ok(vec![GLYPH_F]));
+
+    // Without the feature, nothing happens.
+    assert_eq!(gsub.substitute(&[GLYPH_F, GLYPH_I], &[]), Ok(vec![GLYPH_F, GLYPH_I]));
+}
+
+#[test]
+fn ligatures_keep_the_cluster_of_their_first_component() {
+    let bytes = test_gsub_table_bytes();
+    let gsub = GsubTable::new(FontTable {
+        bytes: &bytes,
+    }).unwrap();
+
+    let glyphs = [(GLYPH_A, 0), (GLYPH_F, 1), (GLYPH_F, 2), (GLYPH_I, 3), (GLYPH_B, 4)];
+    assert_eq!(gsub.substitute_with_clusters(&glyphs, &[tag(b"liga"), tag(b"smcp")]),
+               Ok(vec![(GLYPH_A_SMALL_CAP, 0), (GLYPH_FFI, 1), (GLYPH_B_SMALL_CAP, 4)]));
+    assert_eq!(gsub.substitute_with_clusters(&glyphs[1..3], &[tag(b"liga")]),
+               Ok(vec![(GLYPH_F, 1), (GLYPH_F, 2)]));
+}
+
+#[test]
+fn single_substitutions() {
+    let bytes = test_gsub_table_bytes();
+    let gsub = GsubTable::new(FontTable {
+        bytes: &bytes,
+    }).unwrap();
+
+    assert_eq!(gsub.substitute(&[GLYPH_A, GLYPH_B, GLYPH_F], &[tag(b"smcp")]),
+               Ok(vec![GLYPH_A_SMALL_CAP, GLYPH_B_SMALL_CAP, GLYPH_F]));
+    assert_eq!(gsub.substitute(&[GLYPH_A, GLYPH_B], &[tag(b"salt")]),
+               Ok(vec![GLYPH_A_ALTERNATE, GLYPH_B]));
+
+    // Lookups apply in lookup list order, so small caps win over the alternate.
+    assert_eq!(gsub.substitute(&[GLYPH_A], &[tag(b"salt"), tag(b"smcp")]),
+               Ok(vec![GLYPH_A_SMALL_CAP]));
+}
@@ -0,0 +1,155 @@
+/* Any copyright is dedicated to the Public Domain.
+ * http://creativecommons.org/publicdomain/zero/1.0/ */
+
+use euclid::Point2D;
+use font::{Font, FontTable, Point, VariationAxis};
+use memmap::{Mmap, Protection};
+use tables::glyf::GlyfTable;
+use tables::gvar::GvarTable;
+use tables::loca::LocaTable;
+use tests::head::head_table;
+use util::{push_u16, push_u32};
+
+static TEST_FONT_PATH: &'static str = "resources/tests/nimbus-sans/NimbusSanL-Regu.ttf";
+
+// A 100-unit square with all four points on the curve.
+static SQUARE_GLYPH: [u8; 34] = [
+    0, 1,                               // numberOfContours
+    0, 0, 0, 0, 0, 100, 0, 100,         // xMin, yMin, xMax, yMax
+    0, 3,                               // endPtsOfContours
+    0, 0,                               // instructionLength
+    1, 1, 1, 1,                         // flags
+    0, 0, 0, 0, 0, 100, 0, 0,           // xCoordinates
+    0, 0, 0, 100, 0, 0, 0xff, 0x9c,     // yCoordinates
+];
+
+// Glyph 0 moves the square's bottom left and top right corners 10 units outwards at the maximum
+// weight, leaving the other two corners to be inferred. Glyph 1 moves its two points right by 3
+// and 4 units at the minimum weight.
+static GLYPH_VARIATION_DATA: [&'static [u8]; 2] = [
+    &[
+        0, 1, 0, 8,                     // tupleVariationCount, dataOffset
+        0, 10, 0x20, 0,                 // variationDataSize, PRIVATE_POINT_NUMBERS | tuple 0
+        2, 1, 0, 2,                     // points 0 and 2
+        1, 0xf6, 10,                    // x deltas
+        1, 0xf6, 10,                    // y deltas
+    ],
+    &[
+        0, 1, 0, 10,                    // tupleVariationCount, dataOffset
+        0, 8, 0x80, 0, 0xc0, 0,         // variationDataSize, EMBEDDED_PEAK_TUPLE, wght -1.0
+        5, 3, 4, 0, 0, 0, 0,            // x deltas for all points
+        0x85,                           // zero y deltas
+    ],
+];
+
+// Builds a `gvar` table with a weight axis and a shared tuple at its maximum.
+fn gvar_table_bytes() -> Vec<u8> {
+    let glyph_count = GLYPH_VARIATION_DATA.len() as u16;
+    let shared_tuples_offset = 20 + 2 * (glyph_count as u32 + 1);
+
+    let mut bytes = vec![];
+    push_u16(&mut bytes, 1);
+    push_u16(&mut bytes, 0);
+    push_u16(&mut bytes, 1);
+    push_u16(&mut bytes, 1);
+    push_u32(&mut bytes, shared_tuples_offset);
+    push_u16(&mut bytes, glyph_count);
+    push_u16(&mut bytes, 0);
+    push_u32(&mut bytes, shared_tuples_offset + 2);
+
+    let mut offset = 0;
+    push_u16(&mut bytes, 0);
+    for data in &GLYPH_VARIATION_DATA {
+        offset += data.len() as u16;
+        push_u16(&mut bytes, offset / 2)
+    }
+
+    push_u16(&mut bytes, 0x4000);
+    for data in &GLYPH_VARIATION_DATA {
+        bytes.extend_from_slice(data)
+    }
+    bytes
+}
+
+#[test]
+fn inferred_deltas_at_midpoint_of_weight_axis() {
+    let gvar_bytes = gvar_table_bytes();
+    let gvar = GvarTable::new(FontTable {
+        bytes: &gvar_bytes,
+    }).unwrap();
+
+    let mut loca = vec![0, 0, 0, 0];
+    push_u32(&mut loca, SQUARE_GLYPH.len() as u32);
+    let head = head_table(1);
+    let loca = LocaTable::new(FontTable {
+        bytes: &loca,
+    }, &head).unwrap();
+    let glyf = GlyfTable::new(FontTable {
+        bytes: &SQUARE_GLYPH,
+    });
+
+    let mut positions = vec![];
+    glyf.for_each_point_with_variations(&loca, &gvar, &[0.5], 0, |point: &Point| {
+        positions.push((point.position.x, point.position.y))
+    }).unwrap();
+    assert_eq!(positions, vec![(-5, -5), (-5, 105), (105, 105), (105, -5), (-5, -5)]);
+
+    let mut positions = vec![];
+    glyf.for_each_point_with_variations(&loca, &gvar, &[0.0], 0, |point: &Point| {
+        positions.push((point.position.x, point.position.y))
+    }).unwrap();
+    assert_eq!(positions, vec![(0, 0), (0, 100), (100, 100), (100, 0), (0, 0)]);
+}
+
+#[test]
+fn deltas_for_all_points() {
+    let bytes = gvar_table_bytes();
+    let gvar = GvarTable::new(FontTable {
+        bytes: &bytes,
+    }).unwrap();
+
+    let original_points = [Point2D::new(10, 20), Point2D::new(30, 40)];
+    let mut points = original_points;
+    gvar.apply_deltas(1, &[-1.0], &mut points, &[]).unwrap();
+    assert_eq!(points, [Point2D::new(13, 20), Point2D::new(34, 40)]);
+
+    let mut points = original_points;
+    gvar.apply_deltas(1, &[-0.5], &mut points, &[]).unwrap();
+    assert_eq!(points, [Point2D::new(12, 20), Point2D::new(32, 40)]);
+
+    // The tuple doesn't apply in the other direction.
+    let mut points = original_points;
+    gvar.apply_deltas(1, &[0.5], &mut points, &[]).unwrap();
+    assert_eq!(points, original_points);
+}
+
+#[test]
+fn normalized_coordinates() {
+    let axis = VariationAxis {
+        tag: 0,
+        min_value: 100.0,
+        default_value: 400.0,
+        max_value: 900.0,
+        flags: 0,
+        axis_name_id: 0,
+    };
+    assert_eq!(axis.normalize(400.0), 0.0);
+    assert_eq!(axis.normalize(650.0), 0.5);
+    assert_eq!(axis.normalize(100.0), -1.0);
+    assert_eq!(axis.normalize(1000.0), 1.0);
+}
+
+#[test]
+fn static_font_ignores_variations() {
+    let file = Mmap::open_path(TEST_FONT_PATH, Protection::Read).expect("Couldn't open test font");
+    let mut buffer = vec![];
+    let font = Font::new(unsafe { file.as_slice() }, &mut buffer).unwrap();
+    let glyph_id = font.glyph_for_char('o').unwrap().unwrap();
+
+    let (mut points, mut varied_points) = (vec![], vec![]);
+    font.for_each_point(glyph_id, |point| points.push(*point)).unwrap();
+    font.for_each_point_with_variations(glyph_id, &[0.5], |point| {
+        varied_points.push(*point)
+    }).unwrap();
+    assert_eq!(points, varied_points);
+}
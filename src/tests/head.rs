@@ -0,0 +1,106 @@
+/* Any copyright is dedicated to the Public Domain.
+ * http://creativecommons.org/publicdomain/zero/1.0/ */
+
+use error::FontError;
+use font::{Font, FontTable};
+use memmap::{Mmap, Protection};
+use tables::head::HeadTable;
+
+static TEST_FONT_PATH: &'static str = "resources/tests/nimbus-sans/NimbusSanL-Regu.ttf";
+
+fn head_table_bytes() -> Vec<u8> {
+    let mut bytes = vec![
+        0, 1, 0, 0,                     // version
+        0, 1, 0, 0,                     // fontRevision
+        0, 0, 0, 0,                     // checksumAdjustment
+        0x5f, 0x0f, 0x3c, 0xf5,         // magicNumber
+        0, 0x0b,                        // flags
+        0x03, 0xe8,                     // unitsPerEm
+    ];
+    bytes.extend_from_slice(&[0; 16]);  // created, modified
+    bytes.extend_from_slice(&[0xff, 0x9c, 0xff, 0x38, 0x03, 0xe8, 0x01, 0xf4]);
+    bytes.extend_from_slice(&[
+        0, 3,                           // macStyle
+        0, 8,                           // lowestRecPPEM
+        0, 2,                           // fontDirectionHint
+        0, 1,                           // indexToLocFormat
+        0, 0,                           // glyphDataFormat
+    ]);
+    bytes
+}
+
+// Returns a valid `head` table, with 1000 units per em and the given `loca` format, for the tests
+// of tables that depend on it.
+pub fn head_table(index_to_loc_format: i16) -> HeadTable {
+    let mut bytes = head_table_bytes();
+    bytes[51] = index_to_loc_format as u8;
+    HeadTable::new(FontTable {
+        bytes: &bytes,
+    }).unwrap()
+}
+
+#[test]
+fn head_table_fields() {
+    let bytes = head_table_bytes();
+    let head = HeadTable::new(FontTable {
+        bytes: &bytes,
+    }).unwrap();
+    assert_eq!(head.units_per_em(), 1000);
+    assert_eq!(head.index_to_loc_format(), 1);
+    assert_eq!(head.flags(), 0x0b);
+    assert_eq!(head.mac_style(), 3);
+
+    let bounds = head.max_glyph_bounds();
+    assert_eq!((bounds.left, bounds.bottom, bounds.right, bounds.top), (-100, -200, 1000, 500));
+}
+
+#[test]
+fn bad_head_magic_number() {
+    let mut bytes = head_table_bytes();
+    bytes[15] = 0;
+    assert_eq!(HeadTable::new(FontTable {
+        bytes: &bytes,
+    }).err(), Some(FontError::BadHeadMagicNumber));
+}
+
+#[test]
+fn units_per_em_out_of_range() {
+    // 100000 doesn't fit in the field, so the largest value it can hold stands in for it.
+    for &units_per_em in &[0, 15, 16385, 0xffff] {
+        let mut bytes = head_table_bytes();
+        bytes[18] = (units_per_em >> 8) as u8;
+        bytes[19] = units_per_em as u8;
+        assert_eq!(HeadTable::new(FontTable {
+            bytes: &bytes,
+        }).err(), Some(FontError::InvalidUnitsPerEm));
+    }
+
+    for &units_per_em in &[16, 16384] {
+        let mut bytes = head_table_bytes();
+        bytes[18] = (units_per_em >> 8) as u8;
+        bytes[19] = units_per_em as u8;
+        assert_eq!(HeadTable::new(FontTable {
+            bytes: &bytes,
+        }).unwrap().units_per_em(), units_per_em);
+    }
+}
+
+#[test]
+fn invalid_index_to_loc_format() {
+    for &index_to_loc_format in &[2, 0xffff] {
+        let mut bytes = head_table_bytes();
+        bytes[50] = (index_to_loc_format >> 8) as u8;
+        bytes[51] = index_to_loc_format as u8;
+        assert_eq!(HeadTable::new(FontTable {
+            bytes: &bytes,
+        }).err(), Some(FontError::InvalidIndexToLocFormat));
+    }
+}
+
+#[test]
+fn test_font_units_per_em() {
+    let file = Mmap::open_path(TEST_FONT_PATH, Protection::Read).expect("Couldn't open test font");
+    let mut buffer = vec![];
+    let font = Font::new(unsafe { file.as_slice() }, &mut buffer).unwrap();
+    assert_eq!(font.units_per_em(), 2048);
+}
@@ -0,0 +1,66 @@
+/* Any copyright is dedicated to the Public Domain.
+ * http://creativecommons.org/publicdomain/zero/1.0/ */
+
+use font::{Font, FontTable, TableDirectory};
+use memmap::{Mmap, Protection};
+use tables::hhea::{self, HheaTable};
+use tables::hmtx::{self, HmtxTable};
+use test::Bencher;
+
+static TEST_FONT_PATH: &'static str = "resources/tests/nimbus-sans/NimbusSanL-Regu.ttf";
+
+#[test]
+fn hhea_fields() {
+    let mut bytes = vec![0, 1, 0, 0, 0x03, 0x20, 0xff, 0x38, 0, 90, 0x04, 0xb0];
+    bytes.extend_from_slice(&[0; 22]);
+    bytes.extend_from_slice(&[0, 3]);
+    let hhea = HheaTable::new(FontTable {
+        bytes: &bytes,
+    }).unwrap();
+    assert_eq!((hhea.ascender, hhea.descender, hhea.line_gap), (800, -200, 90));
+    assert_eq!(hhea.advance_width_max, 1200);
+    assert_eq!(hhea.number_of_h_metrics, 3);
+}
+
+// Glyphs 2 and 3 share the advance width of glyph 1 but have their own left side bearings.
+#[test]
+fn trailing_glyphs_share_the_last_advance() {
+    let bytes = [0x01, 0xf4, 0, 10, 0x02, 0x58, 0, 20, 0, 30, 0xff, 0xd8];
+    let hmtx = HmtxTable::new(FontTable {
+        bytes: &bytes,
+    });
+
+    let metrics: Vec<_> = (0..4).map(|glyph_id| {
+        let metrics = hmtx.metrics(glyph_id, 2).unwrap();
+        (metrics.advance_width, metrics.lsb)
+    }).collect();
+    assert_eq!(metrics, vec![(500, 10), (600, 20), (600, 30), (600, -40)]);
+    assert_eq!(hmtx.advance_width(3, 2), Ok(600));
+
+    assert!(hmtx.metrics(4, 2).is_err());
+    assert!(hmtx.metrics(0, 0).is_err());
+}
+
+// Times looking up the metrics of the first or last glyph of the test font, which should take as
+// long either way.
+fn bench_metrics(bencher: &mut Bencher, last: bool) {
+    let file = Mmap::open_path(TEST_FONT_PATH, Protection::Read).expect("Couldn't open test font");
+    let bytes = unsafe { file.as_slice() };
+    let mut buffer = vec![];
+    let glyph_id = if last { Font::new(bytes, &mut buffer).unwrap().num_glyphs() - 1 } else { 0 };
+    let directory = TableDirectory::new(bytes).unwrap();
+    let hhea = HheaTable::new(directory.table(hhea::TAG).unwrap()).unwrap();
+    let hmtx = HmtxTable::new(directory.table(hmtx::TAG).unwrap());
+
+    bencher.iter(|| hmtx.metrics(glyph_id, hhea.number_of_h_metrics).unwrap());
+}
+
+#[bench]
+fn bench_metrics_of_first_glyph(bencher: &mut Bencher) {
+    bench_metrics(bencher, false)
+}
+
+#[bench]
+fn bench_metrics_of_last_glyph(bencher: &mut Bencher) {
+    bench_metrics(bencher, true)
+}
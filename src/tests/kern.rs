@@ -0,0 +1,81 @@
+/* Any copyright is dedicated to the Public Domain.
+ * http://creativecommons.org/publicdomain/zero/1.0/ */
+
+use error::FontError;
+use font::FontTable;
+use tables::kern::KernTable;
+use util::push_u16;
+
+const GLYPH_A: u16 = 36;
+const GLYPH_T: u16 = 55;
+const GLYPH_V: u16 = 57;
+const GLYPH_O: u16 = 82;
+
+// Builds a format 0 subtable with the given coverage and pairs, which must be sorted.
+fn subtable(coverage: u16, pairs: &[(u16, u16, i16)]) -> Vec<u8> {
+    let mut bytes = vec![];
+    push_u16(&mut bytes, 0);
+    push_u16(&mut bytes, 14 + 6 * pairs.len() as u16);
+    push_u16(&mut bytes, coverage);
+    for &value in &[pairs.len() as u16, 0, 0, 0] {
+        push_u16(&mut bytes, value)
+    }
+    for &(left, right, value) in pairs {
+        push_u16(&mut bytes, left);
+        push_u16(&mut bytes, right);
+        push_u16(&mut bytes, value as u16)
+    }
+    bytes
+}
+
+fn kern_table_bytes(subtables: &[Vec<u8>]) -> Vec<u8> {
+    let mut bytes = vec![];
+    push_u16(&mut bytes, 0);
+    push_u16(&mut bytes, subtables.len() as u16);
+    for subtable in subtables {
+        bytes.extend_from_slice(subtable)
+    }
+    bytes
+}
+
+#[test]
+fn av_pair() {
+    let bytes = kern_table_bytes(&[
+        subtable(0x0001, &[
+            (GLYPH_A, GLYPH_T, -50),
+            (GLYPH_A, GLYPH_V, -80),
+            (GLYPH_T, GLYPH_O, -60),
+        ]),
+    ]);
+    let kern = KernTable::new(FontTable {
+        bytes: &bytes,
+    }).unwrap();
+    assert_eq!(kern.kerning(GLYPH_A, GLYPH_V), -80);
+    assert_eq!(kern.kerning(GLYPH_T, GLYPH_O), -60);
+    assert_eq!(kern.kerning(GLYPH_V, GLYPH_A), 0);
+}
+
+#[test]
+fn multiple_subtables() {
+    let bytes = kern_table_bytes(&[
+        subtable(0x0001, &[(GLYPH_A, GLYPH_T, -50), (GLYPH_A, GLYPH_V, -80)]),
+        // Vertical and minimum-value subtables are ignored.
+        subtable(0x0000, &[(GLYPH_A, GLYPH_V, -1000)]),
+        subtable(0x0003, &[(GLYPH_A, GLYPH_V, -1000)]),
+        subtable(0x0001, &[(GLYPH_A, GLYPH_V, -20)]),
+        subtable(0x0009, &[(GLYPH_A, GLYPH_T, -10)]),
+    ]);
+    let kern = KernTable::new(FontTable {
+        bytes: &bytes,
+    }).unwrap();
+    assert_eq!(kern.kerning(GLYPH_A, GLYPH_V), -100);
+    assert_eq!(kern.kerning(GLYPH_A, GLYPH_T), -10);
+}
+
+#[test]
+fn no_horizontal_subtables() {
+    let bytes = kern_table_bytes(&[subtable(0x0000, &[(GLYPH_A, GLYPH_V, -80)])]);
+    assert_eq!(KernTable::new(FontTable {
+        bytes: &bytes,
+    }).err(), Some(FontError::UnknownFormat));
+}
@@ -0,0 +1,119 @@
+/* Any copyright is dedicated to the Public Domain.
+ * http://creativecommons.org/publicdomain/zero/1.0/ */
+
+use font::FontTable;
+use tables::layout::{ClassDef, Coverage, LayoutTable};
+use util::tag;
+
+fn u16_bytes(values: &[u16]) -> Vec<u8> {
+    let mut bytes = vec![];
+    for &value in values {
+        bytes.extend_from_slice(&[(value >> 8) as u8, value as u8])
+    }
+    bytes
+}
+
+// Builds a layout table with a default script whose default language system enables only the
+// second of two features, `liga` (lookup 0) and `smcp` (lookup 1).
+fn layout_table_bytes() -> Vec<u8> {
+    // Header.
+    let mut bytes = u16_bytes(&[1, 0, 10, 30, 56]);
+
+    // Script list.
+    bytes.extend_from_slice(&u16_bytes(&[1]));
+    bytes.extend_from_slice(b"DFLT");
+    bytes.extend_from_slice(&u16_bytes(&[8, 4, 0, 0, 0xffff, 1, 1]));
+
+    // Feature list.
+    bytes.extend_from_slice(&u16_bytes(&[2]));
+    bytes.extend_from_slice(b"liga");
+    bytes.extend_from_slice(&u16_bytes(&[14]));
+    bytes.extend_from_slice(b"smcp");
+    bytes.extend_from_slice(&u16_bytes(&[20, 0, 1, 0, 0, 1, 1]));
+
+    // Lookup list.
+    bytes.extend_from_slice(&u16_bytes(&[2, 6, 12, 1, 0, 0, 4, 0, 0]));
+    bytes
+}
+
+#[test]
+fn coverage_format_1() {
+    let bytes = u16_bytes(&[1, 3, 10, 20, 30]);
+    let coverage = Coverage::new(&bytes, 0).unwrap();
+    assert_eq!(coverage.coverage_index(10), Ok(Some(0)));
+    assert_eq!(coverage.coverage_index(20), Ok(Some(1)));
+    assert_eq!(coverage.coverage_index(30), Ok(Some(2)));
+    assert_eq!(coverage.coverage_index(15), Ok(None));
+    assert_eq!(coverage.coverage_index(31), Ok(None));
+}
+
+#[test]
+fn coverage_format_2() {
+    let bytes = u16_bytes(&[2, 2, 10, 19, 0, 30, 39, 10]);
+    let coverage = Coverage::new(&bytes, 0).unwrap();
+    assert_eq!(coverage.coverage_index(15), Ok(Some(5)));
+    assert_eq!(coverage.coverage_index(30), Ok(Some(10)));
+    assert_eq!(coverage.coverage_index(39), Ok(Some(19)));
+    assert_eq!(coverage.coverage_index(25), Ok(None));
+    assert_eq!(coverage.coverage_index(40), Ok(None));
+}
+
+#[test]
+fn class_def_format_1() {
+    let bytes = u16_bytes(&[1, 50, 3, 1, 2, 3]);
+    let class_def = ClassDef::new(&bytes, 0).unwrap();
+    assert_eq!(class_def.class_of(50), Ok(1));
+    assert_eq!(class_def.class_of(51), Ok(2));
+    assert_eq!(class_def.class_of(52), Ok(3));
+    assert_eq!(class_def.class_of(49), Ok(0));
+    assert_eq!(class_def.class_of(53), Ok(0));
+}
+
+#[test]
+fn class_def_format_2() {
+    let bytes = u16_bytes(&[2, 2, 10, 19, 1, 30, 39, 2]);
+    let class_def = ClassDef::new(&bytes, 0).unwrap();
+    assert_eq!(class_def.class_of(15), Ok(1));
+    assert_eq!(class_def.class_of(35), Ok(2));
+    assert_eq!(class_def.class_of(25), Ok(0));
+    assert_eq!(class_def.class_of(5), Ok(0));
+}
+
+#[test]
+fn scripts_features_and_lookups() {
+    let bytes = layout_table_bytes();
+    let layout = LayoutTable::new(FontTable {
+        bytes: &bytes,
+    }).unwrap();
+
+    let scripts: Vec<u32> = layout.scripts().unwrap().map(|script| script.unwrap().0).collect();
+    assert_eq!(scripts, vec![tag(b"DFLT")]);
+
+    let features: Vec<u32> = layout.features()
+                                   .unwrap()
+                                   .map(|feature| feature.unwrap().0)
+                                   .collect();
+    assert_eq!(features, vec![tag(b"liga"), tag(b"smcp")]);
+
+    let lookups: Vec<u16> = layout.lookups()
+                                  .unwrap()
+                                  .map(|lookup| lookup.unwrap().lookup_type)
+                                  .collect();
+    assert_eq!(lookups, vec![1, 4]);
+
+    assert_eq!(layout.lookup_indices_for_features(&[tag(b"liga"), tag(b"smcp")]), Ok(vec![1]));
+}
+
+#[test]
+fn bad_record_offset_stops_iteration() {
+    let mut bytes = layout_table_bytes();
+    bytes[16] = 0xff;
+    bytes[17] = 0xff;
+    let layout = LayoutTable::new(FontTable {
+        bytes: &bytes,
+    }).unwrap();
+
+    let mut scripts = layout.scripts().unwrap();
+    assert!(scripts.next().unwrap().is_err());
+    assert!(scripts.next().is_none());
+}
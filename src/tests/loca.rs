@@ -0,0 +1,98 @@
+/* Any copyright is dedicated to the Public Domain.
+ * http://creativecommons.org/publicdomain/zero/1.0/ */
+
+use error::FontError;
+use font::{Font, FontTable, TableDirectory};
+use memmap::{Mmap, Protection};
+use tables::head::{self, HeadTable};
+use tables::loca::{self, LocaTable};
+use test::Bencher;
+use tests::head::head_table;
+
+static TEST_FONT_PATH: &'static str = "resources/tests/nimbus-sans/NimbusSanL-Regu.ttf";
+
+fn loca_table(bytes: &[u8], index_to_loc_format: i16) -> Result<LocaTable, FontError> {
+    LocaTable::new(FontTable {
+        bytes: bytes,
+    }, &head_table(index_to_loc_format))
+}
+
+#[test]
+fn short_offsets() {
+    // Short offsets are halved, so these are 0, 20, 20, and 64.
+    let bytes = [0, 0, 0, 10, 0, 10, 0, 32];
+    let loca = loca_table(&bytes, 0).unwrap();
+    assert_eq!(loca.glyph_range(0), Some(0..20));
+    assert_eq!(loca.glyph_range(1), Some(20..20));
+    assert_eq!(loca.glyph_range(2), Some(20..64));
+    assert_eq!(loca.glyph_range(3), None);
+    assert_eq!(loca.range_of(2), Ok(Some((20, 64))));
+    assert_eq!(loca.location_of(1), Ok(None));
+}
+
+#[test]
+fn long_offsets() {
+    let bytes = [0, 0, 0, 0, 0, 1, 0, 0, 0, 1, 0, 0, 0, 1, 0, 12];
+    let loca = loca_table(&bytes, 1).unwrap();
+    assert_eq!(loca.glyph_range(0), Some(0..0x10000));
+    assert_eq!(loca.glyph_range(1), Some(0x10000..0x10000));
+    assert_eq!(loca.glyph_range(2), Some(0x10000..0x1000c));
+    assert_eq!(loca.glyph_range(3), None);
+
+    // The same bytes read as short offsets are different glyphs.
+    assert_eq!(loca_table(&bytes, 0).unwrap().glyph_range(1), Some(0..2));
+}
+
+#[test]
+fn empty_glyphs() {
+    let bytes = [0, 0, 0, 10, 0, 10, 0, 32];
+    let loca = loca_table(&bytes, 0).unwrap();
+    assert!(!loca.is_empty_glyph(0));
+    assert!(loca.is_empty_glyph(1));
+    assert!(!loca.is_empty_glyph(2));
+    assert!(!loca.is_empty_glyph(3));
+
+    // The space in the test font has no outline.
+    let file = Mmap::open_path(TEST_FONT_PATH, Protection::Read).expect("Couldn't open test font");
+    let bytes = unsafe { file.as_slice() };
+    let mut buffer = vec![];
+    let font = Font::new(bytes, &mut buffer).unwrap();
+    let (space, a) = (font.glyph_for_char(' ').unwrap().unwrap(),
+                      font.glyph_for_char('a').unwrap().unwrap());
+    let directory = TableDirectory::new(bytes).unwrap();
+    let head = HeadTable::new(directory.table(head::TAG).unwrap()).unwrap();
+    let loca = LocaTable::new(directory.table(loca::TAG).unwrap(), &head).unwrap();
+    assert!(loca.is_empty_glyph(space));
+    assert!(!loca.is_empty_glyph(a));
+}
+
+#[test]
+fn decreasing_offsets() {
+    let bytes = [0, 0, 0, 10, 0, 8, 0, 32];
+    assert_eq!(loca_table(&bytes, 0).err(), Some(FontError::LocaMalformed));
+    let bytes = [0, 0, 0, 0, 0, 0, 0, 12, 0, 0, 0, 11];
+    assert_eq!(loca_table(&bytes, 1).err(), Some(FontError::LocaMalformed));
+}
+
+// Times looking up the first or last glyph of the test font, which should take as long either way.
+fn bench_glyph_range(bencher: &mut Bencher, last: bool) {
+    let file = Mmap::open_path(TEST_FONT_PATH, Protection::Read).expect("Couldn't open test font");
+    let bytes = unsafe { file.as_slice() };
+    let mut buffer = vec![];
+    let glyph_id = if last { Font::new(bytes, &mut buffer).unwrap().num_glyphs() - 1 } else { 0 };
+    let directory = TableDirectory::new(bytes).unwrap();
+    let head = HeadTable::new(directory.table(head::TAG).unwrap()).unwrap();
+    let loca = LocaTable::new(directory.table(loca::TAG).unwrap(), &head).unwrap();
+
+    bencher.iter(|| loca.glyph_range(glyph_id));
+}
+
+#[bench]
+fn bench_glyph_range_of_first_glyph(bencher: &mut Bencher) {
+    bench_glyph_range(bencher, false)
+}
+
+#[bench]
+fn bench_glyph_range_of_last_glyph(bencher: &mut Bencher) {
+    bench_glyph_range(bencher, true)
+}
@@ -0,0 +1,199 @@
+/* Any copyright is dedicated to the Public Domain.
+ * http://creativecommons.org/publicdomain/zero/1.0/ */
+
+use error::FontError;
+use font::{FontTable, MathDirection};
+use tables::math::{GlyphAssembly, GlyphPart, GlyphVariant, MathTable};
+use util::push_u16;
+
+const GLYPH_F_ITALIC: u16 = 10;
+const GLYPH_X_ITALIC: u16 = 20;
+const GLYPH_PAREN_LEFT: u16 = 30;
+const GLYPH_PAREN_LEFT_SIZE_1: u16 = 31;
+const GLYPH_PAREN_LEFT_TOP: u16 = 32;
+const GLYPH_PAREN_LEFT_EXTENDER: u16 = 33;
+const GLYPH_PAREN_LEFT_BOTTOM: u16 = 34;
+const GLYPH_ARROW_RIGHT: u16 = 40;
+const GLYPH_ARROW_RIGHT_WIDE: u16 = 41;
+
+fn coverage(glyph_ids: &[u16]) -> Vec<u8> {
+    let mut bytes = vec![];
+    push_u16(&mut bytes, 1);
+    push_u16(&mut bytes, glyph_ids.len() as u16);
+    for &glyph_id in glyph_ids {
+        push_u16(&mut bytes, glyph_id)
+    }
+    bytes
+}
+
+// Builds a table of one `MathValueRecord` per glyph, as used for italic corrections and top
+// accent attachments. The glyphs must be sorted.
+fn glyph_values(values: &[(u16, i16)]) -> Vec<u8> {
+    let mut bytes = vec![];
+    push_u16(&mut bytes, 4 + 4 * values.len() as u16);
+    push_u16(&mut bytes, values.len() as u16);
+    for &(_, value) in values {
+        push_u16(&mut bytes, value as u16);
+        push_u16(&mut bytes, 0)
+    }
+    let glyph_ids: Vec<_> = values.iter().map(|&(glyph_id, _)| glyph_id).collect();
+    bytes.extend(coverage(&glyph_ids));
+    bytes
+}
+
+// Builds a MATH table in which each `MathValueRecord` constant has the value 100 plus its index,
+// apart from the axis height, and with a few glyphs' worth of glyph info and variants.
+fn math_table_bytes() -> Vec<u8> {
+    let mut constants = vec![];
+    for &value in &[70, 50, 1300, 2600] {
+        push_u16(&mut constants, value)
+    }
+    for index in 0..51 {
+        push_u16(&mut constants, if index == 1 { 250 } else { 100 + index });
+        push_u16(&mut constants, 0)
+    }
+    push_u16(&mut constants, 60);
+
+    let italics_correction_info = glyph_values(&[(GLYPH_F_ITALIC, 50), (GLYPH_X_ITALIC, 15)]);
+    let top_accent_attachment = glyph_values(&[(GLYPH_F_ITALIC, 240)]);
+    let mut glyph_info = vec![];
+    for &offset in &[8, 8 + italics_correction_info.len() as u16, 0, 0] {
+        push_u16(&mut glyph_info, offset)
+    }
+    glyph_info.extend(italics_correction_info);
+    glyph_info.extend(top_accent_attachment);
+
+    // A vertical construction for the left parenthesis with two variants and an assembly, and a
+    // horizontal one for the arrow with only a variant.
+    let mut paren_construction = vec![];
+    for &value in &[12, 2, GLYPH_PAREN_LEFT, 1000, GLYPH_PAREN_LEFT_SIZE_1, 1500, 0, 0, 3] {
+        push_u16(&mut paren_construction, value)
+    }
+    for &part in &[[GLYPH_PAREN_LEFT_BOTTOM, 0, 150, 800, 0],
+                   [GLYPH_PAREN_LEFT_EXTENDER, 150, 150, 300, 1],
+                   [GLYPH_PAREN_LEFT_TOP, 150, 0, 800, 0]] {
+        for &value in &part {
+            push_u16(&mut paren_construction, value)
+        }
+    }
+    let mut arrow_construction = vec![];
+    for &value in &[0, 1, GLYPH_ARROW_RIGHT_WIDE, 2000] {
+        push_u16(&mut arrow_construction, value)
+    }
+
+    let paren_construction_offset = 14;
+    let arrow_construction_offset = paren_construction_offset + paren_construction.len() as u16;
+    let vert_coverage_offset = arrow_construction_offset + arrow_construction.len() as u16;
+    let horiz_coverage_offset = vert_coverage_offset + 6;
+    let mut variants = vec![];
+    for &value in &[20,
+                    vert_coverage_offset,
+                    horiz_coverage_offset,
+                    1,
+                    1,
+                    paren_construction_offset,
+                    arrow_construction_offset] {
+        push_u16(&mut variants, value)
+    }
+    variants.extend(paren_construction);
+    variants.extend(arrow_construction);
+    variants.extend(coverage(&[GLYPH_PAREN_LEFT]));
+    variants.extend(coverage(&[GLYPH_ARROW_RIGHT]));
+
+    let mut bytes = vec![0, 1, 0, 0];
+    let glyph_info_offset = 10 + constants.len() as u16;
+    for &offset in &[10, glyph_info_offset, glyph_info_offset + glyph_info.len() as u16] {
+        push_u16(&mut bytes, offset)
+    }
+    bytes.extend(constants);
+    bytes.extend(glyph_info);
+    bytes.extend(variants);
+    bytes
+}
+
+#[test]
+fn math_constants() {
+    let bytes = math_table_bytes();
+    let math = MathTable::new(FontTable {
+        bytes: &bytes,
+    }).unwrap();
+    let constants = math.constants().unwrap();
+    assert_eq!(constants.script_percent_scale_down, 70);
+    assert_eq!(constants.script_script_percent_scale_down, 50);
+    assert_eq!(constants.delimited_sub_formula_min_height, 1300);
+    assert_eq!(constants.display_operator_min_height, 2600);
+    assert_eq!(constants.math_leading, 100);
+    assert_eq!(constants.axis_height, 250);
+    assert_eq!(constants.fraction_rule_thickness, 134);
+    assert_eq!(constants.radical_kern_after_degree, 150);
+    assert_eq!(constants.radical_degree_bottom_raise_percent, 60);
+}
+
+#[test]
+fn italic_corrections_and_accent_attachments() {
+    let bytes = math_table_bytes();
+    let math = MathTable::new(FontTable {
+        bytes: &bytes,
+    }).unwrap();
+    assert_eq!(math.italic_correction(GLYPH_F_ITALIC), Ok(Some(50)));
+    assert_eq!(math.italic_correction(GLYPH_X_ITALIC), Ok(Some(15)));
+    assert_eq!(math.italic_correction(GLYPH_PAREN_LEFT), Ok(None));
+    assert_eq!(math.top_accent_attachment(GLYPH_F_ITALIC), Ok(Some(240)));
+    assert_eq!(math.top_accent_attachment(GLYPH_X_ITALIC), Ok(None));
+}
+
+#[test]
+fn glyph_variants_and_assemblies() {
+    let bytes = math_table_bytes();
+    let math = MathTable::new(FontTable {
+        bytes: &bytes,
+    }).unwrap();
+    assert_eq!(math.min_connector_overlap(), Ok(20));
+
+    assert_eq!(math.glyph_variants(GLYPH_PAREN_LEFT, MathDirection::Vertical),
+               Ok(vec![GlyphVariant { glyph_id: GLYPH_PAREN_LEFT, advance: 1000 },
+                       GlyphVariant { glyph_id: GLYPH_PAREN_LEFT_SIZE_1, advance: 1500 }]));
+    assert_eq!(math.glyph_assembly(GLYPH_PAREN_LEFT, MathDirection::Vertical),
+               Ok(Some(GlyphAssembly {
+                   italics_correction: 0,
+                   parts: vec![
+                       GlyphPart {
+                           glyph_id: GLYPH_PAREN_LEFT_BOTTOM,
+                           start_connector_length: 0,
+                           end_connector_length: 150,
+                           full_advance: 800,
+                           is_extender: false,
+                       },
+                       GlyphPart {
+                           glyph_id: GLYPH_PAREN_LEFT_EXTENDER,
+                           start_connector_length: 150,
+                           end_connector_length: 150,
+                           full_advance: 300,
+                           is_extender: true,
+                       },
+                       GlyphPart {
+                           glyph_id: GLYPH_PAREN_LEFT_TOP,
+                           start_connector_length: 150,
+                           end_connector_length: 0,
+                           full_advance: 800,
+                           is_extender: false,
+                       },
+                   ],
+               })));
+
+    // The horizontal constructions are separate from the vertical ones.
+    assert_eq!(math.glyph_variants(GLYPH_PAREN_LEFT, MathDirection::Horizontal), Ok(vec![]));
+    assert_eq!(math.glyph_variants(GLYPH_ARROW_RIGHT, MathDirection::Horizontal),
+               Ok(vec![GlyphVariant { glyph_id: GLYPH_ARROW_RIGHT_WIDE, advance: 2000 }]));
+    assert_eq!(math.glyph_assembly(GLYPH_ARROW_RIGHT, MathDirection::Horizontal), Ok(None));
+    assert_eq!(math.glyph_variants(GLYPH_ARROW_RIGHT, MathDirection::Vertical), Ok(vec![]));
+}
+
+#[test]
+fn unsupported_math_version() {
+    let mut bytes = math_table_bytes();
+    bytes[1] = 2;
+    assert_eq!(MathTable::new(FontTable {
+        bytes: &bytes,
+    }).err(), Some(FontError::UnsupportedMathVersion));
+}
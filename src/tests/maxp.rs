@@ -0,0 +1,47 @@
+/* Any copyright is dedicated to the Public Domain.
+ * http://creativecommons.org/publicdomain/zero/1.0/ */
+
+use error::FontError;
+use font::{Font, FontTable};
+use memmap::{Mmap, Protection};
+use tables::maxp::MaxpTable;
+
+static TEST_FONT_PATH: &'static str = "resources/tests/nimbus-sans/NimbusSanL-Regu.ttf";
+
+#[test]
+fn maxp_versions() {
+    let bytes = [0, 0, 0x50, 0, 0x01, 0x2c];
+    let maxp = MaxpTable::new(FontTable {
+        bytes: &bytes,
+    }).unwrap();
+    assert_eq!(maxp.num_glyphs(), 300);
+
+    let mut bytes = vec![0, 1, 0, 0, 0, 42];
+    bytes.extend_from_slice(&[0; 26]);
+    let maxp = MaxpTable::new(FontTable {
+        bytes: &bytes,
+    }).unwrap();
+    assert_eq!(maxp.num_glyphs(), 42);
+
+    let bytes = [0, 2, 0, 0, 0, 42];
+    assert_eq!(MaxpTable::new(FontTable {
+        bytes: &bytes,
+    }).err(), Some(FontError::UnsupportedMaxpVersion));
+}
+
+#[test]
+fn glyph_ids_are_validated() {
+    let file = Mmap::open_path(TEST_FONT_PATH, Protection::Read).expect("Couldn't open test font");
+    let mut buffer = vec![];
+    let font = Font::new(unsafe { file.as_slice() }, &mut buffer).unwrap();
+
+    let last_glyph_id = font.num_glyphs() - 1;
+    assert!(font.glyph_bounds(last_glyph_id).is_ok());
+    assert!(font.metrics_for_glyph(last_glyph_id).is_ok());
+    assert!(font.for_each_point(last_glyph_id, |_| {}).is_ok());
+
+    let glyph_id = font.num_glyphs();
+    assert_eq!(font.glyph_bounds(glyph_id).err(), Some(FontError::NoSuchGlyph));
+    assert_eq!(font.metrics_for_glyph(glyph_id).err(), Some(FontError::NoSuchGlyph));
+    assert_eq!(font.for_each_point(glyph_id, |_| {}), Err(FontError::NoSuchGlyph));
+}
@@ -0,0 +1,62 @@
+/* Any copyright is dedicated to the Public Domain.
+ * http://creativecommons.org/publicdomain/zero/1.0/ */
+
+use error::FontError;
+use font::FontTable;
+use tables::meta::MetaTable;
+use util::{push_u16, push_u32};
+
+// Builds a `meta` table with the given tags and data, stored in order after the data maps.
+fn meta_table_bytes(data_maps: &[(&[u8; 4], &[u8])]) -> Vec<u8> {
+    let mut bytes = vec![];
+    for &value in &[1, 0, 0, data_maps.len() as u32] {
+        push_u32(&mut bytes, value)
+    }
+    let mut offset = 16 + 12 * data_maps.len();
+    for &(tag, data) in data_maps {
+        bytes.extend_from_slice(tag);
+        push_u32(&mut bytes, offset as u32);
+        push_u32(&mut bytes, data.len() as u32);
+        offset += data.len()
+    }
+    for &(_, data) in data_maps {
+        bytes.extend_from_slice(data)
+    }
+    bytes
+}
+
+#[test]
+fn language_tags() {
+    let bytes = meta_table_bytes(&[(b"appl", &[1, 2, 3][..]),
+                                   (b"slng", &b"Latn, Grek,Cyrl, zh-Hant"[..]),
+                                   (b"dlng", &b"Latn"[..])]);
+    let meta = MetaTable::new(FontTable {
+        bytes: &bytes,
+    }).unwrap();
+    assert_eq!(meta.supported_languages(), vec!["Latn", "Grek", "Cyrl", "zh-Hant"]);
+    assert_eq!(meta.design_languages(), vec!["Latn"]);
+    assert_eq!(meta.get(0x6170706c), Some(&[1, 2, 3][..]));
+    assert_eq!(meta.get(0x62696c64), None);
+}
+
+#[test]
+fn missing_language_tags() {
+    let bytes = meta_table_bytes(&[(b"slng", &b""[..])]);
+    let meta = MetaTable::new(FontTable {
+        bytes: &bytes,
+    }).unwrap();
+    assert!(meta.supported_languages().is_empty());
+    assert!(meta.design_languages().is_empty());
+
+    let mut bytes = meta_table_bytes(&[(b"dlng", &b"Latn"[..])]);
+    bytes.pop();
+    let meta = MetaTable::new(FontTable {
+        bytes: &bytes,
+    }).unwrap();
+    assert_eq!(meta.get(0x646c6e67), None);
+
+    bytes[3] = 2;
+    assert_eq!(MetaTable::new(FontTable {
+        bytes: &bytes,
+    }).err(), Some(FontError::UnsupportedMetaVersion));
+}
@@ -9,5 +9,44 @@
 // except according to those terms.
 
 mod buffers;
+mod cbdt;
+mod cff;
+mod cmap;
+mod colr;
+#[cfg(any(feature = "kurbo", feature = "lyon"))]
+mod convert;
+mod dsig;
+mod ebdt;
+mod font;
+mod fuzzing;
+mod fvar;
+mod gasp;
+mod glyf;
+mod glyph_cache;
+mod gpos;
+mod gsub;
+mod gvar;
+mod head;
+mod hmtx;
+mod kern;
+mod layout;
+mod loca;
+mod math;
+mod maxp;
+mod meta;
+mod morx;
+mod name;
+mod os_2;
+mod otf;
+mod post;
 mod rect_packer;
+mod sbix;
+mod stat;
+mod svg;
+mod type1;
+mod util;
+mod vmtx;
+mod vorg;
+mod woff;
+mod woff2;
 
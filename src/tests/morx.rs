@@ -0,0 +1,194 @@
+/* Any copyright is dedicated to the Public Domain.
+ * http://creativecommons.org/publicdomain/zero/1.0/ */
+
+use error::FontError;
+use font::FontTable;
+use tables::morx::MorxTable;
+use util::{push_u16, push_u32};
+
+const GLYPH_F: u16 = 10;
+const GLYPH_I: u16 = 11;
+const GLYPH_L: u16 = 12;
+const GLYPH_FI: u16 = 20;
+const GLYPH_FFI: u16 = 21;
+const GLYPH_DOTLESS_I: u16 = 30;
+
+const COVERAGE_LIGATURE: u32 = 2;
+const COVERAGE_NONCONTEXTUAL: u32 = 4;
+const COVERAGE_VERTICAL: u32 = 0x8000_0000;
+
+const SET_COMPONENT: u16 = 0x8000;
+const PERFORM_ACTION: u16 = 0x2000;
+const ACTION_LAST: u32 = 0x8000_0000;
+
+// A chain's default flags, (feature type, setting, enable flags, disable flags) entries, and
+// (coverage, feature flags, body) subtables.
+type Chain = (u32, Vec<(u16, u16, u32, u32)>, Vec<(u32, u32, Vec<u8>)>);
+
+fn morx_table_bytes(version: u16, chains: &[Chain]) -> Vec<u8> {
+    let mut bytes = vec![];
+    push_u16(&mut bytes, version);
+    push_u16(&mut bytes, 0);
+    push_u32(&mut bytes, chains.len() as u32);
+
+    for &(default_flags, ref features, ref subtables) in chains {
+        let subtables_length: usize = subtables.iter().map(|&(_, _, ref body)| {
+            12 + body.len()
+        }).sum();
+        push_u32(&mut bytes, default_flags);
+        push_u32(&mut bytes, (16 + 12 * features.len() + subtables_length) as u32);
+        push_u32(&mut bytes, features.len() as u32);
+        push_u32(&mut bytes, subtables.len() as u32);
+        for &(feature_type, setting, enable_flags, disable_flags) in features {
+            push_u16(&mut bytes, feature_type);
+            push_u16(&mut bytes, setting);
+            push_u32(&mut bytes, enable_flags);
+            push_u32(&mut bytes, disable_flags)
+        }
+        for &(coverage, feature_flags, ref body) in subtables {
+            push_u32(&mut bytes, 12 + body.len() as u32);
+            push_u32(&mut bytes, coverage);
+            push_u32(&mut bytes, feature_flags);
+            bytes.extend_from_slice(body)
+        }
+    }
+    bytes
+}
+
+// A ligature subtable forming "fi" and "ffi". Its states are the start of text and line, then
+// having seen "f" and "ff"; "f" is class 4 and "i" is class 5.
+fn ligature_subtable() -> Vec<u8> {
+    // A format 2 lookup table of the two classes.
+    let mut class_table = vec![];
+    for &value in &[2, 6, 2, 12, 1, 0, GLYPH_F, GLYPH_F, 4, GLYPH_I, GLYPH_I, 5] {
+        push_u16(&mut class_table, value)
+    }
+
+    let mut state_array = vec![];
+    for &value in &[0, 0, 0, 0, 1, 0,
+                    0, 0, 0, 0, 1, 0,
+                    0, 0, 0, 0, 2, 3,
+                    0, 0, 0, 0, 2, 4] {
+        push_u16(&mut state_array, value)
+    }
+
+    let mut entry_table = vec![];
+    for &(new_state, flags, action_index) in &[(0, 0, 0),
+                                               (2, SET_COMPONENT, 0),
+                                               (3, SET_COMPONENT, 0),
+                                               (0, SET_COMPONENT | PERFORM_ACTION, 0),
+                                               (0, SET_COMPONENT | PERFORM_ACTION, 2)] {
+        for &value in &[new_state, flags, action_index] {
+            push_u16(&mut entry_table, value)
+        }
+    }
+
+    // Each action pops a component and adds the component table entry at its glyph ID plus the
+    // offset to the ligature index. "fi" sums to ligature 0 and "ffi" to ligature 1.
+    let mut actions = vec![];
+    for &(last, offset) in &[(0, -11), (ACTION_LAST, -9), (0, -9), (0, -7), (ACTION_LAST, -6)] {
+        push_u32(&mut actions, last | (offset as u32 & 0x3fff_ffff))
+    }
+    let mut components = vec![];
+    for &value in &[0, 0, 1, 0, 0] {
+        push_u16(&mut components, value)
+    }
+    let mut ligatures = vec![];
+    for &value in &[GLYPH_FI, GLYPH_FFI] {
+        push_u16(&mut ligatures, value)
+    }
+
+    // The header, with the class count and offsets to each part.
+    let parts = [class_table, state_array, entry_table, actions, components, ligatures];
+    let mut bytes = vec![];
+    push_u32(&mut bytes, 6);
+    let mut offset = 4 + 4 * parts.len();
+    for part in &parts {
+        push_u32(&mut bytes, offset as u32);
+        offset += part.len()
+    }
+    for part in &parts {
+        bytes.extend_from_slice(part)
+    }
+    bytes
+}
+
+// A noncontextual subtable replacing "i" with a dotless "i" by a format 6 lookup table.
+fn dotless_i_subtable() -> Vec<u8> {
+    let mut bytes = vec![];
+    for &value in &[6, 4, 1, 4, 0, 0, GLYPH_I, GLYPH_DOTLESS_I] {
+        push_u16(&mut bytes, value)
+    }
+    bytes
+}
+
+fn substitute(bytes: &[u8], glyphs: &[u16], features: &[(u16, u16)]) -> Vec<u16> {
+    let morx = MorxTable::new(FontTable {
+        bytes: bytes,
+    }).unwrap();
+    morx.substitute(glyphs, features).unwrap()
+}
+
+#[test]
+fn ligature_state_machine() {
+    let subtables = vec![(COVERAGE_LIGATURE, 1, ligature_subtable())];
+    let bytes = morx_table_bytes(2, &[(1, vec![], subtables)]);
+
+    assert_eq!(substitute(&bytes, &[GLYPH_F, GLYPH_I], &[]), [GLYPH_FI]);
+    assert_eq!(substitute(&bytes, &[GLYPH_F, GLYPH_F, GLYPH_I], &[]), [GLYPH_FFI]);
+    assert_eq!(substitute(&bytes, &[GLYPH_F, GLYPH_I, GLYPH_F, GLYPH_I], &[]),
+               [GLYPH_FI, GLYPH_FI]);
+
+    // Other glyphs are out of bounds and start the machine over.
+    assert_eq!(substitute(&bytes, &[1, GLYPH_F, GLYPH_F, GLYPH_I, GLYPH_L], &[]),
+               [1, GLYPH_FFI, GLYPH_L]);
+    assert_eq!(substitute(&bytes, &[GLYPH_F, GLYPH_F, GLYPH_F, GLYPH_I], &[]),
+               [GLYPH_F, GLYPH_FFI]);
+    assert_eq!(substitute(&bytes, &[GLYPH_F, GLYPH_L, GLYPH_I], &[]),
+               [GLYPH_F, GLYPH_L, GLYPH_I]);
+    assert!(substitute(&bytes, &[], &[]).is_empty());
+}
+
+#[test]
+fn features_select_subtables() {
+    // The dotless "i" is off by default. Feature 1's setting 0 turns it on, and setting 1 turns
+    // off ligatures.
+    let features = vec![(1, 0, 2, !0), (1, 1, 0, !1)];
+    let subtables = vec![(COVERAGE_LIGATURE, 1, ligature_subtable()),
+                         (COVERAGE_NONCONTEXTUAL, 2, dotless_i_subtable())];
+    let bytes = morx_table_bytes(3, &[(1, features, subtables)]);
+
+    assert_eq!(substitute(&bytes, &[GLYPH_I], &[]), [GLYPH_I]);
+    assert_eq!(substitute(&bytes, &[GLYPH_I], &[(1, 0)]), [GLYPH_DOTLESS_I]);
+
+    // Ligatures form first, so there's no "i" left to replace.
+    assert_eq!(substitute(&bytes, &[GLYPH_F, GLYPH_I], &[(1, 0)]), [GLYPH_FI]);
+    assert_eq!(substitute(&bytes, &[GLYPH_F, GLYPH_I], &[(1, 1)]), [GLYPH_F, GLYPH_I]);
+    assert_eq!(substitute(&bytes, &[GLYPH_F, GLYPH_I], &[(1, 1), (1, 0)]),
+               [GLYPH_F, GLYPH_DOTLESS_I]);
+}
+
+#[test]
+fn noncontextual_lookup_formats() {
+    // A format 8 lookup table of the glyphs from "f" to "i".
+    let mut trimmed_array = vec![];
+    for &value in &[8, GLYPH_F, 2, 40, 41] {
+        push_u16(&mut trimmed_array, value)
+    }
+    let subtables = vec![(COVERAGE_NONCONTEXTUAL, 1, trimmed_array)];
+    let bytes = morx_table_bytes(2, &[(1, vec![], subtables)]);
+    assert_eq!(substitute(&bytes, &[GLYPH_F, GLYPH_I, GLYPH_L], &[]), [40, 41, GLYPH_L]);
+
+    // Subtables for vertical text are skipped.
+    let subtables = vec![(COVERAGE_VERTICAL | COVERAGE_NONCONTEXTUAL, 1, dotless_i_subtable())];
+    let bytes = morx_table_bytes(2, &[(1, vec![], subtables)]);
+    assert_eq!(substitute(&bytes, &[GLYPH_I], &[]), [GLYPH_I]);
+}
+
+#[test]
+fn unsupported_version() {
+    let bytes = morx_table_bytes(1, &[]);
+    assert_eq!(MorxTable::new(FontTable {
+        bytes: &bytes,
+    }).err(), Some(FontError::UnsupportedMorxVersion));
+}
@@ -0,0 +1,79 @@
+/* Any copyright is dedicated to the Public Domain.
+ * http://creativecommons.org/publicdomain/zero/1.0/ */
+
+use font::{Font, FontTable};
+use memmap::{Mmap, Protection};
+use tables::name::{NameId, NameTable};
+use util::push_u16;
+
+static TEST_FONT_PATH: &'static str = "resources/tests/nimbus-sans/NimbusSanL-Regu.ttf";
+
+fn utf16_be(string: &str) -> Vec<u8> {
+    let mut bytes = vec![];
+    for code_unit in string.encode_utf16() {
+        push_u16(&mut bytes, code_unit)
+    }
+    bytes
+}
+
+// Builds a format 0 `name` table from (platform ID, encoding ID, language ID, name ID, string)
+// records.
+fn name_table_bytes(records: &[(u16, u16, u16, u16, Vec<u8>)]) -> Vec<u8> {
+    let mut bytes = vec![];
+    push_u16(&mut bytes, 0);
+    push_u16(&mut bytes, records.len() as u16);
+    push_u16(&mut bytes, 6 + 12 * records.len() as u16);
+
+    let mut offset = 0;
+    for &(platform_id, encoding_id, language_id, name_id, ref string) in records {
+        for &value in &[platform_id, encoding_id, language_id, name_id, string.len() as u16,
+                        offset] {
+            push_u16(&mut bytes, value)
+        }
+        offset += string.len() as u16
+    }
+    for &(_, _, _, _, ref string) in records {
+        bytes.extend_from_slice(string)
+    }
+    bytes
+}
+
+#[test]
+fn utf16_be_family_name() {
+    let bytes = name_table_bytes(&[
+        (3, 1, 0x040c, 1, utf16_be("Café Français")),
+        (3, 1, 0x0409, 1, utf16_be("Café")),
+        (3, 1, 0x0409, 6, utf16_be("Cafe-Regular")),
+    ]);
+    let name = NameTable::new(FontTable {
+        bytes: &bytes,
+    }).unwrap();
+    assert_eq!(name.family_name(), Some("Café".to_owned()));
+    assert_eq!(name.postscript_name(), Some("Cafe-Regular".to_owned()));
+    assert_eq!(name.get(NameId::Designer), None);
+}
+
+#[test]
+fn mac_roman_family_name() {
+    let bytes = name_table_bytes(&[
+        (1, 0, 0, 1, b"Caf\x8e \xd2Noir\xd3".to_vec()),
+        (1, 0, 0, 2, b"Regular".to_vec()),
+        (3, 1, 0x0409, 2, utf16_be("Normal")),
+    ]);
+    let name = NameTable::new(FontTable {
+        bytes: &bytes,
+    }).unwrap();
+    assert_eq!(name.family_name(), Some("Café \u{201c}Noir\u{201d}".to_owned()));
+
+    // The Microsoft record wins over the Macintosh one.
+    assert_eq!(name.subfamily_name(), Some("Normal".to_owned()));
+}
+
+#[test]
+fn test_font_names() {
+    let file = Mmap::open_path(TEST_FONT_PATH, Protection::Read).expect("Couldn't open test font");
+    let mut buffer = vec![];
+    let font = Font::new(unsafe { file.as_slice() }, &mut buffer).unwrap();
+    assert_eq!(font.family_name(), Some("NimbusSanL".to_owned()));
+    assert_eq!(font.postscript_name(), Some("NimbusSanL-Regu".to_owned()));
+}
@@ -0,0 +1,341 @@
+/* Any copyright is dedicated to the Public Domain.
+ * http://creativecommons.org/publicdomain/zero/1.0/ */
+
+use byteorder::{BigEndian, ReadBytesExt};
+use error::{ErrorContext, FontError};
+use font::FontTable;
+use memmap::{Mmap, Protection};
+use tables::os_2::{self, Os2Table, Panose, SubSuperStrikeMetrics, UnicodeBlock};
+use util::Jump;
+
+static TEST_FONT_PATH: &'static str = "resources/tests/nimbus-sans/NimbusSanL-Regu.ttf";
+
+// Offsets of fields within the OS/2 table.
+const OFFSET_WEIGHT_CLASS: usize = 4;
+const OFFSET_WIDTH_CLASS: usize = 6;
+const OFFSET_FS_TYPE: usize = 8;
+const OFFSET_PANOSE: usize = 32;
+const OFFSET_UNICODE_RANGE: usize = 42;
+const OFFSET_VENDOR_ID: usize = 58;
+const OFFSET_FS_SELECTION: usize = 62;
+const OFFSET_TYPO_LINE_GAP: usize = 72;
+const OFFSET_CODE_PAGE_RANGE: usize = 78;
+const OFFSET_X_HEIGHT: usize = 86;
+const OFFSET_CAP_HEIGHT: usize = 88;
+const OFFSET_DEFAULT_CHAR: usize = 90;
+const OFFSET_BREAK_CHAR: usize = 92;
+const OFFSET_MAX_CONTEXT: usize = 94;
+const OFFSET_LOWER_OPTICAL_POINT_SIZE: usize = 96;
+const OFFSET_UPPER_OPTICAL_POINT_SIZE: usize = 98;
+
+// Returns a zeroed OS/2 table of the size that the given version requires.
+fn os_2_table_bytes(version: u16) -> Vec<u8> {
+    let length = match version {
+        0 => 78,
+        1 => 86,
+        2...4 => 96,
+        _ => 100,
+    };
+    let mut bytes = vec![0; length];
+    set_u16(&mut bytes, 0, version);
+    bytes
+}
+
+// Finds the OS/2 table in an uncompressed single font.
+fn find_os_2_table(font: &[u8]) -> &[u8] {
+    let mut reader = font;
+    reader.jump(4).unwrap();
+    let num_tables = reader.read_u16::<BigEndian>().unwrap();
+    reader.jump(6).unwrap();
+    for _ in 0..num_tables {
+        let tag = reader.read_u32::<BigEndian>().unwrap();
+        let _checksum = reader.read_u32::<BigEndian>().unwrap();
+        let offset = reader.read_u32::<BigEndian>().unwrap() as usize;
+        let length = reader.read_u32::<BigEndian>().unwrap() as usize;
+        if tag == os_2::TAG {
+            return &font[offset..offset + length]
+        }
+    }
+    panic!("No OS/2 table found")
+}
+
+fn set_u16(bytes: &mut [u8], offset: usize, value: u16) {
+    bytes[offset] = (value >> 8) as u8;
+    bytes[offset + 1] = value as u8;
+}
+
+fn set_u32(bytes: &mut [u8], offset: usize, value: u32) {
+    set_u16(bytes, offset, (value >> 16) as u16);
+    set_u16(bytes, offset + 2, value as u16)
+}
+
+fn os_2_table(bytes: &[u8]) -> Os2Table {
+    Os2Table::new(FontTable {
+        bytes: bytes,
+    }).unwrap()
+}
+
+#[test]
+fn weight_and_width_classes() {
+    let mut bytes = os_2_table_bytes(4);
+    set_u16(&mut bytes, OFFSET_WEIGHT_CLASS, 700);
+    set_u16(&mut bytes, OFFSET_WIDTH_CLASS, 3);
+    let os_2 = os_2_table(&bytes);
+    assert_eq!(os_2.weight_class, 700);
+    assert_eq!(os_2.width_class, 3);
+}
+
+#[test]
+fn style_flags() {
+    // Regular, as in Nimbus Sans.
+    let mut bytes = os_2_table_bytes(1);
+    set_u16(&mut bytes, OFFSET_FS_SELECTION, 0x0040);
+    let os_2 = os_2_table(&bytes);
+    assert!(os_2.is_regular());
+    assert!(!os_2.is_bold() && !os_2.is_italic() && !os_2.is_oblique());
+    assert!(!os_2.use_typo_metrics());
+
+    // Bold italic, with typographic metrics.
+    let mut bytes = os_2_table_bytes(4);
+    set_u16(&mut bytes, OFFSET_FS_SELECTION, 0x00a1);
+    let os_2 = os_2_table(&bytes);
+    assert!(os_2.is_bold() && os_2.is_italic());
+    assert!(!os_2.is_regular() && !os_2.is_oblique());
+    assert!(os_2.use_typo_metrics());
+
+    // Oblique faces also set the italic bit.
+    let mut bytes = os_2_table_bytes(4);
+    set_u16(&mut bytes, OFFSET_FS_SELECTION, 0x0201);
+    let os_2 = os_2_table(&bytes);
+    assert!(os_2.is_italic() && os_2.is_oblique());
+}
+
+#[test]
+fn x_height_and_cap_height() {
+    let mut bytes = os_2_table_bytes(1);
+    set_u16(&mut bytes, OFFSET_TYPO_LINE_GAP, 90);
+    let os_2 = os_2_table(&bytes);
+    assert_eq!(os_2.typo_line_gap, 90);
+    assert_eq!((os_2.x_height, os_2.cap_height), (None, None));
+
+    for &version in &[2, 4, 5] {
+        let mut bytes = os_2_table_bytes(version);
+        set_u16(&mut bytes, OFFSET_TYPO_LINE_GAP, 90);
+        set_u16(&mut bytes, OFFSET_X_HEIGHT, 523);
+        set_u16(&mut bytes, OFFSET_CAP_HEIGHT, 718);
+        let os_2 = os_2_table(&bytes);
+        assert_eq!(os_2.typo_line_gap, 90);
+        assert_eq!((os_2.x_height, os_2.cap_height), (Some(523), Some(718)));
+    }
+}
+
+#[test]
+fn version_gated_fields() {
+    let os_2 = os_2_table(&os_2_table_bytes(0));
+    assert_eq!(os_2.version, 0);
+    assert_eq!(os_2.code_page_range, None);
+    assert_eq!((os_2.x_height, os_2.cap_height), (None, None));
+    assert_eq!((os_2.default_char, os_2.break_char, os_2.max_context), (None, None, None));
+    assert_eq!(os_2.optical_point_size_range, None);
+
+    let mut bytes = os_2_table_bytes(4);
+    set_u16(&mut bytes, OFFSET_BREAK_CHAR, 0x20);
+    set_u16(&mut bytes, OFFSET_MAX_CONTEXT, 2);
+    let os_2 = os_2_table(&bytes);
+    assert_eq!(os_2.version, 4);
+    assert_eq!((os_2.default_char, os_2.break_char, os_2.max_context),
+               (Some(0), Some(0x20), Some(2)));
+    assert_eq!(os_2.optical_point_size_range, None);
+
+    // Sizes from 9 points up to, but not including, 24 points.
+    let mut bytes = os_2_table_bytes(5);
+    set_u16(&mut bytes, OFFSET_DEFAULT_CHAR, 0x25a1);
+    set_u16(&mut bytes, OFFSET_LOWER_OPTICAL_POINT_SIZE, 180);
+    set_u16(&mut bytes, OFFSET_UPPER_OPTICAL_POINT_SIZE, 480);
+    let os_2 = os_2_table(&bytes);
+    assert_eq!(os_2.version, 5);
+    assert_eq!(os_2.default_char, Some(0x25a1));
+    assert_eq!(os_2.optical_point_size_range, Some((180, 480)));
+
+    // A version 5 table without the optical sizes is truncated.
+    bytes.truncate(OFFSET_UPPER_OPTICAL_POINT_SIZE);
+    assert_eq!(Os2Table::new(FontTable {
+        bytes: &bytes,
+    }).err(), Some(FontError::UnexpectedEofIn(ErrorContext {
+        table: os_2::TAG,
+        field: "upper optical point size",
+    })));
+}
+
+#[test]
+fn test_font_version() {
+    let file = Mmap::open_path(TEST_FONT_PATH, Protection::Read).expect("Couldn't open test font");
+    let os_2 = os_2_table(find_os_2_table(unsafe { file.as_slice() }));
+    assert_eq!(os_2.version, 1);
+    assert!(os_2.code_page_range.is_some());
+    assert_eq!((os_2.x_height, os_2.max_context), (None, None));
+}
+
+#[test]
+fn truncated_tables_report_the_missing_field() {
+    let mut bytes = os_2_table_bytes(1);
+    bytes.truncate(OFFSET_TYPO_LINE_GAP - 3);
+    let error = Os2Table::new(FontTable {
+        bytes: &bytes,
+    }).err().unwrap();
+    assert_eq!(error, FontError::UnexpectedEofIn(ErrorContext {
+        table: os_2::TAG,
+        field: "typo ascender",
+    }));
+    assert!(error.is_eof());
+
+    let mut bytes = os_2_table_bytes(2);
+    bytes.truncate(OFFSET_CAP_HEIGHT);
+    let error = Os2Table::new(FontTable {
+        bytes: &bytes,
+    }).err().unwrap();
+    assert_eq!(error, FontError::UnexpectedEofIn(ErrorContext {
+        table: os_2::TAG,
+        field: "cap height",
+    }));
+}
+
+#[test]
+fn embedding_restrictions() {
+    let os_2 = os_2_table(&os_2_table_bytes(4));
+    assert!(os_2.embedding.is_installable());
+    assert!(os_2.embedding.allows_editing());
+    assert!(os_2.embedding.allows_subsetting());
+
+    // Preview and print, no subsetting.
+    let mut bytes = os_2_table_bytes(4);
+    set_u16(&mut bytes, OFFSET_FS_TYPE, 0x0104);
+    let os_2 = os_2_table(&bytes);
+    assert!(!os_2.embedding.is_installable());
+    assert!(!os_2.embedding.allows_editing());
+    assert!(!os_2.embedding.allows_subsetting());
+
+    // Editable, as in Nimbus Sans.
+    let mut bytes = os_2_table_bytes(1);
+    set_u16(&mut bytes, OFFSET_FS_TYPE, 0x0008);
+    let os_2 = os_2_table(&bytes);
+    assert!(!os_2.embedding.is_installable());
+    assert!(os_2.embedding.allows_editing());
+    assert!(os_2.embedding.allows_subsetting());
+}
+
+#[test]
+fn unicode_ranges() {
+    // Basic Latin, Latin-1 Supplement, Latin Extended-A, and Cyrillic.
+    let mut bytes = os_2_table_bytes(1);
+    set_u32(&mut bytes, OFFSET_UNICODE_RANGE, 0x0000_0207);
+    let os_2 = os_2_table(&bytes);
+    assert!(os_2.covers_block(UnicodeBlock::BasicLatin));
+    assert!(os_2.covers_block(UnicodeBlock::Cyrillic));
+    assert!(!os_2.covers_block(UnicodeBlock::GreekAndCoptic));
+    assert!(!os_2.covers_block(UnicodeBlock::CjkUnifiedIdeographs));
+
+    // CJK Symbols and Punctuation, Hiragana, Katakana, and CJK Unified Ideographs in the second
+    // range; Halfwidth and Fullwidth Forms in the third.
+    let mut bytes = os_2_table_bytes(4);
+    set_u32(&mut bytes, OFFSET_UNICODE_RANGE + 4, 0x0807_0000);
+    set_u32(&mut bytes, OFFSET_UNICODE_RANGE + 8, 0x0000_0010);
+    let os_2 = os_2_table(&bytes);
+    assert!(!os_2.covers_block(UnicodeBlock::BasicLatin));
+    assert!(os_2.covers_block(UnicodeBlock::Hiragana));
+    assert!(os_2.covers_block(UnicodeBlock::Katakana));
+    assert!(os_2.covers_block(UnicodeBlock::CjkUnifiedIdeographs));
+    assert!(os_2.covers_block(UnicodeBlock::HalfwidthAndFullwidthForms));
+    assert!(os_2.supports_unicode_range(48));
+    assert!(!os_2.supports_unicode_range(200));
+}
+
+#[test]
+fn panose_classification() {
+    // Courier New, a monospaced font.
+    let mut bytes = os_2_table_bytes(4);
+    bytes[OFFSET_PANOSE..(OFFSET_PANOSE + 10)].copy_from_slice(&[2, 7, 3, 9, 2, 2, 5, 2, 4, 4]);
+    bytes[OFFSET_VENDOR_ID..(OFFSET_VENDOR_ID + 4)].copy_from_slice(b"MONO");
+    let os_2 = os_2_table(&bytes);
+    assert!(os_2.is_monospaced());
+    assert_eq!(os_2.vendor_id, *b"MONO");
+    assert_eq!(os_2.panose, Panose {
+        family_type: 2,
+        serif_style: 7,
+        weight: 3,
+        proportion: 9,
+        contrast: 2,
+        stroke_variation: 2,
+        arm_style: 5,
+        letterform: 2,
+        midline: 4,
+        x_height: 4,
+    });
+
+    // Times New Roman, a proportional serif font.
+    let mut bytes = os_2_table_bytes(4);
+    bytes[OFFSET_PANOSE..(OFFSET_PANOSE + 10)].copy_from_slice(&[2, 2, 6, 3, 5, 4, 5, 2, 3, 4]);
+    let os_2 = os_2_table(&bytes);
+    assert!(!os_2.is_monospaced());
+    assert_eq!((os_2.panose.serif_style, os_2.panose.weight), (2, 6));
+}
+
+#[test]
+fn test_font_vendor_id() {
+    let file = Mmap::open_path(TEST_FONT_PATH, Protection::Read).expect("Couldn't open test font");
+    let os_2 = os_2_table(find_os_2_table(unsafe { file.as_slice() }));
+    assert_eq!(os_2.vendor_id, *b"URW ");
+    assert_eq!(os_2.panose.family_type, 0);
+    assert!(!os_2.is_monospaced());
+}
+
+#[test]
+fn test_font_win_and_typo_metrics() {
+    let file = Mmap::open_path(TEST_FONT_PATH, Protection::Read).expect("Couldn't open test font");
+    let os_2 = os_2_table(find_os_2_table(unsafe { file.as_slice() }));
+    assert_eq!(os_2.avg_char_width, 904);
+    assert_eq!((os_2.typo_ascender, os_2.typo_descender, os_2.typo_line_gap), (1599, -449, 0));
+    assert_eq!((os_2.win_ascent, os_2.win_descent), (1953, 451));
+    assert!(os_2.win_ascent as i16 > os_2.typo_ascender);
+    assert!(os_2.supports_latin1() && !os_2.supports_shift_jis());
+}
+
+#[test]
+fn code_page_ranges() {
+    // Latin 1 and Shift-JIS in the first range, as in MS Gothic, plus a bit in the second range.
+    let mut bytes = os_2_table_bytes(1);
+    set_u32(&mut bytes, OFFSET_CODE_PAGE_RANGE, 0x0002_0001);
+    set_u32(&mut bytes, OFFSET_CODE_PAGE_RANGE + 4, 0x8000_0000);
+    let os_2 = os_2_table(&bytes);
+    assert_eq!(os_2.code_page_range, Some([0x0002_0001, 0x8000_0000]));
+    assert!(os_2.supports_shift_jis());
+    assert!(os_2.supports_latin1());
+    assert!(!os_2.supports_code_page(18));
+    assert!(os_2.supports_code_page(63));
+    assert!(!os_2.supports_code_page(64));
+
+    // Version 0 tables have no code page ranges.
+    let os_2 = os_2_table(&os_2_table_bytes(0));
+    assert_eq!(os_2.code_page_range, None);
+    assert!(!os_2.supports_latin1() && !os_2.supports_shift_jis());
+}
+
+#[test]
+fn test_font_sub_super_strike_metrics() {
+    let file = Mmap::open_path(TEST_FONT_PATH, Protection::Read).expect("Couldn't open test font");
+    let os_2 = os_2_table(find_os_2_table(unsafe { file.as_slice() }));
+    assert_eq!(os_2.weight_class, 400);
+    assert!(os_2.is_regular());
+    assert_eq!(os_2.sub_super_strike, SubSuperStrikeMetrics {
+        subscript_x_size: 1331,
+        subscript_y_size: 1331,
+        subscript_x_offset: 0,
+        subscript_y_offset: 307,
+        superscript_x_size: 1331,
+        superscript_y_size: 1331,
+        superscript_x_offset: 0,
+        superscript_y_offset: 983,
+        strikeout_size: 102,
+        strikeout_position: 485,
+    });
+}
@@ -0,0 +1,70 @@
+/* Any copyright is dedicated to the Public Domain.
+ * http://creativecommons.org/publicdomain/zero/1.0/ */
+
+use error::FontError;
+use font::{TableDirectory, TableRecord};
+use memmap::{Mmap, Protection};
+use util::tag;
+
+static TEST_FONT_PATH: &'static str = "resources/tests/nimbus-sans/NimbusSanL-Regu.ttf";
+
+#[test]
+fn test_font_tables() {
+    let file = Mmap::open_path(TEST_FONT_PATH, Protection::Read).expect("Couldn't open test font");
+    let bytes = unsafe { file.as_slice() };
+    let directory = TableDirectory::new(bytes).unwrap();
+    assert_eq!(directory.sfnt_version(), 0x10000);
+    assert_eq!(directory.num_tables(), 16);
+
+    let tags: Vec<_> = directory.records().map(|record| record.tag).collect();
+    let expected: Vec<_> = [
+        b"OS/2", b"cmap", b"cvt ", b"fpgm", b"gasp", b"glyf", b"head", b"hhea",
+        b"hmtx", b"loca", b"maxp", b"name", b"post", b"prep", b"vhea", b"vmtx",
+    ].iter().map(|name| tag(name)).collect();
+    assert_eq!(tags, expected);
+
+    assert_eq!(directory.records().next(), Some(TableRecord {
+        tag: tag(b"OS/2"),
+        checksum: 0xef6c04cd,
+        offset: 268,
+        length: 86,
+    }));
+    let gasp = directory.table(tag(b"gasp")).unwrap();
+    assert_eq!(gasp.bytes, &bytes[2288..2304]);
+    assert!(directory.table(tag(b"CFF ")).is_none());
+}
+
+#[test]
+fn malformed_directories_are_rejected() {
+    let file = Mmap::open_path(TEST_FONT_PATH, Protection::Read).expect("Couldn't open test font");
+    let mut bytes = unsafe { file.as_slice() }.to_vec();
+
+    // Truncated in the middle of the table records.
+    assert_eq!(TableDirectory::new(&bytes[0..100]).err(), Some(FontError::UnexpectedEof));
+
+    // A search range too big for 16 tables.
+    bytes[6] = 0x02;
+    assert_eq!(TableDirectory::new(&bytes).err(), Some(FontError::BadTableDirectory));
+
+    bytes[0] = b'x';
+    assert_eq!(TableDirectory::new(&bytes).err(), Some(FontError::UnknownFormat));
+}
+
+#[test]
+fn checksums() {
+    let file = Mmap::open_path(TEST_FONT_PATH, Protection::Read).expect("Couldn't open test font");
+    let mut bytes = unsafe { file.as_slice() }.to_vec();
+    assert_eq!(TableDirectory::new(&bytes).unwrap().verify_checksums(), Ok(()));
+
+    // Flip a byte in the `glyf` table, which starts at offset 2304.
+    bytes[2400] ^= 0xff;
+    assert_eq!(TableDirectory::new(&bytes).unwrap().verify_checksums(),
+               Err(FontError::BadTableChecksum(tag(b"glyf"))));
+    bytes[2400] ^= 0xff;
+
+    // Changing the checksum adjustment in the `head` table, which starts at offset 93832, doesn't
+    // affect the table's own checksum, only the whole font's.
+    bytes[93832 + 11] ^= 0x01;
+    assert_eq!(TableDirectory::new(&bytes).unwrap().verify_checksums(),
+               Err(FontError::BadFontChecksum));
+}
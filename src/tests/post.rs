@@ -0,0 +1,85 @@
+/* Any copyright is dedicated to the Public Domain.
+ * http://creativecommons.org/publicdomain/zero/1.0/ */
+
+use error::FontError;
+use font::{Font, FontTable};
+use memmap::{Mmap, Protection};
+use tables::post::PostTable;
+
+static TEST_FONT_PATH: &'static str = "resources/tests/nimbus-sans/NimbusSanL-Regu.ttf";
+
+// Builds a `post` table with the given version and, for version 2.0, glyph name indices and
+// custom names.
+fn post_table_bytes(version: u32, glyph_name_indices: &[u16], names: &[&str]) -> Vec<u8> {
+    let mut bytes = vec![(version >> 24) as u8, (version >> 16) as u8, (version >> 8) as u8,
+                         version as u8];
+    bytes.extend_from_slice(&[0; 28]);
+    if version != 0x00020000 {
+        return bytes
+    }
+
+    bytes.extend_from_slice(&[(glyph_name_indices.len() >> 8) as u8,
+                              glyph_name_indices.len() as u8]);
+    for &index in glyph_name_indices {
+        bytes.extend_from_slice(&[(index >> 8) as u8, index as u8])
+    }
+    for name in names {
+        bytes.push(name.len() as u8);
+        bytes.extend_from_slice(name.as_bytes())
+    }
+    bytes
+}
+
+#[test]
+fn version_2_custom_names() {
+    let bytes = post_table_bytes(0x00020000, &[0, 36, 259, 258, 300], &["Euro", "f_f"]);
+    let post = PostTable::new(FontTable {
+        bytes: &bytes,
+    }).unwrap();
+
+    assert_eq!(post.glyph_name(0), Some(".notdef"));
+    assert_eq!(post.glyph_name(1), Some("A"));
+    assert_eq!(post.glyph_name(2), Some("f_f"));
+    assert_eq!(post.glyph_name(3), Some("Euro"));
+    assert_eq!(post.glyph_name(4), None);
+    assert_eq!(post.glyph_name(5), None);
+
+    assert_eq!(post.glyph_for_name("A"), Some(1));
+    assert_eq!(post.glyph_for_name("f_f"), Some(2));
+    assert_eq!(post.glyph_for_name("Euro"), Some(3));
+    assert_eq!(post.glyph_for_name("B"), None);
+    assert_eq!(post.glyph_for_name("f_f_i"), None);
+}
+
+#[test]
+fn versions_1_and_3() {
+    let bytes = post_table_bytes(0x00010000, &[], &[]);
+    let post = PostTable::new(FontTable {
+        bytes: &bytes,
+    }).unwrap();
+    assert_eq!(post.glyph_name(3), Some("space"));
+    assert_eq!(post.glyph_for_name("dcroat"), Some(257));
+
+    let bytes = post_table_bytes(0x00030000, &[], &[]);
+    let post = PostTable::new(FontTable {
+        bytes: &bytes,
+    }).unwrap();
+    assert_eq!(post.glyph_name(3), None);
+    assert_eq!(post.glyph_for_name("space"), None);
+
+    let bytes = post_table_bytes(0x00040000, &[], &[]);
+    assert_eq!(PostTable::new(FontTable {
+        bytes: &bytes,
+    }).err(), Some(FontError::UnsupportedPostVersion));
+}
+
+#[test]
+fn test_font_glyph_names() {
+    let file = Mmap::open_path(TEST_FONT_PATH, Protection::Read).expect("Couldn't open test font");
+    let mut buffer = vec![];
+    let font = Font::new(unsafe { file.as_slice() }, &mut buffer).unwrap();
+    assert_eq!(font.glyph_name(3), Some("space"));
+    assert_eq!(font.glyph_name(36), Some("A"));
+    assert_eq!(font.glyph_name(210), Some("Euro"));
+    assert_eq!(font.glyph_for_name("Euro"), Some(210));
+}
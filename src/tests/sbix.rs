@@ -0,0 +1,107 @@
+/* Any copyright is dedicated to the Public Domain.
+ * http://creativecommons.org/publicdomain/zero/1.0/ */
+
+use font::{BitmapFormat, BitmapStrike, FontTable};
+use tables::sbix::SbixTable;
+use util::{push_u16, push_u32};
+
+const NUM_GLYPHS: u16 = 4;
+
+static PNG_SMALL: &'static [u8] = b"\x89PNG small";
+static PNG_LARGE: &'static [u8] = b"\x89PNG large";
+static JPEG_LARGE: &'static [u8] = b"\xff\xd8\xff large";
+
+// Builds a strike with the given graphic type and data for each glyph, if any.
+fn strike_bytes(ppem: u16, glyphs: &[Option<(&[u8; 4], &[u8])>]) -> Vec<u8> {
+    let mut data = vec![];
+    let mut offsets = vec![];
+    let data_offset = 4 + 4 * (glyphs.len() as u32 + 1);
+    for glyph in glyphs {
+        offsets.push(data_offset + data.len() as u32);
+        if let Some((graphic_type, image)) = *glyph {
+            push_u16(&mut data, 0);
+            push_u16(&mut data, 0);
+            data.extend_from_slice(graphic_type);
+            data.extend_from_slice(image);
+        }
+    }
+    offsets.push(data_offset + data.len() as u32);
+
+    let mut bytes = vec![];
+    push_u16(&mut bytes, ppem);
+    push_u16(&mut bytes, 72);
+    for &offset in &offsets {
+        push_u32(&mut bytes, offset)
+    }
+    bytes.extend_from_slice(&data);
+    bytes
+}
+
+// A large strike listed before a small one. Glyph 0 has no bitmaps, glyph 1 is a PNG, glyph 2 is
+// a duplicate of glyph 1, and glyph 3 only has a large JPEG.
+fn sbix_table_bytes() -> Vec<u8> {
+    let strikes = [
+        strike_bytes(40, &[None,
+                           Some((b"png ", PNG_LARGE)),
+                           Some((b"dupe", &[0, 1])),
+                           Some((b"jpg ", JPEG_LARGE))]),
+        strike_bytes(20, &[None, Some((b"png ", PNG_SMALL)), Some((b"dupe", &[0, 1])), None]),
+    ];
+
+    let mut bytes = vec![];
+    push_u16(&mut bytes, 1);
+    push_u16(&mut bytes, 1);
+    push_u32(&mut bytes, strikes.len() as u32);
+    let mut offset = 8 + 4 * strikes.len() as u32;
+    for strike in &strikes {
+        push_u32(&mut bytes, offset);
+        offset += strike.len() as u32
+    }
+    for strike in &strikes {
+        bytes.extend_from_slice(strike)
+    }
+    bytes
+}
+
+#[test]
+fn strikes() {
+    let bytes = sbix_table_bytes();
+    let sbix = SbixTable::new(FontTable {
+        bytes: &bytes,
+    }, NUM_GLYPHS).unwrap();
+    assert_eq!(sbix.strikes(), Ok(vec![
+        BitmapStrike {
+            ppem: 40,
+            resolution: 72,
+        },
+        BitmapStrike {
+            ppem: 20,
+            resolution: 72,
+        },
+    ]));
+}
+
+#[test]
+fn png_bitmap_data() {
+    let bytes = sbix_table_bytes();
+    let sbix = SbixTable::new(FontTable {
+        bytes: &bytes,
+    }, NUM_GLYPHS).unwrap();
+    assert_eq!(sbix.bitmap_data(1, 12), Ok(Some((BitmapFormat::Png, PNG_SMALL))));
+    assert_eq!(sbix.bitmap_data(1, 20), Ok(Some((BitmapFormat::Png, PNG_SMALL))));
+    assert_eq!(sbix.bitmap_data(1, 21), Ok(Some((BitmapFormat::Png, PNG_LARGE))));
+    assert_eq!(sbix.bitmap_data(1, 96), Ok(Some((BitmapFormat::Png, PNG_LARGE))));
+}
+
+#[test]
+fn duplicate_and_missing_bitmaps() {
+    let bytes = sbix_table_bytes();
+    let sbix = SbixTable::new(FontTable {
+        bytes: &bytes,
+    }, NUM_GLYPHS).unwrap();
+    assert_eq!(sbix.bitmap_data(2, 20), Ok(Some((BitmapFormat::Png, PNG_SMALL))));
+    assert_eq!(sbix.bitmap_data(3, 40), Ok(Some((BitmapFormat::Jpeg, JPEG_LARGE))));
+    assert_eq!(sbix.bitmap_data(3, 20), Ok(None));
+    assert_eq!(sbix.bitmap_data(0, 20), Ok(None));
+    assert_eq!(sbix.bitmap_data(NUM_GLYPHS, 20), Ok(None));
+}
@@ -0,0 +1,217 @@
+/* Any copyright is dedicated to the Public Domain.
+ * http://creativecommons.org/publicdomain/zero/1.0/ */
+
+use error::FontError;
+use font::{AxisValue, AxisValueKind, FontTable, StyleAxis};
+use tables::name::NameTable;
+use tables::stat::StatTable;
+use util::{push_fixed, push_u16, push_u32, tag};
+
+// A family with weight and italic axes. Weights of 350 to 450 are "Regular", which is elidable,
+// and 400 in particular links to "Bold" at 700. "SemiBold" is a range, and "ExtraBold Italic" is
+// a combination of both axes. The last axis value has a format that doesn't exist.
+fn axis_value_tables() -> Vec<Vec<u8>> {
+    let mut regular_range = vec![];
+    for &value in &[2, 0, 0, 256] {
+        push_u16(&mut regular_range, value)
+    }
+    for &value in &[400.0, 350.0, 450.0] {
+        push_fixed(&mut regular_range, value)
+    }
+
+    let mut regular = vec![];
+    for &value in &[3, 0, 2, 257] {
+        push_u16(&mut regular, value)
+    }
+    push_fixed(&mut regular, 400.0);
+    push_fixed(&mut regular, 700.0);
+
+    let mut semibold = vec![];
+    for &value in &[2, 0, 0, 258] {
+        push_u16(&mut semibold, value)
+    }
+    for &value in &[600.0, 550.0, 650.0] {
+        push_fixed(&mut semibold, value)
+    }
+
+    let mut bold = vec![];
+    for &value in &[1, 0, 0, 259] {
+        push_u16(&mut bold, value)
+    }
+    push_fixed(&mut bold, 700.0);
+
+    let mut extrabold_italic = vec![];
+    for &value in &[4, 2, 0, 260] {
+        push_u16(&mut extrabold_italic, value)
+    }
+    push_u16(&mut extrabold_italic, 0);
+    push_fixed(&mut extrabold_italic, 800.0);
+    push_u16(&mut extrabold_italic, 1);
+    push_fixed(&mut extrabold_italic, 1.0);
+
+    let mut roman = vec![];
+    for &value in &[1, 1, 2, 261] {
+        push_u16(&mut roman, value)
+    }
+    push_fixed(&mut roman, 0.0);
+
+    let mut unknown = vec![];
+    for &value in &[5, 0, 0, 262] {
+        push_u16(&mut unknown, value)
+    }
+
+    vec![regular_range, regular, semibold, bold, extrabold_italic, roman, unknown]
+}
+
+fn stat_table_bytes(major_version: u16, minor_version: u16) -> Vec<u8> {
+    let header_size = if minor_version >= 1 { 20 } else { 18 };
+    let axes: &[(&[u8; 4], u16, u16)] = &[(b"wght", 270, 0), (b"ital", 271, 1)];
+    let axis_values = axis_value_tables();
+    let axis_value_offsets_offset = header_size + 8 * axes.len();
+
+    let mut bytes = vec![];
+    for &value in &[major_version, minor_version, 8, axes.len() as u16] {
+        push_u16(&mut bytes, value)
+    }
+    push_u32(&mut bytes, header_size as u32);
+    push_u16(&mut bytes, axis_values.len() as u16);
+    push_u32(&mut bytes, axis_value_offsets_offset as u32);
+    if minor_version >= 1 {
+        push_u16(&mut bytes, 263)
+    }
+
+    for &(axis_tag, axis_name_id, ordering) in axes {
+        bytes.extend_from_slice(axis_tag);
+        push_u16(&mut bytes, axis_name_id);
+        push_u16(&mut bytes, ordering)
+    }
+
+    let mut offset = 2 * axis_values.len();
+    for axis_value in &axis_values {
+        push_u16(&mut bytes, offset as u16);
+        offset += axis_value.len()
+    }
+    for axis_value in &axis_values {
+        bytes.extend_from_slice(axis_value)
+    }
+    bytes
+}
+
+fn stat_table(bytes: &[u8]) -> Result<StatTable, FontError> {
+    StatTable::new(FontTable {
+        bytes: bytes,
+    })
+}
+
+#[test]
+fn design_axes_and_axis_values() {
+    let bytes = stat_table_bytes(1, 1);
+    let stat = stat_table(&bytes).unwrap();
+    assert_eq!(stat.axes(), &[
+        StyleAxis {
+            tag: tag(b"wght"),
+            axis_name_id: 270,
+            ordering: 0,
+        },
+        StyleAxis {
+            tag: tag(b"ital"),
+            axis_name_id: 271,
+            ordering: 1,
+        },
+    ]);
+
+    // The value with an unknown format is skipped.
+    let axis_values = stat.axis_values();
+    assert_eq!(axis_values.len(), 6);
+    assert_eq!(axis_values[1], AxisValue {
+        kind: AxisValueKind::Linked {
+            axis_index: 0,
+            value: 400.0,
+            linked_value: 700.0,
+        },
+        flags: 2,
+        value_name_id: 257,
+    });
+    assert!(axis_values[1].is_elidable());
+    assert_eq!(axis_values[2].kind, AxisValueKind::Range {
+        axis_index: 0,
+        nominal_value: 600.0,
+        min_value: 550.0,
+        max_value: 650.0,
+    });
+    assert!(!axis_values[2].is_elidable());
+    assert_eq!(axis_values[3].kind, AxisValueKind::Single {
+        axis_index: 0,
+        value: 700.0,
+    });
+    assert_eq!(axis_values[4].kind, AxisValueKind::Combination(vec![(0, 800.0), (1, 1.0)]));
+}
+
+#[test]
+fn elided_fallback_name_id() {
+    assert_eq!(stat_table(&stat_table_bytes(1, 1)).unwrap().elided_fallback_name_id(), 263);
+
+    // Version 1.0 tables don't have one, so the subfamily name is used.
+    assert_eq!(stat_table(&stat_table_bytes(1, 0)).unwrap().elided_fallback_name_id(), 2);
+}
+
+#[test]
+fn value_name_ids() {
+    let bytes = stat_table_bytes(1, 1);
+    let stat = stat_table(&bytes).unwrap();
+    let weight = tag(b"wght");
+
+    // The exact value 400 wins over the range that contains it.
+    assert_eq!(stat.value_name_id(weight, 400.0), Some(257));
+    assert_eq!(stat.value_name_id(weight, 360.0), Some(256));
+    assert_eq!(stat.value_name_id(weight, 600.0), Some(258));
+    assert_eq!(stat.value_name_id(weight, 650.0), Some(258));
+    assert_eq!(stat.value_name_id(weight, 700.0), Some(259));
+    assert_eq!(stat.value_name_id(weight, 500.0), None);
+
+    // Combinations don't name single values.
+    assert_eq!(stat.value_name_id(weight, 800.0), None);
+
+    assert_eq!(stat.value_name_id(tag(b"ital"), 0.0), Some(261));
+    assert_eq!(stat.value_name_id(tag(b"wdth"), 100.0), None);
+}
+
+#[test]
+fn style_name_for_weight() {
+    let stat_bytes = stat_table_bytes(1, 1);
+    let stat = stat_table(&stat_bytes).unwrap();
+
+    // A format 0 `name` table with American English names for the weights.
+    let names = [(257, "Regular"), (258, "SemiBold"), (259, "Bold")];
+    let mut name_bytes = vec![];
+    for &value in &[0, names.len() as u16, 6 + 12 * names.len() as u16] {
+        push_u16(&mut name_bytes, value)
+    }
+    let mut strings = vec![];
+    for &(name_id, name) in &names {
+        let start = strings.len();
+        for code_unit in name.encode_utf16() {
+            push_u16(&mut strings, code_unit)
+        }
+        for &value in &[3, 1, 0x0409, name_id, (strings.len() - start) as u16, start as u16] {
+            push_u16(&mut name_bytes, value)
+        }
+    }
+    name_bytes.extend_from_slice(&strings);
+    let name = NameTable::new(FontTable {
+        bytes: &name_bytes,
+    }).unwrap();
+
+    let style_name = |weight: f32| {
+        stat.value_name_id(tag(b"wght"), weight).and_then(|name_id| name.get_by_id(name_id))
+    };
+    assert_eq!(style_name(400.0), Some("Regular".to_owned()));
+    assert_eq!(style_name(620.0), Some("SemiBold".to_owned()));
+    assert_eq!(style_name(700.0), Some("Bold".to_owned()));
+    assert_eq!(style_name(500.0), None);
+}
+
+#[test]
+fn unsupported_version() {
+    assert_eq!(stat_table(&stat_table_bytes(2, 0)).err(), Some(FontError::UnsupportedStatVersion));
+}
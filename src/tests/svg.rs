@@ -0,0 +1,71 @@
+/* Any copyright is dedicated to the Public Domain.
+ * http://creativecommons.org/publicdomain/zero/1.0/ */
+
+use flate2::Compression;
+use flate2::write::GzEncoder;
+use font::FontTable;
+use std::borrow::Cow;
+use std::io::Write;
+use tables::svg::SvgTable;
+use util::{push_u16, push_u32};
+
+static PLAIN_DOCUMENT: &'static [u8] =
+    b"<svg xmlns=\"http://www.w3.org/2000/svg\"><path id=\"glyph2\" d=\"M0 0h10v10z\"/></svg>";
+static COMPRESSED_DOCUMENT: &'static [u8] =
+    b"<svg xmlns=\"http://www.w3.org/2000/svg\"><circle id=\"glyph5\" r=\"5\"/></svg>";
+
+fn gzip(data: &[u8]) -> Vec<u8> {
+    let mut encoder = GzEncoder::new(vec![], Compression::Default);
+    encoder.write_all(data).unwrap();
+    encoder.finish().unwrap()
+}
+
+// Glyphs 2 and 3 share a plain document; glyphs 5 through 7 share a compressed one.
+fn svg_table_bytes() -> Vec<u8> {
+    let compressed_document = gzip(COMPRESSED_DOCUMENT);
+    let documents_offset = 2 + 12 * 2;
+
+    let mut bytes = vec![];
+    push_u16(&mut bytes, 0);
+    push_u32(&mut bytes, 10);
+    push_u32(&mut bytes, 0);
+
+    push_u16(&mut bytes, 2);
+    for &(start, end, offset, length) in &[
+        (2, 3, documents_offset, PLAIN_DOCUMENT.len() as u32),
+        (5, 7, documents_offset + PLAIN_DOCUMENT.len() as u32, compressed_document.len() as u32),
+    ] {
+        push_u16(&mut bytes, start);
+        push_u16(&mut bytes, end);
+        push_u32(&mut bytes, offset);
+        push_u32(&mut bytes, length)
+    }
+    bytes.extend_from_slice(PLAIN_DOCUMENT);
+    bytes.extend_from_slice(&compressed_document);
+    bytes
+}
+
+#[test]
+fn plain_document() {
+    let bytes = svg_table_bytes();
+    let svg = SvgTable::new(FontTable {
+        bytes: &bytes,
+    }).unwrap();
+    assert_eq!(svg.svg_document(2), Ok(Some(Cow::Borrowed(PLAIN_DOCUMENT))));
+    assert_eq!(svg.svg_document(3), Ok(Some(Cow::Borrowed(PLAIN_DOCUMENT))));
+    assert_eq!(svg.svg_document(1), Ok(None));
+    assert_eq!(svg.svg_document(4), Ok(None));
+}
+
+#[test]
+fn compressed_document() {
+    let bytes = svg_table_bytes();
+    let svg = SvgTable::new(FontTable {
+        bytes: &bytes,
+    }).unwrap();
+    for glyph_id in 5..8 {
+        let document = svg.svg_document(glyph_id).unwrap().unwrap();
+        assert_eq!(&*document, COMPRESSED_DOCUMENT);
+    }
+    assert_eq!(svg.svg_document(8), Ok(None));
+}
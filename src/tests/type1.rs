@@ -0,0 +1,340 @@
+/* Any copyright is dedicated to the Public Domain.
+ * http://creativecommons.org/publicdomain/zero/1.0/ */
+
+use error::FontError;
+use euclid::{Matrix2D, Point2D};
+use font::{Point, PointKind};
+use type1::Type1Font;
+
+use self::Token::{Esc, N, Op};
+
+// CharString operators.
+const HSBW: u8 = 13;
+const RMOVETO: u8 = 21;
+const RLINETO: u8 = 5;
+const HVCURVETO: u8 = 31;
+const CLOSEPATH: u8 = 9;
+const CALLSUBR: u8 = 10;
+const RETURN: u8 = 11;
+const ENDCHAR: u8 = 14;
+
+// Escaped CharString operators.
+const SEAC: u8 = 6;
+const DIV: u8 = 12;
+
+enum Token {
+    N(i32),
+    Op(u8),
+    Esc(u8),
+}
+
+fn char_string(tokens: &[Token]) -> Vec<u8> {
+    let mut bytes = vec![];
+    for token in tokens {
+        match *token {
+            N(value) if value >= -107 && value <= 107 => bytes.push((value + 139) as u8),
+            N(value) if value >= 108 && value <= 1131 => {
+                bytes.push(((value - 108) / 256 + 247) as u8);
+                bytes.push(((value - 108) % 256) as u8)
+            }
+            N(value) if value >= -1131 && value <= -108 => {
+                bytes.push(((-value - 108) / 256 + 251) as u8);
+                bytes.push(((-value - 108) % 256) as u8)
+            }
+            N(value) => {
+                bytes.extend_from_slice(&[255,
+                                          (value >> 24) as u8,
+                                          (value >> 16) as u8,
+                                          (value >> 8) as u8,
+                                          value as u8])
+            }
+            Op(operator) => bytes.push(operator),
+            Esc(operator) => bytes.extend_from_slice(&[12, operator]),
+        }
+    }
+    bytes
+}
+
+// Encrypts the given bytes after four bytes of (not very) random padding.
+fn encrypt(plain: &[u8], key: u16) -> Vec<u8> {
+    let mut r = key;
+    [0, 0, 0, 0].iter().chain(plain.iter()).map(|&byte| {
+        let cipher = byte ^ (r >> 8) as u8;
+        r = (cipher as u16).wrapping_add(r).wrapping_mul(52845).wrapping_add(22719);
+        cipher
+    }).collect()
+}
+
+fn binary_entry(prefix: &str, char_string: &[u8], suffix: &str) -> Vec<u8> {
+    let encrypted = encrypt(char_string, 4330);
+    let mut bytes = format!("{} {} RD ", prefix, encrypted.len()).into_bytes();
+    bytes.extend_from_slice(&encrypted);
+    bytes.extend_from_slice(format!(" {}\n", suffix).as_bytes());
+    bytes
+}
+
+fn notdef() -> Vec<u8> {
+    char_string(&[N(0), N(500), Op(HSBW), Op(ENDCHAR)])
+}
+
+// A triangle, with one side drawn by a subroutine.
+fn triangle() -> Vec<u8> {
+    char_string(&[
+        N(50), N(600), Op(HSBW),
+        N(0), N(100), Op(RMOVETO),
+        N(500), N(0), Op(RLINETO),
+        N(0), Op(CALLSUBR),
+        Op(CLOSEPATH),
+        Op(ENDCHAR),
+    ])
+}
+
+fn triangle_subr() -> Vec<u8> {
+    char_string(&[N(-250), N(400), Op(RLINETO), Op(RETURN)])
+}
+
+// A curve whose first operand is computed with `div`.
+fn curve() -> Vec<u8> {
+    char_string(&[
+        N(0), N(600), Op(HSBW),
+        N(100), N(0), Op(RMOVETO),
+        N(400), N(2), Esc(DIV), N(100), N(100), N(200), Op(HVCURVETO),
+        Op(CLOSEPATH),
+        Op(ENDCHAR),
+    ])
+}
+
+fn acute() -> Vec<u8> {
+    char_string(&[
+        N(0), N(300), Op(HSBW),
+        N(0), N(700), Op(RMOVETO),
+        N(100), N(0), Op(RLINETO),
+        Op(ENDCHAR),
+    ])
+}
+
+// `A` with `acute` on top, 200 units to the right. 65 and 194 are `A` and `acute` in Standard
+// Encoding.
+fn a_acute() -> Vec<u8> {
+    char_string(&[
+        N(50), N(600), Op(HSBW),
+        N(50), N(250), N(0), N(65), N(194), Esc(SEAC),
+    ])
+}
+
+fn cleartext(encoding: &str) -> Vec<u8> {
+    format!("%!PS-AdobeFont-1.0: Test 001.000\n\
+             %%Title: Test\n\
+             12 dict begin\n\
+             /FontName /Test def\n\
+             /FontMatrix [0.001 0 0 0.001 0 0] readonly def\n\
+             /Encoding {} def\n\
+             currentfile eexec\n",
+            encoding).into_bytes()
+}
+
+// The decrypted private part of a font with the given CharStrings.
+fn private(subrs: &[Vec<u8>], char_strings: &[(&str, Vec<u8>)]) -> Vec<u8> {
+    let mut bytes = b"dup /Private 8 dict dup begin\n\
+                      /RD {string currentfile exch readstring pop} executeonly def\n\
+                      /ND {noaccess def} executeonly def\n\
+                      /NP {noaccess put} executeonly def\n\
+                      /lenIV 4 def\n".to_vec();
+    bytes.extend_from_slice(format!("/Subrs {} array\n", subrs.len()).as_bytes());
+    for (index, subr) in subrs.iter().enumerate() {
+        bytes.extend_from_slice(&binary_entry(&format!("dup {}", index), subr, "NP"))
+    }
+    bytes.extend_from_slice(format!("ND\n2 index /CharStrings {} dict dup begin\n",
+                                    char_strings.len()).as_bytes());
+    for &(name, ref char_string) in char_strings {
+        bytes.extend_from_slice(&binary_entry(&format!("/{}", name), char_string, "ND"))
+    }
+    bytes.extend_from_slice(b"end\nend\nreadonly put\nnoaccess put\n\
+                              dup /FontName get exch definefont pop\n\
+                              mark currentfile closefile\n");
+    bytes
+}
+
+fn trailer() -> Vec<u8> {
+    let mut bytes = vec![];
+    for _ in 0..8 {
+        bytes.extend_from_slice(&[b'0'; 64]);
+        bytes.push(b'\n')
+    }
+    bytes.extend_from_slice(b"cleartomark\n");
+    bytes
+}
+
+fn pfa(cleartext: &[u8], private: &[u8]) -> Vec<u8> {
+    let mut bytes = cleartext.to_vec();
+    for line in encrypt(private, 55665).chunks(32) {
+        for byte in line {
+            bytes.extend_from_slice(format!("{:02x}", byte).as_bytes())
+        }
+        bytes.push(b'\n')
+    }
+    bytes.extend_from_slice(&trailer());
+    bytes
+}
+
+fn pfb(cleartext: &[u8], private: &[u8]) -> Vec<u8> {
+    let mut bytes = vec![];
+    for &(segment_type, ref segment) in &[(1, cleartext.to_vec()),
+                                          (2, encrypt(private, 55665)),
+                                          (1, trailer())] {
+        let length = segment.len() as u32;
+        bytes.extend_from_slice(&[0x80,
+                                  segment_type,
+                                  length as u8,
+                                  (length >> 8) as u8,
+                                  (length >> 16) as u8,
+                                  (length >> 24) as u8]);
+        bytes.extend_from_slice(segment)
+    }
+    bytes.extend_from_slice(&[0x80, 3]);
+    bytes
+}
+
+fn test_private() -> Vec<u8> {
+    private(&[triangle_subr()],
+            &[("A", triangle()),
+              (".notdef", notdef()),
+              ("B", curve()),
+              ("acute", acute()),
+              ("Aacute", a_acute())])
+}
+
+fn glyph_points(font: &Type1Font, name: &str) -> Result<Vec<Point>, FontError> {
+    let glyph_id = font.glyph_for_name(name).unwrap();
+    let mut points = vec![];
+    try!(font.for_each_point(glyph_id, |point| points.push(*point)));
+    Ok(points)
+}
+
+fn point(x: i16, y: i16, index_in_contour: u16, kind: PointKind) -> Point {
+    Point {
+        position: Point2D::new(x, y),
+        index_in_contour: index_in_contour,
+        kind: kind,
+    }
+}
+
+fn triangle_points() -> Vec<Point> {
+    vec![
+        point(50, 100, 0, PointKind::OnCurve),
+        point(550, 100, 1, PointKind::OnCurve),
+        point(300, 500, 2, PointKind::OnCurve),
+        point(50, 100, 3, PointKind::OnCurve),
+    ]
+}
+
+#[test]
+fn glyphs_are_listed_with_notdef_first() {
+    let font = Type1Font::new(&pfa(&cleartext("StandardEncoding"), &test_private())).unwrap();
+    assert_eq!(font.num_glyphs(), 5);
+    assert_eq!(font.glyph_name(0), Some(".notdef"));
+    assert_eq!(font.glyph_name(1), Some("A"));
+    assert_eq!(font.glyph_name(5), None);
+    assert_eq!(font.glyph_for_name("B"), Some(2));
+    assert_eq!(font.glyph_for_name("C"), None);
+    assert_eq!(font.font_matrix(), Matrix2D::create_scale(0.001, 0.001));
+}
+
+#[test]
+fn simple_glyph_with_subroutine() {
+    let font = Type1Font::new(&pfa(&cleartext("StandardEncoding"), &test_private())).unwrap();
+    assert_eq!(glyph_points(&font, "A"), Ok(triangle_points()));
+    assert_eq!(font.advance_width(font.glyph_for_name("A").unwrap()), Ok(600.0));
+    assert_eq!(glyph_points(&font, ".notdef"), Ok(vec![]));
+}
+
+#[test]
+fn curve_with_div() {
+    let font = Type1Font::new(&pfa(&cleartext("StandardEncoding"), &test_private())).unwrap();
+    assert_eq!(glyph_points(&font, "B"), Ok(vec![
+        point(100, 0, 0, PointKind::OnCurve),
+        point(300, 0, 1, PointKind::FirstCubicControl),
+        point(400, 100, 2, PointKind::SecondCubicControl),
+        point(400, 300, 3, PointKind::OnCurve),
+        point(100, 0, 4, PointKind::OnCurve),
+    ]));
+}
+
+#[test]
+fn current_point_is_rounded_only_when_emitted() {
+    // Three lines of 301 / 3 units each, which end at 301 rather than 300, and then a line far out
+    // of range and back, which is clamped on the way out but not on the way back.
+    let mut tokens = vec![N(0), N(600), Op(HSBW), N(0), N(0), Op(RMOVETO)];
+    for _ in 0..3 {
+        tokens.extend(vec![N(301), N(3), Esc(DIV), N(0), Op(RLINETO)])
+    }
+    tokens.extend(vec![N(40000), N(0), Op(RLINETO), N(-40000), N(0), Op(RLINETO), Op(ENDCHAR)]);
+    let private = private(&[triangle_subr()],
+                          &[(".notdef", notdef()), ("C", char_string(&tokens))]);
+    let font = Type1Font::new(&pfa(&cleartext("StandardEncoding"), &private)).unwrap();
+    assert_eq!(glyph_points(&font, "C"), Ok(vec![
+        point(0, 0, 0, PointKind::OnCurve),
+        point(100, 0, 1, PointKind::OnCurve),
+        point(201, 0, 2, PointKind::OnCurve),
+        point(301, 0, 3, PointKind::OnCurve),
+        point(32767, 0, 4, PointKind::OnCurve),
+        point(301, 0, 5, PointKind::OnCurve),
+        point(0, 0, 6, PointKind::OnCurve),
+    ]));
+}
+
+#[test]
+fn seac_combines_base_and_accent() {
+    let font = Type1Font::new(&pfa(&cleartext("StandardEncoding"), &test_private())).unwrap();
+    let mut expected = triangle_points();
+    expected.extend_from_slice(&[
+        point(200, 700, 0, PointKind::OnCurve),
+        point(300, 700, 1, PointKind::OnCurve),
+        point(200, 700, 2, PointKind::OnCurve),
+    ]);
+    assert_eq!(glyph_points(&font, "Aacute"), Ok(expected));
+    assert_eq!(font.advance_width(font.glyph_for_name("Aacute").unwrap()), Ok(600.0));
+}
+
+#[test]
+fn pfa_and_pfb_decode_identically() {
+    let (cleartext, private) = (cleartext("StandardEncoding"), test_private());
+    let pfa = Type1Font::new(&pfa(&cleartext, &private)).unwrap();
+    let pfb = Type1Font::new(&pfb(&cleartext, &private)).unwrap();
+    assert_eq!(pfb.num_glyphs(), pfa.num_glyphs());
+    for name in &[".notdef", "A", "B", "acute", "Aacute"] {
+        assert_eq!(glyph_points(&pfb, name), glyph_points(&pfa, name));
+    }
+    assert_eq!(glyph_points(&pfb, "A"), Ok(triangle_points()));
+}
+
+#[test]
+fn encodings() {
+    let standard = Type1Font::new(&pfa(&cleartext("StandardEncoding"), &test_private())).unwrap();
+    assert_eq!(standard.glyph_for_code(b'A'), standard.glyph_for_name("A"));
+    assert_eq!(standard.glyph_for_code(b'C'), None);
+
+    let custom = cleartext("256 array 0 1 255 {1 index exch /.notdef put} for \
+                            dup 97 /A put dup 98 /B put readonly");
+    let custom = Type1Font::new(&pfa(&custom, &test_private())).unwrap();
+    assert_eq!(custom.glyph_for_code(b'a'), custom.glyph_for_name("A"));
+    assert_eq!(custom.glyph_for_code(b'b'), custom.glyph_for_name("B"));
+    assert_eq!(custom.glyph_for_code(b'A'), None);
+}
+
+#[test]
+fn malformed_fonts_are_rejected() {
+    assert_eq!(Type1Font::new(b"%!PS-AdobeFont-1.0: Test\n").err(),
+               Some(FontError::Type1MalformedFont));
+
+    let pfb = pfb(&cleartext("StandardEncoding"), &test_private());
+    assert_eq!(Type1Font::new(&pfb[..(pfb.len() - 100)]).err(), Some(FontError::UnexpectedEof));
+
+    // Operator 0 doesn't exist, and subroutine 1 wasn't defined.
+    let private = private(&[triangle_subr()],
+                          &[(".notdef", char_string(&[N(0), N(500), Op(HSBW), Op(0)])),
+                            ("A", char_string(&[N(1), Op(CALLSUBR), Op(ENDCHAR)]))]);
+    let font = Type1Font::new(&pfa(&cleartext("StandardEncoding"), &private)).unwrap();
+    assert_eq!(glyph_points(&font, ".notdef"), Err(FontError::Type1MalformedCharString));
+    assert_eq!(glyph_points(&font, "A"), Err(FontError::Type1MalformedCharString));
+}
@@ -0,0 +1,80 @@
+/* Any copyright is dedicated to the Public Domain.
+ * http://creativecommons.org/publicdomain/zero/1.0/ */
+
+use error::FontError;
+use euclid::Point2D;
+use font::{Point, PointKind, SvgPathOptions};
+use util::{Reader, SvgPathWriter};
+
+#[test]
+fn reads_past_the_end_fail_without_advancing() {
+    let bytes = [0x12, 0x34, 0x56];
+    let mut reader = Reader::new(&bytes);
+    assert_eq!(reader.read_u16_be(), Ok(0x1234));
+    assert_eq!(reader.read_u16_be(), Err(FontError::UnexpectedEof));
+    assert_eq!(reader.read_u32_be(), Err(FontError::UnexpectedEof));
+    assert_eq!(reader.len(), 1);
+    assert_eq!(reader.read_u8(), Ok(0x56));
+    assert!(reader.is_empty());
+    assert_eq!(reader.read_u8(), Err(FontError::UnexpectedEof));
+}
+
+#[test]
+fn signed_reads() {
+    let bytes = [0xff, 0xfe, 0xff, 0xff, 0xff];
+    let mut reader = Reader::new(&bytes);
+    assert_eq!(reader.read_i16_be(), Ok(-2));
+    assert_eq!(reader.read_i32_be(), Err(FontError::UnexpectedEof));
+    assert_eq!(Reader::new(&bytes[1..]).read_i32_be(), Ok(-0x01000001));
+}
+
+#[test]
+fn subslices_and_jumps_are_bounds_checked() {
+    let bytes = [1, 2, 3, 4, 5];
+    let mut reader = Reader::new(&bytes);
+    assert_eq!(reader.subslice(2), Ok(&bytes[0..2]));
+    assert_eq!(reader.subslice(4), Err(FontError::UnexpectedEof));
+    assert_eq!(reader.jump(4), Err(FontError::UnexpectedEof));
+    assert_eq!(reader.jump(1), Ok(()));
+    assert_eq!(reader.remaining(), &bytes[3..]);
+
+    assert_eq!(Reader::at(&bytes, 5).map(|reader| reader.is_empty()), Ok(true));
+    assert!(Reader::at(&bytes, 6).is_err());
+}
+
+#[test]
+fn offsets_of_each_size() {
+    let bytes = [0x01, 0x02, 0x03, 0x04];
+    for &(size, offset) in &[(1, 0x01), (2, 0x0102), (3, 0x010203), (4, 0x01020304)] {
+        assert_eq!(Reader::new(&bytes).read_offset(size), Ok(offset));
+    }
+
+    assert_eq!(Reader::new(&bytes).read_offset(0), Err(FontError::Failed));
+    assert_eq!(Reader::new(&bytes).read_offset(5), Err(FontError::Failed));
+    assert_eq!(Reader::new(&bytes[0..2]).read_offset(3), Err(FontError::UnexpectedEof));
+}
+
+#[test]
+fn svg_paths_keep_lines_through_the_start_of_a_contour() {
+    fn svg_path(points: &[(i16, i16, PointKind)]) -> String {
+        let mut writer = SvgPathWriter::new(&SvgPathOptions::default());
+        for (index, &(x, y, kind)) in points.iter().enumerate() {
+            writer.push(&Point {
+                position: Point2D::new(x, y),
+                index_in_contour: index as u16,
+                kind: kind,
+            })
+        }
+        writer.finish()
+    }
+
+    // A bowtie that passes back through its start in the middle, then ends there.
+    let on = PointKind::OnCurve;
+    assert_eq!(svg_path(&[(0, 0, on), (10, 10, on), (0, 0, on), (10, -10, on), (0, 0, on)]),
+               "M0 0L10 10L0 0L10 -10Z");
+
+    // The same, left through a curve.
+    let control = PointKind::QuadControl;
+    assert_eq!(svg_path(&[(0, 0, on), (10, 10, on), (0, 0, on), (5, -5, control), (10, -10, on)]),
+               "M0 0L10 10L0 0Q5 -5 10 -10Z");
+}
@@ -0,0 +1,57 @@
+/* Any copyright is dedicated to the Public Domain.
+ * http://creativecommons.org/publicdomain/zero/1.0/ */
+
+use error::FontError;
+use font::{Font, FontTable};
+use memmap::{Mmap, Protection};
+use tables::vhea::VheaTable;
+use tables::vmtx::VmtxTable;
+
+static TEST_FONT_PATH: &'static str = "resources/tests/nimbus-sans/NimbusSanL-Regu.ttf";
+
+#[test]
+fn vhea_fields() {
+    let mut bytes = vec![0, 1, 0x10, 0, 0x01, 0xf4, 0xfe, 0x0c, 0, 0, 0x03, 0xe8];
+    bytes.extend_from_slice(&[0; 22]);
+    bytes.extend_from_slice(&[0, 2]);
+    let vhea = VheaTable::new(FontTable {
+        bytes: &bytes,
+    }).unwrap();
+    assert_eq!((vhea.vert_ascender, vhea.vert_descender, vhea.vert_line_gap), (500, -500, 0));
+    assert_eq!(vhea.advance_height_max, 1000);
+    assert_eq!(vhea.number_of_v_metrics, 2);
+
+    bytes[1] = 2;
+    assert!(VheaTable::new(FontTable {
+        bytes: &bytes,
+    }).is_err());
+}
+
+// Glyph 2 shares the advance height of glyph 1 but has its own top side bearing.
+#[test]
+fn trailing_glyphs_share_the_last_advance() {
+    let bytes = [0x03, 0xe8, 0, 80, 0x04, 0x00, 0, 120, 0xff, 0xf6];
+    let vmtx = VmtxTable::new(FontTable {
+        bytes: &bytes,
+    });
+
+    let metrics: Vec<_> = (0..3).map(|glyph_id| {
+        let metrics = vmtx.metrics(glyph_id, 2).unwrap();
+        (metrics.advance_height, metrics.tsb)
+    }).collect();
+    assert_eq!(metrics, vec![(1000, 80), (1024, 120), (1024, -10)]);
+    assert_eq!(vmtx.advance_height(2, 2), Ok(1024));
+    assert!(vmtx.metrics(3, 2).is_err());
+}
+
+#[test]
+fn font_advance_height() {
+    let file = Mmap::open_path(TEST_FONT_PATH, Protection::Read).expect("Couldn't open test font");
+    let mut buffer = vec![];
+    let font = Font::new(unsafe { file.as_slice() }, &mut buffer).unwrap();
+
+    assert_eq!(font.advance_height(0), Ok(Some(2458)));
+    let metrics = font.vertical_metrics_for_glyph(3).unwrap().unwrap();
+    assert_eq!((metrics.advance_height, metrics.tsb), (2458, 1794));
+    assert_eq!(font.advance_height(font.num_glyphs()), Err(FontError::NoSuchGlyph));
+}
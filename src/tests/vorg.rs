@@ -0,0 +1,44 @@
+/* Any copyright is dedicated to the Public Domain.
+ * http://creativecommons.org/publicdomain/zero/1.0/ */
+
+use error::{ErrorContext, FontError};
+use font::FontTable;
+use tables::vorg::{self, VorgTable};
+
+// Version 1.0 with a default origin of 880 and explicit origins for glyphs 3, 7, and 12.
+static VORG: [u8; 20] = [
+    0, 1, 0, 0, 0x03, 0x70, 0, 3,
+    0, 3, 0x03, 0x84,
+    0, 7, 0x03, 0x20,
+    0, 12, 0xff, 0xf6,
+];
+
+#[test]
+fn explicit_vertical_origins() {
+    let vorg = VorgTable::new(FontTable {
+        bytes: &VORG,
+    }).unwrap();
+    assert_eq!(vorg.vertical_origin_y(3), 900);
+    assert_eq!(vorg.vertical_origin_y(7), 800);
+    assert_eq!(vorg.vertical_origin_y(12), -10);
+}
+
+#[test]
+fn unlisted_glyphs_use_the_default_origin() {
+    let vorg = VorgTable::new(FontTable {
+        bytes: &VORG,
+    }).unwrap();
+    for &glyph_id in &[0, 4, 8, 13, 0xffff] {
+        assert_eq!(vorg.vertical_origin_y(glyph_id), 880)
+    }
+}
+
+#[test]
+fn truncated_metrics_are_rejected() {
+    assert_eq!(VorgTable::new(FontTable {
+        bytes: &VORG[0..18],
+    }).err(), Some(FontError::UnexpectedEofIn(ErrorContext {
+        table: vorg::TAG,
+        field: "vertical origins",
+    })));
+}
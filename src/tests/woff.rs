@@ -0,0 +1,103 @@
+/* Any copyright is dedicated to the Public Domain.
+ * http://creativecommons.org/publicdomain/zero/1.0/ */
+
+use byteorder::{BigEndian, ReadBytesExt};
+use error::FontError;
+use flate2::Compression;
+use flate2::write::ZlibEncoder;
+use font::{Font, Point};
+use memmap::{Mmap, Protection};
+use std::io::Write;
+use util::{push_u16, push_u32};
+
+static TEST_FONT_PATH: &'static str = "resources/tests/nimbus-sans/NimbusSanL-Regu.ttf";
+
+fn zlib(data: &[u8]) -> Vec<u8> {
+    let mut encoder = ZlibEncoder::new(vec![], Compression::Default);
+    encoder.write_all(data).unwrap();
+    encoder.finish().unwrap()
+}
+
+// Wraps an sfnt font in a WOFF container. Every other table is stored uncompressed.
+fn woff(sfnt: &[u8]) -> Vec<u8> {
+    let mut reader = sfnt;
+    let flavor = reader.read_u32::<BigEndian>().unwrap();
+    let num_tables = reader.read_u16::<BigEndian>().unwrap();
+    reader = &reader[6..];
+
+    let mut tables = vec![];
+    for index in 0..num_tables {
+        let tag = reader.read_u32::<BigEndian>().unwrap();
+        let checksum = reader.read_u32::<BigEndian>().unwrap();
+        let offset = reader.read_u32::<BigEndian>().unwrap() as usize;
+        let length = reader.read_u32::<BigEndian>().unwrap() as usize;
+        let table = &sfnt[offset..(offset + length)];
+        let stored = if index % 2 == 0 { zlib(table) } else { table.to_vec() };
+        tables.push((tag, checksum, length, stored))
+    }
+
+    let total_sfnt_size = tables.iter().fold(12 + 16 * tables.len(), |size, table| {
+        size + (table.2 + 3) / 4 * 4
+    });
+
+    let mut offset = 44 + 20 * tables.len();
+    let mut directory = vec![];
+    let mut data = vec![];
+    for &(tag, checksum, length, ref stored) in &tables {
+        push_u32(&mut directory, tag);
+        push_u32(&mut directory, offset as u32);
+        push_u32(&mut directory, stored.len() as u32);
+        push_u32(&mut directory, length as u32);
+        push_u32(&mut directory, checksum);
+        data.extend_from_slice(stored);
+        while data.len() % 4 != 0 {
+            data.push(0)
+        }
+        offset = 44 + 20 * tables.len() + data.len();
+    }
+
+    let mut bytes = b"wOFF".to_vec();
+    push_u32(&mut bytes, flavor);
+    push_u32(&mut bytes, offset as u32);
+    push_u16(&mut bytes, tables.len() as u16);
+    push_u16(&mut bytes, 0);
+    push_u32(&mut bytes, total_sfnt_size as u32);
+    bytes.extend_from_slice(&[0; 24]);
+    bytes.extend(directory);
+    bytes.extend(data);
+    bytes
+}
+
+fn glyph_points(font: &Font, glyph_id: u16) -> Vec<Point> {
+    let mut points = vec![];
+    font.for_each_point(glyph_id, |point| points.push(*point)).unwrap();
+    points
+}
+
+#[test]
+fn woff_glyphs_match_the_original_font() {
+    let file = Mmap::open_path(TEST_FONT_PATH, Protection::Read).expect("Couldn't open test font");
+    let mut buffer = vec![];
+    let ttf = Font::new(unsafe { file.as_slice() }, &mut buffer).unwrap();
+
+    let woff_bytes = woff(unsafe { file.as_slice() });
+    let mut woff_buffer = vec![];
+    let woff = Font::new(&woff_bytes, &mut woff_buffer).unwrap();
+
+    assert_eq!(woff.num_glyphs(), ttf.num_glyphs());
+    assert_eq!(woff.glyph_for_char('o'), ttf.glyph_for_char('o'));
+    for glyph_id in 0..ttf.num_glyphs() {
+        assert_eq!(glyph_points(&woff, glyph_id), glyph_points(&ttf, glyph_id));
+    }
+}
+
+#[test]
+fn truncated_woff_is_rejected() {
+    let file = Mmap::open_path(TEST_FONT_PATH, Protection::Read).expect("Couldn't open test font");
+    let mut woff_bytes = woff(unsafe { file.as_slice() });
+    let length = woff_bytes.len();
+    woff_bytes.truncate(length / 2);
+
+    let mut buffer = vec![];
+    assert_eq!(Font::new(&woff_bytes, &mut buffer).err(), Some(FontError::UnexpectedEof));
+}
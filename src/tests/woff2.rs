@@ -0,0 +1,393 @@
+/* Any copyright is dedicated to the Public Domain.
+ * http://creativecommons.org/publicdomain/zero/1.0/ */
+
+use byteorder::{BigEndian, ReadBytesExt};
+use error::FontError;
+use font::{Font, Point};
+use memmap::{Mmap, Protection};
+use util::{push_u16, push_u32};
+
+static TEST_FONT_PATH: &'static str = "resources/tests/nimbus-sans/NimbusSanL-Regu.ttf";
+
+// The first few tags that the table directory can refer to by index. Other tables are written with
+// explicit tags.
+static KNOWN_TAGS: [&'static [u8; 4]; 13] = [
+    b"cmap", b"head", b"hhea", b"hmtx", b"maxp", b"name", b"OS/2", b"post", b"cvt ", b"fpgm",
+    b"glyf", b"loca", b"prep",
+];
+
+fn push_uint_base_128(bytes: &mut Vec<u8>, mut value: u32) {
+    let mut groups = vec![(value & 0x7f) as u8];
+    value >>= 7;
+    while value != 0 {
+        groups.push((value & 0x7f) as u8 | 0x80);
+        value >>= 7;
+    }
+    groups.reverse();
+    bytes.extend(groups)
+}
+
+fn push_255_u16(bytes: &mut Vec<u8>, value: u16) {
+    match value {
+        0...252 => bytes.push(value as u8),
+        253...505 => bytes.extend_from_slice(&[255, (value - 253) as u8]),
+        506...761 => bytes.extend_from_slice(&[254, (value - 506) as u8]),
+        _ => {
+            bytes.push(253);
+            push_u16(bytes, value)
+        }
+    }
+}
+
+// Writes a stream of bits, least significant first.
+struct BitWriter {
+    bytes: Vec<u8>,
+    bit_count: usize,
+}
+
+impl BitWriter {
+    fn push(&mut self, value: u32, count: usize) {
+        for bit in 0..count {
+            if self.bit_count % 8 == 0 {
+                self.bytes.push(0)
+            }
+            if (value >> bit) & 1 != 0 {
+                *self.bytes.last_mut().unwrap() |= 1 << (self.bit_count % 8)
+            }
+            self.bit_count += 1
+        }
+    }
+
+    // Appends whole bytes, starting at the next byte boundary.
+    fn push_bytes(&mut self, bytes: &[u8]) {
+        self.bytes.extend_from_slice(bytes);
+        self.bit_count = self.bytes.len() * 8
+    }
+}
+
+// Wraps data in a Brotli stream made of uncompressed meta-blocks, which any decoder must accept.
+fn brotli_stored(data: &[u8]) -> Vec<u8> {
+    let mut writer = BitWriter {
+        bytes: vec![],
+        bit_count: 0,
+    };
+
+    // A 64 KB window, then for each chunk a meta-block header: not last, four length nibbles, the
+    // length minus one, and uncompressed.
+    writer.push(0, 1);
+    for chunk in data.chunks(0x10000) {
+        writer.push(0, 3);
+        writer.push(chunk.len() as u32 - 1, 16);
+        writer.push(1, 1);
+        writer.push_bytes(chunk);
+    }
+
+    // A last, empty meta-block.
+    writer.push(3, 2);
+    writer.bytes
+}
+
+// Decodes a simple glyph from a `glyf` table into its contour end points, its instructions, and
+// its points.
+fn simple_glyph(glyph: &[u8]) -> (Vec<u16>, &[u8], Vec<(i32, i32, bool)>) {
+    let mut reader = glyph;
+    let contour_count = reader.read_i16::<BigEndian>().unwrap();
+    reader = &reader[8..];
+
+    let end_points: Vec<u16> = (0..contour_count).map(|_| {
+        reader.read_u16::<BigEndian>().unwrap()
+    }).collect();
+    let instruction_length = reader.read_u16::<BigEndian>().unwrap() as usize;
+    let (instructions, rest) = reader.split_at(instruction_length);
+    reader = rest;
+
+    let point_count = *end_points.last().unwrap() as usize + 1;
+    let mut flags = vec![];
+    while flags.len() < point_count {
+        let flag = reader.read_u8().unwrap();
+        flags.push(flag);
+        if flag & 8 != 0 {
+            for _ in 0..reader.read_u8().unwrap() {
+                flags.push(flag)
+            }
+        }
+    }
+
+    let mut coordinates = vec![];
+    for &(short, same) in &[(2, 0x10), (4, 0x20)] {
+        let mut value = 0;
+        coordinates.push(flags.iter().map(|&flag| {
+            value += if flag & short != 0 {
+                let delta = reader.read_u8().unwrap() as i32;
+                if flag & same != 0 { delta } else { -delta }
+            } else if flag & same != 0 {
+                0
+            } else {
+                reader.read_i16::<BigEndian>().unwrap() as i32
+            };
+            value
+        }).collect::<Vec<_>>());
+    }
+
+    let points = flags.iter().enumerate().map(|(index, &flag)| {
+        (coordinates[0][index], coordinates[1][index], flag & 1 != 0)
+    }).collect();
+    (end_points, instructions, points)
+}
+
+// Appends the WOFF 2.0 triplet encoding of a point to the flag and glyph streams.
+fn push_triplet(flags: &mut Vec<u8>,
+                glyph_stream: &mut Vec<u8>,
+                dx: i32,
+                dy: i32,
+                on_curve: bool) {
+    let (abs_x, abs_y) = (dx.abs(), dy.abs());
+    let on_curve_bit = if on_curve { 0 } else { 0x80 };
+    let x_sign_bit = if dx < 0 { 0 } else { 1 };
+    let y_sign_bit = if dy < 0 { 0 } else { 1 };
+    let xy_sign_bits = x_sign_bit + 2 * y_sign_bit;
+
+    let (flag, bytes) = if dx == 0 && abs_y < 1280 {
+        (((abs_y & 0xf00) >> 7) + y_sign_bit, vec![abs_y as u8])
+    } else if dy == 0 && abs_x < 1280 {
+        (10 + ((abs_x & 0xf00) >> 7) + x_sign_bit, vec![abs_x as u8])
+    } else if abs_x < 65 && abs_y < 65 {
+        (20 + ((abs_x - 1) & 0x30) + (((abs_y - 1) & 0x30) >> 2) + xy_sign_bits,
+         vec![((((abs_x - 1) & 0xf) << 4) | ((abs_y - 1) & 0xf)) as u8])
+    } else if abs_x < 769 && abs_y < 769 {
+        (84 + 12 * (((abs_x - 1) & 0x300) >> 8) + (((abs_y - 1) & 0x300) >> 6) + xy_sign_bits,
+         vec![(abs_x - 1) as u8, (abs_y - 1) as u8])
+    } else if abs_x < 4096 && abs_y < 4096 {
+        (120 + xy_sign_bits,
+         vec![(abs_x >> 4) as u8, (((abs_x & 0xf) << 4) | (abs_y >> 8)) as u8, abs_y as u8])
+    } else {
+        (124 + xy_sign_bits,
+         vec![(abs_x >> 8) as u8, abs_x as u8, (abs_y >> 8) as u8, abs_y as u8])
+    };
+    flags.push((flag + on_curve_bit) as u8);
+    glyph_stream.extend(bytes)
+}
+
+// Applies the WOFF 2.0 glyph transform to a `glyf` table.
+fn transform_glyf(glyf: &[u8], loca: &[u8], index_format: u16) -> Vec<u8> {
+    let mut reader = loca;
+    let offsets: Vec<usize> = match index_format {
+        0 => (0..(loca.len() / 2)).map(|_| {
+            reader.read_u16::<BigEndian>().unwrap() as usize * 2
+        }).collect(),
+        _ => (0..(loca.len() / 4)).map(|_| {
+            reader.read_u32::<BigEndian>().unwrap() as usize
+        }).collect(),
+    };
+    let glyph_count = offsets.len() - 1;
+
+    let (mut contour_counts, mut point_counts, mut flags) = (vec![], vec![], vec![]);
+    let (mut glyph_stream, mut composites, mut instruction_stream) = (vec![], vec![], vec![]);
+    let (mut bbox_bitmap, mut bboxes) = (vec![0; (glyph_count + 31) / 32 * 4], vec![]);
+    for glyph_id in 0..glyph_count {
+        let glyph = &glyf[offsets[glyph_id]..offsets[glyph_id + 1]];
+        if glyph.is_empty() {
+            push_u16(&mut contour_counts, 0);
+            continue
+        }
+        contour_counts.extend_from_slice(&glyph[0..2]);
+        let bbox = &glyph[2..10];
+
+        let contour_count = (&glyph[0..2]).read_i16::<BigEndian>().unwrap();
+        if contour_count < 0 {
+            bbox_bitmap[glyph_id / 8] |= 0x80 >> (glyph_id % 8);
+            bboxes.extend_from_slice(bbox);
+
+            let mut reader = &glyph[10..];
+            let mut have_instructions = false;
+            loop {
+                let component_flags = reader.read_u16::<BigEndian>().unwrap();
+                let mut length = if component_flags & 1 != 0 { 6 } else { 4 };
+                if component_flags & (1 << 3) != 0 {
+                    length += 2
+                } else if component_flags & (1 << 6) != 0 {
+                    length += 4
+                } else if component_flags & (1 << 7) != 0 {
+                    length += 8
+                }
+                reader = &reader[length..];
+                have_instructions = have_instructions || component_flags & (1 << 8) != 0;
+                if component_flags & (1 << 5) == 0 {
+                    break
+                }
+            }
+            composites.extend_from_slice(&glyph[10..(glyph.len() - reader.len())]);
+
+            if have_instructions {
+                let instruction_length = reader.read_u16::<BigEndian>().unwrap();
+                push_255_u16(&mut glyph_stream, instruction_length);
+                instruction_stream.extend_from_slice(&reader[0..instruction_length as usize]);
+            }
+            continue
+        }
+
+        let (end_points, instructions, points) = simple_glyph(glyph);
+        let mut last_end_point = -1;
+        for &end_point in &end_points {
+            push_255_u16(&mut point_counts, (end_point as i32 - last_end_point) as u16);
+            last_end_point = end_point as i32;
+        }
+        let (mut last_x, mut last_y) = (0, 0);
+        for &(x, y, on_curve) in &points {
+            push_triplet(&mut flags, &mut glyph_stream, x - last_x, y - last_y, on_curve);
+            last_x = x;
+            last_y = y;
+        }
+        push_255_u16(&mut glyph_stream, instructions.len() as u16);
+        instruction_stream.extend_from_slice(instructions);
+
+        // Bounding boxes are only stored if they differ from the one the points imply.
+        let mut computed_bbox = vec![];
+        for &value in &[
+            points.iter().map(|point| point.0).min().unwrap(),
+            points.iter().map(|point| point.1).min().unwrap(),
+            points.iter().map(|point| point.0).max().unwrap(),
+            points.iter().map(|point| point.1).max().unwrap(),
+        ] {
+            push_u16(&mut computed_bbox, value as u16)
+        }
+        if computed_bbox != bbox {
+            bbox_bitmap[glyph_id / 8] |= 0x80 >> (glyph_id % 8);
+            bboxes.extend_from_slice(bbox);
+        }
+    }
+    bbox_bitmap.extend(bboxes);
+
+    let mut bytes = vec![];
+    for &value in &[0, 0, glyph_count as u16, index_format] {
+        push_u16(&mut bytes, value)
+    }
+    let streams = [contour_counts, point_counts, flags, glyph_stream, composites, bbox_bitmap,
+                   instruction_stream];
+    for stream in &streams {
+        push_u32(&mut bytes, stream.len() as u32)
+    }
+    for stream in &streams {
+        bytes.extend_from_slice(stream)
+    }
+    bytes
+}
+
+// Wraps an sfnt font in a WOFF 2.0 container, applying the glyph transform if requested.
+fn woff2(sfnt: &[u8], transform: bool) -> Vec<u8> {
+    let mut reader = sfnt;
+    let flavor = reader.read_u32::<BigEndian>().unwrap();
+    let num_tables = reader.read_u16::<BigEndian>().unwrap();
+    reader = &reader[6..];
+
+    let mut tables = vec![];
+    for _ in 0..num_tables {
+        let mut tag = [0; 4];
+        tag.copy_from_slice(&reader[0..4]);
+        reader = &reader[8..];
+        let offset = reader.read_u32::<BigEndian>().unwrap() as usize;
+        let length = reader.read_u32::<BigEndian>().unwrap() as usize;
+        tables.push((tag, &sfnt[offset..(offset + length)]))
+    }
+
+    // The `loca` table must follow the `glyf` table.
+    let loca_index = tables.iter().position(|&(tag, _)| &tag == b"loca").unwrap();
+    let loca = tables.remove(loca_index);
+    let glyf_index = tables.iter().position(|&(tag, _)| &tag == b"glyf").unwrap();
+    tables.insert(glyf_index + 1, loca);
+    let head = tables.iter().find(|&&(tag, _)| &tag == b"head").unwrap().1;
+    let index_format = (&head[50..52]).read_u16::<BigEndian>().unwrap();
+
+    let (mut directory, mut data) = (vec![], vec![]);
+    for &(tag, table) in &tables {
+        let transformed = match &tag {
+            b"glyf" if transform => Some(transform_glyf(table, loca.1, index_format)),
+            b"loca" if transform => Some(vec![]),
+            _ => None,
+        };
+
+        let mut flags = match KNOWN_TAGS.iter().position(|known_tag| **known_tag == tag) {
+            Some(tag_index) => tag_index as u8,
+            None => 63,
+        };
+        if (&tag == b"glyf" || &tag == b"loca") && !transform {
+            flags |= 3 << 6
+        }
+        directory.push(flags);
+        if flags & 0x3f == 63 {
+            directory.extend_from_slice(&tag)
+        }
+        push_uint_base_128(&mut directory, table.len() as u32);
+
+        match transformed {
+            Some(transformed) => {
+                push_uint_base_128(&mut directory, transformed.len() as u32);
+                data.extend(transformed)
+            }
+            None => data.extend_from_slice(table),
+        }
+    }
+    let compressed = brotli_stored(&data);
+
+    let mut bytes = b"wOF2".to_vec();
+    push_u32(&mut bytes, flavor);
+    push_u32(&mut bytes, (48 + directory.len() + compressed.len()) as u32);
+    push_u16(&mut bytes, tables.len() as u16);
+    push_u16(&mut bytes, 0);
+    push_u32(&mut bytes, sfnt.len() as u32);
+    push_u32(&mut bytes, compressed.len() as u32);
+    push_u16(&mut bytes, 1);
+    push_u16(&mut bytes, 0);
+    bytes.extend_from_slice(&[0; 20]);
+    bytes.extend(directory);
+    bytes.extend(compressed);
+    bytes
+}
+
+fn glyph_points(font: &Font, glyph_id: u16) -> Vec<Point> {
+    let mut points = vec![];
+    font.for_each_point(glyph_id, |point| points.push(*point)).unwrap();
+    points
+}
+
+fn assert_glyphs_match_the_original_font(transform: bool) {
+    let file = Mmap::open_path(TEST_FONT_PATH, Protection::Read).expect("Couldn't open test font");
+    let mut buffer = vec![];
+    let ttf = Font::new(unsafe { file.as_slice() }, &mut buffer).unwrap();
+
+    let woff2_bytes = woff2(unsafe { file.as_slice() }, transform);
+    let mut woff2_buffer = vec![];
+    let woff2 = Font::new(&woff2_bytes, &mut woff2_buffer).unwrap();
+
+    assert_eq!(woff2.num_glyphs(), ttf.num_glyphs());
+    assert_eq!(woff2.glyph_for_char('o'), ttf.glyph_for_char('o'));
+    for glyph_id in 0..ttf.num_glyphs() {
+        assert_eq!(glyph_points(&woff2, glyph_id), glyph_points(&ttf, glyph_id));
+
+        let (bounds, ttf_bounds) = (woff2.glyph_bounds(glyph_id).unwrap(),
+                                    ttf.glyph_bounds(glyph_id).unwrap());
+        assert_eq!((bounds.left, bounds.bottom, bounds.right, bounds.top),
+                   (ttf_bounds.left, ttf_bounds.bottom, ttf_bounds.right, ttf_bounds.top));
+    }
+}
+
+#[test]
+fn transformed_glyf_matches_the_original_font() {
+    assert_glyphs_match_the_original_font(true)
+}
+
+#[test]
+fn null_transformed_glyf_matches_the_original_font() {
+    assert_glyphs_match_the_original_font(false)
+}
+
+#[test]
+fn truncated_compressed_data_is_rejected() {
+    let file = Mmap::open_path(TEST_FONT_PATH, Protection::Read).expect("Couldn't open test font");
+    let mut woff2_bytes = woff2(unsafe { file.as_slice() }, true);
+    let length = woff2_bytes.len();
+    woff2_bytes.truncate(length - 1);
+
+    let mut buffer = vec![];
+    assert_eq!(Font::new(&woff2_bytes, &mut buffer).err(), Some(FontError::UnexpectedEof));
+}
@@ -0,0 +1,861 @@
+// Copyright 2017 The Servo Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Type 1 (PostScript) fonts, as binary `.pfb` or ASCII `.pfa` files.
+//!
+//! These predate OpenType, but legacy PDFs and older systems still carry them. Their CharStrings
+//! are the ancestors of the Type 2 CharStrings in CFF fonts, and produce the same kinds of points.
+//!
+//! See Adobe's spec: https://partners.adobe.com/public/developer/en/font/T1_SPEC.PDF
+
+use error::FontError;
+use euclid::{Matrix2D, Point2D};
+use font::{Point, PointKind};
+use std::i16;
+use std::str;
+use tables::cff::close_path_if_necessary;
+use tables::cff_encoding;
+use tables::cff_strings::STANDARD_STRINGS;
+
+// The first byte of each segment of a `.pfb` file.
+const PFB_SEGMENT_MARKER: u8 = 0x80;
+const PFB_SEGMENT_ASCII: u8 = 1;
+const PFB_SEGMENT_BINARY: u8 = 2;
+const PFB_SEGMENT_EOF: u8 = 3;
+
+// The initial keys for decrypting the private part of the font and its CharStrings.
+const EEXEC_KEY: u16 = 55665;
+const CHARSTRING_KEY: u16 = 4330;
+
+// The number of random bytes at the start of encrypted CharStrings, unless the font says
+// otherwise with `lenIV`.
+const DEFAULT_LEN_IV: i32 = 4;
+
+// Limits taken from the spec.
+const MAX_SUBR_NESTING: u8 = 10;
+const MAX_STACK_SIZE: usize = 24;
+
+/// A Type 1 font.
+///
+/// The font's CharStrings are decrypted when it's loaded, so this owns its data rather than
+/// borrowing the file.
+#[derive(Clone, Debug)]
+pub struct Type1Font {
+    font_matrix: Matrix2D<f32>,
+    // The glyph name for each character code, if the font has its own encoding.
+    encoding: Option<Vec<Option<String>>>,
+    // Glyph names in glyph ID order, with `.notdef` first.
+    glyph_names: Vec<String>,
+    char_strings: Vec<Vec<u8>>,
+    subrs: Vec<Vec<u8>>,
+}
+
+impl Type1Font {
+    /// Parses a Type 1 font from the contents of a `.pfb` or `.pfa` file.
+    pub fn new(bytes: &[u8]) -> Result<Type1Font, FontError> {
+        let (cleartext, encrypted) = if bytes.first() == Some(&PFB_SEGMENT_MARKER) {
+            try!(read_pfb_segments(bytes))
+        } else {
+            try!(split_pfa(bytes))
+        };
+        let private = decrypt(&encrypted, EEXEC_KEY, 4);
+
+        let (font_matrix, encoding) = try!(parse_cleartext(&cleartext));
+        let private = try!(parse_private(&private));
+
+        let len_iv = private.len_iv;
+        let decrypt_char_string = |char_string: &Vec<u8>| {
+            if len_iv < 0 {
+                char_string.to_vec()
+            } else {
+                decrypt(char_string, CHARSTRING_KEY, len_iv as usize)
+            }
+        };
+
+        // Glyph 0 is always `.notdef`, as in OpenType fonts.
+        let mut glyphs = private.char_strings;
+        if let Some(index) = glyphs.iter().position(|&(ref name, _)| name == ".notdef") {
+            let notdef = glyphs.remove(index);
+            glyphs.insert(0, notdef)
+        }
+        if glyphs.len() > u16::max_value() as usize {
+            return Err(FontError::Type1MalformedFont)
+        }
+
+        Ok(Type1Font {
+            font_matrix: font_matrix,
+            encoding: encoding,
+            glyph_names: glyphs.iter().map(|&(ref name, _)| name.clone()).collect(),
+            char_strings: glyphs.iter().map(|&(_, ref data)| decrypt_char_string(data)).collect(),
+            subrs: private.subrs.iter().map(&decrypt_char_string).collect(),
+        })
+    }
+
+    /// Returns the number of glyphs in the font.
+    #[inline]
+    pub fn num_glyphs(&self) -> u16 {
+        self.glyph_names.len() as u16
+    }
+
+    /// Returns the `FontMatrix`, which maps CharString units to ems. This is almost always a
+    /// scale of 1/1000.
+    #[inline]
+    pub fn font_matrix(&self) -> Matrix2D<f32> {
+        self.font_matrix
+    }
+
+    /// Returns the name of the given glyph, such as `Aacute`.
+    #[inline]
+    pub fn glyph_name(&self, glyph_id: u16) -> Option<&str> {
+        self.glyph_names.get(glyph_id as usize).map(|name| &**name)
+    }
+
+    /// Returns the glyph with the given name.
+    pub fn glyph_for_name(&self, name: &str) -> Option<u16> {
+        self.glyph_names.iter().position(|glyph_name| glyph_name == name).map(|id| id as u16)
+    }
+
+    /// Returns the glyph for the given character code in the font's encoding, which is Adobe's
+    /// Standard Encoding unless the font defines its own.
+    pub fn glyph_for_code(&self, code: u8) -> Option<u16> {
+        match self.encoding {
+            Some(ref encoding) => {
+                encoding[code as usize].as_ref().and_then(|name| self.glyph_for_name(name))
+            }
+            None => self.glyph_for_standard_code(code),
+        }
+    }
+
+    /// Calls the given callback for each point in the supplied glyph's contour, like
+    /// `Font::for_each_point()`.
+    pub fn for_each_point<F>(&self, glyph_id: u16, mut callback: F) -> Result<(), FontError>
+                             where F: FnMut(&Point) {
+        let mut state = EvaluationState::new(Point2D::zero());
+        try!(self.evaluate_glyph(glyph_id, &mut state, &mut callback));
+        close_path_if_necessary(&state.start, state.index_in_contour, &mut callback);
+        Ok(())
+    }
+
+    /// Returns the advance width of the given glyph, as set by its `hsbw` or `sbw` operator, in
+    /// CharString units.
+    pub fn advance_width(&self, glyph_id: u16) -> Result<f32, FontError> {
+        let mut state = EvaluationState::new(Point2D::zero());
+        try!(self.evaluate_glyph(glyph_id, &mut state, &mut |_: &Point| {}));
+        Ok(state.advance_width)
+    }
+
+    fn glyph_for_standard_code(&self, code: u8) -> Option<u16> {
+        cff_encoding::standard_encoding_sid(code).and_then(|sid| {
+            self.glyph_for_name(STANDARD_STRINGS[sid as usize])
+        })
+    }
+
+    fn evaluate_glyph<F>(&self, glyph_id: u16, state: &mut EvaluationState, callback: &mut F)
+                         -> Result<(), FontError> where F: FnMut(&Point) {
+        let char_string = match self.char_strings.get(glyph_id as usize) {
+            Some(char_string) => char_string,
+            None => return Err(FontError::NoSuchGlyph),
+        };
+        try!(self.evaluate_char_string(char_string, state, 0, callback));
+        Ok(())
+    }
+
+    // Evaluates a CharString or a subroutine. Returns true if `endchar` or `seac` was reached, in
+    // which case evaluation of the glyph is finished.
+    fn evaluate_char_string<F>(&self,
+                               char_string: &[u8],
+                               state: &mut EvaluationState,
+                               depth: u8,
+                               callback: &mut F)
+                               -> Result<bool, FontError> where F: FnMut(&Point) {
+        let mut bytes = char_string.iter().cloned();
+        while let Some(b0) = bytes.next() {
+            // Type 1 has no 16-bit operands, and the 32-bit one is an integer, not fixed-point.
+            match b0 {
+                32...246 => try!(state.push(b0 as f32 - 139.0)),
+                247...250 => {
+                    let b1 = try!(bytes.next().ok_or(FontError::UnexpectedEof));
+                    try!(state.push(((b0 as i32 - 247) * 256 + b1 as i32 + 108) as f32))
+                }
+                251...254 => {
+                    let b1 = try!(bytes.next().ok_or(FontError::UnexpectedEof));
+                    try!(state.push((-(b0 as i32 - 251) * 256 - b1 as i32 - 108) as f32))
+                }
+                255 => {
+                    let mut number = 0;
+                    for _ in 0..4 {
+                        let byte = try!(bytes.next().ok_or(FontError::UnexpectedEof));
+                        number = (number << 8) | byte as u32
+                    }
+                    try!(state.push(number as i32 as f32))
+                }
+
+                13 => {
+                    // sbx wx hsbw
+                    let args = try!(state.args(2));
+                    state.pos = state.origin + Point2D::new(args[0], 0.0);
+                    state.advance_width = args[1]
+                }
+                21 => {
+                    // dx dy rmoveto
+                    let args = try!(state.args(2));
+                    state.move_to(args[0], args[1], &mut *callback)
+                }
+                22 => {
+                    // dx hmoveto
+                    let args = try!(state.args(1));
+                    state.move_to(args[0], 0.0, &mut *callback)
+                }
+                4 => {
+                    // dy vmoveto
+                    let args = try!(state.args(1));
+                    state.move_to(0.0, args[0], &mut *callback)
+                }
+                5 => {
+                    // dx dy rlineto
+                    let args = try!(state.args(2));
+                    state.line_to(args[0], args[1], &mut *callback)
+                }
+                6 => {
+                    // dx hlineto
+                    let args = try!(state.args(1));
+                    state.line_to(args[0], 0.0, &mut *callback)
+                }
+                7 => {
+                    // dy vlineto
+                    let args = try!(state.args(1));
+                    state.line_to(0.0, args[0], &mut *callback)
+                }
+                8 => {
+                    // dx1 dy1 dx2 dy2 dx3 dy3 rrcurveto
+                    let args = try!(state.args(6));
+                    state.curve_to(args[0], args[1], args[2], args[3], args[4], args[5], callback)
+                }
+                30 => {
+                    // dy1 dx2 dy2 dx3 vhcurveto
+                    let args = try!(state.args(4));
+                    state.curve_to(0.0, args[0], args[1], args[2], args[3], 0.0, callback)
+                }
+                31 => {
+                    // dx1 dx2 dy2 dy3 hvcurveto
+                    let args = try!(state.args(4));
+                    state.curve_to(args[0], 0.0, args[1], args[2], 0.0, args[3], callback)
+                }
+                9 => {
+                    // closepath
+                    //
+                    // Unlike in PostScript, this leaves the current point where it is.
+                    state.stack.clear();
+                    close_path_if_necessary(&state.start, state.index_in_contour, &mut *callback);
+                    state.index_in_contour = 0
+                }
+                10 => {
+                    // subr# callsubr
+                    if depth >= MAX_SUBR_NESTING {
+                        return Err(FontError::Type1MalformedCharString)
+                    }
+                    let subr = match state.stack.pop() {
+                        Some(subr) if subr >= 0.0 => self.subrs.get(subr as usize),
+                        _ => None,
+                    };
+                    let subr = try!(subr.ok_or(FontError::Type1MalformedCharString));
+                    if try!(self.evaluate_char_string(subr, state, depth + 1, callback)) {
+                        return Ok(true)
+                    }
+                }
+                11 => {
+                    // return
+                    return Ok(false)
+                }
+                14 => {
+                    // endchar
+                    state.stack.clear();
+                    return Ok(true)
+                }
+                1 | 3 => {
+                    // y dy hstem, x dx vstem (ignored)
+                    state.stack.clear()
+                }
+
+                12 => {
+                    let b1 = try!(bytes.next().ok_or(FontError::UnexpectedEof));
+                    match b1 {
+                        0 | 1 | 2 => {
+                            // dotsection, vstem3, hstem3 (ignored)
+                            state.stack.clear()
+                        }
+                        6 => {
+                            // asb adx ady bchar achar seac
+                            let args = try!(state.args(5));
+                            try!(self.evaluate_seac(&args, state, depth, callback));
+                            return Ok(true)
+                        }
+                        7 => {
+                            // sbx sby wx wy sbw
+                            let args = try!(state.args(4));
+                            state.pos = state.origin + Point2D::new(args[0], args[1]);
+                            state.advance_width = args[2]
+                        }
+                        12 => {
+                            // num1 num2 div
+                            //
+                            // Unlike the other operators, this leaves the rest of the stack alone.
+                            let (num2, num1) = match (state.stack.pop(), state.stack.pop()) {
+                                (Some(num2), Some(num1)) if num2 != 0.0 => (num2, num1),
+                                _ => return Err(FontError::Type1MalformedCharString),
+                            };
+                            try!(state.push(num1 / num2))
+                        }
+                        16 => {
+                            // arg1 … argn n othersubr# callothersubr
+                            try!(state.call_other_subr(&mut *callback))
+                        }
+                        17 => {
+                            // pop
+                            let value = state.other_subr_results.pop().unwrap_or(0.0);
+                            try!(state.push(value))
+                        }
+                        33 => {
+                            // x y setcurrentpoint
+                            let args = try!(state.args(2));
+                            state.pos = state.origin + Point2D::new(args[0], args[1])
+                        }
+                        _ => return Err(FontError::Type1MalformedCharString),
+                    }
+                }
+
+                _ => return Err(FontError::Type1MalformedCharString),
+            }
+        }
+
+        Ok(false)
+    }
+
+    // Draws an accented character made of two Standard Encoding glyphs. The accent is offset from
+    // the base by `adx - asb` horizontally and `ady` vertically.
+    fn evaluate_seac<F>(&self,
+                        args: &[f32],
+                        state: &mut EvaluationState,
+                        depth: u8,
+                        callback: &mut F)
+                        -> Result<(), FontError> where F: FnMut(&Point) {
+        if depth >= MAX_SUBR_NESTING {
+            return Err(FontError::Type1MalformedCharString)
+        }
+
+        let (asb, adx, ady) = (args[0], args[1], args[2]);
+        let component = |code: f32| {
+            if code < 0.0 || code > 255.0 {
+                return Err(FontError::Type1MalformedCharString)
+            }
+            self.glyph_for_standard_code(code as u8).ok_or(FontError::Type1MalformedCharString)
+        };
+        let (base, accent) = (try!(component(args[3])), try!(component(args[4])));
+
+        // The base glyph's width is the width of the whole character.
+        close_path_if_necessary(&state.start, state.index_in_contour, &mut *callback);
+        let mut base_state = EvaluationState::new(state.origin);
+        try!(self.evaluate_glyph(base, &mut base_state, callback));
+        close_path_if_necessary(&base_state.start, base_state.index_in_contour, &mut *callback);
+        state.advance_width = base_state.advance_width;
+
+        let offset = Point2D::new(adx - asb, ady);
+        let mut accent_state = EvaluationState::new(state.origin + offset);
+        try!(self.evaluate_glyph(accent, &mut accent_state, callback));
+        state.start = accent_state.start;
+        state.index_in_contour = accent_state.index_in_contour;
+        Ok(())
+    }
+}
+
+// The state of CharString evaluation, which persists across subroutine calls.
+struct EvaluationState {
+    stack: Vec<f32>,
+    // Where this glyph's origin is, which is only nonzero for the accent of a `seac` character.
+    //
+    // The origin and current point are kept unrounded, so that fractional `div` results don't
+    // accumulate error over a contour. They're rounded only when a point is emitted.
+    origin: Point2D<f32>,
+    start: Point2D<i16>,
+    pos: Point2D<f32>,
+    index_in_contour: u16,
+    advance_width: f32,
+    // The values that `pop` returns after `callothersubr`.
+    other_subr_results: Vec<f32>,
+    // While drawing a flex, the points that `rmoveto` visits, which become two curves.
+    flex_points: Option<Vec<Point2D<f32>>>,
+}
+
+impl EvaluationState {
+    fn new(origin: Point2D<f32>) -> EvaluationState {
+        EvaluationState {
+            stack: vec![],
+            origin: origin,
+            start: round_point(origin),
+            pos: origin,
+            index_in_contour: 0,
+            advance_width: 0.0,
+            other_subr_results: vec![],
+            flex_points: None,
+        }
+    }
+
+    fn push(&mut self, value: f32) -> Result<(), FontError> {
+        if self.stack.len() >= MAX_STACK_SIZE {
+            return Err(FontError::Type1MalformedCharString)
+        }
+        self.stack.push(value);
+        Ok(())
+    }
+
+    // Returns the top `count` values on the stack, bottom first, and clears it.
+    fn args(&mut self, count: usize) -> Result<Vec<f32>, FontError> {
+        if self.stack.len() < count {
+            return Err(FontError::Type1MalformedCharString)
+        }
+        let args = self.stack[(self.stack.len() - count)..].to_vec();
+        self.stack.clear();
+        Ok(args)
+    }
+
+    fn move_to<F>(&mut self, dx: f32, dy: f32, mut callback: F) where F: FnMut(&Point) {
+        self.pos = self.pos + Point2D::new(dx, dy);
+        if let Some(ref mut flex_points) = self.flex_points {
+            flex_points.push(self.pos);
+            return
+        }
+
+        close_path_if_necessary(&self.start, self.index_in_contour, &mut callback);
+        self.start = round_point(self.pos);
+        callback(&Point {
+            position: self.start,
+            index_in_contour: 0,
+            kind: PointKind::OnCurve,
+        });
+        self.index_in_contour = 1
+    }
+
+    fn line_to<F>(&mut self, dx: f32, dy: f32, mut callback: F) where F: FnMut(&Point) {
+        self.pos = self.pos + Point2D::new(dx, dy);
+        callback(&Point {
+            position: round_point(self.pos),
+            index_in_contour: self.index_in_contour,
+            kind: PointKind::OnCurve,
+        });
+        self.index_in_contour += 1
+    }
+
+    fn curve_to<F>(&mut self, dx0: f32, dy0: f32, dx1: f32, dy1: f32, dx2: f32, dy2: f32,
+                   callback: &mut F)
+                   where F: FnMut(&Point) {
+        let deltas = [Point2D::new(dx0, dy0), Point2D::new(dx1, dy1), Point2D::new(dx2, dy2)];
+        let kinds = [PointKind::FirstCubicControl,
+                     PointKind::SecondCubicControl,
+                     PointKind::OnCurve];
+        for (&delta, &kind) in deltas.iter().zip(kinds.iter()) {
+            self.pos = self.pos + delta;
+            callback(&Point {
+                position: round_point(self.pos),
+                index_in_contour: self.index_in_contour,
+                kind: kind,
+            });
+            self.index_in_contour = self.index_in_contour.wrapping_add(1)
+        }
+    }
+
+    // Runs one of the standard OtherSubrs, which implement flex and hint replacement. Unknown
+    // OtherSubrs return their arguments.
+    fn call_other_subr<F>(&mut self, callback: &mut F) -> Result<(), FontError>
+                          where F: FnMut(&Point) {
+        let (other_subr, count) = match (self.stack.pop(), self.stack.pop()) {
+            (Some(other_subr), Some(count)) if count >= 0.0 => (other_subr, count as usize),
+            _ => return Err(FontError::Type1MalformedCharString),
+        };
+        if self.stack.len() < count {
+            return Err(FontError::Type1MalformedCharString)
+        }
+        let start = self.stack.len() - count;
+        let args = self.stack.split_off(start);
+
+        match other_subr as i32 {
+            0 => {
+                // Ends a flex. The points are the reference point, which isn't drawn, and then the
+                // control points and end points of two curves.
+                let points = match self.flex_points.take() {
+                    Some(ref points) if points.len() == 7 => points.clone(),
+                    _ => return Err(FontError::Type1MalformedCharString),
+                };
+                for curve in points[1..].chunks(3) {
+                    let kinds = [PointKind::FirstCubicControl,
+                                 PointKind::SecondCubicControl,
+                                 PointKind::OnCurve];
+                    for (point, &kind) in curve.iter().zip(kinds.iter()) {
+                        callback(&Point {
+                            position: round_point(*point),
+                            index_in_contour: self.index_in_contour,
+                            kind: kind,
+                        });
+                        self.index_in_contour += 1
+                    }
+                }
+                self.pos = points[6];
+
+                // `setcurrentpoint` follows with the end point, which `pop` returns from here.
+                if args.len() != 3 {
+                    return Err(FontError::Type1MalformedCharString)
+                }
+                self.other_subr_results = vec![args[2], args[1]]
+            }
+            1 => {
+                // Starts a flex.
+                self.flex_points = Some(vec![]);
+                self.other_subr_results.clear()
+            }
+            _ => {
+                // Othersubr 2 adds a flex point, which `rmoveto` has already done, and othersubr 3
+                // replaces hints, which we ignore. Both just hand back their arguments.
+                self.other_subr_results = args.into_iter().rev().collect()
+            }
+        }
+        Ok(())
+    }
+}
+
+// The parts of the private dictionary that we use.
+struct PrivateDict {
+    len_iv: i32,
+    subrs: Vec<Vec<u8>>,
+    // Glyph names and their encrypted CharStrings, in the order that the font lists them.
+    char_strings: Vec<(String, Vec<u8>)>,
+}
+
+// Rounds an unrounded current point to the nearest glyph coordinate. Malformed CharStrings can
+// move the point out of range, so coordinates are clamped rather than cast out of range.
+fn round_point(position: Point2D<f32>) -> Point2D<i16> {
+    let round = |value: f32| value.round().max(i16::MIN as f32).min(i16::MAX as f32) as i16;
+    Point2D::new(round(position.x), round(position.y))
+}
+
+// Splits a `.pfb` file into its cleartext part and its encrypted part.
+
+fn read_pfb_segments(bytes: &[u8]) -> Result<(Vec<u8>, Vec<u8>), FontError> {
+    let (mut cleartext, mut encrypted) = (vec![], vec![]);
+    let mut rest = bytes;
+    while rest.len() >= 2 {
+        if rest[0] != PFB_SEGMENT_MARKER {
+            return Err(FontError::Type1MalformedFont)
+        }
+        let segment_type = rest[1];
+        if segment_type == PFB_SEGMENT_EOF {
+            break
+        }
+        if rest.len() < 6 {
+            return Err(FontError::UnexpectedEof)
+        }
+
+        // Segment lengths are little-endian, unlike everything in OpenType.
+        let length = rest[2..6].iter().rev().fold(0, |length, &byte| (length << 8) | byte as usize);
+        if rest.len() < 6 + length {
+            return Err(FontError::UnexpectedEof)
+        }
+        let segment = &rest[6..(6 + length)];
+        match segment_type {
+            // The trailer after the encrypted part is also ASCII, and is of no use to us.
+            PFB_SEGMENT_ASCII if encrypted.is_empty() => cleartext.extend_from_slice(segment),
+            PFB_SEGMENT_ASCII => {}
+            PFB_SEGMENT_BINARY => encrypted.extend_from_slice(segment),
+            _ => return Err(FontError::Type1MalformedFont),
+        }
+        rest = &rest[(6 + length)..]
+    }
+
+    // Some `.pfb` files store the encrypted part in hexadecimal even so.
+    if is_hex(&encrypted) {
+        encrypted = decode_hex(&encrypted)
+    }
+    Ok((cleartext, encrypted))
+}
+
+// Splits a `.pfa` file into its cleartext part and its encrypted part, which follows `eexec` in
+// hexadecimal.
+fn split_pfa(bytes: &[u8]) -> Result<(Vec<u8>, Vec<u8>), FontError> {
+    let eexec = b"eexec";
+    let end = match bytes.windows(eexec.len()).position(|window| window == eexec) {
+        Some(position) => position + eexec.len(),
+        None => return Err(FontError::Type1MalformedFont),
+    };
+    let mut encrypted = &bytes[end..];
+    while encrypted.first().map_or(false, |&byte| is_whitespace(byte)) {
+        encrypted = &encrypted[1..]
+    }
+
+    if is_hex(encrypted) {
+        Ok((bytes[..end].to_vec(), decode_hex(encrypted)))
+    } else {
+        Ok((bytes[..end].to_vec(), encrypted.to_vec()))
+    }
+}
+
+// Whether the encrypted data is in hexadecimal, which the spec says to decide from its first four
+// bytes.
+fn is_hex(bytes: &[u8]) -> bool {
+    bytes.len() >= 4 && bytes[0..4].iter().all(|&byte| hex_digit(byte).is_some())
+}
+
+fn hex_digit(byte: u8) -> Option<u8> {
+    match byte {
+        b'0'...b'9' => Some(byte - b'0'),
+        b'a'...b'f' => Some(byte - b'a' + 10),
+        b'A'...b'F' => Some(byte - b'A' + 10),
+        _ => None,
+    }
+}
+
+// Decodes hexadecimal data, skipping whitespace and stopping at anything else.
+fn decode_hex(bytes: &[u8]) -> Vec<u8> {
+    let mut result = vec![];
+    let mut high_nibble = None;
+    for &byte in bytes {
+        let digit = match hex_digit(byte) {
+            Some(digit) => digit,
+            None if is_whitespace(byte) => continue,
+            None => break,
+        };
+        match high_nibble.take() {
+            None => high_nibble = Some(digit),
+            Some(high_nibble) => result.push((high_nibble << 4) | digit),
+        }
+    }
+    result
+}
+
+// Decrypts eexec-encrypted data or a CharString with the given key, dropping the given number of
+// random bytes from the start.
+fn decrypt(bytes: &[u8], key: u16, skip: usize) -> Vec<u8> {
+    let mut r = key;
+    let decrypted = bytes.iter().map(|&cipher| {
+        let plain = cipher ^ (r >> 8) as u8;
+        r = (cipher as u16).wrapping_add(r).wrapping_mul(52845).wrapping_add(22719);
+        plain
+    });
+    decrypted.skip(skip).collect()
+}
+
+// Reads the `FontMatrix` and `Encoding` from the cleartext part of the font. An encoding of
+// `None` is Standard Encoding.
+fn parse_cleartext(bytes: &[u8])
+                   -> Result<(Matrix2D<f32>, Option<Vec<Option<String>>>), FontError> {
+    let mut font_matrix = Matrix2D::create_scale(0.001, 0.001);
+    let mut encoding = None;
+
+    let mut lexer = Lexer::new(bytes);
+    while let Some(token) = lexer.next() {
+        if token == b"/FontMatrix" {
+            if !lexer.next_is(b"[") {
+                return Err(FontError::Type1MalformedFont)
+            }
+            let mut values = [0.0; 6];
+            for value in &mut values {
+                *value = try!(lexer.next_number())
+            }
+            font_matrix = Matrix2D::row_major(values[0], values[1],
+                                              values[2], values[3],
+                                              values[4], values[5])
+        } else if token == b"/Encoding" {
+            if lexer.peek_is(b"StandardEncoding") {
+                continue
+            }
+
+            // A custom encoding is built with `dup code /name put`, up to `def`.
+            let mut names = vec![None; 256];
+            while let Some(token) = lexer.next() {
+                if token == b"def" {
+                    break
+                }
+                if token != b"dup" {
+                    continue
+                }
+                let code = try!(lexer.next_number());
+                let name = match lexer.next() {
+                    Some(name) if name.starts_with(b"/") => name,
+                    _ => continue,
+                };
+                if code >= 0.0 && code <= 255.0 {
+                    names[code as usize] = Some(try!(name_to_string(name)))
+                }
+            }
+            encoding = Some(names)
+        }
+    }
+
+    Ok((font_matrix, encoding))
+}
+
+// Reads the `lenIV` value, `Subrs`, and `CharStrings` from the decrypted private part of the font.
+fn parse_private(bytes: &[u8]) -> Result<PrivateDict, FontError> {
+    let mut private = PrivateDict {
+        len_iv: DEFAULT_LEN_IV,
+        subrs: vec![],
+        char_strings: vec![],
+    };
+
+    let mut lexer = Lexer::new(bytes);
+    while let Some(token) = lexer.next() {
+        if token == b"/lenIV" {
+            private.len_iv = try!(lexer.next_number()) as i32
+        } else if token == b"/Subrs" {
+            // Each subroutine is `dup index length RD <binary> NP`.
+            let count = try!(lexer.next_number());
+            if count < 0.0 {
+                return Err(FontError::Type1MalformedFont)
+            }
+            if !lexer.next_is(b"array") {
+                return Err(FontError::Type1MalformedFont)
+            }
+            private.subrs = vec![vec![]; count as usize];
+            while lexer.peek_is(b"dup") {
+                lexer.next();
+                let index = try!(lexer.next_number());
+                let subr = try!(lexer.next_binary());
+                match private.subrs.get_mut(index as usize) {
+                    Some(slot) if index >= 0.0 => *slot = subr.to_vec(),
+                    _ => return Err(FontError::Type1MalformedFont),
+                }
+                lexer.skip_put_or_def();
+            }
+        } else if token == b"/CharStrings" {
+            // Each CharString is `/name length RD <binary> ND`, up to `end`.
+            try!(lexer.next_number());
+            while let Some(token) = lexer.next() {
+                if token == b"end" {
+                    break
+                }
+                if !token.starts_with(b"/") {
+                    continue
+                }
+                let name = try!(name_to_string(token));
+                let char_string = try!(lexer.next_binary());
+                private.char_strings.push((name, char_string.to_vec()));
+                lexer.skip_put_or_def();
+            }
+        }
+    }
+
+    Ok(private)
+}
+
+fn name_to_string(token: &[u8]) -> Result<String, FontError> {
+    match str::from_utf8(&token[1..]) {
+        Ok(name) => Ok(name.to_owned()),
+        Err(_) => Err(FontError::Type1MalformedFont),
+    }
+}
+
+fn is_whitespace(byte: u8) -> bool {
+    byte == b' ' || byte == b'\t' || byte == b'\r' || byte == b'\n' || byte == b'\x0c' ||
+        byte == 0
+}
+
+fn is_newline(byte: u8) -> bool {
+    byte == b'\r' || byte == b'\n'
+}
+
+fn is_delimiter(byte: u8) -> bool {
+    is_whitespace(byte) || b"()<>[]{}/%".contains(&byte)
+}
+
+// A minimal PostScript tokenizer, just enough to find the dictionary entries that we need.
+struct Lexer<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Lexer<'a> {
+    fn new(bytes: &'a [u8]) -> Lexer<'a> {
+        Lexer {
+            bytes: bytes,
+            position: 0,
+        }
+    }
+
+    // Returns the next token. Names keep their leading slash.
+    fn next(&mut self) -> Option<&'a [u8]> {
+        loop {
+            match self.bytes.get(self.position) {
+                None => return None,
+                Some(&byte) if is_whitespace(byte) => self.position += 1,
+                Some(&b'%') => {
+                    while self.bytes.get(self.position).map_or(false, |&byte| !is_newline(byte)) {
+                        self.position += 1
+                    }
+                }
+                Some(_) => break,
+            }
+        }
+
+        let start = self.position;
+        self.position += 1;
+        if b"[]{}".contains(&self.bytes[start]) {
+            return Some(&self.bytes[start..self.position])
+        }
+        while self.bytes.get(self.position).map_or(false, |&byte| !is_delimiter(byte)) {
+            self.position += 1
+        }
+        Some(&self.bytes[start..self.position])
+    }
+
+    fn peek(&self) -> Option<&'a [u8]> {
+        Lexer {
+            bytes: self.bytes,
+            position: self.position,
+        }.next()
+    }
+
+    fn peek_is(&self, token: &[u8]) -> bool {
+        self.peek() == Some(token)
+    }
+
+    fn next_is(&mut self, token: &[u8]) -> bool {
+        self.next() == Some(token)
+    }
+
+    fn next_number(&mut self) -> Result<f32, FontError> {
+        let token = try!(self.next().ok_or(FontError::Type1MalformedFont));
+        str::from_utf8(token).ok()
+                             .and_then(|token| token.parse().ok())
+                             .ok_or(FontError::Type1MalformedFont)
+    }
+
+    // Reads `length RD <binary>`, where `RD` is whatever the font calls `readstring`. The data
+    // starts after a single space.
+    fn next_binary(&mut self) -> Result<&'a [u8], FontError> {
+        let length = try!(self.next_number());
+        if length < 0.0 || self.next().is_none() {
+            return Err(FontError::Type1MalformedFont)
+        }
+        let start = self.position + 1;
+        let end = start + length as usize;
+        if end > self.bytes.len() {
+            return Err(FontError::UnexpectedEof)
+        }
+        self.position = end;
+        Ok(&self.bytes[start..end])
+    }
+
+    // Skips the `NP`, `ND`, `noaccess put`, or similar that follows a subroutine or CharString.
+    fn skip_put_or_def(&mut self) {
+        let tokens: [&[u8]; 8] = [
+            b"NP", b"ND", b"|-", b"|", b"noaccess", b"put", b"def", b"readonly"
+        ];
+        while self.peek().map_or(false, |token| tokens.contains(&token)) {
+            self.next();
+        }
+    }
+}
@@ -8,7 +8,12 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
+use byteorder::{BigEndian, ByteOrder};
+use error::{ErrorContext, FontError};
+use euclid::Point2D;
+use font::{Contour, Point, PointKind, SvgPathOptions};
 use num_traits::identities::Zero;
+use std::mem;
 use std::ops::{Add, Div, Mul, Neg, Sub};
 
 pub const F26DOT6_ZERO: F26Dot6 = F26Dot6(0);
@@ -138,3 +143,381 @@ impl<'a> Jump for &'a [u8] {
     }
 }
 
+
+/// A cursor over font data whose reads are bounds-checked.
+///
+/// Every method fails with `FontError::UnexpectedEof` instead of panicking if the data runs out,
+/// and leaves the cursor where it was on failure. Use `EofContext::eof_in()` on the result to say
+/// what was being read.
+#[derive(Clone, Copy, Debug)]
+pub struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    #[inline]
+    pub fn new(bytes: &'a [u8]) -> Reader<'a> {
+        Reader {
+            bytes: bytes,
+        }
+    }
+
+    /// Returns a reader starting at the given offset into `bytes`.
+    #[inline]
+    pub fn at(bytes: &'a [u8], offset: usize) -> Result<Reader<'a>, FontError> {
+        let mut reader = Reader::new(bytes);
+        try!(reader.jump(offset));
+        Ok(reader)
+    }
+
+    /// Returns the data that hasn't been read yet.
+    #[inline]
+    pub fn remaining(&self) -> &'a [u8] {
+        self.bytes
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.bytes.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+
+    /// Skips the next `n` bytes.
+    #[inline]
+    pub fn jump(&mut self, n: usize) -> Result<(), FontError> {
+        self.subslice(n).map(drop)
+    }
+
+    /// Returns the next `length` bytes and moves past them.
+    #[inline]
+    pub fn subslice(&mut self, length: usize) -> Result<&'a [u8], FontError> {
+        if length > self.bytes.len() {
+            return Err(FontError::UnexpectedEof)
+        }
+        let (subslice, rest) = self.bytes.split_at(length);
+        self.bytes = rest;
+        Ok(subslice)
+    }
+
+    #[inline]
+    pub fn read_u8(&mut self) -> Result<u8, FontError> {
+        self.subslice(1).map(|bytes| bytes[0])
+    }
+
+    #[inline]
+    pub fn read_u16_be(&mut self) -> Result<u16, FontError> {
+        self.subslice(2).map(BigEndian::read_u16)
+    }
+
+    #[inline]
+    pub fn read_i16_be(&mut self) -> Result<i16, FontError> {
+        self.subslice(2).map(BigEndian::read_i16)
+    }
+
+    #[inline]
+    pub fn read_u32_be(&mut self) -> Result<u32, FontError> {
+        self.subslice(4).map(BigEndian::read_u32)
+    }
+
+    #[inline]
+    pub fn read_i32_be(&mut self) -> Result<i32, FontError> {
+        self.subslice(4).map(BigEndian::read_i32)
+    }
+
+    /// Reads a big-endian unsigned integer of `size` bytes, as used for CFF offsets.
+    ///
+    /// `size` must be from 1 to 4; other sizes fail with `FontError::Failed`.
+    pub fn read_offset(&mut self, size: u8) -> Result<u32, FontError> {
+        if size < 1 || size > 4 {
+            return Err(FontError::Failed)
+        }
+        self.subslice(size as usize).map(|bytes| {
+            bytes.iter().fold(0, |offset, &byte| (offset << 8) | byte as u32)
+        })
+    }
+}
+
+/// Attaches the table and field being read to errors, which should only ever be EOFs.
+pub trait EofContext<T> {
+    fn eof_in(self, table: u32, field: &'static str) -> Result<T, FontError>;
+}
+
+impl<T, E> EofContext<T> for Result<T, E> {
+    #[inline]
+    fn eof_in(self, table: u32, field: &'static str) -> Result<T, FontError> {
+        self.map_err(|_| {
+            FontError::UnexpectedEofIn(ErrorContext {
+                table: table,
+                field: field,
+            })
+        })
+    }
+}
+
+/// Appends a big-endian `u16` to the table being written.
+#[inline]
+pub fn push_u16(bytes: &mut Vec<u8>, value: u16) {
+    bytes.extend_from_slice(&[(value >> 8) as u8, value as u8])
+}
+
+/// Appends a big-endian `u32` to the table being written.
+#[inline]
+pub fn push_u32(bytes: &mut Vec<u8>, value: u32) {
+    push_u16(bytes, (value >> 16) as u16);
+    push_u16(bytes, value as u16)
+}
+
+/// Appends a 16.16 fixed-point number to the table being written.
+#[inline]
+pub fn push_fixed(bytes: &mut Vec<u8>, value: f32) {
+    push_u32(bytes, (value * 65536.0) as i32 as u32)
+}
+
+/// Returns a four-byte tag as a `u32`, the form that table `TAG`s and feature and axis tags take.
+#[inline]
+pub fn tag(tag: &[u8; 4]) -> u32 {
+    ((tag[0] as u32) << 24) | ((tag[1] as u32) << 16) | ((tag[2] as u32) << 8) | (tag[3] as u32)
+}
+
+/// Returns the binary search parameters that precede an array of `count` items of `item_size`
+/// bytes each, as in the table directory and `cmap` format 4: the search range, the entry
+/// selector, and the range shift.
+pub fn binary_search_parameters(count: u16, item_size: u16) -> (u16, u16, u16) {
+    if count == 0 {
+        return (0, 0, 0)
+    }
+    let (mut power, mut log) = (1, 0);
+    while power * 2 <= count as u32 {
+        power *= 2;
+        log += 1
+    }
+    let search_range = power * item_size as u32;
+    (search_range as u16, log, (count as u32 * item_size as u32 - search_range) as u16)
+}
+
+/// Writes the points emitted by a `for_each_point()` function as SVG path data.
+pub struct SvgPathWriter {
+    options: SvgPathOptions,
+    path: String,
+    start: Option<Point2D<i16>>,
+    // Whether the last on-curve point was a line back to the start, which `Z` draws if the
+    // contour ends there.
+    line_to_start: bool,
+    control_points: Vec<Point2D<i16>>,
+}
+
+impl SvgPathWriter {
+    pub fn new(options: &SvgPathOptions) -> SvgPathWriter {
+        SvgPathWriter {
+            options: *options,
+            path: String::new(),
+            start: None,
+            line_to_start: false,
+            control_points: vec![],
+        }
+    }
+
+    pub fn push(&mut self, point: &Point) {
+        if point.index_in_contour == 0 {
+            self.close();
+            self.command('M', &[point.position]);
+            self.start = Some(point.position);
+            return
+        }
+
+        if point.kind != PointKind::OnCurve {
+            self.control_points.push(point.position);
+            return
+        }
+
+        // The contour went on past its start, so the line back to it has to be drawn after all.
+        if mem::replace(&mut self.line_to_start, false) {
+            if let Some(start) = self.start {
+                self.command('L', &[start])
+            }
+        }
+
+        let mut points = mem::replace(&mut self.control_points, vec![]);
+        points.push(point.position);
+        match points.len() {
+            1 if self.start == Some(point.position) => self.line_to_start = true,
+            1 => self.command('L', &points),
+            2 => self.command('Q', &points),
+            _ => self.command('C', &points),
+        }
+    }
+
+    /// Returns the path data, with each contour closed by `Z`.
+    pub fn finish(mut self) -> String {
+        self.close();
+        self.path
+    }
+
+    fn close(&mut self) {
+        if self.start.take().is_some() {
+            self.path.push('Z')
+        }
+        self.line_to_start = false;
+        self.control_points.clear()
+    }
+
+    fn command(&mut self, command: char, points: &[Point2D<i16>]) {
+        self.path.push(command);
+        for (index, point) in points.iter().enumerate() {
+            if index > 0 {
+                self.path.push(' ')
+            }
+            let (x, y) = (point.x as f32, point.y as f32);
+            let (x, y) = (self.format(x), self.format(if self.options.flip_y { -y } else { y }));
+            self.path.push_str(&x);
+            self.path.push(' ');
+            self.path.push_str(&y)
+        }
+    }
+
+    // Formats a coordinate with at most `precision` digits after the decimal point.
+    fn format(&self, value: f32) -> String {
+        let mut string = format!("{:.*}", self.options.precision, value * self.options.scale);
+        if string.contains('.') {
+            let len = string.trim_right_matches('0').trim_right_matches('.').len();
+            string.truncate(len)
+        }
+        if string == "-0" {
+            string = "0".to_owned()
+        }
+        string
+    }
+}
+
+// The most line segments that `Flattener` will split a single curve into.
+const MAX_SEGMENTS_PER_CURVE: f32 = 256.0;
+
+/// Flattens the curves of the points emitted by a `for_each_point()` function into polylines.
+///
+/// Curves are split into segments of equal parameter length, with enough segments that no point
+/// of the curve is more than the tolerance away from the polyline.
+pub struct Flattener {
+    tolerance: f32,
+    contours: Vec<Contour<Point2D<f32>>>,
+    points: Vec<Point2D<f32>>,
+    control_points: Vec<Point2D<f32>>,
+    last_on_curve: Point2D<f32>,
+}
+
+impl Flattener {
+    pub fn new(tolerance: f32) -> Flattener {
+        Flattener {
+            tolerance: tolerance,
+            contours: vec![],
+            points: vec![],
+            control_points: vec![],
+            last_on_curve: Point2D::new(0.0, 0.0),
+        }
+    }
+
+    pub fn push(&mut self, point: &Point) {
+        let position = Point2D::new(point.position.x as f32, point.position.y as f32);
+        if point.index_in_contour == 0 {
+            self.finish_contour();
+            self.add_point(position);
+            self.last_on_curve = position;
+            return
+        }
+
+        if point.kind != PointKind::OnCurve {
+            self.control_points.push(position);
+            return
+        }
+
+        let last_on_curve = self.last_on_curve;
+        match self.control_points.len() {
+            1 => {
+                let curve = [last_on_curve, self.control_points[0], position];
+                let (dx, dy) = (curve[0].x - 2.0 * curve[1].x + curve[2].x,
+                                curve[0].y - 2.0 * curve[1].y + curve[2].y);
+
+                // The error of a chord over a parameter interval `h` is at most `|B''| h² / 8`.
+                let count = segment_count((dx * dx + dy * dy).sqrt() / 4.0, self.tolerance);
+                for i in 1..count {
+                    let t = i as f32 / count as f32;
+                    let mt = 1.0 - t;
+                    self.add_point(Point2D::new(
+                        mt * mt * curve[0].x + 2.0 * mt * t * curve[1].x + t * t * curve[2].x,
+                        mt * mt * curve[0].y + 2.0 * mt * t * curve[1].y + t * t * curve[2].y))
+                }
+            }
+            2 => {
+                let curve = [last_on_curve,
+                             self.control_points[0],
+                             self.control_points[1],
+                             position];
+                let second_differences = [
+                    (curve[0].x - 2.0 * curve[1].x + curve[2].x,
+                     curve[0].y - 2.0 * curve[1].y + curve[2].y),
+                    (curve[1].x - 2.0 * curve[2].x + curve[3].x,
+                     curve[1].y - 2.0 * curve[2].y + curve[3].y),
+                ];
+                let max_second_difference = second_differences.iter().fold(0.0, |max, &(dx, dy)| {
+                    f32::max(max, (dx * dx + dy * dy).sqrt())
+                });
+
+                // `|B''|` is at most 6 times the largest second difference of the control points.
+                let count = segment_count(max_second_difference * 0.75, self.tolerance);
+                for i in 1..count {
+                    let t = i as f32 / count as f32;
+                    let mt = 1.0 - t;
+                    let (a, b) = (mt * mt * mt, 3.0 * mt * mt * t);
+                    let (c, d) = (3.0 * mt * t * t, t * t * t);
+                    self.add_point(Point2D::new(
+                        a * curve[0].x + b * curve[1].x + c * curve[2].x + d * curve[3].x,
+                        a * curve[0].y + b * curve[1].y + c * curve[2].y + d * curve[3].y))
+                }
+            }
+            _ => {}
+        }
+
+        self.add_point(position);
+        self.control_points.clear();
+        self.last_on_curve = position
+    }
+
+    /// Returns the flattened contours. Each is implicitly closed by a line from its last point
+    /// back to its first.
+    pub fn finish(mut self) -> Vec<Contour<Point2D<f32>>> {
+        self.finish_contour();
+        self.contours
+    }
+
+    // Adds a point to the current contour, skipping zero-length segments.
+    fn add_point(&mut self, position: Point2D<f32>) {
+        if self.points.last() != Some(&position) {
+            self.points.push(position)
+        }
+    }
+
+    fn finish_contour(&mut self) {
+        // The contour's final line back to its start is implicit.
+        if self.points.len() > 1 && self.points.first() == self.points.last() {
+            self.points.pop();
+        }
+
+        // Contours that collapse to a point draw nothing.
+        if self.points.len() > 1 {
+            self.contours.push(Contour {
+                points: mem::replace(&mut self.points, vec![]),
+            })
+        }
+        self.points.clear();
+        self.control_points.clear()
+    }
+}
+
+// Returns the number of segments of equal parameter length to split a curve into, given that the
+// error of a single segment is `error`. The error shrinks with the square of the number.
+fn segment_count(error: f32, tolerance: f32) -> u32 {
+    (error / tolerance).sqrt().ceil().max(1.0).min(MAX_SEGMENTS_PER_CURVE) as u32
+}